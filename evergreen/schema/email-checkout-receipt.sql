@@ -0,0 +1,55 @@
+-- Emailed checkout receipts.
+-- See eg::common::circ::create_email_receipt_events.
+--
+-- The event definition is created inactive.  Review the template and
+-- activate it (and set its owner) via the staff client.
+
+BEGIN;
+
+INSERT INTO action_trigger.hook (key, core_type, description, passive)
+    SELECT 'circ.checkout.email_receipt', 'circ',
+        'Circulations for which the patron requested an emailed receipt',
+        FALSE
+    WHERE NOT EXISTS (
+        SELECT 1 FROM action_trigger.hook
+        WHERE key = 'circ.checkout.email_receipt');
+
+INSERT INTO action_trigger.event_definition (
+    active, owner, name, hook, validator, reactor, group_field, template
+) SELECT FALSE, 1, 'Email Checkout Receipt (SIP)',
+    'circ.checkout.email_receipt', 'NOOP_True', 'SendEmail', 'usr',
+$$
+[%- USE date -%]
+[%- user = target.0.usr -%]
+To: [%- params.recipient_email || user.email %]
+From: [%- helpers.get_org_setting(target.0.circ_lib.id, 'org.bounced_emails') || target.0.circ_lib.email || params.sender_email || default_sender %]
+Date: [%- date.format(date.now, '%a, %d %b %Y %T -0000', gmt => 1) %]
+Subject: Checkout Receipt
+Auto-Submitted: auto-generated
+
+Dear [% user.first_given_name %] [% user.family_name %],
+
+The following items were checked out:
+
+[% FOR circ IN target %]
+    [%- copy_details = helpers.get_copy_bib_basics(circ.target_copy.id) -%]
+    Title: [% copy_details.title %]
+    Barcode: [% circ.target_copy.barcode %]
+    Due: [% date.format(helpers.format_date(circ.due_date), '%Y-%m-%d') %]
+
+[% END %]
+$$
+WHERE NOT EXISTS (
+    SELECT 1 FROM action_trigger.event_definition
+    WHERE hook = 'circ.checkout.email_receipt');
+
+INSERT INTO action_trigger.environment (event_def, path)
+    SELECT def.id, env.path
+    FROM action_trigger.event_definition def,
+        (VALUES ('target_copy'), ('circ_lib'), ('usr')) AS env (path)
+    WHERE def.hook = 'circ.checkout.email_receipt'
+        AND NOT EXISTS (
+            SELECT 1 FROM action_trigger.environment
+            WHERE event_def = def.id AND path = env.path);
+
+COMMIT;
@@ -0,0 +1,15 @@
+-- Cards deactivated by SIP Block Patron requests.
+-- See the sip2 service's handle_block_patron / handle_patron_enable.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS actor.sip_blocked_card (
+    id      SERIAL  PRIMARY KEY,
+    penalty INT     NOT NULL REFERENCES actor.usr_standing_penalty (id)
+                    ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    card    INT     NOT NULL REFERENCES actor.card (id)
+                    ON DELETE CASCADE DEFERRABLE INITIALLY DEFERRED,
+    CONSTRAINT sip_blocked_card_once UNIQUE (penalty, card)
+);
+
+COMMIT;
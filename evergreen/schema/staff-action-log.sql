@@ -0,0 +1,44 @@
+-- Staff activity (workstation audit) log.
+-- See eg::common::audit.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS actor.staff_action_log (
+    id          BIGSERIAL   PRIMARY KEY,
+    action      TEXT        NOT NULL,
+    event_time  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    staff       INT         NOT NULL REFERENCES actor.usr (id)
+                            DEFERRABLE INITIALLY DEFERRED,
+    workstation INT         REFERENCES actor.workstation (id)
+                            ON DELETE SET NULL
+                            DEFERRABLE INITIALLY DEFERRED,
+    org_unit    INT         NOT NULL REFERENCES actor.org_unit (id)
+                            DEFERRABLE INITIALLY DEFERRED,
+    reason_code TEXT,
+    target_type TEXT,
+    target_id   BIGINT,
+    note        TEXT
+);
+
+CREATE INDEX IF NOT EXISTS staff_action_log_event_time_idx
+    ON actor.staff_action_log (event_time);
+CREATE INDEX IF NOT EXISTS staff_action_log_staff_idx
+    ON actor.staff_action_log (staff);
+CREATE INDEX IF NOT EXISTS staff_action_log_org_unit_idx
+    ON actor.staff_action_log (org_unit);
+CREATE INDEX IF NOT EXISTS staff_action_log_target_idx
+    ON actor.staff_action_log (target_type, target_id);
+
+INSERT INTO permission.perm_list (code, description)
+    SELECT 'VIEW_STAFF_AUDIT_LOG',
+        'View staff activity audit log entries for an org unit'
+    WHERE NOT EXISTS (
+        SELECT 1 FROM permission.perm_list WHERE code = 'VIEW_STAFF_AUDIT_LOG');
+
+INSERT INTO permission.perm_list (code, description)
+    SELECT 'PURGE_STAFF_AUDIT_LOG',
+        'Delete staff activity audit log entries past their retention period'
+    WHERE NOT EXISTS (
+        SELECT 1 FROM permission.perm_list WHERE code = 'PURGE_STAFF_AUDIT_LOG');
+
+COMMIT;
@@ -0,0 +1,32 @@
+-- Till (cash drawer) closeouts.
+-- See eg::common::till.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS money.till_closeout (
+    id                  SERIAL      PRIMARY KEY,
+    workstation         INT         NOT NULL REFERENCES actor.workstation (id)
+                                    DEFERRABLE INITIALLY DEFERRED,
+    closed_by           INT         NOT NULL REFERENCES actor.usr (id)
+                                    DEFERRABLE INITIALLY DEFERRED,
+    close_time          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    period_start        TIMESTAMPTZ NOT NULL,
+    period_end          TIMESTAMPTZ NOT NULL,
+    cash_total          NUMERIC(8,2) NOT NULL DEFAULT 0.0,
+    check_total         NUMERIC(8,2) NOT NULL DEFAULT 0.0,
+    credit_card_total   NUMERIC(8,2) NOT NULL DEFAULT 0.0,
+    debit_card_total    NUMERIC(8,2) NOT NULL DEFAULT 0.0,
+    note                TEXT,
+    CONSTRAINT till_closeout_period_check CHECK (period_start < period_end)
+);
+
+CREATE INDEX IF NOT EXISTS till_closeout_workstation_idx
+    ON money.till_closeout (workstation, close_time);
+
+INSERT INTO permission.perm_list (code, description)
+    SELECT 'CLOSE_TILL',
+        'Close out the till (cash drawer) for a workstation'
+    WHERE NOT EXISTS (
+        SELECT 1 FROM permission.perm_list WHERE code = 'CLOSE_TILL');
+
+COMMIT;
@@ -0,0 +1,111 @@
+use eg::common::autorenew::{self, AutoRenewer};
+use eg::init::InitOptions;
+use eg::result::EgResult;
+use eg::util;
+use eg::Editor;
+use evergreen as eg;
+
+const HELP_TEXT: &str = r#"
+Batch circulation auto-renewal.
+
+Renews open circulations flagged for auto-renewal which are due
+within the renewal window and creates "autorenewal" A/T events
+describing the outcome of each renewal for patron notices.
+
+Typically run nightly via cron.
+
+./eg-auto-renewer --window "1 day" --lockfile /tmp/auto_renewer-LOCK
+
+General Options
+    --lockfile [/tmp/auto_renewer-LOCK]
+        Full path to lock file
+
+    --verbose
+        Print the outcome of each renewal.
+
+    Standard OpenSRF environment variables (e.g. OSRF_CONFIG) are
+    also supported.
+
+Renewal Options
+
+    --window <interval>
+        Renew circulations due between now and now + interval.
+        Defaults to "1 day".  Use an interval matching the time
+        between runs so each circulation is attempted once.
+
+    --limit <count>
+        Process at most this many circulations.
+"#;
+
+fn main() -> EgResult<()> {
+    let mut options = getopts::Options::new();
+
+    options.optflag("", "help", "Show this message");
+    options.optflag("", "verbose", "");
+    options.optopt("", "lockfile", "", "");
+    options.optopt("", "window", "", "");
+    options.optopt("", "limit", "", "");
+
+    let args: Vec<String> = std::env::args().collect();
+
+    let params = options
+        .parse(&args[1..])
+        .map_err(|e| format!("Error parsing params: {e}"))?;
+
+    if params.opt_present("help") {
+        println!("{HELP_TEXT}");
+        return Ok(());
+    }
+
+    if let Some(path) = params.opt_str("lockfile") {
+        if util::lockfile(&path, "check")? {
+            return Err(format!("Remove lockfile first: {}", path).into());
+        }
+        util::lockfile(&path, "create")?;
+    }
+
+    let verbose = params.opt_present("verbose");
+
+    let mut init_ops = InitOptions::new();
+    init_ops.skip_host_settings = true; // we don't need it.
+
+    let client = eg::init::with_options(&init_ops)?;
+
+    let mut editor = Editor::new(&client);
+    let mut renewer = AutoRenewer::new(&mut editor);
+
+    renewer.set_window(
+        params
+            .opt_str("window")
+            .as_deref()
+            .unwrap_or(autorenew::DEFAULT_WINDOW),
+    );
+
+    if let Some(limit) = params.opt_str("limit") {
+        let limit = limit
+            .parse::<i64>()
+            .map_err(|e| format!("Invalid limit: {limit} {e}"))?;
+        renewer.set_limit(limit);
+    }
+
+    for circ in renewer.find_circs()? {
+        match renewer.renew_circ(&circ) {
+            Ok(Some(renewal)) => {
+                if verbose {
+                    println!("{}", renewal.to_eg_value().dump());
+                }
+            }
+            Ok(None) => {}
+            // Keep going.  Other patrons' renewals may still succeed.
+            Err(e) => eprintln!("Error auto-renewing circ {}: {e}", circ["id"]),
+        }
+    }
+
+    println!("Auto-renewal complete: {}", renewer.summary().dump());
+
+    if let Some(path) = params.opt_str("lockfile") {
+        util::lockfile(&path, "delete")?;
+    }
+
+    Ok(())
+}
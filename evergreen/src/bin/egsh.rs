@@ -60,6 +60,13 @@ Commands
 
     login <username> <password> [<login_type> <workstation>]
 
+    logout
+        Delete the auth session created by 'login'.
+
+    whoami
+        Display the user, workstation, and duration of the current
+        auth session.
+
     req <service> <method> [<param> <param> ...]
         Send an API request.
 
@@ -125,6 +132,11 @@ Commands
             checkin <item-barcode>
             checkout <item-barcode> <patron-barcode>
 
+    source <file>
+        Execute the egsh commands contained in <file>, one per line.
+        Empty lines and lines starting with '#' are ignored.
+        Processing stops at the first failed command.
+
     help
         Show this message
 "#;
@@ -309,39 +321,51 @@ impl Shell {
             return Ok(());
         }
 
+        if let Err(e) = self.run_commands(&mut reader) {
+            eprintln!("Error processing piped requests: {e}");
+        }
+
+        // If we started on the receiving end of a pipe, exit after
+        // all piped data has been processed, even if no usable
+        // data was found.
+        self.exit();
+
+        Ok(())
+    }
+
+    /// Execute one command per line until EOF or the first failure.
+    fn run_commands(&mut self, reader: &mut dyn BufRead) -> Result<(), String> {
         let mut buffer = String::new();
 
         loop {
             buffer.clear();
-            match reader.read_line(&mut buffer) {
-                Ok(count) => {
-                    if count == 0 {
-                        break; // EOF
-                    }
-
-                    let command = buffer.trim();
-
-                    if command.is_empty() || command.starts_with('#') {
-                        // Skip empty lines and comments.
-                        continue;
-                    }
-
-                    if let Err(e) = self.dispatch_command(command) {
-                        eprintln!("Error processing piped requests: {e}");
-                        break;
-                    }
-                }
 
-                Err(e) => return Err(format!("Error reading commands: {e}")),
+            let count = reader
+                .read_line(&mut buffer)
+                .map_err(|e| format!("Error reading commands: {e}"))?;
+
+            if count == 0 {
+                return Ok(()); // EOF
+            }
+
+            let command = buffer.trim();
+
+            if command.is_empty() || command.starts_with('#') {
+                // Skip empty lines and comments.
+                continue;
             }
+
+            self.dispatch_command(command)?;
         }
+    }
 
-        // If we started on the receiving end of a pipe, exit after
-        // all piped data has been processed, even if no usable
-        // data was found.
-        self.exit();
+    fn handle_source(&mut self, args: &[&str]) -> Result<(), String> {
+        self.args_min_length(args, 1)?;
 
-        Ok(())
+        let filename = args[0];
+        let file = File::open(filename).map_err(|e| format!("Cannot open {filename}: {e}"))?;
+
+        self.run_commands(&mut BufReader::new(file))
     }
 
     /// Read a single line of user input and execute the command.
@@ -409,6 +433,9 @@ impl Shell {
                 Ok(())
             }
             "login" => self.handle_login(args),
+            "logout" => self.handle_logout(),
+            "whoami" => self.handle_whoami(),
+            "source" => self.handle_source(args),
             "db" => self.db_command(args),
             "req" | "request" => self.send_request(args),
             "reqauth" => self.send_reqauth(args),
@@ -730,6 +757,56 @@ impl Shell {
         Ok(())
     }
 
+    fn handle_logout(&mut self) -> Result<(), String> {
+        let authses = match self.auth_session.take() {
+            Some(s) => s,
+            None => return Err("Not logged in".to_string()),
+        };
+
+        auth::Session::logout(self.client(), authses.token())?;
+
+        println!("Logged out: {}", authses.token());
+
+        Ok(())
+    }
+
+    fn handle_whoami(&mut self) -> Result<(), String> {
+        let authses = match &self.auth_session {
+            Some(s) => s,
+            None => return Err("Not logged in".to_string()),
+        };
+
+        let token = authses.token().to_string();
+        let workstation = authses.workstation().map(|w| w.to_string());
+        let authtime = authses.authtime();
+
+        // Fetch the session from the server so we know it's still valid.
+        let user = match self.client().send_recv_one(
+            "open-ils.auth",
+            "open-ils.auth.session.retrieve",
+            token.as_str(),
+        )? {
+            Some(u) => u,
+            None => return Err("Auth session retrieve timed out".to_string()),
+        };
+
+        if let Some(evt) = event::EgEvent::parse(&user) {
+            return Err(format!("Auth session is no longer valid: {evt}"));
+        }
+
+        println!("Token: {token}");
+        println!("User: {} ({})", user["usrname"], user["id"]);
+        println!("Home Org Unit: {}", user["home_ou"]);
+
+        if let Some(ws) = workstation {
+            println!("Workstation: {ws} ({})", user["wsid"]);
+        }
+
+        println!("Auth Duration: {authtime}");
+
+        Ok(())
+    }
+
     fn introspect(&mut self, args: &[&str]) -> Result<(), String> {
         self.args_min_length(args, 1)?;
 
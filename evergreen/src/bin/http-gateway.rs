@@ -1,4 +1,5 @@
 //! Evergreen HTTP+JSON Gateway
+use eg::common::payment::stripe;
 use eg::date;
 use eg::idl;
 use eg::osrf::conf;
@@ -9,7 +10,7 @@ use evergreen as eg;
 use std::any::Any;
 use std::env;
 use std::io::{Read, Write};
-use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
 use url::Url;
 
 const BUFSIZE: usize = 1024;
@@ -58,6 +59,8 @@ struct ParsedHttpRequest {
     method: String,
     /// Only POST requests will have an HTTP body
     body: Option<String>,
+    /// Stripe-Signature header value, for payment webhooks.
+    stripe_signature: Option<String>,
 }
 
 struct GatewayHandler {
@@ -96,8 +99,26 @@ impl GatewayHandler {
                     // request exits early on a failure.
                     self.log_request(request, http_req.as_ref().unwrap());
 
-                    match self.relay_to_osrf(http_req.as_mut().unwrap()) {
+                    // relay_to_osrf() consumes the method.
+                    let api = http_req
+                        .as_ref()
+                        .unwrap()
+                        .method
+                        .as_ref()
+                        .map(|m| m.method().to_string());
+
+                    let client_ip = request.address.ip();
+
+                    match self.relay_to_osrf(http_req.as_mut().unwrap(), client_ip) {
                         Ok(list) => {
+                            // Track request latency by API name.  Only
+                            // label calls the backend answered, so
+                            // unknown method names sent by clients
+                            // do not each become a new metric label.
+                            if let Some(api) = api.as_deref() {
+                                mptc::metrics::set_request_label(api);
+                            }
+
                             response["payload"] = EgValue::Array(list);
                             response["status"] = EgValue::from(200);
                         }
@@ -142,7 +163,11 @@ impl GatewayHandler {
         Ok(())
     }
 
-    fn relay_to_osrf(&mut self, request: &mut ParsedGatewayRequest) -> EgResult<Vec<EgValue>> {
+    fn relay_to_osrf(
+        &mut self,
+        request: &mut ParsedGatewayRequest,
+        client_ip: IpAddr,
+    ) -> EgResult<Vec<EgValue>> {
         let recipient = eg::osrf::addr::BusAddress::for_bare_service(&request.service);
 
         // Send every request to the router on our gateway domain.
@@ -155,15 +180,21 @@ impl GatewayHandler {
         // We know method is non-None here.
         let method = request.method.take().unwrap();
 
+        let mut msg = eg::osrf::message::Message::new(
+            eg::osrf::message::MessageType::Request,
+            1, // thread trace
+            eg::osrf::message::Payload::Method(method),
+        );
+
+        // The message is built here from the HTTP request, so there
+        // is no client-supplied remote_ip to strip.
+        msg.set_remote_ip(&client_ip.to_string());
+
         let tm = eg::osrf::message::TransportMessage::with_body(
             recipient.as_str(),
             self.bus().address().as_str(),
             &eg::util::random_number(16), // thread
-            eg::osrf::message::Message::new(
-                eg::osrf::message::MessageType::Request,
-                1, // thread trace
-                eg::osrf::message::Payload::Method(method),
-            ),
+            msg,
         );
 
         self.bus().send_to(tm, router.as_str())?;
@@ -321,13 +352,16 @@ impl GatewayHandler {
                 // once full parsed.
                 header_byte_count = res.unwrap();
 
+                let mut stripe_signature = None;
+
                 for header in req.headers.iter() {
-                    if header.name.to_lowercase().as_str() == "content-length" {
+                    if header.name.eq_ignore_ascii_case("content-length") {
                         let len = String::from_utf8_lossy(header.value);
                         if let Ok(size) = len.parse::<usize>() {
                             content_length = size;
-                            break;
                         }
+                    } else if header.name.eq_ignore_ascii_case(stripe::SIGNATURE_HEADER) {
+                        stripe_signature = Some(String::from_utf8_lossy(header.value).to_string());
                     }
                 }
 
@@ -345,6 +379,7 @@ impl GatewayHandler {
                     method,
                     path,
                     body: None,
+                    stripe_signature,
                 });
             }
 
@@ -391,6 +426,10 @@ impl GatewayHandler {
     ///
     /// Returns Err if the request cannot be translated.
     fn parse_request(&self, http_req: ParsedHttpRequest) -> EgResult<ParsedGatewayRequest> {
+        if http_req.path.split('?').next() == Some(stripe::WEBHOOK_PATH) {
+            return self.parse_stripe_webhook(http_req);
+        }
+
         let url_params = match http_req.body {
             // POST params are in the body
             Some(b) => format!("{}?{}", DUMMY_BASE_URL, &b),
@@ -454,6 +493,29 @@ impl GatewayHandler {
         })
     }
 
+    /// Translate a Stripe webhook POST into a webhook API call.
+    ///
+    /// Stripe can only POST the raw event JSON with a Stripe-Signature
+    /// header, so the body and header are relayed unchanged.
+    fn parse_stripe_webhook(&self, http_req: ParsedHttpRequest) -> EgResult<ParsedGatewayRequest> {
+        if http_req.method != "POST" {
+            return Err("Stripe webhooks must be POSTed".into());
+        }
+
+        let body = http_req.body.ok_or("Stripe webhook has no body")?;
+
+        let signature = http_req
+            .stripe_signature
+            .ok_or("Stripe webhook has no Stripe-Signature header")?;
+
+        Ok(ParsedGatewayRequest {
+            format: idl::DataFormat::Fieldmapper,
+            service: stripe::WEBHOOK_SERVICE.to_string(),
+            method: Some(stripe::webhook_method_call(&body, &signature)),
+            http_method: http_req.method,
+        })
+    }
+
     fn log_request(&self, request: &GatewayRequest, req: &ParsedGatewayRequest) {
         let method = req.method.as_ref().unwrap();
 
@@ -461,6 +523,7 @@ impl GatewayHandler {
             method.method(),
             method.params(),
             conf::config().log_protect(),
+            &eg::osrf::method::redacted_params(method.method(), None),
         );
 
         log::info!(
@@ -619,5 +682,10 @@ fn main() {
         server.set_max_worker_requests(n.parse::<usize>().expect("Invalid max-requests"));
     }
 
+    // e.g. 127.0.0.1:9683
+    if let Ok(addr) = env::var("EG_HTTP_GATEWAY_METRICS_ADDRESS") {
+        server.set_metrics_address(&addr);
+    }
+
     server.run();
 }
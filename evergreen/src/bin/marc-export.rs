@@ -539,6 +539,8 @@ fn export(scripter: &mut script::Runner, ops: &mut ExportOptions) -> Result<(),
                 let options = marc::xml::XmlOptions {
                     formatted: ops.pretty_print_xml,
                     with_xml_declaration: false,
+                    field_order: None,
+                    ..Default::default()
                 };
 
                 write(&mut writer, record.to_xml_string_ops(&options).as_bytes())?;
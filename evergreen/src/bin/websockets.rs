@@ -595,6 +595,9 @@ impl Session {
             let mut msg = message::Message::from_json_value(msg_json, false)?;
             msg.set_ingress(WEBSOCKET_INGRESS);
 
+            // Replace any remote_ip sent by the client.
+            msg.set_remote_ip(&self.client_ip.ip().to_string());
+
             match msg.mtype() {
                 message::MessageType::Connect => {
                     self.reqs_in_flight += 1;
@@ -749,6 +752,7 @@ impl Session {
             request.method(),
             request.params(),
             conf::config().log_protect(),
+            &eg::osrf::method::redacted_params(request.method(), None),
         );
 
         log::info!(
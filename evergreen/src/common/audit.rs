@@ -0,0 +1,215 @@
+//! Staff activity (workstation audit) log.
+//!
+//! Records actions taken by staff, e.g. checkouts, overrides, and
+//! patron edits, along with the workstation, org unit, and an optional
+//! reason code.
+//!
+//! Entries are stored as "asal" (actor.staff_action_log) objects with
+//! the fields: id, action, event_time, staff, workstation, org_unit,
+//! reason_code, target_type, target_id, note.
+//!
+//! The table and IDL class are not part of stock Evergreen.  They ship
+//! in evergreen/schema/staff-action-log.{sql,idl.xml}.
+use crate as eg;
+use eg::date;
+use eg::editor::Editor;
+use eg::idl;
+use eg::result::EgResult;
+use eg::EgValue;
+use std::sync::Once;
+
+/// IDL class of audit log entries.
+pub const AUDIT_CLASS: &str = "asal";
+
+/// Maximum number of entries returned by a single search.
+const MAX_SEARCH_LIMIT: i64 = 1000;
+
+static MISSING_CLASS_WARNING: Once = Once::new();
+
+/// True if the audit log class is defined in the IDL.
+///
+/// Allows callers to skip logging on systems where the audit log
+/// has not been installed.  Logs a warning the first time the class
+/// is found missing.
+pub fn is_enabled() -> bool {
+    if idl::get_class(AUDIT_CLASS).is_ok() {
+        return true;
+    }
+
+    MISSING_CLASS_WARNING.call_once(|| {
+        log::warn!(
+            "IDL class '{AUDIT_CLASS}' is not installed.  Staff actions will not be audited."
+        );
+    });
+
+    false
+}
+
+/// A staff action to add to the audit log.
+#[derive(Debug, Clone, Default)]
+pub struct AuditEntry {
+    /// Short action name, e.g. "checkout.override"
+    pub action: String,
+
+    /// Type of object acted upon, typically an IDL class, e.g. "circ"
+    pub target_type: Option<String>,
+
+    pub target_id: Option<i64>,
+
+    /// Staff-selected code explaining the action, e.g. for overrides.
+    pub reason_code: Option<String>,
+
+    /// Free-form details
+    pub note: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(action: &str) -> Self {
+        AuditEntry {
+            action: action.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the IDL class and ID of the object acted upon.
+    pub fn set_target(&mut self, target_type: &str, target_id: i64) {
+        self.target_type = Some(target_type.to_string());
+        self.target_id = Some(target_id);
+    }
+}
+
+/// Add an entry to the audit log on behalf of the editor's requestor.
+///
+/// The editor must be in a transaction.  Logging the entry within the
+/// same transaction as the action ensures we only log actions that
+/// were actually committed.
+pub fn log_action(editor: &mut Editor, entry: &AuditEntry) -> EgResult<EgValue> {
+    let org_unit = match editor.requestor_ws_ou() {
+        Some(o) => o,
+        None => editor.requestor_home_ou()?,
+    };
+
+    let value = eg::hash! {
+        "action": entry.action.as_str(),
+        "event_time": date::to_iso(&date::now()),
+        "staff": editor.requestor_id()?,
+        "workstation": editor.requestor_ws_id(),
+        "org_unit": org_unit,
+        "reason_code": entry.reason_code.as_deref(),
+        "target_type": entry.target_type.as_deref(),
+        "target_id": entry.target_id,
+        "note": entry.note.as_deref(),
+    };
+
+    log::info!(
+        "Audit: action={} staff={} target={:?}:{:?}",
+        entry.action,
+        value["staff"],
+        entry.target_type,
+        entry.target_id
+    );
+
+    editor.create(EgValue::create(AUDIT_CLASS, value)?)
+}
+
+/// Search the audit log.
+///
+/// Supported filters:
+///
+/// * start_date / end_date - ISO dates bounding the event_time.
+/// * org_unit - Single org unit ID or list of IDs.
+/// * staff - Staff user ID.
+/// * workstation - Workstation ID.
+/// * action / reason_code / target_type / target_id - exact match.
+/// * limit / offset - paging.  Limit is capped at 1000.
+///
+/// Results are sorted newest first.
+pub fn search(editor: &mut Editor, filters: &EgValue) -> EgResult<Vec<EgValue>> {
+    let mut query = eg::hash! {};
+
+    let start_date = filters["start_date"].as_str();
+    let end_date = filters["end_date"].as_str();
+
+    if start_date.is_some() || end_date.is_some() {
+        let mut range = eg::hash! {};
+
+        if let Some(d) = start_date {
+            range[">="] = EgValue::from(date::to_iso(&date::parse_datetime(d)?));
+        }
+        if let Some(d) = end_date {
+            range["<"] = EgValue::from(date::to_iso(&date::parse_datetime(d)?));
+        }
+
+        query["event_time"] = range;
+    }
+
+    for field in [
+        "org_unit",
+        "staff",
+        "workstation",
+        "action",
+        "reason_code",
+        "target_type",
+        "target_id",
+    ] {
+        let value = &filters[field];
+        if !value.is_null() {
+            query[field] = value.clone();
+        }
+    }
+
+    let limit = filters["limit"]
+        .as_int()
+        .unwrap_or(MAX_SEARCH_LIMIT)
+        .min(MAX_SEARCH_LIMIT);
+
+    let offset = filters["offset"].as_int().unwrap_or(0);
+
+    let mut ops = eg::hash! {
+        "limit": limit,
+        "offset": offset,
+    };
+
+    ops["order_by"][AUDIT_CLASS] = EgValue::from("event_time DESC, id DESC");
+
+    editor.search_with_ops(AUDIT_CLASS, query, ops)
+}
+
+/// Delete audit entries older than the provided interval, e.g. "1 year".
+///
+/// Purges in batches, committing each batch in its own transaction to
+/// avoid excessively large transactions.  The editor must not be in a
+/// transaction.
+///
+/// Returns the number of deleted entries.
+pub fn purge(editor: &mut Editor, retention: &str) -> EgResult<usize> {
+    let cutoff = date::subtract_interval(date::now(), retention)?;
+    let cutoff = date::to_iso(&cutoff);
+
+    log::info!("Purging audit log entries older than {cutoff}");
+
+    let query = eg::hash! {"event_time": {"<": cutoff.as_str()}};
+    let ops = eg::hash! {"limit": MAX_SEARCH_LIMIT};
+
+    let mut count = 0;
+
+    loop {
+        editor.xact_begin()?;
+
+        let entries = editor.search_with_ops(AUDIT_CLASS, query.clone(), ops.clone())?;
+
+        if entries.is_empty() {
+            editor.rollback()?;
+            break;
+        }
+
+        for entry in entries {
+            editor.delete(entry)?;
+            count += 1;
+        }
+
+        editor.commit()?;
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,444 @@
+//! Batch circulation auto-renewal.
+//!
+//! Finds open circulations flagged for auto-renewal which come due
+//! within a time window, renews each on behalf of its patron, and
+//! creates "autorenewal" A/T events describing the outcome so the
+//! patron can be notified of successful and failed renewals.
+use crate as eg;
+use eg::common::auth;
+use eg::common::circulator::Circulator;
+use eg::common::settings::{SettingContext, Settings};
+use eg::common::trigger;
+use eg::date;
+use eg::Editor;
+use eg::EgEvent;
+use eg::EgResult;
+use eg::EgValue;
+use std::collections::HashMap;
+
+/// Renew circulations due within this interval of now.
+pub const DEFAULT_WINDOW: &str = "1 day";
+
+/// A/T hook used for auto-renewal notices.
+const NOTICE_HOOK: &str = "autorenewal";
+
+/// Org unit setting, checked at the circulating library.
+const ORG_DISABLED_SETTING: &str = "circ.autorenewal.disabled";
+
+/// Patron setting.
+const USER_OPT_OUT_SETTING: &str = "circ.autorenewal.opt_out";
+
+/// Outcome of a single auto-renewal attempt.
+#[derive(Debug, Clone)]
+pub struct AutoRenewal {
+    circ_id: i64,
+    copy_id: i64,
+    patron_id: i64,
+    circ_lib: i64,
+    is_renewed: bool,
+    textcode: String,
+    reason: String,
+    new_due_date: String,
+    old_due_date: String,
+    total_renewal_remaining: i64,
+    auto_renewal_remaining: i64,
+}
+
+impl AutoRenewal {
+    /// Compile the renewal outcome from the circulation we tried to
+    /// renew, the event returned by the renewal, and the new circ
+    /// when the renewal succeeded.
+    ///
+    /// ```
+    /// use evergreen as eg;
+    /// use eg::common::autorenew::AutoRenewal;
+    /// use eg::EgEvent;
+    ///
+    /// let circ = eg::hash! {
+    ///     "id": 10,
+    ///     "usr": 3,
+    ///     "target_copy": 55,
+    ///     "circ_lib": 4,
+    ///     "due_date": "2024-06-01T23:59:59-0400",
+    ///     "renewal_remaining": 2,
+    ///     "auto_renewal_remaining": 1,
+    /// };
+    ///
+    /// let mut evt = EgEvent::new("MAX_RENEWALS_REACHED");
+    /// evt.set_desc("Max renewals reached");
+    ///
+    /// let renewal = AutoRenewal::new(&circ, &evt, None).unwrap();
+    ///
+    /// assert!(!renewal.is_renewed());
+    /// assert_eq!(renewal.copy_id(), 55);
+    ///
+    /// let data = renewal.notice_data();
+    /// assert_eq!(data["reason"].str().unwrap(), "Max renewals reached");
+    /// assert_eq!(data["old_due_date"].str().unwrap(), "2024-06-01T23:59:59-0400");
+    /// // Auto-renewals remaining never drops below the total remaining.
+    /// assert_eq!(data["auto_renewal_remaining"].int().unwrap(), 2);
+    /// ```
+    pub fn new(
+        source_circ: &EgValue,
+        evt: &EgEvent,
+        new_circ: Option<&EgValue>,
+    ) -> EgResult<AutoRenewal> {
+        let copy_id = link_id(&source_circ["target_copy"])?;
+        let patron_id = link_id(&source_circ["usr"])?;
+        let circ_lib = link_id(&source_circ["circ_lib"])?;
+
+        let mut new_due_date = "";
+        let mut old_due_date = "";
+        let mut reason = "";
+
+        let is_renewed = evt.is_success() && new_circ.is_some();
+
+        let circ = match new_circ {
+            Some(c) if is_renewed => {
+                new_due_date = c["due_date"].str()?;
+                c
+            }
+            _ => {
+                old_due_date = source_circ["due_date"].str()?;
+                reason = evt.desc().unwrap_or("");
+                source_circ
+            }
+        };
+
+        let total_remaining = circ["renewal_remaining"].int()?.max(0);
+
+        // nullable / maybe a string
+        let auto_remaining = circ["auto_renewal_remaining"]
+            .as_int()
+            .unwrap_or_default()
+            .max(total_remaining);
+
+        Ok(AutoRenewal {
+            circ_id: source_circ.id()?,
+            copy_id,
+            patron_id,
+            circ_lib,
+            is_renewed,
+            textcode: evt.textcode().to_string(),
+            reason: reason.to_string(),
+            new_due_date: new_due_date.to_string(),
+            old_due_date: old_due_date.to_string(),
+            total_renewal_remaining: total_remaining,
+            auto_renewal_remaining: auto_remaining,
+        })
+    }
+
+    /// ID of the circulation we attempted to renew.
+    pub fn circ_id(&self) -> i64 {
+        self.circ_id
+    }
+
+    pub fn copy_id(&self) -> i64 {
+        self.copy_id
+    }
+
+    pub fn patron_id(&self) -> i64 {
+        self.patron_id
+    }
+
+    pub fn circ_lib(&self) -> i64 {
+        self.circ_lib
+    }
+
+    pub fn is_renewed(&self) -> bool {
+        self.is_renewed
+    }
+
+    /// Event textcode returned by the renewal.
+    pub fn textcode(&self) -> &str {
+        &self.textcode
+    }
+
+    /// User data attached to the autorenewal A/T event.
+    pub fn notice_data(&self) -> EgValue {
+        eg::hash! {
+            "copy": self.copy_id,
+            "is_renewed": self.is_renewed,
+            "reason": self.reason.as_str(),
+            "new_due_date": self.new_due_date.as_str(),
+            "old_due_date": self.old_due_date.as_str(),
+            "textcode": self.textcode.as_str(),
+            "total_renewal_remaining": self.total_renewal_remaining,
+            "auto_renewal_remaining": self.auto_renewal_remaining,
+        }
+    }
+
+    /// Summary of the renewal for reporting to API callers.
+    pub fn to_eg_value(&self) -> EgValue {
+        let mut value = self.notice_data();
+        value["circ"] = EgValue::from(self.circ_id);
+        value["patron"] = EgValue::from(self.patron_id);
+        value
+    }
+
+    /// Create the autorenewal A/T events for the source circulation.
+    ///
+    /// Events are created from the source circ instead of the new circ,
+    /// since the renewal may have failed.
+    pub fn create_notice_events(&self, editor: &mut Editor, source_circ: &EgValue) -> EgResult<()> {
+        trigger::create_events_for_object(
+            editor,
+            NOTICE_HOOK,
+            source_circ,
+            self.circ_lib,
+            None,
+            Some(&self.notice_data()),
+            false,
+        )
+    }
+}
+
+/// "usr", "target_copy", etc. may be an ID or a fleshed object.
+fn link_id(value: &EgValue) -> EgResult<i64> {
+    match value.as_int() {
+        Some(id) => Ok(id),
+        None => value.id(),
+    }
+}
+
+/// Finds and renews circulations that are due for auto-renewal.
+pub struct AutoRenewer<'a> {
+    editor: &'a mut Editor,
+    settings: Settings,
+
+    /// Renew circs due between now and now + window.
+    window: String,
+
+    /// Maximum number of circs to process in one run.
+    limit: Option<i64>,
+
+    /// Auth session for the patron whose circs are being renewed.
+    /// Circs are processed in patron order, so we only need one.
+    patron_session: Option<(i64, String)>,
+
+    renewed: usize,
+    failed: usize,
+    skipped: usize,
+}
+
+impl<'a> AutoRenewer<'a> {
+    pub fn new(editor: &'a mut Editor) -> AutoRenewer<'a> {
+        let settings = Settings::new(editor);
+
+        AutoRenewer {
+            editor,
+            settings,
+            window: DEFAULT_WINDOW.to_string(),
+            limit: None,
+            patron_session: None,
+            renewed: 0,
+            failed: 0,
+            skipped: 0,
+        }
+    }
+
+    pub fn set_window(&mut self, window: &str) {
+        self.window = window.to_string();
+    }
+
+    pub fn set_limit(&mut self, limit: i64) {
+        self.limit = Some(limit);
+    }
+
+    pub fn renewed(&self) -> usize {
+        self.renewed
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    /// Circs skipped because auto-renewal is disabled for the
+    /// circulating library or the patron has opted out.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Summary counts for the run so far.
+    pub fn summary(&self) -> EgValue {
+        eg::hash! {
+            "renewed": self.renewed,
+            "failed": self.failed,
+            "skipped": self.skipped,
+        }
+    }
+
+    /// Find open circulations flagged for auto-renewal which are due
+    /// within our window and have renewals remaining.
+    pub fn find_circs(&mut self) -> EgResult<Vec<EgValue>> {
+        let now = date::now();
+        let end = date::add_interval(now, &self.window)?;
+
+        let query = eg::hash! {
+            "checkin_time": EgValue::Null,
+            "xact_finish": EgValue::Null,
+            "auto_renewal": "t",
+            "renewal_remaining": {">": 0},
+            "due_date": {"between": [date::to_iso(&now), date::to_iso(&end)]},
+            "-or": [
+                {"auto_renewal_remaining": EgValue::Null},
+                {"auto_renewal_remaining": {">": 0}},
+            ],
+        };
+
+        let mut ops = eg::hash! {"order_by": {"circ": "usr, due_date"}};
+
+        if let Some(limit) = self.limit {
+            ops["limit"] = EgValue::from(limit);
+        }
+
+        let circs = self.editor.search_with_ops("circ", query, ops)?;
+
+        log::info!(
+            "Found {} circs for auto-renewal due within {}",
+            circs.len(),
+            self.window
+        );
+
+        Ok(circs)
+    }
+
+    /// True if auto-renewal is disabled for the circ by its
+    /// circulating library or by the patron.
+    fn is_disabled(&mut self, circ: &EgValue) -> EgResult<bool> {
+        let circ_lib = link_id(&circ["circ_lib"])?;
+
+        if self
+            .settings
+            .get_value_at_org(ORG_DISABLED_SETTING, circ_lib)?
+            .boolish()
+        {
+            return Ok(true);
+        }
+
+        let mut ctx = SettingContext::new();
+        ctx.set_user_id(link_id(&circ["usr"])?);
+
+        Ok(self
+            .settings
+            .get_context_value(&ctx, USER_OPT_OUT_SETTING)?
+            .boolish())
+    }
+
+    /// Apply an auth session for the patron so the renewal runs
+    /// as if the patron renewed the item themselves.
+    fn apply_patron_session(&mut self, patron_id: i64) -> EgResult<()> {
+        if let Some((id, token)) = self.patron_session.as_ref() {
+            if *id == patron_id {
+                let token = token.to_string();
+                self.editor.set_authtoken(&token);
+                return Ok(());
+            }
+        }
+
+        let patron = self
+            .editor
+            .retrieve("au", patron_id)?
+            .ok_or_else(|| self.editor.die_event())?;
+
+        let mut auth_args = auth::InternalLoginArgs::new(patron_id, auth::LoginType::Opac);
+        auth_args.set_org_unit(patron["home_ou"].int()?);
+
+        let auth_ses =
+            auth::Session::internal_session_api(self.editor.client_mut(), &auth_args)?
+                .ok_or_else(|| format!("Cannot create auth session for patron {patron_id}"))?;
+
+        if !self.editor.apply_authtoken(auth_ses.token())? {
+            return Err(format!("Cannot apply auth session for patron {patron_id}").into());
+        }
+
+        self.patron_session = Some((patron_id, auth_ses.token().to_string()));
+
+        Ok(())
+    }
+
+    /// Attempt to renew one circulation and create its notice events.
+    ///
+    /// Returns None if the circ was skipped per settings.
+    pub fn renew_circ(&mut self, circ: &EgValue) -> EgResult<Option<AutoRenewal>> {
+        let result = self.try_renew_circ(circ);
+
+        if result.is_err() {
+            // Leave the editor usable for the next circ.
+            if let Err(e) = self.editor.rollback() {
+                log::warn!("Rollback failed after auto-renewal error: {e}");
+            }
+        }
+
+        result
+    }
+
+    fn try_renew_circ(&mut self, circ: &EgValue) -> EgResult<Option<AutoRenewal>> {
+        if self.is_disabled(circ)? {
+            log::info!("Auto-renewal disabled for circ {}", circ["id"]);
+            self.skipped += 1;
+            return Ok(None);
+        }
+
+        let patron_id = link_id(&circ["usr"])?;
+        let copy_id = link_id(&circ["target_copy"])?;
+
+        self.apply_patron_session(patron_id)?;
+
+        log::info!("Auto-Renewing Circ id={} copy={copy_id}", circ["id"]);
+
+        let options = HashMap::from([
+            ("patron_id".to_string(), EgValue::from(patron_id)),
+            ("copy_id".to_string(), EgValue::from(copy_id)),
+            ("auto_renewal".to_string(), EgValue::from(true)),
+        ]);
+
+        let mut circulator = Circulator::new(self.editor, options)?;
+
+        circulator.begin()?;
+
+        let (evt, new_circ) = match circulator.renew() {
+            Ok(()) => {
+                let evt = circulator
+                    .events()
+                    .first()
+                    .cloned()
+                    .unwrap_or_else(EgEvent::success);
+
+                if evt.is_success() {
+                    (evt, circulator.circ.clone())
+                } else {
+                    (evt, None)
+                }
+            }
+            Err(err) => (err.event_or_default(), None),
+        };
+
+        if new_circ.is_some() {
+            circulator.commit()?;
+            circulator.post_commit_tasks()?;
+        } else {
+            circulator.rollback()?;
+        }
+
+        let renewal = AutoRenewal::new(circ, &evt, new_circ.as_ref())?;
+
+        log::info!(
+            "Auto-renewal of circ {} returned {}",
+            renewal.circ_id(),
+            renewal.textcode()
+        );
+
+        if renewal.is_renewed() {
+            self.renewed += 1;
+        } else {
+            self.failed += 1;
+        }
+
+        self.editor.xact_begin()?;
+        renewal.create_notice_events(self.editor, circ)?;
+        self.editor.commit()?;
+
+        Ok(Some(renewal))
+    }
+}
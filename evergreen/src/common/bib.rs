@@ -186,6 +186,7 @@ pub struct RecordSummary {
     copy_counts: Vec<EgValue>,
     hold_count: i64,
     has_holdable_copy: bool,
+    brief: Option<marc::display::BriefView>,
 }
 
 impl RecordSummary {
@@ -199,6 +200,7 @@ impl RecordSummary {
         }
 
         let copy_counts = std::mem::take(&mut self.copy_counts);
+        let brief = self.brief.as_ref().map(brief_view_value);
 
         eg::hash! {
             id: self.id,
@@ -210,6 +212,7 @@ impl RecordSummary {
             hold_count: self.hold_count,
             urls: urls,
             has_holdable_copy: self.has_holdable_copy,
+            brief: brief,
             // TODO
             staff_view_metabib_attributes: eg::hash!{},
             // TODO
@@ -259,6 +262,11 @@ pub fn catalog_record_summary(
 
     let urls = record_urls(editor, None, Some(record["marc"].str()?))?;
 
+    let brief = match marc::Record::from_xml(record["marc"].str()?).next() {
+        Some(result) => Some(result?.brief_view()),
+        None => None,
+    };
+
     let note_count = record["notes"].len();
     let copy_counts = record_copy_counts(editor, org_id, rec_id, is_staff, is_meta)?;
     let hold_count = holds::record_hold_counts(editor, rec_id, None)?;
@@ -281,9 +289,73 @@ pub fn catalog_record_summary(
         attributes: attrs,
         has_holdable_copy,
         record_note_count: note_count,
+        brief,
     })
 }
 
+/// Translate a MARC brief view into a flat JSON-friendly hash.
+pub fn brief_view_value(view: &marc::display::BriefView) -> EgValue {
+    let mut authors = EgValue::new_array();
+
+    for author in view.authors() {
+        let author = eg::hash! {
+            name: author.name(),
+            relators: author.relators().clone(),
+            main_entry: author.is_main_entry(),
+        };
+        authors.push(author).expect("Is Array");
+    }
+
+    eg::hash! {
+        title: view.title(),
+        authors: authors,
+        edition: view.edition(),
+        imprint: view.imprint(),
+        physical_description: view.physical_description(),
+        series: view.series().clone(),
+        isbns: view.isbns().clone(),
+        issns: view.issns().clone(),
+        subjects: view.subjects().clone(),
+        material_types: view.material_types().clone(),
+    }
+}
+
+/// Count the material types of a set of bib records, e.g. for
+/// building format facets for a page of search results.
+///
+/// Returns (material type, record count) pairs, most common first.
+/// Deleted and nonexistent records are ignored.
+pub fn material_type_facets(
+    editor: &mut Editor,
+    rec_ids: &[i64],
+) -> EgResult<Vec<(String, usize)>> {
+    if rec_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+
+    let records = editor.search("bre", eg::hash! {"id": rec_ids, "deleted": "f"})?;
+
+    for bre in records {
+        let record = match marc::Record::from_xml(bre["marc"].str()?).next() {
+            Some(result) => result?,
+            None => continue,
+        };
+
+        for mtype in record.material_types() {
+            *counts.entry(mtype).or_default() += 1;
+        }
+    }
+
+    let mut facets: Vec<(String, usize)> = counts.into_iter().collect();
+
+    // Most common first, then alphabetical for stable output.
+    facets.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    Ok(facets)
+}
+
 pub struct RecordUrl {
     href: String,
     label: Option<String>,
@@ -405,3 +477,74 @@ pub fn record_copy_counts(
 
     Ok(data)
 }
+
+/// Outcome of applying batch edit tasks to a bib record.
+pub struct RecordEdit {
+    /// Number of fields added, removed, or modified.
+    pub change_count: usize,
+    /// Breaker-style diff of the changes.  Empty if nothing changed.
+    pub diff: String,
+    /// True if the edited record was saved.
+    pub saved: bool,
+}
+
+impl RecordEdit {
+    pub fn to_value(&self) -> EgValue {
+        eg::hash! {
+            "changed": !self.diff.is_empty(),
+            "change_count": self.change_count,
+            "diff": self.diff.as_str(),
+            "saved": self.saved,
+        }
+    }
+}
+
+/// Apply batch edit tasks to a bib record.
+///
+/// Unless this is a dry run, changed records are saved, which causes
+/// the database to reingest (or queue reingest of) the record.
+///
+/// The editor must be in a transaction unless this is a dry run.
+pub fn edit_record(
+    editor: &mut Editor,
+    rec_id: i64,
+    tasks: &marc::edit::EditTasks,
+    dry_run: bool,
+) -> EgResult<RecordEdit> {
+    let mut bre = editor
+        .retrieve("bre", rec_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    if bre["deleted"].boolish() {
+        return Err(format!("Bib record {rec_id} is deleted").into());
+    }
+
+    let original = match marc::Record::from_xml(bre["marc"].str()?).next() {
+        Some(result) => result?,
+        None => return Err(format!("Bib record {rec_id} has no MARC").into()),
+    };
+
+    let mut record = original.clone();
+    tasks.apply(&mut record);
+    let diff = original.diff(&record);
+
+    let mut edit = RecordEdit {
+        change_count: diff.changes().len(),
+        diff: diff.to_breaker(),
+        saved: false,
+    };
+
+    if dry_run || edit.diff.is_empty() {
+        return Ok(edit);
+    }
+
+    bre["marc"] = EgValue::from(record.to_xml_string());
+    bre["edit_date"] = EgValue::from("now");
+    bre["editor"] = EgValue::from(editor.requestor_id()?);
+
+    editor.update(bre)?;
+
+    edit.saved = true;
+
+    Ok(edit)
+}
@@ -0,0 +1,74 @@
+//! Containers (buckets) of bib records, call numbers, and copies.
+use crate as eg;
+use eg::Editor;
+use eg::EgResult;
+
+/// Bucket types whose items may be used in batch operations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BucketType {
+    Biblio,
+    CallNumber,
+    Copy,
+}
+
+impl BucketType {
+    /// Bucket IDL class.
+    pub fn bucket_class(&self) -> &'static str {
+        match self {
+            Self::Biblio => "cbreb",
+            Self::CallNumber => "ccnb",
+            Self::Copy => "ccb",
+        }
+    }
+
+    /// Bucket item IDL class.
+    pub fn item_class(&self) -> &'static str {
+        match self {
+            Self::Biblio => "cbrebi",
+            Self::CallNumber => "ccnbi",
+            Self::Copy => "ccbi",
+        }
+    }
+
+    /// Bucket item field linking to the bucket's target objects.
+    pub fn target_field(&self) -> &'static str {
+        match self {
+            Self::Biblio => "target_biblio_record_entry",
+            Self::CallNumber => "target_call_number",
+            Self::Copy => "target_copy",
+        }
+    }
+}
+
+/// Returns the IDs of the objects in a bucket, oldest item first.
+///
+/// The requestor may use their own buckets and public buckets.  Other
+/// buckets require VIEW_CONTAINER at the bucket owner's home library.
+pub fn target_ids(editor: &mut Editor, btype: BucketType, bucket_id: i64) -> EgResult<Vec<i64>> {
+    let bucket = editor
+        .retrieve(btype.bucket_class(), bucket_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    let owner_id = bucket["owner"].int()?;
+
+    if owner_id != editor.requestor_id()? && !bucket["pub"].boolish() {
+        let owner = editor
+            .retrieve("au", owner_id)?
+            .ok_or_else(|| editor.die_event())?;
+
+        if !editor.allowed_at("VIEW_CONTAINER", owner["home_ou"].int()?)? {
+            return Err(editor.die_event());
+        }
+    }
+
+    let query = eg::hash! {"bucket": bucket_id};
+    let ops = eg::hash! {"order_by": {[btype.item_class()]: "id"}};
+
+    let mut ids = Vec::new();
+
+    for item in editor.search_with_ops(btype.item_class(), query, ops)? {
+        ids.push(item[btype.target_field()].int()?);
+    }
+
+    Ok(ids)
+}
@@ -36,6 +36,7 @@ impl Circulator<'_> {
 
         self.set_circ_policy()?;
         self.inspect_policy_failures()?;
+        self.load_runtime_copy_alerts()?;
         self.check_copy_alerts()?;
         self.try_override_events()?;
 
@@ -1144,6 +1145,12 @@ impl Circulator<'_> {
         false
     }
 
+    /// Bill the patron for the copy's deposit or rental fee.
+    ///
+    /// Callers may pass the "skip_deposit_fee" or "skip_rental_fee"
+    /// options to suppress the bill, e.g. when the fee was collected
+    /// by other means.  Patrons in the exempt groups configured via
+    /// org settings are never billed.
     fn apply_deposit_fee(&mut self) -> EgResult<()> {
         let is_deposit = self.is_deposit();
         let is_rental = self.is_rental();
@@ -1155,13 +1162,11 @@ impl Circulator<'_> {
         // confirmed above
         let deposit_amount = self.copy()["deposit_amount"].as_f64().unwrap();
 
-        let skip_deposit_fee = self.settings.get_value("skip_deposit_fee")?.boolish();
-        if is_deposit && (skip_deposit_fee || self.is_deposit_exempt()?) {
+        if is_deposit && (self.get_option_bool("skip_deposit_fee") || self.is_deposit_exempt()?) {
             return Ok(());
         }
 
-        let skip_rental_fee = self.settings.get_value("skip_rental_fee")?.boolish();
-        if is_rental && (skip_rental_fee | self.is_rental_exempt()?) {
+        if is_rental && (self.get_option_bool("skip_rental_fee") || self.is_rental_exempt()?) {
             return Ok(());
         }
 
@@ -1198,26 +1203,22 @@ impl Circulator<'_> {
     }
 
     fn is_deposit_exempt(&mut self) -> EgResult<bool> {
-        let profile = self.patron.as_ref().unwrap()["profile"].id()?;
-
-        let groups = self.settings.get_value("circ.deposit.exempt_groups")?;
-
-        if !groups.is_array() || groups.is_empty() {
-            return Ok(false);
-        }
-
-        let mut parent_ids = Vec::new();
-        for grp in groups.members() {
-            parent_ids.push(grp.id()?);
-        }
-
-        self.is_group_descendant(profile, parent_ids.as_slice())
+        self.is_exempt_by_group("circ.deposit.exempt_groups")
     }
 
     fn is_rental_exempt(&mut self) -> EgResult<bool> {
-        let profile = self.patron.as_ref().unwrap()["profile"].id()?;
+        self.is_exempt_by_group("circ.rental.exempt_groups")
+    }
+
+    /// True if the patron's profile is within one of the permission
+    /// groups listed in the provided org setting.
+    fn is_exempt_by_group(&mut self, setting: &str) -> EgResult<bool> {
+        let profile = match self.patron.as_ref() {
+            Some(p) => p["profile"].id()?,
+            None => return Ok(false),
+        };
 
-        let groups = self.settings.get_value("circ.rental.exempt_groups")?;
+        let groups = self.settings.get_value(setting)?;
 
         if !groups.is_array() || groups.is_empty() {
             return Ok(false);
@@ -1,5 +1,6 @@
 //! Shared, circ-focused utility functions
 use crate as eg;
+use eg::common::trigger;
 use eg::Editor;
 use eg::EgResult;
 use eg::EgValue;
@@ -34,3 +35,61 @@ pub fn circ_chain(e: &mut Editor, circ_id: i64) -> EgResult<Vec<EgValue>> {
 
     Ok(chains)
 }
+
+/// A/T hook for emailed checkout receipts.  The hook and an event
+/// definition grouped on "usr", so one email covers all circs, are
+/// shipped in schema/email-checkout-receipt.sql.
+pub const EMAIL_RECEIPT_HOOK: &str = "circ.checkout.email_receipt";
+
+/// Create A/T events which send a receipt email listing the provided
+/// circulations to the patron who owns them.
+///
+/// All circulations must belong to the same patron.  Patrons with no
+/// email address are skipped.  The A/T runner renders and delivers
+/// the email.
+///
+/// The editor must be in a transaction.
+///
+/// Returns the patron ID.
+pub fn create_email_receipt_events(e: &mut Editor, circ_ids: &[i64]) -> EgResult<i64> {
+    if circ_ids.is_empty() {
+        return Err("Email receipt requires at least one circulation".into());
+    }
+
+    let circs = e.search("circ", eg::hash! {id: circ_ids})?;
+
+    if circs.len() != circ_ids.len() {
+        return Err(format!("Cannot find all circulations for receipt: {circ_ids:?}").into());
+    }
+
+    let user_id = circs[0]["usr"].int()?;
+
+    if circs.iter().any(|c| c["usr"].as_int() != Some(user_id)) {
+        return Err("Email receipt circulations belong to multiple patrons".into());
+    }
+
+    let user = e.retrieve("au", user_id)?.ok_or_else(|| e.die_event())?;
+
+    if user["email"]
+        .as_str()
+        .map(|s| s.trim().is_empty())
+        .unwrap_or(true)
+    {
+        log::info!("User {user_id} has no email address.  Skipping email receipt");
+        return Ok(user_id);
+    }
+
+    for circ in circs.iter() {
+        trigger::create_events_for_object(
+            e,
+            EMAIL_RECEIPT_HOOK,
+            circ,
+            circ["circ_lib"].int()?,
+            None,
+            None,
+            false,
+        )?;
+    }
+
+    Ok(user_id)
+}
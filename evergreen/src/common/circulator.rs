@@ -1,6 +1,8 @@
 use crate as eg;
+use eg::common::copy_alert;
 use eg::common::holds;
 use eg::common::org;
+use eg::common::override_token::{self, OverrideToken};
 use eg::common::settings::Settings;
 use eg::common::trigger;
 use eg::constants as C;
@@ -173,6 +175,13 @@ pub struct Circulator<'a> {
     /// to the caller.
     pub failed_events: Vec<EgEvent>,
 
+    /// Textcodes of events that were successfully overridden.
+    pub overridden_events: Vec<String>,
+
+    /// Supervisor-approved overrides provided via the
+    /// "override_token" option.
+    pub override_token: Option<OverrideToken>,
+
     /// None until a status is determined one way or the other.
     pub is_booking_enabled: Option<bool>,
 
@@ -271,6 +280,8 @@ impl<'a> Circulator<'a> {
             is_override: false,
             override_args: None,
             failed_events: Vec::new(),
+            overridden_events: Vec::new(),
+            override_token: None,
             exit_early: false,
             is_booking_enabled: None,
             retarget_holds: None,
@@ -785,6 +796,8 @@ impl<'a> Circulator<'a> {
             return Ok(());
         }
 
+        self.acknowledge_runtime_copy_alerts()?;
+
         let mut alert_on = Vec::new();
         for alert in self.runtime_copy_alerts.iter() {
             alert_on.push(alert.clone());
@@ -816,6 +829,39 @@ impl<'a> Circulator<'a> {
         Ok(())
     }
 
+    /// Acknowledge runtime copy alerts instead of reporting them when
+    /// the caller requests it via the "ack_copy_alerts" option and
+    /// the requestor is allowed to override copy alerts.
+    fn acknowledge_runtime_copy_alerts(&mut self) -> EgResult<()> {
+        if self.runtime_copy_alerts.is_empty()
+            || self.is_inspect()
+            || !self.get_option_bool("ack_copy_alerts")
+        {
+            return Ok(());
+        }
+
+        let circ_lib = self.circ_lib;
+        if !self
+            .editor()
+            .allowed_at("COPY_ALERT_MESSAGE.override", circ_lib)?
+        {
+            return Ok(());
+        }
+
+        let mut alert_ids = Vec::new();
+        for alert in self.runtime_copy_alerts.iter() {
+            alert_ids.push(alert.id()?);
+        }
+
+        let acked = copy_alert::acknowledge(self.editor(), &alert_ids)?;
+
+        log::info!("{self} acknowledged copy alerts {acked:?}");
+
+        self.runtime_copy_alerts.clear();
+
+        Ok(())
+    }
+
     /// Find an open circulation linked to our copy if possible.
     fn load_circ(&mut self) -> EgResult<()> {
         if self.circ.is_some() {
@@ -967,6 +1013,7 @@ impl<'a> Circulator<'a> {
             self.is_noncat = v.boolish();
         }
 
+        self.load_override_token()?;
         self.load_copy()?;
         self.load_patron()?;
         self.load_circ()?;
@@ -975,6 +1022,43 @@ impl<'a> Circulator<'a> {
         Ok(())
     }
 
+    /// Apply the events approved by a supervisor override token.
+    ///
+    /// Exits with PERM_FAILURE if the token is unknown, expired, or
+    /// was issued to a different staff account.
+    fn load_override_token(&mut self) -> EgResult<()> {
+        let token = match self
+            .options
+            .get(override_token::OVERRIDE_TOKEN_PARAM)
+            .and_then(|t| t.as_str())
+        {
+            Some(t) => t.to_string(),
+            None => return Ok(()),
+        };
+
+        let requestor_id = self.requestor_id()?;
+
+        let token = match OverrideToken::retrieve(&token, requestor_id)? {
+            Some(t) => t,
+            None => {
+                log::warn!("{self} invalid or expired override token");
+                return self.exit_err_on_event_code("PERM_FAILURE");
+            }
+        };
+
+        self.is_override = true;
+
+        match self.override_args.as_mut() {
+            Some(Overrides::All) => {}
+            Some(Overrides::Events(list)) => list.extend(token.events().iter().cloned()),
+            None => self.override_args = Some(Overrides::Events(token.events().clone())),
+        }
+
+        self.override_token = Some(token);
+
+        Ok(())
+    }
+
     /// Perform post-commit tasks and cleanup, i.e. jobs that can
     /// be performed after one of our core actions (e.g. checkin) has
     /// completed and produced a response.
@@ -1075,6 +1159,18 @@ impl<'a> Circulator<'a> {
                 continue;
             }
 
+            if let Some(token) = self.override_token.as_ref() {
+                if token.covers(evt.textcode()) {
+                    log::info!(
+                        "{selfstr} overriding {} approved by supervisor {}",
+                        evt.textcode(),
+                        token.supervisor_id()
+                    );
+                    self.overridden_events.push(evt.textcode().to_string());
+                    continue;
+                }
+            }
+
             let perm = format!("{}.override", evt.textcode());
             log::info!("{selfstr} attempting to override: {perm}");
 
@@ -1087,6 +1183,8 @@ impl<'a> Circulator<'a> {
                     // Should not get here.
                     self.failed_events.push(evt);
                 }
+            } else {
+                self.overridden_events.push(evt.textcode().to_string());
             }
         }
 
@@ -0,0 +1,517 @@
+//! Bibliographic citation export.
+//!
+//! Converts bib records into RIS, BibTeX, and CSL-JSON citations so
+//! catalog clients can offer "export citation" without parsing MARC.
+//! Descriptive values come from the marctk brief view, so they are
+//! normalized and stripped of ISBD punctuation.
+use crate as eg;
+use eg::{Editor, EgResult, EgValue};
+use marc::display::clean_value;
+use marctk as marc;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CitationFormat {
+    Ris,
+    Bibtex,
+    CslJson,
+}
+
+impl FromStr for CitationFormat {
+    type Err = String;
+
+    /// ```
+    /// use evergreen::common::citation::CitationFormat;
+    ///
+    /// assert_eq!("ris".parse::<CitationFormat>(), Ok(CitationFormat::Ris));
+    /// assert_eq!("csl-json".parse::<CitationFormat>(), Ok(CitationFormat::CslJson));
+    /// assert!("mla".parse::<CitationFormat>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ris" => Ok(Self::Ris),
+            "bibtex" => Ok(Self::Bibtex),
+            "csl-json" | "csljson" | "csl" => Ok(Self::CslJson),
+            _ => Err(format!("Unsupported citation format: {s}")),
+        }
+    }
+}
+
+/// General kind of resource, mapped to each format's type vocabulary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ResourceKind {
+    Book,
+    Ebook,
+    Serial,
+    Score,
+    Map,
+    SoundRecording,
+    MusicRecording,
+    Video,
+    Software,
+    Image,
+    Other,
+}
+
+impl ResourceKind {
+    fn from_record(record: &marc::Record) -> ResourceKind {
+        let leader = record.leader().as_bytes();
+        let rec_type = leader[6];
+        let bib_level = leader[7];
+
+        match rec_type {
+            b'a' | b't' if b"bis".contains(&bib_level) => ResourceKind::Serial,
+            b'a' | b't' => {
+                let form = record
+                    .get_control_fields("008")
+                    .first()
+                    .and_then(|cf| cf.content().chars().nth(23));
+
+                if matches!(form, Some('o' | 'q' | 's')) {
+                    ResourceKind::Ebook
+                } else {
+                    ResourceKind::Book
+                }
+            }
+            b'c' | b'd' => ResourceKind::Score,
+            b'e' | b'f' => ResourceKind::Map,
+            b'i' => ResourceKind::SoundRecording,
+            b'j' => ResourceKind::MusicRecording,
+            b'g' => ResourceKind::Video,
+            b'm' => ResourceKind::Software,
+            b'k' => ResourceKind::Image,
+            _ => ResourceKind::Other,
+        }
+    }
+
+    fn ris_type(&self) -> &'static str {
+        match self {
+            Self::Book => "BOOK",
+            Self::Ebook => "EBOOK",
+            Self::Serial => "JFULL",
+            Self::Score => "MUSIC",
+            Self::Map => "MAP",
+            Self::SoundRecording => "SOUND",
+            Self::MusicRecording => "MUSIC",
+            Self::Video => "VIDEO",
+            Self::Software => "COMP",
+            Self::Image => "ART",
+            Self::Other => "GEN",
+        }
+    }
+
+    fn bibtex_type(&self) -> &'static str {
+        match self {
+            Self::Book | Self::Ebook => "book",
+            _ => "misc",
+        }
+    }
+
+    fn csl_type(&self) -> &'static str {
+        match self {
+            Self::Book | Self::Ebook => "book",
+            Self::Serial => "periodical",
+            Self::Score => "musical_score",
+            Self::Map => "map",
+            Self::SoundRecording | Self::MusicRecording => "song",
+            Self::Video => "motion_picture",
+            Self::Software => "software",
+            Self::Image => "graphic",
+            Self::Other => "document",
+        }
+    }
+}
+
+/// A personal or corporate name.
+#[derive(Debug, Clone, PartialEq)]
+struct Name {
+    name: String,
+    is_personal: bool,
+}
+
+impl Name {
+    /// Split an inverted personal name into (family, given).
+    fn family_given(&self) -> Option<(&str, &str)> {
+        if !self.is_personal {
+            return None;
+        }
+        self.name
+            .split_once(", ")
+            .map(|(f, g)| (f.trim(), g.trim()))
+    }
+}
+
+/// Citation data extracted from a bib record.
+#[derive(Debug, Clone)]
+pub struct Citation {
+    id: i64,
+    kind: ResourceKind,
+    title: Option<String>,
+    authors: Vec<Name>,
+    edition: Option<String>,
+    place: Option<String>,
+    publisher: Option<String>,
+    year: Option<String>,
+    isbns: Vec<String>,
+    issns: Vec<String>,
+    series: Vec<String>,
+    keywords: Vec<String>,
+    urls: Vec<String>,
+}
+
+impl Citation {
+    /// Extract citation data from a MARC record.
+    ///
+    /// ```
+    /// use evergreen::common::citation::Citation;
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =008 170101s2017\\\\flu\\\\\\\\\\\000\0\spa\d
+    /// =020 \\$a9781945540042$q(paperback)
+    /// =100 1\$aCala, Ismael.$eauthor.
+    /// =245 10$aDespierta con Cala :$binspiraciones /$cIsmael Cala.
+    /// =264 \1$aMiami, FL :$bAguilar,$c2017."#
+    /// ).unwrap();
+    ///
+    /// let citation = Citation::from_record(42, &record);
+    ///
+    /// assert_eq!(
+    ///     citation.to_ris(),
+    ///     "TY  - BOOK\nTI  - Despierta con Cala : inspiraciones\nAU  - Cala, Ismael\n\
+    ///      PY  - 2017\nCY  - Miami, FL\nPB  - Aguilar\nSN  - 9781945540042\nID  - 42\nER  - \n"
+    /// );
+    ///
+    /// assert_eq!(
+    ///     citation.to_bibtex(),
+    ///     "@book{eg42,\n  title = {Despierta con Cala : inspiraciones},\n  \
+    ///      author = {Cala, Ismael},\n  year = {2017},\n  address = {Miami, FL},\n  \
+    ///      publisher = {Aguilar},\n  isbn = {9781945540042}\n}\n"
+    /// );
+    ///
+    /// let csl = citation.to_csl_json();
+    /// assert_eq!(csl["type"].str().unwrap(), "book");
+    /// assert_eq!(csl["author"][0]["family"].str().unwrap(), "Cala");
+    /// assert_eq!(csl["issued"]["date-parts"][0][0].int().unwrap(), 2017);
+    /// ```
+    pub fn from_record(id: i64, record: &marc::Record) -> Citation {
+        let view = record.brief_view();
+
+        let mut citation = Citation {
+            id,
+            kind: ResourceKind::from_record(record),
+            title: view.title().map(|t| t.to_string()),
+            authors: Vec::new(),
+            edition: view.edition().map(|e| e.to_string()),
+            place: None,
+            publisher: None,
+            year: None,
+            isbns: view.isbns().clone(),
+            issns: view.issns().clone(),
+            series: view.series().clone(),
+            keywords: view.subjects().clone(),
+            urls: Vec::new(),
+        };
+
+        for field in record.extract_fields("100:110:111:700:710:711") {
+            let codes = if field.tag().ends_with("00") {
+                "a"
+            } else {
+                "ab"
+            };
+
+            let parts: Vec<&str> = field
+                .subfields()
+                .iter()
+                .filter(|sf| codes.contains(sf.code()))
+                .map(|sf| sf.content())
+                .collect();
+
+            let name = clean_value(&parts.join(" "));
+
+            if !name.is_empty() && !citation.authors.iter().any(|a| a.name == name) {
+                citation.authors.push(Name {
+                    name,
+                    is_personal: field.tag().ends_with("00"),
+                });
+            }
+        }
+
+        // Prefer the 264 publication statement, falling back to 260.
+        let imprint = record
+            .get_fields("264")
+            .into_iter()
+            .find(|f| f.ind2() == "1")
+            .or_else(|| record.get_fields("260").into_iter().next());
+
+        if let Some(field) = imprint {
+            let first = |code| {
+                field
+                    .first_subfield(code)
+                    .map(|sf| clean_value(sf.content()))
+                    .filter(|v| !v.is_empty())
+            };
+
+            citation.place = first("a");
+            citation.publisher = first("b");
+            citation.year = first("c").and_then(|c| Self::extract_year(&c));
+        }
+
+        if citation.year.is_none() {
+            // 008/07-10 Date 1
+            citation.year = record
+                .get_control_fields("008")
+                .first()
+                .and_then(|cf| cf.content().get(7..11))
+                .and_then(Self::extract_year);
+        }
+
+        for field in record.get_fields("856") {
+            if field.ind1() == "4" {
+                for sf in field.get_subfields("u") {
+                    let url = sf.content().trim();
+                    if !url.is_empty() {
+                        citation.urls.push(url.to_string());
+                    }
+                }
+            }
+        }
+
+        citation
+    }
+
+    /// Find the first 4-digit year in a date string, e.g. "c2017."
+    fn extract_year(value: &str) -> Option<String> {
+        value
+            .as_bytes()
+            .windows(4)
+            .find(|w| w.iter().all(|b| b.is_ascii_digit()))
+            .map(|w| String::from_utf8_lossy(w).to_string())
+    }
+
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    /// Render the citation in the requested format.
+    ///
+    /// RIS and BibTeX citations are returned as strings.  CSL-JSON
+    /// citations are returned as objects.
+    pub fn to_format(&self, format: CitationFormat) -> EgValue {
+        match format {
+            CitationFormat::Ris => EgValue::from(self.to_ris()),
+            CitationFormat::Bibtex => EgValue::from(self.to_bibtex()),
+            CitationFormat::CslJson => self.to_csl_json(),
+        }
+    }
+
+    pub fn to_ris(&self) -> String {
+        let mut ris = String::new();
+        let mut add = |tag: &str, value: &str| {
+            ris += &format!("{tag}  - {value}\n");
+        };
+
+        add("TY", self.kind.ris_type());
+
+        if let Some(v) = self.title.as_deref() {
+            add("TI", v);
+        }
+        for author in &self.authors {
+            add("AU", &author.name);
+        }
+        if let Some(v) = self.edition.as_deref() {
+            add("ET", v);
+        }
+        if let Some(v) = self.year.as_deref() {
+            add("PY", v);
+        }
+        if let Some(v) = self.place.as_deref() {
+            add("CY", v);
+        }
+        if let Some(v) = self.publisher.as_deref() {
+            add("PB", v);
+        }
+        for v in self.isbns.iter().chain(self.issns.iter()) {
+            add("SN", v);
+        }
+        for v in &self.series {
+            add("T3", v);
+        }
+        for v in &self.keywords {
+            add("KW", v);
+        }
+        for v in &self.urls {
+            add("UR", v);
+        }
+
+        add("ID", &self.id.to_string());
+        add("ER", "");
+
+        ris
+    }
+
+    pub fn to_bibtex(&self) -> String {
+        let mut fields: Vec<(&str, String)> = Vec::new();
+
+        if let Some(v) = self.title.as_deref() {
+            fields.push(("title", bibtex_escape(v)));
+        }
+
+        if !self.authors.is_empty() {
+            let names: Vec<String> = self
+                .authors
+                .iter()
+                .map(|a| {
+                    if a.is_personal {
+                        bibtex_escape(&a.name)
+                    } else {
+                        // Braces prevent corporate names from being
+                        // parsed as family/given names.
+                        format!("{{{}}}", bibtex_escape(&a.name))
+                    }
+                })
+                .collect();
+
+            fields.push(("author", names.join(" and ")));
+        }
+
+        let optional = [
+            ("edition", &self.edition),
+            ("year", &self.year),
+            ("address", &self.place),
+            ("publisher", &self.publisher),
+        ];
+
+        for (key, value) in optional {
+            if let Some(v) = value {
+                fields.push((key, bibtex_escape(v)));
+            }
+        }
+
+        if let Some(v) = self.isbns.first() {
+            fields.push(("isbn", bibtex_escape(v)));
+        }
+        if let Some(v) = self.issns.first() {
+            fields.push(("issn", bibtex_escape(v)));
+        }
+        if let Some(v) = self.series.first() {
+            fields.push(("series", bibtex_escape(v)));
+        }
+        if let Some(v) = self.urls.first() {
+            fields.push(("url", v.to_string()));
+        }
+        if !self.keywords.is_empty() {
+            fields.push(("keywords", bibtex_escape(&self.keywords.join(", "))));
+        }
+
+        let body: Vec<String> = fields
+            .iter()
+            .map(|(k, v)| format!("  {k} = {{{v}}}"))
+            .collect();
+
+        format!(
+            "@{}{{eg{},\n{}\n}}\n",
+            self.kind.bibtex_type(),
+            self.id,
+            body.join(",\n")
+        )
+    }
+
+    pub fn to_csl_json(&self) -> EgValue {
+        let mut csl = eg::hash! {
+            "id": self.id.to_string(),
+            "type": self.kind.csl_type(),
+        };
+
+        let authors: Vec<EgValue> = self
+            .authors
+            .iter()
+            .map(|a| match a.family_given() {
+                Some((family, given)) => eg::hash! {"family": family, "given": given},
+                None => eg::hash! {"literal": a.name.as_str()},
+            })
+            .collect();
+
+        if !authors.is_empty() {
+            csl["author"] = EgValue::from(authors);
+        }
+
+        let optional = [
+            ("title", &self.title),
+            ("edition", &self.edition),
+            ("publisher", &self.publisher),
+            ("publisher-place", &self.place),
+        ];
+
+        for (key, value) in optional {
+            if let Some(v) = value {
+                csl[key] = EgValue::from(v.as_str());
+            }
+        }
+
+        if let Some(year) = self.year.as_deref().and_then(|y| y.parse::<i64>().ok()) {
+            csl["issued"] = eg::hash! {"date-parts": vec![EgValue::from(vec![year])]};
+        }
+
+        if let Some(v) = self.isbns.first() {
+            csl["ISBN"] = EgValue::from(v.as_str());
+        }
+        if let Some(v) = self.issns.first() {
+            csl["ISSN"] = EgValue::from(v.as_str());
+        }
+        if let Some(v) = self.series.first() {
+            csl["collection-title"] = EgValue::from(v.as_str());
+        }
+        if let Some(v) = self.urls.first() {
+            csl["URL"] = EgValue::from(v.as_str());
+        }
+
+        csl
+    }
+}
+
+/// Escape characters with special meaning in BibTeX values.
+///
+/// ```
+/// use evergreen::common::citation::bibtex_escape;
+///
+/// assert_eq!(bibtex_escape("Smith & Sons {Ltd} 100%"), r"Smith \& Sons \{Ltd\} 100\%");
+/// ```
+pub fn bibtex_escape(value: &str) -> String {
+    let mut escaped = String::new();
+
+    for c in value.chars() {
+        match c {
+            '\\' => escaped += r"\textbackslash{}",
+            '{' | '}' | '&' | '%' | '$' | '#' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Load a bib record and build its citation.
+///
+/// Returns None if the record does not exist or is deleted.
+pub fn record_citation(editor: &mut Editor, rec_id: i64) -> EgResult<Option<Citation>> {
+    let bre = match editor.retrieve("bre", rec_id)? {
+        Some(r) => r,
+        None => return Ok(None),
+    };
+
+    if bre["deleted"].boolish() {
+        return Ok(None);
+    }
+
+    let record = match marc::Record::from_xml(bre["marc"].str()?).next() {
+        Some(result) => result?,
+        None => return Err(format!("Bib record {rec_id} has no MARC").into()),
+    };
+
+    Ok(Some(Citation::from_record(rec_id, &record)))
+}
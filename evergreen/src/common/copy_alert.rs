@@ -0,0 +1,237 @@
+//! Copy alerts, copy alert types, and copy alert suppressions.
+//!
+//! Copy alerts ("aca") are attached to individual copies and raised
+//! during circulation until acknowledged.  Alert types ("ccat") define
+//! when an alert applies.  Suppressions ("acas") disable an alert type
+//! at an org unit and its descendants.
+use crate as eg;
+use eg::common::org;
+use eg::util;
+use eg::Editor;
+use eg::EgResult;
+use eg::EgValue;
+
+/// Verify the editor may edit alerts on the copy.
+fn check_copy_perm(editor: &mut Editor, copy_id: i64) -> EgResult<()> {
+    let copy = editor
+        .retrieve("acp", copy_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    if !editor.allowed_at("UPDATE_COPY", copy["circ_lib"].int()?)? {
+        return Err(editor.die_event());
+    }
+
+    Ok(())
+}
+
+/// Alerts for a copy with their alert types fleshed.
+///
+/// Acknowledged alerts are only included if requested.
+pub fn copy_alerts(editor: &mut Editor, copy_id: i64, with_acked: bool) -> EgResult<Vec<EgValue>> {
+    let mut query = eg::hash! {"copy": copy_id};
+
+    if !with_acked {
+        query["ack_time"] = EgValue::Null;
+    }
+
+    let ops = eg::hash! {
+        "flesh": 1,
+        "flesh_fields": {"aca": ["alert_type"]},
+        "order_by": {"aca": "create_time"},
+    };
+
+    editor.search_with_ops("aca", query, ops)
+}
+
+/// Create a copy alert.
+///
+/// The creator and create time are set here.
+///
+/// The editor must be in a transaction.
+pub fn create_alert(editor: &mut Editor, mut alert: EgValue) -> EgResult<EgValue> {
+    util::verify_class(&alert, "aca")?;
+
+    check_copy_perm(editor, alert["copy"].int()?)?;
+
+    alert["create_staff"] = EgValue::from(editor.requestor_id()?);
+    alert["create_time"] = EgValue::from("now");
+
+    editor.create(alert)
+}
+
+/// Update a copy alert.
+///
+/// The editor must be in a transaction.
+pub fn update_alert(editor: &mut Editor, alert: EgValue) -> EgResult<()> {
+    util::verify_class(&alert, "aca")?;
+
+    let existing = editor
+        .retrieve("aca", alert.id()?)?
+        .ok_or_else(|| editor.die_event())?;
+
+    // Moving an alert requires access to both copies.
+    check_copy_perm(editor, existing["copy"].int()?)?;
+
+    if existing["copy"] != alert["copy"] {
+        check_copy_perm(editor, alert["copy"].int()?)?;
+    }
+
+    editor.update(alert)
+}
+
+/// Delete a copy alert.
+///
+/// The editor must be in a transaction.
+pub fn delete_alert(editor: &mut Editor, alert_id: i64) -> EgResult<()> {
+    let alert = editor
+        .retrieve("aca", alert_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    check_copy_perm(editor, alert["copy"].int()?)?;
+
+    editor.delete(alert)?;
+
+    Ok(())
+}
+
+/// Acknowledge copy alerts as the requestor.
+///
+/// Alerts which are already acknowledged are left as-is.  Callers are
+/// responsible for verifying the requestor may acknowledge the alerts.
+///
+/// The editor must be in a transaction.
+///
+/// Returns the IDs of the newly acknowledged alerts.
+pub fn acknowledge(editor: &mut Editor, alert_ids: &[i64]) -> EgResult<Vec<i64>> {
+    if alert_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let requestor = editor.requestor_id()?;
+    let query = eg::hash! {"id": alert_ids, "ack_time": EgValue::Null};
+
+    let mut acked = Vec::new();
+
+    for mut alert in editor.search("aca", query)? {
+        acked.push(alert.id()?);
+
+        alert["ack_time"] = EgValue::from("now");
+        alert["ack_staff"] = EgValue::from(requestor);
+
+        editor.update(alert)?;
+    }
+
+    Ok(acked)
+}
+
+/// All copy alert types, sorted by name.
+pub fn alert_types(editor: &mut Editor) -> EgResult<Vec<EgValue>> {
+    let query = eg::hash! {"id": {"!=": EgValue::Null}};
+    let ops = eg::hash! {"order_by": {"ccat": "name"}};
+
+    editor.search_with_ops("ccat", query, ops)
+}
+
+/// Create or update a copy alert type.  Types with no ID are created.
+///
+/// The editor must be in a transaction.
+///
+/// Returns the alert type ID.
+pub fn save_alert_type(editor: &mut Editor, atype: EgValue) -> EgResult<i64> {
+    util::verify_class(&atype, "ccat")?;
+
+    let scope_org = atype["scope_org"].int()?;
+
+    if atype["id"].is_null() {
+        if !editor.allowed_at("ADMIN_COPY_ALERT_TYPE", scope_org)? {
+            return Err(editor.die_event());
+        }
+
+        return editor.create(atype)?.id();
+    }
+
+    let type_id = atype.id()?;
+
+    let existing = editor
+        .retrieve("ccat", type_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    // Moving a type requires permission at both the old and new scope.
+    let mut orgs = vec![existing["scope_org"].int()?];
+    if orgs[0] != scope_org {
+        orgs.push(scope_org);
+    }
+
+    for org_id in orgs {
+        if !editor.allowed_at("ADMIN_COPY_ALERT_TYPE", org_id)? {
+            return Err(editor.die_event());
+        }
+    }
+
+    editor.update(atype)?;
+
+    Ok(type_id)
+}
+
+/// Alert types suppressed at the org unit, as a hash of alert type
+/// ID to the list of org units along the org unit's full path which
+/// suppress the type.
+///
+/// Alert types which are not suppressed are not included.
+pub fn suppression_matrix(editor: &mut Editor, org_id: i64) -> EgResult<EgValue> {
+    let orgs = org::full_path(editor, org_id, None)?;
+
+    let mut matrix = EgValue::new_object();
+
+    for supp in editor.search("acas", eg::hash! {"org": orgs.as_slice()})? {
+        let type_id = supp["alert_type"].int()?.to_string();
+
+        if matrix[type_id.as_str()].is_null() {
+            matrix[type_id.as_str()] = EgValue::new_array();
+        }
+
+        matrix[type_id.as_str()].push(supp["org"].int()?)?;
+    }
+
+    Ok(matrix)
+}
+
+/// Suppress an alert type at an org unit.
+///
+/// Does nothing if the alert type is already suppressed at the org unit.
+///
+/// The editor must be in a transaction.
+///
+/// Returns the suppression ID.
+pub fn suppress(editor: &mut Editor, alert_type: i64, org_id: i64) -> EgResult<i64> {
+    if !editor.allowed_at("ADMIN_COPY_ALERT_SUPPRESS", org_id)? {
+        return Err(editor.die_event());
+    }
+
+    let query = eg::hash! {"alert_type": alert_type, "org": org_id};
+
+    if let Some(existing) = editor.search("acas", query)?.pop() {
+        return existing.id();
+    }
+
+    let supp = eg::hash! {"alert_type": alert_type, "org": org_id};
+
+    editor.create(EgValue::create("acas", supp)?)?.id()
+}
+
+/// Remove a copy alert suppression.
+///
+/// The editor must be in a transaction.
+pub fn delete_suppression(editor: &mut Editor, supp_id: i64) -> EgResult<()> {
+    let supp = editor
+        .retrieve("acas", supp_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    if !editor.allowed_at("ADMIN_COPY_ALERT_SUPPRESS", supp["org"].int()?)? {
+        return Err(editor.die_event());
+    }
+
+    editor.delete(supp)?;
+
+    Ok(())
+}
@@ -0,0 +1,299 @@
+//! Feature flags for gradually rolling out service features.
+//!
+//! Flags are global flags ("cgf") named "rs.feature.<name>".  A flag
+//! applies only when it is enabled.  Its value, if set, is a JSON hash
+//! which further limits where the flag applies:
+//!
+//! ```text
+//! {"percent": 10, "orgs": [4, 5]}
+//! ```
+//!
+//! * percent -- Share of lookups, 0-100, for which the flag applies.
+//!   Lookups are bucketed on a caller-provided key, e.g. a copy barcode,
+//!   so the same key always gets the same answer.  Lookups without a key
+//!   are bucketed at random.  Defaults to 100.
+//! * orgs -- Org units where the flag applies, including their
+//!   descendants.  Defaults to everywhere.
+//!
+//! Flags are cached per process.  Changes made via [`save`] clear the
+//! local cache.  Other processes pick up changes once their cache
+//! expires, so flags may be flipped without restarting services.
+use crate as eg;
+use eg::common::org;
+use eg::Editor;
+use eg::EgResult;
+use eg::EgValue;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Global flag name prefix for feature flags.
+pub const FLAG_PREFIX: &str = "rs.feature.";
+
+/// How long a cached flag remains valid.
+const CACHE_TIMEOUT: Duration = Duration::from_secs(60);
+
+type Cache<K, V> = OnceLock<Mutex<HashMap<K, (Instant, V)>>>;
+
+static FLAG_CACHE: Cache<String, Option<FeatureFlag>> = OnceLock::new();
+
+/// Org unit ancestors, which change rarely enough to cache alongside
+/// the flags.
+static ANCESTOR_CACHE: Cache<i64, Vec<i64>> = OnceLock::new();
+
+fn cached<K, V>(cache: &'static Cache<K, V>, key: &K) -> Option<V>
+where
+    K: std::hash::Hash + Eq,
+    V: Clone,
+{
+    cache
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap()
+        .get(key)
+        .filter(|(time, _)| time.elapsed() < CACHE_TIMEOUT)
+        .map(|(_, value)| value.clone())
+}
+
+fn cache<K, V>(cache: &'static Cache<K, V>, key: K, value: V)
+where
+    K: std::hash::Hash + Eq,
+{
+    cache
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap()
+        .insert(key, (Instant::now(), value));
+}
+
+/// Remove all cached flags from this process.
+pub fn clear_cache() {
+    if let Some(c) = FLAG_CACHE.get() {
+        c.lock().unwrap().clear();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureFlag {
+    name: String,
+    enabled: bool,
+    percent: u8,
+    orgs: Vec<i64>,
+}
+
+impl FeatureFlag {
+    /// Build a flag from a "cgf" object.
+    fn from_cgf(cgf: &EgValue) -> EgResult<FeatureFlag> {
+        let full_name = cgf["name"].str()?;
+
+        let mut flag = FeatureFlag {
+            name: full_name
+                .strip_prefix(FLAG_PREFIX)
+                .unwrap_or(full_name)
+                .to_string(),
+            enabled: cgf["enabled"].boolish(),
+            percent: 100,
+            orgs: Vec::new(),
+        };
+
+        if let Some(value) = cgf["value"].as_str() {
+            if !value.trim().is_empty() {
+                let value = EgValue::parse(value)
+                    .map_err(|e| format!("Invalid value for feature flag {full_name}: {e}"))?;
+
+                flag.apply_value(&value)?;
+            }
+        }
+
+        Ok(flag)
+    }
+
+    /// Apply percent and orgs values from a hash.
+    fn apply_value(&mut self, value: &EgValue) -> EgResult<()> {
+        if let Some(pct) = value["percent"].as_int() {
+            if !(0..=100).contains(&pct) {
+                return Err(format!("Invalid feature flag percent: {pct}").into());
+            }
+            self.percent = pct as u8;
+        }
+
+        self.orgs.clear();
+        for org_id in value["orgs"].members() {
+            self.orgs.push(org_id.int()?);
+        }
+
+        Ok(())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn percent(&self) -> u8 {
+        self.percent
+    }
+
+    pub fn orgs(&self) -> &[i64] {
+        &self.orgs
+    }
+
+    /// True if the flag applies for the org unit and bucketing key.
+    ///
+    /// Flags limited to specific org units never apply when no org
+    /// unit is provided.
+    pub fn applies(
+        &self,
+        editor: &mut Editor,
+        org_id: Option<i64>,
+        key: Option<&str>,
+    ) -> EgResult<bool> {
+        if !self.enabled || self.percent == 0 {
+            return Ok(false);
+        }
+
+        if !self.orgs.is_empty() {
+            let org_id = match org_id {
+                Some(id) => id,
+                None => return Ok(false),
+            };
+
+            let ancestors = match cached(&ANCESTOR_CACHE, &org_id) {
+                Some(a) => a,
+                None => {
+                    let a = org::ancestors(editor, org_id)?;
+                    cache(&ANCESTOR_CACHE, org_id, a.clone());
+                    a
+                }
+            };
+
+            if !ancestors.iter().any(|a| self.orgs.contains(a)) {
+                return Ok(false);
+            }
+        }
+
+        if self.percent >= 100 {
+            return Ok(true);
+        }
+
+        Ok(self.bucket(key) < self.percent)
+    }
+
+    /// Bucket from 0 to 99 for the key.
+    fn bucket(&self, key: Option<&str>) -> u8 {
+        let digest = match key {
+            Some(k) => md5::compute(format!("{}:{k}", self.name)),
+            None => md5::compute(eg::util::random_number(20)),
+        };
+
+        let num = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+
+        (num % 100) as u8
+    }
+
+    pub fn to_value(&self) -> EgValue {
+        eg::hash! {
+            "name": self.name.as_str(),
+            "enabled": self.enabled,
+            "percent": self.percent,
+            "orgs": self.orgs.as_slice(),
+        }
+    }
+}
+
+/// Load a flag by name, without the "rs.feature." prefix.
+///
+/// Returns None if no such flag exists.
+pub fn load(editor: &mut Editor, name: &str) -> EgResult<Option<FeatureFlag>> {
+    if let Some(flag) = cached(&FLAG_CACHE, &name.to_string()) {
+        return Ok(flag);
+    }
+
+    let flag = match editor.retrieve("cgf", format!("{FLAG_PREFIX}{name}"))? {
+        Some(cgf) => Some(FeatureFlag::from_cgf(&cgf)?),
+        None => None,
+    };
+
+    cache(&FLAG_CACHE, name.to_string(), flag.clone());
+
+    Ok(flag)
+}
+
+/// True if the named flag exists and applies for the org unit and
+/// bucketing key.
+///
+/// A flag which cannot be loaded, e.g. because its value is invalid,
+/// is treated as off.
+pub fn is_enabled(
+    editor: &mut Editor,
+    name: &str,
+    org_id: Option<i64>,
+    key: Option<&str>,
+) -> EgResult<bool> {
+    match load(editor, name) {
+        Ok(Some(flag)) => flag.applies(editor, org_id, key),
+        Ok(None) => Ok(false),
+        Err(e) => {
+            log::error!("Cannot load feature flag {name}: {e}");
+            Ok(false)
+        }
+    }
+}
+
+/// All feature flags, sorted by name.
+pub fn list(editor: &mut Editor) -> EgResult<Vec<FeatureFlag>> {
+    let query = eg::hash! {"name": {"like": format!("{FLAG_PREFIX}%")}};
+    let ops = eg::hash! {"order_by": {"cgf": "name"}};
+
+    let mut flags = Vec::new();
+    for cgf in editor.search_with_ops("cgf", query, ops)? {
+        flags.push(FeatureFlag::from_cgf(&cgf)?);
+    }
+
+    Ok(flags)
+}
+
+/// Create or update a flag from a hash in the format returned by
+/// [`FeatureFlag::to_value`].
+///
+/// The editor must be in a transaction.
+pub fn save(editor: &mut Editor, value: &EgValue) -> EgResult<FeatureFlag> {
+    let name = value["name"].str()?;
+
+    let mut flag = FeatureFlag {
+        name: name.to_string(),
+        enabled: value["enabled"].boolish(),
+        percent: 100,
+        orgs: Vec::new(),
+    };
+
+    flag.apply_value(value)?;
+
+    let flag_value = eg::hash! {"percent": flag.percent, "orgs": flag.orgs.as_slice()};
+    let enabled = if flag.enabled { "t" } else { "f" };
+    let full_name = format!("{FLAG_PREFIX}{name}");
+
+    match editor.retrieve("cgf", full_name.as_str())? {
+        Some(mut cgf) => {
+            cgf["enabled"] = EgValue::from(enabled);
+            cgf["value"] = EgValue::from(flag_value.dump());
+            editor.update(cgf)?;
+        }
+        None => {
+            let cgf = eg::hash! {
+                "name": full_name.as_str(),
+                "label": format!("Feature: {name}"),
+                "enabled": enabled,
+                "value": flag_value.dump(),
+            };
+            editor.create(EgValue::create("cgf", cgf)?)?;
+        }
+    }
+
+    clear_cache();
+
+    Ok(flag)
+}
@@ -1,4 +1,5 @@
 use crate as eg;
+use eg::common::holds;
 use eg::Editor;
 use eg::EgResult;
 use eg::EgValue;
@@ -26,3 +27,192 @@ pub fn copy_status(
         Err("copy_status() requires a useful parameter".into())
     }
 }
+
+/// Move a call number (and its copies) to another bib record and,
+/// optionally, another owning library.
+///
+/// If the destination record already has a matching call number
+/// (same label, prefix, suffix, class, and owning library), the copies
+/// are merged into the existing call number and the source call number
+/// is deleted.
+///
+/// Unfilled holds targeting the moved copies are retargeted.
+///
+/// The editor must be in a transaction.
+///
+/// Returns the ID of the call number now containing the copies.
+pub fn transfer_volume(
+    editor: &mut Editor,
+    volume_id: i64,
+    record_id: i64,
+    org_id: Option<i64>,
+) -> EgResult<i64> {
+    let mut volume = editor
+        .retrieve("acn", volume_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    if volume["deleted"].boolish() {
+        return Err(format!("Call number {volume_id} is deleted").into());
+    }
+
+    let record = editor
+        .retrieve("bre", record_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    if record["deleted"].boolish() {
+        return Err(format!("Bib record {record_id} is deleted").into());
+    }
+
+    let source_org = volume["owning_lib"].int()?;
+    let dest_org = org_id.unwrap_or(source_org);
+
+    for org in [source_org, dest_org] {
+        if !editor.allowed_at("UPDATE_VOLUME", org)? {
+            return Err(editor.die_event());
+        }
+    }
+
+    let query = eg::hash! {
+        "id": {"!=": volume_id},
+        "record": record_id,
+        "owning_lib": dest_org,
+        "label": volume["label"].clone(),
+        "label_class": volume["label_class"].clone(),
+        "prefix": volume["prefix"].clone(),
+        "suffix": volume["suffix"].clone(),
+        "deleted": "f",
+    };
+
+    let copy_ids = volume_copy_ids(editor, volume_id)?;
+
+    let dest_id = if let Some(dest) = editor.search("acn", query)?.pop() {
+        let dest_id = dest.id()?;
+
+        log::info!("Merging call number {volume_id} into {dest_id} on record {record_id}");
+
+        for copy_id in copy_ids.iter() {
+            move_copy(editor, *copy_id, dest_id)?;
+        }
+
+        // Call number holds follow the copies.
+        let query = eg::hash! {
+            "hold_type": "V",
+            "target": volume_id,
+            "fulfillment_time": EgValue::Null,
+            "cancel_time": EgValue::Null,
+        };
+
+        for mut hold in editor.search("ahr", query)? {
+            hold["target"] = EgValue::from(dest_id);
+            editor.update(hold)?;
+        }
+
+        volume["deleted"] = EgValue::from("t");
+        volume["editor"] = EgValue::from(editor.requestor_id()?);
+        volume["edit_date"] = EgValue::from("now");
+        editor.update(volume)?;
+
+        dest_id
+    } else {
+        log::info!("Moving call number {volume_id} to record {record_id} at org {dest_org}");
+
+        volume["record"] = EgValue::from(record_id);
+        volume["owning_lib"] = EgValue::from(dest_org);
+        volume["editor"] = EgValue::from(editor.requestor_id()?);
+        volume["edit_date"] = EgValue::from("now");
+        editor.update(volume)?;
+
+        volume_id
+    };
+
+    retarget_copy_holds(editor, &copy_ids)?;
+
+    Ok(dest_id)
+}
+
+/// Move a copy to a different call number.
+///
+/// Unfilled holds targeting the copy are retargeted.
+///
+/// The editor must be in a transaction.
+pub fn transfer_copy(editor: &mut Editor, copy_id: i64, volume_id: i64) -> EgResult<()> {
+    let copy = editor
+        .retrieve("acp", copy_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    if copy["deleted"].boolish() {
+        return Err(format!("Copy {copy_id} is deleted").into());
+    }
+
+    let volume = editor
+        .retrieve("acn", volume_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    if volume["deleted"].boolish() {
+        return Err(format!("Call number {volume_id} is deleted").into());
+    }
+
+    for org in [copy["circ_lib"].int()?, volume["owning_lib"].int()?] {
+        if !editor.allowed_at("UPDATE_COPY", org)? {
+            return Err(editor.die_event());
+        }
+    }
+
+    log::info!("Moving copy {copy_id} to call number {volume_id}");
+
+    move_copy(editor, copy_id, volume_id)?;
+
+    retarget_copy_holds(editor, &[copy_id])?;
+
+    Ok(())
+}
+
+/// IDs of the non-deleted copies attached to a call number.
+fn volume_copy_ids(editor: &mut Editor, volume_id: i64) -> EgResult<Vec<i64>> {
+    let query = eg::hash! {"call_number": volume_id, "deleted": "f"};
+
+    let mut ids = Vec::new();
+    for copy in editor.search("acp", query)? {
+        ids.push(copy.id()?);
+    }
+
+    Ok(ids)
+}
+
+fn move_copy(editor: &mut Editor, copy_id: i64, volume_id: i64) -> EgResult<()> {
+    let mut copy = editor
+        .retrieve("acp", copy_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    copy["call_number"] = EgValue::from(volume_id);
+    copy["editor"] = EgValue::from(editor.requestor_id()?);
+    copy["edit_date"] = EgValue::from("now");
+
+    editor.update(copy)
+}
+
+/// Retarget uncaptured, unfilled holds whose current copy is one of
+/// the provided copies, since the copies may no longer be suitable
+/// targets after a transfer.
+///
+/// The editor must be in a transaction.
+fn retarget_copy_holds(editor: &mut Editor, copy_ids: &[i64]) -> EgResult<()> {
+    if copy_ids.is_empty() {
+        return Ok(());
+    }
+
+    let query = eg::hash! {
+        "current_copy": copy_ids,
+        "capture_time": EgValue::Null,
+        "fulfillment_time": EgValue::Null,
+        "cancel_time": EgValue::Null,
+    };
+
+    for hold in editor.search("ahr", query)? {
+        let hold_id = hold.id()?;
+        log::info!("Retargeting hold {hold_id} after holdings transfer");
+        holds::retarget_hold(editor, hold_id)?;
+    }
+
+    Ok(())
+}
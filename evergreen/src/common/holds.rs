@@ -12,6 +12,10 @@ use eg::EgResult;
 use eg::EgValue;
 use std::fmt;
 
+/// Maximum number of copies run through the hold permit test when
+/// placing a hold.
+const MAX_PERMIT_TEST_COPIES: i64 = 100;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum HoldType {
     Copy,
@@ -778,3 +782,500 @@ pub fn record_has_holdable_copy(editor: &mut Editor, rec_id: i64, is_meta: bool)
 
     Ok(data[&func].boolish())
 }
+
+/// Details for a new hold request.
+pub struct HoldRequest {
+    pub patron_id: i64,
+    pub hold_type: HoldType,
+    pub target: i64,
+    pub pickup_lib: i64,
+    /// Org unit where the request is placed.  Defaults to the
+    /// requestor's workstation org unit.
+    pub request_lib: Option<i64>,
+    /// Org unit whose range of holdings the hold may target.
+    /// Defaults to the pickup library.
+    pub selection_ou: Option<i64>,
+    /// Org unit depth at the selection org unit.  Defaults to the
+    /// soft hold boundary and is never broader than the hard boundary.
+    pub selection_depth: Option<i64>,
+    pub frozen: bool,
+    pub thaw_date: Option<String>,
+    pub expire_time: Option<String>,
+    pub email_notify: bool,
+    pub phone_notify: Option<String>,
+    pub sms_notify: Option<String>,
+    pub sms_carrier: Option<i64>,
+    /// Format filter for metarecord holds.
+    pub holdable_formats: Option<String>,
+}
+
+impl HoldRequest {
+    pub fn new(patron_id: i64, hold_type: HoldType, target: i64, pickup_lib: i64) -> Self {
+        HoldRequest {
+            patron_id,
+            hold_type,
+            target,
+            pickup_lib,
+            request_lib: None,
+            selection_ou: None,
+            selection_depth: None,
+            frozen: false,
+            thaw_date: None,
+            expire_time: None,
+            email_notify: false,
+            phone_notify: None,
+            sms_notify: None,
+            sms_carrier: None,
+            holdable_formats: None,
+        }
+    }
+
+    /// Build a request from an API hash, using the same field names
+    /// as the "ahr" class.
+    pub fn from_eg_value(value: &EgValue) -> EgResult<Self> {
+        let hold_type = HoldType::try_from(value["hold_type"].str()?)?;
+
+        let mut request = HoldRequest::new(
+            value["usr"].int()?,
+            hold_type,
+            value["target"].int()?,
+            value["pickup_lib"].int()?,
+        );
+
+        let string_op = |key: &str| value[key].as_str().map(|s| s.to_string());
+
+        request.request_lib = value["request_lib"].as_int();
+        request.selection_ou = value["selection_ou"].as_int();
+        request.selection_depth = value["selection_depth"].as_int();
+        request.frozen = value["frozen"].boolish();
+        request.thaw_date = string_op("thaw_date");
+        request.expire_time = string_op("expire_time");
+        request.email_notify = value["email_notify"].boolish();
+        request.phone_notify = string_op("phone_notify");
+        request.sms_notify = string_op("sms_notify");
+        request.sms_carrier = value["sms_carrier"].as_int();
+        request.holdable_formats = string_op("holdable_formats");
+
+        Ok(request)
+    }
+}
+
+/// Outcome of a hold placement attempt.
+pub enum HoldPlacement {
+    /// The newly created hold.
+    Placed(EgValue),
+    /// Events which prevented the hold from being placed.
+    Blocked(Vec<EgEvent>),
+}
+
+/// Permission required to place a hold of the provided type.
+pub fn hold_type_perm(hold_type: HoldType) -> &'static str {
+    match hold_type {
+        HoldType::Metarecord => "MR_HOLDS",
+        HoldType::Title => "TITLE_HOLDS",
+        HoldType::Volume => "VOLUME_HOLDS",
+        HoldType::Part => "TITLE_HOLDS",
+        HoldType::Issuance => "ISSUANCE_HOLDS",
+        HoldType::Copy => "COPY_HOLDS",
+        HoldType::Force => "COPY_HOLDS_FORCE",
+        HoldType::Recall => "COPY_HOLDS_RECALL",
+    }
+}
+
+/// Returns an event if the org unit cannot act as a hold pickup
+/// location, None otherwise.
+///
+/// Pickup libraries must exist, be of an org unit type which can have
+/// volumes, and not be flagged via opac.holds.org_unit_not_pickup_lib.
+pub fn check_pickup_lib(
+    editor: &mut Editor,
+    settings: &mut Settings,
+    org_id: i64,
+) -> EgResult<Option<EgEvent>> {
+    let flesh = eg::hash! {"flesh": 1, "flesh_fields": {"aou": ["ou_type"]}};
+
+    let org = match editor.retrieve_with_ops("aou", org_id, flesh)? {
+        Some(o) => o,
+        None => return Ok(Some(last_event(editor, "ACTOR_ORG_UNIT_NOT_FOUND"))),
+    };
+
+    let mut evt = EgEvent::new("BAD_PARAMS");
+    evt.set_payload(eg::hash! {"pickup_lib": org_id});
+
+    if !org["ou_type"]["can_have_vols"].boolish() {
+        evt.set_desc(&format!(
+            "Org unit {} cannot be a hold pickup library",
+            org["shortname"]
+        ));
+        return Ok(Some(evt));
+    }
+
+    if settings
+        .get_value_at_org("opac.holds.org_unit_not_pickup_lib", org_id)?
+        .boolish()
+    {
+        evt.set_desc(&format!(
+            "Org unit {} is not a valid hold pickup library",
+            org["shortname"]
+        ));
+        return Ok(Some(evt));
+    }
+
+    Ok(None)
+}
+
+/// The editor's last event or a new event with the fallback textcode.
+fn last_event(editor: &Editor, fallback: &str) -> EgEvent {
+    editor
+        .last_event()
+        .cloned()
+        .unwrap_or_else(|| EgEvent::new(fallback))
+}
+
+/// Returns the (selection_ou, selection_depth) for a new hold.
+///
+/// The selection org unit defaults to the pickup library.  The depth
+/// defaults to circ.hold_boundary.soft, then 0, and is raised to
+/// circ.hold_boundary.hard when that boundary is narrower.
+pub fn hold_selection_range(
+    settings: &mut Settings,
+    pickup_lib: i64,
+    selection_ou: Option<i64>,
+    selection_depth: Option<i64>,
+) -> EgResult<(i64, i64)> {
+    let selection_ou = selection_ou.unwrap_or(pickup_lib);
+
+    let mut depth = match selection_depth {
+        Some(d) => d,
+        None => settings
+            .get_value_at_org("circ.hold_boundary.soft", pickup_lib)?
+            .as_int()
+            .unwrap_or(0),
+    };
+
+    let hard_boundary = settings
+        .get_value_at_org("circ.hold_boundary.hard", pickup_lib)?
+        .as_int();
+
+    if let Some(hard) = hard_boundary {
+        if depth < hard {
+            log::info!("Raising hold selection depth from {depth} to hard boundary {hard}");
+            depth = hard;
+        }
+    }
+
+    Ok((selection_ou, depth))
+}
+
+/// Standing penalties which block the patron from placing holds at
+/// the pickup library, as events named for each penalty.
+pub fn hold_penalty_events(
+    editor: &mut Editor,
+    patron_id: i64,
+    pickup_lib: i64,
+) -> EgResult<Vec<EgEvent>> {
+    let query = eg::hash! {
+        "select": {"csp": ["name", "label"]},
+        "from": {"ausp": "csp"},
+        "where": {
+            "+ausp": {
+                "usr": patron_id,
+                "org_unit": org::full_path(editor, pickup_lib, None)?,
+                "-or": [
+                    {"stop_date": eg::NULL},
+                    {"stop_date": {">": "now"}}
+                ]
+            },
+            "+csp": {"block_list": {"like": "%HOLD%"}}
+        }
+    };
+
+    let mut events = Vec::new();
+
+    for pen in editor.json_query(query)? {
+        let mut evt = EgEvent::new(pen["name"].str()?);
+        if let Some(d) = pen["label"].as_str() {
+            evt.set_desc(d);
+        }
+        events.push(evt);
+    }
+
+    Ok(events)
+}
+
+/// IDs of copies within the selection range which a new hold could
+/// target, at most MAX_PERMIT_TEST_COPIES.
+fn hold_candidate_copies(
+    editor: &mut Editor,
+    request: &HoldRequest,
+    selection_ou: i64,
+    selection_depth: i64,
+) -> EgResult<Vec<i64>> {
+    let target = request.target;
+
+    let target_filter = match request.hold_type {
+        HoldType::Copy | HoldType::Force | HoldType::Recall => {
+            return Ok(vec![target]);
+        }
+        HoldType::Volume => eg::hash! {"call_number": target},
+        HoldType::Title => eg::hash! {
+            "call_number": {
+                "in": {
+                    "select": {"acn": ["id"]},
+                    "from": "acn",
+                    "where": {"record": target, "deleted": "f"}
+                }
+            }
+        },
+        HoldType::Metarecord => eg::hash! {
+            "call_number": {
+                "in": {
+                    "select": {"acn": ["id"]},
+                    "from": "acn",
+                    "where": {
+                        "deleted": "f",
+                        "record": {
+                            "in": {
+                                "select": {"mmrsm": ["source"]},
+                                "from": "mmrsm",
+                                "where": {"metarecord": target}
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        HoldType::Part => eg::hash! {
+            "id": {
+                "in": {
+                    "select": {"acpm": ["target_copy"]},
+                    "from": "acpm",
+                    "where": {"part": target}
+                }
+            }
+        },
+        HoldType::Issuance => eg::hash! {
+            "id": {
+                "in": {
+                    "select": {"sitem": ["unit"]},
+                    "from": "sitem",
+                    "where": {"issuance": target}
+                }
+            }
+        },
+    };
+
+    let range_query = eg::hash! {
+        "from": ["actor.org_unit_descendants", selection_ou, selection_depth]
+    };
+
+    let mut range = Vec::new();
+    for org in editor.json_query(range_query)? {
+        range.push(org.id()?);
+    }
+
+    let query = eg::hash! {
+        "select": {"acp": ["id"]},
+        "from": "acp",
+        "where": {
+            "deleted": "f",
+            "circ_lib": range,
+            "-and": [target_filter],
+        },
+        "limit": MAX_PERMIT_TEST_COPIES,
+    };
+
+    let mut copy_ids = Vec::new();
+    for copy in editor.json_query(query)? {
+        copy_ids.push(copy.id()?);
+    }
+
+    Ok(copy_ids)
+}
+
+/// Run the hold policy permit test against the copies the new hold
+/// could target.
+///
+/// Returns no events when any copy passes.  Otherwise returns the
+/// (de-duplicated) events of the failed tests, or
+/// HIGH_LEVEL_HOLD_HAS_NO_COPIES if there are no copies to test.
+pub fn hold_permit_events(
+    editor: &mut Editor,
+    request: &HoldRequest,
+    request_lib: i64,
+    selection_ou: i64,
+    selection_depth: i64,
+) -> EgResult<Vec<EgEvent>> {
+    let copy_ids = hold_candidate_copies(editor, request, selection_ou, selection_depth)?;
+
+    if copy_ids.is_empty() {
+        let mut evt = EgEvent::new("HIGH_LEVEL_HOLD_HAS_NO_COPIES");
+        evt.set_payload(eg::hash! {"target": request.target});
+        return Ok(vec![evt]);
+    }
+
+    let mut events: Vec<EgEvent> = Vec::new();
+
+    for copy_id in copy_ids {
+        let params = CopyHoldParams {
+            patron_id: request.patron_id,
+            copy_id,
+            pickup_lib: request.pickup_lib,
+            request_lib,
+            requestor: editor.requestor_id()?,
+            is_retarget: false,
+        };
+
+        // Overrides are applied to the collected events by the caller.
+        let result = test_copy_for_hold(editor, params, None, false)?;
+
+        let failures: Vec<EgEvent> = result
+            .permit_results
+            .into_iter()
+            .filter_map(|r| r.mapped_event)
+            .collect();
+
+        if failures.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        for evt in failures {
+            if !events.iter().any(|e| e.textcode() == evt.textcode()) {
+                events.push(evt);
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+/// Place a new hold.
+///
+/// Checks permissions, validates the pickup library, runs the hold
+/// policy permit test, checks for standing penalties which block
+/// holds, detects duplicate holds, applies hold boundaries, and
+/// creates the hold.
+/// A transaction must be active on the editor.  Blocking events which
+/// are overridden require the "{textcode}.override" permission.
+///
+/// The caller is responsible for committing the transaction and
+/// targeting the new hold (see `retarget_holds()`).
+pub fn place_hold(
+    editor: &mut Editor,
+    request: &HoldRequest,
+    overrides: Option<&Overrides>,
+) -> EgResult<HoldPlacement> {
+    let requestor = editor.requestor_id()?;
+    let hold_type: &str = request.hold_type.into();
+
+    let patron = match editor.retrieve("au", request.patron_id)? {
+        Some(p) => p,
+        None => return Err(editor.die_event()),
+    };
+
+    if requestor != request.patron_id
+        && !editor.allowed_at("REQUEST_HOLDS", patron["home_ou"].int()?)?
+    {
+        return Ok(HoldPlacement::Blocked(vec![last_event(
+            editor,
+            "PERM_FAILURE",
+        )]));
+    }
+
+    if !editor.allowed_at(hold_type_perm(request.hold_type), request.pickup_lib)? {
+        return Ok(HoldPlacement::Blocked(vec![last_event(
+            editor,
+            "PERM_FAILURE",
+        )]));
+    }
+
+    let mut settings = Settings::new(editor);
+
+    if let Some(evt) = check_pickup_lib(editor, &mut settings, request.pickup_lib)? {
+        return Ok(HoldPlacement::Blocked(vec![evt]));
+    }
+
+    let (selection_ou, selection_depth) = hold_selection_range(
+        &mut settings,
+        request.pickup_lib,
+        request.selection_ou,
+        request.selection_depth,
+    )?;
+
+    let request_lib = request
+        .request_lib
+        .or(editor.requestor_ws_ou())
+        .unwrap_or(request.pickup_lib);
+
+    let mut events = hold_penalty_events(editor, request.patron_id, request.pickup_lib)?;
+
+    events.extend(hold_permit_events(
+        editor,
+        request,
+        request_lib,
+        selection_ou,
+        selection_depth,
+    )?);
+
+    let query = eg::hash! {
+        "usr": request.patron_id,
+        "hold_type": hold_type,
+        "target": request.target,
+        "cancel_time": EgValue::Null,
+        "fulfillment_time": EgValue::Null,
+    };
+
+    if let Some(existing) = editor.search("ahr", query)?.first() {
+        let mut evt = EgEvent::new("HOLD_EXISTS");
+        evt.set_payload(eg::hash! {"hold": existing.id()?});
+        events.push(evt);
+    }
+
+    let mut blocked = Vec::new();
+
+    for evt in events {
+        let try_override = match overrides {
+            Some(Overrides::All) => true,
+            Some(Overrides::Events(list)) => list.iter().any(|e| e == evt.textcode()),
+            None => false,
+        };
+
+        if !try_override {
+            blocked.push(evt);
+            continue;
+        }
+
+        let permission = format!("{}.override", evt.textcode());
+
+        if !editor.allowed_at(&permission, request.pickup_lib)? {
+            blocked.push(last_event(editor, "PERM_FAILURE"));
+        }
+    }
+
+    if !blocked.is_empty() {
+        return Ok(HoldPlacement::Blocked(blocked));
+    }
+
+    let hold = eg::hash! {
+        "usr": request.patron_id,
+        "requestor": requestor,
+        "hold_type": hold_type,
+        "target": request.target,
+        "pickup_lib": request.pickup_lib,
+        "request_lib": request_lib,
+        "selection_ou": selection_ou,
+        "selection_depth": selection_depth,
+        "frozen": request.frozen,
+        "thaw_date": request.thaw_date.as_deref(),
+        "expire_time": request.expire_time.as_deref(),
+        "email_notify": request.email_notify,
+        "phone_notify": request.phone_notify.as_deref(),
+        "sms_notify": request.sms_notify.as_deref(),
+        "sms_carrier": request.sms_carrier,
+        "holdable_formats": request.holdable_formats.as_deref(),
+    };
+
+    let hold = editor.create(EgValue::create("ahr", hold)?)?;
+
+    Ok(HoldPlacement::Placed(hold))
+}
@@ -0,0 +1,149 @@
+//! Idempotency keys for API calls which should not be repeated.
+//!
+//! Clients that may retry a request after a timeout (e.g. self-check
+//! units) can pass an "idempotency_key" value along with their request.
+//! The first request reserves the key with an in-progress marker
+//! before doing any work, then replaces the marker with its response,
+//! including error event responses.  Any subsequent request carrying
+//! the same key, within the TTL, receives the cached response instead
+//! of repeating the operation, or is turned away while the first
+//! request is still running.
+//!
+//! Keys are scoped by API name and requestor so different staff
+//! accounts / APIs cannot collide.
+use crate as eg;
+use eg::osrf::cache::Cache;
+use eg::util;
+use eg::EgError;
+use eg::EgResult;
+use eg::EgValue;
+
+const CACHE_PREFIX: &str = "rs.idempotency";
+
+/// Name of the option / parameter key that carries the caller's key.
+pub const IDEMPOTENCY_KEY_PARAM: &str = "idempotency_key";
+
+/// Default time to retain a response in seconds.
+pub const DEFAULT_IDEMPOTENCY_TTL: u32 = 3600;
+
+/// How long an in-progress marker lives in seconds.  Reservations
+/// left behind by a crashed worker expire after this long.
+const IN_PROGRESS_TTL: u32 = 300;
+
+/// Keys longer than this are rejected to avoid storing junk in the cache.
+const MAX_KEY_LENGTH: usize = 128;
+
+/// Outcome of reserving an idempotency key.
+pub enum Reservation {
+    /// The caller owns the key and should perform the operation.
+    Reserved,
+    /// Another request with the same key is still running.
+    InProgress,
+    /// A previous request with the same key completed with this response.
+    Completed(EgValue),
+}
+
+pub struct IdempotencyKey {
+    cache_key: String,
+    ttl: u32,
+}
+
+impl IdempotencyKey {
+    /// Create a key for the provided API, requestor, and caller key.
+    ///
+    /// Returns None if the caller key is empty or too long.
+    ///
+    /// ```
+    /// use evergreen::common::idempotency::IdempotencyKey;
+    ///
+    /// let key = IdempotencyKey::new("open-ils.circ.checkout", 1, "abc").unwrap();
+    /// assert_eq!(key.cache_key(), "rs.idempotency.open-ils.circ.checkout.1.abc");
+    ///
+    /// assert!(IdempotencyKey::new("open-ils.circ.checkout", 1, "").is_none());
+    /// ```
+    pub fn new(api: &str, requestor_id: i64, key: &str) -> Option<Self> {
+        let key = key.trim();
+
+        if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+            return None;
+        }
+
+        Some(IdempotencyKey {
+            cache_key: format!("{CACHE_PREFIX}.{api}.{requestor_id}.{key}"),
+            ttl: DEFAULT_IDEMPOTENCY_TTL,
+        })
+    }
+
+    /// Extract the idempotency key from a set of API call options.
+    ///
+    /// ```
+    /// use evergreen::common::idempotency::IdempotencyKey;
+    ///
+    /// let ops = evergreen::hash! {"copy_barcode": "123", "idempotency_key": "xyz"};
+    /// let key = IdempotencyKey::from_options("checkout", 5, &ops).unwrap();
+    /// assert_eq!(key.cache_key(), "rs.idempotency.checkout.5.xyz");
+    ///
+    /// let ops = evergreen::hash! {"copy_barcode": "123"};
+    /// assert!(IdempotencyKey::from_options("checkout", 5, &ops).is_none());
+    /// ```
+    pub fn from_options(api: &str, requestor_id: i64, options: &EgValue) -> Option<Self> {
+        options[IDEMPOTENCY_KEY_PARAM]
+            .to_string()
+            .and_then(|k| IdempotencyKey::new(api, requestor_id, &k))
+    }
+
+    pub fn cache_key(&self) -> &str {
+        &self.cache_key
+    }
+
+    /// How long to retain cached responses in seconds.
+    pub fn set_ttl(&mut self, ttl: u32) {
+        self.ttl = ttl;
+    }
+
+    /// Reserve the key before performing the operation.
+    ///
+    /// Once reserved, the caller must call [`Self::complete`] with the
+    /// response, or [`Self::release`] if the operation failed without
+    /// producing a response worth repeating.
+    pub fn reserve(&self) -> EgResult<Reservation> {
+        let marker = eg::hash! {"in_progress": util::random_number(16)};
+
+        if Cache::add_global_for(&self.cache_key, marker, IN_PROGRESS_TTL)? {
+            return Ok(Reservation::Reserved);
+        }
+
+        match Cache::get_global(&self.cache_key)? {
+            Some(value) if value["in_progress"].is_null() => {
+                log::info!("Returning cached response for key {}", self.cache_key);
+                Ok(Reservation::Completed(value["response"].clone()))
+            }
+            // Either still running or the marker expired between the
+            // add and the lookup.  The caller may try again.
+            _ => Ok(Reservation::InProgress),
+        }
+    }
+
+    /// Replace the reservation with the response so retries can reuse it.
+    pub fn complete(&self, response: &EgValue) -> EgResult<()> {
+        let value = eg::hash! {"response": response.clone()};
+        Cache::set_global_for(&self.cache_key, value, self.ttl)
+    }
+
+    /// Drop the reservation so the request may be retried.
+    pub fn release(&self) {
+        if let Err(e) = Cache::del_global(&self.cache_key) {
+            log::error!("Cannot release idempotency key {}: {e}", self.cache_key);
+        }
+    }
+
+    /// Error to return to callers when a request with the same key
+    /// is still running.
+    pub fn in_progress_error(&self) -> EgError {
+        format!(
+            "A request with idempotency key {} is already in progress",
+            self.cache_key
+        )
+        .into()
+    }
+}
@@ -1,21 +1,36 @@
 //! Shared, common utility functions
 
+pub mod audit;
 pub mod auth;
+pub mod autorenew;
 pub mod bib;
 pub mod billing;
+pub mod bucket;
 pub mod checkin;
 pub mod checkout;
 pub mod circ;
 pub mod circulator;
+pub mod citation;
+pub mod copy_alert;
+pub mod feature_flag;
 pub mod holdings;
 pub mod holds;
+pub mod idempotency;
 pub mod jq;
 pub mod noncat;
 pub mod org;
+pub mod org_tree;
+pub mod override_token;
+pub mod payment;
 pub mod penalty;
 pub mod renew;
 pub mod settings;
+pub mod statcat;
+pub mod survey;
 pub mod targeter;
+pub mod task;
+pub mod till;
 pub mod transit;
 pub mod trigger;
 pub mod user;
+pub mod user_merge;
@@ -5,6 +5,7 @@ use eg::date;
 use eg::editor::Editor;
 use eg::result::EgResult;
 use eg::EgValue;
+use regex::Regex;
 use std::time::Duration;
 
 /// Create X number of non-cat checkouts.
@@ -78,3 +79,106 @@ pub fn noncat_due_date(editor: &mut Editor, noncat: &EgValue) -> EgResult<String
 
     Ok(date::to_iso(&duedate))
 }
+
+/// Longest circulation duration of any noncat type in seconds.
+fn max_circ_duration(editor: &mut Editor) -> EgResult<i64> {
+    let mut max = 0;
+
+    for ntype in editor.search("cnct", eg::hash! {id: {"!=": EgValue::Null}})? {
+        max = max.max(date::interval_to_seconds(ntype["circ_duration"].str()?)?);
+    }
+
+    Ok(max)
+}
+
+/// Noncat circulations for a patron which are not yet due, fleshed
+/// with their item type, oldest first.
+///
+/// Only circulations recent enough to still be open under the
+/// longest noncat circ duration are fetched, so the query does not
+/// grow with the patron's noncat history.
+fn undue_circs(editor: &mut Editor, patron_id: i64) -> EgResult<Vec<EgValue>> {
+    let max_seconds = max_circ_duration(editor)?;
+    let now = date::now();
+    let oldest = now - Duration::from_secs(max_seconds.max(0) as u64);
+
+    let query = eg::hash! {
+        patron: patron_id,
+        circ_time: {">=": date::to_iso(&oldest)},
+    };
+
+    let flesh = eg::hash! {
+        flesh: 1,
+        flesh_fields: {ancc: ["item_type"]},
+        order_by: {ancc: "circ_time"},
+    };
+
+    let mut open = Vec::new();
+
+    for circ in editor.search_with_ops("ancc", query, flesh)? {
+        let seconds = date::interval_to_seconds(circ["item_type"]["circ_duration"].str()?)?;
+        let circ_time = date::parse_datetime(circ["circ_time"].str()?)?;
+
+        // Cheap check before calculating the real due date, which
+        // requires org unit closed date lookups.
+        if circ_time + Duration::from_secs(seconds as u64) < now {
+            continue;
+        }
+
+        open.push(circ);
+    }
+
+    Ok(open)
+}
+
+/// Returns the open (not yet due) noncat circulations for a patron,
+/// fleshed with their item type and with the duedate calculated.
+pub fn open_circs(editor: &mut Editor, patron_id: i64) -> EgResult<Vec<EgValue>> {
+    let mut open = undue_circs(editor, patron_id)?;
+
+    for circ in open.iter_mut() {
+        circ["duedate"] = EgValue::from(noncat_due_date(editor, circ)?);
+    }
+
+    Ok(open)
+}
+
+/// Returns the number of open noncat circulations for a patron.
+///
+/// Skips the due date calculation needed by [`open_circs`].
+pub fn open_circ_count(editor: &mut Editor, patron_id: i64) -> EgResult<usize> {
+    Ok(undue_circs(editor, patron_id)?.len())
+}
+
+/// Returns the noncat type ID for an item barcode from a map of
+/// barcode regex patterns to noncat type IDs, e.g.
+/// {"^NCMAG": 2, "^NCPB": 3}.
+///
+/// Invalid patterns are logged and skipped.
+///
+/// ```
+/// use evergreen as eg;
+/// use eg::common::noncat;
+///
+/// let patterns = eg::hash! {"^NCMAG": 2, "^NCPB": 3};
+///
+/// assert_eq!(noncat::type_for_barcode(&patterns, "NCPB0001"), Some(3));
+/// assert_eq!(noncat::type_for_barcode(&patterns, "3000012345"), None);
+/// ```
+pub fn type_for_barcode(patterns: &EgValue, barcode: &str) -> Option<i64> {
+    for (pattern, noncat_type) in patterns.entries() {
+        let regex = match Regex::new(pattern) {
+            Ok(r) => r,
+            Err(e) => {
+                log::error!("Invalid noncat barcode pattern '{pattern}': {e}");
+                continue;
+            }
+        };
+
+        if regex.is_match(barcode) {
+            return noncat_type.as_int();
+        }
+    }
+
+    None
+}
@@ -0,0 +1,341 @@
+//! Org unit display trees.
+//!
+//! The full org tree follows the parent_ou links of every org unit.
+//! Custom trees ("aouct" / "aouctn") provide alternate display trees
+//! for a purpose, e.g. "opac", with their own ordering and with
+//! branches omitted as needed.
+//!
+//! Display trees are cached per process.  Changes made via
+//! [`save_custom_tree`] and [`delete_custom_tree`] clear the local
+//! cache.  Other processes pick up changes once their cache expires.
+use crate as eg;
+use eg::Editor;
+use eg::EgResult;
+use eg::EgValue;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long a cached display tree remains valid.
+const CACHE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Cache key for the full org tree.
+const FULL_TREE_KEY: &str = "";
+
+/// Purpose for which org units with opac_visible=false are hidden
+/// when no custom tree is active.
+pub const PURPOSE_OPAC: &str = "opac";
+
+static TREE_CACHE: OnceLock<Mutex<HashMap<String, (Instant, EgValue)>>> = OnceLock::new();
+
+fn cache() -> &'static Mutex<HashMap<String, (Instant, EgValue)>> {
+    TREE_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cached_tree(key: &str) -> Option<EgValue> {
+    let cache = cache().lock().unwrap();
+
+    cache
+        .get(key)
+        .filter(|(time, _)| time.elapsed() < CACHE_TIMEOUT)
+        .map(|(_, tree)| tree.clone())
+}
+
+fn cache_tree(key: &str, tree: &EgValue) {
+    cache()
+        .lock()
+        .unwrap()
+        .insert(key.to_string(), (Instant::now(), tree.clone()));
+}
+
+/// Remove all cached display trees from this process.
+pub fn clear_cache() {
+    cache().lock().unwrap().clear();
+}
+
+/// The full org tree as nested "aou" objects with "children" lists.
+///
+/// Siblings are sorted by name.
+pub fn full_tree(editor: &mut Editor) -> EgResult<EgValue> {
+    if let Some(tree) = cached_tree(FULL_TREE_KEY) {
+        return Ok(tree);
+    }
+
+    let tree = build_full_tree(editor, false)?;
+
+    cache_tree(FULL_TREE_KEY, &tree);
+
+    Ok(tree)
+}
+
+/// The effective display tree for a purpose.
+///
+/// Returns the active custom tree for the purpose, if one exists.
+/// Otherwise returns the full org tree, without any org units which
+/// are not OPAC visible when the purpose is "opac".
+pub fn display_tree(editor: &mut Editor, purpose: &str) -> EgResult<EgValue> {
+    if let Some(tree) = cached_tree(purpose) {
+        return Ok(tree);
+    }
+
+    let tree = match active_custom_tree(editor, purpose)? {
+        Some(ctree) => build_custom_tree(editor, ctree.id()?)?,
+        None if purpose == PURPOSE_OPAC => build_full_tree(editor, true)?,
+        None => full_tree(editor)?,
+    };
+
+    cache_tree(purpose, &tree);
+
+    Ok(tree)
+}
+
+fn active_custom_tree(editor: &mut Editor, purpose: &str) -> EgResult<Option<EgValue>> {
+    let query = eg::hash! {"purpose": purpose, "active": "t"};
+    Ok(editor.search("aouct", query)?.pop())
+}
+
+fn build_full_tree(editor: &mut Editor, opac_visible_only: bool) -> EgResult<EgValue> {
+    let mut query = eg::hash! {"id": {"!=": EgValue::Null}};
+
+    if opac_visible_only {
+        query["opac_visible"] = EgValue::from("t");
+    }
+
+    let ops = eg::hash! {"order_by": {"aou": "name"}};
+
+    let mut orgs = editor.search_with_ops("aou", query, ops)?;
+
+    let root_idx = orgs
+        .iter()
+        .position(|o| o["parent_ou"].is_null())
+        .ok_or("Org tree has no root org unit")?;
+
+    let mut root = orgs.remove(root_idx);
+
+    attach_org_children(&mut root, &mut orgs)?;
+
+    Ok(root)
+}
+
+/// Move the children of the org unit from the list into its
+/// "children" field, recursively.
+///
+/// Org units whose parent is not in the list, e.g. because the parent
+/// is hidden, are left out of the tree.
+fn attach_org_children(org: &mut EgValue, orgs: &mut Vec<EgValue>) -> EgResult<()> {
+    let org_id = org.id()?;
+    let mut children = Vec::new();
+
+    let mut idx = 0;
+    while idx < orgs.len() {
+        if orgs[idx]["parent_ou"].as_int() == Some(org_id) {
+            children.push(orgs.remove(idx));
+        } else {
+            idx += 1;
+        }
+    }
+
+    for child in children.iter_mut() {
+        attach_org_children(child, orgs)?;
+    }
+
+    org["children"] = EgValue::from(children);
+
+    Ok(())
+}
+
+/// Build the org unit tree described by the nodes of a custom tree.
+fn build_custom_tree(editor: &mut Editor, tree_id: i64) -> EgResult<EgValue> {
+    let ops = eg::hash! {
+        "flesh": 1,
+        "flesh_fields": {"aouctn": ["org_unit"]},
+        "order_by": {"aouctn": "sibling_order"},
+    };
+
+    let mut nodes = editor.search_with_ops("aouctn", eg::hash! {"tree": tree_id}, ops)?;
+
+    let root_idx = nodes
+        .iter()
+        .position(|n| n["parent_node"].is_null())
+        .ok_or_else(|| format!("Custom org tree {tree_id} has no root node"))?;
+
+    let root = nodes.remove(root_idx);
+
+    custom_node_to_org(root, &mut nodes)
+}
+
+fn custom_node_to_org(mut node: EgValue, nodes: &mut Vec<EgValue>) -> EgResult<EgValue> {
+    let node_id = node.id()?;
+    let mut children = Vec::new();
+
+    let mut idx = 0;
+    while idx < nodes.len() {
+        if nodes[idx]["parent_node"].as_int() == Some(node_id) {
+            let child = nodes.remove(idx);
+            children.push(custom_node_to_org(child, nodes)?);
+        } else {
+            idx += 1;
+        }
+    }
+
+    let mut org = node["org_unit"].take();
+    org["children"] = EgValue::from(children);
+
+    Ok(org)
+}
+
+/// A custom tree as a hash of {id, purpose, active, root}, where root
+/// is a nested hash of {org_unit, children} nodes.
+///
+/// Returns None if no custom tree exists for the purpose.
+pub fn custom_tree(editor: &mut Editor, purpose: &str) -> EgResult<Option<EgValue>> {
+    let query = eg::hash! {"purpose": purpose};
+
+    let tree = match editor.search("aouct", query)?.pop() {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+
+    let tree_id = tree.id()?;
+
+    let ops = eg::hash! {"order_by": {"aouctn": "sibling_order"}};
+    let mut nodes = editor.search_with_ops("aouctn", eg::hash! {"tree": tree_id}, ops)?;
+
+    let root = match nodes.iter().position(|n| n["parent_node"].is_null()) {
+        Some(idx) => {
+            let root = nodes.remove(idx);
+            node_to_hash(&root, &mut nodes)?
+        }
+        None => EgValue::Null,
+    };
+
+    Ok(Some(eg::hash! {
+        "id": tree_id,
+        "purpose": purpose,
+        "active": tree["active"].boolish(),
+        "root": root,
+    }))
+}
+
+fn node_to_hash(node: &EgValue, nodes: &mut Vec<EgValue>) -> EgResult<EgValue> {
+    let node_id = node.id()?;
+    let mut children = Vec::new();
+
+    let mut idx = 0;
+    while idx < nodes.len() {
+        if nodes[idx]["parent_node"].as_int() == Some(node_id) {
+            let child = nodes.remove(idx);
+            children.push(node_to_hash(&child, nodes)?);
+        } else {
+            idx += 1;
+        }
+    }
+
+    Ok(eg::hash! {
+        "org_unit": node["org_unit"].int()?,
+        "children": children,
+    })
+}
+
+/// Create or replace the custom tree for a purpose.
+///
+/// The tree is a hash in the format returned by [`custom_tree`].  Its
+/// nodes replace any existing nodes.  Siblings are ordered as listed.
+///
+/// The editor must be in a transaction.
+///
+/// Returns the custom tree ID.
+pub fn save_custom_tree(editor: &mut Editor, tree: &EgValue) -> EgResult<i64> {
+    let purpose = tree["purpose"].str()?;
+    let active = if tree["active"].boolish() { "t" } else { "f" };
+
+    let query = eg::hash! {"purpose": purpose};
+
+    let tree_id = match editor.search("aouct", query)?.pop() {
+        Some(mut existing) => {
+            let tree_id = existing.id()?;
+
+            existing["active"] = EgValue::from(active);
+            editor.update(existing)?;
+
+            delete_nodes(editor, tree_id)?;
+
+            tree_id
+        }
+        None => {
+            let ctree = eg::hash! {"purpose": purpose, "active": active};
+            editor.create(EgValue::create("aouct", ctree)?)?.id()?
+        }
+    };
+
+    if !tree["root"].is_null() {
+        create_node(editor, tree_id, &tree["root"], None, 0)?;
+    }
+
+    clear_cache();
+
+    Ok(tree_id)
+}
+
+fn create_node(
+    editor: &mut Editor,
+    tree_id: i64,
+    node: &EgValue,
+    parent_node: Option<i64>,
+    sibling_order: usize,
+) -> EgResult<()> {
+    let value = eg::hash! {
+        "tree": tree_id,
+        "org_unit": node["org_unit"].int()?,
+        "parent_node": parent_node,
+        "sibling_order": sibling_order,
+    };
+
+    let node_id = editor.create(EgValue::create("aouctn", value)?)?.id()?;
+
+    for (idx, child) in node["children"].members().enumerate() {
+        create_node(editor, tree_id, child, Some(node_id), idx)?;
+    }
+
+    Ok(())
+}
+
+/// Delete the nodes of a custom tree, children first.
+fn delete_nodes(editor: &mut Editor, tree_id: i64) -> EgResult<()> {
+    let mut nodes = editor.search("aouctn", eg::hash! {"tree": tree_id})?;
+
+    while !nodes.is_empty() {
+        let parents: Vec<i64> = nodes
+            .iter()
+            .filter_map(|n| n["parent_node"].as_int())
+            .collect();
+
+        let (leaves, rest): (Vec<EgValue>, Vec<EgValue>) = nodes
+            .into_iter()
+            .partition(|n| n.id().map(|id| !parents.contains(&id)).unwrap_or(true));
+
+        for leaf in leaves {
+            editor.delete(leaf)?;
+        }
+
+        nodes = rest;
+    }
+
+    Ok(())
+}
+
+/// Delete a custom tree and its nodes.
+///
+/// The editor must be in a transaction.
+pub fn delete_custom_tree(editor: &mut Editor, tree_id: i64) -> EgResult<()> {
+    let tree = editor
+        .retrieve("aouct", tree_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    delete_nodes(editor, tree_id)?;
+    editor.delete(tree)?;
+
+    clear_cache();
+
+    Ok(())
+}
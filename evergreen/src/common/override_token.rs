@@ -0,0 +1,201 @@
+//! Supervisor override tokens.
+//!
+//! When a circulation action returns events that the operator lacks
+//! permission to override, a supervisor may authenticate on their
+//! behalf.  Rather than passing the supervisor's credentials along
+//! with the retried action, the supervisor logs in once to create a
+//! short-lived token listing the events they approve.  The operator
+//! then retries the action with an "override_token" option.
+//!
+//! Tokens are bound to the requesting staff account and are only
+//! issued for events the supervisor has the `<EVENT>.override`
+//! permission for.  A token may be reused until it expires so that
+//! batch operations (e.g. renewing several items) only require a
+//! single supervisor login.
+use crate as eg;
+use eg::common::auth::{self, LoginArgs, LoginType};
+use eg::osrf::cache::Cache;
+use eg::util;
+use eg::{Editor, EgError, EgEvent, EgResult, EgValue};
+use md5;
+
+const CACHE_PREFIX: &str = "rs.override_token";
+
+/// Name of the option key that carries the override token.
+pub const OVERRIDE_TOKEN_PARAM: &str = "override_token";
+
+/// Default token lifetime in seconds.
+pub const DEFAULT_OVERRIDE_TOKEN_TTL: u32 = 300;
+
+/// Cache key for a token.
+///
+/// ```
+/// use evergreen::common::override_token;
+///
+/// assert_eq!(override_token::cache_key("abc"), "rs.override_token.abc");
+/// ```
+pub fn cache_key(token: &str) -> String {
+    format!("{CACHE_PREFIX}.{token}")
+}
+
+#[derive(Debug, Clone)]
+pub struct OverrideToken {
+    token: String,
+    requestor_id: i64,
+    supervisor_id: i64,
+    events: Vec<String>,
+}
+
+impl OverrideToken {
+    /// Authenticate a supervisor and create a token allowing the
+    /// requestor of the provided editor to override the listed events.
+    ///
+    /// Returns Err with a LOGIN_FAILED event if the supervisor
+    /// credentials are invalid or with the permission failure event
+    /// if the supervisor may not override any of the events.
+    pub fn create(
+        editor: &mut Editor,
+        username: &str,
+        password: &str,
+        events: &[String],
+        ttl: u32,
+    ) -> EgResult<OverrideToken> {
+        if events.is_empty() {
+            return Err("Override token requires at least one event".into());
+        }
+
+        let requestor_id = editor.requestor_id()?;
+        let perm_org = editor.perm_org();
+
+        let args = LoginArgs::new(username, password, LoginType::Temp, None);
+
+        let supervisor_ses = match auth::Session::login(editor.client_mut(), &args)? {
+            Some(s) => s,
+            None => return Err(EgError::from_event(EgEvent::new("LOGIN_FAILED"))),
+        };
+
+        let result =
+            OverrideToken::check_supervisor(editor, supervisor_ses.token(), events, perm_org);
+
+        // The supervisor session is only needed for the permission checks.
+        if let Err(e) = auth::Session::logout(editor.client_mut(), supervisor_ses.token()) {
+            log::warn!("Cannot logout supervisor session: {e}");
+        }
+
+        let supervisor_id = result?;
+
+        let token = OverrideToken {
+            token: format!("{:x}", md5::compute(util::random_number(20))),
+            requestor_id,
+            supervisor_id,
+            events: events.to_vec(),
+        };
+
+        Cache::set_global_for(&cache_key(&token.token), token.to_cache_value(), ttl)?;
+
+        log::info!(
+            "Supervisor {supervisor_id} created override token for user {requestor_id} events={:?}",
+            token.events
+        );
+
+        Ok(token)
+    }
+
+    /// Verify the supervisor may override every event at the org unit.
+    ///
+    /// Returns the supervisor's user ID.
+    fn check_supervisor(
+        editor: &mut Editor,
+        authtoken: &str,
+        events: &[String],
+        perm_org: i64,
+    ) -> EgResult<i64> {
+        let mut sup_editor = Editor::with_auth(editor.client_mut(), authtoken);
+
+        if !sup_editor.checkauth()? {
+            return Err(sup_editor.die_event());
+        }
+
+        for event in events {
+            let perm = format!("{event}.override");
+            if !sup_editor.allowed_at(&perm, perm_org)? {
+                return Err(sup_editor.die_event());
+            }
+        }
+
+        sup_editor.requestor_id()
+    }
+
+    /// Load a token on behalf of the requestor.
+    ///
+    /// Returns None if the token does not exist, has expired, or was
+    /// issued to a different staff account.
+    pub fn retrieve(token: &str, requestor_id: i64) -> EgResult<Option<OverrideToken>> {
+        let value = match Cache::get_global(&cache_key(token))? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        if value["requestor"].int()? != requestor_id {
+            log::warn!(
+                "User {requestor_id} attempted to use an override token issued to another user"
+            );
+            return Ok(None);
+        }
+
+        let events = value["events"]
+            .members()
+            .filter_map(|e| e.as_str())
+            .map(|e| e.to_string())
+            .collect();
+
+        Ok(Some(OverrideToken {
+            token: token.to_string(),
+            requestor_id,
+            supervisor_id: value["supervisor"].int()?,
+            events,
+        }))
+    }
+
+    /// Remove the token from the cache so it may no longer be used.
+    pub fn remove(&self) -> EgResult<()> {
+        Cache::del_global(&cache_key(&self.token))
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    pub fn requestor_id(&self) -> i64 {
+        self.requestor_id
+    }
+
+    pub fn supervisor_id(&self) -> i64 {
+        self.supervisor_id
+    }
+
+    pub fn events(&self) -> &Vec<String> {
+        &self.events
+    }
+
+    /// True if the supervisor approved overriding the event.
+    pub fn covers(&self, textcode: &str) -> bool {
+        self.events.iter().any(|e| e == textcode)
+    }
+
+    fn to_cache_value(&self) -> EgValue {
+        eg::hash! {
+            requestor: self.requestor_id,
+            supervisor: self.supervisor_id,
+            events: self.events.clone(),
+        }
+    }
+
+    pub fn to_eg_value(&self) -> EgValue {
+        eg::hash! {
+            token: self.token.as_str(),
+            supervisor: self.supervisor_id,
+            events: self.events.clone(),
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! Self-service payments via external payment processors.
+//!
+//! Patrons pay for one or more of their own transactions by creating a
+//! payment intent with an external processor.  Once the processor
+//! reports (via a signed webhook) that the intent succeeded, the
+//! payments are recorded as credit card payments.
+//!
+//! Processor credentials are managed with org unit settings, e.g.
+//! credit.processor.stripe.secretkey.
+use crate as eg;
+use eg::common::billing;
+use eg::common::penalty;
+use eg::common::settings::Settings;
+use eg::date;
+use eg::editor::Editor;
+use eg::result::EgResult;
+use eg::EgValue;
+use std::fmt;
+
+pub mod stripe;
+
+/// Org setting naming the processor used for self-service payments.
+const DEFAULT_PROCESSOR_SETTING: &str = "credit.processor.default";
+
+/// Lifecycle states of a payment intent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntentStatus {
+    /// Waiting on the patron to provide payment details.
+    Pending,
+    /// Funds are authorized but not yet captured.
+    Authorized,
+    Succeeded,
+    Canceled,
+    Refunded,
+    Failed,
+}
+
+impl fmt::Display for IntentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::Pending => "pending",
+            Self::Authorized => "authorized",
+            Self::Succeeded => "succeeded",
+            Self::Canceled => "canceled",
+            Self::Refunded => "refunded",
+            Self::Failed => "failed",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// A single transaction to pay and the amount to apply to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XactPayment {
+    pub xact_id: i64,
+    pub amount: f64,
+}
+
+/// Request for a new payment intent.
+#[derive(Debug, Clone)]
+pub struct PaymentRequest {
+    pub user_id: i64,
+
+    /// Org unit whose processor settings apply.
+    pub org_id: i64,
+
+    pub payments: Vec<XactPayment>,
+
+    /// Capture funds immediately on success instead of leaving
+    /// them authorized for a later capture().
+    pub auto_capture: bool,
+}
+
+impl PaymentRequest {
+    /// Total of all payments in cents.
+    ///
+    /// ```
+    /// use evergreen::common::payment::{PaymentRequest, XactPayment};
+    ///
+    /// let req = PaymentRequest {
+    ///     user_id: 1,
+    ///     org_id: 1,
+    ///     auto_capture: true,
+    ///     payments: vec![
+    ///         XactPayment { xact_id: 1, amount: 0.10 },
+    ///         XactPayment { xact_id: 2, amount: 0.20 },
+    ///     ],
+    /// };
+    ///
+    /// assert_eq!(req.amount_cents(), 30);
+    /// ```
+    pub fn amount_cents(&self) -> i64 {
+        self.payments
+            .iter()
+            .map(|p| (p.amount * 100.0).round() as i64)
+            .sum()
+    }
+}
+
+/// Processor-side state of a payment.
+#[derive(Debug, Clone)]
+pub struct PaymentIntent {
+    /// Processor-assigned ID
+    pub id: String,
+    pub status: IntentStatus,
+    pub amount_cents: i64,
+
+    /// Value passed to the client-side payment form, if any.
+    pub client_secret: Option<String>,
+
+    pub user_id: i64,
+    pub org_id: i64,
+    pub payments: Vec<XactPayment>,
+}
+
+impl PaymentIntent {
+    /// Values safe to return to the patron.
+    pub fn to_eg_value(&self) -> EgValue {
+        eg::hash! {
+            "id": self.id.as_str(),
+            "status": self.status.to_string(),
+            "amount": self.amount_cents as f64 / 100.0,
+            "client_secret": self.client_secret.as_deref(),
+        }
+    }
+}
+
+/// Operations supported by an external payment processor.
+pub trait PaymentProcessor {
+    /// Processor name, recorded on payments as the cc_processor.
+    fn name(&self) -> &str;
+
+    /// Create a payment intent for the requested amount.
+    fn authorize(&mut self, request: &PaymentRequest) -> EgResult<PaymentIntent>;
+
+    /// Capture the funds of a previously authorized intent.
+    fn capture(&mut self, intent_id: &str) -> EgResult<PaymentIntent>;
+
+    /// Refund some or all (when amount_cents is None) of a
+    /// completed payment.
+    fn refund(&mut self, intent_id: &str, amount_cents: Option<i64>) -> EgResult<PaymentIntent>;
+
+    /// Verify the signature on a webhook payload and return the
+    /// payment intent it describes, if any.
+    ///
+    /// Returns Err if the signature is invalid.
+    fn verify_webhook(&self, payload: &str, signature: &str) -> EgResult<Option<PaymentIntent>>;
+}
+
+/// Returns the payment processor configured for the org unit.
+pub fn processor_for_org(editor: &mut Editor, org_id: i64) -> EgResult<Box<dyn PaymentProcessor>> {
+    let mut settings = Settings::new(editor);
+
+    settings.fetch_values_for_org(
+        org_id,
+        &[
+            DEFAULT_PROCESSOR_SETTING,
+            stripe::ENABLED_SETTING,
+            stripe::SECRET_KEY_SETTING,
+            stripe::WEBHOOK_SECRET_SETTING,
+        ],
+    )?;
+
+    let name = settings
+        .get_value_at_org(DEFAULT_PROCESSOR_SETTING, org_id)?
+        .as_str()
+        .unwrap_or(stripe::PROCESSOR_NAME)
+        .to_string();
+
+    match name.as_str() {
+        stripe::PROCESSOR_NAME => Ok(Box::new(stripe::StripeProcessor::from_settings(
+            &mut settings,
+            org_id,
+        )?)),
+        _ => Err(format!("Unsupported payment processor: {name}").into()),
+    }
+}
+
+/// Build a payment request for the full balance owed on each of the
+/// provided transactions.
+///
+/// Returns Err if any transaction does not belong to the user or
+/// has nothing owed.
+pub fn request_for_xacts(
+    editor: &mut Editor,
+    user_id: i64,
+    org_id: i64,
+    xact_ids: &[i64],
+) -> EgResult<PaymentRequest> {
+    if xact_ids.is_empty() {
+        return Err("No transactions to pay".into());
+    }
+
+    let mut payments = Vec::new();
+
+    for xact_id in xact_ids {
+        let summary = editor
+            .retrieve("mbts", *xact_id)?
+            .ok_or_else(|| editor.die_event())?;
+
+        if summary["usr"].int()? != user_id {
+            return Err(format!("Transaction {xact_id} does not belong to user {user_id}").into());
+        }
+
+        let balance = summary["balance_owed"].float()?;
+
+        if balance <= 0.0 {
+            return Err(format!("Transaction {xact_id} has no balance owed").into());
+        }
+
+        payments.push(XactPayment {
+            xact_id: *xact_id,
+            amount: balance,
+        });
+    }
+
+    Ok(PaymentRequest {
+        user_id,
+        org_id,
+        payments,
+        auto_capture: true,
+    })
+}
+
+/// Record the payments for a successful payment intent.
+///
+/// Webhooks may be delivered more than once.  If payments already
+/// exist for the intent, nothing is recorded.
+///
+/// The editor must be in a transaction.
+///
+/// Returns the newly created payments.
+pub fn record_intent_payments(
+    editor: &mut Editor,
+    processor: &str,
+    intent: &PaymentIntent,
+) -> EgResult<Vec<EgValue>> {
+    if intent.status != IntentStatus::Succeeded {
+        return Err(format!("Cannot record payments for {} intent", intent.status).into());
+    }
+
+    let existing = editor.search("mccp", eg::hash! {"cc_order_number": intent.id.as_str()})?;
+
+    if !existing.is_empty() {
+        log::info!("Payments already recorded for intent {}", intent.id);
+        return Ok(Vec::new());
+    }
+
+    let mut payments = Vec::new();
+
+    for pay in intent.payments.iter() {
+        let payment = eg::hash! {
+            "xact": pay.xact_id,
+            "amount": pay.amount,
+            "amount_collected": pay.amount,
+            "payment_ts": date::to_iso(&date::now()),
+            "accepting_usr": intent.user_id,
+            "cc_processor": processor,
+            "cc_order_number": intent.id.as_str(),
+            "approval_code": intent.id.as_str(),
+            "note": "Self-service payment",
+        };
+
+        let payment = editor.create(EgValue::create("mccp", payment)?)?;
+
+        billing::check_open_xact(editor, pay.xact_id)?;
+
+        payments.push(payment);
+    }
+
+    penalty::calculate_penalties(editor, intent.user_id, intent.org_id, None)?;
+
+    log::info!(
+        "Recorded {} payments for intent {} totaling {:.2}",
+        payments.len(),
+        intent.id,
+        intent.amount_cents as f64 / 100.0
+    );
+
+    Ok(payments)
+}
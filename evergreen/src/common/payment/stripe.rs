@@ -0,0 +1,436 @@
+//! Stripe-compatible payment processor using the Payment Intents API.
+use super::{IntentStatus, PaymentIntent, PaymentProcessor, PaymentRequest, XactPayment};
+use crate as eg;
+use eg::common::settings::Settings;
+use eg::date;
+use eg::osrf::message::MethodCall;
+use eg::result::EgResult;
+use eg::EgValue;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+pub const PROCESSOR_NAME: &str = "Stripe";
+
+pub const ENABLED_SETTING: &str = "credit.processor.stripe.enabled";
+pub const SECRET_KEY_SETTING: &str = "credit.processor.stripe.secretkey";
+pub const WEBHOOK_SECRET_SETTING: &str = "credit.processor.stripe.webhook_secret";
+
+/// Path the HTTP gateway accepts Stripe webhook POSTs on.  Point the
+/// Stripe webhook endpoint (via the proxy in front of the gateway) here.
+pub const WEBHOOK_PATH: &str = "/stripe-webhook";
+
+/// HTTP header carrying the webhook signature.
+pub const SIGNATURE_HEADER: &str = "Stripe-Signature";
+
+/// Service and API which verify and record webhook payments.
+pub const WEBHOOK_SERVICE: &str = "open-ils.circ";
+pub const WEBHOOK_API: &str = "open-ils.circ.money.payment_intent.webhook";
+
+const API_URL: &str = "https://api.stripe.com/v1";
+const CURRENCY: &str = "usd";
+
+/// Reject webhook signatures older than this many seconds to
+/// prevent replay of captured requests.
+const WEBHOOK_TOLERANCE: i64 = 300;
+
+/// HTTP request timeout in seconds.
+const REQUEST_TIMEOUT: u64 = 30;
+
+pub struct StripeProcessor {
+    api_url: String,
+    secret_key: String,
+    webhook_secret: Option<String>,
+}
+
+impl StripeProcessor {
+    pub fn new(secret_key: &str, webhook_secret: Option<&str>) -> Self {
+        StripeProcessor {
+            api_url: API_URL.to_string(),
+            secret_key: secret_key.to_string(),
+            webhook_secret: webhook_secret.map(|s| s.to_string()),
+        }
+    }
+
+    /// Override the API base URL, e.g. for a test server or a
+    /// Stripe-compatible alternative.
+    pub fn set_api_url(&mut self, url: &str) {
+        self.api_url = url.trim_end_matches('/').to_string();
+    }
+
+    /// Create a processor from org unit settings.
+    ///
+    /// Returns Err if Stripe is not enabled or configured at the org unit.
+    pub fn from_settings(settings: &mut Settings, org_id: i64) -> EgResult<Self> {
+        if !settings
+            .get_value_at_org(ENABLED_SETTING, org_id)?
+            .boolish()
+        {
+            return Err(format!("Stripe payments are not enabled at org unit {org_id}").into());
+        }
+
+        let secret_key = settings
+            .get_value_at_org(SECRET_KEY_SETTING, org_id)?
+            .as_str()
+            .ok_or_else(|| format!("Stripe secret key not configured at org unit {org_id}"))?
+            .to_string();
+
+        let webhook_secret = settings
+            .get_value_at_org(WEBHOOK_SECRET_SETTING, org_id)?
+            .as_str()
+            .map(|s| s.to_string());
+
+        Ok(StripeProcessor::new(&secret_key, webhook_secret.as_deref()))
+    }
+
+    fn agent(&self) -> ureq::Agent {
+        ureq::AgentBuilder::new()
+            .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT))
+            .build()
+    }
+
+    fn auth_header(&self) -> String {
+        format!("Bearer {}", self.secret_key)
+    }
+
+    /// Parse a response body, translating HTTP errors into EgErrors
+    /// containing the Stripe error message.
+    fn handle_response(
+        path: &str,
+        result: Result<ureq::Response, ureq::Error>,
+    ) -> EgResult<EgValue> {
+        let (status, body) = match result {
+            Ok(r) => (r.status(), r.into_string()),
+            Err(ureq::Error::Status(code, r)) => (code, r.into_string()),
+            Err(e) => return Err(format!("Stripe request {path} failed: {e}").into()),
+        };
+
+        let body = body.map_err(|e| format!("Cannot read Stripe response: {e}"))?;
+        let value = EgValue::parse(&body)?;
+
+        if status >= 400 {
+            let msg = value["error"]["message"]
+                .as_str()
+                .unwrap_or("unknown error");
+            return Err(format!("Stripe request {path} failed with status {status}: {msg}").into());
+        }
+
+        Ok(value)
+    }
+
+    fn post(&self, path: &str, params: &[(&str, &str)]) -> EgResult<EgValue> {
+        let url = format!("{}/{path}", self.api_url);
+
+        log::info!("Stripe POST {path}");
+
+        let result = self
+            .agent()
+            .post(&url)
+            .set("Authorization", &self.auth_header())
+            .send_form(params);
+
+        Self::handle_response(path, result)
+    }
+
+    fn get(&self, path: &str) -> EgResult<EgValue> {
+        let url = format!("{}/{path}", self.api_url);
+
+        log::info!("Stripe GET {path}");
+
+        let result = self
+            .agent()
+            .get(&url)
+            .set("Authorization", &self.auth_header())
+            .call();
+
+        Self::handle_response(path, result)
+    }
+}
+
+/// Translate a Stripe intent status into our own status.
+fn intent_status(status: &str) -> IntentStatus {
+    match status {
+        "succeeded" => IntentStatus::Succeeded,
+        "requires_capture" => IntentStatus::Authorized,
+        "canceled" => IntentStatus::Canceled,
+        "requires_payment_method" | "requires_confirmation" | "requires_action" | "processing" => {
+            IntentStatus::Pending
+        }
+        _ => IntentStatus::Failed,
+    }
+}
+
+/// Encode transaction payments for storage in intent metadata.
+///
+/// ```
+/// use evergreen::common::payment::XactPayment;
+/// use evergreen::common::payment::stripe;
+///
+/// let payments = vec![
+///     XactPayment { xact_id: 10, amount: 1.5 },
+///     XactPayment { xact_id: 11, amount: 0.25 },
+/// ];
+///
+/// let encoded = stripe::encode_xacts(&payments);
+/// assert_eq!(encoded, "10:1.50,11:0.25");
+/// assert_eq!(stripe::decode_xacts(&encoded).unwrap(), payments);
+/// ```
+pub fn encode_xacts(payments: &[XactPayment]) -> String {
+    payments
+        .iter()
+        .map(|p| format!("{}:{:.2}", p.xact_id, p.amount))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Decode transaction payments from intent metadata.
+pub fn decode_xacts(value: &str) -> EgResult<Vec<XactPayment>> {
+    let mut payments = Vec::new();
+
+    for part in value.split(',').filter(|p| !p.is_empty()) {
+        let (id, amount) = part
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid transaction metadata: {part}"))?;
+
+        payments.push(XactPayment {
+            xact_id: id
+                .parse()
+                .map_err(|_| format!("Invalid transaction ID: {id}"))?,
+            amount: amount
+                .parse()
+                .map_err(|_| format!("Invalid transaction amount: {amount}"))?,
+        });
+    }
+
+    Ok(payments)
+}
+
+/// Build a PaymentIntent from a Stripe payment_intent object.
+///
+/// The user, org unit, and transactions are read from the intent
+/// metadata we applied at creation time.
+pub fn intent_from_value(value: &EgValue) -> EgResult<PaymentIntent> {
+    let metadata = &value["metadata"];
+
+    let user_id = metadata["user_id"]
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or("Payment intent has no user_id metadata")?;
+
+    let org_id = metadata["org_id"]
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or("Payment intent has no org_id metadata")?;
+
+    Ok(PaymentIntent {
+        id: value["id"].string()?,
+        status: intent_status(value["status"].as_str().unwrap_or("")),
+        amount_cents: value["amount"].int()?,
+        client_secret: value["client_secret"].as_str().map(|s| s.to_string()),
+        user_id,
+        org_id,
+        payments: decode_xacts(metadata["xacts"].as_str().unwrap_or(""))?,
+    })
+}
+
+/// Returns the org unit ID from the metadata of the payment intent
+/// within an (unverified) webhook payload.
+///
+/// Used to locate the webhook secret needed to verify the payload.
+pub fn webhook_org_id(payload: &str) -> EgResult<i64> {
+    let event = EgValue::parse(payload)?;
+
+    event["data"]["object"]["metadata"]["org_id"]
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| "Webhook payload has no org_id metadata".into())
+}
+
+/// Verify a Stripe-Signature header value against the payload.
+///
+/// The header has the form "t=<timestamp>,v1=<hex signature>[,v1=...]"
+/// where the signature is an HMAC-SHA256 of "<timestamp>.<payload>".
+///
+/// ```
+/// use evergreen::common::payment::stripe;
+///
+/// let payload = r#"{"type":"ping"}"#;
+/// let header = stripe::sign_payload("whsec_test", payload, 1700000000);
+///
+/// assert!(stripe::verify_signature("whsec_test", payload, &header, 1700000100).is_ok());
+/// assert!(stripe::verify_signature("whsec_other", payload, &header, 1700000100).is_err());
+/// assert!(stripe::verify_signature("whsec_test", "{}", &header, 1700000100).is_err());
+///
+/// // Too old
+/// assert!(stripe::verify_signature("whsec_test", payload, &header, 1700009999).is_err());
+/// ```
+pub fn verify_signature(secret: &str, payload: &str, header: &str, now: i64) -> EgResult<()> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", t)) => timestamp = t.parse::<i64>().ok(),
+            Some(("v1", s)) => signatures.push(s),
+            _ => {}
+        }
+    }
+
+    let timestamp = timestamp.ok_or("Webhook signature has no timestamp")?;
+
+    if (now - timestamp).abs() > WEBHOOK_TOLERANCE {
+        return Err("Webhook signature timestamp is outside the tolerance window".into());
+    }
+
+    for sig in signatures {
+        let Some(bytes) = hex_decode(sig) else {
+            continue;
+        };
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+            .map_err(|e| format!("Invalid webhook secret: {e}"))?;
+
+        mac.update(format!("{timestamp}.{payload}").as_bytes());
+
+        // verify_slice() performs a constant-time comparison.
+        if mac.verify_slice(&bytes).is_ok() {
+            return Ok(());
+        }
+    }
+
+    Err("Webhook signature verification failed".into())
+}
+
+/// Build the webhook API call from the raw body and Stripe-Signature
+/// header of a webhook POST.
+///
+/// Both are passed through unchanged, since the signature covers the
+/// exact bytes of the body.
+///
+/// ```
+/// use evergreen::common::payment::stripe;
+///
+/// let payload = "{\"type\": \"payment_intent.succeeded\",\n \"id\": \"evt_1\"}";
+/// let header = stripe::sign_payload("whsec_test", payload, 1700000000);
+///
+/// let call = stripe::webhook_method_call(payload, &header);
+/// assert_eq!(call.method(), stripe::WEBHOOK_API);
+///
+/// let body = call.params()[0].as_str().unwrap();
+/// let signature = call.params()[1].as_str().unwrap();
+///
+/// assert_eq!(body, payload);
+/// assert!(stripe::verify_signature("whsec_test", body, signature, 1700000100).is_ok());
+/// ```
+pub fn webhook_method_call(body: &str, signature: &str) -> MethodCall {
+    MethodCall::new(
+        WEBHOOK_API,
+        vec![EgValue::from(body), EgValue::from(signature)],
+    )
+}
+
+/// Create a Stripe-Signature header value for a payload.
+///
+/// Useful for testing webhook handling.
+pub fn sign_payload(secret: &str, payload: &str, timestamp: i64) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+
+    mac.update(format!("{timestamp}.{payload}").as_bytes());
+
+    let sig: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    format!("t={timestamp},v1={sig}")
+}
+
+fn hex_decode(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+impl PaymentProcessor for StripeProcessor {
+    fn name(&self) -> &str {
+        PROCESSOR_NAME
+    }
+
+    fn authorize(&mut self, request: &PaymentRequest) -> EgResult<PaymentIntent> {
+        let amount = request.amount_cents();
+
+        if amount <= 0 {
+            return Err("Payment amount must be greater than zero".into());
+        }
+
+        let amount = amount.to_string();
+        let user_id = request.user_id.to_string();
+        let org_id = request.org_id.to_string();
+        let xacts = encode_xacts(&request.payments);
+        let capture_method = if request.auto_capture {
+            "automatic"
+        } else {
+            "manual"
+        };
+
+        let params = [
+            ("amount", amount.as_str()),
+            ("currency", CURRENCY),
+            ("capture_method", capture_method),
+            ("metadata[user_id]", user_id.as_str()),
+            ("metadata[org_id]", org_id.as_str()),
+            ("metadata[xacts]", xacts.as_str()),
+        ];
+
+        let value = self.post("payment_intents", &params)?;
+
+        intent_from_value(&value)
+    }
+
+    fn capture(&mut self, intent_id: &str) -> EgResult<PaymentIntent> {
+        let value = self.post(&format!("payment_intents/{intent_id}/capture"), &[])?;
+        intent_from_value(&value)
+    }
+
+    fn refund(&mut self, intent_id: &str, amount_cents: Option<i64>) -> EgResult<PaymentIntent> {
+        let amount = amount_cents.map(|a| a.to_string());
+
+        let mut params = vec![("payment_intent", intent_id)];
+        if let Some(a) = amount.as_deref() {
+            params.push(("amount", a));
+        }
+
+        self.post("refunds", &params)?;
+
+        let mut intent = intent_from_value(&self.get(&format!("payment_intents/{intent_id}"))?)?;
+        intent.status = IntentStatus::Refunded;
+
+        Ok(intent)
+    }
+
+    fn verify_webhook(&self, payload: &str, signature: &str) -> EgResult<Option<PaymentIntent>> {
+        let secret = self
+            .webhook_secret
+            .as_deref()
+            .ok_or("Stripe webhook secret is not configured")?;
+
+        verify_signature(secret, payload, signature, date::now().timestamp())?;
+
+        let event = EgValue::parse(payload)?;
+        let etype = event["type"].as_str().unwrap_or("");
+
+        if !etype.starts_with("payment_intent.") {
+            log::debug!("Ignoring Stripe webhook event {etype}");
+            return Ok(None);
+        }
+
+        intent_from_value(&event["data"]["object"]).map(Some)
+    }
+}
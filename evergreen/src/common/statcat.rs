@@ -0,0 +1,338 @@
+//! Patron (actor) and copy (asset) statistical categories.
+use crate as eg;
+use eg::common::org;
+use eg::util;
+use eg::Editor;
+use eg::EgResult;
+use eg::EgValue;
+
+/// Patron and copy stat cats share a structure, but live in
+/// different IDL classes and use different permissions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatCatType {
+    Actor,
+    Asset,
+}
+
+impl StatCatType {
+    /// Stat cat IDL class.
+    pub fn cat_class(&self) -> &'static str {
+        match self {
+            Self::Actor => "actsc",
+            Self::Asset => "asc",
+        }
+    }
+
+    /// Stat cat entry IDL class.
+    pub fn entry_class(&self) -> &'static str {
+        match self {
+            Self::Actor => "actsce",
+            Self::Asset => "asce",
+        }
+    }
+
+    /// IDL class linking entries to patrons or copies.
+    pub fn map_class(&self) -> &'static str {
+        match self {
+            Self::Actor => "actscecm",
+            Self::Asset => "ascecm",
+        }
+    }
+
+    /// Permission name for an action on a stat cat or its entries.
+    ///
+    /// ```
+    /// use evergreen::common::statcat::StatCatType;
+    ///
+    /// assert_eq!(StatCatType::Actor.perm("CREATE", false), "CREATE_PATRON_STAT_CAT");
+    /// assert_eq!(StatCatType::Asset.perm("DELETE", true), "DELETE_COPY_STAT_CAT_ENTRY");
+    /// ```
+    pub fn perm(&self, action: &str, is_entry: bool) -> String {
+        let target = match self {
+            Self::Actor => "PATRON",
+            Self::Asset => "COPY",
+        };
+
+        let suffix = if is_entry { "_ENTRY" } else { "" };
+
+        format!("{action}_{target}_STAT_CAT{suffix}")
+    }
+
+    /// Determine the stat cat type from an API method name,
+    /// e.g. open-ils.actor.stat_cat.asset.retrieve.all
+    pub fn from_method(method: &str) -> StatCatType {
+        if method.contains(".asset.") {
+            Self::Asset
+        } else {
+            Self::Actor
+        }
+    }
+}
+
+/// Returns stat cats owned by the org unit or its ancestors, fleshed
+/// with their entries, limited to the entries owned by the org unit
+/// or its ancestors.
+pub fn visible_stat_cats(
+    editor: &mut Editor,
+    sctype: StatCatType,
+    org_id: i64,
+) -> EgResult<Vec<EgValue>> {
+    let orgs = org::ancestors(editor, org_id)?;
+    let cat_class = sctype.cat_class();
+
+    let flesh = eg::hash! {
+        flesh: 1,
+        flesh_fields: {[cat_class]: ["entries"]},
+        order_by: {[cat_class]: "name"},
+    };
+
+    let mut cats = editor.search_with_ops(cat_class, eg::hash! {owner: orgs.as_slice()}, flesh)?;
+
+    for cat in cats.iter_mut() {
+        let mut entries = Vec::new();
+
+        for entry in cat["entries"].take_vec().unwrap_or_default() {
+            if orgs.contains(&entry["owner"].int()?) {
+                entries.push(entry);
+            }
+        }
+
+        cat["entries"] = EgValue::from(entries);
+    }
+
+    Ok(cats)
+}
+
+/// Create or update a stat cat along with any entries in its
+/// "entries" list.  Objects with no ID are created.
+///
+/// Entries default to being owned by the owner of the stat cat.
+/// Updates require permission at the stored owner as well as any
+/// new owner, and existing entries must belong to the stat cat.
+///
+/// The editor must be in a transaction.
+///
+/// Returns the stat cat ID.
+pub fn save_stat_cat(editor: &mut Editor, sctype: StatCatType, mut cat: EgValue) -> EgResult<i64> {
+    util::verify_class(&cat, sctype.cat_class())?;
+
+    let owner = cat["owner"].int()?;
+    let entries = cat["entries"].take_vec().unwrap_or_default();
+
+    let cat_id = if cat["id"].is_null() {
+        if !editor.allowed_at(&sctype.perm("CREATE", false), owner)? {
+            return Err(editor.die_event());
+        }
+
+        editor.create(cat)?.id()?
+    } else {
+        let cat_id = cat.id()?;
+
+        let existing = editor
+            .retrieve(sctype.cat_class(), cat_id)?
+            .ok_or_else(|| editor.die_event())?;
+
+        let perm = sctype.perm("UPDATE", false);
+
+        for org_id in util::owner_orgs(&existing, owner)? {
+            if !editor.allowed_at(&perm, org_id)? {
+                return Err(editor.die_event());
+            }
+        }
+
+        editor.update(cat)?;
+
+        cat_id
+    };
+
+    for mut entry in entries {
+        util::verify_class(&entry, sctype.entry_class())?;
+
+        entry["stat_cat"] = EgValue::from(cat_id);
+
+        if entry["owner"].is_null() {
+            entry["owner"] = EgValue::from(owner);
+        }
+
+        let entry_owner = entry["owner"].int()?;
+
+        if entry["id"].is_null() {
+            if !editor.allowed_at(&sctype.perm("CREATE", true), entry_owner)? {
+                return Err(editor.die_event());
+            }
+            editor.create(entry)?;
+            continue;
+        }
+
+        let entry_id = entry.id()?;
+
+        let existing = editor
+            .retrieve(sctype.entry_class(), entry_id)?
+            .ok_or_else(|| editor.die_event())?;
+
+        if existing["stat_cat"].int()? != cat_id {
+            return Err(format!("Entry {entry_id} does not belong to stat cat {cat_id}").into());
+        }
+
+        let perm = sctype.perm("UPDATE", true);
+
+        for org_id in util::owner_orgs(&existing, entry_owner)? {
+            if !editor.allowed_at(&perm, org_id)? {
+                return Err(editor.die_event());
+            }
+        }
+
+        editor.update(entry)?;
+    }
+
+    Ok(cat_id)
+}
+
+/// Delete a stat cat, its entries, and all patron or copy values
+/// for the stat cat.
+///
+/// The editor must be in a transaction.
+pub fn delete_stat_cat(editor: &mut Editor, sctype: StatCatType, cat_id: i64) -> EgResult<()> {
+    let cat = editor
+        .retrieve(sctype.cat_class(), cat_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    if !editor.allowed_at(&sctype.perm("DELETE", false), cat["owner"].int()?)? {
+        return Err(editor.die_event());
+    }
+
+    for map in editor.search(sctype.map_class(), eg::hash! {stat_cat: cat_id})? {
+        editor.delete(map)?;
+    }
+
+    for entry in editor.search(sctype.entry_class(), eg::hash! {stat_cat: cat_id})? {
+        editor.delete(entry)?;
+    }
+
+    editor.delete(cat)?;
+
+    Ok(())
+}
+
+/// Delete a stat cat entry along with any patron or copy values
+/// using the entry.
+///
+/// The editor must be in a transaction.
+pub fn delete_entry(editor: &mut Editor, sctype: StatCatType, entry_id: i64) -> EgResult<()> {
+    let entry = editor
+        .retrieve(sctype.entry_class(), entry_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    if !editor.allowed_at(&sctype.perm("DELETE", true), entry["owner"].int()?)? {
+        return Err(editor.die_event());
+    }
+
+    // Patron maps store the entry value.  Copy maps link to the entry.
+    let query = match sctype {
+        StatCatType::Actor => eg::hash! {
+            stat_cat: entry["stat_cat"].int()?,
+            stat_cat_entry: entry["value"].str()?,
+        },
+        StatCatType::Asset => eg::hash! {stat_cat_entry: entry_id},
+    };
+
+    for map in editor.search(sctype.map_class(), query)? {
+        editor.delete(map)?;
+    }
+
+    editor.delete(entry)?;
+
+    Ok(())
+}
+
+/// Apply patron stat cat values.  Each value is a hash of
+/// {"stat_cat": ID, "value": "..."}.  An empty or null value removes
+/// the patron's value for the stat cat.
+///
+/// Values for stat cats that do not allow freetext must match one
+/// of the stat cat's entries, and values for required stat cats
+/// may not be removed.
+///
+/// The editor must be in a transaction.
+pub fn set_user_values(editor: &mut Editor, user_id: i64, values: &EgValue) -> EgResult<()> {
+    for value in values.members() {
+        let cat_id = value["stat_cat"].int()?;
+        let new_value = value["value"].as_str().unwrap_or("");
+
+        let cat = editor
+            .retrieve("actsc", cat_id)?
+            .ok_or_else(|| editor.die_event())?;
+
+        let existing = editor
+            .search(
+                "actscecm",
+                eg::hash! {stat_cat: cat_id, target_usr: user_id},
+            )?
+            .pop();
+
+        if new_value.is_empty() {
+            if cat["required"].boolish() {
+                return Err(format!("Stat cat {cat_id} requires a value").into());
+            }
+            if let Some(map) = existing {
+                editor.delete(map)?;
+            }
+            continue;
+        }
+
+        if !cat["allow_freetext"].boolish() {
+            let query = eg::hash! {stat_cat: cat_id, value: new_value};
+
+            if editor.search("actsce", query)?.is_empty() {
+                return Err(
+                    format!("'{new_value}' is not an allowed value for stat cat {cat_id}").into(),
+                );
+            }
+        }
+
+        match existing {
+            Some(mut map) => {
+                if map["stat_cat_entry"].as_str() != Some(new_value) {
+                    map["stat_cat_entry"] = EgValue::from(new_value);
+                    editor.update(map)?;
+                }
+            }
+            None => {
+                let map = eg::hash! {
+                    stat_cat: cat_id,
+                    stat_cat_entry: new_value,
+                    target_usr: user_id,
+                };
+                editor.create(EgValue::create("actscecm", map)?)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the IDs of required patron stat cats visible at the org
+/// unit for which the patron has no value.
+pub fn missing_required_user_values(
+    editor: &mut Editor,
+    user_id: i64,
+    org_id: i64,
+) -> EgResult<Vec<i64>> {
+    let orgs = org::ancestors(editor, org_id)?;
+
+    let query = eg::hash! {owner: orgs.as_slice(), required: "t"};
+
+    let mut missing = Vec::new();
+
+    for cat in editor.search("actsc", query)? {
+        let cat_id = cat.id()?;
+
+        let query = eg::hash! {stat_cat: cat_id, target_usr: user_id};
+
+        if editor.search("actscecm", query)?.is_empty() {
+            missing.push(cat_id);
+        }
+    }
+
+    Ok(missing)
+}
@@ -0,0 +1,218 @@
+//! Patron surveys
+use crate as eg;
+use eg::common::org;
+use eg::date;
+use eg::util;
+use eg::Editor;
+use eg::EgResult;
+use eg::EgValue;
+
+/// Returns surveys owned by the org unit or its ancestors, fleshed
+/// with their questions and answers.
+///
+/// * `active_only` - Limit to surveys whose start/end dates include now.
+/// * `required_only` - Limit to surveys which must be completed at
+///   patron registration.
+pub fn visible_surveys(
+    editor: &mut Editor,
+    org_id: i64,
+    active_only: bool,
+    required_only: bool,
+) -> EgResult<Vec<EgValue>> {
+    let orgs = org::ancestors(editor, org_id)?;
+
+    let mut query = eg::hash! {owner: orgs.as_slice()};
+
+    if active_only {
+        let now = date::to_iso(&date::now());
+        query["start_date"] = eg::hash! {"<=": now.as_str()};
+        query["end_date"] = eg::hash! {">": now.as_str()};
+    }
+
+    if required_only {
+        query["required"] = EgValue::from("t");
+    }
+
+    let flesh = eg::hash! {
+        flesh: 2,
+        flesh_fields: {asv: ["questions"], asvq: ["answers"]},
+        order_by: {asv: "name"},
+    };
+
+    editor.search_with_ops("asv", query, flesh)
+}
+
+/// Create or update a survey along with the questions in its
+/// "questions" list and the answers in each question's "answers"
+/// list.  Objects with no ID are created.
+///
+/// Creating a survey, question, or answer requires CREATE_SURVEY and
+/// updating one requires ADMIN_SURVEY, checked at the survey owner.
+/// Updates to a survey are also checked at its stored owner, and
+/// existing questions and answers must belong to their parent.
+///
+/// The editor must be in a transaction.
+///
+/// Returns the survey ID.
+pub fn save_survey(editor: &mut Editor, mut survey: EgValue) -> EgResult<i64> {
+    if survey.classname() != Some("asv") {
+        return Err(format!("Expected a survey object: {survey}").into());
+    }
+
+    let owner = survey["owner"].int()?;
+    let questions = survey["questions"].take_vec().unwrap_or_default();
+
+    let survey_id = if survey["id"].is_null() {
+        if !editor.allowed_at("CREATE_SURVEY", owner)? {
+            return Err(editor.die_event());
+        }
+
+        editor.create(survey)?.id()?
+    } else {
+        let survey_id = survey.id()?;
+
+        let existing = editor
+            .retrieve("asv", survey_id)?
+            .ok_or_else(|| editor.die_event())?;
+
+        for org_id in util::owner_orgs(&existing, owner)? {
+            if !editor.allowed_at("ADMIN_SURVEY", org_id)? {
+                return Err(editor.die_event());
+            }
+        }
+
+        editor.update(survey)?;
+
+        survey_id
+    };
+
+    for mut question in questions {
+        let answers = question["answers"].take_vec().unwrap_or_default();
+
+        question["survey"] = EgValue::from(survey_id);
+
+        let question_id = if question["id"].is_null() {
+            if !editor.allowed_at("CREATE_SURVEY", owner)? {
+                return Err(editor.die_event());
+            }
+
+            editor.create(question)?.id()?
+        } else {
+            let question_id = question.id()?;
+
+            let existing = editor
+                .retrieve("asvq", question_id)?
+                .ok_or_else(|| editor.die_event())?;
+
+            if existing["survey"].int()? != survey_id {
+                return Err(format!(
+                    "Question {question_id} does not belong to survey {survey_id}"
+                )
+                .into());
+            }
+
+            if !editor.allowed_at("ADMIN_SURVEY", owner)? {
+                return Err(editor.die_event());
+            }
+
+            editor.update(question)?;
+            question_id
+        };
+
+        for mut answer in answers {
+            answer["question"] = EgValue::from(question_id);
+
+            if answer["id"].is_null() {
+                if !editor.allowed_at("CREATE_SURVEY", owner)? {
+                    return Err(editor.die_event());
+                }
+
+                editor.create(answer)?;
+                continue;
+            }
+
+            let answer_id = answer.id()?;
+
+            let existing = editor
+                .retrieve("asva", answer_id)?
+                .ok_or_else(|| editor.die_event())?;
+
+            if existing["question"].int()? != question_id {
+                return Err(format!(
+                    "Answer {answer_id} does not belong to question {question_id}"
+                )
+                .into());
+            }
+
+            if !editor.allowed_at("ADMIN_SURVEY", owner)? {
+                return Err(editor.die_event());
+            }
+
+            editor.update(answer)?;
+        }
+    }
+
+    Ok(survey_id)
+}
+
+/// Record a patron's survey responses.  Each response is a hash of
+/// {"survey": ID, "question": ID, "answer": ID}.
+///
+/// All responses share a response group ID and answer date.
+///
+/// The editor must be in a transaction.
+///
+/// Returns the created responses.
+pub fn record_responses(
+    editor: &mut Editor,
+    user_id: i64,
+    responses: &EgValue,
+) -> EgResult<Vec<EgValue>> {
+    let query = eg::hash! {"from": ["nextval", "action.survey_response_group_id_seq"]};
+
+    let group_id = editor
+        .json_query(query)?
+        .first()
+        .and_then(|r| r["nextval"].as_int())
+        .ok_or("Cannot allocate survey response group ID")?;
+
+    let now = date::to_iso(&date::now());
+    let mut created = Vec::new();
+
+    for response in responses.members() {
+        let survey_id = response["survey"].int()?;
+        let question_id = response["question"].int()?;
+        let answer_id = response["answer"].int()?;
+
+        // Make sure the answer belongs to the question and the
+        // question belongs to the survey.
+        let answer = editor
+            .retrieve("asva", answer_id)?
+            .ok_or_else(|| editor.die_event())?;
+
+        let question = editor
+            .retrieve("asvq", question_id)?
+            .ok_or_else(|| editor.die_event())?;
+
+        if answer["question"].int()? != question_id || question["survey"].int()? != survey_id {
+            return Err(format!(
+                "Answer {answer_id} does not match question {question_id} of survey {survey_id}"
+            )
+            .into());
+        }
+
+        let resp = eg::hash! {
+            response_group_id: group_id,
+            usr: user_id,
+            survey: survey_id,
+            question: question_id,
+            answer: answer_id,
+            answer_date: now.as_str(),
+            effective_date: now.as_str(),
+        };
+
+        created.push(editor.create(EgValue::create("asvr", resp)?)?);
+    }
+
+    Ok(created)
+}
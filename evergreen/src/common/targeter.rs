@@ -1,5 +1,6 @@
 use crate as eg;
 use eg::common::holds;
+use eg::common::org;
 use eg::common::settings::Settings;
 use eg::common::trigger;
 use eg::constants as C;
@@ -12,11 +13,49 @@ use std::fmt;
 
 const PRECACHE_ORG_SETTINGS: &[&str] = &[
     "circ.pickup_hold_stalling.hard",
+    "circ.pickup_hold_stalling.soft",
     "circ.holds.max_org_unit_target_loops",
     "circ.holds.org_unit_target_weight",
     "circ.holds.recall_threshold",
 ];
 
+/// Proximity tier for copies at the pickup library while a hold is
+/// within its soft stall interval.  Sorts ahead of all real proximities.
+const SOFT_STALL_PROXIMITY: i64 = i64::MIN;
+
+/// Returns the proximity used to weight a copy for targeting.
+///
+/// While inside the soft stall interval, copies whose circ lib is the
+/// pickup library are moved to a dedicated tier that sorts ahead of all
+/// other copies, regardless of any proximity adjustments.  Unlike hard
+/// stalling, copies at other libraries remain eligible when no pickup
+/// library copy can be targeted.
+///
+/// ```
+/// use evergreen::common::targeter::weighted_proximity;
+///
+/// // Outside soft stalling, proximity is unchanged.
+/// assert_eq!(weighted_proximity(3, 4, 4, false), 3);
+///
+/// // Inside soft stalling, pickup lib copies jump the line.
+/// assert!(weighted_proximity(3, 4, 4, true) < weighted_proximity(-5, 2, 4, true));
+///
+/// // Other copies retain their proximity.
+/// assert_eq!(weighted_proximity(2, 2, 4, true), 2);
+/// ```
+pub fn weighted_proximity(
+    proximity: i64,
+    circ_lib: i64,
+    pickup_lib: i64,
+    inside_soft_stall: bool,
+) -> i64 {
+    if inside_soft_stall && circ_lib == pickup_lib {
+        SOFT_STALL_PROXIMITY
+    } else {
+        proximity
+    }
+}
+
 /// Slimmed down copy.
 #[derive(Debug)]
 pub struct PotentialCopy {
@@ -123,6 +162,168 @@ impl HoldTargetContext {
     }
 }
 
+/// Criteria for selecting open holds to retarget, e.g. after a hold
+/// policy change affecting a pickup library or copy location group.
+///
+/// Only holds which are not captured, fulfilled, canceled, or frozen
+/// are considered.  Holds must match every provided criterion.
+///
+/// ```
+/// use evergreen::common::targeter::RetargetFilter;
+///
+/// let filter = RetargetFilter::from_value(&evergreen::hash! {
+///     "pickup_lib": 4,
+///     "hold_type": ["T", "V"],
+/// }).unwrap();
+///
+/// assert_eq!(filter.pickup_lib, Some(4));
+/// assert_eq!(filter.hold_types, vec!["T", "V"]);
+/// assert!(!filter.is_empty());
+///
+/// let filter = RetargetFilter::from_value(&evergreen::hash! {}).unwrap();
+/// assert!(filter.is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RetargetFilter {
+    pub pickup_lib: Option<i64>,
+    /// Also match holds picked up at descendants of the pickup library.
+    pub pickup_lib_descendants: bool,
+    pub request_lib: Option<i64>,
+    pub hold_types: Vec<String>,
+    /// Match holds whose targeted copy lives in one of these locations.
+    pub copy_locations: Vec<i64>,
+    /// Match holds whose targeted copy lives in a location within
+    /// this copy location group.
+    pub copy_location_group: Option<i64>,
+    /// When set, only match holds picked up at one of these org units,
+    /// e.g. the org units where the caller may update holds.  Not read
+    /// from the filter hash and not a criterion for is_empty().
+    pub pickup_lib_scope: Option<Vec<i64>>,
+}
+
+impl RetargetFilter {
+    /// Build a filter from a hash of criteria.
+    ///
+    /// Keys are pickup_lib, pickup_lib_descendants, request_lib,
+    /// hold_type (a string or a list), copy_location (a number or a
+    /// list), and copy_location_group.
+    pub fn from_value(v: &EgValue) -> EgResult<RetargetFilter> {
+        let hold_types = if v["hold_type"].is_array() {
+            v["hold_type"]
+                .members()
+                .map(|t| t.str().map(|s| s.to_string()))
+                .collect::<EgResult<Vec<String>>>()?
+        } else {
+            v["hold_type"]
+                .as_str()
+                .map(|s| vec![s.to_string()])
+                .unwrap_or_default()
+        };
+
+        let copy_locations = if v["copy_location"].is_array() {
+            v["copy_location"]
+                .members()
+                .map(|l| l.int())
+                .collect::<EgResult<Vec<i64>>>()?
+        } else {
+            v["copy_location"].as_int().into_iter().collect()
+        };
+
+        Ok(RetargetFilter {
+            pickup_lib: v["pickup_lib"].as_int(),
+            pickup_lib_descendants: v["pickup_lib_descendants"].boolish(),
+            request_lib: v["request_lib"].as_int(),
+            hold_types,
+            copy_locations,
+            copy_location_group: v["copy_location_group"].as_int(),
+            pickup_lib_scope: None,
+        })
+    }
+
+    /// True if no criteria are set.
+    pub fn is_empty(&self) -> bool {
+        self.pickup_lib.is_none()
+            && self.request_lib.is_none()
+            && self.hold_types.is_empty()
+            && self.copy_locations.is_empty()
+            && self.copy_location_group.is_none()
+    }
+
+    /// IDs of open holds matching the filter, oldest request first.
+    pub fn find_holds(&self, editor: &mut Editor) -> EgResult<Vec<i64>> {
+        if self.is_empty() {
+            return Err("Retarget filter requires at least one criterion".into());
+        }
+
+        let mut query = eg::hash! {
+            "select": {"ahr": ["id"]},
+            "from": "ahr",
+            "where": {
+                "capture_time": eg::NULL,
+                "fulfillment_time": eg::NULL,
+                "cancel_time": eg::NULL,
+                "frozen": "f"
+            },
+            "order_by": [{"class": "ahr", "field": "request_time"}]
+        };
+
+        if let Some(org_id) = self.pickup_lib {
+            query["where"]["pickup_lib"] = if self.pickup_lib_descendants {
+                EgValue::from(org::descendants(editor, org_id)?)
+            } else {
+                EgValue::from(org_id)
+            };
+        }
+
+        if let Some(scope) = self.pickup_lib_scope.as_ref() {
+            if scope.is_empty() {
+                return Ok(Vec::new());
+            }
+            query["where"]["-and"] = EgValue::from(vec![eg::hash! {"pickup_lib": scope.clone()}]);
+        }
+
+        if let Some(org_id) = self.request_lib {
+            query["where"]["request_lib"] = EgValue::from(org_id);
+        }
+
+        if !self.hold_types.is_empty() {
+            query["where"]["hold_type"] = EgValue::from(self.hold_types.clone());
+        }
+
+        let mut locations = self.copy_locations.clone();
+
+        if let Some(group_id) = self.copy_location_group {
+            for map in editor.search("acplgm", eg::hash! {"lgroup": group_id})? {
+                let loc_id = map["location"].int()?;
+                if !locations.contains(&loc_id) {
+                    locations.push(loc_id);
+                }
+            }
+
+            if locations.is_empty() {
+                // An empty group matches no holds.
+                return Ok(Vec::new());
+            }
+        }
+
+        if !locations.is_empty() {
+            query["where"]["current_copy"] = eg::hash! {
+                "in": {
+                    "select": {"acp": ["id"]},
+                    "from": "acp",
+                    "where": {"location": locations}
+                }
+            };
+        }
+
+        let holds = editor.json_query(query)?;
+
+        log::info!("Retarget filter {self:?} matched {} holds", holds.len());
+
+        holds.iter().map(|h| h["id"].int()).collect()
+    }
+}
+
 /// Targets a batch of holds.
 pub struct HoldTargeter<'a> {
     editor: &'a mut Editor,
@@ -1237,9 +1438,28 @@ impl<'a> HoldTargeter<'a> {
     }
 
     fn inside_hard_stall_interval(&mut self, context: &mut HoldTargetContext) -> EgResult<bool> {
-        let interval = self
-            .settings
-            .get_value_at_org("circ.pickup_hold_stalling.hard", context.pickup_lib)?;
+        self.inside_stall_interval(context, "hard")
+    }
+
+    /// Soft stalling prefers copies at the pickup library during the
+    /// stall interval without preventing other copies from being
+    /// targeted.  See [`weighted_proximity()`].
+    fn inside_soft_stall_interval(&mut self, context: &mut HoldTargetContext) -> EgResult<bool> {
+        self.inside_stall_interval(context, "soft")
+    }
+
+    /// True if the hold's request time plus the configured pickup lib
+    /// stalling interval of the given type ("hard" or "soft") is
+    /// still in the future.
+    fn inside_stall_interval(
+        &mut self,
+        context: &mut HoldTargetContext,
+        stall_type: &str,
+    ) -> EgResult<bool> {
+        let interval = self.settings.get_value_at_org(
+            &format!("circ.pickup_hold_stalling.{stall_type}"),
+            context.pickup_lib,
+        )?;
 
         let interval = match interval.as_str() {
             Some(s) => s,
@@ -1250,13 +1470,13 @@ impl<'a> HoldTargeter<'a> {
         let req_time = context.hold["request_time"].as_str().unwrap();
         let req_time = date::parse_datetime(req_time)?;
 
-        let hard_stall_time = date::add_interval(req_time, interval)?;
+        let stall_time = date::add_interval(req_time, interval)?;
 
-        log::info!("{self} hard stall deadline is/was {hard_stall_time}");
+        log::info!("{self} {stall_type} stall deadline is/was {stall_time}");
 
-        let inside = hard_stall_time > date::now();
+        let inside = stall_time > date::now();
 
-        log::info!("{self} still within hard stall interval? {inside}");
+        log::info!("{self} still within {stall_type} stall interval? {inside}");
 
         Ok(inside)
     }
@@ -1387,6 +1607,8 @@ impl<'a> HoldTargeter<'a> {
 
         let copy_maps = self.editor().json_query(query)?;
 
+        let inside_soft_stall = self.inside_soft_stall_interval(context)?;
+
         let mut flat_map: HashMap<i64, i64> = HashMap::new();
 
         for map in copy_maps.iter() {
@@ -1407,6 +1629,9 @@ impl<'a> HoldTargeter<'a> {
 
             copy.proximity = prox;
 
+            let prox =
+                weighted_proximity(prox, copy.circ_lib, context.pickup_lib, inside_soft_stall);
+
             weighted.entry(prox).or_default();
 
             let weight = self
@@ -0,0 +1,283 @@
+//! Progress reporting for long-running API calls.
+//!
+//! Batch operations (imports, batch edits, transfers, exports) register
+//! a task and record their progress as they go.  Task state lives in
+//! the global cache so any client may poll it, e.g. via the
+//! open-ils.actor.task.retrieve API, or subscribe to a stream of
+//! updates via open-ils.actor.task.watch, regardless of which worker
+//! is running the task.
+//!
+//! Running tasks expire if they are not updated within their TTL.
+//! Once finished, the final state, including any result, is retained
+//! for the result TTL.
+use crate as eg;
+use eg::date;
+use eg::osrf::cache::Cache;
+use eg::util;
+use eg::{EgResult, EgValue};
+use md5;
+
+const CACHE_PREFIX: &str = "rs.task";
+
+/// How long a running task may go without an update before it
+/// expires from the cache.
+pub const DEFAULT_TASK_TTL: u32 = 3600;
+
+/// How long to retain finished tasks and their results.
+pub const DEFAULT_RESULT_TTL: u32 = 3600;
+
+/// Cache key for a task.
+///
+/// ```
+/// use evergreen::common::task;
+///
+/// assert_eq!(task::cache_key("abc"), "rs.task.abc");
+/// ```
+pub fn cache_key(task_id: &str) -> String {
+    format!("{CACHE_PREFIX}.{task_id}")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TaskStatus {
+    Running,
+    Complete,
+    Failed,
+}
+
+impl From<TaskStatus> for &'static str {
+    fn from(s: TaskStatus) -> &'static str {
+        match s {
+            TaskStatus::Running => "running",
+            TaskStatus::Complete => "complete",
+            TaskStatus::Failed => "failed",
+        }
+    }
+}
+
+impl TryFrom<&str> for TaskStatus {
+    type Error = eg::EgError;
+    fn try_from(s: &str) -> EgResult<TaskStatus> {
+        match s {
+            "running" => Ok(TaskStatus::Running),
+            "complete" => Ok(TaskStatus::Complete),
+            "failed" => Ok(TaskStatus::Failed),
+            _ => Err(format!("Invalid task status: {s}").into()),
+        }
+    }
+}
+
+/// A long-running task and its progress.
+///
+/// ```
+/// use evergreen::common::task::{Task, TaskStatus};
+///
+/// let mut task = Task::new(1, "open-ils.cat.asset.copy.transfer");
+/// task.set_total(4);
+///
+/// assert_eq!(task.status(), TaskStatus::Running);
+/// assert_eq!(task.to_eg_value()["total"].int().unwrap(), 4);
+/// assert_eq!(task.to_eg_value()["done"].int().unwrap(), 0);
+///
+/// let task2 = Task::from_eg_value(&task.to_eg_value()).unwrap();
+/// assert_eq!(task2.id(), task.id());
+/// assert_eq!(task2.owner(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Task {
+    id: String,
+    /// ID of the user who started the task.
+    owner: i64,
+    /// Describes the task, typically the API name.
+    label: String,
+    status: TaskStatus,
+    done: i64,
+    total: Option<i64>,
+    message: Option<String>,
+    result: EgValue,
+    started: f64,
+    updated: f64,
+    ttl: u32,
+    result_ttl: u32,
+}
+
+impl Task {
+    /// Create a task without registering it.
+    ///
+    /// See [`Task::start`].
+    pub fn new(owner: i64, label: &str) -> Task {
+        let now = date::epoch_secs();
+
+        Task {
+            id: format!("{:x}", md5::compute(util::random_number(20))),
+            owner,
+            label: label.to_string(),
+            status: TaskStatus::Running,
+            done: 0,
+            total: None,
+            message: None,
+            result: EgValue::Null,
+            started: now,
+            updated: now,
+            ttl: DEFAULT_TASK_TTL,
+            result_ttl: DEFAULT_RESULT_TTL,
+        }
+    }
+
+    /// Create a task and register it in the cache.
+    pub fn start(owner: i64, label: &str, total: Option<i64>) -> EgResult<Task> {
+        let mut task = Task::new(owner, label);
+        task.total = total;
+        task.store()?;
+
+        log::info!("Task {} started by user {owner}: {label}", task.id);
+
+        Ok(task)
+    }
+
+    /// Load a task from the cache.
+    ///
+    /// Returns None if no such task exists or it has expired.
+    pub fn retrieve(task_id: &str) -> EgResult<Option<Task>> {
+        match Cache::get_global(&cache_key(task_id))? {
+            Some(v) => Ok(Some(Task::from_eg_value(&v)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn owner(&self) -> i64 {
+        self.owner
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn status(&self) -> TaskStatus {
+        self.status
+    }
+
+    pub fn done(&self) -> i64 {
+        self.done
+    }
+
+    pub fn total(&self) -> Option<i64> {
+        self.total
+    }
+
+    pub fn result(&self) -> &EgValue {
+        &self.result
+    }
+
+    /// Epoch seconds of the most recent update.
+    pub fn updated(&self) -> f64 {
+        self.updated
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.status != TaskStatus::Running
+    }
+
+    pub fn set_total(&mut self, total: i64) {
+        self.total = Some(total);
+    }
+
+    /// How long a running task may go without an update.
+    pub fn set_ttl(&mut self, ttl: u32) {
+        self.ttl = ttl;
+    }
+
+    /// How long to retain the task once finished.
+    pub fn set_result_ttl(&mut self, ttl: u32) {
+        self.result_ttl = ttl;
+    }
+
+    /// Record the number of units of work completed so far.
+    pub fn progress(&mut self, done: i64, message: Option<&str>) -> EgResult<()> {
+        self.done = done;
+        if let Some(m) = message {
+            self.message = Some(m.to_string());
+        }
+        self.store()
+    }
+
+    /// Record the completion of one more unit of work.
+    pub fn increment(&mut self, message: Option<&str>) -> EgResult<()> {
+        self.progress(self.done + 1, message)
+    }
+
+    /// Mark the task as successfully completed.
+    pub fn complete(&mut self, result: EgValue) -> EgResult<()> {
+        self.status = TaskStatus::Complete;
+        self.result = result;
+
+        log::info!("Task {} complete", self.id);
+
+        self.store()
+    }
+
+    /// Mark the task as failed.
+    ///
+    /// The result will typically be an event or error message.
+    pub fn fail(&mut self, result: EgValue) -> EgResult<()> {
+        self.status = TaskStatus::Failed;
+        self.result = result;
+
+        log::warn!("Task {} failed: {}", self.id, self.result.dump());
+
+        self.store()
+    }
+
+    fn store(&mut self) -> EgResult<()> {
+        self.updated = date::epoch_secs();
+
+        let ttl = if self.is_finished() {
+            self.result_ttl
+        } else {
+            self.ttl
+        };
+
+        Cache::set_global_for(&cache_key(&self.id), self.to_eg_value(), ttl)
+    }
+
+    pub fn to_eg_value(&self) -> EgValue {
+        let status: &str = self.status.into();
+
+        eg::hash! {
+            id: self.id.as_str(),
+            owner: self.owner,
+            label: self.label.as_str(),
+            status: status,
+            done: self.done,
+            total: self.total,
+            message: self.message.as_deref(),
+            result: self.result.clone(),
+            started: self.started,
+            updated: self.updated,
+            ttl: self.ttl,
+            result_ttl: self.result_ttl,
+        }
+    }
+
+    pub fn from_eg_value(v: &EgValue) -> EgResult<Task> {
+        Ok(Task {
+            id: v["id"].string()?,
+            owner: v["owner"].int()?,
+            label: v["label"].string()?,
+            status: TaskStatus::try_from(v["status"].str()?)?,
+            done: v["done"].int()?,
+            total: v["total"].as_int(),
+            message: v["message"].to_string(),
+            result: v["result"].clone(),
+            started: v["started"].float()?,
+            updated: v["updated"].float()?,
+            ttl: v["ttl"].as_int().unwrap_or(DEFAULT_TASK_TTL as i64) as u32,
+            result_ttl: v["result_ttl"]
+                .as_int()
+                .unwrap_or(DEFAULT_RESULT_TTL as i64) as u32,
+        })
+    }
+}
@@ -0,0 +1,259 @@
+//! Desk payment reconciliation (till) reports.
+//!
+//! Summarizes desk payments (cash, check, credit/debit card) by
+//! workstation (cash drawer), accepting staff member, and day, and
+//! tracks till closeouts.
+//!
+//! Closeouts are stored as "mtc" (money.till_closeout) objects with the
+//! fields: id, workstation, closed_by, close_time, period_start,
+//! period_end, cash_total, check_total, credit_card_total,
+//! debit_card_total, note.
+//!
+//! The closeout table, IDL class, and CLOSE_TILL permission are not
+//! part of stock Evergreen.  They ship in
+//! evergreen/schema/till-closeout.{sql,idl.xml}.
+use crate as eg;
+use eg::date;
+use eg::editor::Editor;
+use eg::result::EgResult;
+use eg::util;
+use eg::EgValue;
+
+/// IDL class of till closeout records.
+pub const CLOSEOUT_CLASS: &str = "mtc";
+
+/// Desk payment types and the closeout fields where their totals live.
+const PAYMENT_TYPES: &[(&str, &str)] = &[
+    ("cash_payment", "cash_total"),
+    ("check_payment", "check_total"),
+    ("credit_card_payment", "credit_card_total"),
+    ("debit_card_payment", "debit_card_total"),
+];
+
+/// Column headers for CSV exports of payment summaries.
+const CSV_HEADERS: &[&str] = &[
+    "payment_date",
+    "workstation",
+    "accepting_usr",
+    "payment_type",
+    "payment_count",
+    "total",
+];
+
+/// Summarize non-voided desk payments collected between the provided
+/// dates, grouped by day, workstation, staff member, and payment type.
+///
+/// Supported filters:
+///
+/// * start_date / end_date - Required ISO dates.  The period excludes
+///   its start and includes its end, so consecutive periods sharing a
+///   boundary never count a payment twice.
+/// * workstation - Workstation (cash drawer) ID or list of IDs.
+/// * accepting_usr - Staff user ID or list of IDs.
+/// * org_unit - Limit to workstations owned by this org unit or list
+///   of org units.
+///
+/// Each summary row contains: payment_date, workstation, accepting_usr,
+/// payment_type, payment_count, total.
+pub fn summarize_payments(editor: &mut Editor, filters: &EgValue) -> EgResult<Vec<EgValue>> {
+    let start_date = date::parse_datetime(filters["start_date"].str()?)?;
+    let end_date = date::parse_datetime(filters["end_date"].str()?)?;
+
+    let mut query = eg::hash! {
+        "select": {
+            "mdp": [
+                {"column": "payment_ts", "transform": "date", "alias": "payment_date"},
+                {"column": "cash_drawer", "alias": "workstation"},
+                "accepting_usr",
+                "payment_type",
+                {"column": "id", "transform": "count", "aggregate": true, "alias": "payment_count"},
+                {"column": "amount", "transform": "sum", "aggregate": true, "alias": "total"},
+            ]
+        },
+        "from": "mdp",
+    };
+
+    let mut filters_and = vec![
+        eg::hash! {"payment_ts": {">": date::to_iso(&start_date)}},
+        eg::hash! {"payment_ts": {"<=": date::to_iso(&end_date)}},
+    ];
+
+    if !filters["workstation"].is_null() {
+        filters_and.push(eg::hash! {"cash_drawer": filters["workstation"].clone()});
+    }
+
+    if !filters["accepting_usr"].is_null() {
+        filters_and.push(eg::hash! {"accepting_usr": filters["accepting_usr"].clone()});
+    }
+
+    if !filters["org_unit"].is_null() {
+        filters_and.push(eg::hash! {
+            "cash_drawer": {
+                "in": {
+                    "select": {"aws": ["id"]},
+                    "from": "aws",
+                    "where": {"owning_lib": filters["org_unit"].clone()},
+                }
+            }
+        });
+    }
+
+    query["where"] = eg::hash! {
+        "voided": "f",
+        "-and": filters_and,
+    };
+
+    let mut rows = editor.json_query(query)?;
+
+    // Sort in Rust for predictable ordering regardless of grouping.
+    rows.sort_by_key(|r| {
+        (
+            r["payment_date"].to_string(),
+            r["workstation"].as_int(),
+            r["accepting_usr"].as_int(),
+            r["payment_type"].to_string(),
+        )
+    });
+
+    Ok(rows)
+}
+
+/// Total amount collected per payment type across a set of summary rows.
+///
+/// Returns a hash of payment_type => total.
+///
+/// ```
+/// use evergreen::common::till;
+///
+/// let rows = vec![
+///     evergreen::hash! {"payment_type": "cash_payment", "total": "10.10"},
+///     evergreen::hash! {"payment_type": "cash_payment", "total": "0.20"},
+///     evergreen::hash! {"payment_type": "check_payment", "total": 5},
+/// ];
+///
+/// let totals = till::totals_by_type(&rows);
+/// assert_eq!(totals["cash_payment"].as_float(), Some(10.3));
+/// assert_eq!(totals["check_payment"].as_float(), Some(5.0));
+/// assert_eq!(totals["credit_card_payment"].as_float(), Some(0.0));
+/// ```
+pub fn totals_by_type(rows: &[EgValue]) -> EgValue {
+    let mut totals = eg::hash! {};
+
+    for (ptype, _) in PAYMENT_TYPES {
+        totals[*ptype] = EgValue::from(0.0);
+    }
+
+    for row in rows {
+        let Some(ptype) = row["payment_type"].as_str() else {
+            continue;
+        };
+
+        let amount = row["total"].as_float().unwrap_or(0.0);
+        let current = totals[ptype].as_float().unwrap_or(0.0);
+
+        totals[ptype] = EgValue::from(util::fpsum(current, amount));
+    }
+
+    totals
+}
+
+/// Render payment summary rows as CSV, including a header line.
+///
+/// ```
+/// use evergreen::common::till;
+///
+/// let rows = vec![evergreen::hash! {
+///     "payment_date": "2024-03-01",
+///     "workstation": 12,
+///     "accepting_usr": 5,
+///     "payment_type": "cash_payment",
+///     "payment_count": 2,
+///     "total": "10.50",
+/// }];
+///
+/// let csv = till::summary_to_csv(&rows);
+/// let mut lines = csv.lines();
+/// assert_eq!(lines.next(), Some("payment_date,workstation,accepting_usr,payment_type,payment_count,total"));
+/// assert_eq!(lines.next(), Some("2024-03-01,12,5,cash_payment,2,10.50"));
+/// ```
+pub fn summary_to_csv(rows: &[EgValue]) -> String {
+    let mut csv = CSV_HEADERS.join(",");
+    csv += "\n";
+
+    for row in rows {
+        let line: Vec<String> = CSV_HEADERS
+            .iter()
+            .map(|h| csv_escape(&row[*h].to_string().unwrap_or_default()))
+            .collect();
+
+        csv += &line.join(",");
+        csv += "\n";
+    }
+
+    csv
+}
+
+/// Quote CSV values containing commas, quotes, or line breaks.
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Returns the most recent closeout for a workstation.
+pub fn last_closeout(editor: &mut Editor, workstation: i64) -> EgResult<Option<EgValue>> {
+    let query = eg::hash! {"workstation": workstation};
+    let mut ops = eg::hash! {"limit": 1};
+    ops["order_by"][CLOSEOUT_CLASS] = EgValue::from("close_time DESC");
+
+    Ok(editor.search_with_ops(CLOSEOUT_CLASS, query, ops)?.pop())
+}
+
+/// Close out the till for a workstation.
+///
+/// The closeout period starts at the end of the previous closeout
+/// (or the provided start date when there is no previous closeout)
+/// and ends now.  Totals per payment type are recorded on the closeout.
+///
+/// The editor must be in a transaction.
+pub fn close_till(
+    editor: &mut Editor,
+    workstation: i64,
+    default_start: &str,
+    note: Option<&str>,
+) -> EgResult<EgValue> {
+    let period_start = match last_closeout(editor, workstation)? {
+        Some(c) => c["period_end"].string()?,
+        None => date::to_iso(&date::parse_datetime(default_start)?),
+    };
+
+    let period_end = date::to_iso(&date::now());
+
+    let filters = eg::hash! {
+        "start_date": period_start.as_str(),
+        "end_date": period_end.as_str(),
+        "workstation": workstation,
+    };
+
+    let rows = summarize_payments(editor, &filters)?;
+    let totals = totals_by_type(&rows);
+
+    let mut closeout = eg::hash! {
+        "workstation": workstation,
+        "closed_by": editor.requestor_id()?,
+        "close_time": period_end.as_str(),
+        "period_start": period_start.as_str(),
+        "period_end": period_end.as_str(),
+        "note": note,
+    };
+
+    for (ptype, field) in PAYMENT_TYPES {
+        closeout[*field] = totals[*ptype].clone();
+    }
+
+    log::info!("Closing till for workstation {workstation} with totals {totals}");
+
+    editor.create(EgValue::create(CLOSEOUT_CLASS, closeout)?)
+}
@@ -1,7 +1,8 @@
 //! Base module for A/T Reactors
 use crate as eg;
 use eg::common::auth;
-use eg::common::{trigger, trigger::Event, trigger::Processor};
+use eg::common::autorenew::AutoRenewal;
+use eg::common::{trigger::Event, trigger::Processor};
 use eg::EgEvent;
 use eg::EgResult;
 use eg::EgValue;
@@ -77,70 +78,17 @@ impl Processor<'_> {
 
         log::info!("{self} autorenewal returned {eg_evt}");
 
-        let source_circ = event.target();
         let new_circ = &eg_evt.payload()["circ"];
-
-        let mut new_due_date = "";
-        let mut old_due_date = "";
-        let mut fail_reason = "";
-        let mut total_remaining;
-        let mut auto_remaining;
-
-        let success = eg_evt.is_success();
-        if success && new_circ.is_object() {
-            new_due_date = new_circ["due_date"].as_str().unwrap(); // required
-            total_remaining = new_circ["renewal_remaining"].int()?;
-
-            // nullable / maybe a string
-            auto_remaining = new_circ["auto_renewal_remaining"]
-                .as_int()
-                .unwrap_or_default();
+        let new_circ = if new_circ.is_object() {
+            Some(new_circ)
         } else {
-            old_due_date = source_circ["due_date"].as_str().unwrap(); // required
-            total_remaining = source_circ["renewal_remaining"].int()?;
-            fail_reason = eg_evt.desc().unwrap_or("");
-
-            // nullable / maybe a string
-            auto_remaining = source_circ["auto_renewal_remaining"]
-                .as_int()
-                .unwrap_or_default();
-        }
-
-        if total_remaining < 0 {
-            total_remaining = 0;
-        }
-        if auto_remaining < 0 {
-            auto_remaining = 0;
-        }
-        if auto_remaining < total_remaining {
-            auto_remaining = total_remaining;
-        }
-
-        let user_data = eg::hash! {
-            "copy": copy_id,
-            "is_renewed": success,
-            "reason": fail_reason,
-            "new_due_date": new_due_date,
-            "old_due_date": old_due_date,
-            "textcode": eg_evt.textcode(),
-            "total_renewal_remaining": total_remaining,
-            "auto_renewal_remaining": auto_remaining,
+            None
         };
 
-        let target = &event.target()["circ_lib"];
-        let circ_lib = target.as_int().unwrap_or(target.id()?);
-
         // Create the event from the source circ instead of the new
         // circ, since the renewal may have failed.  Fire and do not
         // forget so we don't flood A/T.
-        trigger::create_events_for_object(
-            self.editor,
-            "autorenewal",
-            event.target(),
-            circ_lib,
-            None,
-            Some(&user_data),
-            false,
-        )
+        AutoRenewal::new(event.target(), &eg_evt, new_circ)?
+            .create_notice_events(self.editor, event.target())
     }
 }
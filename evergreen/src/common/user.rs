@@ -1,5 +1,6 @@
 //! Shared, user-focused utility functions
 use crate as eg;
+use eg::common::noncat;
 use eg::editor::Editor;
 use eg::result::EgResult;
 use eg::EgValue;
@@ -97,25 +98,31 @@ pub fn has_work_perm_at(e: &mut Editor, user_id: i64, perm: &str) -> EgResult<Ve
 }
 
 /// Returns counts of items out, overdue, etc. for a user.
+///
+/// Open non-cataloged circulations are counted separately as "noncat".
 pub fn open_checkout_counts(e: &mut Editor, user_id: i64) -> EgResult<EgValue> {
-    match e.retrieve("ocirccount", user_id)? {
+    let mut counts = match e.retrieve("ocirccount", user_id)? {
         Some(mut c) => {
             c["total_out"] = EgValue::from(c["out"].int()? + c["overdue"].int()?);
             c.unbless();
-            Ok(c)
+            c
         }
         None => {
             // There will be no response if the user has no open circs.
-            Ok(eg::hash! {
+            eg::hash! {
                 out: 0,
                 overdue: 0,
                 lost: 0,
                 claims_returned: 0,
                 long_overdue: 0,
                 total_count: 0,
-            })
+            }
         }
-    }
+    };
+
+    counts["noncat"] = EgValue::from(noncat::open_circ_count(e, user_id)?);
+
+    Ok(counts)
 }
 
 /// Returns a summary of fines owed by a user
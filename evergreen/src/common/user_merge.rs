@@ -0,0 +1,231 @@
+//! Merge one patron account into another.
+//!
+//! The merge itself is performed by the actor.usr_merge() database
+//! function, which moves everything linked to the source patron to
+//! the lead patron and then deletes the source patron.
+//!
+//! The returned [`MergeReport`] lists the source patron's
+//! circulations, bills, holds, notes, penalties, stat cat values, and
+//! cards as they were before the merge, which covers what staff most
+//! often need to manually reverse a merge made in error.
+use crate as eg;
+use eg::common::audit::{self, AuditEntry};
+use eg::Editor;
+use eg::EgError;
+use eg::EgResult;
+use eg::EgValue;
+
+/// Classes whose rows are listed in the merge report, along with the
+/// name of the field linking each row to its patron.
+const REPORTED_CLASSES: &[(&str, &str)] = &[
+    ("circ", "usr"),
+    ("mg", "usr"),
+    ("ahr", "usr"),
+    ("aun", "usr"),
+    ("ausp", "usr"),
+];
+
+/// What to do with the source patron's library cards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CardDisposition {
+    /// Move the cards to the lead patron as inactive cards, so their
+    /// barcodes still find the lead patron.
+    Move,
+    /// Delete the cards.
+    Delete,
+}
+
+impl TryFrom<&str> for CardDisposition {
+    type Error = EgError;
+    fn try_from(s: &str) -> EgResult<Self> {
+        match s {
+            "move" => Ok(Self::Move),
+            "delete" => Ok(Self::Delete),
+            _ => Err(format!("Invalid card disposition: {s}").into()),
+        }
+    }
+}
+
+/// Summary of a completed merge.
+#[derive(Debug, Clone)]
+pub struct MergeReport {
+    pub lead_id: i64,
+    pub source_id: i64,
+    pub card_disposition: CardDisposition,
+
+    /// IDL class and IDs of the reported rows moved to the lead patron.
+    pub moved: Vec<(String, Vec<i64>)>,
+
+    /// Source patron stat cat values dropped because the lead patron
+    /// already has a value for the same stat cat.
+    pub dropped_stat_cat_values: Vec<EgValue>,
+
+    /// The source patron's cards as they were before the merge.
+    pub cards: Vec<EgValue>,
+}
+
+impl MergeReport {
+    /// Unmerge report payload.
+    pub fn to_value(&self) -> EgValue {
+        let mut moved = eg::hash! {};
+        for (class, ids) in self.moved.iter() {
+            moved[class.as_str()] = EgValue::from(ids.clone());
+        }
+
+        let disposition = match self.card_disposition {
+            CardDisposition::Move => "move",
+            CardDisposition::Delete => "delete",
+        };
+
+        eg::hash! {
+            "lead": self.lead_id,
+            "source": self.source_id,
+            "card_disposition": disposition,
+            "moved": moved,
+            "dropped_stat_cat_values": self.dropped_stat_cat_values.clone(),
+            "cards": self.cards.clone(),
+        }
+    }
+}
+
+/// Merge the source patron into the lead patron.
+///
+/// Requires the MERGE_USERS permission at the home library of both
+/// patrons.  The merge is recorded in the staff audit log, when
+/// installed, with the unmerge report as the note.
+///
+/// The editor must be in a transaction.  Any error means nothing
+/// should be committed.
+pub fn merge(
+    editor: &mut Editor,
+    lead_id: i64,
+    source_id: i64,
+    card_disposition: CardDisposition,
+) -> EgResult<MergeReport> {
+    if lead_id == source_id {
+        return Err(format!("Cannot merge patron {lead_id} into itself").into());
+    }
+
+    let lead = load_patron(editor, lead_id)?;
+    let source = load_patron(editor, source_id)?;
+
+    for patron in [&lead, &source] {
+        if !editor.allowed_at("MERGE_USERS", patron["home_ou"].int()?)? {
+            return Err(editor.die_event());
+        }
+    }
+
+    log::info!("Merging patron {source_id} into {lead_id}");
+
+    let mut report = MergeReport {
+        lead_id,
+        source_id,
+        card_disposition,
+        moved: Vec::new(),
+        dropped_stat_cat_values: Vec::new(),
+        cards: Vec::new(),
+    };
+
+    // Collect the report before the merge moves everything.
+    for (class, field) in REPORTED_CLASSES {
+        let ids = linked_ids(editor, class, field, source_id)?;
+        if !ids.is_empty() {
+            report.moved.push((class.to_string(), ids));
+        }
+    }
+
+    report_stat_cat_values(editor, &mut report)?;
+    report_cards(editor, &mut report)?;
+
+    let (del_cards, deactivate_cards) = match card_disposition {
+        CardDisposition::Move => ("f", "t"),
+        CardDisposition::Delete => ("t", "f"),
+    };
+
+    // Addresses are kept and moved to the lead patron.
+    let query = eg::hash! {
+        from: ["actor.usr_merge", source_id, lead_id, "f", del_cards, deactivate_cards]
+    };
+
+    editor.json_query(query)?;
+
+    if audit::is_enabled() {
+        let mut entry = AuditEntry::new("patron.merge");
+        entry.set_target("au", lead_id);
+        entry.note = Some(report.to_value().dump());
+        audit::log_action(editor, &entry)?;
+    }
+
+    Ok(report)
+}
+
+/// Load a patron, which must exist and not be deleted.
+fn load_patron(editor: &mut Editor, user_id: i64) -> EgResult<EgValue> {
+    let user = editor
+        .retrieve("au", user_id)?
+        .ok_or_else(|| editor.die_event())?;
+
+    if user["deleted"].boolish() {
+        return Err(format!("Patron {user_id} is deleted").into());
+    }
+
+    Ok(user)
+}
+
+/// IDs of the rows of the class linked to the patron.
+fn linked_ids(editor: &mut Editor, class: &str, field: &str, user_id: i64) -> EgResult<Vec<i64>> {
+    let mut query = eg::hash! {};
+    query[field] = EgValue::from(user_id);
+
+    let mut ids = Vec::new();
+
+    for row in editor.search(class, query)? {
+        ids.push(row.id()?);
+    }
+
+    Ok(ids)
+}
+
+/// Report which stat cat values move to the lead patron.  Where both
+/// patrons have a value for the same stat cat, the lead patron's
+/// value wins and the source value is dropped.
+fn report_stat_cat_values(editor: &mut Editor, report: &mut MergeReport) -> EgResult<()> {
+    let mut moved = Vec::new();
+
+    for map in editor.search("actscecm", eg::hash! {target_usr: report.source_id})? {
+        let query = eg::hash! {
+            stat_cat: map["stat_cat"].int()?,
+            target_usr: report.lead_id,
+        };
+
+        if editor.search("actscecm", query)?.is_empty() {
+            moved.push(map.id()?);
+        } else {
+            report.dropped_stat_cat_values.push(map);
+        }
+    }
+
+    if !moved.is_empty() {
+        report.moved.push(("actscecm".to_string(), moved));
+    }
+
+    Ok(())
+}
+
+fn report_cards(editor: &mut Editor, report: &mut MergeReport) -> EgResult<()> {
+    let mut moved = Vec::new();
+
+    for card in editor.search("ac", eg::hash! {usr: report.source_id})? {
+        if report.card_disposition == CardDisposition::Move {
+            moved.push(card.id()?);
+        }
+
+        report.cards.push(card);
+    }
+
+    if !moved.is_empty() {
+        report.moved.push(("ac".to_string(), moved));
+    }
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 //! Evergreen API Response Events
 use crate as eg;
 use eg::date;
+use eg::i18n;
 use eg::EgValue;
 use std::fmt;
 
@@ -94,6 +95,9 @@ impl From<&EgEvent> for EgValue {
 
 impl EgEvent {
     /// Create a new event with the provided code.
+    ///
+    /// The description is taken from the message catalog for the
+    /// current thread locale.  See [`i18n::event_desc`].
     pub fn new(textcode: &str) -> Self {
         let servertime = date::to_iso(&date::now());
 
@@ -101,7 +105,7 @@ impl EgEvent {
             code: -1,
             textcode: textcode.to_string(),
             payload: EgValue::Null,
-            desc: None,
+            desc: i18n::event_desc(textcode),
             debug: None,
             note: None,
             org: None,
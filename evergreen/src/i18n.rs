@@ -0,0 +1,335 @@
+//! Translation of server-generated strings.
+//!
+//! Message catalogs are gettext-style .po files named by locale, e.g.
+//! fr-CA.po, read from the directory named by the EG_LOCALE_DIR
+//! environment variable (default /openils/var/data/locale).  Catalogs
+//! are loaded on first use.
+//!
+//! The msgid is the English text, so untranslated strings fall back to
+//! English.  The one exception is event descriptions (see
+//! [`event_desc`]), whose msgid is the event textcode.  Plural forms
+//! and message contexts are not supported.
+use crate::osrf::message;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::sync::{OnceLock, RwLock};
+
+const DEFAULT_LOCALE_DIR: &str = "/openils/var/data/locale";
+const LOCALE_DIR_ENV: &str = "EG_LOCALE_DIR";
+
+/// Locale => msgid => msgstr
+type Catalogs = HashMap<String, HashMap<String, String>>;
+
+static CATALOGS: OnceLock<RwLock<Catalogs>> = OnceLock::new();
+
+fn catalogs() -> &'static RwLock<Catalogs> {
+    CATALOGS.get_or_init(|| {
+        let dir = env::var(LOCALE_DIR_ENV).unwrap_or(DEFAULT_LOCALE_DIR.to_string());
+        RwLock::new(read_catalog_dir(&dir))
+    })
+}
+
+/// Read every .po file in a directory.  Unreadable files are logged
+/// and skipped.
+fn read_catalog_dir(dir: &str) -> Catalogs {
+    let mut catalogs = HashMap::new();
+
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(e) => {
+            log::debug!("No message catalogs loaded from {dir}: {e}");
+            return catalogs;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.extension().and_then(|e| e.to_str()) != Some("po") {
+            continue;
+        }
+
+        let Some(locale) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                log::info!("Loaded message catalog for locale {locale}");
+                catalogs.insert(normalize_locale(locale), parse_po(&text));
+            }
+            Err(e) => log::error!("Cannot read message catalog {path:?}: {e}"),
+        }
+    }
+
+    catalogs
+}
+
+/// Replace all catalogs with those found in the provided directory.
+pub fn load_catalogs(dir: &Path) {
+    let loaded = read_catalog_dir(&dir.to_string_lossy());
+
+    if let Ok(mut c) = catalogs().write() {
+        *c = loaded;
+    }
+}
+
+/// Add or replace the catalog for a single locale from .po file content.
+pub fn add_catalog(locale: &str, po_text: &str) {
+    if let Ok(mut c) = catalogs().write() {
+        c.insert(normalize_locale(locale), parse_po(po_text));
+    }
+}
+
+/// Use a consistent form for locale keys, e.g. "fr_ca" => "fr-CA".
+///
+/// ```
+/// use evergreen::i18n::normalize_locale;
+/// assert_eq!(normalize_locale("fr_ca"), "fr-CA");
+/// assert_eq!(normalize_locale("en-US.UTF-8"), "en-US");
+/// assert_eq!(normalize_locale("ES"), "es");
+/// ```
+pub fn normalize_locale(locale: &str) -> String {
+    let locale = locale.split('.').next().unwrap_or("");
+    let mut parts = locale.split(['-', '_']);
+
+    let lang = parts.next().unwrap_or("").to_lowercase();
+
+    match parts.next() {
+        Some(region) if !region.is_empty() => format!("{lang}-{}", region.to_uppercase()),
+        _ => lang,
+    }
+}
+
+/// Parse the msgid/msgstr pairs from .po file content.
+///
+/// Entries with an empty msgstr (untranslated) and fuzzy entries
+/// are skipped.
+///
+/// ```
+/// use evergreen::i18n::parse_po;
+///
+/// let catalog = parse_po(r#"
+/// # Translator comment
+/// msgid ""
+/// msgstr "Content-Type: text/plain; charset=UTF-8\n"
+///
+/// msgid "Item Is Currently Checked Out"
+/// msgstr "Le document est "
+/// "déjà prêté"
+///
+/// #, fuzzy
+/// msgid "blocked"
+/// msgstr "bloqué"
+///
+/// msgid "Untranslated"
+/// msgstr ""
+/// "#);
+///
+/// assert_eq!(catalog.len(), 1);
+/// assert_eq!(catalog["Item Is Currently Checked Out"], "Le document est déjà prêté");
+/// ```
+pub fn parse_po(text: &str) -> HashMap<String, String> {
+    let mut catalog = HashMap::new();
+
+    let mut msgid = String::new();
+    let mut msgstr = String::new();
+    let mut fuzzy = false;
+
+    // Flags (e.g. fuzzy) precede the msgid they apply to.
+    let mut next_is_fuzzy = false;
+
+    // Which value continuation lines apply to.
+    let mut in_msgstr = false;
+
+    for line in text.lines().map(|l| l.trim()) {
+        if let Some(comment) = line.strip_prefix('#') {
+            if comment.starts_with(',') && comment.contains("fuzzy") {
+                next_is_fuzzy = true;
+            }
+        } else if let Some(value) = line.strip_prefix("msgid ") {
+            if !msgid.is_empty() && !msgstr.is_empty() && !fuzzy {
+                catalog.insert(std::mem::take(&mut msgid), std::mem::take(&mut msgstr));
+            }
+
+            msgid = unquote(value);
+            msgstr.clear();
+            fuzzy = next_is_fuzzy;
+            next_is_fuzzy = false;
+            in_msgstr = false;
+        } else if let Some(value) = line.strip_prefix("msgstr ") {
+            msgstr = unquote(value);
+            in_msgstr = true;
+        } else if line.starts_with('"') {
+            if in_msgstr {
+                msgstr += &unquote(line);
+            } else {
+                msgid += &unquote(line);
+            }
+        }
+    }
+
+    if !msgid.is_empty() && !msgstr.is_empty() && !fuzzy {
+        catalog.insert(msgid, msgstr);
+    }
+
+    catalog
+}
+
+/// Remove surrounding quotes and process escape sequences.
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let value = value.strip_prefix('"').unwrap_or(value);
+    let value = value.strip_suffix('"').unwrap_or(value);
+
+    let mut result = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+
+    result
+}
+
+/// Returns the translation of msgid for the locale, if one exists.
+///
+/// Falls back from a regional locale to its base language,
+/// e.g. "fr-CA" => "fr".
+pub fn lookup(locale: &str, msgid: &str) -> Option<String> {
+    let catalogs = catalogs().read().ok()?;
+
+    let locale = normalize_locale(locale);
+
+    if let Some(s) = catalogs.get(&locale).and_then(|c| c.get(msgid)) {
+        return Some(s.to_string());
+    }
+
+    let lang = locale.split('-').next()?;
+
+    catalogs
+        .get(lang)
+        .and_then(|c| c.get(msgid))
+        .map(|s| s.to_string())
+}
+
+/// Description of an event for the locale of the current thread.
+///
+/// The English event descriptions live in Evergreen's ils_events.xml
+/// rather than in this crate, so event descriptions use the event
+/// textcode as the msgid and have no fallback.
+///
+/// ```
+/// use evergreen::i18n;
+/// use evergreen::osrf::message;
+///
+/// i18n::add_catalog("fr", "msgid \"PERM_FAILURE\"\nmsgstr \"Permission refusée\"");
+///
+/// message::set_thread_locale("fr-CA");
+/// assert_eq!(i18n::event_desc("PERM_FAILURE").as_deref(), Some("Permission refusée"));
+///
+/// message::set_thread_locale("de-DE");
+/// assert_eq!(i18n::event_desc("PERM_FAILURE"), None);
+/// ```
+pub fn event_desc(textcode: &str) -> Option<String> {
+    lookup(&message::thread_locale(), textcode)
+}
+
+/// Translate msgid for the provided locale, falling back to msgid.
+pub fn translate(locale: &str, msgid: &str) -> String {
+    lookup(locale, msgid).unwrap_or(msgid.to_string())
+}
+
+/// Translate msgid for the locale of the current thread, i.e. the
+/// locale of the API request being processed.
+///
+/// ```
+/// use evergreen::i18n;
+/// use evergreen::osrf::message;
+///
+/// i18n::add_catalog("es", "msgid \"blocked\"\nmsgstr \"bloqueado\"");
+///
+/// message::set_thread_locale("es-MX");
+/// assert_eq!(i18n::tr("blocked"), "bloqueado");
+///
+/// message::set_thread_locale("de-DE");
+/// assert_eq!(i18n::tr("blocked"), "blocked");
+/// ```
+pub fn tr(msgid: &str) -> String {
+    translate(&message::thread_locale(), msgid)
+}
+
+/// Translate msgid for the current thread locale then replace
+/// "{name}" placeholders with the provided values.
+///
+/// ```
+/// use evergreen::i18n;
+///
+/// let s = i18n::tr_args("Hold available at {library}", &[("library", "BR1")]);
+/// assert_eq!(s, "Hold available at BR1");
+/// ```
+pub fn tr_args(msgid: &str, args: &[(&str, &str)]) -> String {
+    let mut value = tr(msgid);
+
+    for (name, arg) in args {
+        value = value.replace(&format!("{{{name}}}"), arg);
+    }
+
+    value
+}
+
+/// Map a SIP2 language code (e.g. from the patron status request)
+/// to a locale.
+///
+/// Returns None for "000" (unknown) and unsupported codes.
+///
+/// ```
+/// use evergreen::i18n::sip_language_locale;
+/// assert_eq!(sip_language_locale("002"), Some("fr-FR"));
+/// assert_eq!(sip_language_locale("000"), None);
+/// ```
+pub fn sip_language_locale(code: &str) -> Option<&'static str> {
+    let locale = match code {
+        "001" => "en-US",
+        "002" => "fr-FR",
+        "003" => "de-DE",
+        "004" => "it-IT",
+        "005" => "nl-NL",
+        "006" => "sv-SE",
+        "007" => "fi-FI",
+        "008" => "es-ES",
+        "009" => "da-DK",
+        "010" => "pt-PT",
+        "011" => "fr-CA",
+        "012" => "nb-NO",
+        "013" => "he-IL",
+        "014" => "ja-JP",
+        "015" => "ru-RU",
+        "016" => "ar",
+        "017" => "pl-PL",
+        "018" => "el-GR",
+        "019" => "zh-CN",
+        "020" => "ko-KR",
+        "021" => "es-US",
+        "022" => "ta",
+        "023" => "ms",
+        "024" => "en-GB",
+        "025" => "is-IS",
+        "026" => "nl-BE",
+        "027" => "zh-TW",
+        _ => return None,
+    };
+
+    Some(locale)
+}
@@ -170,6 +170,8 @@ pub fn osrf_init(options: &InitOptions) -> EgResult<Client> {
 
     if !options.skip_host_settings {
         HostSettings::load(&client)?;
+    } else if HostSettings::has_files() {
+        HostSettings::load_files()?;
     }
 
     Ok(client)
@@ -20,6 +20,7 @@ pub mod date;
 pub mod db;
 pub mod editor;
 pub mod event;
+pub mod i18n;
 pub mod idl;
 pub mod idldb;
 pub mod init;
@@ -1,5 +1,12 @@
+use crate as eg;
+use eg::Editor;
+use eg::EgResult;
+use eg::EgValue;
 use icu_normalizer::DecomposingNormalizer;
+use marctk::callnumber::{self, CallNumberScheme};
+use marctk::standard_numbers::Isbn;
 use regex::Regex;
+use std::collections::HashMap;
 use std::sync::OnceLock;
 
 /// Store these globally to avoid repititive regex recompilation.
@@ -161,3 +168,313 @@ impl Normalizer {
         value.trim().to_lowercase()
     }
 }
+
+/// A named normalization step.
+///
+/// Receives the value to normalize and any parameters configured for
+/// the step, e.g. the search and replace strings for "replace".
+pub type NormalizerFn = fn(&str, &[String]) -> String;
+
+/// One step within a pipeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PipelineStep {
+    name: String,
+    params: Vec<String>,
+}
+
+impl PipelineStep {
+    pub fn new(name: &str, params: &[&str]) -> Self {
+        PipelineStep {
+            name: name.to_string(),
+            params: params.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn params(&self) -> &Vec<String> {
+        &self.params
+    }
+}
+
+/// Registry of named normalizers and named pipelines composed from them.
+///
+/// Built-in normalizers use the same names as the "func" values in
+/// config.index_normalizer so pipelines can be built directly from the
+/// database configuration:
+///
+/// * naco_normalize
+/// * nfkd
+/// * lowercase / uppercase
+/// * btrim
+/// * strip_punctuation
+/// * remove_paren_substring
+/// * replace (params: search, replacement)
+/// * left_trunc / right_trunc (params: length)
+/// * pad_numeric (params: width, default 10)
+/// * translate_isbn1013
+/// * call_number_key (params: "lc", "dewey", or none to detect)
+///
+/// # Examples
+///
+/// ```
+/// use evergreen::norm::{NormalizerRegistry, PipelineStep};
+///
+/// let mut registry = NormalizerRegistry::new();
+///
+/// registry.define_pipeline(
+///     "title",
+///     vec![
+///         PipelineStep::new("remove_paren_substring", &[]),
+///         PipelineStep::new("naco_normalize", &[]),
+///     ],
+/// ).unwrap();
+///
+/// assert_eq!(registry.apply("title", "Café (Paris edition)").unwrap(), "cafe");
+///
+/// registry.define_pipeline("vol", vec![PipelineStep::new("pad_numeric", &["4"])]).unwrap();
+/// assert_eq!(registry.apply("vol", "v. 12 no. 3").unwrap(), "v. 0012 no. 0003");
+///
+/// registry.define_pipeline("isbn", vec![PipelineStep::new("translate_isbn1013", &[])]).unwrap();
+/// assert_eq!(registry.apply("isbn", "0-306-40615-2 (pbk.)").unwrap(), "0306406152 9780306406157");
+///
+/// assert!(registry.define_pipeline("bad", vec![PipelineStep::new("nope", &[])]).is_err());
+/// assert!(registry.apply("nope", "value").is_err());
+/// ```
+pub struct NormalizerRegistry {
+    normalizers: HashMap<String, NormalizerFn>,
+    pipelines: HashMap<String, Vec<PipelineStep>>,
+}
+
+impl Default for NormalizerRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NormalizerRegistry {
+    /// Create a registry containing the built-in normalizers.
+    pub fn new() -> Self {
+        Normalizer::init();
+
+        let mut registry = NormalizerRegistry {
+            normalizers: HashMap::new(),
+            pipelines: HashMap::new(),
+        };
+
+        registry.register("naco_normalize", |v, _| Normalizer::naco_normalize_once(v));
+        registry.register("nfkd", |v, _| {
+            DecomposingNormalizer::new_nfkd().normalize(v)
+        });
+        registry.register("lowercase", |v, _| v.to_lowercase());
+        registry.register("uppercase", |v, _| v.to_uppercase());
+        registry.register("btrim", |v, _| v.trim().to_string());
+        registry.register("strip_punctuation", strip_punctuation);
+        registry.register("remove_paren_substring", remove_paren_substring);
+        registry.register("replace", |v, p| match p {
+            [search, replacement, ..] => v.replace(search.as_str(), replacement),
+            _ => v.to_string(),
+        });
+        registry.register("left_trunc", |v, p| {
+            let len = param_usize(p, 0).unwrap_or(usize::MAX);
+            v.chars().skip(len).collect()
+        });
+        registry.register("right_trunc", |v, p| {
+            let len = param_usize(p, 0).unwrap_or(usize::MAX);
+            v.chars().take(len).collect()
+        });
+        registry.register("pad_numeric", pad_numeric);
+        registry.register("translate_isbn1013", translate_isbn1013);
+        registry.register("call_number_key", call_number_key);
+
+        registry
+    }
+
+    /// Add or replace a normalizer.
+    pub fn register(&mut self, name: &str, func: NormalizerFn) {
+        self.normalizers.insert(name.to_string(), func);
+    }
+
+    pub fn has_normalizer(&self, name: &str) -> bool {
+        self.normalizers.contains_key(name)
+    }
+
+    /// Add or replace a pipeline.
+    ///
+    /// Returns Err if any step references an unknown normalizer.
+    pub fn define_pipeline(&mut self, name: &str, steps: Vec<PipelineStep>) -> EgResult<()> {
+        if let Some(step) = steps.iter().find(|s| !self.has_normalizer(s.name())) {
+            return Err(format!("Pipeline {name} uses unknown normalizer: {}", step.name()).into());
+        }
+
+        self.pipelines.insert(name.to_string(), steps);
+
+        Ok(())
+    }
+
+    pub fn pipeline(&self, name: &str) -> Option<&Vec<PipelineStep>> {
+        self.pipelines.get(name)
+    }
+
+    /// Run a value through each step of the named pipeline.
+    pub fn apply(&self, pipeline: &str, value: &str) -> EgResult<String> {
+        let steps = self
+            .pipelines
+            .get(pipeline)
+            .ok_or_else(|| format!("No such normalization pipeline: {pipeline}"))?;
+
+        let mut value = value.to_string();
+
+        for step in steps {
+            // Verified in define_pipeline()
+            let func = self.normalizers[step.name()];
+            value = func(&value, step.params());
+        }
+
+        Ok(value)
+    }
+
+    /// Define a pipeline for each metabib field with normalizers
+    /// configured in config.metabib_field_index_norm_map, named
+    /// "metabib_field.<id>".
+    ///
+    /// Fields that use a normalizer with no Rust implementation are
+    /// skipped with a warning.  Returns the names of the pipelines
+    /// that were defined.
+    pub fn load_metabib_field_pipelines(&mut self, editor: &mut Editor) -> EgResult<Vec<String>> {
+        let query = eg::hash! {"id": {"!=": EgValue::Null}};
+
+        let flesh = eg::hash! {
+            "flesh": 1,
+            "flesh_fields": {"cmfinm": ["norm"]},
+            "order_by": {"cmfinm": ["field", "pos"]},
+        };
+
+        let maps = editor.search_with_ops("cmfinm", query, flesh)?;
+
+        let mut field_steps: Vec<(i64, Vec<PipelineStep>)> = Vec::new();
+
+        for map in maps.iter() {
+            let field_id = map["field"].int()?;
+
+            let params = match map["params"].as_str() {
+                Some(p) => EgValue::parse(p)?,
+                None => EgValue::Null,
+            };
+
+            let step = PipelineStep {
+                name: map["norm"]["func"].str()?.to_string(),
+                params: params
+                    .members()
+                    .map(|p| p.to_string().unwrap_or_default())
+                    .collect(),
+            };
+
+            match field_steps.last_mut() {
+                Some((id, steps)) if *id == field_id => steps.push(step),
+                _ => field_steps.push((field_id, vec![step])),
+            }
+        }
+
+        let mut names = Vec::new();
+
+        for (field_id, steps) in field_steps {
+            let name = format!("metabib_field.{field_id}");
+
+            if let Err(e) = self.define_pipeline(&name, steps) {
+                log::warn!("Skipping normalization for metabib field {field_id}: {e}");
+                continue;
+            }
+
+            names.push(name);
+        }
+
+        Ok(names)
+    }
+}
+
+fn param_usize(params: &[String], idx: usize) -> Option<usize> {
+    params.get(idx).and_then(|p| p.trim().parse::<usize>().ok())
+}
+
+/// Replace punctuation with spaces and collapse whitespace.
+fn strip_punctuation(value: &str, _: &[String]) -> String {
+    let value = REGEX_PUNCTUATION
+        .get()
+        .expect("Normalizer::init() should be called first")
+        .replace_all(value, " ");
+
+    value.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Remove parenthesized substrings, e.g. qualifiers.
+fn remove_paren_substring(value: &str, _: &[String]) -> String {
+    let mut result = String::new();
+    let mut depth = 0;
+
+    for c in value.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            _ if depth == 0 => result.push(c),
+            _ => {}
+        }
+    }
+
+    result.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Left-pad each run of digits with zeros to the requested width
+/// so numbers sort numerically.
+fn pad_numeric(value: &str, params: &[String]) -> String {
+    let width = param_usize(params, 0).unwrap_or(10);
+
+    let mut result = String::new();
+    let mut digits = String::new();
+
+    for c in value.chars().chain(std::iter::once('\0')) {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+
+        if !digits.is_empty() {
+            result += &format!("{digits:0>width$}");
+            digits.clear();
+        }
+
+        if c != '\0' {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+/// Replace an ISBN with its ISBN-10 and ISBN-13 forms, separated by
+/// a space.  Values which are not ISBNs are returned as-is.
+fn translate_isbn1013(value: &str, _: &[String]) -> String {
+    let Some(isbn) = Isbn::parse(value) else {
+        return value.to_string();
+    };
+
+    match isbn.to_isbn10() {
+        Some(isbn10) => format!("{isbn10} {}", isbn.to_isbn13()),
+        None => isbn.to_isbn13(),
+    }
+}
+
+/// Sortable call number key.
+fn call_number_key(value: &str, params: &[String]) -> String {
+    let scheme = match params.first().map(|p| p.as_str()) {
+        Some("lc") => Some(CallNumberScheme::LibraryOfCongress),
+        Some("dewey") => Some(CallNumberScheme::Dewey),
+        _ => CallNumberScheme::detect(value),
+    };
+
+    // The government document scheme applies generic normalization.
+    callnumber::sort_key(
+        value,
+        scheme.unwrap_or(CallNumberScheme::GovernmentDocument),
+    )
+}
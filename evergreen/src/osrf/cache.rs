@@ -73,6 +73,32 @@ impl CacheConnection {
             .map_err(|e| format!("{self} set key={key} failed: {e}").into())
     }
 
+    /// Store a value only if the key has no value.
+    ///
+    /// Returns true if this call stored the value.
+    fn add(&self, key: &str, value: EgValue, mut timeout: u32) -> EgResult<bool> {
+        let value = value.into_json_value().dump();
+
+        if timeout == 0 {
+            timeout = self.max_cache_time;
+        }
+
+        if let Err(e) = self.memcache.add(key, &value, timeout) {
+            // Expected when the key is already set.  Any other
+            // failure will surface in the lookup below.
+            log::debug!("{self} add key={key} not stored: {e}");
+        }
+
+        // Confirm the stored value is ours instead of relying on how
+        // the server reports an existing key.
+        let stored: Option<String> = self
+            .memcache
+            .get(key)
+            .map_err(|e| format!("{self} get key={key} failed: {e}"))?;
+
+        Ok(stored.as_deref() == Some(value.as_str()))
+    }
+
     fn get(&self, key: &str) -> EgResult<Option<EgValue>> {
         let result: Option<String> = match self.memcache.get(key) {
             Ok(r) => r,
@@ -224,6 +250,27 @@ impl Cache {
         Cache::set(GLOBAL_CACHE_NAME, key, value, timeout)
     }
 
+    /// Store a value using the specified cache, unless the key
+    /// already has a value.
+    ///
+    /// Returns true if the value was stored.  Callers racing to add
+    /// the same key should add distinct values, e.g. by including a
+    /// random token, so only one of them sees true.
+    pub fn add(cache_name: &str, key: &str, value: EgValue, timeout: u32) -> EgResult<bool> {
+        Cache::verify_cache(cache_name)?;
+
+        let mut result = Ok(false);
+        CACHE_CONNECTIONS
+            .with(|c| result = c.borrow().get(cache_name).unwrap().add(key, value, timeout));
+        result
+    }
+
+    /// Shortcut for adding a value to the "global" cache with the
+    /// provided timeout.
+    pub fn add_global_for(key: &str, value: EgValue, timeout: u32) -> EgResult<bool> {
+        Cache::add(GLOBAL_CACHE_NAME, key, value, timeout)
+    }
+
     /// Shortcut for storing a value in the "anon" cache with the
     /// default timeout.
     pub fn set_anon(key: &str, value: EgValue) -> EgResult<()> {
@@ -2,6 +2,7 @@ use gethostname::gethostname;
 use roxmltree;
 use std::fmt;
 use std::fs;
+use std::net::IpAddr;
 use std::str::FromStr;
 use std::sync::OnceLock;
 use syslog;
@@ -146,6 +147,9 @@ impl BusClient {
         // Assumes other aspects of the domain are identical
         self.domain.name = domain.to_string();
     }
+    pub fn set_port(&mut self, port: u16) {
+        self.domain.port = port;
+    }
     pub fn set_username(&mut self, username: &str) {
         self.username = username.to_string();
     }
@@ -195,12 +199,160 @@ impl Router {
     }
 }
 
+/// Range of IP addresses in CIDR notation, e.g. "10.0.0.0/8".
+///
+/// A bare address matches only itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IpRange {
+    addr: IpAddr,
+    prefix: u32,
+}
+
+impl IpRange {
+    /// True if the address falls within this range.
+    ///
+    /// ```
+    /// use evergreen::osrf::conf::IpRange;
+    ///
+    /// let range: IpRange = "10.1.0.0/16".parse().unwrap();
+    /// assert!(range.contains(&"10.1.4.20".parse().unwrap()));
+    /// assert!(!range.contains(&"10.2.4.20".parse().unwrap()));
+    ///
+    /// let range: IpRange = "::1".parse().unwrap();
+    /// assert!(range.contains(&"::1".parse().unwrap()));
+    /// assert!(!range.contains(&"127.0.0.1".parse().unwrap()));
+    /// ```
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        match (self.addr, addr) {
+            (IpAddr::V4(net), IpAddr::V4(a)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix).unwrap_or(0);
+                u32::from(net) & mask == u32::from(*a) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(a)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix).unwrap_or(0);
+                u128::from(net) & mask == u128::from(*a) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.trim().split_once('/') {
+            Some((a, p)) => (a, Some(p)),
+            None => (s.trim(), None),
+        };
+
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|e| format!("Invalid IP range '{s}': {e}"))?;
+
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+
+        let prefix = match prefix {
+            Some(p) => p
+                .parse::<u32>()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| format!("Invalid IP range prefix '{s}'"))?,
+            None => max,
+        };
+
+        Ok(IpRange { addr, prefix })
+    }
+}
+
+/// Access restrictions for one or more API methods.
+///
+/// Every restriction on a matching ACL must pass before the method
+/// is dispatched.
+///
+/// ```xml
+/// <method_acl method="open-ils.actor.internal.*">
+///   <internal>true</internal>
+/// </method_acl>
+/// <method_acl method="open-ils.circ.money.till.close">
+///   <authenticated>true</authenticated>
+///   <workstation>12</workstation>
+///   <ip_range>10.0.0.0/8</ip_range>
+/// </method_acl>
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MethodAcl {
+    /// Method name.  A trailing "*" matches any method with the
+    /// preceding prefix.
+    method: String,
+
+    /// Only callers connected to our own (private) bus domain may
+    /// call the method.
+    internal: bool,
+
+    /// The first parameter must be a valid authtoken.
+    authenticated: bool,
+
+    /// Authenticated callers must be logged in on one of these
+    /// workstations, by ID.  Implies authenticated.
+    workstations: Vec<i64>,
+
+    /// Callers must connect from one of these IP ranges.  Requests
+    /// with no known remote IP, e.g. those from other services, are
+    /// denied.  The remote IP is stamped on requests by the gateway
+    /// (websockets or HTTP) the client connected to.
+    ip_ranges: Vec<IpRange>,
+}
+
+impl MethodAcl {
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+    pub fn internal(&self) -> bool {
+        self.internal
+    }
+    pub fn authenticated(&self) -> bool {
+        self.authenticated || !self.workstations.is_empty()
+    }
+    pub fn workstations(&self) -> &Vec<i64> {
+        &self.workstations
+    }
+    pub fn ip_ranges(&self) -> &Vec<IpRange> {
+        &self.ip_ranges
+    }
+
+    /// True if this ACL applies to the API.
+    ///
+    /// ```
+    /// use evergreen::osrf::conf::ConfigBuilder;
+    ///
+    /// let xml = r#"<config><shared>
+    ///   <method_acl method="open-ils.foo.internal.*"><internal>true</internal></method_acl>
+    /// </shared></config>"#;
+    ///
+    /// let builder = ConfigBuilder::from_xml_string(xml).unwrap();
+    /// let acl = &builder.method_acls()[0];
+    ///
+    /// assert!(acl.internal());
+    /// assert!(acl.matches("open-ils.foo.internal.reset"));
+    /// assert!(!acl.matches("open-ils.foo.public"));
+    /// ```
+    pub fn matches(&self, api_name: &str) -> bool {
+        match self.method.strip_suffix('*') {
+            Some(prefix) => api_name.starts_with(prefix),
+            None => api_name == self.method,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ConfigBuilder {
     client: Option<BusClient>,
     routers: Vec<Router>,
     gateway: Option<BusClient>,
     log_protect: Vec<String>,
+    redacted_params: Vec<(String, Vec<usize>)>,
+    method_acls: Vec<MethodAcl>,
 }
 
 impl ConfigBuilder {
@@ -215,9 +367,15 @@ impl ConfigBuilder {
             routers: self.routers,
             gateway: self.gateway,
             log_protect: self.log_protect,
+            redacted_params: self.redacted_params,
+            method_acls: self.method_acls,
         })
     }
 
+    pub fn method_acls(&self) -> &Vec<MethodAcl> {
+        &self.method_acls
+    }
+
     /// Load configuration from a YAML file.
     ///
     /// May panic on invalid values (e.g. invalid log level) or unexpected
@@ -245,6 +403,8 @@ impl ConfigBuilder {
             gateway: None,
             routers: Vec::new(),
             log_protect: Vec::new(),
+            redacted_params: Vec::new(),
+            method_acls: Vec::new(),
         };
 
         // Start with the Client portion, which will contain values
@@ -274,11 +434,66 @@ impl ConfigBuilder {
                     self.log_protect.push(t.to_string());
                 }
             }
+
+            // <redact_params method="open-ils.foo.bar">1,2</redact_params>
+            for rp in lp.children().filter(|c| c.has_tag_name("redact_params")) {
+                let Some(method) = rp.attribute("method") else {
+                    continue;
+                };
+
+                let positions = rp
+                    .text()
+                    .unwrap_or("")
+                    .split(',')
+                    .map(|p| p.trim().parse::<usize>())
+                    .collect::<Result<Vec<usize>, _>>()
+                    .map_err(|e| format!("Invalid redact_params for {method}: {e}"))?;
+
+                self.redacted_params.push((method.to_string(), positions));
+            }
+        }
+
+        for acl_node in node.children().filter(|c| c.has_tag_name("method_acl")) {
+            self.method_acls
+                .push(self.unpack_method_acl_node(&acl_node)?);
         }
 
         Ok(())
     }
 
+    fn unpack_method_acl_node(&self, node: &roxmltree::Node) -> Result<MethodAcl, String> {
+        let Some(method) = node.attribute("method") else {
+            return Err("method_acl requires a 'method' attribute".to_string());
+        };
+
+        let is_true = |name: &str| {
+            self.child_node_text(node, name)
+                .map(|t| t.trim() == "true")
+                .unwrap_or(false)
+        };
+
+        let mut acl = MethodAcl {
+            method: method.to_string(),
+            internal: is_true("internal"),
+            authenticated: is_true("authenticated"),
+            ..Default::default()
+        };
+
+        for ws in node.children().filter(|c| c.has_tag_name("workstation")) {
+            let text = ws.text().unwrap_or("").trim();
+            let id = text
+                .parse::<i64>()
+                .map_err(|e| format!("Invalid workstation ID for {method}: {text} {e}"))?;
+            acl.workstations.push(id);
+        }
+
+        for range in node.children().filter(|c| c.has_tag_name("ip_range")) {
+            acl.ip_ranges.push(range.text().unwrap_or("").parse()?);
+        }
+
+        Ok(acl)
+    }
+
     fn unpack_routers(&mut self, node: &roxmltree::Node) -> Result<(), String> {
         for rnode in node.children().filter(|n| n.has_tag_name("router")) {
             // Router client configs are (mostly) nested in a <transport> element.
@@ -513,6 +728,8 @@ pub struct Config {
     routers: Vec<Router>,
     gateway: Option<BusClient>,
     log_protect: Vec<String>,
+    redacted_params: Vec<(String, Vec<usize>)>,
+    method_acls: Vec<MethodAcl>,
 }
 
 impl Config {
@@ -538,6 +755,24 @@ impl Config {
         &self.log_protect
     }
 
+    /// Parameter positions to redact from logs for the API per
+    /// the log_protect redact_params configs.
+    pub fn redacted_params(&self, api_name: &str) -> Vec<usize> {
+        self.redacted_params
+            .iter()
+            .filter(|(name, _)| name == api_name)
+            .flat_map(|(_, positions)| positions.iter().copied())
+            .collect()
+    }
+
+    /// Access control lists which apply to the API.
+    pub fn method_acls(&self, api_name: &str) -> Vec<&MethodAcl> {
+        self.method_acls
+            .iter()
+            .filter(|acl| acl.matches(api_name))
+            .collect()
+    }
+
     pub fn gateway(&self) -> Option<&BusClient> {
         self.gateway.as_ref()
     }
@@ -0,0 +1,401 @@
+//! In-memory message bus for hermetic testing.
+//!
+//! Provides the same API as the Redis-backed [`Bus`] and is compiled in
+//! its place when the "memory-bus" feature is enabled.  All Bus
+//! instances within a process share a single set of message queues,
+//! so clients, services, and routers running in different threads of
+//! the same process can talk to each other without Redis.
+//!
+//! Authentication (username/password) is not enforced.
+use crate::osrf::addr::BusAddress;
+use crate::osrf::conf;
+use crate::osrf::logging::Logger;
+use crate::osrf::message::TransportMessage;
+use crate::EgResult;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Queues and key expire times, shared by all Bus instances.
+#[derive(Default)]
+struct Store {
+    queues: HashMap<String, VecDeque<String>>,
+    expires: HashMap<String, Instant>,
+}
+
+impl Store {
+    /// Remove the key if its expire time has passed.
+    fn check_expired(&mut self, key: &str) {
+        if let Some(expire) = self.expires.get(key) {
+            if *expire <= Instant::now() {
+                self.queues.remove(key);
+                self.expires.remove(key);
+            }
+        }
+    }
+
+    /// Remove a key and its expire time.
+    fn del(&mut self, key: &str) -> i32 {
+        self.expires.remove(key);
+        match self.queues.remove(key) {
+            Some(_) => 1,
+            None => 0,
+        }
+    }
+
+    fn lpop(&mut self, key: &str) -> Option<String> {
+        self.check_expired(key);
+
+        let queue = self.queues.get_mut(key)?;
+        let value = queue.pop_front();
+
+        // Like Redis, empty lists cease to exist.
+        if queue.is_empty() {
+            self.del(key);
+        }
+
+        value
+    }
+}
+
+static STORE: OnceLock<(Mutex<Store>, Condvar)> = OnceLock::new();
+
+fn shared_store() -> &'static (Mutex<Store>, Condvar) {
+    STORE.get_or_init(|| (Mutex::new(Store::default()), Condvar::new()))
+}
+
+fn lock_store() -> EgResult<MutexGuard<'static, Store>> {
+    shared_store()
+        .0
+        .lock()
+        .map_err(|e| format!("Memory bus lock poisoned: {e}").into())
+}
+
+/// Returns true if the key matches the Redis-style glob pattern.
+///
+/// Supports the "*" and "?" wildcards.
+///
+/// ```
+/// use evergreen::osrf::bus::glob_match;
+///
+/// assert!(glob_match("opensrf:*", "opensrf:client:foo"));
+/// assert!(glob_match("opensrf:?lient:*", "opensrf:client:foo"));
+/// assert!(!glob_match("opensrf:router:*", "opensrf:client:foo"));
+/// assert!(glob_match("*", ""));
+/// ```
+pub fn glob_match(pattern: &str, key: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let key: Vec<char> = key.chars().collect();
+
+    let (mut p, mut k) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while k < key.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == key[k]) {
+            p += 1;
+            k += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, k));
+            p += 1;
+        } else if let Some((sp, sk)) = star {
+            // Backtrack and let the last "*" absorb one more character.
+            p = sp + 1;
+            k = sk + 1;
+            star = Some((sp, sk + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Manages a connection to the in-memory message queues.
+pub struct Bus {
+    /// Every bus connection has a unique client address.
+    address: BusAddress,
+
+    /// Name of the router running on our primary domain.
+    router_name: String,
+
+    /// See the Redis Bus for details.
+    raw_data_mode: bool,
+}
+
+impl Bus {
+    pub fn new(config: &conf::BusClient) -> EgResult<Self> {
+        let username = config.username();
+        let domain = config.domain().name();
+
+        log::trace!("Bus::new() creating memory bus for {username}@{domain}");
+
+        Ok(Bus {
+            raw_data_mode: false,
+            address: BusAddress::for_client(username, domain),
+            router_name: config.router_name().to_string(),
+        })
+    }
+
+    pub fn set_raw_data_mode(&mut self, on: bool) {
+        self.raw_data_mode = on;
+    }
+
+    /// The unique bus address for this bus connection.
+    pub fn address(&self) -> &BusAddress {
+        &self.address
+    }
+
+    /// Apply a new bus address
+    pub fn set_address(&mut self, addr: &BusAddress) {
+        self.address = addr.clone();
+    }
+
+    /// Generates a new BusAddress and applies it to this Bus.
+    pub fn generate_address(&mut self) {
+        self.address = BusAddress::for_client(self.username(), self.domain());
+    }
+
+    /// The name of the router running on our primary domain.
+    pub fn router_name(&self) -> &str {
+        &self.router_name
+    }
+
+    /// Our primary domain
+    pub fn domain(&self) -> &str {
+        self.address().domain()
+    }
+
+    pub fn username(&self) -> &str {
+        self.address().username()
+    }
+
+    /// Returns at most one String pulled from the queue or None if the
+    /// pop times out.
+    fn recv_one_chunk(
+        &mut self,
+        timeout: u64,
+        recipient: Option<&str>,
+    ) -> EgResult<Option<String>> {
+        let recipient = recipient.unwrap_or(self.address().as_str());
+
+        let mut store = lock_store()?;
+
+        if timeout == 0 {
+            return Ok(store.lpop(recipient));
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(timeout);
+
+        loop {
+            if let Some(value) = store.lpop(recipient) {
+                log::trace!("recv_one_chunk() pulled from bus: {}", value);
+                return Ok(Some(value));
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+
+            store = shared_store()
+                .1
+                .wait_timeout(store, deadline - now)
+                .map_err(|e| format!("Memory bus lock poisoned: {e}"))?
+                .0;
+        }
+    }
+
+    /// Returns at most one JSON value pulled from the queue.
+    ///
+    /// Keeps trying until a value is returned or the timeout is exceeded.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - Time in seconds to wait for a value.
+    ///   0 means do not block.
+    pub fn recv_json_value(
+        &mut self,
+        timeout: u64,
+        recipient: Option<&str>,
+    ) -> EgResult<Option<json::JsonValue>> {
+        let json_string = match self.recv_one_chunk(timeout, recipient)? {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+
+        log::trace!("{self} read json from the bus: {json_string}");
+
+        match json::parse(&json_string) {
+            Ok(json_val) => Ok(Some(json_val)),
+            Err(err) => Err(format!("Error parsing JSON: {err:?}").into()),
+        }
+    }
+
+    /// Returns at most one TransportMessage.
+    ///
+    /// See the Redis Bus for details on invalid message handling.
+    pub fn recv(
+        &mut self,
+        timeout: u64,
+        recipient: Option<&str>,
+    ) -> EgResult<Option<TransportMessage>> {
+        let json_op = self.recv_json_value(timeout, recipient)?;
+
+        if let Some(jv) = json_op {
+            match TransportMessage::from_json_value(jv, self.raw_data_mode) {
+                Ok(v) => Ok(Some(v)),
+                Err(e) => {
+                    log::error!("Error translating JSON value into EgValue: {e}");
+                    Ok(None)
+                }
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Send a TransportMessage to the "to" value in the message.
+    pub fn send(&mut self, msg: TransportMessage) -> EgResult<()> {
+        self.send_internal(msg, None)
+    }
+
+    /// Send a TransportMessage to the specified BusAddress, regardless
+    /// of what value is in the msg.to() field.
+    pub fn send_to(&mut self, msg: TransportMessage, recipient: &str) -> EgResult<()> {
+        self.send_internal(msg, Some(recipient))
+    }
+
+    fn send_internal(&mut self, msg: TransportMessage, recipient: Option<&str>) -> EgResult<()> {
+        let mut json_val = msg.into_json_value();
+
+        json_val["osrf_xid"] = json::from(Logger::get_log_trace());
+
+        let recipient = recipient.unwrap_or(json_val["to"].as_str().unwrap());
+        let json_str = json_val.dump();
+
+        log::trace!("send() writing chunk to={}: {}", recipient, json_str);
+
+        let mut store = lock_store()?;
+        store.check_expired(recipient);
+
+        store
+            .queues
+            .entry(recipient.to_string())
+            .or_default()
+            .push_back(json_str);
+
+        // Wake any receivers so they can check their queues.
+        shared_store().1.notify_all();
+
+        Ok(())
+    }
+
+    /// Returns a list of keys that match the provided pattern.
+    pub fn keys(&mut self, pattern: &str) -> EgResult<Vec<String>> {
+        let mut store = lock_store()?;
+
+        let keys: Vec<String> = store.queues.keys().cloned().collect();
+        for key in keys.iter() {
+            store.check_expired(key);
+        }
+
+        Ok(store
+            .queues
+            .keys()
+            .filter(|k| glob_match(pattern, k))
+            .cloned()
+            .collect())
+    }
+
+    /// Returns the length of the array specified by 'key'.
+    pub fn llen(&mut self, key: &str) -> EgResult<i32> {
+        let mut store = lock_store()?;
+        store.check_expired(key);
+
+        Ok(store.queues.get(key).map(|q| q.len() as i32).unwrap_or(0))
+    }
+
+    /// Returns the time-to-live (in seconds) of the specified key.
+    ///
+    /// Return -1 if no expire time is set, -2 if no such key exists.
+    pub fn ttl(&mut self, key: &str) -> EgResult<i32> {
+        let mut store = lock_store()?;
+        store.check_expired(key);
+
+        if !store.queues.contains_key(key) {
+            return Ok(-2);
+        }
+
+        match store.expires.get(key) {
+            Some(e) => Ok(e.saturating_duration_since(Instant::now()).as_secs() as i32),
+            None => Ok(-1),
+        }
+    }
+
+    /// Returns an array slice as a Vec of Strings.
+    ///
+    /// Negative indexes count from the end of the list, like Redis.
+    pub fn lrange(&mut self, key: &str, start: isize, stop: isize) -> EgResult<Vec<String>> {
+        let mut store = lock_store()?;
+        store.check_expired(key);
+
+        let queue = match store.queues.get(key) {
+            Some(q) => q,
+            None => return Ok(Vec::new()),
+        };
+
+        let len = queue.len() as isize;
+        let start = if start < 0 { len + start } else { start }.max(0);
+        let stop = if stop < 0 { len + stop } else { stop }.min(len - 1);
+
+        if start > stop {
+            return Ok(Vec::new());
+        }
+
+        Ok(queue
+            .iter()
+            .skip(start as usize)
+            .take((stop - start + 1) as usize)
+            .cloned()
+            .collect())
+    }
+
+    /// Set the expire time on the specified key to 'timeout' seconds from now.
+    ///
+    /// Returns 1 if the timeout was set, 0 if the key does not exist.
+    pub fn set_key_timeout(&mut self, key: &str, timeout: u64) -> EgResult<i32> {
+        let mut store = lock_store()?;
+        store.check_expired(key);
+
+        if !store.queues.contains_key(key) {
+            return Ok(0);
+        }
+
+        let expire = Instant::now() + Duration::from_secs(timeout);
+        store.expires.insert(key.to_string(), expire);
+
+        Ok(1)
+    }
+
+    /// Remove all pending data from the recipient queue.
+    pub fn clear_bus(&mut self) -> EgResult<()> {
+        lock_store()?.del(self.address().as_str());
+        Ok(())
+    }
+}
+
+impl fmt::Display for Bus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Bus {}", self.address().as_str())
+    }
+}
+
+/// Remove any messages that linger for our address.
+impl Drop for Bus {
+    fn drop(&mut self) {
+        if let Ok(mut store) = lock_store() {
+            store.del(self.address().as_str());
+        }
+    }
+}
@@ -439,6 +439,12 @@ pub struct Message {
     timezone: Option<String>,
     api_level: u8,
     ingress: Option<String>,
+    /// IP address of the remote client for requests relayed by a
+    /// gateway.  Unlike the ingress, this is not passed along to
+    /// subsequent requests.
+    ///
+    /// Only gateways may set this value.  See [`Message::set_remote_ip`].
+    remote_ip: Option<String>,
     payload: Payload,
 }
 
@@ -451,6 +457,7 @@ impl Message {
             api_level: DEFAULT_API_LEVEL,
             timezone: None,
             ingress: None,
+            remote_ip: None,
         }
     }
 
@@ -498,6 +505,21 @@ impl Message {
         self.ingress = Some(ingress.to_string())
     }
 
+    pub fn remote_ip(&self) -> Option<&str> {
+        self.remote_ip.as_deref()
+    }
+
+    /// Set the IP address of the client a gateway is relaying for.
+    ///
+    /// Method ACLs trust this value, so only gateways may set it, and
+    /// every gateway must replace whatever value arrived from its
+    /// client with the address of the client connection.  Messages
+    /// parsed from the bus keep their value as-is, since the bus is
+    /// only reachable by trusted components.
+    pub fn set_remote_ip(&mut self, remote_ip: &str) {
+        self.remote_ip = Some(remote_ip.to_string())
+    }
+
     /// Creates a Message from a JSON value, consuming the JSON value.
     ///
     /// Returns Err if the JSON value cannot be coerced into a Message.
@@ -540,6 +562,12 @@ impl Message {
             msg.set_api_level(al);
         }
 
+        // Gateways overwrite this value with the client address
+        // before relaying messages from their clients.
+        if let Some(ip) = msg_hash["remote_ip"].as_str() {
+            msg.set_remote_ip(ip);
+        }
+
         Ok(msg)
     }
 
@@ -589,6 +617,10 @@ impl Message {
             THREAD_INGRESS.with(|lc| obj["ingress"] = lc.borrow().as_str().into());
         }
 
+        if let Some(ip) = self.remote_ip() {
+            obj["remote_ip"] = ip.into();
+        }
+
         match self.payload {
             // Avoid adding the "payload" key for non-payload messages.
             Payload::NoPayload => {}
@@ -6,6 +6,22 @@ use crate::EgValue;
 use json::JsonValue;
 use std::fmt;
 
+/// Parameter positions to redact from logs for an API, combining
+/// positions from the method definition, when known, with those
+/// listed in the OpenSRF config, for processes like gateways which
+/// relay calls to methods they do not host.
+pub fn redacted_params(api_name: &str, method: Option<&MethodDef>) -> Vec<usize> {
+    let mut positions = crate::osrf::conf::config().redacted_params(api_name);
+
+    if let Some(m) = method {
+        positions.extend(m.redacted_params());
+    }
+
+    positions.sort();
+    positions.dedup();
+    positions
+}
+
 pub type MethodHandler = fn(
     &mut Box<dyn app::ApplicationWorker>,
     &mut session::ServerSession,
@@ -152,6 +168,9 @@ pub struct StaticMethodDef {
     pub param_count: ParamCount,
     pub handler: MethodHandler,
     pub params: &'static [StaticParam],
+    /// Positions of sensitive parameters, e.g. passwords, which are
+    /// redacted when logging calls to this method.
+    pub redacted_params: &'static [usize],
 }
 
 impl StaticMethodDef {
@@ -197,6 +216,8 @@ impl StaticMethodDef {
             m.desc = Some(self.desc.to_string());
         }
 
+        m.redact_params(self.redacted_params);
+
         m
     }
 }
@@ -208,6 +229,9 @@ pub struct MethodDef {
     pub param_count: ParamCount,
     pub handler: MethodHandler,
     pub params: Option<Vec<Param>>,
+    /// Positions of sensitive parameters, e.g. passwords, which are
+    /// redacted when logging calls to this method.
+    pub redacted_params: Vec<usize>,
 }
 
 impl MethodDef {
@@ -218,6 +242,7 @@ impl MethodDef {
             params: None,
             desc: None,
             name: name.to_string(),
+            redacted_params: Vec::new(),
         }
     }
 
@@ -247,6 +272,29 @@ impl MethodDef {
     pub fn set_desc(&mut self, desc: &str) {
         self.desc = Some(desc.to_string());
     }
+    pub fn redacted_params(&self) -> &[usize] {
+        &self.redacted_params
+    }
+
+    /// Mark the parameters at the provided positions as sensitive.
+    ///
+    /// ```
+    /// use evergreen::osrf::method::{MethodDef, ParamCount};
+    ///
+    /// let mut method = MethodDef::new("opensrf.test", ParamCount::Any, |_, _, _| Ok(()));
+    /// method.redact_params(&[1, 1]);
+    ///
+    /// assert_eq!(method.redacted_params(), &[1]);
+    /// assert_eq!(method.to_eg_value()["redacted_params"][0].int().unwrap(), 1);
+    /// ```
+    pub fn redact_params(&mut self, positions: &[usize]) {
+        for pos in positions {
+            if !self.redacted_params.contains(pos) {
+                self.redacted_params.push(*pos);
+            }
+        }
+    }
+
     pub fn add_param(&mut self, param: Param) {
         let params = match self.params.as_mut() {
             Some(p) => p,
@@ -271,6 +319,7 @@ impl MethodDef {
             "api_name": self.name(),
             "argc": self.param_count().to_string(),
             "params": pa.into_json_value(),
+            "redacted_params": self.redacted_params.as_slice(),
             // All Rust methods are streaming.
             "stream": JsonValue::Boolean(true),
             "desc": match self.desc() {
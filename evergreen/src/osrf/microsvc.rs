@@ -252,6 +252,13 @@ impl Microservice {
                 log::info!("received a stop signal");
                 break;
             }
+
+            if self.sig_tracker.reload_requested() {
+                self.sig_tracker.handle_reload_requested();
+                if let Err(e) = HostSettings::reload() {
+                    log::error!("Host settings reload failed: {e}");
+                }
+            }
         }
 
         log::debug!("{self} exiting listen loop and cleaning up");
@@ -432,6 +439,10 @@ impl Microservice {
             &api_name,
             method_call.params(),
             conf::config().log_protect(),
+            &method::redacted_params(
+                &api_name,
+                Microservice::methods().get(api_name.trim_end_matches(".atomic")),
+            ),
         );
 
         // Log the API call
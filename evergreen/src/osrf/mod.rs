@@ -1,6 +1,10 @@
 //! OpenSRF Components
 pub mod addr;
 pub mod app;
+#[cfg(not(feature = "memory-bus"))]
+pub mod bus;
+#[cfg(feature = "memory-bus")]
+#[path = "membus.rs"]
 pub mod bus;
 pub mod cache;
 pub mod client;
@@ -1,31 +1,72 @@
 //! Host Settings Module
+//!
+//! Host settings are built in layers, each overriding the last:
+//!
+//! 1. The host config from opensrf.settings, when it's available.
+//! 2. JSON files listed (colon-separated) in the EG_HOST_SETTINGS_FILES
+//!    environment variable, in order.  The "hosts" object within
+//!    a file may contain per-hostname overrides, which are applied
+//!    after the rest of the file.
+//!
+//! Objects are merged key by key.  Other values, including arrays,
+//! replace the value from the previous layer.  String values may
+//! reference environment variables as ${NAME} or ${NAME:-default}.
+//!
+//! Call HostSettings::reload(), e.g. on SIGHUP, to re-read the files.
 use crate::osrf::conf;
 use crate::Client;
 use crate::EgResult;
 use crate::EgValue;
-use std::sync::OnceLock;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::sync::{Mutex, RwLock};
 
 const SETTINGS_TIMEOUT: u64 = 10;
 
+/// Colon-separated list of JSON settings files.
+const SETTINGS_FILES_ENV: &str = "EG_HOST_SETTINGS_FILES";
+
+/// Per-host overrides within a settings file.
+const HOSTS_KEY: &str = "hosts";
+
 /// If we fetch host settings, they will live here.
-/// They may be fetched and stored at most one time.
-static OSRF_HOST_CONFIG: OnceLock<HostSettings> = OnceLock::new();
+static OSRF_HOST_CONFIG: RwLock<Option<HostSettings>> = RwLock::new(None);
+
+type ChangeCallback = Box<dyn Fn() + Send + Sync>;
+
+/// Called after a reload which modifies the settings.
+static CHANGE_CALLBACKS: Mutex<Vec<ChangeCallback>> = Mutex::new(Vec::new());
 
 /// Read-only wrapper around a JSON blob of server setting values, which
 /// provides accessor methods for pulling setting values.
 pub struct HostSettings {
+    /// Settings from opensrf.settings, if any, before any file
+    /// layers are applied.
+    base: EgValue,
+
+    /// Fully layered settings.
     settings: EgValue,
 }
 
 impl HostSettings {
     /// True if the host settings have been loaded.
     pub fn is_loaded() -> bool {
-        OSRF_HOST_CONFIG.get().is_some()
+        OSRF_HOST_CONFIG
+            .read()
+            .map(|c| c.is_some())
+            .unwrap_or(false)
     }
 
-    /// Fetch the host config for our host and store the result in
-    /// our global host settings.
-    ///
+    /// True if any settings files are configured via the environment.
+    pub fn has_files() -> bool {
+        env::var(SETTINGS_FILES_ENV)
+            .map(|v| !v.is_empty())
+            .unwrap_or(false)
+    }
+
+    /// Fetch the host config for our host, apply any settings files,
+    /// and store the result in our global host settings.
     pub fn load(client: &Client) -> EgResult<()> {
         let mut ses = client.session("opensrf.settings");
 
@@ -35,30 +76,174 @@ impl HostSettings {
         )?;
 
         if let Some(s) = req.recv_with_timeout(SETTINGS_TIMEOUT)? {
-            let sets = HostSettings { settings: s };
-            if OSRF_HOST_CONFIG.set(sets).is_err() {
-                return Err("Cannot apply host settings more than once".into());
-            }
-
-            Ok(())
+            HostSettings::apply_base(s)
         } else {
             Err("Settings server returned no response!".into())
         }
     }
 
+    /// Load host settings from the settings files only, for services
+    /// that do not have access to opensrf.settings.
+    pub fn load_files() -> EgResult<()> {
+        HostSettings::apply_base(EgValue::new_object())
+    }
+
+    fn apply_base(base: EgValue) -> EgResult<()> {
+        let settings = HostSettings::layer_files(base.clone())?;
+
+        let mut config = OSRF_HOST_CONFIG
+            .write()
+            .map_err(|e| format!("Host settings lock failed: {e}"))?;
+
+        if config.is_some() {
+            return Err("Cannot apply host settings more than once".into());
+        }
+
+        *config = Some(HostSettings { base, settings });
+
+        Ok(())
+    }
+
+    /// Re-read the settings files and apply them to the original
+    /// opensrf.settings values.
+    ///
+    /// Change callbacks are run if the settings were modified.  If
+    /// a settings file cannot be read or parsed, the current
+    /// settings are retained.
+    pub fn reload() -> EgResult<()> {
+        let changed = {
+            let mut config = OSRF_HOST_CONFIG
+                .write()
+                .map_err(|e| format!("Host settings lock failed: {e}"))?;
+
+            let hsets = config
+                .as_mut()
+                .ok_or_else(|| "Host settings have not been retrieved".to_string())?;
+
+            let settings = HostSettings::layer_files(hsets.base.clone())?;
+
+            if settings == hsets.settings {
+                false
+            } else {
+                hsets.settings = settings;
+                true
+            }
+        };
+
+        if changed {
+            log::info!("Host settings changed on reload");
+
+            // Settings lock is released so callbacks can read the
+            // new values.
+            if let Ok(callbacks) = CHANGE_CALLBACKS.lock() {
+                for callback in callbacks.iter() {
+                    callback();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Register a function to call after a reload modifies the settings.
+    pub fn on_change(callback: impl Fn() + Send + Sync + 'static) {
+        if let Ok(mut callbacks) = CHANGE_CALLBACKS.lock() {
+            callbacks.push(Box::new(callback));
+        }
+    }
+
+    /// Apply each configured settings file, in order, to the base settings.
+    fn layer_files(mut settings: EgValue) -> EgResult<EgValue> {
+        let files = match env::var(SETTINGS_FILES_ENV) {
+            Ok(f) => f,
+            Err(_) => return Ok(settings),
+        };
+
+        let vars: HashMap<String, String> = env::vars().collect();
+
+        for path in files.split(':').filter(|p| !p.is_empty()) {
+            let text = fs::read_to_string(path)
+                .map_err(|e| format!("Cannot read settings file {path}: {e}"))?;
+
+            let layer = EgValue::parse(&text)
+                .map_err(|e| format!("Cannot parse settings file {path}: {e}"))?;
+
+            log::debug!("Applying host settings file {path}");
+
+            HostSettings::apply_layer(&mut settings, layer, conf::config().hostname(), &vars);
+        }
+
+        Ok(settings)
+    }
+
+    /// Merge one settings layer into the provided settings, followed
+    /// by the layer's overrides for the provided hostname.
+    ///
+    /// ```
+    /// use evergreen as eg;
+    /// use eg::osrf::sclient::HostSettings;
+    /// use std::collections::HashMap;
+    ///
+    /// let mut settings = eg::hash! {
+    ///     "cache": {"servers": ["localhost:11211"], "max_time": 300},
+    ///     "IDL": "/openils/conf/fm_IDL.xml",
+    /// };
+    ///
+    /// let layer = eg::hash! {
+    ///     "cache": {"servers": ["${CACHE_HOST}:11211"]},
+    ///     "hosts": {
+    ///         "app1": {"IDL": "${IDL_DIR:-/tmp}/fm_IDL.xml"},
+    ///         "app2": {"IDL": "/app2/fm_IDL.xml"},
+    ///     }
+    /// };
+    ///
+    /// let vars = HashMap::from([("CACHE_HOST".to_string(), "memcached".to_string())]);
+    ///
+    /// HostSettings::apply_layer(&mut settings, layer, "app1", &vars);
+    ///
+    /// assert_eq!(settings["cache"]["servers"][0].str().unwrap(), "memcached:11211");
+    /// assert_eq!(settings["cache"]["max_time"].int().unwrap(), 300);
+    /// assert_eq!(settings["IDL"].str().unwrap(), "/tmp/fm_IDL.xml");
+    /// assert!(settings["hosts"].is_null());
+    /// ```
+    pub fn apply_layer(
+        settings: &mut EgValue,
+        mut layer: EgValue,
+        hostname: &str,
+        vars: &HashMap<String, String>,
+    ) {
+        let hosts = match layer {
+            EgValue::Hash(ref mut h) => h.remove(HOSTS_KEY),
+            _ => None,
+        };
+
+        merge(settings, layer, vars);
+
+        if let Some(mut hosts) = hosts.filter(|h| h.is_object()) {
+            let host_layer = hosts[hostname].take();
+            if !host_layer.is_null() {
+                merge(settings, host_layer, vars);
+            }
+        }
+    }
+
     /// Returns the full host settings config as a JsonValue.
     pub fn settings(&self) -> &EgValue {
         &self.settings
     }
 
-    /// Returns the JsonValue at the specified path.
+    /// Returns a copy of the value at the specified path.
     ///
-    /// Panics of the host config has not yet been retrieved.
+    /// Returns Err if the host config has not yet been retrieved.
     ///
-    /// E.g. sclient.value("apps/opensrf.settings/unix_config/max_children");
-    pub fn get(slashpath: &str) -> EgResult<&EgValue> {
-        let hsets = OSRF_HOST_CONFIG
-            .get()
+    /// E.g. HostSettings::get("apps/opensrf.settings/unix_config/max_children");
+    pub fn get(slashpath: &str) -> EgResult<EgValue> {
+        let config = OSRF_HOST_CONFIG
+            .read()
+            .map_err(|e| format!("Host settings lock failed: {e}"))?;
+
+        let hsets = config
+            .as_ref()
             .ok_or_else(|| "Host settings have not been retrieved".to_string())?;
 
         let mut value = hsets.settings();
@@ -66,6 +251,97 @@ impl HostSettings {
             value = &value[part]; // -> JsonValue::Null if key is not found.
         }
 
-        Ok(value)
+        Ok(value.clone())
+    }
+
+    /// Returns the string value at the specified path, if it's a string.
+    pub fn get_string(slashpath: &str) -> EgResult<Option<String>> {
+        Ok(HostSettings::get(slashpath)?
+            .as_str()
+            .map(|s| s.to_string()))
+    }
+
+    /// Returns the integer value at the specified path, if it's a
+    /// number or numeric string.
+    pub fn get_int(slashpath: &str) -> EgResult<Option<i64>> {
+        Ok(HostSettings::get(slashpath)?.as_int())
+    }
+
+    /// Returns the usize value at the specified path, if it's a
+    /// non-negative number.
+    pub fn get_usize(slashpath: &str) -> EgResult<Option<usize>> {
+        Ok(HostSettings::get(slashpath)?.as_usize())
+    }
+
+    /// Returns true if the value at the specified path is true-ish.
+    pub fn get_bool(slashpath: &str) -> EgResult<bool> {
+        Ok(HostSettings::get(slashpath)?.boolish())
+    }
+}
+
+/// Merge the overlay into the target, interpolating environment
+/// variables in overlay strings.
+fn merge(target: &mut EgValue, overlay: EgValue, vars: &HashMap<String, String>) {
+    match (target, overlay) {
+        (EgValue::Hash(t), EgValue::Hash(o)) => {
+            for (key, value) in o {
+                match t.get_mut(&key) {
+                    Some(existing) => merge(existing, value, vars),
+                    None => {
+                        let mut value = value;
+                        interpolate(&mut value, vars);
+                        t.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, mut overlay) => {
+            interpolate(&mut overlay, vars);
+            *target = overlay;
+        }
+    }
+}
+
+/// Replace ${NAME} and ${NAME:-default} references in all strings
+/// within the value.  Unknown variables without a default are
+/// replaced with an empty string.
+fn interpolate(value: &mut EgValue, vars: &HashMap<String, String>) {
+    match value {
+        EgValue::String(s) if s.contains("${") => *s = interpolate_str(s, vars),
+        EgValue::Array(list) => list.iter_mut().for_each(|v| interpolate(v, vars)),
+        EgValue::Hash(h) => h.values_mut().for_each(|v| interpolate(v, vars)),
+        _ => {}
     }
 }
+
+fn interpolate_str(s: &str, vars: &HashMap<String, String>) -> String {
+    let mut result = String::new();
+    let mut remainder = s;
+
+    while let Some(start) = remainder.find("${") {
+        let Some(end) = remainder[start..].find('}') else {
+            break;
+        };
+
+        result += &remainder[..start];
+
+        let reference = &remainder[start + 2..start + end];
+
+        let (name, default) = match reference.split_once(":-") {
+            Some((n, d)) => (n, Some(d)),
+            None => (reference, None),
+        };
+
+        match vars.get(name) {
+            Some(v) => result += v,
+            None => match default {
+                Some(d) => result += d,
+                None => log::warn!("Host settings reference unset variable {name}"),
+            },
+        }
+
+        remainder = &remainder[start + end + 1..];
+    }
+
+    result + remainder
+}
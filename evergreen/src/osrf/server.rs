@@ -381,6 +381,13 @@ impl Server {
                 break;
             }
 
+            if self.sig_tracker.reload_requested() {
+                self.sig_tracker.handle_reload_requested();
+                if let Err(e) = HostSettings::reload() {
+                    log::error!("Host settings reload failed: {e}");
+                }
+            }
+
             if !work_performed {
                 // Only perform idle worker maintenance if no other
                 // tasks were performed during this loop iter.
@@ -14,6 +14,7 @@ use crate::util;
 use crate::{EgResult, EgValue};
 use std::cell::RefCell;
 use std::cell::RefMut;
+use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::fmt;
 use std::rc::Rc;
@@ -193,8 +194,10 @@ struct ClientSessionInternal {
     /// queue-like behavior (push back / pop front).
     backlog: VecDeque<Message>,
 
-    /// Staging ground for "partial" messages arriving in chunks.
-    partial_buffer: Option<String>,
+    /// Staging ground for "partial" messages arriving in chunks,
+    /// keyed on thread_trace, since replies to pipelined requests
+    /// may be interleaved.
+    partial_buffers: HashMap<usize, String>,
 }
 
 impl fmt::Display for ClientSessionInternal {
@@ -218,7 +221,7 @@ impl ClientSessionInternal {
             service: String::from(service),
             connected: false,
             last_thread_trace: 0,
-            partial_buffer: None,
+            partial_buffers: HashMap::new(),
             backlog: VecDeque::new(),
             thread: util::random_number(16),
         }
@@ -243,6 +246,52 @@ impl ClientSessionInternal {
         self.backlog.clear();
     }
 
+    /// Discard state for a failed request.
+    ///
+    /// Connected sessions are reset entirely.  For stateless sessions,
+    /// replies to other requests, which may be in flight via other
+    /// workers, are retained.
+    fn reset_request(&mut self, thread_trace: usize) {
+        if self.connected {
+            self.reset();
+        } else {
+            self.worker_addr = None;
+            self.backlog.retain(|m| m.thread_trace() != thread_trace);
+        }
+        self.partial_buffers.remove(&thread_trace);
+    }
+
+    /// True if any replies to the request are waiting in our backlog.
+    fn has_backlog_for(&self, thread_trace: usize) -> bool {
+        self.backlog
+            .iter()
+            .any(|m| m.thread_trace() == thread_trace)
+    }
+
+    /// Wait up to `timeout` seconds for a new transport message to
+    /// arrive for this session and add its contents to our backlog.
+    ///
+    /// Returns true if a message arrived.
+    fn wait(&mut self, timeout: u64) -> EgResult<bool> {
+        let mut timer = util::Timer::new(timeout);
+
+        let mut tmsg = match self
+            .client_internal_mut()
+            .recv_session(&mut timer, self.thread())?
+        {
+            Some(m) => m,
+            None => return Ok(false),
+        };
+
+        self.worker_addr = Some(BusAddress::parse_str(tmsg.from())?);
+
+        for msg in tmsg.body_mut().drain(..) {
+            self.backlog.push_back(msg);
+        }
+
+        Ok(true)
+    }
+
     fn router_addr(&self) -> &BusAddress {
         &self.router_addr
     }
@@ -336,6 +385,8 @@ impl ClientSessionInternal {
         timer: &mut util::Timer,
         mut msg: Message,
     ) -> EgResult<Option<Response>> {
+        let trace = msg.thread_trace();
+
         if let Payload::Result(resp) = msg.payload_mut() {
             log::trace!("{self} Unpacking osrf message status={}", resp.status());
 
@@ -343,13 +394,7 @@ impl ClientSessionInternal {
             let mut value = resp.take_content();
 
             if resp.status() == &MessageStatus::Partial {
-                let buf = match self.partial_buffer.as_mut() {
-                    Some(b) => b,
-                    None => {
-                        self.partial_buffer = Some(String::new());
-                        self.partial_buffer.as_mut().unwrap()
-                    }
-                };
+                let buf = self.partial_buffers.entry(trace).or_default();
 
                 // The content of a partial message is a raw JSON string,
                 // representing a subset of the JSON value response as a whole.
@@ -364,7 +409,7 @@ impl ClientSessionInternal {
                 }));
             } else if resp.status() == &MessageStatus::PartialComplete {
                 // Take + clear the partial buffer.
-                let mut buf = self.partial_buffer.take().unwrap_or_default();
+                let mut buf = self.partial_buffers.remove(&trace).unwrap_or_default();
 
                 // Append any trailing content if available.
                 if let Some(chunk) = value.as_str() {
@@ -396,15 +441,13 @@ impl ClientSessionInternal {
             }));
         }
 
-        let trace = msg.thread_trace();
-
         if let Payload::Status(stat) = msg.payload() {
             self.unpack_status_message(trace, timer, stat).map_err(|e| {
-                self.reset();
+                self.reset_request(trace);
                 e
             })
         } else {
-            self.reset();
+            self.reset_request(trace);
             Err(format!("{self} unexpected response for request {trace}: {msg:?}").into())
         }
     }
@@ -435,7 +478,7 @@ impl ClientSessionInternal {
                 }))
             }
             _ => {
-                self.reset();
+                self.reset_request(trace);
                 Err(format!("{self} request {trace} failed: {}", statmsg).into())
             }
         }
@@ -618,6 +661,230 @@ impl ClientSession {
     pub fn connected(&self) -> bool {
         self.session.borrow().connected()
     }
+
+    /// Create a [`Pipeline`] for sending a batch of requests via this
+    /// session with up to `max_in_flight` requests outstanding at once.
+    pub fn pipeline(&self, max_in_flight: usize) -> Pipeline {
+        Pipeline::new(self.session.clone(), max_in_flight)
+    }
+}
+
+/// Final outcome of one request sent via a [`Pipeline`].
+pub struct PipelineResponse {
+    /// Position of the request in the order it was queued, starting at 0.
+    pub index: usize,
+
+    /// All responses to the request or the error which ended it.
+    ///
+    /// An error for one request does not affect the other requests
+    /// in the pipeline.
+    pub result: EgResult<Vec<EgValue>>,
+}
+
+/// A request which has been sent but is not yet complete.
+struct PipelinedRequest {
+    index: usize,
+    request: Request,
+    responses: Vec<EgValue>,
+}
+
+/// Sends a batch of requests via a single session, keeping up to a
+/// fixed number in flight at once.
+///
+/// Requests are queued with [`Pipeline::request`] and sent as earlier
+/// requests complete.  Iterating the pipeline yields one
+/// [`PipelineResponse`] per request, in the order the requests
+/// complete, which may differ from the order they were queued.
+///
+/// ```no_run
+/// # fn example(client: &evergreen::Client) -> evergreen::EgResult<()> {
+/// let ses = client.session("open-ils.circ");
+/// let mut pipeline = ses.pipeline(4);
+///
+/// for copy_id in [1, 2, 3, 4, 5, 6] {
+///     pipeline.request("open-ils.circ.renew", vec![evergreen::hash! {"copy_id": copy_id}]);
+/// }
+///
+/// for resp in pipeline {
+///     match resp.result {
+///         Ok(values) => println!("request {} returned {values:?}", resp.index),
+///         Err(e) => eprintln!("request {} failed: {e}", resp.index),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Pipeline {
+    session: Rc<RefCell<ClientSessionInternal>>,
+    max_in_flight: usize,
+    timeout: u64,
+    queued: VecDeque<(usize, String, ApiParams)>,
+    in_flight: Vec<PipelinedRequest>,
+    request_count: usize,
+}
+
+impl Pipeline {
+    fn new(session: Rc<RefCell<ClientSessionInternal>>, max_in_flight: usize) -> Pipeline {
+        Pipeline {
+            session,
+            max_in_flight: max_in_flight.max(1),
+            timeout: DEFAULT_REQUEST_TIMEOUT,
+            queued: VecDeque::new(),
+            in_flight: Vec::new(),
+            request_count: 0,
+        }
+    }
+
+    /// Seconds to wait for any activity before the oldest in-flight
+    /// request fails with a timeout.
+    pub fn set_timeout(&mut self, timeout: u64) {
+        self.timeout = timeout;
+    }
+
+    /// Queue a request to be sent once there is room in the pipeline.
+    ///
+    /// Returns the index of the request, which is included in its
+    /// [`PipelineResponse`].
+    pub fn request(&mut self, method: &str, params: impl Into<ApiParams>) -> usize {
+        let index = self.request_count;
+        self.request_count += 1;
+        self.queued
+            .push_back((index, method.to_string(), params.into()));
+        index
+    }
+
+    /// Number of requests sent and awaiting completion.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Number of requests not yet sent.
+    pub fn queued(&self) -> usize {
+        self.queued.len()
+    }
+
+    /// True if every queued request has completed.
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty() && self.in_flight.is_empty()
+    }
+
+    /// Send queued requests until the pipeline is full.
+    ///
+    /// Returns the response for a request which could not be sent.
+    fn fill(&mut self) -> Option<PipelineResponse> {
+        while self.in_flight.len() < self.max_in_flight {
+            let (index, method, params) = self.queued.pop_front()?;
+
+            let trace = match self.session.borrow_mut().request(&method, params) {
+                Ok(t) => t,
+                Err(e) => {
+                    return Some(PipelineResponse {
+                        index,
+                        result: Err(e),
+                    })
+                }
+            };
+
+            let thread = self.session.borrow().thread().to_string();
+
+            self.in_flight.push(PipelinedRequest {
+                index,
+                request: Request::new(thread, self.session.clone(), trace),
+                responses: Vec::new(),
+            });
+        }
+
+        None
+    }
+
+    /// Pull any available responses for our in-flight requests
+    /// without blocking.
+    ///
+    /// Returns the response for the first request found to be
+    /// complete or failed.
+    fn drain(&mut self) -> Option<PipelineResponse> {
+        for pos in 0..self.in_flight.len() {
+            let req = &mut self.in_flight[pos];
+
+            loop {
+                match req.request.recv_with_timeout(0) {
+                    Ok(Some(value)) => req.responses.push(value),
+                    Ok(None) => break,
+                    Err(e) => {
+                        let req = self.in_flight.remove(pos);
+                        return Some(PipelineResponse {
+                            index: req.index,
+                            result: Err(e),
+                        });
+                    }
+                }
+            }
+
+            if req.request.complete() {
+                let req = self.in_flight.remove(pos);
+                return Some(PipelineResponse {
+                    index: req.index,
+                    result: Ok(req.responses),
+                });
+            }
+        }
+
+        None
+    }
+}
+
+impl Iterator for Pipeline {
+    type Item = PipelineResponse;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(resp) = self.fill() {
+            return Some(resp);
+        }
+
+        loop {
+            if self.in_flight.is_empty() {
+                return None;
+            }
+
+            if let Some(resp) = self.drain() {
+                return Some(resp);
+            }
+
+            // Status messages (e.g. CONTINUE) produce no response
+            // value, so replies may remain in the backlog.
+            let backlogged = {
+                let ses = self.session.borrow();
+                self.in_flight
+                    .iter()
+                    .any(|r| ses.has_backlog_for(r.request.thread_trace()))
+            };
+
+            if backlogged {
+                continue;
+            }
+
+            let arrived = match self.session.borrow_mut().wait(self.timeout) {
+                Ok(a) => a,
+                Err(e) => {
+                    let req = self.in_flight.remove(0);
+                    return Some(PipelineResponse {
+                        index: req.index,
+                        result: Err(e),
+                    });
+                }
+            };
+
+            if !arrived {
+                let req = self.in_flight.remove(0);
+                log::warn!("Pipelined request {} timed out", req.index);
+
+                return Some(PipelineResponse {
+                    index: req.index,
+                    result: Err(format!("Request {} timed out", req.index).into()),
+                });
+            }
+        }
+    }
 }
 
 /// Iterates over a series of replies to an API request.
@@ -9,15 +9,18 @@ use crate::osrf::message::MessageStatus;
 use crate::osrf::message::MessageType;
 use crate::osrf::message::Payload;
 use crate::osrf::message::TransportMessage;
+use crate::osrf::method;
 use crate::osrf::method::ParamCount;
 use crate::osrf::sclient::HostSettings;
 use crate::osrf::server::Server;
 use crate::osrf::session::ServerSession;
 use crate::util;
 use crate::EgResult;
+use crate::EgValue;
 use mptc::signals::SignalTracker;
 use std::cell::RefMut;
 use std::fmt;
+use std::net::IpAddr;
 use std::sync::mpsc;
 use std::thread;
 use std::time;
@@ -445,11 +448,16 @@ impl Worker {
 
         let param_count = method_call.params().len();
         let api_name = method_call.method().to_string();
+        let remote_ip = msg.remote_ip().map(|ip| ip.to_string());
 
         let log_params = util::stringify_params(
             &api_name,
             method_call.params(),
             conf::config().log_protect(),
+            &method::redacted_params(
+                &api_name,
+                Server::methods().get(api_name.trim_end_matches(".atomic")),
+            ),
         );
 
         // Log the API call
@@ -491,6 +499,15 @@ impl Worker {
             );
         }
 
+        if let Some(reason) = self.check_method_acls(&method_call, remote_ip.as_deref())? {
+            log::warn!("{self} access denied to {api_name}: {reason}");
+
+            return self.reply_with_status(
+                MessageStatus::Forbidden,
+                &format!("ACCESS_DENIED: {reason}"),
+            );
+        }
+
         let method_def = method_def.unwrap();
         let pcount = method_def.param_count();
 
@@ -545,6 +562,84 @@ impl Worker {
         }
     }
 
+    /// Returns the reason the caller may not call the API, if any,
+    /// per the method_acl configs.
+    fn check_method_acls(
+        &self,
+        method_call: &message::MethodCall,
+        remote_ip: Option<&str>,
+    ) -> EgResult<Option<String>> {
+        let api_name = method_call.method();
+        let acls = conf::config().method_acls(api_name);
+
+        if acls.is_empty() {
+            return Ok(None);
+        }
+
+        let sender_domain = self.session().sender().domain();
+        let remote_ip: Option<IpAddr> = remote_ip.and_then(|ip| ip.parse().ok());
+
+        // Retrieved at most once, only if needed.
+        let mut auth_user: Option<Option<EgValue>> = None;
+
+        for acl in acls {
+            if acl.internal() && sender_domain != conf::config().client().domain().name() {
+                return Ok(Some(format!(
+                    "internal only; caller domain is {sender_domain}"
+                )));
+            }
+
+            if !acl.ip_ranges().is_empty() {
+                let allowed = remote_ip
+                    .as_ref()
+                    .is_some_and(|ip| acl.ip_ranges().iter().any(|r| r.contains(ip)));
+
+                if !allowed {
+                    return Ok(Some(format!("remote IP {remote_ip:?} not allowed")));
+                }
+            }
+
+            if !acl.authenticated() {
+                continue;
+            }
+
+            if auth_user.is_none() {
+                auth_user = Some(self.retrieve_auth_user(method_call)?);
+            }
+
+            let Some(Some(user)) = auth_user.as_ref() else {
+                return Ok(Some("authentication required".to_string()));
+            };
+
+            if !acl.workstations().is_empty() {
+                let wsid = user["wsid"].as_int();
+
+                if !wsid.is_some_and(|id| acl.workstations().contains(&id)) {
+                    return Ok(Some(format!("workstation {wsid:?} not allowed")));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the user linked to the authtoken passed as the first
+    /// parameter, or None if the token is missing or invalid.
+    fn retrieve_auth_user(&self, method_call: &message::MethodCall) -> EgResult<Option<EgValue>> {
+        let Some(token) = method_call.params().first().and_then(|p| p.as_str()) else {
+            return Ok(None);
+        };
+
+        let user = self.client.send_recv_one(
+            "open-ils.auth",
+            "open-ils.auth.session.retrieve",
+            EgValue::from(token),
+        )?;
+
+        // Failures come back as events.
+        Ok(user.filter(|u| u.has_key("usrname")))
+    }
+
     fn reply_server_error(&mut self, text: &str) -> EgResult<()> {
         self.connected = false;
 
@@ -1,15 +1,25 @@
+use eg::common::audit::{self, AuditEntry};
+use eg::common::feature_flag;
+use eg::common::org_tree;
 use eg::common::penalty;
 use eg::common::settings::Settings;
+use eg::common::statcat::{self, StatCatType};
+use eg::common::survey;
+use eg::common::task::Task;
 use eg::common::user;
+use eg::common::user_merge::{self, CardDisposition};
 use eg::osrf::app::ApplicationWorker;
 use eg::osrf::message;
 use eg::osrf::method::{ParamCount, ParamDataType, StaticMethodDef, StaticParam};
 use eg::osrf::session::ServerSession;
+use eg::util;
 use eg::Editor;
 use eg::EgResult;
 use eg::EgValue;
 use evergreen as eg;
 use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
 
 // Import our local app module
 use crate::app;
@@ -45,6 +55,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "Whole barcode or a partial 'completable' barcode",
             },
         ],
+        redacted_params: &[3],
     },
     StaticMethodDef {
         name: "user_has_work_perm_at.batch",
@@ -68,6 +79,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "User ID to check permissions for; defaults to the API requestor",
             },
         ],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "ou_setting.ancestor_default.batch",
@@ -91,6 +103,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "Authtoken.  Required for perm-protected settings",
             },
         ],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "settings.retrieve",
@@ -114,6 +127,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "",
             },
         ],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "user.opac.vital_stats",
@@ -132,6 +146,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "User ID whose stats to load; defaults to requestor",
             },
         ],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "user.penalties.update",
@@ -156,6 +171,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                     May be a list of strings (names) or numbers (IDs)",
             },
         ],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "user.penalties.update_at_home",
@@ -180,232 +196,1357 @@ pub static METHODS: &[StaticMethodDef] = &[
                     May be a list of strings (names) or numbers (IDs)",
             },
         ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "staff_audit.search",
+        desc: "Search the staff activity audit log",
+        param_count: ParamCount::Exactly(2),
+        handler: search_staff_audit,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Filters",
+                datatype: ParamDataType::Object,
+                desc: "Hash of filters: start_date, end_date, org_unit,
+                    staff, workstation, action, reason_code, target_type,
+                    target_id, limit, offset",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "staff_audit.purge",
+        desc: "Delete staff activity audit log entries older than the retention interval",
+        param_count: ParamCount::Exactly(2),
+        handler: purge_staff_audit,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Retention Interval",
+                datatype: ParamDataType::String,
+                desc: "Entries older than this interval are deleted, e.g. '1 year'",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "stat_cat.actor.retrieve.all",
+        desc: "Retrieve patron stat cats and entries visible at an org unit",
+        param_count: ParamCount::Range(1, 2),
+        handler: retrieve_stat_cats,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Org Unit ID",
+                datatype: ParamDataType::Number,
+                desc: "Defaults to the workstation org unit",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "stat_cat.asset.retrieve.all",
+        desc: "Retrieve copy stat cats and entries visible at an org unit",
+        param_count: ParamCount::Range(1, 2),
+        handler: retrieve_stat_cats,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Org Unit ID",
+                datatype: ParamDataType::Number,
+                desc: "Defaults to the workstation org unit",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "stat_cat.actor.save",
+        desc: "Create or update a patron stat cat along with the entries in its entries list.  Returns the stat cat ID",
+        param_count: ParamCount::Exactly(2),
+        handler: save_stat_cat,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Stat Cat",
+                datatype: ParamDataType::Object,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "stat_cat.asset.save",
+        desc: "Create or update a copy stat cat along with the entries in its entries list.  Returns the stat cat ID",
+        param_count: ParamCount::Exactly(2),
+        handler: save_stat_cat,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Stat Cat",
+                datatype: ParamDataType::Object,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "stat_cat.actor.delete",
+        desc: "Delete a patron stat cat, its entries, and all patron values for the stat cat",
+        param_count: ParamCount::Exactly(2),
+        handler: delete_stat_cat,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Stat Cat ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "stat_cat.asset.delete",
+        desc: "Delete a copy stat cat, its entries, and all copy values for the stat cat",
+        param_count: ParamCount::Exactly(2),
+        handler: delete_stat_cat,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Stat Cat ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "stat_cat_entry.actor.delete",
+        desc: "Delete a patron stat cat entry and all patron values using the entry",
+        param_count: ParamCount::Exactly(2),
+        handler: delete_stat_cat_entry,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Stat Cat Entry ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "stat_cat_entry.asset.delete",
+        desc: "Delete a copy stat cat entry and all copy values using the entry",
+        param_count: ParamCount::Exactly(2),
+        handler: delete_stat_cat_entry,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Stat Cat Entry ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "user.stat_cat_values.update",
+        desc: "Apply patron stat cat values.  Fails if the patron would be missing a value for a required stat cat",
+        param_count: ParamCount::Exactly(3),
+        handler: update_user_stat_cat_values,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "User ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+            StaticParam {
+                name: "Values",
+                datatype: ParamDataType::Array,
+                desc: "List of {stat_cat: ID, value: string}.  An empty value removes the patron value",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "survey.retrieve.all",
+        desc: "Retrieve surveys with questions and answers visible at an org unit",
+        param_count: ParamCount::Range(1, 3),
+        handler: retrieve_surveys,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Org Unit ID",
+                datatype: ParamDataType::Number,
+                desc: "Defaults to the workstation org unit",
+            },
+            StaticParam {
+                name: "Options",
+                datatype: ParamDataType::Object,
+                desc: "Hash of options: active_only, required_only",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "survey.save",
+        desc: "Create or update a survey along with its questions and answers.  Returns the survey ID",
+        param_count: ParamCount::Exactly(2),
+        handler: save_survey,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Survey",
+                datatype: ParamDataType::Object,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "survey.response.create",
+        desc: "Record a patron's survey responses, e.g. at registration",
+        param_count: ParamCount::Exactly(3),
+        handler: create_survey_responses,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "User ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+            StaticParam {
+                name: "Responses",
+                datatype: ParamDataType::Array,
+                desc: "List of {survey: ID, question: ID, answer: ID}",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "user.merge",
+        desc: "Merge a patron into a lead patron via actor.usr_merge(),
+            which moves everything linked to the patron and deletes it.
+            Returns an unmerge report listing the patron's circulations,
+            bills, holds, notes, penalties, stat cat values, and cards
+            as they were before the merge",
+        param_count: ParamCount::Range(3, 4),
+        handler: merge_users,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Lead User ID",
+                datatype: ParamDataType::Number,
+                desc: "Patron who receives the merged data",
+            },
+            StaticParam {
+                name: "Source User ID",
+                datatype: ParamDataType::Number,
+                desc: "Patron to merge and delete",
+            },
+            StaticParam {
+                name: "Options",
+                datatype: ParamDataType::Object,
+                desc: "Hash of options: card_disposition ('move' or
+                    'delete', defaults to 'move').  Moved cards are
+                    deactivated",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "task.retrieve",
+        desc: "Retrieve the progress of a long-running task.  Returns
+            {id, label, status, done, total, message, result, ...} or
+            null if the task does not exist or has expired",
+        param_count: ParamCount::Exactly(2),
+        handler: retrieve_task,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Task ID",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "task.watch",
+        desc: "Stream the progress of a long-running task.  Responds with
+            the current task state, then again each time the task is
+            updated, until the task finishes or the timeout expires.
+            Watches are capped at 30 seconds; callers watching longer
+            tasks should call again with the same task ID",
+        param_count: ParamCount::Range(2, 3),
+        handler: watch_task,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Task ID",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Timeout",
+                datatype: ParamDataType::Number,
+                desc: "Maximum number of seconds to watch.  Defaults to and
+                    may not exceed 30",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "org_tree.display.retrieve",
+        desc: "Retrieve the org unit tree to display for a purpose, e.g.
+            'opac'.  Returns the active custom tree for the purpose when
+            one exists, otherwise the full org tree",
+        param_count: ParamCount::Exactly(1),
+        handler: retrieve_display_org_tree,
+        params: &[StaticParam {
+            name: "Purpose",
+            datatype: ParamDataType::String,
+            desc: "",
+        }],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "org_tree.custom.retrieve",
+        desc: "Retrieve the custom org tree for a purpose as
+            {id, purpose, active, root}, where root is a nested
+            {org_unit, children} hash.  Returns null if none exists",
+        param_count: ParamCount::Exactly(2),
+        handler: retrieve_custom_org_tree,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Purpose",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "org_tree.custom.save",
+        desc: "Create or replace the custom org tree for a purpose.
+            Returns the custom tree ID",
+        param_count: ParamCount::Exactly(2),
+        handler: save_custom_org_tree,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Tree",
+                datatype: ParamDataType::Object,
+                desc: "Custom tree in the format returned by
+                    org_tree.custom.retrieve.  Siblings are displayed
+                    in the order listed",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "org_tree.custom.delete",
+        desc: "Delete a custom org tree",
+        param_count: ParamCount::Exactly(2),
+        handler: delete_custom_org_tree,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Custom Tree ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "feature_flag.retrieve.all",
+        desc: "Retrieve all feature flags",
+        param_count: ParamCount::Exactly(1),
+        handler: retrieve_feature_flags,
+        params: &[StaticParam {
+            name: "Authtoken",
+            datatype: ParamDataType::String,
+            desc: "",
+        }],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "feature_flag.check",
+        desc: "Returns true if a feature flag applies for the org unit
+            and bucketing key",
+        param_count: ParamCount::Range(2, 4),
+        handler: check_feature_flag,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Flag Name",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Org Unit ID",
+                datatype: ParamDataType::Number,
+                desc: "Defaults to the workstation org unit",
+            },
+            StaticParam {
+                name: "Key",
+                datatype: ParamDataType::String,
+                desc: "Bucketing key for percentage rollouts",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "feature_flag.update",
+        desc: "Create or update a feature flag.  Changes apply to
+            running services once their flag cache expires",
+        param_count: ParamCount::Exactly(2),
+        handler: update_feature_flag,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Flag",
+                datatype: ParamDataType::Object,
+                desc: "Hash of name, enabled, percent, and orgs",
+            },
+        ],
+        redacted_params: &[],
     },
 ];
 
-pub fn get_barcodes(
+pub fn get_barcodes(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    // Cast our worker instance into something we know how to use.
+    let worker = app::ActorWorker::downcast(worker)?;
+
+    // Extract the method call parameters.
+    // Incorrectly shaped parameters will result in an error
+    // response to the caller.
+    let authtoken = method.param(0).str()?;
+    let org_id = method.param(1).int()?;
+    let context = method.param(2).str()?;
+    let barcode = method.param(3).str()?;
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    // Auth check
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    // Perm check
+    if !editor.allowed_at("STAFF_LOGIN", org_id)? {
+        return session.respond(editor.event());
+    }
+
+    // Inline JSON object construction
+    let query = eg::hash! {
+        from: [
+            "evergreen.get_barcodes",
+            org_id, context, barcode
+        ]
+    };
+
+    let result = editor.json_query(query)?;
+
+    if context.ne("actor") {
+        // Perm checks not needed for asset/serial/booking items.
+        return session.respond(result);
+    }
+
+    let requestor_id = editor.requestor_id()?;
+    let mut response: Vec<EgValue> = Vec::new();
+
+    // "actor" barcodes require additional perm checks.
+    for user_row in result {
+        let user_id = user_row.id()?;
+
+        if user_id == requestor_id {
+            // We're allowed to know about ourselves.
+            response.push(user_row);
+            continue;
+        }
+
+        // Do we have permission to view info about this user?
+        let u = editor.retrieve("au", user_id)?.unwrap();
+        let home_ou = u["home_ou"].int()?;
+
+        if editor.allowed_at("VIEW_USER", home_ou)? {
+            response.push(user_row);
+        } else {
+            response.push(editor.event());
+        }
+    }
+
+    session.respond(response)
+}
+
+/// Returns a map of permission name to a list of org units where the
+/// provided user (or the caller, if no user is specified) has each
+/// of the provided permissions.
+pub fn user_has_work_perm_at_batch(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    // Cast our worker instance into something we know how to use.
+    let worker = app::ActorWorker::downcast(worker)?;
+
+    let authtoken = method.param(0).str()?;
+
+    let perm_names: Vec<&str> = method
+        .param(1)
+        .members() // json array iterator
+        .filter(|v| v.is_string())
+        .map(|v| v.as_str().unwrap())
+        .collect();
+
+    if perm_names.is_empty() {
+        return Ok(());
+    }
+
+    let mut editor = Editor::new(worker.client());
+
+    if !editor.apply_authtoken(authtoken)? {
+        return session.respond(editor.event());
+    }
+
+    // user_id parameter is optional
+    let user_id = match method.params().get(2) {
+        Some(id) => id.int()?,
+        None => editor.requestor_id()?,
+    };
+
+    let mut map: HashMap<String, Vec<i64>> = HashMap::new();
+    for perm in perm_names {
+        map.insert(
+            perm.to_string(),
+            user::has_work_perm_at(&mut editor, user_id, perm)?,
+        );
+    }
+
+    session.respond(map)
+}
+
+pub fn retrieve_cascade_settigs(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+
+    let setting_names: Vec<&str> = method
+        .param(0)
+        .members() // json array iterator
+        .filter(|v| v.is_string())
+        .map(|v| v.as_str().unwrap())
+        .collect();
+
+    if setting_names.is_empty() {
+        return Ok(());
+    }
+
+    let mut editor = Editor::new(worker.client());
+
+    // Authtoken is optional.  If set, verify it's valid and absorb
+    // it into our editor so its context info can be picked up by
+    // our Settings instance.
+    if let Some(token) = method.param(1).as_str() {
+        if !editor.apply_authtoken(token)? {
+            return session.respond(editor.event());
+        }
+    }
+
+    let mut settings = Settings::new(&editor);
+
+    // If the caller requests values for a specific org unit, that
+    // supersedes the org unit potentially linked to the workstation.
+    if let Some(org_id) = method.param(2).as_i64() {
+        settings.set_org_id(org_id);
+    }
+
+    // Pre-cache the settings en masse, then pull each from the settings
+    // cache and return to the caller.
+    settings.fetch_values(setting_names.as_slice())?;
+
+    for name in setting_names {
+        let mut obj = EgValue::new_object();
+        obj[name] = settings.get_value(name)?.clone();
+        session.respond(obj)?;
+    }
+
+    Ok(())
+}
+
+pub fn ou_setting_ancestor_default_batch(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let org_id = method.param(0).int()?;
+
+    let setting_names: Vec<&str> = method
+        .param(1)
+        .members() // iterate json array
+        .filter(|v| v.is_string())
+        .map(|v| v.as_str().unwrap())
+        .collect();
+
+    if setting_names.is_empty() {
+        return Ok(());
+    }
+
+    let mut editor = Editor::new(worker.client());
+    let mut settings = Settings::new(&editor);
+
+    settings.set_org_id(org_id);
+
+    // If available, apply the authtoken to the editor after we apply
+    // the org id to the Settings instance (above).  Otherwise, the
+    // workstation org unit could supersede the requested org id.
+    if let Some(token) = method.param(2).as_str() {
+        // Authtoken is only required for perm-lmited org settings.
+        // If it's provided, though, we gotta check it.
+        if !editor.apply_authtoken(token)? {
+            return session.respond(editor.event());
+        }
+        settings.set_user_id(editor.requestor_id()?);
+    }
+
+    // Pre-cache the settings en masse, then pull each from the settings
+    // cache and return to the caller.
+    settings.fetch_values(setting_names.as_slice())?;
+
+    for name in setting_names {
+        let mut obj = EgValue::new_object();
+        obj[name] = settings.get_value(name)?.clone();
+        session.respond(obj)?;
+    }
+
+    Ok(())
+}
+
+pub fn user_opac_vital_stats(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let user_id = method.param(1).as_i64().unwrap_or(editor.requestor_id()?);
+    let mut user = match editor.retrieve("au", user_id)? {
+        Some(u) => u,
+        None => return session.respond(editor.event()),
+    };
+
+    if user_id != editor.requestor_id()? {
+        let home_ou = user["home_ou"].int()?;
+
+        // This list of perms seems like overkill for summary data, but
+        // it matches the perm checks of the existing open-ils.actor APIs.
+        if !editor.allowed_at("VIEW_USER", home_ou)?
+            || !editor.allowed_at("VIEW_USER_FINES_SUMMARY", home_ou)?
+            || !editor.allowed_at("VIEW_CIRCULATIONS", home_ou)?
+            || !editor.allowed_at("VIEW_HOLD", home_ou)?
+        {
+            return session.respond(editor.event());
+        }
+    }
+
+    let holds = user::active_hold_counts(&mut editor, user_id)?;
+    let fines = user::fines_summary(&mut editor, user_id)?;
+    let checkouts = user::open_checkout_counts(&mut editor, user_id)?;
+
+    let unread_query = eg::hash! {
+        select: {aum: [{
+            column: "id",
+            transform: "count",
+            aggregate: 1,
+            alias: "count",
+        }]},
+        from: "aum",
+        where: {
+            usr: user_id,
+            read_date: EgValue::Null,
+            deleted: "f",
+            pub: "t",
+        }
+    };
+
+    let mut unread_count = 0;
+    if let Some(unread) = editor.json_query(unread_query)?.first() {
+        unread_count = unread["count"].int()?;
+    }
+
+    let resp = eg::hash! {
+        fines: fines,
+        holds: holds,
+        checkouts: checkouts,
+        messages: {unread: unread_count},
+        user: {
+            first_given_name: user["first_given_name"].take(),
+            second_given_name: user["second_given_name"].take(),
+            family_name: user["family_name"].take(),
+            alias: user["alias"].take(),
+            usrname: user["usrname"].take(),
+        },
+    };
+
+    session.respond(resp)
+}
+
+pub fn update_penalties(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let user_id = method.param(1).int()?;
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let user = match editor.retrieve("au", user_id)? {
+        Some(u) => u,
+        None => return session.respond(editor.event()),
+    };
+
+    let mut context_org = user["home_ou"].int()?;
+
+    if !editor.allowed_at("UPDATE_USER", context_org)? {
+        return session.respond(editor.event());
+    }
+
+    if method.method().contains("_at_home") {
+        if let Some(org) = editor.requestor_ws_ou() {
+            context_org = org;
+        }
+    }
+
+    let only_penalties = match method.params().get(2) {
+        Some(EgValue::Array(arr)) => Some(arr),
+        _ => None,
+    };
+
+    editor.xact_begin()?;
+
+    penalty::calculate_penalties(&mut editor, user_id, context_org, only_penalties)?;
+
+    editor.commit()?;
+
+    session.respond(1)
+}
+
+pub fn search_staff_audit(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let filters = method.param(1);
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let mut filters = filters.clone();
+
+    // Filtering on a single org unit checks permissions at that org.
+    // Otherwise, results are limited to the org units where the
+    // requestor has the permission.
+    if let Some(org_id) = filters["org_unit"].as_int() {
+        if !editor.allowed_at("VIEW_STAFF_AUDIT_LOG", org_id)? {
+            return session.respond(editor.event());
+        }
+    } else {
+        let requestor_id = editor.requestor_id()?;
+        let perm_orgs = user::has_work_perm_at(&mut editor, requestor_id, "VIEW_STAFF_AUDIT_LOG")?;
+
+        if perm_orgs.is_empty() {
+            // Sets the permission failure event.
+            editor.allowed("VIEW_STAFF_AUDIT_LOG")?;
+            return session.respond(editor.event());
+        }
+
+        let orgs: Vec<i64> = if filters["org_unit"].is_array() {
+            filters["org_unit"]
+                .members()
+                .filter_map(|o| o.as_int())
+                .filter(|o| perm_orgs.contains(o))
+                .collect()
+        } else {
+            perm_orgs
+        };
+
+        if orgs.is_empty() {
+            return Ok(());
+        }
+
+        filters["org_unit"] = EgValue::from(orgs);
+    }
+
+    for entry in audit::search(&mut editor, &filters)? {
+        session.respond(entry)?;
+    }
+
+    Ok(())
+}
+
+pub fn purge_staff_audit(
     worker: &mut Box<dyn ApplicationWorker>,
     session: &mut ServerSession,
     method: message::MethodCall,
 ) -> EgResult<()> {
-    // Cast our worker instance into something we know how to use.
     let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let retention = method.param(1).str()?;
 
-    // Extract the method call parameters.
-    // Incorrectly shaped parameters will result in an error
-    // response to the caller.
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    if !editor.allowed("PURGE_STAFF_AUDIT_LOG")? {
+        return session.respond(editor.event());
+    }
+
+    // Purging manages its own transactions.
+    let count = audit::purge(&mut editor, retention)?;
+
+    session.respond(count)
+}
+
+/// Returns true if the requestor may modify the provided user.
+fn user_update_allowed(editor: &mut Editor, user_id: i64) -> EgResult<bool> {
+    let user = match editor.retrieve("au", user_id)? {
+        Some(u) => u,
+        None => return Ok(false),
+    };
+
+    editor.allowed_at("UPDATE_USER", user["home_ou"].int()?)
+}
+
+pub fn retrieve_stat_cats(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
     let authtoken = method.param(0).str()?;
-    let org_id = method.param(1).int()?;
-    let context = method.param(2).str()?;
-    let barcode = method.param(3).str()?;
+    let sctype = StatCatType::from_method(method.method());
 
     let mut editor = Editor::with_auth(worker.client(), authtoken);
 
-    // Auth check
     if !editor.checkauth()? {
         return session.respond(editor.event());
     }
 
-    // Perm check
-    if !editor.allowed_at("STAFF_LOGIN", org_id)? {
+    let org_id = method.param(1).as_int().unwrap_or(editor.perm_org());
+
+    let cats = statcat::visible_stat_cats(&mut editor, sctype, org_id)?;
+
+    session.respond(cats)
+}
+
+pub fn save_stat_cat(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let cat = method.param(1).clone();
+    let sctype = StatCatType::from_method(method.method());
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
         return session.respond(editor.event());
     }
 
-    // Inline JSON object construction
-    let query = eg::hash! {
-        from: [
-            "evergreen.get_barcodes",
-            org_id, context, barcode
-        ]
+    editor.xact_begin()?;
+
+    let cat_id = statcat::save_stat_cat(&mut editor, sctype, cat)?;
+
+    editor.commit()?;
+
+    session.respond(cat_id)
+}
+
+pub fn delete_stat_cat(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let cat_id = method.param(1).int()?;
+    let sctype = StatCatType::from_method(method.method());
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    editor.xact_begin()?;
+
+    statcat::delete_stat_cat(&mut editor, sctype, cat_id)?;
+
+    editor.commit()?;
+
+    session.respond(1)
+}
+
+pub fn delete_stat_cat_entry(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let entry_id = method.param(1).int()?;
+    let sctype = StatCatType::from_method(method.method());
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    editor.xact_begin()?;
+
+    statcat::delete_entry(&mut editor, sctype, entry_id)?;
+
+    editor.commit()?;
+
+    session.respond(1)
+}
+
+pub fn update_user_stat_cat_values(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let user_id = method.param(1).int()?;
+    let values = method.param(2);
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let user = match editor.retrieve("au", user_id)? {
+        Some(u) => u,
+        None => return session.respond(editor.event()),
     };
 
-    let result = editor.json_query(query)?;
+    let home_ou = user["home_ou"].int()?;
 
-    if context.ne("actor") {
-        // Perm checks not needed for asset/serial/booking items.
-        return session.respond(result);
+    if !editor.allowed_at("UPDATE_USER", home_ou)? {
+        return session.respond(editor.event());
     }
 
-    let requestor_id = editor.requestor_id()?;
-    let mut response: Vec<EgValue> = Vec::new();
+    editor.xact_begin()?;
 
-    // "actor" barcodes require additional perm checks.
-    for user_row in result {
-        let user_id = user_row.id()?;
+    statcat::set_user_values(&mut editor, user_id, values)?;
+
+    let missing = statcat::missing_required_user_values(&mut editor, user_id, home_ou)?;
+
+    if !missing.is_empty() {
+        editor.rollback()?;
+        return Err(
+            format!("User {user_id} has no value for required stat cats {missing:?}").into(),
+        );
+    }
+
+    if audit::is_enabled() {
+        let mut entry = AuditEntry::new(method.method());
+        entry.set_target("au", user_id);
+        audit::log_action(&mut editor, &entry)?;
+    }
+
+    editor.commit()?;
+
+    session.respond(1)
+}
+
+pub fn retrieve_surveys(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let options = method.param(2);
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let org_id = method.param(1).as_int().unwrap_or(editor.perm_org());
+
+    let surveys = survey::visible_surveys(
+        &mut editor,
+        org_id,
+        options["active_only"].boolish(),
+        options["required_only"].boolish(),
+    )?;
+
+    session.respond(surveys)
+}
+
+pub fn save_survey(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let survey = method.param(1).clone();
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    editor.xact_begin()?;
+
+    let survey_id = survey::save_survey(&mut editor, survey)?;
+
+    editor.commit()?;
+
+    session.respond(survey_id)
+}
+
+pub fn create_survey_responses(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let user_id = method.param(1).int()?;
+    let responses = method.param(2);
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    // Patrons may record their own responses.
+    if user_id != editor.requestor_id()? && !user_update_allowed(&mut editor, user_id)? {
+        return session.respond(editor.event());
+    }
+
+    editor.xact_begin()?;
+
+    let created = survey::record_responses(&mut editor, user_id, responses)?;
+
+    editor.commit()?;
+
+    session.respond(created)
+}
+
+pub fn merge_users(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let lead_id = method.param(1).int()?;
+    let source_id = method.param(2).int()?;
+    let options = method.param(3);
+
+    let card_disposition = match options["card_disposition"].as_str() {
+        Some(d) => CardDisposition::try_from(d)?,
+        None => CardDisposition::Move,
+    };
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    editor.xact_begin()?;
+
+    // Report why a merge failed instead of a generic server error.
+    let report = match user_merge::merge(&mut editor, lead_id, source_id, card_disposition) {
+        Ok(r) => r,
+        Err(e) => {
+            editor.rollback()?;
+            return session.respond(e.event_or_default());
+        }
+    };
+
+    editor.commit()?;
+
+    session.respond(report.to_value())
+}
+
+pub fn retrieve_display_org_tree(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let purpose = method.param(0).str()?;
+
+    let mut editor = Editor::new(worker.client());
 
-        if user_id == requestor_id {
-            // We're allowed to know about ourselves.
-            response.push(user_row);
-            continue;
-        }
+    session.respond(org_tree::display_tree(&mut editor, purpose)?)
+}
 
-        // Do we have permission to view info about this user?
-        let u = editor.retrieve("au", user_id)?.unwrap();
-        let home_ou = u["home_ou"].int()?;
+pub fn retrieve_custom_org_tree(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let purpose = method.param(1).str()?;
 
-        if editor.allowed_at("VIEW_USER", home_ou)? {
-            response.push(user_row);
-        } else {
-            response.push(editor.event());
-        }
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
     }
 
-    session.respond(response)
+    match org_tree::custom_tree(&mut editor, purpose)? {
+        Some(tree) => session.respond(tree),
+        None => session.respond(EgValue::Null),
+    }
 }
 
-/// Returns a map of permission name to a list of org units where the
-/// provided user (or the caller, if no user is specified) has each
-/// of the provided permissions.
-pub fn user_has_work_perm_at_batch(
+pub fn save_custom_org_tree(
     worker: &mut Box<dyn ApplicationWorker>,
     session: &mut ServerSession,
     method: message::MethodCall,
 ) -> EgResult<()> {
-    // Cast our worker instance into something we know how to use.
     let worker = app::ActorWorker::downcast(worker)?;
-
     let authtoken = method.param(0).str()?;
+    let tree = method.param(1);
 
-    let perm_names: Vec<&str> = method
-        .param(1)
-        .members() // json array iterator
-        .filter(|v| v.is_string())
-        .map(|v| v.as_str().unwrap())
-        .collect();
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
 
-    if perm_names.is_empty() {
-        return Ok(());
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
     }
 
-    let mut editor = Editor::new(worker.client());
-
-    if !editor.apply_authtoken(authtoken)? {
+    if !editor.allowed("ADMIN_ORG_UNIT_CUSTOM_TREE")? {
         return session.respond(editor.event());
     }
 
-    // user_id parameter is optional
-    let user_id = match method.params().get(2) {
-        Some(id) => id.int()?,
-        None => editor.requestor_id()?,
-    };
+    editor.xact_begin()?;
 
-    let mut map: HashMap<String, Vec<i64>> = HashMap::new();
-    for perm in perm_names {
-        map.insert(
-            perm.to_string(),
-            user::has_work_perm_at(&mut editor, user_id, perm)?,
-        );
-    }
+    let tree_id = org_tree::save_custom_tree(&mut editor, tree)?;
 
-    session.respond(map)
+    editor.commit()?;
+
+    session.respond(tree_id)
 }
 
-pub fn retrieve_cascade_settigs(
+pub fn delete_custom_org_tree(
     worker: &mut Box<dyn ApplicationWorker>,
     session: &mut ServerSession,
     method: message::MethodCall,
 ) -> EgResult<()> {
     let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let tree_id = method.param(1).int()?;
 
-    let setting_names: Vec<&str> = method
-        .param(0)
-        .members() // json array iterator
-        .filter(|v| v.is_string())
-        .map(|v| v.as_str().unwrap())
-        .collect();
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
 
-    if setting_names.is_empty() {
-        return Ok(());
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
     }
 
-    let mut editor = Editor::new(worker.client());
-
-    // Authtoken is optional.  If set, verify it's valid and absorb
-    // it into our editor so its context info can be picked up by
-    // our Settings instance.
-    if let Some(token) = method.param(1).as_str() {
-        if !editor.apply_authtoken(token)? {
-            return session.respond(editor.event());
-        }
+    if !editor.allowed("ADMIN_ORG_UNIT_CUSTOM_TREE")? {
+        return session.respond(editor.event());
     }
 
-    let mut settings = Settings::new(&editor);
-
-    // If the caller requests values for a specific org unit, that
-    // supersedes the org unit potentially linked to the workstation.
-    if let Some(org_id) = method.param(2).as_i64() {
-        settings.set_org_id(org_id);
-    }
+    editor.xact_begin()?;
 
-    // Pre-cache the settings en masse, then pull each from the settings
-    // cache and return to the caller.
-    settings.fetch_values(setting_names.as_slice())?;
+    org_tree::delete_custom_tree(&mut editor, tree_id)?;
 
-    for name in setting_names {
-        let mut obj = EgValue::new_object();
-        obj[name] = settings.get_value(name)?.clone();
-        session.respond(obj)?;
-    }
+    editor.commit()?;
 
-    Ok(())
+    session.respond(1)
 }
 
-pub fn ou_setting_ancestor_default_batch(
+pub fn retrieve_feature_flags(
     worker: &mut Box<dyn ApplicationWorker>,
     session: &mut ServerSession,
     method: message::MethodCall,
 ) -> EgResult<()> {
     let worker = app::ActorWorker::downcast(worker)?;
-    let org_id = method.param(0).int()?;
+    let authtoken = method.param(0).str()?;
 
-    let setting_names: Vec<&str> = method
-        .param(1)
-        .members() // iterate json array
-        .filter(|v| v.is_string())
-        .map(|v| v.as_str().unwrap())
-        .collect();
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
 
-    if setting_names.is_empty() {
-        return Ok(());
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
     }
 
-    let mut editor = Editor::new(worker.client());
-    let mut settings = Settings::new(&editor);
+    let flags: Vec<EgValue> = feature_flag::list(&mut editor)?
+        .iter()
+        .map(|f| f.to_value())
+        .collect();
 
-    settings.set_org_id(org_id);
+    session.respond(flags)
+}
 
-    // If available, apply the authtoken to the editor after we apply
-    // the org id to the Settings instance (above).  Otherwise, the
-    // workstation org unit could supersede the requested org id.
-    if let Some(token) = method.param(2).as_str() {
-        // Authtoken is only required for perm-lmited org settings.
-        // If it's provided, though, we gotta check it.
-        if !editor.apply_authtoken(token)? {
-            return session.respond(editor.event());
-        }
-        settings.set_user_id(editor.requestor_id()?);
-    }
+pub fn check_feature_flag(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let name = method.param(1).str()?;
+    let key = method.param(3).as_str();
 
-    // Pre-cache the settings en masse, then pull each from the settings
-    // cache and return to the caller.
-    settings.fetch_values(setting_names.as_slice())?;
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
 
-    for name in setting_names {
-        let mut obj = EgValue::new_object();
-        obj[name] = settings.get_value(name)?.clone();
-        session.respond(obj)?;
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
     }
 
-    Ok(())
+    let org_id = method.param(2).as_int().unwrap_or(editor.perm_org());
+
+    let enabled = feature_flag::is_enabled(&mut editor, name, Some(org_id), key)?;
+
+    session.respond(enabled)
 }
 
-pub fn user_opac_vital_stats(
+pub fn update_feature_flag(
     worker: &mut Box<dyn ApplicationWorker>,
     session: &mut ServerSession,
     method: message::MethodCall,
 ) -> EgResult<()> {
     let worker = app::ActorWorker::downcast(worker)?;
     let authtoken = method.param(0).str()?;
+    let flag = method.param(1);
 
     let mut editor = Editor::with_auth(worker.client(), authtoken);
 
@@ -413,76 +1554,57 @@ pub fn user_opac_vital_stats(
         return session.respond(editor.event());
     }
 
-    let user_id = method.param(1).as_i64().unwrap_or(editor.requestor_id()?);
-    let mut user = match editor.retrieve("au", user_id)? {
-        Some(u) => u,
-        None => return session.respond(editor.event()),
-    };
+    if !editor.allowed("ADMIN_GLOBAL_FLAG")? {
+        return session.respond(editor.event());
+    }
 
-    if user_id != editor.requestor_id()? {
-        let home_ou = user["home_ou"].int()?;
+    editor.xact_begin()?;
 
-        // This list of perms seems like overkill for summary data, but
-        // it matches the perm checks of the existing open-ils.actor APIs.
-        if !editor.allowed_at("VIEW_USER", home_ou)?
-            || !editor.allowed_at("VIEW_USER_FINES_SUMMARY", home_ou)?
-            || !editor.allowed_at("VIEW_CIRCULATIONS", home_ou)?
-            || !editor.allowed_at("VIEW_HOLD", home_ou)?
-        {
-            return session.respond(editor.event());
-        }
-    }
+    let flag = feature_flag::save(&mut editor, flag)?;
 
-    let holds = user::active_hold_counts(&mut editor, user_id)?;
-    let fines = user::fines_summary(&mut editor, user_id)?;
-    let checkouts = user::open_checkout_counts(&mut editor, user_id)?;
+    editor.commit()?;
 
-    let unread_query = eg::hash! {
-        select: {aum: [{
-            column: "id",
-            transform: "count",
-            aggregate: 1,
-            alias: "count",
-        }]},
-        from: "aum",
-        where: {
-            usr: user_id,
-            read_date: EgValue::Null,
-            deleted: "f",
-            pub: "t",
-        }
-    };
+    session.respond(flag.to_value())
+}
 
-    let mut unread_count = 0;
-    if let Some(unread) = editor.json_query(unread_query)?.first() {
-        unread_count = unread["count"].int()?;
-    }
+/// Maximum time to stream task updates in seconds.
+///
+/// Watching ties up a worker, so watches are kept short and clients
+/// re-issue the call to keep watching.
+const MAX_TASK_WATCH_TIMEOUT: u64 = 30;
 
-    let resp = eg::hash! {
-        fines: fines,
-        holds: holds,
-        checkouts: checkouts,
-        messages: {unread: unread_count},
-        user: {
-            first_given_name: user["first_given_name"].take(),
-            second_given_name: user["second_given_name"].take(),
-            family_name: user["family_name"].take(),
-            alias: user["alias"].take(),
-            usrname: user["usrname"].take(),
-        },
+/// How often to check for task updates while watching.
+const TASK_WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Load a task, verifying it belongs to the requestor.
+///
+/// Returns None if the task does not exist or belongs to another user.
+fn load_owned_task(editor: &mut Editor, task_id: &str) -> EgResult<Option<Task>> {
+    let task = match Task::retrieve(task_id)? {
+        Some(t) => t,
+        None => return Ok(None),
     };
 
-    session.respond(resp)
+    if task.owner() != editor.requestor_id()? {
+        log::warn!(
+            "User {} attempted to access task {task_id} owned by user {}",
+            editor.requestor_id()?,
+            task.owner()
+        );
+        return Ok(None);
+    }
+
+    Ok(Some(task))
 }
 
-pub fn update_penalties(
+pub fn retrieve_task(
     worker: &mut Box<dyn ApplicationWorker>,
     session: &mut ServerSession,
     method: message::MethodCall,
 ) -> EgResult<()> {
     let worker = app::ActorWorker::downcast(worker)?;
     let authtoken = method.param(0).str()?;
-    let user_id = method.param(1).int()?;
+    let task_id = method.param(1).str()?;
 
     let mut editor = Editor::with_auth(worker.client(), authtoken);
 
@@ -490,33 +1612,58 @@ pub fn update_penalties(
         return session.respond(editor.event());
     }
 
-    let user = match editor.retrieve("au", user_id)? {
-        Some(u) => u,
-        None => return session.respond(editor.event()),
-    };
+    match load_owned_task(&mut editor, task_id)? {
+        Some(task) => session.respond(task.to_eg_value()),
+        None => session.respond(EgValue::Null),
+    }
+}
 
-    let mut context_org = user["home_ou"].int()?;
+pub fn watch_task(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::ActorWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let task_id = method.param(1).str()?;
 
-    if !editor.allowed_at("UPDATE_USER", context_org)? {
-        return session.respond(editor.event());
-    }
+    let timeout = method
+        .param(2)
+        .as_int()
+        .filter(|t| *t > 0)
+        .map(|t| (t as u64).min(MAX_TASK_WATCH_TIMEOUT))
+        .unwrap_or(MAX_TASK_WATCH_TIMEOUT);
 
-    if method.method().contains("_at_home") {
-        if let Some(org) = editor.requestor_ws_ou() {
-            context_org = org;
-        }
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
     }
 
-    let only_penalties = match method.params().get(2) {
-        Some(EgValue::Array(arr)) => Some(arr),
-        _ => None,
+    let mut task = match load_owned_task(&mut editor, task_id)? {
+        Some(t) => t,
+        None => return session.respond(EgValue::Null),
     };
 
-    editor.xact_begin()?;
+    session.respond(task.to_eg_value())?;
 
-    penalty::calculate_penalties(&mut editor, user_id, context_org, only_penalties)?;
+    let timer = util::Timer::new(timeout);
 
-    editor.commit()?;
+    while !task.is_finished() && !timer.done() {
+        thread::sleep(TASK_WATCH_INTERVAL);
 
-    session.respond(1)
+        let latest = match Task::retrieve(task_id)? {
+            Some(t) => t,
+            // Task expired without finishing.
+            None => break,
+        };
+
+        if latest.updated() != task.updated() {
+            session.respond(latest.to_eg_value())?;
+        }
+
+        task = latest;
+    }
+
+    Ok(())
 }
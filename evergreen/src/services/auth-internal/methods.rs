@@ -24,6 +24,7 @@ pub static METHODS: &[StaticMethodDef] = &[
             datatype: ParamDataType::Object,
             desc: "Hash of Login Options and Values",
         }],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "user.validate",
@@ -35,6 +36,7 @@ pub static METHODS: &[StaticMethodDef] = &[
             datatype: ParamDataType::Object,
             desc: "Hash of Login Options and Values",
         }],
+        redacted_params: &[],
     },
 ];
 
@@ -0,0 +1,763 @@
+use eg::common::bib;
+use eg::common::bucket::{self, BucketType};
+use eg::common::copy_alert;
+use eg::common::holdings;
+use eg::common::task::Task;
+use eg::editor::Editor;
+use eg::osrf::app::ApplicationWorker;
+use eg::osrf::message;
+use eg::osrf::method::{ParamCount, ParamDataType, StaticMethodDef, StaticParam};
+use eg::osrf::session::ServerSession;
+use eg::EgResult;
+use eg::EgValue;
+use evergreen as eg;
+use marctk::edit::EditTasks;
+
+// Import our local app module
+use crate::app;
+
+/// List of method definitions we know at compile time.
+///
+/// These will form the basis (and possibly all) of our published methods.
+pub static METHODS: &[StaticMethodDef] = &[
+    StaticMethodDef {
+        name: "asset.volume.transfer",
+        desc: "Transfer call numbers and their copies to another bib record
+            and optionally another owning library.  Streams one result
+            per call number",
+        param_count: ParamCount::Range(3, 4),
+        handler: transfer_volumes,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Call Number IDs",
+                datatype: ParamDataType::Array,
+                desc: "",
+            },
+            StaticParam {
+                name: "Bib Record ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+            StaticParam {
+                name: "Owning Library ID",
+                datatype: ParamDataType::Number,
+                desc: "Defaults to the current owning library of each call number",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "asset.volume.transfer.bucket",
+        desc: "Transfer the call numbers in a call number bucket to another
+            bib record and optionally another owning library.  Streams one
+            result per call number",
+        param_count: ParamCount::Range(3, 4),
+        handler: transfer_volumes,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Call Number Bucket ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+            StaticParam {
+                name: "Bib Record ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+            StaticParam {
+                name: "Owning Library ID",
+                datatype: ParamDataType::Number,
+                desc: "Defaults to the current owning library of each call number",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "asset.copy.transfer",
+        desc: "Transfer copies to another call number.  Streams one result
+            per copy",
+        param_count: ParamCount::Exactly(3),
+        handler: transfer_copies,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Copy IDs",
+                datatype: ParamDataType::Array,
+                desc: "",
+            },
+            StaticParam {
+                name: "Call Number ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "asset.copy.transfer.bucket",
+        desc: "Transfer the copies in a copy bucket to another call number.
+            Streams one result per copy",
+        param_count: ParamCount::Exactly(3),
+        handler: transfer_copies,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Copy Bucket ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+            StaticParam {
+                name: "Call Number ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "biblio.record.batch_edit.bucket",
+        desc: "Apply batch edit tasks to the bib records in a record
+            bucket.  Streams one result per record, each including a
+            diff of the changes and the ID of the task tracking the
+            overall progress.  Saved records are reingested",
+        param_count: ParamCount::Range(3, 4),
+        handler: batch_edit_records,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Record Bucket ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+            StaticParam {
+                name: "Edit Tasks",
+                datatype: ParamDataType::String,
+                desc: "marctk batch edit tasks, one per line.
+                    See marctk::edit for the syntax",
+            },
+            StaticParam {
+                name: "Options",
+                datatype: ParamDataType::Object,
+                desc: "Hash of options: dry_run.  Dry runs report the
+                    changes without saving them",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "copy_alert.retrieve",
+        desc: "Retrieve the copy alerts for a copy with their alert
+            types fleshed",
+        param_count: ParamCount::Range(2, 3),
+        handler: retrieve_copy_alerts,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Copy ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+            StaticParam {
+                name: "Include Acknowledged",
+                datatype: ParamDataType::Boolish,
+                desc: "Defaults to false",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "copy_alert.create",
+        desc: "Create a copy alert.  Returns the new alert",
+        param_count: ParamCount::Exactly(2),
+        handler: create_copy_alert,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Copy Alert",
+                datatype: ParamDataType::Object,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "copy_alert.update",
+        desc: "Update a copy alert",
+        param_count: ParamCount::Exactly(2),
+        handler: update_copy_alert,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Copy Alert",
+                datatype: ParamDataType::Object,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "copy_alert.delete",
+        desc: "Delete a copy alert",
+        param_count: ParamCount::Exactly(2),
+        handler: delete_copy_alert,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Copy Alert ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "copy_alert_type.retrieve.all",
+        desc: "Retrieve all copy alert types",
+        param_count: ParamCount::Exactly(1),
+        handler: retrieve_copy_alert_types,
+        params: &[StaticParam {
+            name: "Authtoken",
+            datatype: ParamDataType::String,
+            desc: "",
+        }],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "copy_alert_type.save",
+        desc: "Create or update a copy alert type.  Returns the alert
+            type ID",
+        param_count: ParamCount::Exactly(2),
+        handler: save_copy_alert_type,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Copy Alert Type",
+                datatype: ParamDataType::Object,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "copy_alert_suppress.matrix",
+        desc: "Returns a hash of alert type ID to the list of org units
+            along the org unit's full path which suppress the alert type",
+        param_count: ParamCount::Range(1, 2),
+        handler: copy_alert_suppress_matrix,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Org Unit ID",
+                datatype: ParamDataType::Number,
+                desc: "Defaults to the workstation org unit",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "copy_alert_suppress.create",
+        desc: "Suppress a copy alert type at an org unit.  Returns the
+            suppression ID",
+        param_count: ParamCount::Exactly(3),
+        handler: create_copy_alert_suppress,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Copy Alert Type ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+            StaticParam {
+                name: "Org Unit ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "copy_alert_suppress.delete",
+        desc: "Remove a copy alert suppression",
+        param_count: ParamCount::Exactly(2),
+        handler: delete_copy_alert_suppress,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Copy Alert Suppression ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+];
+
+/// Collect the target IDs from either a list of IDs or, for .bucket
+/// method variants, the items in a bucket the requestor may use.
+fn target_ids(
+    editor: &mut Editor,
+    method: &message::MethodCall,
+    btype: BucketType,
+) -> EgResult<Vec<i64>> {
+    if method.method().ends_with(".bucket") {
+        return bucket::target_ids(editor, btype, method.param(1).int()?);
+    }
+
+    let mut ids = Vec::new();
+
+    for id in method.param(1).members() {
+        ids.push(id.int()?);
+    }
+
+    Ok(ids)
+}
+
+/// Per-item response for batch transfers.
+///
+/// Each response includes the ID of the task tracking the overall
+/// progress of the batch.
+fn transfer_result(task: &Task, id: i64, result: EgResult<EgValue>) -> EgValue {
+    match result {
+        Ok(v) => eg::hash! {"id": id, "success": true, "result": v, "task": task.id()},
+        Err(e) => {
+            log::warn!("Transfer of {id} failed: {e}");
+            eg::hash! {
+                "id": id,
+                "success": false,
+                "result": e.event_or_default().to_value(),
+                "task": task.id(),
+            }
+        }
+    }
+}
+
+/// Record the outcome of one transfer in the batch task.
+fn track_transfer(task: &mut Task, result: &EgResult<EgValue>, failures: &mut i64) -> EgResult<()> {
+    if result.is_err() {
+        *failures += 1;
+    }
+    task.increment(None)
+}
+
+pub fn transfer_volumes(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CatWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let record_id = method.param(2).int()?;
+    let org_id = method.param(3).as_int();
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let volume_ids = match target_ids(&mut editor, &method, BucketType::CallNumber) {
+        Ok(ids) => ids,
+        Err(e) => return session.respond(e.event_or_default()),
+    };
+
+    let mut task = Task::start(
+        editor.requestor_id()?,
+        method.method(),
+        Some(volume_ids.len() as i64),
+    )?;
+    let mut failures = 0;
+
+    // Each call number is transferred within its own transaction so
+    // one failure does not prevent the remaining transfers.
+    for volume_id in volume_ids {
+        editor.xact_begin()?;
+
+        let result = holdings::transfer_volume(&mut editor, volume_id, record_id, org_id)
+            .and_then(|dest_id| editor.commit().map(|_| EgValue::from(dest_id)));
+
+        if result.is_err() {
+            editor.rollback()?;
+        }
+
+        track_transfer(&mut task, &result, &mut failures)?;
+        session.respond(transfer_result(&task, volume_id, result))?;
+    }
+
+    task.complete(eg::hash! {"transferred": task.done() - failures, "failed": failures})
+}
+
+pub fn transfer_copies(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CatWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let volume_id = method.param(2).int()?;
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let copy_ids = match target_ids(&mut editor, &method, BucketType::Copy) {
+        Ok(ids) => ids,
+        Err(e) => return session.respond(e.event_or_default()),
+    };
+
+    let mut task = Task::start(
+        editor.requestor_id()?,
+        method.method(),
+        Some(copy_ids.len() as i64),
+    )?;
+    let mut failures = 0;
+
+    for copy_id in copy_ids {
+        editor.xact_begin()?;
+
+        let result = holdings::transfer_copy(&mut editor, copy_id, volume_id)
+            .and_then(|_| editor.commit().map(|_| EgValue::from(volume_id)));
+
+        if result.is_err() {
+            editor.rollback()?;
+        }
+
+        track_transfer(&mut task, &result, &mut failures)?;
+        session.respond(transfer_result(&task, copy_id, result))?;
+    }
+
+    task.complete(eg::hash! {"transferred": task.done() - failures, "failed": failures})
+}
+
+pub fn batch_edit_records(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CatWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let bucket_id = method.param(1).int()?;
+    let tasks = EditTasks::from_text(method.param(2).str()?)?;
+    let dry_run = method.param(3)["dry_run"].boolish();
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    if !dry_run && !editor.allowed("UPDATE_MARC")? {
+        return session.respond(editor.event());
+    }
+
+    let record_ids = match bucket::target_ids(&mut editor, BucketType::Biblio, bucket_id) {
+        Ok(ids) => ids,
+        Err(e) => return session.respond(e.event_or_default()),
+    };
+
+    let mut task = Task::start(
+        editor.requestor_id()?,
+        method.method(),
+        Some(record_ids.len() as i64),
+    )?;
+    let mut failures = 0;
+    let mut saved = 0;
+
+    // Each record is saved within its own transaction so one failure
+    // does not prevent the remaining edits.
+    for rec_id in record_ids {
+        if !dry_run {
+            editor.xact_begin()?;
+        }
+
+        let mut result = bib::edit_record(&mut editor, rec_id, &tasks, dry_run);
+
+        if !dry_run {
+            result = match result {
+                Ok(edit) => editor.commit().map(|_| edit),
+                Err(e) => {
+                    editor.rollback()?;
+                    Err(e)
+                }
+            };
+        }
+
+        let result = result.map(|edit| {
+            if edit.saved {
+                saved += 1;
+            }
+            edit.to_value()
+        });
+
+        track_transfer(&mut task, &result, &mut failures)?;
+        session.respond(transfer_result(&task, rec_id, result))?;
+    }
+
+    task.complete(eg::hash! {
+        "edited": task.done() - failures,
+        "saved": saved,
+        "failed": failures,
+        "dry_run": dry_run,
+    })
+}
+
+pub fn retrieve_copy_alerts(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CatWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let copy_id = method.param(1).int()?;
+    let with_acked = method.param(2).boolish();
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let alerts = copy_alert::copy_alerts(&mut editor, copy_id, with_acked)?;
+
+    session.respond(alerts)
+}
+
+pub fn create_copy_alert(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CatWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let alert = method.param(1).clone();
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    editor.xact_begin()?;
+
+    let alert = copy_alert::create_alert(&mut editor, alert)?;
+
+    editor.commit()?;
+
+    session.respond(alert)
+}
+
+pub fn update_copy_alert(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CatWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let alert = method.param(1).clone();
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    editor.xact_begin()?;
+
+    copy_alert::update_alert(&mut editor, alert)?;
+
+    editor.commit()?;
+
+    session.respond(1)
+}
+
+pub fn delete_copy_alert(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CatWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let alert_id = method.param(1).int()?;
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    editor.xact_begin()?;
+
+    copy_alert::delete_alert(&mut editor, alert_id)?;
+
+    editor.commit()?;
+
+    session.respond(1)
+}
+
+pub fn retrieve_copy_alert_types(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CatWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let types = copy_alert::alert_types(&mut editor)?;
+
+    session.respond(types)
+}
+
+pub fn save_copy_alert_type(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CatWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let atype = method.param(1).clone();
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    editor.xact_begin()?;
+
+    let type_id = copy_alert::save_alert_type(&mut editor, atype)?;
+
+    editor.commit()?;
+
+    session.respond(type_id)
+}
+
+pub fn copy_alert_suppress_matrix(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CatWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let org_id = method.param(1).as_int().unwrap_or(editor.perm_org());
+
+    let matrix = copy_alert::suppression_matrix(&mut editor, org_id)?;
+
+    session.respond(matrix)
+}
+
+pub fn create_copy_alert_suppress(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CatWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let alert_type = method.param(1).int()?;
+    let org_id = method.param(2).int()?;
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    editor.xact_begin()?;
+
+    let supp_id = copy_alert::suppress(&mut editor, alert_type, org_id)?;
+
+    editor.commit()?;
+
+    session.respond(supp_id)
+}
+
+pub fn delete_copy_alert_suppress(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CatWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let supp_id = method.param(1).int()?;
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    editor.xact_begin()?;
+
+    copy_alert::delete_suppression(&mut editor, supp_id)?;
+
+    editor.commit()?;
+
+    session.respond(1)
+}
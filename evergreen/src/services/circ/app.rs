@@ -1,4 +1,5 @@
 use eg::osrf::app::{Application, ApplicationWorker, ApplicationWorkerFactory};
+use eg::osrf::cache::Cache;
 use eg::osrf::method::MethodDef;
 use eg::Client;
 use eg::{EgError, EgResult};
@@ -94,6 +95,8 @@ impl ApplicationWorker for CircWorker {
 
     /// Absorb our global dataset.
     fn worker_start(&mut self, client: Client) -> EgResult<()> {
+        // Needed for idempotency key tracking.
+        Cache::init_cache("global")?;
         self.client = Some(client);
         Ok(())
     }
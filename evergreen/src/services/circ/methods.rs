@@ -1,10 +1,21 @@
+use eg::common::audit::{self, AuditEntry};
 use eg::common::circ;
 use eg::common::circulator::Circulator;
+use eg::common::copy_alert;
+use eg::common::holds::{self, HoldPlacement, HoldRequest};
+use eg::common::idempotency::{IdempotencyKey, Reservation};
+use eg::common::noncat;
+use eg::common::org;
+use eg::common::override_token::{self, OverrideToken};
+use eg::common::payment::{self, stripe, IntentStatus};
+use eg::common::till;
 use eg::editor::Editor;
+use eg::event::Overrides;
 use eg::osrf::app::ApplicationWorker;
 use eg::osrf::message;
 use eg::osrf::method::{ParamCount, ParamDataType, StaticMethodDef, StaticParam};
 use eg::osrf::session::ServerSession;
+use eg::EgError;
 use eg::EgResult;
 use eg::EgValue;
 use evergreen as eg;
@@ -34,6 +45,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "Options including copy_barcode, etc.", // TODO expand
             },
         ],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "checkin.override",
@@ -52,6 +64,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "Options including copy_barcode, etc.", // TODO expand
             },
         ],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "checkout",
@@ -67,9 +80,10 @@ pub static METHODS: &[StaticMethodDef] = &[
             StaticParam {
                 name: "options",
                 datatype: ParamDataType::Object,
-                desc: "Options including copy_barcode, etc.",
+                desc: "Options including copy_barcode, idempotency_key, override_token, etc.",
             },
         ],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "checkout.override",
@@ -85,9 +99,10 @@ pub static METHODS: &[StaticMethodDef] = &[
             StaticParam {
                 name: "options",
                 datatype: ParamDataType::Object,
-                desc: "Options including copy_barcode, etc.",
+                desc: "Options including copy_barcode, idempotency_key, override_token, etc.",
             },
         ],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "checkout.inspect",
@@ -103,9 +118,10 @@ pub static METHODS: &[StaticMethodDef] = &[
             StaticParam {
                 name: "options",
                 datatype: ParamDataType::Object,
-                desc: "Options including copy_barcode, etc.",
+                desc: "Options including copy_barcode, idempotency_key, override_token, etc.",
             },
         ],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "renew",
@@ -121,9 +137,10 @@ pub static METHODS: &[StaticMethodDef] = &[
             StaticParam {
                 name: "options",
                 datatype: ParamDataType::Object,
-                desc: "Options including copy_barcode, etc.",
+                desc: "Options including copy_barcode, idempotency_key, override_token, etc.",
             },
         ],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "renew.override",
@@ -139,9 +156,120 @@ pub static METHODS: &[StaticMethodDef] = &[
             StaticParam {
                 name: "options",
                 datatype: ParamDataType::Object,
-                desc: "Options including copy_barcode, etc.",
+                desc: "Options including copy_barcode, idempotency_key, override_token, etc.",
             },
         ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "checkout.email_receipt",
+        desc: "Email a receipt listing the provided circulations to the
+            patron who owns them",
+        param_count: ParamCount::Exactly(2),
+        handler: email_checkout_receipt,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Circ IDs",
+                datatype: ParamDataType::Array,
+                desc: "Circulations belonging to a single patron",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "copy_alert.acknowledge",
+        desc: "Acknowledge copy alerts so they are no longer raised during
+            circulation.  Returns the IDs of the newly acknowledged alerts",
+        param_count: ParamCount::Exactly(2),
+        handler: acknowledge_copy_alerts,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Copy Alert IDs",
+                datatype: ParamDataType::Array,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "override_token.create",
+        desc: "Authenticate a supervisor and create a short-lived token
+            which allows the logged in user to override the listed events
+            by passing the token as the override_token option to checkout,
+            checkin, and renew calls",
+        param_count: ParamCount::Exactly(4),
+        handler: create_override_token,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Supervisor Username",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Supervisor Password",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Events",
+                datatype: ParamDataType::Array,
+                desc: "Event textcodes to override, e.g. PATRON_EXCEEDS_FINES",
+            },
+        ],
+        redacted_params: &[2],
+    },
+    StaticMethodDef {
+        name: "holds.create",
+        desc: "Place a hold",
+        param_count: ParamCount::Exactly(2),
+        handler: create_hold,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Hold",
+                datatype: ParamDataType::Object,
+                desc: "Hold request hash using ahr field names",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "holds.create.override",
+        desc: "Place a hold, overriding any overridable events",
+        param_count: ParamCount::Exactly(2),
+        handler: create_hold,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Hold",
+                datatype: ParamDataType::Object,
+                desc: "Hold request hash using ahr field names",
+            },
+        ],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "renewal_chain.retrieve_by_circ.summary",
@@ -160,6 +288,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "Circulation ID to lookup",
             },
         ],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "prev_renewal_chain.retrieve_by_circ.summary",
@@ -178,6 +307,143 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "Circulation ID to lookup",
             },
         ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "open_non_cataloged_circulation.user",
+        desc: "Open non-cataloged circulations for a user, fleshed with
+            their item type and calculated due date",
+        param_count: ParamCount::Range(1, 2),
+        handler: open_noncat_circs,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "User ID",
+                datatype: ParamDataType::Number,
+                desc: "Defaults to the logged in user",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "money.desk_payment.summary",
+        desc: "Summarize desk payments by day, workstation, staff, and payment type",
+        param_count: ParamCount::Exactly(2),
+        handler: desk_payment_summary,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Filters",
+                datatype: ParamDataType::Object,
+                desc: "Hash of filters: start_date, end_date (required),
+                    workstation, accepting_usr, org_unit",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "money.desk_payment.summary.csv",
+        desc: "Desk payment summary as CSV.  See money.desk_payment.summary",
+        param_count: ParamCount::Exactly(2),
+        handler: desk_payment_summary,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Filters",
+                datatype: ParamDataType::Object,
+                desc: "See money.desk_payment.summary",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "money.till.close",
+        desc: "Close out the till for a workstation, recording payment totals
+            collected since the previous closeout",
+        param_count: ParamCount::Range(3, 4),
+        handler: close_till,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Workstation ID",
+                datatype: ParamDataType::Number,
+                desc: "",
+            },
+            StaticParam {
+                name: "Start Date",
+                datatype: ParamDataType::String,
+                desc: "Start of the closeout period when the workstation
+                    has no previous closeout",
+            },
+            StaticParam {
+                name: "Note",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "money.payment_intent.create",
+        desc: "Create a payment intent with the configured external payment
+            processor for the full balance owed on the selected transactions
+            belonging to the logged in patron",
+        param_count: ParamCount::Range(2, 3),
+        handler: create_payment_intent,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Transaction IDs",
+                datatype: ParamDataType::Array,
+                desc: "",
+            },
+            StaticParam {
+                name: "Options",
+                datatype: ParamDataType::Object,
+                desc: "Options including idempotency_key",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "money.payment_intent.webhook",
+        desc: "Verify a payment processor webhook callback and record
+            payments for successful payment intents",
+        param_count: ParamCount::Exactly(2),
+        handler: payment_intent_webhook,
+        params: &[
+            StaticParam {
+                name: "Payload",
+                datatype: ParamDataType::String,
+                desc: "Raw webhook request body",
+            },
+            StaticParam {
+                name: "Signature",
+                datatype: ParamDataType::String,
+                desc: "Webhook signature header value",
+            },
+        ],
+        redacted_params: &[1],
     },
 ];
 
@@ -203,47 +469,277 @@ pub fn checkout_renew_checkin(
         return session.respond(editor.event());
     }
 
+    let idem_key = IdempotencyKey::from_options(method.method(), editor.requestor_id()?, op_params);
+
     let mut circulator = Circulator::new(&mut editor, options)?;
     circulator.is_inspect = method.method().contains(".inspect");
     circulator.is_override = method.method().contains(".override");
 
+    // If the caller provided an idempotency key and we have already
+    // handled a request with the same key, return the original response.
+    if let Some(key) = idem_key.as_ref() {
+        match key.reserve()? {
+            Reservation::Reserved => {}
+            Reservation::InProgress => return Err(key.in_progress_error()),
+            Reservation::Completed(response) => return session.respond_complete(response),
+        }
+    }
+
+    let (response, committed) = match run_circulator(&mut circulator, method.method(), op_params) {
+        Ok(r) => r,
+        Err(e) => {
+            // Nothing was saved.  Let the caller retry.
+            if let Some(key) = idem_key.as_ref() {
+                key.release();
+            }
+            return Err(e);
+        }
+    };
+
+    // Error events are cached along with successful responses so a
+    // retry sees the same outcome as the original request.
+    if let Some(key) = idem_key.as_ref() {
+        if let Err(e) = key.complete(&response) {
+            // The operation itself completed.  Don't fail the call.
+            log::error!("Cannot cache response for {}: {e}", key.cache_key());
+        }
+    }
+
+    // Send the compiled events to the caller and let them know we're done.
+    session.respond_complete(response)?;
+
+    if committed {
+        // Work that the caller does not care about.
+        circulator.post_commit_tasks()?;
+    }
+
+    Ok(())
+}
+
+/// Run the checkout, renewal, or checkin for the API call.
+///
+/// Returns the response for the caller and whether the changes were
+/// committed.  Error events and inspect results are returned as
+/// responses after rolling back.
+fn run_circulator(
+    circulator: &mut Circulator,
+    method: &str,
+    op_params: &EgValue,
+) -> EgResult<(EgValue, bool)> {
     circulator.begin()?;
 
-    let result = if method.method().contains("checkout") {
+    let result = if method.contains("checkout") {
         circulator.checkout()
-    } else if method.method().contains("checkin") {
+    } else if method.contains("checkin") {
         circulator.checkin()
-    } else if method.method().contains("renew") {
+    } else if method.contains("renew") {
         circulator.renew()
     } else {
-        return Err(format!("Unhandled method {}", method.method()).into());
+        circulator.rollback()?;
+        return Err(format!("Unhandled method {method}").into());
     };
 
     if let Err(err) = result {
         circulator.rollback()?;
         // Return the error event to the caller.
-        session.respond(err.event_or_default())?;
-        return Ok(());
+        return Ok((err.event_or_default().into(), false));
     }
 
     if circulator.is_inspect() {
-        session.respond(circulator.policy_to_eg_value())?;
+        let policy = circulator.policy_to_eg_value();
         circulator.rollback()?;
-        return Ok(());
+        return Ok((policy, false));
     }
 
     let events: Vec<EgValue> = circulator.events().iter().map(|e| e.into()).collect();
 
+    // Checkouts, renewals, and overrides are recorded in the staff
+    // audit log within the same transaction as the action itself.
+    let wants_audit = !method.contains("checkin") || circulator.is_override;
+
+    if wants_audit && audit::is_enabled() {
+        let mut entry = AuditEntry::new(method);
+
+        if let Some(id) = circulator.circ.as_ref().and_then(|c| c["id"].as_int()) {
+            entry.set_target("circ", id);
+        } else if circulator.copy_id > 0 {
+            entry.set_target("acp", circulator.copy_id);
+        }
+
+        entry.reason_code = op_params["reason_code"].to_string();
+
+        if !circulator.overridden_events.is_empty() {
+            let mut note = format!("Overrides: {}", circulator.overridden_events.join(", "));
+
+            if let Some(token) = circulator.override_token.as_ref() {
+                note += &format!(" (supervisor {})", token.supervisor_id());
+            }
+
+            entry.note = Some(note);
+        }
+
+        audit::log_action(circulator.editor(), &entry)?;
+    }
+
     // Checkin call completed
     circulator.commit()?;
 
-    // Send the compiled events to the caller and let them know we're done.
-    session.respond_complete(events)?;
+    Ok((EgValue::from(events), true))
+}
 
-    // Work that the caller does not care about.
-    circulator.post_commit_tasks()?;
+pub fn email_checkout_receipt(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CircWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
 
-    Ok(())
+    let circ_ids = method
+        .param(1)
+        .members()
+        .map(|id| id.int())
+        .collect::<EgResult<Vec<i64>>>()?;
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let circs = editor.search("circ", eg::hash! {id: circ_ids.as_slice()})?;
+
+    // Patrons may request receipts for their own circulations.
+    let requestor_id = editor.requestor_id()?;
+    for circ in circs.iter() {
+        if circ["usr"].int()? != requestor_id
+            && !editor.allowed_at("VIEW_CIRCULATIONS", circ["circ_lib"].int()?)?
+        {
+            return session.respond(editor.event());
+        }
+    }
+
+    editor.xact_begin()?;
+
+    circ::create_email_receipt_events(&mut editor, &circ_ids)?;
+
+    editor.commit()?;
+
+    session.respond(1)
+}
+
+pub fn acknowledge_copy_alerts(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CircWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+
+    let mut alert_ids = Vec::new();
+    for id in method.param(1).members() {
+        alert_ids.push(id.int()?);
+    }
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    // Acknowledging an alert is equivalent to overriding it.
+    if !editor.allowed("COPY_ALERT_MESSAGE.override")? {
+        return session.respond(editor.event());
+    }
+
+    editor.xact_begin()?;
+
+    let acked = copy_alert::acknowledge(&mut editor, &alert_ids)?;
+
+    editor.commit()?;
+
+    session.respond(acked)
+}
+
+pub fn create_override_token(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CircWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let username = method.param(1).str()?;
+    let password = method.param(2).str()?;
+
+    let events: Vec<String> = method
+        .param(3)
+        .members()
+        .filter_map(|e| e.as_str())
+        .map(|e| e.to_string())
+        .collect();
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let token = match OverrideToken::create(
+        &mut editor,
+        username,
+        password,
+        &events,
+        override_token::DEFAULT_OVERRIDE_TOKEN_TTL,
+    ) {
+        Ok(t) => t,
+        Err(e) => return session.respond(e.event_or_default()),
+    };
+
+    let mut response = token.to_eg_value();
+    response["ttl"] = EgValue::from(override_token::DEFAULT_OVERRIDE_TOKEN_TTL);
+
+    session.respond(response)
+}
+
+pub fn create_hold(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CircWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let request = HoldRequest::from_eg_value(method.param(1))?;
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let overrides = match method.method().ends_with(".override") {
+        true => Some(Overrides::All),
+        false => None,
+    };
+
+    editor.xact_begin()?;
+
+    let hold = match holds::place_hold(&mut editor, &request, overrides.as_ref())? {
+        HoldPlacement::Placed(h) => h,
+        HoldPlacement::Blocked(events) => {
+            editor.rollback()?;
+            let events: Vec<EgValue> = events.iter().map(|e| e.into()).collect();
+            return session.respond(events);
+        }
+    };
+
+    editor.commit()?;
+
+    let hold_id = hold.id()?;
+
+    session.respond_complete(hold_id)?;
+
+    // Target the new hold after the caller has their response.
+    holds::retarget_holds(&editor, &[hold_id])
 }
 
 pub fn renewal_chain_summary(
@@ -321,3 +817,227 @@ pub fn prev_renewal_chain_summary(
 
     session.respond(circ::summarize_circ_chain(&mut editor, prev_circ[0].id()?)?)
 }
+
+pub fn open_noncat_circs(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CircWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let user_id = method.param(1).as_i64().unwrap_or(editor.requestor_id()?);
+
+    if user_id != editor.requestor_id()? {
+        let user = match editor.retrieve("au", user_id)? {
+            Some(u) => u,
+            None => return session.respond(editor.event()),
+        };
+
+        if !editor.allowed_at("VIEW_CIRCULATIONS", user["home_ou"].int()?)? {
+            return session.respond(editor.event());
+        }
+    }
+
+    let circs = noncat::open_circs(&mut editor, user_id)?;
+
+    session.respond(circs)
+}
+
+pub fn desk_payment_summary(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CircWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let filters = method.param(1);
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let mut filters = filters.clone();
+
+    // Without an org unit filter, the summary covers the workstation
+    // org unit and its descendants.
+    let org_id = match filters["org_unit"].as_int() {
+        Some(o) => o,
+        None => {
+            let ws_org = editor.perm_org();
+            filters["org_unit"] = EgValue::from(org::descendants(&mut editor, ws_org)?);
+            ws_org
+        }
+    };
+
+    if !editor.allowed_at("VIEW_TRANSACTION", org_id)? {
+        return session.respond(editor.event());
+    }
+
+    let rows = till::summarize_payments(&mut editor, &filters)?;
+
+    if method.method().ends_with(".csv") {
+        return session.respond(till::summary_to_csv(&rows));
+    }
+
+    let response = eg::hash! {
+        "payments": rows.as_slice(),
+        "totals": till::totals_by_type(&rows),
+    };
+
+    session.respond(response)
+}
+
+pub fn close_till(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CircWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let workstation = method.param(1).int()?;
+    let start_date = method.param(2).str()?;
+    let note = method.param(3).as_str();
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    let ws = match editor.retrieve("aws", workstation)? {
+        Some(w) => w,
+        None => return session.respond(editor.event()),
+    };
+
+    if !editor.allowed_at("CLOSE_TILL", ws["owning_lib"].int()?)? {
+        return session.respond(editor.event());
+    }
+
+    editor.xact_begin()?;
+
+    let closeout = till::close_till(&mut editor, workstation, start_date, note)?;
+
+    editor.commit()?;
+
+    session.respond(closeout)
+}
+
+pub fn create_payment_intent(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CircWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+
+    let mut xact_ids = Vec::new();
+    for id in method.param(1).members() {
+        xact_ids.push(id.int()?);
+    }
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    // Patrons pay their own transactions using the processor
+    // configured for their home library.
+    let user_id = editor.requestor_id()?;
+    let org_id = editor.requestor_home_ou()?;
+
+    // A retried request returns the original intent instead of
+    // creating a second one for the same balance.
+    let idem_key = IdempotencyKey::from_options(method.method(), user_id, method.param(2));
+
+    if let Some(key) = idem_key.as_ref() {
+        match key.reserve()? {
+            Reservation::Reserved => {}
+            Reservation::InProgress => return Err(key.in_progress_error()),
+            Reservation::Completed(response) => return session.respond(response),
+        }
+    }
+
+    let result =
+        payment::request_for_xacts(&mut editor, user_id, org_id, &xact_ids).and_then(|request| {
+            let mut processor = payment::processor_for_org(&mut editor, org_id)?;
+            let intent = processor.authorize(&request)?;
+
+            log::info!(
+                "Created {} payment intent {} for user {user_id}",
+                processor.name(),
+                intent.id
+            );
+
+            Ok(intent.to_eg_value())
+        });
+
+    let response = match result {
+        Ok(r) => r,
+        Err(EgError::Event(evt)) => EgValue::from(*evt),
+        Err(e) => {
+            // No intent was created.  Let the caller retry.
+            if let Some(key) = idem_key.as_ref() {
+                key.release();
+            }
+            return Err(e);
+        }
+    };
+
+    if let Some(key) = idem_key.as_ref() {
+        if let Err(e) = key.complete(&response) {
+            log::error!("Cannot cache response for {}: {e}", key.cache_key());
+        }
+    }
+
+    session.respond(response)
+}
+
+pub fn payment_intent_webhook(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::CircWorker::downcast(worker)?;
+    let payload = method.param(0).str()?;
+    let signature = method.param(1).str()?;
+
+    let mut editor = Editor::new(worker.client());
+
+    // The webhook secret is an org setting, so peek at the org unit
+    // in the payload before verifying it.  Nothing else from the
+    // payload is trusted until the signature is verified.
+    let org_id = stripe::webhook_org_id(payload)?;
+    let processor = payment::processor_for_org(&mut editor, org_id)?;
+
+    let intent = match processor.verify_webhook(payload, signature)? {
+        Some(i) => i,
+        None => return session.respond(EgValue::from(0)),
+    };
+
+    if intent.status != IntentStatus::Succeeded {
+        log::info!(
+            "Ignoring webhook for {} intent {}",
+            intent.status,
+            intent.id
+        );
+        return session.respond(EgValue::from(0));
+    }
+
+    editor.xact_begin()?;
+
+    let payments = payment::record_intent_payments(&mut editor, processor.name(), &intent)?;
+
+    editor.commit()?;
+
+    session.respond(EgValue::from(payments.len()))
+}
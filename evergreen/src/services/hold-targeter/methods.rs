@@ -1,10 +1,13 @@
-use eg::common::targeter;
+use eg::common::targeter::{self, RetargetFilter};
+use eg::common::task::Task;
+use eg::common::user;
 use eg::editor::Editor;
 use eg::osrf::app::ApplicationWorker;
 use eg::osrf::message;
 use eg::osrf::method::{ParamCount, ParamDataType, StaticMethodDef, StaticParam};
 use eg::osrf::session::ServerSession;
 use eg::EgResult;
+use eg::EgValue;
 use evergreen as eg;
 
 // Import our local app module
@@ -13,17 +16,46 @@ use crate::app;
 /// List of method definitions we know at compile time.
 ///
 /// These will form the basis (and possibly all) of our published methods.
-pub static METHODS: &[StaticMethodDef] = &[StaticMethodDef {
-    name: "target",
-    desc: "Target one or more holds",
-    param_count: ParamCount::Range(0, 1),
-    handler: target,
-    params: &[StaticParam {
-        name: "options",
-        datatype: ParamDataType::Object,
-        desc: "Targeting Options",
-    }],
-}];
+pub static METHODS: &[StaticMethodDef] = &[
+    StaticMethodDef {
+        name: "target",
+        desc: "Target one or more holds",
+        param_count: ParamCount::Range(0, 1),
+        handler: target,
+        params: &[StaticParam {
+            name: "options",
+            datatype: ParamDataType::Object,
+            desc: "Targeting Options",
+        }],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "retarget.filtered",
+        desc: "Immediately retarget the open holds matching a set of
+            criteria, e.g. after a hold policy change.  Streams one
+            result per hold, each including the ID of the task tracking
+            the overall progress",
+        param_count: ParamCount::Exactly(2),
+        handler: retarget_filtered,
+        params: &[
+            StaticParam {
+                name: "Authtoken",
+                datatype: ParamDataType::String,
+                desc: "",
+            },
+            StaticParam {
+                name: "Filter",
+                datatype: ParamDataType::Object,
+                desc: "Hash of criteria: pickup_lib, pickup_lib_descendants,
+                    request_lib, hold_type, copy_location, and
+                    copy_location_group.  At least one is required.
+                    Without a pickup_lib, only holds picked up where the
+                    caller has UPDATE_HOLD are retargeted",
+            },
+        ],
+        redacted_params: &[],
+    },
+];
 
 pub fn target(
     worker: &mut Box<dyn ApplicationWorker>,
@@ -93,3 +125,100 @@ pub fn target(
 
     Ok(())
 }
+
+pub fn retarget_filtered(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::HoldTargeterWorker::downcast(worker)?;
+    let authtoken = method.param(0).str()?;
+    let mut filter = RetargetFilter::from_value(method.param(1))?;
+
+    let mut editor = Editor::with_auth(worker.client(), authtoken);
+
+    if !editor.checkauth()? {
+        return session.respond(editor.event());
+    }
+
+    if let Some(org_id) = filter.request_lib {
+        if !editor.allowed_at("UPDATE_HOLD", org_id)? {
+            return session.respond(editor.event());
+        }
+    }
+
+    // Without a pickup library, only holds picked up where the caller
+    // may update holds are retargeted.
+    if let Some(org_id) = filter.pickup_lib {
+        if !editor.allowed_at("UPDATE_HOLD", org_id)? {
+            return session.respond(editor.event());
+        }
+    } else {
+        let requestor_id = editor.requestor_id()?;
+        let perm_orgs = user::has_work_perm_at(&mut editor, requestor_id, "UPDATE_HOLD")?;
+
+        if perm_orgs.is_empty() {
+            // Sets the permission failure event.
+            editor.allowed("UPDATE_HOLD")?;
+            return session.respond(editor.event());
+        }
+
+        filter.pickup_lib_scope = Some(perm_orgs);
+    }
+
+    let hold_ids = filter.find_holds(&mut editor)?;
+
+    let mut task = Task::start(
+        editor.requestor_id()?,
+        method.method(),
+        Some(hold_ids.len() as i64),
+    )?;
+
+    // Clear the last check time of every matching hold up front so
+    // any holds we fail to reach here are picked up by the next
+    // regular targeter run.
+    if !hold_ids.is_empty() {
+        editor.xact_begin()?;
+        for mut hold in editor.search("ahr", eg::hash! {"id": hold_ids.clone()})? {
+            hold["prev_check_time"] = EgValue::Null;
+            editor.update(hold)?;
+        }
+        editor.commit()?;
+    }
+
+    let mut tgtr = targeter::HoldTargeter::new(&mut editor);
+
+    // Each hold is targeted and committed within its own transaction.
+    tgtr.set_transaction_manged_externally(true);
+    tgtr.init()?;
+
+    let mut failures = 0;
+
+    for hold_id in hold_ids {
+        tgtr.editor().xact_begin()?;
+
+        let result = tgtr
+            .target_hold(hold_id, None)
+            .and_then(|ctx| tgtr.editor().commit().map(|_| ctx.to_json()));
+
+        let response = match result {
+            Ok(v) => eg::hash! {"hold": hold_id, "success": true, "result": v, "task": task.id()},
+            Err(e) => {
+                // target_hold() has already rolled back.
+                log::warn!("Retarget of hold {hold_id} failed: {e}");
+                failures += 1;
+                eg::hash! {
+                    "hold": hold_id,
+                    "success": false,
+                    "result": e.event_or_default().to_value(),
+                    "task": task.id(),
+                }
+            }
+        };
+
+        task.increment(None)?;
+        session.respond(response)?;
+    }
+
+    task.complete(eg::hash! {"retargeted": task.done() - failures, "failed": failures})
+}
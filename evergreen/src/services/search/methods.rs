@@ -1,10 +1,12 @@
 use eg::common::bib;
+use eg::common::citation::{self, CitationFormat};
 use eg::osrf::app::ApplicationWorker;
 use eg::osrf::message;
 use eg::osrf::method::{ParamCount, ParamDataType, StaticMethodDef, StaticParam};
 use eg::osrf::session::ServerSession;
 use eg::Editor;
 use eg::EgResult;
+use eg::EgValue;
 use evergreen as eg;
 
 // Import our local app module
@@ -34,6 +36,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "Options Hash",
             },
         ],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "biblio.record.catalog_summary.staff",
@@ -57,6 +60,43 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "Options Hash",
             },
         ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "biblio.record.citation",
+        desc: "Export citations for bib records.  Streams one
+            {id, format, citation} response per record.  RIS and BibTeX
+            citations are strings; CSL-JSON citations are objects.
+            The citation is null for deleted or nonexistent records",
+        param_count: ParamCount::Exactly(2),
+        handler: record_citations,
+        params: &[
+            StaticParam {
+                name: "Record IDs",
+                datatype: ParamDataType::Array,
+                desc: "",
+            },
+            StaticParam {
+                name: "Format",
+                datatype: ParamDataType::String,
+                desc: "ris, bibtex, or csl-json",
+            },
+        ],
+        redacted_params: &[],
+    },
+    StaticMethodDef {
+        name: "biblio.record.material_type.facets",
+        desc: "Material type facet counts for a set of bib records.
+            Streams one {material_type, count} response per material
+            type, most common first",
+        param_count: ParamCount::Exactly(1),
+        handler: material_type_facets,
+        params: &[StaticParam {
+            name: "Record IDs",
+            datatype: ParamDataType::Array,
+            desc: "",
+        }],
+        redacted_params: &[],
     },
 ];
 
@@ -88,3 +128,56 @@ pub fn catalog_record_summary(
 
     Ok(())
 }
+
+pub fn record_citations(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::SearchWorker::downcast(worker)?;
+
+    let format_str = method.param(1).str()?;
+    let format: CitationFormat = format_str.parse()?;
+
+    let mut editor = Editor::new(worker.client());
+
+    for rec_id in method.param(0).members() {
+        let rec_id = rec_id.int()?;
+
+        let value = citation::record_citation(&mut editor, rec_id)?
+            .map(|c| c.to_format(format))
+            .unwrap_or(EgValue::Null);
+
+        session.respond(eg::hash! {
+            "id": rec_id,
+            "format": format_str,
+            "citation": value,
+        })?;
+    }
+
+    Ok(())
+}
+
+pub fn material_type_facets(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: message::MethodCall,
+) -> EgResult<()> {
+    let worker = app::SearchWorker::downcast(worker)?;
+
+    let mut rec_ids = Vec::new();
+    for rec_id in method.param(0).members() {
+        rec_ids.push(rec_id.int()?);
+    }
+
+    let mut editor = Editor::new(worker.client());
+
+    for (mtype, count) in bib::material_type_facets(&mut editor, &rec_ids)? {
+        session.respond(eg::hash! {
+            "material_type": mtype,
+            "count": count,
+        })?;
+    }
+
+    Ok(())
+}
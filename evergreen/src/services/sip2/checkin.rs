@@ -3,6 +3,7 @@ use super::session::Session;
 use chrono::NaiveDateTime;
 use eg::common::circulator::Circulator;
 use eg::constants as C;
+use eg::i18n;
 use eg::result::EgResult;
 use eg::EgValue;
 use evergreen as eg;
@@ -95,7 +96,7 @@ impl Session {
             resp.add_field("DA", n);
         }
         if blocked_on_co {
-            resp.add_field("AF", "Item Is Currently Checked Out");
+            resp.add_field("AF", &i18n::tr("Item Is Currently Checked Out"));
         }
 
         Ok(resp)
@@ -158,8 +159,11 @@ impl Session {
         ovride: bool,
     ) -> EgResult<CheckinResult> {
         // There is no seed data for use_native_checkin, so this will
-        // always be false unless locally modified.
-        if self.config().setting_is_true("use_native_checkin") {
+        // always be false unless locally modified.  The native checkin
+        // may also be rolled out gradually via feature flag.
+        if self.config().setting_is_true("use_native_checkin")
+            || self.use_native_flag("sip2.native_checkin", &item.barcode)?
+        {
             self.checkin_native(item, checkin_loc_op, return_date, cancel, ovride)
         } else {
             self.checkin_api(item, checkin_loc_op, return_date, cancel, ovride)
@@ -343,6 +347,10 @@ impl Session {
             options.insert("revert_hold_fulfillment".to_string(), EgValue::from(cancel));
         }
 
+        if self.config().setting_is_true("checkin_ack_copy_alerts") {
+            options.insert("ack_copy_alerts".to_string(), EgValue::from(true));
+        }
+
         if return_date.trim().len() == 18 {
             let fmt = sip2::spec::SIP_DATE_FORMAT;
 
@@ -1,9 +1,9 @@
-use super::session::DEFAULT_DUE_DATE_FORMAT;
 use crate::item::Item;
 use crate::patron::Patron;
 use crate::session::Session;
 use eg::common::circulator::Circulator;
-use eg::date;
+use eg::common::noncat;
+use eg::i18n;
 use eg::result::EgResult;
 use eg::EgValue;
 use evergreen as eg;
@@ -70,20 +70,24 @@ impl Session {
         let mut items_renewed = Vec::new();
         let mut items_unrenewed = Vec::new();
 
-        for item_id in patron
+        let flesh = eg::hash! {"flesh": 1, "flesh_fields": {"circ": ["target_copy"]}};
+
+        // The patron's open circulations, overdue first.
+        for circ_id in patron
             .items_overdue_ids
             .iter()
             .chain(patron.items_out_ids.iter())
         {
-            let item = self
+            let circ = self
                 .editor()
-                .retrieve("acp", *item_id)?
+                .retrieve_with_ops("circ", *circ_id, flesh.clone())?
                 .ok_or_else(|| self.editor().die_event())?;
 
-            let item_barcode = item["barcode"].str()?;
+            let item_barcode = circ["target_copy"]["barcode"].string()?;
 
+            // Renew each item the same way as a single Renew request.
             let result = self.checkout(
-                item_barcode,
+                &item_barcode,
                 patron_barcode,
                 fee_ack_op.is_some(),
                 true, // is_explicit_renewal
@@ -91,10 +95,17 @@ impl Session {
             )?;
 
             // Presence of circ id indicates success
-            if result.circ_id.is_some() {
-                items_renewed.push(item_barcode.to_string());
+            if let Some(new_circ_id) = result.circ_id {
+                if self.config().setting_is_true("email_checkout_receipt") {
+                    // The renewal is already committed.
+                    if let Err(e) = self.add_receipt_circ(patron_barcode, new_circ_id) {
+                        log::error!("{self} could not track receipt circ {new_circ_id}: {e}");
+                    }
+                }
+                items_renewed.push(item_barcode);
             } else {
-                items_unrenewed.push(item_barcode.to_string());
+                log::info!("{self} Renew All could not renew {item_barcode}");
+                items_unrenewed.push(item_barcode);
             }
         }
 
@@ -144,6 +155,12 @@ impl Session {
 
         log::info!("{self} Checking out item {item_barcode} to patron {patron_barcode}");
 
+        if !is_explicit_renewal {
+            if let Some(noncat_type) = self.noncat_type_for_barcode(item_barcode) {
+                return self.checkout_noncat(item_barcode, patron_barcode, noncat_type);
+            }
+        }
+
         let fee_ack_op = msg.get_field_value("BO");
 
         let item = match self.get_item_details(item_barcode)? {
@@ -179,9 +196,113 @@ impl Session {
             self.config().setting_is_true("checkout_override_all"),
         )?;
 
+        if let Some(circ_id) = result.circ_id {
+            if self.config().setting_is_true("email_checkout_receipt") {
+                // The checkout is already committed.
+                if let Err(e) = self.add_receipt_circ(patron_barcode, circ_id) {
+                    log::error!("{self} could not track receipt circ {circ_id}: {e}");
+                }
+            }
+        }
+
         self.compile_checkout_response(&item, &patron, &result, is_explicit_renewal)
     }
 
+    /// Returns the noncat type for an item barcode if it matches one
+    /// of the patterns in the noncat_barcode_patterns setting.
+    fn noncat_type_for_barcode(&self, item_barcode: &str) -> Option<i64> {
+        self.config()
+            .settings()
+            .get("noncat_barcode_patterns")
+            .and_then(|patterns| noncat::type_for_barcode(patterns, item_barcode))
+    }
+
+    /// Checkout a non-cataloged item.  Noncat circulations are tracked
+    /// by count only and cannot be renewed or checked in.
+    fn checkout_noncat(
+        &mut self,
+        item_barcode: &str,
+        patron_barcode: &str,
+        noncat_type: i64,
+    ) -> EgResult<sip2::Message> {
+        let nctype = match self.editor().retrieve("cnct", noncat_type)? {
+            Some(t) => t,
+            None => {
+                log::error!("{self} invalid noncat type {noncat_type} for {item_barcode}");
+                return Ok(self.checkout_item_not_found(item_barcode, patron_barcode, false));
+            }
+        };
+
+        let mut options: HashMap<String, EgValue> = HashMap::new();
+
+        options.insert("patron_barcode".to_string(), patron_barcode.into());
+        options.insert("is_noncat".to_string(), EgValue::from(true));
+        options.insert("noncat_type".to_string(), EgValue::from(noncat_type));
+
+        // Standalone transaction; cloning is just easier here.
+        let mut editor = self.editor().clone();
+
+        let mut circulator = Circulator::new(&mut editor, options)?;
+        circulator.begin()?;
+
+        let evt = match circulator.checkout() {
+            Ok(()) => {
+                circulator.commit()?;
+                circulator
+                    .events()
+                    .first()
+                    .cloned()
+                    .ok_or_else(|| "API call failed to return an event".to_string())?
+            }
+            Err(err) => {
+                circulator.rollback()?;
+                err.event_or_default()
+            }
+        };
+
+        log::info!(
+            "{self} Noncat checkout of {item_barcode} returned: {}",
+            evt.to_value().dump()
+        );
+
+        let noncat_circ = &evt.payload()["noncat_circ"];
+
+        if !evt.is_success() || !noncat_circ.is_object() {
+            let msg = self
+                .editor()
+                .retrieve("sipsm", "checkout.patron_not_allowed")?
+                .ok_or_else(|| self.editor().die_event())?;
+
+            let mut resp = self.checkout_item_not_found(item_barcode, patron_barcode, false);
+            resp.add_field("AF", &i18n::tr(msg["message"].str()?));
+
+            return Ok(resp);
+        }
+
+        let due_date = self.format_iso_due_date(noncat_circ["duedate"].str()?)?;
+
+        let resp = sip2::Message::from_values(
+            "12",
+            &[
+                "1",                         // checkout ok
+                "N",                         // renew ok
+                "N",                         // magnetic
+                "Y",                         // desensitize
+                &sip2::util::sip_date_now(), // timestamp
+            ],
+            &[
+                ("AA", patron_barcode),
+                ("AB", item_barcode),
+                ("AH", &due_date),
+                ("AJ", nctype["name"].str()?),
+                ("AO", self.config().institution()),
+            ],
+        )
+        .unwrap();
+
+        Ok(resp)
+    }
+
     pub fn handle_checkout(&mut self, msg: &sip2::Message) -> EgResult<sip2::Message> {
         self.checkout_renew_common(msg, false)
     }
@@ -229,7 +350,7 @@ impl Session {
         }
 
         if item.deposit_amount > 0.0 {
-            resp.add_field("BV", &format!("{:.2}", item.deposit_amount));
+            resp.add_field("BV", &self.format_money(item.deposit_amount));
         }
 
         Ok(resp)
@@ -269,7 +390,9 @@ impl Session {
         is_renewal: bool,
         ovride: bool,
     ) -> EgResult<CheckoutResult> {
-        if self.config().setting_is_true("use_native_checkout") {
+        if self.config().setting_is_true("use_native_checkout")
+            || self.use_native_flag("sip2.native_checkout", item_barcode)?
+        {
             self.checkout_native(item_barcode, patron_barcode, fee_ack, is_renewal, ovride)
         } else {
             self.checkout_api(item_barcode, patron_barcode, fee_ack, is_renewal, ovride)
@@ -339,17 +462,7 @@ impl Session {
                 result.renewal_remaining = circ["renewal_remaining"].int()?;
 
                 let iso_date = circ["due_date"].as_str().unwrap(); // required
-                let due_dt = date::parse_datetime(iso_date)?;
-
-                if self
-                    .config()
-                    .setting_is_true("due_date_use_sip_date_format")
-                {
-                    result.due_date = Some(sip2::util::sip_date_from_dt(&due_dt));
-                } else {
-                    // YYYY-MM-DD HH:MM:SS
-                    result.due_date = Some(due_dt.format(DEFAULT_DUE_DATE_FORMAT).to_string());
-                }
+                result.due_date = Some(self.format_iso_due_date(iso_date)?);
 
                 return Ok(result);
             } else {
@@ -380,14 +493,14 @@ impl Session {
                 .retrieve("sipsm", "checkout.open_circ_exists")?
                 .ok_or_else(|| self.editor().die_event())?;
 
-            result.screen_msg = Some(msg["message"].string()?)
+            result.screen_msg = Some(i18n::tr(msg["message"].str()?))
         } else {
             let msg = self
                 .editor()
                 .retrieve("sipsm", "checkout.patron_not_allowed")?
                 .ok_or_else(|| self.editor().die_event())?;
 
-            result.screen_msg = Some(msg["message"].string()?);
+            result.screen_msg = Some(i18n::tr(msg["message"].str()?));
         }
 
         Ok(result)
@@ -408,6 +521,10 @@ impl Session {
         options.insert("copy_barcode".to_string(), item_barcode.into());
         options.insert("patron_barcode".to_string(), patron_barcode.into());
 
+        if self.config().setting_is_true("checkout_ack_copy_alerts") {
+            options.insert("ack_copy_alerts".to_string(), EgValue::from(true));
+        }
+
         // Standalone transaction; cloning is just easier here.
         let mut editor = self.editor().clone();
 
@@ -454,17 +571,7 @@ impl Session {
                 result.renewal_remaining = circ["renewal_remaining"].int()?;
 
                 let iso_date = circ["due_date"].as_str().unwrap(); // required
-                let due_dt = date::parse_datetime(iso_date)?;
-
-                if self
-                    .config()
-                    .setting_is_true("due_date_use_sip_date_format")
-                {
-                    result.due_date = Some(sip2::util::sip_date_from_dt(&due_dt));
-                } else {
-                    // YYYY-MM-DD HH:MM:SS
-                    result.due_date = Some(due_dt.format(DEFAULT_DUE_DATE_FORMAT).to_string());
-                }
+                result.due_date = Some(self.format_iso_due_date(iso_date)?);
 
                 return Ok(result);
             } else {
@@ -495,14 +602,14 @@ impl Session {
                 .retrieve("sipsm", "checkout.open_circ_exists")?
                 .ok_or_else(|| self.editor().die_event())?;
 
-            result.screen_msg = Some(msg["message"].string()?)
+            result.screen_msg = Some(i18n::tr(msg["message"].str()?))
         } else {
             let msg = self
                 .editor()
                 .retrieve("sipsm", "checkout.patron_not_allowed")?
                 .ok_or_else(|| self.editor().die_event())?;
 
-            result.screen_msg = Some(msg["message"].string()?);
+            result.screen_msg = Some(i18n::tr(msg["message"].str()?));
         }
 
         Ok(result)
@@ -1,4 +1,7 @@
 use crate::session::Session;
+use chrono::NaiveDateTime;
+use eg::common::holds::{self, HoldPlacement, HoldRequest, HoldType};
+use eg::date;
 use eg::result::EgResult;
 use eg::EgEvent;
 use eg::EgValue;
@@ -8,6 +11,11 @@ use crate::item::Item;
 use crate::patron::Patron;
 
 impl Session {
+    /// Handle a Hold request (15).
+    ///
+    /// Hold mode "+" places a hold, "-" cancels the patron's hold on
+    /// the item, and "*" changes the pickup location (BS) and/or
+    /// expiration date (BW) of the patron's hold on the item.
     pub fn handle_hold(&mut self, sip_msg: sip2::Message) -> EgResult<sip2::Message> {
         let patron_barcode = sip_msg.get_field_value("AA").unwrap_or("");
         let item_barcode = sip_msg.get_field_value("AB").unwrap_or("");
@@ -17,6 +25,7 @@ impl Session {
             &[
                 "0", // OK
                 "N", // Available
+                &sip2::util::sip_date_now(),
             ],
             &[
                 ("AA", patron_barcode),
@@ -26,9 +35,10 @@ impl Session {
         )
         .unwrap();
 
-        // At present, hold cancelation is the only supported operation.
-        if sip_msg.fixed_fields().first().map(|f| f.value()) != Some("-") {
-            log::warn!("{self} unsupported hold operation");
+        let mode = sip_msg.fixed_fields().first().map(|f| f.value());
+
+        if !matches!(mode, Some("+") | Some("-") | Some("*")) {
+            log::warn!("{self} unsupported hold operation: {mode:?}");
             return Ok(response);
         }
 
@@ -42,19 +52,61 @@ impl Session {
             None => return Ok(response),
         };
 
-        let hold = match self.hold_from_copy(&patron, &item)? {
-            Some(v) => v,
-            None => return Ok(response),
-        };
+        let hold_id = if mode == Some("+") {
+            match self.place_hold(&sip_msg, &patron, &item)? {
+                Some(id) => id,
+                None => return Ok(response),
+            }
+        } else {
+            let hold = match self.hold_from_copy(&patron, &item)? {
+                Some(v) => v,
+                None => return Ok(response),
+            };
 
-        if !self.cancel_hold(hold.id()?)? {
-            return Ok(response);
-        }
+            let hold_id = hold.id()?;
+
+            let success = if mode == Some("-") {
+                self.cancel_hold(hold_id)?
+            } else {
+                self.modify_hold(&sip_msg, hold_id)?
+            };
+
+            if !success {
+                return Ok(response);
+            }
+
+            hold_id
+        };
 
         // Set the "OK" flag
         response.fixed_fields_mut()[0].set_value("1").unwrap();
 
-        response.add_field("AJ", &format!("{}", item.record_id));
+        response.add_field("AJ", &item.title);
+
+        self.add_hold_fields(&mut response, hold_id)?;
+
+        Ok(response)
+    }
+
+    /// Add the hold's availability, queue position, pickup location,
+    /// and expiration date to a Hold response.
+    fn add_hold_fields(&mut self, response: &mut sip2::Message, hold_id: i64) -> EgResult<()> {
+        let flesh = eg::hash! {
+            "flesh": 1,
+            "flesh_fields": {"ahr": ["current_copy", "pickup_lib"]},
+        };
+
+        let hold = self
+            .editor()
+            .retrieve_with_ops("ahr", hold_id, flesh)?
+            .ok_or_else(|| self.editor().die_event())?;
+
+        let on_shelf = !hold["shelf_time"].is_null()
+            && hold["current_shelf_lib"].as_int() == hold["pickup_lib"]["id"].as_int();
+
+        if on_shelf {
+            response.fixed_fields_mut()[1].set_value("Y").unwrap();
+        }
 
         // Use the targeted copy
         if let Some(bc) = hold["current_copy"]["barcode"].as_str() {
@@ -65,7 +117,159 @@ impl Session {
             }
         }
 
-        Ok(response)
+        if hold["cancel_time"].is_null() {
+            if let Some(position) = self.hold_queue_position(hold_id)? {
+                response.add_field("BR", &position.to_string());
+            }
+        }
+
+        response.maybe_add_field("BS", hold["pickup_lib"]["shortname"].as_str());
+
+        if let Some(expire) = hold["expire_time"].as_str() {
+            let expire = date::parse_datetime(expire)?;
+            response.add_field("BW", &sip2::util::sip_date_from_dt(&expire));
+        }
+
+        Ok(())
+    }
+
+    fn hold_queue_position(&mut self, hold_id: i64) -> EgResult<Option<i64>> {
+        let params = vec![
+            EgValue::from(self.editor().authtoken().unwrap()),
+            EgValue::from(hold_id),
+        ];
+
+        let stats = self.editor().client_mut().send_recv_one(
+            "open-ils.circ",
+            "open-ils.circ.hold.queue_stats.retrieve",
+            params,
+        )?;
+
+        Ok(stats.and_then(|s| s["queue_position"].as_int()))
+    }
+
+    /// Place a hold for the patron on the item, returning the new
+    /// hold's ID.
+    ///
+    /// Hold type "3" (specific copy) places a copy hold.  All other
+    /// types place a title hold on the item's bib record.  The pickup
+    /// library comes from the BS field, falling back to the patron's
+    /// home library.
+    fn place_hold(
+        &mut self,
+        sip_msg: &sip2::Message,
+        patron: &Patron,
+        item: &Item,
+    ) -> EgResult<Option<i64>> {
+        if patron.holds_denied {
+            log::info!("{self} holds denied for patron {}", patron.barcode);
+            return Ok(None);
+        }
+
+        let pickup_sn = sip_msg
+            .get_field_value("BS")
+            .map(|s| s.to_string())
+            .or(patron.home_lib.clone());
+
+        let Some(pickup_sn) = pickup_sn else {
+            log::warn!("{self} no pickup library for hold request");
+            return Ok(None);
+        };
+
+        let Some(pickup_lib) = self.pickup_lib(&pickup_sn)? else {
+            return Ok(None);
+        };
+
+        let mut request = match sip_msg.get_field_value("BY") {
+            Some("3") => HoldRequest::new(patron.id, HoldType::Copy, item.id, pickup_lib),
+            _ => HoldRequest::new(patron.id, HoldType::Title, item.record_id, pickup_lib),
+        };
+
+        if let Some(expire) = sip_msg.get_field_value("BW") {
+            request.expire_time = self.hold_expire_date(expire);
+        }
+
+        self.editor().xact_begin()?;
+
+        match holds::place_hold(self.editor(), &request, None)? {
+            HoldPlacement::Placed(hold) => {
+                self.editor().commit()?;
+                let hold_id = hold.id()?;
+                holds::retarget_holds(self.editor(), &[hold_id])?;
+                Ok(Some(hold_id))
+            }
+            HoldPlacement::Blocked(events) => {
+                self.editor().rollback()?;
+                for evt in events {
+                    log::info!("{self} hold placement blocked: {evt}");
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Apply a new pickup location (BS) and/or expiration date (BW)
+    /// to an existing hold.
+    fn modify_hold(&mut self, sip_msg: &sip2::Message, hold_id: i64) -> EgResult<bool> {
+        let mut hold = self
+            .editor()
+            .retrieve("ahr", hold_id)?
+            .ok_or_else(|| self.editor().die_event())?;
+
+        if let Some(pickup_sn) = sip_msg.get_field_value("BS") {
+            match self.pickup_lib(pickup_sn)? {
+                Some(id) => hold["pickup_lib"] = EgValue::from(id),
+                None => return Ok(false),
+            }
+        }
+
+        if let Some(expire) = sip_msg.get_field_value("BW") {
+            match self.hold_expire_date(expire) {
+                Some(d) => hold["expire_time"] = EgValue::from(d),
+                None => return Ok(false),
+            }
+        }
+
+        let params = vec![EgValue::from(self.editor().authtoken().unwrap()), hold];
+
+        let resp = self.editor().client_mut().send_recv_one(
+            "open-ils.circ",
+            "open-ils.circ.hold.update",
+            params,
+        )?;
+
+        match resp {
+            Some(r) => match EgEvent::parse(&r) {
+                Some(evt) => {
+                    log::info!("{self} hold update failed: {evt}");
+                    Ok(false)
+                }
+                None => Ok(true),
+            },
+            None => Ok(false),
+        }
+    }
+
+    /// Org unit ID for a pickup library shortname.
+    fn pickup_lib(&mut self, shortname: &str) -> EgResult<Option<i64>> {
+        match self.org_from_sn(shortname)? {
+            Some(o) => Ok(Some(o.id()?)),
+            None => {
+                log::warn!("{self} no such pickup library: {shortname}");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Translate a SIP date into a hold expire date.
+    fn hold_expire_date(&self, sip_date: &str) -> Option<String> {
+        match NaiveDateTime::parse_from_str(sip_date, sip2::spec::SIP_DATE_FORMAT) {
+            Ok(d) => Some(d.format("%Y-%m-%d").to_string()),
+            Err(_) => {
+                log::warn!("{self} Invalid hold expire date: {sip_date}");
+                None
+            }
+        }
     }
 
     fn cancel_hold(&mut self, hold_id: i64) -> EgResult<bool> {
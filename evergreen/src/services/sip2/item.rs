@@ -1,5 +1,5 @@
-use super::session::DEFAULT_DUE_DATE_FORMAT;
 use crate::session::Session;
+use eg::common::copy_alert;
 use eg::constants as C;
 use eg::date;
 use eg::EgResult;
@@ -72,16 +72,7 @@ impl Session {
             circ_patron_id = Some(circ["usr"].int()?);
 
             if let Some(iso_date) = circ["due_date"].as_str() {
-                let due_dt = date::parse_datetime(iso_date)?;
-                if self
-                    .config()
-                    .setting_is_true("due_date_use_sip_date_format")
-                {
-                    due_date = Some(sip2::util::sip_date_from_dt(&due_dt));
-                } else {
-                    // YYYY-MM-DD HH:MM:SS
-                    due_date = Some(due_dt.format(DEFAULT_DUE_DATE_FORMAT).to_string());
-                }
+                due_date = Some(self.format_iso_due_date(iso_date)?);
             }
         }
 
@@ -177,6 +168,153 @@ impl Session {
         }))
     }
 
+    /// Handle an Item Status Update request (19).
+    ///
+    /// The item properties (CH) value is recorded on the copy per the
+    /// "item_status_update_mode" setting:
+    ///
+    /// * "alert" (default) - Adds a copy alert of the type set in
+    ///   "item_status_update_alert_type" with the properties as its note.
+    /// * "stat_cat" - Maps the copy to the entry of the copy stat cat set
+    ///   in "item_status_update_stat_cat" whose value matches the
+    ///   properties.
+    pub fn handle_item_status_update(&mut self, sip_msg: sip2::Message) -> EgResult<sip2::Message> {
+        let barcode = sip_msg.get_field_value("AB").unwrap_or("");
+        let properties = sip_msg.get_field_value("CH").unwrap_or("");
+
+        let mut resp = sip2::Message::from_values(
+            "20",
+            &[
+                "0", // item properties ok
+                &sip2::util::sip_date_now(),
+            ],
+            &[("AB", barcode)],
+        )
+        .unwrap();
+
+        let item = match self.get_item_details(barcode)? {
+            Some(i) => i,
+            None => {
+                log::info!("{self} No copy found with barcode: {barcode}");
+                return Ok(resp);
+            }
+        };
+
+        resp.add_field("AJ", &item.title);
+
+        if properties.is_empty() {
+            log::info!("{self} Item Status Update for {item} has no item properties");
+            return Ok(resp);
+        }
+
+        let mode = self
+            .config()
+            .settings()
+            .get("item_status_update_mode")
+            .and_then(|m| m.as_str())
+            .unwrap_or("alert")
+            .to_string();
+
+        self.editor().xact_begin()?;
+
+        let result = match mode.as_str() {
+            "alert" => self.add_item_status_alert(&item, properties),
+            "stat_cat" => self.set_item_status_stat_cat(&item, properties),
+            _ => Err(format!("Invalid item_status_update_mode: {mode}").into()),
+        };
+
+        match result {
+            Ok(true) => {
+                self.editor().commit()?;
+                resp.fixed_fields_mut()[0].set_value("1").unwrap();
+                resp.add_field("CH", properties);
+            }
+            Ok(false) => self.editor().rollback()?,
+            Err(e) => {
+                log::error!("{self} Item Status Update for {item} failed: {e}");
+                self.editor().rollback()?;
+            }
+        }
+
+        Ok(resp)
+    }
+
+    /// Add a copy alert with the item properties as its note.
+    fn add_item_status_alert(&mut self, item: &Item, properties: &str) -> EgResult<bool> {
+        let alert_type = match self
+            .config()
+            .settings()
+            .get("item_status_update_alert_type")
+            .and_then(|t| t.as_int())
+        {
+            Some(t) => t,
+            None => {
+                log::warn!("{self} item_status_update_alert_type is not set");
+                return Ok(false);
+            }
+        };
+
+        let alert = eg::blessed! {
+            "_classname": "aca",
+            "copy": item.id,
+            "alert_type": alert_type,
+            "temp": "f",
+            "note": properties,
+        }?;
+
+        copy_alert::create_alert(self.editor(), alert)?;
+
+        Ok(true)
+    }
+
+    /// Map the copy to the stat cat entry matching the item properties.
+    fn set_item_status_stat_cat(&mut self, item: &Item, properties: &str) -> EgResult<bool> {
+        let stat_cat = match self
+            .config()
+            .settings()
+            .get("item_status_update_stat_cat")
+            .and_then(|c| c.as_int())
+        {
+            Some(c) => c,
+            None => {
+                log::warn!("{self} item_status_update_stat_cat is not set");
+                return Ok(false);
+            }
+        };
+
+        let query = eg::hash! {"stat_cat": stat_cat, "value": properties};
+
+        let entry = match self.editor().search("asce", query)?.pop() {
+            Some(e) => e,
+            None => {
+                log::info!("{self} stat cat {stat_cat} has no entry for {properties}");
+                return Ok(false);
+            }
+        };
+
+        if !self.editor().allowed_at("UPDATE_COPY", item.circ_lib)? {
+            return Err(self.editor().die_event());
+        }
+
+        let query = eg::hash! {"owning_copy": item.id, "stat_cat": stat_cat};
+
+        if let Some(mut map) = self.editor().search("ascecm", query)?.pop() {
+            map["stat_cat_entry"] = EgValue::from(entry.id()?);
+            self.editor().update(map)?;
+        } else {
+            let map = eg::blessed! {
+                "_classname": "ascecm",
+                "owning_copy": item.id,
+                "stat_cat": stat_cat,
+                "stat_cat_entry": entry.id()?,
+            }?;
+
+            self.editor().create(map)?;
+        }
+
+        Ok(true)
+    }
+
     /// Find an active hold linked to the copy.  The copy must be on
     /// the holds shelf or in transit to the holds shelf.
     fn get_copy_hold(
@@ -1,4 +1,6 @@
+use eg::common::circ;
 use eg::common::user;
+use eg::i18n;
 use eg::osrf::app::ApplicationWorker;
 use eg::osrf::message;
 use eg::osrf::method::{ParamCount, ParamDataType, StaticMethodDef, StaticParam};
@@ -33,6 +35,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "SIP2 sip2::Message JSON Value",
             },
         ],
+        redacted_params: &[1],
     },
     StaticMethodDef {
         name: "account.cud",
@@ -53,6 +56,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                 as the new password for the account",
             },
         ],
+        redacted_params: &[1],
     },
     StaticMethodDef {
         name: "setting_group.delete",
@@ -76,6 +80,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "Orphaned accounts will be transferred to this group",
             },
         ],
+        redacted_params: &[],
     },
 ];
 
@@ -131,25 +136,71 @@ pub fn dispatch_sip_request(
         }
     };
 
+    // Screen messages are translated using the language from the
+    // patron request, falling back to the locale configured for the
+    // SIP setting group.
+    let orig_locale = message::thread_locale();
+    if let Some(locale) = request_locale(&sip_ses, &sip_msg) {
+        message::set_thread_locale(&locale);
+    }
+
+    let response = dispatch_session_request(&mut sip_ses, msg_code, sip_msg);
+
+    message::set_thread_locale(&orig_locale);
+
+    let value = EgValue::from_json_value(response?.to_json_value())?;
+
+    session.respond_complete(value)
+}
+
+/// Returns the locale to use for responses to the SIP request, if
+/// one is known.
+fn request_locale(sip_ses: &Session, sip_msg: &sip2::Message) -> Option<String> {
+    // Patron status and patron information requests lead with
+    // the language fixed field.
+    if sip_msg.spec().code == "23" || sip_msg.spec().code == "63" {
+        let locale = sip_msg
+            .fixed_fields()
+            .first()
+            .and_then(|ff| i18n::sip_language_locale(ff.value()));
+
+        if let Some(l) = locale {
+            return Some(l.to_string());
+        }
+    }
+
+    sip_ses
+        .config()
+        .settings()
+        .get("locale")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+fn dispatch_session_request(
+    sip_ses: &mut Session,
+    msg_code: &str,
+    sip_msg: sip2::Message,
+) -> EgResult<sip2::Message> {
     let response = match msg_code {
-        "01" => handle_block_patron(&mut sip_ses, sip_msg)?,
-        "09" => handle_checkin(&mut sip_ses, sip_msg)?,
-        "11" => handle_checkout(&mut sip_ses, sip_msg)?,
-        "15" => handle_hold(&mut sip_ses, sip_msg)?,
-        "17" => handle_item_info(&mut sip_ses, sip_msg)?,
-        "23" => handle_patron_status(&mut sip_ses, sip_msg)?,
-        "29" => handle_renew(&mut sip_ses, sip_msg)?,
-        "35" => handle_end_patron_session(&mut sip_ses, sip_msg)?,
-        "37" => handle_payment(&mut sip_ses, sip_msg)?,
-        "63" => handle_patron_info(&mut sip_ses, sip_msg)?,
-        "65" => handle_renew_all(&mut sip_ses, sip_msg)?,
-        "XS" => handle_end_session(&mut sip_ses, sip_msg)?,
+        "01" => handle_block_patron(sip_ses, sip_msg)?,
+        "09" => handle_checkin(sip_ses, sip_msg)?,
+        "11" => handle_checkout(sip_ses, sip_msg)?,
+        "15" => handle_hold(sip_ses, sip_msg)?,
+        "17" => handle_item_info(sip_ses, sip_msg)?,
+        "19" => handle_item_status_update(sip_ses, sip_msg)?,
+        "23" => handle_patron_status(sip_ses, sip_msg)?,
+        "25" => handle_patron_enable(sip_ses, sip_msg)?,
+        "29" => handle_renew(sip_ses, sip_msg)?,
+        "35" => handle_end_patron_session(sip_ses, sip_msg)?,
+        "37" => handle_payment(sip_ses, sip_msg)?,
+        "63" => handle_patron_info(sip_ses, sip_msg)?,
+        "65" => handle_renew_all(sip_ses, sip_msg)?,
+        "XS" => handle_end_session(sip_ses, sip_msg)?,
         _ => return Err(format!("SIP message '{msg_code}' not implemented").into()),
     };
 
-    let value = EgValue::from_json_value(response.to_json_value())?;
-
-    session.respond_complete(value)
+    Ok(response)
 }
 
 fn handle_login(
@@ -281,13 +332,6 @@ fn handle_item_info(sip_ses: &mut Session, sip_msg: sip2::Message) -> EgResult<s
         }
     };
 
-    let cur_set = sip_ses.config().settings().get("currency");
-    let currency = if let Some(cur) = cur_set {
-        cur.str()?
-    } else {
-        "USD"
-    };
-
     let mut resp = sip2::Message::from_values(
         "18",
         &[
@@ -302,8 +346,7 @@ fn handle_item_info(sip_ses: &mut Session, sip_msg: sip2::Message) -> EgResult<s
             ("AJ", &item.title),
             ("AP", &item.current_loc),
             ("AQ", &item.permanent_loc),
-            ("BH", currency),
-            ("BV", &format!("{:.2}", item.deposit_amount)),
+            ("BV", &sip_ses.format_money(item.deposit_amount)),
             //("CI", "N"), // security inhibit / not supported
             ("CF", &format!("{}", item.hold_queue_length)),
             ("CK", &item.media_type),
@@ -313,6 +356,7 @@ fn handle_item_info(sip_ses: &mut Session, sip_msg: sip2::Message) -> EgResult<s
     )
     .unwrap();
 
+    resp.maybe_add_field("BH", sip_ses.currency_type());
     resp.maybe_add_field("CM", item.hold_pickup_date.as_deref());
     resp.maybe_add_field("CY", item.hold_patron_barcode.as_deref());
     resp.maybe_add_field("AH", item.due_date.as_deref());
@@ -344,11 +388,25 @@ fn handle_renew_all(sip_ses: &mut Session, sip_msg: sip2::Message) -> EgResult<s
     sip_ses.handle_renew_all(&sip_msg)
 }
 
-/// This is a no-op.  Just returns the expected response.
+/// Sends the patron an email receipt for items checked out during
+/// the patron session when the account is configured for e-receipts.
 fn handle_end_patron_session(
     sip_ses: &mut Session,
     sip_msg: sip2::Message,
 ) -> EgResult<sip2::Message> {
+    if sip_ses.config().setting_is_true("email_checkout_receipt") {
+        if let Some(barcode) = sip_msg.get_field_value("AA") {
+            let circ_ids = sip_ses.take_receipt_circs(barcode)?;
+
+            if !circ_ids.is_empty() {
+                if let Err(e) = send_email_receipt(sip_ses, &circ_ids) {
+                    // Ending the session succeeds regardless.
+                    log::error!("{sip_ses} cannot send email receipt: {e}");
+                }
+            }
+        }
+    }
+
     let resp = sip2::Message::from_values(
         "36",
         &[sip2::util::sip_bool(true), &sip2::util::sip_date_now()],
@@ -362,6 +420,20 @@ fn handle_end_patron_session(
     Ok(resp)
 }
 
+fn send_email_receipt(sip_ses: &mut Session, circ_ids: &[i64]) -> EgResult<()> {
+    // Standalone transaction; cloning is just easier here.
+    let mut editor = sip_ses.editor().clone();
+
+    editor.xact_begin()?;
+
+    if let Err(e) = circ::create_email_receipt_events(&mut editor, circ_ids) {
+        editor.rollback()?;
+        return Err(e);
+    }
+
+    editor.commit()
+}
+
 /// Remove the cached session data and auth data.
 ///
 /// No removal of sip.account here since we never create those.
@@ -378,10 +450,21 @@ fn handle_block_patron(sip_ses: &mut Session, sip_msg: sip2::Message) -> EgResul
     sip_ses.handle_block_patron(sip_msg)
 }
 
+fn handle_patron_enable(sip_ses: &mut Session, sip_msg: sip2::Message) -> EgResult<sip2::Message> {
+    sip_ses.handle_patron_enable(sip_msg)
+}
+
 fn handle_hold(sip_ses: &mut Session, sip_msg: sip2::Message) -> EgResult<sip2::Message> {
     sip_ses.handle_hold(sip_msg)
 }
 
+fn handle_item_status_update(
+    sip_ses: &mut Session,
+    sip_msg: sip2::Message,
+) -> EgResult<sip2::Message> {
+    sip_ses.handle_item_status_update(sip_msg)
+}
+
 pub fn account_cud(
     worker: &mut Box<dyn ApplicationWorker>,
     session: &mut ServerSession,
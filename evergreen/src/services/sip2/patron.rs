@@ -1,14 +1,45 @@
 use crate::session::Session;
 use eg::constants as C;
 use eg::date;
+use eg::i18n;
+use eg::idl;
 use eg::result::EgResult;
 use eg::EgEvent;
 use eg::EgValue;
 use evergreen as eg;
+use std::sync::Once;
 
 const EG_NULL: EgValue = EgValue::Null;
 const DEFAULT_LIST_ITEM_SIZE: usize = 10;
 
+/// config.standing_penalty applied by Block Patron requests when the
+/// "patron_block_penalty" setting is not set.  Blocks circulation,
+/// holds, and renewals.
+const DEFAULT_BLOCK_PENALTY: &str = "STAFF_CHR";
+
+/// IDL class linking Block Patron penalties to the cards they
+/// deactivated, so Patron Enable only reactivates cards that were
+/// deactivated via SIP.  Ships in schema/sip-blocked-card.*.
+const BLOCKED_CARD_CLASS: &str = "asbc";
+
+static MISSING_BLOCKED_CARD_WARNING: Once = Once::new();
+
+/// True if the class linking block penalties to cards is installed.
+fn blocked_cards_installed() -> bool {
+    if idl::get_class(BLOCKED_CARD_CLASS).is_ok() {
+        return true;
+    }
+
+    MISSING_BLOCKED_CARD_WARNING.call_once(|| {
+        log::warn!(
+            "IDL class '{BLOCKED_CARD_CLASS}' is not installed.  \
+            Cards deactivated by Block Patron will not be reactivated."
+        );
+    });
+
+    false
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Msg64HoldDatatype {
     Barcode,
@@ -388,7 +419,7 @@ impl Session {
 
         match av_format {
             AvFormat::Legacy => {
-                line = format!("{:.2} {}", balance_owed, last_billing_type);
+                line = format!("{} {}", self.format_money(balance_owed), last_billing_type);
                 if is_circ {
                     line += &format!(" {} / {}", title, author);
                 }
@@ -406,8 +437,10 @@ impl Session {
 
             AvFormat::SwyerB => {
                 line = format!(
-                    "Charge-Number: {}, Amount-Due: {:.2}, Fine-Type: {}",
-                    xact_id, balance_owed, fee_type
+                    "Charge-Number: {}, Amount-Due: {}, Fine-Type: {}",
+                    xact_id,
+                    self.format_money(balance_owed),
+                    fee_type
                 );
 
                 if is_circ {
@@ -838,7 +871,7 @@ impl Session {
 
         // Matching EG SIPServer.
         // Unknown if it serves a purpose.
-        patron.screen_msg = Some("blocked".to_string());
+        patron.screen_msg = Some(i18n::tr("blocked"));
 
         Ok(())
     }
@@ -1062,13 +1095,6 @@ impl Session {
             sbool(patron.max_fines)
         );
 
-        let cur_set = self.config().settings().get("currency");
-        let currency = if let Some(cur) = cur_set {
-            cur.str()?
-        } else {
-            "USD"
-        };
-
         let mut resp = sip2::Message::from_values(
             msg_code,
             &[
@@ -1086,9 +1112,8 @@ impl Session {
                 ("AO", self.config().institution()),
                 ("AA", barcode),
                 ("AE", &patron.name),
-                ("BH", currency),
                 ("BL", sip2::util::sip_bool(true)), // valid patron
-                ("BV", &format!("{:.2}", patron.balance_owed)),
+                ("BV", &self.format_money(patron.balance_owed)),
                 ("CQ", sip2::util::sip_bool(patron.password_verified)),
                 ("XI", &format!("{}", patron.id)),
             ],
@@ -1096,16 +1121,57 @@ impl Session {
         .unwrap();
 
         resp.maybe_add_field("AF", patron.screen_msg.as_deref());
+        resp.maybe_add_field("BH", self.currency_type());
         resp.maybe_add_field("BD", patron.address.as_deref());
         resp.maybe_add_field("BE", patron.email.as_deref());
 
         Ok(resp)
     }
 
+    /// Standing penalty applied by Block Patron requests.
+    ///
+    /// Set via the "patron_block_penalty" setting.  Defaults to
+    /// STAFF_CHR, which blocks circulation, holds, and renewals.
+    /// Returns an error if the default penalty does not exist.
+    pub fn patron_block_penalty(&mut self) -> EgResult<i64> {
+        let setting = self
+            .config()
+            .settings()
+            .get("patron_block_penalty")
+            .and_then(|v| v.as_int());
+
+        if let Some(id) = setting {
+            return Ok(id);
+        }
+
+        let penalty = self
+            .editor()
+            .search("csp", eg::hash! {"name": DEFAULT_BLOCK_PENALTY})?
+            .pop()
+            .ok_or_else(|| {
+                format!(
+                    "Standing penalty {DEFAULT_BLOCK_PENALTY} not found.  \
+                    Set patron_block_penalty for this SIP account"
+                )
+            })?;
+
+        penalty.id()
+    }
+
+    /// Handle a Block Patron request (01).
+    ///
+    /// Deactivates the patron's card and applies the block penalty,
+    /// with the blocked card message (AL) recorded in its note.  The
+    /// deactivated card is linked to the penalty when the blocked card
+    /// class is installed.
     pub fn handle_block_patron(&mut self, sip_msg: sip2::Message) -> EgResult<sip2::Message> {
         let barcode = sip_msg.get_field_value("AA").unwrap_or("");
         let block_msg_op = sip_msg.get_field_value("AL");
 
+        if sip_msg.fixed_fields().first().map(|f| f.value()) == Some("Y") {
+            log::info!("{self} card {barcode} was retained by the SC");
+        }
+
         let patron = match self.get_patron_details(barcode, None, None)? {
             Some(p) => p,
             None => return self.patron_response_common("24", barcode, None),
@@ -1123,6 +1189,9 @@ impl Session {
             // should not be able to get here.
             .ok_or_else(|| "Patron card search returned nothing".to_string())?;
 
+        let card_id = card.id()?;
+        let penalty_type = self.patron_block_penalty()?;
+
         self.editor().xact_begin()?;
 
         card["active"] = "f".into();
@@ -1134,7 +1203,7 @@ impl Session {
             "org_unit": self.editor().perm_org(),
             "set_date": "now",
             "staff": self.editor().requestor_id().unwrap(),
-            "standing_penalty": 20, // ALERT_NOTE
+            "standing_penalty": penalty_type,
         }?;
 
         let msg = self
@@ -1142,11 +1211,11 @@ impl Session {
             .retrieve("sipsm", "patron_block.penalty_note")?
             .ok_or_else(|| self.editor().die_event())?;
 
-        let penalty_message = if let Some(block_msg) = block_msg_op {
-            format!("{}\n{block_msg}", msg["message"].str()?)
-        } else {
-            msg["message"].string()?
-        };
+        let mut penalty_message = msg["message"].string()?;
+
+        if let Some(block_msg) = block_msg_op {
+            penalty_message += &format!("\n{block_msg}");
+        }
 
         let msg = self
             .editor()
@@ -1180,6 +1249,11 @@ impl Session {
             log::error!("{self} blocking patron failed with {evt}");
             self.editor().rollback()?;
         } else {
+            // penalty.apply returns the new penalty's ID.
+            match penalty_result.as_int() {
+                Some(penalty_id) => self.link_blocked_card(penalty_id, card_id)?,
+                None => log::warn!("{self} penalty.apply returned no penalty ID"),
+            }
             self.editor().commit()?;
         }
 
@@ -1190,4 +1264,132 @@ impl Session {
         // SIP message 01 wants a message 24 (patron status) response.
         self.patron_response_common("24", barcode, Some(&patron))
     }
+
+    /// Handle a Patron Enable request (25).
+    ///
+    /// Lifts blocks placed via Block Patron, i.e. open block penalties
+    /// carrying the SIP block title, and reactivates the cards those
+    /// blocks deactivated.  Blocks and cards deactivated any other way
+    /// are left alone.  Disallowed entirely when
+    /// the "patron_enable_disallowed" setting is true.
+    pub fn handle_patron_enable(&mut self, sip_msg: sip2::Message) -> EgResult<sip2::Message> {
+        let barcode = sip_msg.get_field_value("AA").unwrap_or("");
+
+        let patron = match self.get_patron_details(barcode, None, None)? {
+            Some(p) => p,
+            None => return self.patron_response_common("26", barcode, None),
+        };
+
+        if self.config().setting_is_true("patron_enable_disallowed") {
+            log::warn!("{self} patron enable is not allowed for this account");
+            return self.patron_response_common("26", barcode, Some(&patron));
+        }
+
+        let msg = self
+            .editor()
+            .retrieve("sipsm", "patron_block.title")?
+            .ok_or_else(|| self.editor().die_event())?;
+
+        let block_title = msg["message"].string()?;
+        let penalty_type = self.patron_block_penalty()?;
+
+        let query = eg::hash! {
+            "usr": patron.id,
+            "standing_penalty": penalty_type,
+            "stop_date": EG_NULL,
+        };
+
+        let flesh = eg::hash! {"flesh": 1, "flesh_fields": {"ausp": ["usr_message"]}};
+
+        let penalties: Vec<EgValue> = self
+            .editor()
+            .search_with_ops("ausp", query, flesh)?
+            .into_iter()
+            .filter(|p| p["usr_message"]["title"].as_str() == Some(block_title.as_str()))
+            .collect();
+
+        if penalties.is_empty() {
+            log::info!("{self} patron {barcode} has no blocks placed via SIP");
+            return self.patron_response_common("26", barcode, Some(&patron));
+        }
+
+        self.editor().xact_begin()?;
+
+        let mut card_ids = Vec::new();
+
+        for mut penalty in penalties {
+            log::info!(
+                "{self} lifting SIP block penalty {} for patron {barcode}",
+                penalty["id"]
+            );
+
+            card_ids.append(&mut self.blocked_card_ids(penalty.id()?)?);
+
+            // Unflesh before updating.
+            penalty["usr_message"] = penalty["usr_message"]["id"].clone();
+            penalty["stop_date"] = "now".into();
+
+            self.editor().update(penalty)?;
+        }
+
+        for card_id in card_ids {
+            let Some(mut card) = self.editor().retrieve("ac", card_id)? else {
+                continue;
+            };
+
+            if card["usr"].int()? != patron.id || card["active"].boolish() {
+                continue;
+            }
+
+            log::info!("{self} reactivating card {card_id} for patron {barcode}");
+
+            card["active"] = "t".into();
+            self.editor().update(card)?;
+        }
+
+        self.editor().commit()?;
+
+        // Update our patron so the response data reflects the
+        // lifted block.
+        let patron = self.get_patron_details(barcode, None, None)?.unwrap();
+
+        self.patron_response_common("26", barcode, Some(&patron))
+    }
+
+    /// Record that the block penalty deactivated the card.
+    ///
+    /// The editor must be in a transaction.
+    fn link_blocked_card(&mut self, penalty_id: i64, card_id: i64) -> EgResult<()> {
+        if !blocked_cards_installed() {
+            return Ok(());
+        }
+
+        let link = eg::blessed! {
+            "_classname": BLOCKED_CARD_CLASS,
+            "penalty": penalty_id,
+            "card": card_id,
+        }?;
+
+        self.editor().create(link)?;
+
+        Ok(())
+    }
+
+    /// IDs of the cards deactivated by the block penalty.
+    fn blocked_card_ids(&mut self, penalty_id: i64) -> EgResult<Vec<i64>> {
+        if !blocked_cards_installed() {
+            return Ok(Vec::new());
+        }
+
+        let mut card_ids = Vec::new();
+
+        for link in self
+            .editor()
+            .search(BLOCKED_CARD_CLASS, eg::hash! {"penalty": penalty_id})?
+        {
+            card_ids.push(link["card"].int()?);
+        }
+
+        Ok(card_ids)
+    }
 }
@@ -1,5 +1,6 @@
 use super::patron::Patron;
 use super::session::Session;
+use eg::i18n;
 use eg::result::EgResult;
 use eg::EgValue;
 use evergreen as eg;
@@ -156,7 +157,7 @@ impl Session {
         }
 
         if pay_amount > sum["balance_owed"].float()? {
-            result.screen_msg = Some("Overpayment not allowed".to_string());
+            result.screen_msg = Some(i18n::tr("Overpayment not allowed"));
             return Ok(Vec::new());
         }
 
@@ -178,7 +179,7 @@ impl Session {
         let xacts = self.get_patron_xacts(&patron, None)?; // see patron mod
 
         if xacts.is_empty() {
-            result.screen_msg = Some("No transactions to pay".to_string());
+            result.screen_msg = Some(i18n::tr("No transactions to pay"));
             return Ok(payments);
         }
 
@@ -222,7 +223,7 @@ impl Session {
         }
 
         if amount_remaining > 0.0 {
-            result.screen_msg = Some("Overpayment not allowed".to_string());
+            result.screen_msg = Some(i18n::tr("Overpayment not allowed"));
             // An overpayment results in no payments at all.
             return Ok(Vec::new());
         }
@@ -1,4 +1,5 @@
 use eg::common::auth;
+use eg::common::feature_flag;
 use eg::osrf::cache::Cache;
 use eg::Editor;
 use eg::EgResult;
@@ -51,7 +52,7 @@ const CACHE_PFX: &str = "sip2";
 /// hold
 /// renew
 /// renew all
-const INSTITUTION_SUPPORTS: &str = "YYYYYNYYYYYNNNYY";
+const INSTITUTION_SUPPORTS: &str = "YYYYYNYYYYYYYYYY";
 
 pub const DEFAULT_DUE_DATE_FORMAT: &str = "%F %T";
 
@@ -174,6 +175,16 @@ impl Session {
         &self.config
     }
 
+    /// True if the named feature flag routes this request to a native
+    /// (Rust) code path.
+    ///
+    /// Flags apply at the workstation org unit and are bucketed on the
+    /// provided key, typically an item barcode.
+    pub fn use_native_flag(&mut self, flag: &str, key: &str) -> EgResult<bool> {
+        let org_id = self.editor.perm_org();
+        feature_flag::is_enabled(&mut self.editor, flag, Some(org_id), Some(key))
+    }
+
     fn load_config(editor: &mut Editor, setting_group: i64) -> EgResult<Config> {
         let flesh = eg::hash! {
             "flesh": 1,
@@ -263,6 +274,36 @@ impl Session {
         Cache::del_global(&format!("{CACHE_PFX}:{}", self.seskey))
     }
 
+    fn receipt_cache_key(&self, patron_barcode: &str) -> String {
+        format!("{CACHE_PFX}:{}:receipt:{patron_barcode}", self.seskey)
+    }
+
+    /// Track a circulation to include in the patron's email receipt
+    /// when their patron session ends.
+    pub fn add_receipt_circ(&self, patron_barcode: &str, circ_id: i64) -> EgResult<()> {
+        let key = self.receipt_cache_key(patron_barcode);
+
+        let mut circ_ids = Cache::get_global(&key)?.unwrap_or_else(|| eg::array! {});
+        circ_ids.push(circ_id)?;
+
+        Cache::set_global(&key, circ_ids)
+    }
+
+    /// Returns and forgets the circulations tracked for the patron's
+    /// email receipt.
+    pub fn take_receipt_circs(&self, patron_barcode: &str) -> EgResult<Vec<i64>> {
+        let key = self.receipt_cache_key(patron_barcode);
+
+        let circ_ids = match Cache::get_global(&key)? {
+            Some(ids) => ids,
+            None => return Ok(Vec::new()),
+        };
+
+        Cache::del_global(&key)?;
+
+        circ_ids.members().map(|id| id.int()).collect()
+    }
+
     /// Get a new authtoken from the ILS.
     ///
     /// This is necessary when creating a new session or when a session
@@ -1,10 +1,15 @@
+use super::session::DEFAULT_DUE_DATE_FORMAT;
 use crate::session::Session;
+use eg::date;
 use eg::result::EgResult;
 use eg::EgValue;
 use evergreen as eg;
+use std::fmt::Write;
 
 const PATRON_NAME_PARTS: [&str; 3] = ["first_given_name", "second_given_name", "family_name"];
 
+const DEFAULT_CURRENCY: &str = "USD";
+
 impl Session {
     /// Extract the title and author info from a copy object.
     ///
@@ -173,6 +178,83 @@ impl Session {
         addr
     }
 
+    /// Format a due date per the account settings.
+    ///
+    /// The "due_date_format" setting may be "sip" for the 18-character
+    /// SIP date format, "iso" for ISO-8601, or a strftime-style format
+    /// string.  The older "due_date_use_sip_date_format" flag is
+    /// honored when no format is set.  Defaults to YYYY-MM-DD HH:MM:SS.
+    pub fn format_due_date(&self, due_dt: &date::EgDate) -> String {
+        let format = self
+            .config()
+            .settings()
+            .get("due_date_format")
+            .and_then(|f| f.as_str());
+
+        match format {
+            Some("sip") => return sip2::util::sip_date_from_dt(due_dt),
+            Some("iso") => return date::to_iso(due_dt),
+            Some(fmt) => {
+                let mut value = String::new();
+                // Invalid format strings produce an error instead of
+                // a value.  Fall through to the default format.
+                if write!(value, "{}", due_dt.format(fmt)).is_ok() {
+                    return value;
+                }
+                log::warn!("{self} invalid due_date_format: {fmt}");
+            }
+            None => {
+                if self
+                    .config()
+                    .setting_is_true("due_date_use_sip_date_format")
+                {
+                    return sip2::util::sip_date_from_dt(due_dt);
+                }
+            }
+        }
+
+        due_dt.format(DEFAULT_DUE_DATE_FORMAT).to_string()
+    }
+
+    /// Format an ISO date string as a due date.
+    pub fn format_iso_due_date(&self, iso_date: &str) -> EgResult<String> {
+        Ok(self.format_due_date(&date::parse_datetime(iso_date)?))
+    }
+
+    /// Format a monetary amount with 2 decimal places using the
+    /// "decimal_separator" setting, which defaults to ".".
+    pub fn format_money(&self, amount: f64) -> String {
+        let value = format!("{amount:.2}");
+
+        match self
+            .config()
+            .settings()
+            .get("decimal_separator")
+            .and_then(|s| s.as_str())
+        {
+            Some(sep) if sep != "." => value.replace('.', sep),
+            _ => value,
+        }
+    }
+
+    /// Currency type for the BH field per the "currency" setting.
+    ///
+    /// Returns None if the account omits currency type fields via the
+    /// "omit_currency_type" setting.
+    pub fn currency_type(&self) -> Option<&str> {
+        if self.config().setting_is_true("omit_currency_type") {
+            return None;
+        }
+
+        Some(
+            self.config()
+                .settings()
+                .get("currency")
+                .and_then(|c| c.as_str())
+                .unwrap_or(DEFAULT_CURRENCY),
+        )
+    }
+
     /// Add a stat cat value to a message using the provided code.
     pub fn _format_stat_cat_sip_field(
         &self,
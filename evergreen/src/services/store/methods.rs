@@ -23,6 +23,7 @@ pub static METHODS: &[StaticMethodDef] = &[
         param_count: ParamCount::Zero,
         handler: manage_xact,
         params: &[],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "transaction.rollback",
@@ -30,6 +31,7 @@ pub static METHODS: &[StaticMethodDef] = &[
         param_count: ParamCount::Zero,
         handler: manage_xact,
         params: &[],
+        redacted_params: &[],
     },
     StaticMethodDef {
         name: "transaction.commit",
@@ -37,6 +39,7 @@ pub static METHODS: &[StaticMethodDef] = &[
         param_count: ParamCount::Zero,
         handler: manage_xact,
         params: &[],
+        redacted_params: &[],
     },
     // Stub method for *.create calls.  Not directly published.
     StaticMethodDef {
@@ -49,6 +52,7 @@ pub static METHODS: &[StaticMethodDef] = &[
             datatype: ParamDataType::Object,
             desc: "Object to update",
         }],
+        redacted_params: &[],
     },
     // Stub method for *.retrieve calls. Not directly published.
     StaticMethodDef {
@@ -68,6 +72,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "Flesh Fields Object",
             },
         ],
+        redacted_params: &[],
     },
     // Stub method for *.search calls. Not directly published.
     StaticMethodDef {
@@ -87,6 +92,7 @@ pub static METHODS: &[StaticMethodDef] = &[
                 desc: "Flesh Fields Object",
             },
         ],
+        redacted_params: &[],
     },
     // Stub method for *.update calls. Not directly published.
     StaticMethodDef {
@@ -99,6 +105,7 @@ pub static METHODS: &[StaticMethodDef] = &[
             datatype: ParamDataType::Object,
             desc: "Object to update",
         }],
+        redacted_params: &[],
     },
     // Stub method for *.delete calls.  Not directly published.
     StaticMethodDef {
@@ -111,6 +118,7 @@ pub static METHODS: &[StaticMethodDef] = &[
             datatype: ParamDataType::Scalar,
             desc: "Primary Key Value",
         }],
+        redacted_params: &[],
     },
     // Stub method for *.delete calls.  Not directly published.
     StaticMethodDef {
@@ -123,6 +131,7 @@ pub static METHODS: &[StaticMethodDef] = &[
             datatype: ParamDataType::Object,
             desc: "JSON Query Object/Hash",
         }],
+        redacted_params: &[],
     },
 ];
 
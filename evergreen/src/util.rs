@@ -12,6 +12,7 @@ use std::time::Duration;
 use std::time::Instant;
 
 pub const REDACTED_PARAMS_STR: &str = "**PARAMS REDACTED**";
+pub const REDACTED_PARAM_STR: &str = "**REDACTED**";
 
 // Typical value for SOMAXCONN
 const CONNECT_TCP_BACKLOG: i32 = 128;
@@ -149,18 +150,29 @@ impl Timer {
 
 /// Creates a (JSON) String verion of a list of method parameters,
 /// replacing params with a generic REDACTED message for log-protected
-/// methods.
+/// methods and replacing individual params at the redacted positions.
 ///
 /// ```
 /// use evergreen::util;
+/// use evergreen::EgValue;
+///
 /// let method = "opensrf.system.private.stuff";
 /// let log_protect = vec!["opensrf.system.private".to_string()];
 /// let params = vec![];
 ///
-/// let s = util::stringify_params(method, &params, &log_protect);
+/// let s = util::stringify_params(method, &params, &log_protect, &[]);
 /// assert_eq!(s.as_str(), util::REDACTED_PARAMS_STR);
+///
+/// let params = vec![EgValue::from("user"), EgValue::from("secret")];
+/// let s = util::stringify_params("opensrf.login", &params, &log_protect, &[1]);
+/// assert_eq!(s.as_str(), r#""user" "**REDACTED**""#);
 /// ```
-pub fn stringify_params(method: &str, params: &[EgValue], log_protect: &[String]) -> String {
+pub fn stringify_params(
+    method: &str,
+    params: &[EgValue],
+    log_protect: &[String],
+    redacted: &[usize],
+) -> String {
     // Check if the method should be protected
     let is_protected = log_protect.iter().any(|m| method.starts_with(m));
 
@@ -169,8 +181,15 @@ pub fn stringify_params(method: &str, params: &[EgValue], log_protect: &[String]
     } else {
         params
             .iter()
-            // EgValue.dump() consumes the value, hence the clone.
-            .map(|p| p.clone().dump())
+            .enumerate()
+            .map(|(idx, p)| {
+                if redacted.contains(&idx) {
+                    EgValue::from(REDACTED_PARAM_STR).dump()
+                } else {
+                    // EgValue.dump() consumes the value, hence the clone.
+                    p.clone().dump()
+                }
+            })
             .collect::<Vec<_>>()
             .join(" ")
     }
@@ -335,3 +354,43 @@ pub fn tcp_listener(address: &str, port: u16, read_timeout: u64) -> EgResult<Tcp
 
     Ok(socket.into())
 }
+
+/// Org units where permission is needed to update an owned object:
+/// the owner stored in `existing`, plus `new_owner` if it differs.
+///
+/// ```
+/// use evergreen as eg;
+/// use eg::util;
+///
+/// let existing = eg::hash! {"owner": 1};
+///
+/// assert_eq!(util::owner_orgs(&existing, 1).unwrap(), vec![1]);
+/// assert_eq!(util::owner_orgs(&existing, 4).unwrap(), vec![1, 4]);
+/// ```
+pub fn owner_orgs(existing: &EgValue, new_owner: i64) -> EgResult<Vec<i64>> {
+    let old_owner = existing["owner"].int()?;
+
+    if old_owner == new_owner {
+        Ok(vec![old_owner])
+    } else {
+        Ok(vec![old_owner, new_owner])
+    }
+}
+
+/// Returns an error unless the value is an IDL object of the class.
+///
+/// ```
+/// use evergreen as eg;
+/// use eg::util;
+///
+/// let value = eg::hash! {"id": 1};
+///
+/// assert!(util::verify_class(&value, "aou").is_err());
+/// ```
+pub fn verify_class(value: &EgValue, class: &str) -> EgResult<()> {
+    if value.classname() == Some(class) {
+        Ok(())
+    } else {
+        Err(format!("Expected an object of class {class}: {value}").into())
+    }
+}
@@ -0,0 +1,50 @@
+use crate::util;
+use eg::common::idempotency::{IdempotencyKey, Reservation};
+use eg::osrf::cache::Cache;
+use eg::EgResult;
+use evergreen as eg;
+
+pub fn run_live_tests(tester: &mut util::Tester) -> EgResult<()> {
+    tester.timer.start();
+
+    Cache::init_cache("global").expect("Cache Connected");
+
+    let key_str = format!("live-test-{}", eg::util::random_number(12));
+    let key = IdempotencyKey::new("open-ils.circ.checkout", 1, &key_str).unwrap();
+
+    assert!(matches!(key.reserve()?, Reservation::Reserved));
+
+    tester.timer.log("Reserved Key");
+
+    // A duplicate request arriving while the first is running is
+    // turned away.
+    assert!(matches!(key.reserve()?, Reservation::InProgress));
+
+    let err = key.in_progress_error().to_string();
+    assert!(err.contains(key.cache_key()));
+
+    tester.timer.log("Duplicate Key In Progress");
+
+    let response = eg::hash! {"circ": 123, "textcode": "SUCCESS"};
+    key.complete(&response)?;
+
+    // Retries get the cached response.
+    match key.reserve()? {
+        Reservation::Completed(value) => {
+            assert_eq!(value["circ"].int()?, 123);
+            assert_eq!(value["textcode"].as_str(), Some("SUCCESS"));
+        }
+        _ => panic!("Completed key should return its cached response"),
+    }
+
+    tester.timer.log("Completed Key Returns Response");
+
+    key.release();
+
+    assert!(matches!(key.reserve()?, Reservation::Reserved));
+    key.release();
+
+    tester.timer.log("Released Key");
+
+    Ok(())
+}
@@ -3,6 +3,7 @@ mod auth;
 mod auth_to_auth_linker;
 mod cache;
 mod circ;
+mod idempotency;
 mod json_query;
 mod store;
 mod util;
@@ -33,6 +34,7 @@ fn main() -> eg::EgResult<()> {
 
     let suites = [
         cache::run_live_tests,
+        idempotency::run_live_tests,
         auth::run_live_tests,
         auth_to_auth_linker::run_live_tests, // THIS ONE DESTROYS DATA, please be careful with it!
         circ::run_live_tests,
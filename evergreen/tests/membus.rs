@@ -0,0 +1,256 @@
+//! Tests for the in-memory message bus.
+//!
+//! To run:
+//! cargo test --package evergreen --features memory-bus --test membus
+#![cfg(feature = "memory-bus")]
+use eg::osrf::addr::BusAddress;
+use eg::osrf::app::ApplicationWorker;
+use eg::osrf::bus::Bus;
+use eg::osrf::client::Client;
+use eg::osrf::conf::ConfigBuilder;
+use eg::osrf::message::{MethodCall, Payload, TransportMessage};
+use eg::osrf::method::{ParamCount, StaticMethodDef};
+use eg::osrf::session::ServerSession;
+use eg::{EgError, EgResult, EgValue};
+use evergreen as eg;
+use std::any::Any;
+use std::sync::Once;
+use std::thread;
+
+const CONFIG: &str = r#"
+<config>
+  <opensrf>
+    <domain>private.localhost</domain>
+    <port>6379</port>
+    <username>opensrf</username>
+    <passwd>password</passwd>
+  </opensrf>
+</config>
+"#;
+
+const SERVICE: &str = "test.echo";
+
+static STORE_CONFIG: Once = Once::new();
+
+fn new_bus() -> Bus {
+    let config = ConfigBuilder::from_xml_string(CONFIG)
+        .expect("Config should parse")
+        .build()
+        .expect("Config should build");
+
+    let mut bus = Bus::new(config.client()).expect("Bus should connect");
+
+    // Client sessions read the router and domain from the global config.
+    STORE_CONFIG.call_once(|| config.store().expect("Config should store"));
+
+    // Skip IDL parsing of message bodies.
+    bus.set_raw_data_mode(true);
+    bus
+}
+
+#[test]
+fn send_recv() {
+    let mut sender = new_bus();
+    let mut receiver = new_bus();
+
+    assert_ne!(sender.address().as_str(), receiver.address().as_str());
+
+    let to = receiver.address().as_str().to_string();
+    let from = sender.address().as_str().to_string();
+
+    sender
+        .send(TransportMessage::new(&to, &from, "thread-1"))
+        .unwrap();
+    sender
+        .send(TransportMessage::new(&to, &from, "thread-2"))
+        .unwrap();
+
+    assert_eq!(receiver.llen(&to).unwrap(), 2);
+
+    let msg = receiver.recv(0, None).unwrap().expect("Message received");
+    assert_eq!(msg.thread(), "thread-1");
+    assert_eq!(msg.from(), from);
+
+    let msg = receiver.recv(0, None).unwrap().expect("Message received");
+    assert_eq!(msg.thread(), "thread-2");
+
+    assert!(receiver.recv(0, None).unwrap().is_none());
+    assert_eq!(receiver.ttl(&to).unwrap(), -2);
+}
+
+#[test]
+fn blocking_recv_across_threads() {
+    let mut receiver = new_bus();
+    let to = receiver.address().as_str().to_string();
+
+    let handle = thread::spawn(move || {
+        let mut sender = new_bus();
+        let from = sender.address().as_str().to_string();
+        sender
+            .send(TransportMessage::new(&to, &from, "threaded"))
+            .unwrap();
+    });
+
+    let msg = receiver.recv(5, None).unwrap().expect("Message received");
+    assert_eq!(msg.thread(), "threaded");
+
+    handle.join().unwrap();
+}
+
+#[test]
+fn keys_and_drop() {
+    let mut bus = new_bus();
+    let addr = {
+        let mut other = new_bus();
+        let addr = other.address().as_str().to_string();
+        let from = bus.address().as_str().to_string();
+
+        bus.send(TransportMessage::new(&addr, &from, "t")).unwrap();
+        assert!(bus.keys("opensrf:client:*").unwrap().contains(&addr));
+        assert_eq!(other.set_key_timeout(&addr, 60).unwrap(), 1);
+        assert!(other.ttl(&addr).unwrap() > 0);
+
+        addr
+    };
+
+    // Pending messages are removed when the recipient Bus is dropped.
+    assert_eq!(bus.llen(&addr).unwrap(), 0);
+}
+
+/// Minimal application worker for dispatching test API calls.
+#[derive(Default)]
+struct EchoWorker {
+    calls: usize,
+}
+
+impl ApplicationWorker for EchoWorker {
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn worker_start(&mut self, _client: Client) -> EgResult<()> {
+        Ok(())
+    }
+    fn start_session(&mut self) -> EgResult<()> {
+        Ok(())
+    }
+    fn end_session(&mut self) -> EgResult<()> {
+        Ok(())
+    }
+    fn keepalive_timeout(&mut self) -> EgResult<()> {
+        Ok(())
+    }
+    fn api_call_error(&mut self, _api_name: &str, _error: EgError) {}
+    fn worker_idle_wake(&mut self, _connected: bool) -> EgResult<()> {
+        Ok(())
+    }
+    fn worker_end(&mut self) -> EgResult<()> {
+        Ok(())
+    }
+}
+
+static METHODS: &[StaticMethodDef] = &[StaticMethodDef {
+    name: "echo",
+    desc: "Responds with each parameter",
+    param_count: ParamCount::Any,
+    handler: echo,
+    params: &[],
+    redacted_params: &[],
+}];
+
+fn echo(
+    worker: &mut Box<dyn ApplicationWorker>,
+    session: &mut ServerSession,
+    method: MethodCall,
+) -> EgResult<()> {
+    let worker = worker
+        .as_any_mut()
+        .downcast_mut::<EchoWorker>()
+        .ok_or("Cannot downcast to EchoWorker")?;
+
+    worker.calls += 1;
+
+    for param in method.params() {
+        session.respond(param.clone())?;
+    }
+
+    Ok(())
+}
+
+/// Stand in for the router and a service worker: pull one request
+/// from the router's queue and dispatch it to the matching method.
+///
+/// Returns the number of API calls handled.
+fn serve_one(bus: Bus) -> usize {
+    let client = Client::from_bus(bus);
+
+    let mut tmsg = client
+        .singleton()
+        .borrow_mut()
+        .bus_mut()
+        .recv(5, None)
+        .unwrap()
+        .expect("Request received");
+
+    let sender = BusAddress::parse_str(tmsg.from()).unwrap();
+    let thread = tmsg.thread().to_string();
+
+    let mut app_worker: Box<dyn ApplicationWorker> = Box::<EchoWorker>::default();
+
+    for mut msg in tmsg.take_body() {
+        let trace = msg.thread_trace();
+
+        let call = match msg.take_payload() {
+            Payload::Method(m) => m,
+            _ => panic!("Request should contain a MethodCall"),
+        };
+
+        let method_def = METHODS
+            .iter()
+            .map(|m| m.into_method(SERVICE))
+            .find(|m| m.name() == call.method())
+            .expect("Method should exist");
+
+        let mut session =
+            ServerSession::new(client.clone(), SERVICE, &thread, trace, sender.clone());
+
+        (method_def.handler())(&mut app_worker, &mut session, call).unwrap();
+
+        session.send_complete().unwrap();
+    }
+
+    let worker = app_worker
+        .as_any_mut()
+        .downcast_mut::<EchoWorker>()
+        .unwrap();
+    worker.calls
+}
+
+#[test]
+fn api_call() {
+    let client = Client::from_bus(new_bus());
+
+    let mut router = new_bus();
+    router.set_address(&BusAddress::for_router("router", client.domain()));
+
+    let handle = thread::spawn(move || serve_one(router));
+
+    let mut ses = client.session(SERVICE);
+    let mut req = ses
+        .request(
+            "test.echo.echo",
+            vec![EgValue::from("hello"), EgValue::from(42)],
+        )
+        .unwrap();
+
+    let mut responses = Vec::new();
+    while let Some(value) = req.recv().unwrap() {
+        responses.push(value);
+    }
+
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0].as_str(), Some("hello"));
+    assert_eq!(responses[1].as_int(), Some(42));
+    assert!(req.complete());
+
+    assert_eq!(handle.join().unwrap(), 1);
+}
@@ -0,0 +1,415 @@
+//! Routines for reading and writing Ex Libris Aleph sequential files.
+//!
+//! Aleph sequential files contain one line per field.  Each line
+//! begins with the 9-digit system number of its record, followed by
+//! the tag, indicators, and an "L" (the field's script).
+//!
+//! ```text
+//! 000000001 FMT   L BK
+//! 000000001 LDR   L ^^^^^nam^a2200000^a^4500
+//! 000000001 001   L ocm12345
+//! 000000001 24510 L $$aTitle /$$cAuthor.
+//! ```
+//!
+//! Blanks in the leader and control fields are written as "^" and
+//! subfields are delimited by "$$".  Lines with the same system number
+//! form a record.
+//!
+//! The Aleph-specific FMT field is derived from the leader when writing
+//! and ignored when reading.
+use super::fixed_fields::MaterialType;
+use super::Controlfield;
+use super::Field;
+use super::Record;
+use super::Subfield;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+
+const SYSNO_SIZE: usize = 9;
+const DATA_START: usize = 18;
+const SUBFIELD_DELIMITER: &str = "$$";
+const FIXED_BLANK: char = '^';
+const FORMAT_TAG: &str = "FMT";
+
+/// Zero-pad a numeric system number to 9 digits.
+fn format_sysno(sysno: &str) -> Result<String, String> {
+    if sysno.is_empty()
+        || sysno.len() > SYSNO_SIZE
+        || sysno.chars().any(|c| c.is_whitespace() || c.is_control())
+    {
+        return Err(format!("Invalid Aleph system number: '{sysno}'"));
+    }
+
+    if sysno.chars().all(|c| c.is_ascii_digit()) {
+        Ok(format!("{sysno:0>9}"))
+    } else {
+        Ok(format!("{sysno:<9}"))
+    }
+}
+
+/// Aleph format code for a record, e.g. "BK" for books.
+fn format_code(record: &Record) -> Option<&'static str> {
+    let code = match MaterialType::from_leader(record.typed_leader())? {
+        MaterialType::Books => "BK",
+        MaterialType::ComputerFiles => "CF",
+        MaterialType::Maps => "MP",
+        MaterialType::Music => "MU",
+        MaterialType::ContinuingResources => "SE",
+        MaterialType::VisualMaterials => "VM",
+        MaterialType::MixedMaterials => "MX",
+    };
+
+    Some(code)
+}
+
+/// Split an Aleph line into its system number and the remainder.
+fn split_sysno(line: &str) -> Result<(&str, &str), String> {
+    match (line.get(..SYSNO_SIZE), line.get(SYSNO_SIZE..)) {
+        (Some(sysno), Some(rest)) if rest.starts_with(' ') => Ok((sysno, rest)),
+        _ => Err(format!("Invalid Aleph line: {line}")),
+    }
+}
+
+impl Record {
+    /// Create a single record from Aleph sequential text.
+    ///
+    /// Err if the lines do not share a system number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let text = "000000042 FMT   L BK
+    /// 000000042 LDR   L ^^^^^nam^a2200000^a^4500
+    /// 000000042 008   L 990101s1999^^^^nyu^^^^^^^^^^^000^0^eng^d
+    /// 000000042 24510 L $$aTitle /$$cAuthor.";
+    ///
+    /// let record = Record::from_aleph(text).unwrap();
+    ///
+    /// assert_eq!(record.leader(), "     nam a2200000 a 4500");
+    /// assert_eq!(&record.get_control_fields("008")[0].content()[15..18], "nyu");
+    /// assert_eq!(record.get_field_values("245", "c"), vec!["Author."]);
+    /// assert_eq!(record.to_aleph("42").unwrap(), text);
+    /// ```
+    pub fn from_aleph(text: &str) -> Result<Record, String> {
+        let mut record = Record::new();
+        let mut record_sysno: Option<String> = None;
+
+        for line in text.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (sysno, _) = split_sysno(line)?;
+
+            match record_sysno.as_deref() {
+                Some(s) if s != sysno => {
+                    return Err(format!("Aleph text contains multiple records: {sysno}"));
+                }
+                Some(_) => {}
+                None => record_sysno = Some(sysno.to_string()),
+            }
+
+            record.add_aleph_line(line, true)?;
+        }
+
+        Ok(record)
+    }
+
+    /// Add a single Aleph line to the record.
+    ///
+    /// Fields with non-numeric tags, other than LDR and FMT, are only
+    /// added when `keep_local` is true.
+    fn add_aleph_line(&mut self, line: &str, keep_local: bool) -> Result<(), String> {
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        let (_, rest) = split_sysno(line)?;
+
+        // " TTTII L "
+        let tag = rest
+            .get(1..4)
+            .ok_or_else(|| format!("Invalid Aleph line: {line}"))?;
+        let ind1 = rest.get(4..5).unwrap_or(" ");
+        let ind2 = rest.get(5..6).unwrap_or(" ");
+        let data = line.get(DATA_START..).unwrap_or("");
+
+        if tag == FORMAT_TAG {
+            return Ok(());
+        }
+
+        if tag == "LDR" {
+            return self.set_leader(data.replace(FIXED_BLANK, " "));
+        }
+
+        if tag < "010" {
+            let cf = Controlfield::new(tag, data.replace(FIXED_BLANK, " "))?;
+            self.control_fields_mut().push(cf);
+            return Ok(());
+        }
+
+        if !keep_local && !tag.chars().all(|c| c.is_ascii_digit()) {
+            return Ok(());
+        }
+
+        let mut field = Field::new(tag)?;
+        field.set_ind1(ind1)?;
+        field.set_ind2(ind2)?;
+
+        for part in data.split(SUBFIELD_DELIMITER).skip(1) {
+            let Some(code) = part.chars().next() else {
+                continue;
+            };
+
+            let sf = Subfield::new(code, &part[code.len_utf8()..])?;
+            field.subfields_mut().push(sf);
+        }
+
+        self.fields_mut().push(field);
+
+        Ok(())
+    }
+
+    /// Generate Aleph sequential text for the record using the provided
+    /// system number, which is zero-padded to 9 digits if numeric.
+    ///
+    /// The text has no trailing newline.
+    pub fn to_aleph(&self, sysno: &str) -> Result<String, String> {
+        let sysno = format_sysno(sysno)?;
+        let mut lines = Vec::new();
+
+        if let Some(code) = format_code(self) {
+            lines.push(format!("{sysno} {FORMAT_TAG}   L {code}"));
+        }
+
+        lines.push(format!(
+            "{sysno} LDR   L {}",
+            self.leader().replace(' ', "^")
+        ));
+
+        for cf in self.control_fields() {
+            lines.push(format!(
+                "{sysno} {}   L {}",
+                cf.tag(),
+                cf.content().replace(' ', "^")
+            ));
+        }
+
+        for field in self.fields() {
+            let mut line = format!("{sysno} {}{}{} L ", field.tag(), field.ind1(), field.ind2());
+
+            for sf in field.subfields() {
+                line += SUBFIELD_DELIMITER;
+                line += sf.code();
+                line += sf.content();
+            }
+
+            lines.push(line);
+        }
+
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Iterates over the records in an Aleph sequential file.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::aleph::AlephRecordIterator;
+///
+/// let text = "000000001 LDR   L ^^^^^nam^a2200000^a^4500
+/// 000000001 24510 L $$aOne
+/// 000000001 CAT   L $$aLOAD$$b00
+/// 000000002 LDR   L ^^^^^nam^a2200000^a^4500
+/// 000000002 24510 L $$aTwo
+/// ";
+///
+/// let mut iter = AlephRecordIterator::from_reader(std::io::Cursor::new(text));
+/// iter.set_keep_local_fields(false);
+///
+/// let record = iter.next().unwrap().unwrap();
+/// assert_eq!(iter.sysno(), Some("000000001"));
+/// assert_eq!(record.get_field_values("245", "a"), vec!["One"]);
+/// assert!(record.get_fields("CAT").is_empty());
+///
+/// let record = iter.next().unwrap().unwrap();
+/// assert_eq!(iter.sysno(), Some("000000002"));
+/// assert_eq!(record.get_field_values("245", "a"), vec!["Two"]);
+///
+/// assert!(iter.next().is_none());
+/// ```
+pub struct AlephRecordIterator {
+    reader: Box<dyn BufRead>,
+    line_num: usize,
+    /// First line of the next record, read while finishing the
+    /// previous record.
+    pending: Option<String>,
+    sysno: Option<String>,
+    keep_local: bool,
+}
+
+impl Iterator for AlephRecordIterator {
+    type Item = Result<Record, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record: Option<Record> = None;
+        let mut record_sysno: Option<String> = None;
+        let mut bad_line: Option<String> = None;
+
+        loop {
+            let line = match self.pending.take() {
+                Some(l) => l,
+                None => {
+                    let mut line = String::new();
+                    match self.reader.read_line(&mut line) {
+                        Ok(0) => break,
+                        Ok(_) => {}
+                        Err(e) => return Some(Err(format!("Error reading Aleph file: {e}"))),
+                    }
+                    self.line_num += 1;
+                    line
+                }
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let sysno = match split_sysno(&line) {
+                Ok((s, _)) => s.to_string(),
+                Err(e) => {
+                    bad_line.get_or_insert(format!("Line {}: {e}", self.line_num));
+                    continue;
+                }
+            };
+
+            match record_sysno.as_deref() {
+                Some(s) if s != sysno => {
+                    self.pending = Some(line);
+                    break;
+                }
+                Some(_) => {}
+                None => record_sysno = Some(sysno),
+            }
+
+            let rec = record.get_or_insert_with(Record::new);
+
+            if let Err(e) = rec.add_aleph_line(&line, self.keep_local) {
+                bad_line.get_or_insert(format!("Line {}: {e}", self.line_num));
+            }
+        }
+
+        if record_sysno.is_some() {
+            self.sysno = record_sysno;
+        }
+
+        // Report errors once the remainder of the bad record has been
+        // consumed, so the next call starts with a new record.
+        if let Some(e) = bad_line {
+            return Some(Err(format!("Invalid Aleph record: {e}")));
+        }
+
+        record.map(Ok)
+    }
+}
+
+impl AlephRecordIterator {
+    /// Create a new iterator from an Aleph sequential file.
+    pub fn from_file(filename: &str) -> Result<Self, String> {
+        match File::open(filename) {
+            Ok(file) => Ok(AlephRecordIterator::from_reader(file)),
+            Err(e) => Err(format!("Cannot read Aleph file: {filename} {e}")),
+        }
+    }
+
+    /// Create a new iterator from an Aleph sequential source.
+    pub fn from_reader(reader: impl std::io::Read + 'static) -> Self {
+        AlephRecordIterator {
+            reader: Box::new(BufReader::new(reader)),
+            line_num: 0,
+            pending: None,
+            sysno: None,
+            keep_local: true,
+        }
+    }
+
+    /// Whether to keep Aleph fields with non-numeric tags, e.g. CAT and
+    /// OWN.  Defaults to true.
+    pub fn set_keep_local_fields(&mut self, keep: bool) {
+        self.keep_local = keep;
+    }
+
+    /// System number of the most recently read record.
+    pub fn sysno(&self) -> Option<&str> {
+        self.sysno.as_deref()
+    }
+}
+
+/// Writes records as Aleph sequential text, one at a time.
+///
+/// Records are numbered sequentially starting at 1 unless written with
+/// [`AlephWriter::write_record_with_sysno`].
+///
+/// # Examples
+///
+/// ```
+/// use marctk::Record;
+/// use marctk::aleph::{AlephRecordIterator, AlephWriter};
+///
+/// let mut writer = AlephWriter::new(Vec::new());
+///
+/// for title in ["One", "Two"] {
+///     let record = Record::from_breaker(&format!("=245 10$a{title}")).unwrap();
+///     writer.write_record(&record).unwrap();
+/// }
+///
+/// let text = writer.into_inner();
+///
+/// let titles: Vec<String> = AlephRecordIterator::from_reader(std::io::Cursor::new(text))
+///     .map(|r| r.unwrap().get_field_values("245", "a")[0].to_string())
+///     .collect();
+///
+/// assert_eq!(titles, vec!["One", "Two"]);
+/// ```
+pub struct AlephWriter<W: Write> {
+    writer: W,
+    written: usize,
+}
+
+impl<W: Write> AlephWriter<W> {
+    pub fn new(writer: W) -> Self {
+        AlephWriter { writer, written: 0 }
+    }
+
+    /// Write a record numbered after the previously written record.
+    pub fn write_record(&mut self, record: &Record) -> Result<(), String> {
+        let sysno = (self.written + 1).to_string();
+        self.write_record_with_sysno(record, &sysno)
+    }
+
+    pub fn write_record_with_sysno(&mut self, record: &Record, sysno: &str) -> Result<(), String> {
+        let text = record.to_aleph(sysno)?;
+
+        writeln!(self.writer, "{text}").map_err(|e| format!("Error writing Aleph text: {e}"))?;
+
+        self.written += 1;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.writer
+            .flush()
+            .map_err(|e| format!("Error writing Aleph text: {e}"))
+    }
+
+    /// Number of records written.
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
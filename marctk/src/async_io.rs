@@ -0,0 +1,368 @@
+//! Async binary and XML record readers and writers.
+//!
+//! Available with the "async" feature.  Readers accept any tokio
+//! [`AsyncRead`], e.g. a `tokio::fs::File` or a socket, and writers
+//! any [`AsyncWrite`], so records may be streamed from async services
+//! without a blocking thread per stream.
+//!
+//! Parsing and generating individual records is CPU-bound and uses the
+//! same routines as the synchronous API.  Only I/O is asynchronous.
+use super::binary::END_OF_RECORD;
+use super::xml::XmlOptions;
+use super::Record;
+use super::MARCXML_NAMESPACE;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Number of bytes requested per read from an XML source.
+const XML_READ_SIZE: usize = 8192;
+
+/// Reads binary MARC records from an async source.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::Record;
+/// use marctk::async_io::AsyncBinaryReader;
+///
+/// let mut bytes = Vec::new();
+/// for title in ["One", "Two"] {
+///     let record = Record::from_breaker(&format!("=245 10$a{title}")).unwrap();
+///     bytes.append(&mut record.to_binary().unwrap());
+/// }
+///
+/// let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+///
+/// rt.block_on(async {
+///     let mut reader = AsyncBinaryReader::new(bytes.as_slice());
+///     let mut titles = Vec::new();
+///
+///     while let Some(record) = reader.next_record().await {
+///         titles.push(record.unwrap().get_field_values("245", "a")[0].to_string());
+///     }
+///
+///     assert_eq!(titles, vec!["One", "Two"]);
+/// });
+/// ```
+pub struct AsyncBinaryReader<R: AsyncRead + Unpin> {
+    reader: BufReader<R>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncBinaryReader<R> {
+    pub fn new(reader: R) -> Self {
+        AsyncBinaryReader {
+            reader: BufReader::new(reader),
+        }
+    }
+
+    /// Returns the next [`Record`], or None once the source is exhausted.
+    pub async fn next_record(&mut self) -> Option<Result<Record, String>> {
+        let mut bytes: Vec<u8> = Vec::new();
+
+        if let Err(e) = self.reader.read_until(END_OF_RECORD, &mut bytes).await {
+            return Some(Err(format!("Error reading binary MARC: {e}")));
+        }
+
+        if bytes.is_empty() {
+            return None;
+        }
+
+        match Record::from_binary(bytes.as_slice()) {
+            Ok(r) => Some(Ok(r)),
+            Err(e) => Some(Err(format!("Error processing bytes: {:?} {}", bytes, e))),
+        }
+    }
+}
+
+/// Reads MARC XML records from an async source.
+///
+/// Each complete `<record/>` element is parsed as soon as it has been
+/// read, so memory use does not grow with the size of the document.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::async_io::AsyncXmlReader;
+///
+/// let xml = r#"<?xml version="1.0"?>
+/// <marc:collection xmlns:marc="http://www.loc.gov/MARC21/slim">
+///   <marc:record><marc:controlfield tag="001">1</marc:controlfield></marc:record>
+///   <marc:record><marc:controlfield tag="001">2</marc:controlfield></marc:record>
+/// </marc:collection>"#;
+///
+/// let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+///
+/// rt.block_on(async {
+///     let mut reader = AsyncXmlReader::new(xml.as_bytes());
+///     let mut ids = Vec::new();
+///
+///     while let Some(record) = reader.next_record().await {
+///         let record = record.unwrap();
+///         ids.push(record.get_control_fields("001")[0].content().to_string());
+///     }
+///
+///     assert_eq!(ids, vec!["1", "2"]);
+/// });
+/// ```
+pub struct AsyncXmlReader<R: AsyncRead + Unpin> {
+    reader: R,
+    buffer: Vec<u8>,
+    eof: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncXmlReader<R> {
+    pub fn new(reader: R) -> Self {
+        AsyncXmlReader {
+            reader,
+            buffer: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// Returns the next [`Record`], or None once the source is exhausted.
+    pub async fn next_record(&mut self) -> Option<Result<Record, String>> {
+        loop {
+            if let Some((start, end, prefix)) = find_record(&self.buffer) {
+                let result = parse_record(&self.buffer[start..end], &prefix);
+                self.buffer.drain(..end);
+                return Some(result);
+            }
+
+            if self.eof {
+                return None;
+            }
+
+            let mut chunk = vec![0; XML_READ_SIZE];
+
+            match self.reader.read(&mut chunk).await {
+                Ok(0) => self.eof = true,
+                Ok(count) => self.buffer.extend_from_slice(&chunk[..count]),
+                Err(e) => {
+                    self.eof = true;
+                    return Some(Err(format!("Error reading MARC XML: {e}")));
+                }
+            }
+        }
+    }
+}
+
+/// Locate the first complete record element in the buffer.
+///
+/// Returns the start and end offsets of the element and its namespace
+/// prefix, if any.
+fn find_record(buffer: &[u8]) -> Option<(usize, usize, String)> {
+    let mut offset = 0;
+
+    while let Some(pos) = find_bytes(&buffer[offset..], b"</") {
+        let tag_start = offset + pos + 2;
+        let tag_end = tag_start + buffer[tag_start..].iter().position(|b| *b == b'>')?;
+
+        let qname = std::str::from_utf8(&buffer[tag_start..tag_end])
+            .ok()?
+            .trim();
+
+        let (prefix, local_name) = match qname.split_once(':') {
+            Some((p, l)) => (p, l),
+            None => ("", qname),
+        };
+
+        if local_name == "record" {
+            let open = format!("<{qname}");
+            let mut search_end = offset + pos;
+
+            // Find the nearest start tag, skipping e.g. "<recordX".
+            while let Some(start) = rfind_bytes(&buffer[..search_end], open.as_bytes()) {
+                let next = buffer.get(start + open.len()).copied().unwrap_or(b'>');
+                if next == b'>' || next == b'/' || next.is_ascii_whitespace() {
+                    return Some((start, tag_end + 1, prefix.to_string()));
+                }
+                search_end = start;
+            }
+        }
+
+        offset = tag_end;
+    }
+
+    None
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn rfind_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|w| w == needle)
+}
+
+/// Parse a single record element, declaring its namespace prefix in
+/// case the declaration lives on an enclosing element.
+fn parse_record(bytes: &[u8], prefix: &str) -> Result<Record, String> {
+    let xml = std::str::from_utf8(bytes).map_err(|e| format!("Invalid UTF-8 in MARC XML: {e}"))?;
+
+    let xml = if prefix.is_empty() {
+        format!("<collection>{xml}</collection>")
+    } else {
+        format!(r#"<collection xmlns:{prefix}="{MARCXML_NAMESPACE}">{xml}</collection>"#)
+    };
+
+    Record::from_xml(&xml)
+        .next()
+        .unwrap_or_else(|| Err("MARC XML record element contains no record".to_string()))
+}
+
+/// Writes binary MARC records to an async destination.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::Record;
+/// use marctk::async_io::AsyncBinaryWriter;
+///
+/// let record = Record::from_breaker("=245 10$aOne").unwrap();
+///
+/// let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+///
+/// let bytes = rt.block_on(async {
+///     let mut writer = AsyncBinaryWriter::new(Vec::new());
+///     writer.write_record(&record).await.unwrap();
+///     writer.into_inner()
+/// });
+///
+/// assert_eq!(bytes, record.to_binary().unwrap());
+/// ```
+pub struct AsyncBinaryWriter<W: AsyncWrite + Unpin> {
+    writer: W,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncBinaryWriter<W> {
+    pub fn new(writer: W) -> Self {
+        AsyncBinaryWriter { writer }
+    }
+
+    pub async fn write_record(&mut self, record: &Record) -> Result<(), String> {
+        let bytes = record.to_binary()?;
+
+        self.writer
+            .write_all(&bytes)
+            .await
+            .map_err(|e| format!("Error writing binary MARC: {e}"))
+    }
+
+    pub async fn flush(&mut self) -> Result<(), String> {
+        self.writer
+            .flush()
+            .await
+            .map_err(|e| format!("Error writing binary MARC: {e}"))
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Writes MARC XML records, wrapped in a `<collection/>` element, to
+/// an async destination.
+///
+/// Call [`AsyncXmlWriter::finish`] after the last record to close the
+/// collection.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::Record;
+/// use marctk::async_io::{AsyncXmlReader, AsyncXmlWriter};
+///
+/// let record = Record::from_breaker(
+///     "=LDR 00000nam a2200000 a 4500\n=001 123\n=245 10$aOne"
+/// ).unwrap();
+///
+/// let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+///
+/// rt.block_on(async {
+///     let mut writer = AsyncXmlWriter::new(Vec::new());
+///     writer.write_record(&record).await.unwrap();
+///     writer.write_record(&record).await.unwrap();
+///     writer.finish().await.unwrap();
+///
+///     let xml = writer.into_inner();
+///     assert!(xml.starts_with(b"<?xml"));
+///
+///     let mut reader = AsyncXmlReader::new(xml.as_slice());
+///     let mut count = 0;
+///     while let Some(r) = reader.next_record().await {
+///         assert_eq!(r.unwrap().to_breaker(), record.to_breaker());
+///         count += 1;
+///     }
+///     assert_eq!(count, 2);
+/// });
+/// ```
+pub struct AsyncXmlWriter<W: AsyncWrite + Unpin> {
+    writer: W,
+    options: XmlOptions,
+    started: bool,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncXmlWriter<W> {
+    pub fn new(writer: W) -> Self {
+        AsyncXmlWriter::with_options(writer, XmlOptions::default())
+    }
+
+    /// Create a writer which generates each record using the provided
+    /// options.  The XML declaration and collection options are
+    /// ignored, since the writer adds its own.
+    pub fn with_options(writer: W, mut options: XmlOptions) -> Self {
+        // The collection carries the XML declaration.
+        options.with_xml_declaration = false;
+        options.collection = false;
+
+        AsyncXmlWriter {
+            writer,
+            options,
+            started: false,
+        }
+    }
+
+    pub async fn write_record(&mut self, record: &Record) -> Result<(), String> {
+        self.start().await?;
+
+        let xml = record.to_xml_string_ops(&self.options);
+        self.write(xml.as_bytes()).await
+    }
+
+    /// Close the collection and flush the destination.
+    pub async fn finish(&mut self) -> Result<(), String> {
+        self.start().await?;
+        let tail = format!("{}\n", self.options.collection_end());
+        self.write(tail.as_bytes()).await?;
+
+        self.writer
+            .flush()
+            .await
+            .map_err(|e| format!("Error writing MARC XML: {e}"))
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    async fn start(&mut self) -> Result<(), String> {
+        if self.started {
+            return Ok(());
+        }
+
+        self.started = true;
+
+        let head = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}",
+            self.options.collection_start()
+        );
+
+        self.write(head.as_bytes()).await
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.writer
+            .write_all(bytes)
+            .await
+            .map_err(|e| format!("Error writing MARC XML: {e}"))
+    }
+}
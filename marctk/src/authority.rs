@@ -0,0 +1,254 @@
+//! MARC21 authority record accessors.
+//!
+//! Authority records describe an established (authorized) heading in
+//! a 1XX field, variant forms of the heading in 4XX "see from"
+//! tracings, and related headings in 5XX "see also from" tracings.
+//!
+//! # References
+//!
+//! * <https://www.loc.gov/marc/authority/>
+use super::display::clean_value;
+use super::leader::TypeOfRecord;
+use super::Field;
+use super::Record;
+use std::fmt;
+
+/// Subdivision subfield codes, displayed with a " -- " separator.
+const SUBDIVISION_SUBFIELDS: &str = "vxyz";
+
+/// Subfield codes which do not contribute to the heading text, e.g.
+/// $w control subfield and $i relationship information.
+const NON_HEADING_SUBFIELDS: &str = "iw0123456789";
+
+/// Kind of heading, based on the last two digits of the tag.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HeadingType {
+    PersonalName,
+    CorporateName,
+    MeetingName,
+    UniformTitle,
+    NamedEvent,
+    ChronologicalTerm,
+    TopicalTerm,
+    GeographicName,
+    GenreFormTerm,
+    MediumOfPerformance,
+    GeneralSubdivision,
+    GeographicSubdivision,
+    ChronologicalSubdivision,
+    FormSubdivision,
+}
+
+impl HeadingType {
+    /// Heading type for a 1XX, 4XX, or 5XX authority tag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::authority::HeadingType;
+    ///
+    /// assert_eq!(HeadingType::from_tag("100"), Some(HeadingType::PersonalName));
+    /// assert_eq!(HeadingType::from_tag("551"), Some(HeadingType::GeographicName));
+    /// assert_eq!(HeadingType::from_tag("245"), None);
+    /// ```
+    pub fn from_tag(tag: &str) -> Option<HeadingType> {
+        if !tag.starts_with(['1', '4', '5']) {
+            return None;
+        }
+
+        let heading_type = match tag.get(1..)? {
+            "00" => HeadingType::PersonalName,
+            "10" => HeadingType::CorporateName,
+            "11" => HeadingType::MeetingName,
+            "30" => HeadingType::UniformTitle,
+            "47" => HeadingType::NamedEvent,
+            "48" => HeadingType::ChronologicalTerm,
+            "50" => HeadingType::TopicalTerm,
+            "51" => HeadingType::GeographicName,
+            "55" => HeadingType::GenreFormTerm,
+            "62" => HeadingType::MediumOfPerformance,
+            "80" => HeadingType::GeneralSubdivision,
+            "81" => HeadingType::GeographicSubdivision,
+            "82" => HeadingType::ChronologicalSubdivision,
+            "85" => HeadingType::FormSubdivision,
+            _ => return None,
+        };
+
+        Some(heading_type)
+    }
+
+    /// True for personal, corporate, and meeting names.
+    pub fn is_name(&self) -> bool {
+        matches!(
+            self,
+            HeadingType::PersonalName | HeadingType::CorporateName | HeadingType::MeetingName
+        )
+    }
+}
+
+/// A heading field from an authority record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading<'a> {
+    field: &'a Field,
+    heading_type: HeadingType,
+}
+
+impl<'a> Heading<'a> {
+    /// Returns None if the field is not an authority heading field.
+    pub fn from_field(field: &'a Field) -> Option<Heading<'a>> {
+        HeadingType::from_tag(field.tag()).map(|heading_type| Heading {
+            field,
+            heading_type,
+        })
+    }
+
+    pub fn field(&self) -> &'a Field {
+        self.field
+    }
+
+    pub fn heading_type(&self) -> HeadingType {
+        self.heading_type
+    }
+
+    /// Value of the $w control subfield, if present.
+    ///
+    /// Position 0 describes the relationship of a 5XX heading to the
+    /// established heading, e.g. "g" for broader term.
+    pub fn control_subfield(&self) -> Option<&'a str> {
+        self.field.first_subfield("w").map(|sf| sf.content())
+    }
+
+    /// Relationship designation from $i, if present.
+    pub fn relationship(&self) -> Option<String> {
+        self.field
+            .first_subfield("i")
+            .map(|sf| clean_value(sf.content()))
+            .filter(|r| !r.is_empty())
+    }
+
+    /// Heading text with subdivisions separated by " -- ".
+    pub fn text(&self) -> String {
+        // Subfields between subdivisions retain their internal
+        // punctuation, e.g. "Twain, Mark, 1835-1910"
+        let mut segments: Vec<String> = Vec::new();
+        let mut current = String::new();
+
+        for sf in self.field.subfields() {
+            if NON_HEADING_SUBFIELDS.contains(sf.code()) {
+                continue;
+            }
+
+            if SUBDIVISION_SUBFIELDS.contains(sf.code()) {
+                segments.push(std::mem::take(&mut current));
+            }
+
+            current += " ";
+            current += sf.content();
+        }
+
+        segments.push(current);
+
+        segments
+            .iter()
+            .map(|s| clean_value(s))
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<String>>()
+            .join(" -- ")
+    }
+}
+
+impl fmt::Display for Heading<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+impl Record {
+    /// True if leader/06 identifies this as an authority record.
+    pub fn is_authority(&self) -> bool {
+        self.typed_leader().type_of_record() == Some(TypeOfRecord::Authority)
+    }
+
+    /// Library of Congress control number from 010 $a.
+    pub fn lccn(&self) -> Option<&str> {
+        self.get_field_values("010", "a")
+            .into_iter()
+            .map(|v| v.trim())
+            .find(|v| !v.is_empty())
+    }
+
+    /// The established (1XX) heading.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::authority::HeadingType;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=LDR 00000nz  a2200000n  4500
+    /// =001 n79021164
+    /// =010 \\$an  79021164
+    /// =100 1\$aTwain, Mark,$d1835-1910.
+    /// =400 1\$aClemens, Samuel Langhorne,$d1835-1910
+    /// =400 1\$aSnodgrass, Quintus Curtius,$d1835-1910
+    /// =500 1\$wnnnc$aConte, Louis de,$d1835-1910"#
+    /// ).unwrap();
+    ///
+    /// assert!(record.is_authority());
+    /// assert_eq!(record.control_number(), Some("n79021164"));
+    /// assert_eq!(record.lccn(), Some("n  79021164"));
+    ///
+    /// let heading = record.established_heading().unwrap();
+    /// assert_eq!(heading.heading_type(), HeadingType::PersonalName);
+    /// assert_eq!(heading.text(), "Twain, Mark, 1835-1910");
+    /// assert_eq!(record.heading_type(), Some(HeadingType::PersonalName));
+    ///
+    /// let see_from: Vec<String> = record
+    ///     .see_from_headings()
+    ///     .iter()
+    ///     .map(|h| h.to_string())
+    ///     .collect();
+    ///
+    /// assert_eq!(
+    ///     see_from,
+    ///     vec![
+    ///         "Clemens, Samuel Langhorne, 1835-1910",
+    ///         "Snodgrass, Quintus Curtius, 1835-1910",
+    ///     ]
+    /// );
+    ///
+    /// let see_also = record.see_also_headings();
+    /// assert_eq!(see_also[0].text(), "Conte, Louis de, 1835-1910");
+    /// assert_eq!(see_also[0].control_subfield(), Some("nnnc"));
+    /// ```
+    pub fn established_heading(&self) -> Option<Heading<'_>> {
+        self.fields()
+            .iter()
+            .filter(|f| f.tag().starts_with('1'))
+            .find_map(Heading::from_field)
+    }
+
+    /// Type of the established heading.
+    pub fn heading_type(&self) -> Option<HeadingType> {
+        self.established_heading().map(|h| h.heading_type())
+    }
+
+    /// Variant forms of the heading from 4XX tracings.
+    pub fn see_from_headings(&self) -> Vec<Heading<'_>> {
+        self.tracing_headings('4')
+    }
+
+    /// Related headings from 5XX tracings.
+    pub fn see_also_headings(&self) -> Vec<Heading<'_>> {
+        self.tracing_headings('5')
+    }
+
+    fn tracing_headings(&self, prefix: char) -> Vec<Heading<'_>> {
+        self.fields()
+            .iter()
+            .filter(|f| f.tag().starts_with(prefix))
+            .filter_map(Heading::from_field)
+            .collect()
+    }
+}
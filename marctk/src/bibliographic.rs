@@ -0,0 +1,576 @@
+//! MARC21 bibliographic record accessors.
+//!
+//! Structured access to descriptive fields commonly needed for indexing
+//! and display: publication statements (260/264), physical description
+//! (300), series (490/8XX), uniform titles (130/240), general notes
+//! (500), and publication dates.  Values are cleaned with [`clean_value`].
+//!
+//! # References
+//!
+//! * <https://www.loc.gov/marc/bibliographic/>
+use super::display::{clean_value, join_subfields};
+use super::fixed_fields::Fixed008;
+use super::Field;
+use super::Record;
+use std::fmt;
+
+/// Uniform title subfields which contribute to the title text.
+const UNIFORM_TITLE_SUBFIELDS: &str = "adfgklmnoprs";
+
+/// Series added entry subfields which contribute to the series title.
+/// Name subfields are included so 800-811 titles remain meaningful.
+const SERIES_TITLE_SUBFIELDS: &str = "abcdefgklmnopqrst";
+
+/// Function of a publication statement, from the second indicator of
+/// a 264 field.  260 fields are always [`PublicationFunction::Publication`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PublicationFunction {
+    Production,
+    Publication,
+    Distribution,
+    Manufacture,
+    Copyright,
+}
+
+impl PublicationFunction {
+    /// Function for a 260 or 264 field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Field;
+    /// use marctk::bibliographic::PublicationFunction;
+    ///
+    /// let mut field = Field::new("264").unwrap();
+    /// field.set_ind2("4").unwrap();
+    ///
+    /// assert_eq!(PublicationFunction::from_field(&field), Some(PublicationFunction::Copyright));
+    /// ```
+    pub fn from_field(field: &Field) -> Option<PublicationFunction> {
+        match field.tag() {
+            "260" => Some(PublicationFunction::Publication),
+            "264" => match field.ind2() {
+                "0" => Some(PublicationFunction::Production),
+                "1" => Some(PublicationFunction::Publication),
+                "2" => Some(PublicationFunction::Distribution),
+                "3" => Some(PublicationFunction::Manufacture),
+                "4" => Some(PublicationFunction::Copyright),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// A publication, distribution, etc. statement from a 260 or 264 field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Imprint {
+    tag: String,
+    function: PublicationFunction,
+    places: Vec<String>,
+    names: Vec<String>,
+    date: Option<String>,
+}
+
+impl Imprint {
+    /// Returns None if the field is not a 260 or a 264 with a valid
+    /// second indicator.
+    pub fn from_field(field: &Field) -> Option<Imprint> {
+        let function = PublicationFunction::from_field(field)?;
+
+        let values = |code| {
+            field
+                .get_subfields(code)
+                .iter()
+                .map(|sf| clean_value(sf.content()))
+                .filter(|v| !v.is_empty())
+                .collect::<Vec<String>>()
+        };
+
+        Some(Imprint {
+            tag: field.tag().to_string(),
+            function,
+            places: values("a"),
+            names: values("b"),
+            date: values("c").into_iter().next(),
+        })
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn function(&self) -> PublicationFunction {
+        self.function
+    }
+
+    /// Places of publication, production, etc. from $a.
+    pub fn places(&self) -> &Vec<String> {
+        &self.places
+    }
+
+    /// Names of publishers, producers, etc. from $b.
+    pub fn names(&self) -> &Vec<String> {
+        &self.names
+    }
+
+    /// Date of publication, production, etc. from $c.
+    pub fn date(&self) -> Option<&str> {
+        self.date.as_deref()
+    }
+}
+
+/// Renders the statement as "Place : Name, Date".
+impl fmt::Display for Imprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut text = self.places.join(" ; ");
+
+        if !self.names.is_empty() {
+            if !text.is_empty() {
+                text += " : ";
+            }
+            text += &self.names.join(" : ");
+        }
+
+        if let Some(date) = self.date() {
+            if !text.is_empty() {
+                text += ", ";
+            }
+            text += date;
+        }
+
+        write!(f, "{text}")
+    }
+}
+
+/// Physical description from a 300 field.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PhysicalDescription {
+    pub extent: Option<String>,
+    pub other_details: Option<String>,
+    pub dimensions: Option<String>,
+    pub accompanying_material: Option<String>,
+}
+
+impl PhysicalDescription {
+    /// Returns None if the field is not a 300 field.
+    pub fn from_field(field: &Field) -> Option<PhysicalDescription> {
+        if field.tag() != "300" {
+            return None;
+        }
+
+        // "+" precedes accompanying material in $e.
+        let value = |codes| {
+            join_subfields(field, codes).map(|v| v.trim_end_matches([' ', '+']).to_string())
+        };
+
+        Some(PhysicalDescription {
+            extent: value("af"),
+            other_details: value("b"),
+            dimensions: value("c"),
+            accompanying_material: value("e"),
+        })
+    }
+}
+
+/// A series statement (490) or series added entry (800/810/811/830).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    tag: String,
+    title: String,
+    volume: Option<String>,
+    issn: Option<String>,
+}
+
+impl Series {
+    /// Returns None if the field is not a series field or has no title.
+    pub fn from_field(field: &Field) -> Option<Series> {
+        let title = match field.tag() {
+            "490" => join_subfields(field, "a")?,
+            "800" | "810" | "811" | "830" => join_subfields(field, SERIES_TITLE_SUBFIELDS)?,
+            _ => return None,
+        };
+
+        Some(Series {
+            tag: field.tag().to_string(),
+            title,
+            volume: join_subfields(field, "v"),
+            issn: join_subfields(field, "x"),
+        })
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Volume or sequential designation from $v.
+    pub fn volume(&self) -> Option<&str> {
+        self.volume.as_deref()
+    }
+
+    /// ISSN from $x.
+    pub fn issn(&self) -> Option<&str> {
+        self.issn.as_deref()
+    }
+
+    /// True for 8XX series added entries, i.e. controlled headings.
+    pub fn is_added_entry(&self) -> bool {
+        self.tag.starts_with('8')
+    }
+}
+
+/// Renders the series as "Title ; volume".
+impl fmt::Display for Series {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.volume() {
+            Some(v) => write!(f, "{} ; {v}", self.title),
+            None => write!(f, "{}", self.title),
+        }
+    }
+}
+
+/// Publication date of a resource.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PubDate {
+    Year(u16),
+    /// Inclusive range of years, e.g. the dates of a multipart item or
+    /// the decade of "[199-?]".
+    Range(u16, u16),
+    Unknown,
+}
+
+impl PubDate {
+    /// Extract a date from a free-text date, e.g. 260 $c.
+    ///
+    /// The first year found wins.  Brackets, question marks, and
+    /// copyright symbols are ignored.  Partial years like "199-" and
+    /// "19uu" become ranges covering the decade or century.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::bibliographic::PubDate;
+    ///
+    /// assert_eq!(PubDate::parse("2017."), PubDate::Year(2017));
+    /// assert_eq!(PubDate::parse("[2017?]"), PubDate::Year(2017));
+    /// assert_eq!(PubDate::parse("©2016"), PubDate::Year(2016));
+    /// assert_eq!(PubDate::parse("c1998, p1997"), PubDate::Year(1998));
+    /// assert_eq!(PubDate::parse("1999-2003."), PubDate::Range(1999, 2003));
+    /// assert_eq!(PubDate::parse("[199-?]"), PubDate::Range(1990, 1999));
+    /// assert_eq!(PubDate::parse("[19--]"), PubDate::Range(1900, 1999));
+    /// assert_eq!(PubDate::parse("19uu"), PubDate::Range(1900, 1999));
+    /// assert_eq!(PubDate::parse("[n.d.]"), PubDate::Unknown);
+    /// assert_eq!(PubDate::parse("uuuu"), PubDate::Unknown);
+    /// ```
+    pub fn parse(value: &str) -> PubDate {
+        let chars: Vec<char> = value.chars().collect();
+        let is_unknown_digit = |c: Option<&char>| matches!(c, Some('-' | '?' | 'u' | 'x'));
+
+        let mut idx = 0;
+        while idx < chars.len() {
+            if !chars[idx].is_ascii_digit() {
+                idx += 1;
+                continue;
+            }
+
+            let start = idx;
+            while idx < chars.len() && chars[idx].is_ascii_digit() {
+                idx += 1;
+            }
+
+            let digits: String = chars[start..idx].iter().collect();
+            // Digit runs are at most 4 digits here, so this fits.
+            let num = || digits.parse::<u16>().unwrap_or(0);
+
+            match digits.len() {
+                4 => {
+                    let year = num();
+                    return match Self::range_end(&chars[idx..]) {
+                        Some(end) if end > year => PubDate::Range(year, end),
+                        _ => PubDate::Year(year),
+                    };
+                }
+                3 if is_unknown_digit(chars.get(idx)) => {
+                    return PubDate::Range(num() * 10, num() * 10 + 9);
+                }
+                2 if is_unknown_digit(chars.get(idx)) && is_unknown_digit(chars.get(idx + 1)) => {
+                    return PubDate::Range(num() * 100, num() * 100 + 99);
+                }
+                _ => {}
+            }
+        }
+
+        PubDate::Unknown
+    }
+
+    /// Year following a "-" at the start of the remaining text.
+    fn range_end(rest: &[char]) -> Option<u16> {
+        let rest: String = rest.iter().collect();
+        let rest = rest.trim_start().strip_prefix('-')?.trim_start();
+
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+
+        if digits.len() == 4 {
+            digits.parse().ok()
+        } else {
+            None
+        }
+    }
+
+    /// Extract a date from the 008 date type and dates.
+    ///
+    /// Date 2 extends the date into a range for multiple, questionable,
+    /// and inclusive dates, unless it's open-ended (9999).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::bibliographic::PubDate;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=008 990101m19992003nyu           000 0 eng d"#
+    /// ).unwrap();
+    ///
+    /// let fixed = record.fixed_008().unwrap();
+    /// assert_eq!(PubDate::from_fixed_008(&fixed), PubDate::Range(1999, 2003));
+    /// ```
+    pub fn from_fixed_008(fixed: &Fixed008) -> PubDate {
+        if matches!(fixed.date_type, 'b' | 'n' | ' ' | '|') {
+            return PubDate::Unknown;
+        }
+
+        let date = PubDate::parse(&fixed.date1);
+
+        let first = match date {
+            PubDate::Year(y) => y,
+            PubDate::Range(y, _) => y,
+            PubDate::Unknown => return date,
+        };
+
+        if !matches!(fixed.date_type, 'c' | 'd' | 'i' | 'k' | 'm' | 'q' | 'u') {
+            return date;
+        }
+
+        match PubDate::parse(&fixed.date2) {
+            PubDate::Year(9999) => date,
+            PubDate::Year(end) | PubDate::Range(_, end) if end > first => {
+                PubDate::Range(first, end)
+            }
+            _ => date,
+        }
+    }
+
+    /// The first, or only, year.
+    pub fn year(&self) -> Option<u16> {
+        match self {
+            PubDate::Year(y) => Some(*y),
+            PubDate::Range(y, _) => Some(*y),
+            PubDate::Unknown => None,
+        }
+    }
+}
+
+/// Renders the date as "1999", "1999-2003", or an empty string.
+impl fmt::Display for PubDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PubDate::Year(y) => write!(f, "{y}"),
+            PubDate::Range(start, end) => write!(f, "{start}-{end}"),
+            PubDate::Unknown => Ok(()),
+        }
+    }
+}
+
+impl Record {
+    /// Publication, distribution, etc. statements from 260 and 264
+    /// fields, in record order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::bibliographic::PublicationFunction;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=264 \1$aMiami, FL :$bAguilar :$bPenguin Random House,$c2017.
+    /// =264 \4$c©2016"#
+    /// ).unwrap();
+    ///
+    /// let imprints = record.imprints();
+    /// assert_eq!(imprints.len(), 2);
+    /// assert_eq!(imprints[0].names(), &vec!["Aguilar", "Penguin Random House"]);
+    /// assert_eq!(imprints[1].function(), PublicationFunction::Copyright);
+    ///
+    /// assert_eq!(
+    ///     record.publication().unwrap().to_string(),
+    ///     "Miami, FL : Aguilar : Penguin Random House, 2017"
+    /// );
+    /// assert_eq!(record.copyright_date(), Some("©2016".to_string()));
+    /// ```
+    pub fn imprints(&self) -> Vec<Imprint> {
+        self.extract_fields("260:264")
+            .filter_map(Imprint::from_field)
+            .collect()
+    }
+
+    /// The publication statement, preferring an RDA 264 with second
+    /// indicator 1 over a 260.
+    pub fn publication(&self) -> Option<Imprint> {
+        let imprints = self.imprints();
+
+        imprints
+            .iter()
+            .find(|i| i.function == PublicationFunction::Publication && i.tag == "264")
+            .or_else(|| {
+                imprints
+                    .iter()
+                    .find(|i| i.function == PublicationFunction::Publication)
+            })
+            .cloned()
+    }
+
+    /// Copyright date from a 264 with second indicator 4.
+    pub fn copyright_date(&self) -> Option<String> {
+        self.imprints()
+            .into_iter()
+            .filter(|i| i.function == PublicationFunction::Copyright)
+            .find_map(|i| i.date)
+    }
+
+    /// Physical descriptions from 300 fields.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=300 \\$a333 pages :$bcolor illustrations ;$c23 cm +$e1 CD-ROM"#
+    /// ).unwrap();
+    ///
+    /// let desc = &record.physical_descriptions()[0];
+    ///
+    /// assert_eq!(desc.extent.as_deref(), Some("333 pages"));
+    /// assert_eq!(desc.other_details.as_deref(), Some("color illustrations"));
+    /// assert_eq!(desc.dimensions.as_deref(), Some("23 cm"));
+    /// assert_eq!(desc.accompanying_material.as_deref(), Some("1 CD-ROM"));
+    /// ```
+    pub fn physical_descriptions(&self) -> Vec<PhysicalDescription> {
+        self.get_fields("300")
+            .into_iter()
+            .filter_map(PhysicalDescription::from_field)
+            .collect()
+    }
+
+    /// Series statements and series added entries, in record order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=490 1\$aHarry Potter ;$vyear 1
+    /// =800 1\$aRowling, J. K.$tHarry Potter ;$vyear 1.
+    /// =830 \0$aMagic books.$x1234-5678"#
+    /// ).unwrap();
+    ///
+    /// let series = record.series();
+    ///
+    /// assert_eq!(series[0].to_string(), "Harry Potter ; year 1");
+    /// assert!(!series[0].is_added_entry());
+    /// assert_eq!(series[1].title(), "Rowling, J. K. Harry Potter");
+    /// assert_eq!(series[2].issn(), Some("1234-5678"));
+    /// ```
+    pub fn series(&self) -> Vec<Series> {
+        self.extract_fields("490:800:810:811:830")
+            .filter_map(Series::from_field)
+            .collect()
+    }
+
+    /// Uniform title from the 130 or 240 field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=100 1\$aTwain, Mark,$d1835-1910.
+    /// =240 10$aAdventures of Huckleberry Finn.$lSpanish"#
+    /// ).unwrap();
+    ///
+    /// assert_eq!(record.uniform_title().as_deref(), Some("Adventures of Huckleberry Finn. Spanish"));
+    /// ```
+    pub fn uniform_title(&self) -> Option<String> {
+        self.extract_fields("130:240")
+            .find_map(|f| join_subfields(f, UNIFORM_TITLE_SUBFIELDS))
+    }
+
+    /// General notes from 500 $a.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     "=500 \\\\$aIncludes index.\n=500 \\\\$aTranslation of: Despierta."
+    /// ).unwrap();
+    ///
+    /// assert_eq!(record.general_notes(), vec!["Includes index", "Translation of: Despierta"]);
+    /// ```
+    pub fn general_notes(&self) -> Vec<String> {
+        self.get_field_values("500", "a")
+            .into_iter()
+            .map(clean_value)
+            .filter(|n| !n.is_empty())
+            .collect()
+    }
+
+    /// Publication date from 008/07-14, falling back to the first
+    /// usable date in 264 $c, preferring publication statements, then
+    /// 260 $c.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::bibliographic::PubDate;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=008 990101nuuuuuuuunyu           000 0 eng d
+    /// =264 \4$c©2016
+    /// =264 \1$a[Place of publication not identified] :$bAguilar,$c[2017?]"#
+    /// ).unwrap();
+    ///
+    /// assert_eq!(record.pub_date(), PubDate::Year(2017));
+    ///
+    /// let record = Record::from_breaker("=260 \\$aNew York :$bHarper,$c[n.d.]").unwrap();
+    /// assert_eq!(record.pub_date(), PubDate::Unknown);
+    /// ```
+    pub fn pub_date(&self) -> PubDate {
+        if let Some(fixed) = self.fixed_008() {
+            let date = PubDate::from_fixed_008(&fixed);
+            if date != PubDate::Unknown {
+                return date;
+            }
+        }
+
+        let mut fields = self.get_fields("264");
+        fields.sort_by_key(|f| f.ind2() != "1");
+        fields.extend(self.get_fields("260"));
+
+        fields
+            .iter()
+            .flat_map(|f| f.get_subfields("c"))
+            .map(|sf| PubDate::parse(sf.content()))
+            .find(|d| *d != PubDate::Unknown)
+            .unwrap_or(PubDate::Unknown)
+    }
+}
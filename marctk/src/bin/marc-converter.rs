@@ -1,3 +1,4 @@
+use marc::order::FieldOrder;
 use marc::Record;
 use marc::MARCXML_NAMESPACE;
 use marctk as marc;
@@ -30,12 +31,22 @@ Options:
     --to-marc
         Produce MARC UTF8 output.
 
+    --to-marc8
+        Produce MARC output encoded as MARC-8.
+
     --to-breaker
         Produce Breaker output.
 
     --format-xml
         Format XML output with 2-space indent.
 
+    --sort-fields
+        Write fields in numeric tag order.
+
+    --keep-last <tag-pattern>
+        With --sort-fields, write fields matching the pattern, e.g. 9XX,
+        at the end of the record in their original order.  Repeatable.
+
 "#;
 
 fn main() {
@@ -44,8 +55,11 @@ fn main() {
 
     opts.optflag("", "to-xml", "");
     opts.optflag("", "to-marc", "");
+    opts.optflag("", "to-marc8", "");
     opts.optflag("", "to-breaker", "");
     opts.optflag("", "format-xml", "");
+    opts.optflag("", "sort-fields", "");
+    opts.optmulti("", "keep-last", "", "");
     opts.optflag("h", "help", "");
 
     let params = match opts.parse(&args[1..]) {
@@ -63,18 +77,44 @@ fn main() {
 
     let to_xml = params.opt_present("to-xml");
     let to_marc = params.opt_present("to-marc");
+    let to_marc8 = params.opt_present("to-marc8");
     let to_breaker = params.opt_present("to-breaker");
     let format_xml = params.opt_present("format-xml");
 
+    let field_order = params.opt_present("sort-fields").then(|| {
+        params
+            .opt_strs("keep-last")
+            .iter()
+            .fold(FieldOrder::new(), |order, p| order.keep_last(p))
+    });
+
     let xml_ops = marc::xml::XmlOptions {
         formatted: format_xml,
         // We'll add our own XML declaration.
         with_xml_declaration: false,
+        field_order: field_order.clone(),
+        ..Default::default()
     };
 
     // Prints one record using the requested output.
     let printer = move |r: &Record| {
-        if to_marc {
+        let sorted;
+        let r = match field_order.as_ref() {
+            Some(order) if !to_xml => {
+                let mut rec = r.clone();
+                rec.sort_fields_with(order);
+                sorted = rec;
+                &sorted
+            }
+            _ => r,
+        };
+
+        if to_marc8 {
+            let bytes = &r.to_binary_marc8().expect("Binary generation failed");
+            std::io::stdout()
+                .write_all(bytes)
+                .expect("Cannot write bytes");
+        } else if to_marc {
             let bytes = &r.to_binary().expect("Binary generation failed");
             std::io::stdout()
                 .write_all(bytes)
@@ -0,0 +1,589 @@
+use marc::aleph::AlephRecordIterator;
+use marc::binary::{BinaryRecordIterator, EncodingPolicy};
+use marc::breaker::BreakerRecordIterator;
+use marc::edit::EditTasks;
+use marc::validate::Validator;
+use marc::xml::XmlOptions;
+use marc::Record;
+use marctk as marc;
+use std::env;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::process;
+
+const HELP_TEXT: &str = r#"
+Convert, validate, count, split, and print MARC records.
+
+Usage:
+
+    marc-tool <command> [options] /path/to/file
+
+Synopsis:
+
+Reads MARC21 binary, MARC XML, MARC Breaker, MARC-in-JSON, or Aleph
+sequential records.
+The type of the input file is determined automatically.
+
+Breaker files may contain multiple records separated by blank lines.
+JSON files may contain a single record, an array of records, or one
+record per line.
+
+Commands:
+
+    convert
+        Write every record in the output format on STDOUT.
+
+    validate
+        Report problems with each record.  Exits with status 1 if any
+        record has errors.
+
+    count
+        Print the number of records.
+
+    split --chunk-size <count> --out-prefix <path>
+        Write records to files of at most <count> records each, named
+        <path>.0001.<ext>, <path>.0002.<ext>, etc.
+
+    print [--position <n>] [--id <value>]
+        Print the record at 1-based position <n> or the records whose
+        001 is <value>.
+
+    encoding
+        Report binary records whose content does not match the character
+        encoding declared in leader position 09.
+
+Options:
+
+    --to <format>
+        Output format: marc, marc8, xml, breaker, json, or aleph.  JSON
+        output contains one record per line.  Aleph records are numbered
+        sequentially starting at 1.  Defaults to breaker for the print
+        command and to the input format for the split command, and is
+        otherwise required.
+
+    --format-xml
+        Format XML output with 2-space indent.
+
+    --xml-prefix <prefix>
+        Write XML elements with a namespace prefix, e.g. "marc", instead
+        of using MARCXML as the default namespace.
+
+    --xml-no-schema-location
+        Omit the xsi:schemaLocation attribute from XML output.
+
+    --encoding <policy>
+        How to read binary records whose content does not match their
+        declared encoding: trust-leader, trust-content, or error.
+        Defaults to trust-content.
+
+    --edit <file>
+        Apply the batch edit tasks in <file>, one task per line, to
+        each record as it is read.  See the marctk::edit module for the
+        task syntax.
+
+    --lenient
+        Repair binary records with incorrect lengths or directory
+        entries where possible.  Repairs are reported on STDERR.  Records
+        which cannot be repaired are reported and skipped.  XML records
+        which cannot be read, including those containing invalid XML,
+        are reported and skipped.
+
+    --warnings
+        With validate, also report warnings.
+
+"#;
+
+/// Record source formats and output formats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Marc,
+    Marc8,
+    Xml,
+    Breaker,
+    Json,
+    Aleph,
+}
+
+impl Format {
+    fn parse(s: &str) -> Result<Format, String> {
+        match s {
+            "marc" => Ok(Format::Marc),
+            "marc8" => Ok(Format::Marc8),
+            "xml" => Ok(Format::Xml),
+            "breaker" => Ok(Format::Breaker),
+            "json" => Ok(Format::Json),
+            "aleph" => Ok(Format::Aleph),
+            _ => Err(format!("Invalid format: {s}")),
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Marc | Format::Marc8 => "mrc",
+            Format::Xml => "xml",
+            Format::Breaker => "mrk",
+            Format::Json => "json",
+            Format::Aleph => "seq",
+        }
+    }
+}
+
+/// Writes records in a single output format.
+struct Writer<W: Write> {
+    out: W,
+    format: Format,
+    xml_ops: XmlOptions,
+    written: usize,
+}
+
+impl<W: Write> Writer<W> {
+    fn new(out: W, format: Format, xml_ops: XmlOptions) -> Writer<W> {
+        Writer {
+            out,
+            format,
+            xml_ops,
+            written: 0,
+        }
+    }
+
+    /// Wrap XML in a <collection/> so that we produce a single valid
+    /// document when outputting multiple records.
+    fn start(&mut self) -> Result<(), String> {
+        if self.format == Format::Xml {
+            let head = format!(
+                "<?xml version=\"1.0\"?>\n{}",
+                self.xml_ops.collection_start()
+            );
+            self.write(head.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn write_record(&mut self, record: &Record) -> Result<(), String> {
+        let bytes = match self.format {
+            Format::Marc => record.to_binary()?,
+            Format::Marc8 => record.to_binary_marc8()?,
+            Format::Xml => record.to_xml_string_ops(&self.xml_ops).into_bytes(),
+            Format::Json => format!("{}\n", record.to_json_string()).into_bytes(),
+            Format::Aleph => {
+                let sysno = (self.written + 1).to_string();
+                format!("{}\n", record.to_aleph(&sysno)?).into_bytes()
+            }
+            Format::Breaker => {
+                // Blank line between records.
+                let sep = if self.written > 0 { "\n" } else { "" };
+                format!("{sep}{}\n", record.to_breaker()).into_bytes()
+            }
+        };
+
+        self.written += 1;
+        self.write(&bytes)
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        if self.format == Format::Xml {
+            let tail = format!("\n{}\n", self.xml_ops.collection_end());
+            self.write(tail.as_bytes())?;
+        }
+        self.out
+            .flush()
+            .map_err(|e| format!("Cannot write output: {e}"))
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.out
+            .write_all(bytes)
+            .map_err(|e| format!("Cannot write output: {e}"))
+    }
+}
+
+type RecordIter = Box<dyn Iterator<Item = Result<Record, String>>>;
+
+/// Reads binary records leniently, reporting repairs and unreadable
+/// records on STDERR instead of failing.
+struct LenientRecords {
+    iter: BinaryRecordIterator,
+    reported: usize,
+}
+
+impl Iterator for LenientRecords {
+    type Item = Result<Record, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let result = self.iter.next()?;
+
+            for (position, repair) in &self.iter.repairs()[self.reported..] {
+                eprintln!("record {position} repaired: {repair}");
+            }
+            self.reported = self.iter.repairs().len();
+
+            match result {
+                Ok(record) => return Some(Ok(record)),
+                Err(e) => eprintln!("record {} skipped: {e}", self.iter.position()),
+            }
+        }
+    }
+}
+
+/// Determine the format of the file from its first non-whitespace byte.
+fn detect_format(filename: &str) -> Result<Format, String> {
+    let mut file = File::open(filename).map_err(|e| format!("Cannot open file: {e}"))?;
+    let mut buf = [0u8; 512];
+
+    let count = file
+        .read(&mut buf)
+        .map_err(|e| format!("Cannot read file: {e}"))?;
+
+    // Skip the UTF-8 byte order mark MarcEdit adds to .mrk files.
+    let bytes = buf[..count]
+        .strip_prefix(&[0xEF, 0xBB, 0xBF])
+        .unwrap_or(&buf[..count]);
+
+    let bytes = match bytes.iter().position(|b| !b.is_ascii_whitespace()) {
+        Some(pos) => &bytes[pos..],
+        None => return Err("File is empty".to_string()),
+    };
+
+    // Aleph sequential lines begin with a 9-digit system number and a
+    // space.  Binary MARC has no spaces in its leading digits.
+    if bytes.len() > 9 && bytes[..9].iter().all(|b| b.is_ascii_digit()) && bytes[9] == b' ' {
+        return Ok(Format::Aleph);
+    }
+
+    match bytes.first() {
+        Some(b'<') => Ok(Format::Xml),
+        Some(b'=') => Ok(Format::Breaker),
+        Some(b'{') | Some(b'[') => Ok(Format::Json),
+        // Binary MARC begins with the record length, i.e. numbers
+        Some(b'0'..=b'9') => Ok(Format::Marc),
+        Some(_) => Err("Unable to determine file type".to_string()),
+        None => Err("File is empty".to_string()),
+    }
+}
+
+fn encoding_policy(params: &getopts::Matches) -> Result<EncodingPolicy, String> {
+    match params.opt_str("encoding") {
+        Some(p) => p.parse::<EncodingPolicy>(),
+        None => Ok(EncodingPolicy::default()),
+    }
+}
+
+fn read_records(
+    filename: &str,
+    format: Format,
+    policy: EncodingPolicy,
+    lenient: bool,
+) -> Result<RecordIter, String> {
+    match format {
+        Format::Xml => {
+            let mut iter = Record::from_xml_file(filename)?;
+
+            if lenient {
+                iter.set_recovery_handler(|raw, e| eprintln!("record skipped: {e}\n{raw}"))?;
+            }
+
+            Ok(Box::new(iter))
+        }
+        Format::Marc | Format::Marc8 => {
+            let mut iter = Record::from_binary_file(filename)?;
+            iter.set_encoding_policy(policy);
+
+            if lenient {
+                iter.set_lenient(true);
+                Ok(Box::new(LenientRecords { iter, reported: 0 }))
+            } else {
+                Ok(Box::new(iter))
+            }
+        }
+        Format::Breaker => Ok(Box::new(BreakerRecordIterator::from_file(filename)?)),
+        Format::Aleph => Ok(Box::new(AlephRecordIterator::from_file(filename)?)),
+        Format::Json => {
+            let text = std::fs::read_to_string(filename)
+                .map_err(|e| format!("Error reading JSON file: {e}"))?;
+
+            let records: Vec<Result<Record, String>> = match json::parse(&text) {
+                Ok(v) if v.is_array() => v.members().map(Record::from_json_value).collect(),
+                Ok(v) => vec![Record::from_json_value(&v)],
+                // Assume one record per line.
+                Err(_) => text
+                    .lines()
+                    .filter(|l| !l.trim().is_empty())
+                    .map(Record::from_json)
+                    .collect(),
+            };
+
+            Ok(Box::new(records.into_iter()))
+        }
+    }
+}
+
+fn output_format(params: &getopts::Matches, default: Option<Format>) -> Result<Format, String> {
+    match params.opt_str("to") {
+        Some(f) => Format::parse(&f),
+        None => default.ok_or_else(|| "--to <format> is required".to_string()),
+    }
+}
+
+/// XML output options from the command line.
+fn xml_options(params: &getopts::Matches) -> XmlOptions {
+    XmlOptions {
+        formatted: params.opt_present("format-xml"),
+        namespace_prefix: params.opt_str("xml-prefix"),
+        schema_location: !params.opt_present("xml-no-schema-location"),
+        // We'll add our own XML declaration and collection.
+        ..Default::default()
+    }
+}
+
+fn convert(params: &getopts::Matches, records: RecordIter) -> Result<(), String> {
+    let format = output_format(params, None)?;
+    let stdout = std::io::stdout().lock();
+    let mut writer = Writer::new(BufWriter::new(stdout), format, xml_options(params));
+
+    writer.start()?;
+    for record in records {
+        writer.write_record(&record?)?;
+    }
+    writer.finish()
+}
+
+fn validate(params: &getopts::Matches, records: RecordIter) -> Result<bool, String> {
+    let validator = Validator::new();
+    let warnings = params.opt_present("warnings");
+    let mut has_errors = false;
+
+    for (idx, record) in records.enumerate() {
+        let record = record?;
+
+        let id = record
+            .get_control_fields("001")
+            .first()
+            .map(|cf| cf.content().to_string())
+            .unwrap_or_default();
+
+        for issue in validator.validate(&record) {
+            if issue.is_error() {
+                has_errors = true;
+            } else if !warnings {
+                continue;
+            }
+            println!("record {} [{id}] {issue}", idx + 1);
+        }
+    }
+
+    Ok(!has_errors)
+}
+
+fn split(params: &getopts::Matches, records: RecordIter, input: Format) -> Result<(), String> {
+    let format = output_format(params, Some(input))?;
+
+    let chunk_size = params
+        .opt_str("chunk-size")
+        .ok_or("--chunk-size is required")?
+        .parse::<usize>()
+        .ok()
+        .filter(|s| *s > 0)
+        .ok_or("Invalid --chunk-size")?;
+
+    let prefix = params
+        .opt_str("out-prefix")
+        .ok_or("--out-prefix is required")?;
+    let xml_ops = xml_options(params);
+
+    let mut writer: Option<Writer<BufWriter<File>>> = None;
+    let mut chunks = 0;
+
+    for record in records {
+        let record = record?;
+
+        if writer
+            .as_ref()
+            .map(|w| w.written >= chunk_size)
+            .unwrap_or(true)
+        {
+            if let Some(mut w) = writer.take() {
+                w.finish()?;
+            }
+
+            chunks += 1;
+            let filename = format!("{prefix}.{chunks:04}.{}", format.extension());
+
+            let file = File::create(&filename)
+                .map_err(|e| format!("Cannot create file {filename}: {e}"))?;
+
+            let mut w = Writer::new(BufWriter::new(file), format, xml_ops.clone());
+            w.start()?;
+            writer = Some(w);
+        }
+
+        if let Some(w) = writer.as_mut() {
+            w.write_record(&record)?;
+        }
+    }
+
+    if let Some(mut w) = writer {
+        w.finish()?;
+    }
+
+    eprintln!("Wrote {chunks} file(s)");
+
+    Ok(())
+}
+
+fn print(params: &getopts::Matches, records: RecordIter) -> Result<(), String> {
+    let format = output_format(params, Some(Format::Breaker))?;
+
+    let position = match params.opt_str("position") {
+        Some(p) => Some(
+            p.parse::<usize>()
+                .ok()
+                .filter(|p| *p > 0)
+                .ok_or("Invalid --position")?,
+        ),
+        None => None,
+    };
+
+    let id = params.opt_str("id");
+
+    if position.is_none() && id.is_none() {
+        return Err("--position or --id is required".to_string());
+    }
+
+    let stdout = std::io::stdout().lock();
+    let mut writer = Writer::new(BufWriter::new(stdout), format, xml_options(params));
+
+    writer.start()?;
+
+    for (idx, record) in records.enumerate() {
+        if let Some(p) = position {
+            if idx + 1 < p {
+                // Skip without requiring unwanted records to parse.
+                continue;
+            } else if idx + 1 > p {
+                break;
+            }
+        }
+
+        let record = record?;
+
+        if let Some(id) = id.as_deref() {
+            let matches = record
+                .get_control_fields("001")
+                .iter()
+                .any(|cf| cf.content().trim() == id);
+
+            if !matches {
+                continue;
+            }
+        }
+
+        writer.write_record(&record)?;
+    }
+
+    writer.finish()
+}
+
+/// Report binary records with mismatched encodings.  Returns false
+/// if any were found.
+fn encoding(filename: &str, input: Format, policy: EncodingPolicy) -> Result<bool, String> {
+    if input != Format::Marc {
+        return Err("The encoding command requires binary MARC input".to_string());
+    }
+
+    let mut iter = Record::from_binary_file(filename)?;
+    iter.set_encoding_policy(policy);
+
+    let mut count = 0;
+    for (idx, record) in iter.by_ref().enumerate() {
+        if let Err(e) = record {
+            println!("record {} {e}", idx + 1);
+        }
+        count += 1;
+    }
+
+    for (position, mismatch) in iter.encoding_mismatches() {
+        let id = mismatch.control_number.as_deref().unwrap_or_default();
+        println!("record {position} [{id}] {mismatch}");
+    }
+
+    let mismatches = iter.encoding_mismatches().len();
+
+    eprintln!("{mismatches} of {count} record(s) have mismatched encodings");
+
+    Ok(mismatches == 0)
+}
+
+fn run(args: &[String]) -> Result<bool, String> {
+    let mut opts = getopts::Options::new();
+
+    opts.optopt("", "to", "", "");
+    opts.optflag("", "format-xml", "");
+    opts.optopt("", "xml-prefix", "", "");
+    opts.optflag("", "xml-no-schema-location", "");
+    opts.optflag("", "warnings", "");
+    opts.optflag("", "lenient", "");
+    opts.optopt("", "edit", "", "");
+    opts.optopt("", "chunk-size", "", "");
+    opts.optopt("", "out-prefix", "", "");
+    opts.optopt("", "position", "", "");
+    opts.optopt("", "id", "", "");
+    opts.optopt("", "encoding", "", "");
+    opts.optflag("h", "help", "");
+
+    let params = opts
+        .parse(args)
+        .map_err(|e| format!("Cannot parse command line options: {e}"))?;
+
+    if params.opt_present("help") || params.free.is_empty() {
+        println!("{HELP_TEXT}");
+        return Ok(true);
+    }
+
+    let command = params.free[0].as_str();
+
+    let filename = params.free.get(1).ok_or("Input file required")?;
+    let input = detect_format(filename)?;
+    let policy = encoding_policy(&params)?;
+
+    if command == "encoding" {
+        return encoding(filename, input, policy);
+    }
+
+    let mut records = read_records(filename, input, policy, params.opt_present("lenient"))?;
+
+    if let Some(edit_file) = params.opt_str("edit") {
+        records = Box::new(EditTasks::from_file(&edit_file)?.edit_records(records));
+    }
+
+    match command {
+        "convert" => convert(&params, records)?,
+        "validate" => return validate(&params, records),
+        "count" => {
+            let mut count = 0;
+            for record in records {
+                record?;
+                count += 1;
+            }
+            println!("{count}");
+        }
+        "split" => split(&params, records, input)?,
+        "print" => print(&params, records)?,
+        _ => return Err(format!("Unknown command: {command}")),
+    }
+
+    Ok(true)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    match run(&args[1..]) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    }
+}
@@ -1,13 +1,19 @@
 //! Routines for reading and writing binary MARC data.
+use super::marc8;
+use super::order::FieldOrder;
+use super::unimarc::MarcFormat;
+use super::visitor::RecordVisitor;
 use super::Controlfield;
 use super::Field;
 use super::Record;
 use super::Subfield;
+use std::fmt;
 use std::fs::File;
 use std::io::prelude::*;
+use std::str::FromStr;
 
 const END_OF_FIELD: u8 = 30; // '\x1E';
-const END_OF_RECORD: u8 = 29; // '\x1D';
+pub(crate) const END_OF_RECORD: u8 = 29; // '\x1D';
 const RECORD_SIZE_ENTRY: usize = 5;
 const LEADER_SIZE: usize = 24;
 const DATA_OFFSET_START: usize = 12;
@@ -16,10 +22,162 @@ const DATA_LENGTH_SIZE: usize = 4;
 const DIRECTORY_ENTRY_LEN: usize = 12;
 const SUBFIELD_SEPARATOR: &str = "\x1F";
 const MAX_RECORD_BYTES: usize = 99999;
+const MAX_FIELD_BYTES: usize = 9999;
+const CHAR_CODING_IDX: usize = 9;
+
+/// How to make a record fit within the binary MARC size limits of
+/// 99,999 bytes per record and 9,999 bytes per field.
+///
+/// Fields matching one of the policy's tag specs (see
+/// [`Field::matches_spec`]) are expendable.  Expendable fields which
+/// are too large are truncated, if truncation is enabled, or removed.
+/// If the record is still too large, expendable fields are removed,
+/// starting from the end of the record, until it fits.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SizePolicy {
+    expendable: Vec<String>,
+    truncate: bool,
+}
+
+impl SizePolicy {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Allow fields matching the tag spec, e.g. "5xx", to be removed
+    /// or truncated.
+    pub fn expendable(mut self, spec: &str) -> Self {
+        self.expendable.push(spec.to_string());
+        self
+    }
+
+    /// Truncate the longest subfields of oversized expendable fields
+    /// instead of removing the fields.
+    pub fn truncate(mut self) -> Self {
+        self.truncate = true;
+        self
+    }
+
+    /// True if the field may be removed or truncated.
+    pub fn is_expendable(&self, field: &Field) -> bool {
+        self.expendable.iter().any(|s| field.matches_spec(s))
+    }
+}
+
+/// Character encoding of binary record content.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    Marc8,
+}
+
+impl Encoding {
+    /// Encoding declared by a leader position 09 value.
+    pub fn from_leader_byte(byte: u8) -> Encoding {
+        if byte == marc8::LEADER_MARC8 as u8 {
+            Encoding::Marc8
+        } else {
+            Encoding::Utf8
+        }
+    }
+
+    /// Encoding suggested by the record bytes, or None if the bytes
+    /// are plain ASCII and therefore valid in either encoding.
+    ///
+    /// Content with multibyte characters which is valid UTF-8 is very
+    /// unlikely to be MARC-8, since MARC-8 diacritics precede plain
+    /// ASCII characters.  Other non-ASCII content is assumed to be
+    /// MARC-8.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::binary::Encoding;
+    ///
+    /// assert_eq!(Encoding::detect(b"Bronte"), None);
+    /// assert_eq!(Encoding::detect("Bront\u{00EB}".as_bytes()), Some(Encoding::Utf8));
+    /// assert_eq!(Encoding::detect(b"Bront\xE8e"), Some(Encoding::Marc8));
+    /// ```
+    pub fn detect(bytes: &[u8]) -> Option<Encoding> {
+        if bytes.is_ascii() {
+            None
+        } else if std::str::from_utf8(bytes).is_ok() {
+            Some(Encoding::Utf8)
+        } else {
+            Some(Encoding::Marc8)
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Encoding::Utf8 => write!(f, "UTF-8"),
+            Encoding::Marc8 => write!(f, "MARC-8"),
+        }
+    }
+}
+
+/// How to read records whose content does not match the encoding
+/// declared in leader position 09.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum EncodingPolicy {
+    /// Decode content using the declared encoding.
+    TrustLeader,
+    /// Decode content using the encoding detected from its bytes.
+    #[default]
+    TrustContent,
+    /// Reject the record.
+    Error,
+}
+
+impl FromStr for EncodingPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "trust-leader" => Ok(Self::TrustLeader),
+            "trust-content" => Ok(Self::TrustContent),
+            "error" => Ok(Self::Error),
+            _ => Err(format!("Invalid encoding policy: {s}")),
+        }
+    }
+}
+
+/// A record whose content does not match its declared encoding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodingMismatch {
+    pub declared: Encoding,
+    pub detected: Encoding,
+    /// Value of the record's 001, if any.
+    pub control_number: Option<String>,
+}
+
+impl fmt::Display for EncodingMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "declares {} but contains {}",
+            self.declared, self.detected
+        )
+    }
+}
 
 /// Parses a binary MARC file and emits [`Record`] values.
 pub struct BinaryRecordIterator {
     file: File,
+    format: MarcFormat,
+    encoding_policy: EncodingPolicy,
+    lenient: bool,
+    position: usize,
+    /// Bytes read from the file so far.
+    bytes_read: u64,
+    /// Byte offset of the most recently read record.
+    offset: u64,
+    /// Length in bytes of the most recently read record.
+    length: usize,
+    mismatches: Vec<(usize, EncodingMismatch)>,
+    repairs: Vec<(usize, String)>,
 }
 
 impl Iterator for BinaryRecordIterator {
@@ -37,6 +195,7 @@ impl Iterator for BinaryRecordIterator {
             match self.file.read(&mut buf) {
                 Ok(count) => {
                     if count == 1 {
+                        self.bytes_read += 1;
                         bytes.push(buf[0]);
                         if buf[0] == END_OF_RECORD {
                             break;
@@ -51,10 +210,43 @@ impl Iterator for BinaryRecordIterator {
             }
         }
 
+        if self.lenient && bytes.iter().all(|b| b.is_ascii_whitespace() || *b == 0) {
+            // Trailing line breaks, etc. after the final record.
+            return None;
+        }
+
         if !bytes.is_empty() {
-            match Record::from_binary(bytes.as_slice()) {
-                Ok(r) => return Some(Ok(r)),
-                Err(e) => return Some(Err(format!("Error processing bytes: {:?} {}", bytes, e))),
+            self.position += 1;
+            self.offset = self.bytes_read - bytes.len() as u64;
+            self.length = bytes.len();
+
+            if self.format == MarcFormat::Unimarc {
+                return match Record::from_binary_unimarc(bytes.as_slice()) {
+                    Ok(r) => Some(Ok(r)),
+                    Err(e) => Some(Err(self.record_error(e))),
+                };
+            }
+
+            let mut repairs = Vec::new();
+
+            let result = Record::parse_binary(
+                bytes.as_slice(),
+                self.encoding_policy,
+                self.lenient.then_some(&mut repairs),
+            );
+
+            for repair in repairs {
+                self.repairs.push((self.position, repair));
+            }
+
+            match result {
+                Ok((r, mismatch)) => {
+                    if let Some(m) = mismatch {
+                        self.mismatches.push((self.position, m));
+                    }
+                    return Some(Ok(r));
+                }
+                Err(e) => return Some(Err(self.record_error(e))),
             }
         }
 
@@ -70,8 +262,247 @@ impl BinaryRecordIterator {
             Err(e) => return Err(format!("Cannot read MARC file: {filename} {e}")),
         };
 
-        Ok(BinaryRecordIterator { file })
+        Ok(BinaryRecordIterator {
+            file,
+            format: MarcFormat::default(),
+            encoding_policy: EncodingPolicy::default(),
+            lenient: false,
+            position: 0,
+            bytes_read: 0,
+            offset: 0,
+            length: 0,
+            mismatches: Vec::new(),
+            repairs: Vec::new(),
+        })
+    }
+
+    /// Set the format of the records in the file.  UNIMARC records
+    /// must be UTF-8 encoded and are not checked against their
+    /// encoding policy.
+    pub fn set_marc_format(&mut self, format: MarcFormat) {
+        self.format = format;
+    }
+
+    /// Set the policy for records whose content does not match their
+    /// declared encoding.  Defaults to [`EncodingPolicy::TrustContent`].
+    pub fn set_encoding_policy(&mut self, policy: EncodingPolicy) {
+        self.encoding_policy = policy;
+    }
+
+    /// Read records leniently, repairing common structural problems
+    /// instead of failing.  See [`Record::from_binary_lenient`].
+    ///
+    /// Records are always separated on the record terminator, so a
+    /// record which cannot be repaired does not affect the records
+    /// which follow it.  Ignored for UNIMARC records.
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// 1-based position of the most recently read record.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Byte offset within the file of the most recently read record.
+    ///
+    /// Together with [`BinaryRecordIterator::length`], this locates
+    /// the record for extraction, e.g. with
+    /// `dd bs=1 skip=<offset> count=<length>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let first = Record::from_breaker("=245 10$aFirst").unwrap().to_binary().unwrap();
+    /// let second = Record::from_breaker("=245 10$aSecond").unwrap().to_binary().unwrap();
+    ///
+    /// let mut bytes = first.clone();
+    /// bytes.extend_from_slice(&second);
+    /// bytes.extend_from_slice(b"not a record\x1D");
+    ///
+    /// let filename = std::env::temp_dir().join("marctk-offset-example.mrc");
+    /// std::fs::write(&filename, &bytes).unwrap();
+    ///
+    /// let mut iter = Record::from_binary_file(filename.to_str().unwrap()).unwrap();
+    ///
+    /// iter.next().unwrap().unwrap();
+    /// assert_eq!(iter.offset(), 0);
+    /// assert_eq!(iter.length(), first.len());
+    ///
+    /// iter.next().unwrap().unwrap();
+    /// assert_eq!(iter.position(), 2);
+    /// assert_eq!(iter.offset(), first.len() as u64);
+    ///
+    /// let error = iter.next().unwrap().unwrap_err();
+    /// let offset = first.len() + second.len();
+    /// assert!(error.starts_with(&format!("Error processing record 3 at byte offset {offset} length 13")));
+    /// ```
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Length in bytes of the most recently read record, including its
+    /// record terminator.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Error for the most recently read record, with its location.
+    fn record_error(&self, error: String) -> String {
+        format!(
+            "Error processing record {} at byte offset {} length {}: {error}",
+            self.position, self.offset, self.length
+        )
+    }
+
+    /// Repairs made to records read so far in lenient mode, with the
+    /// 1-based position of each record in the file.
+    pub fn repairs(&self) -> &[(usize, String)] {
+        &self.repairs
+    }
+
+    /// Records read so far whose content did not match their declared
+    /// encoding, with the 1-based position of each record in the file.
+    ///
+    /// With [`EncodingPolicy::TrustContent`], these are the records
+    /// which were re-encoded.  Records rejected by
+    /// [`EncodingPolicy::Error`] are not included.
+    pub fn encoding_mismatches(&self) -> &[(usize, EncodingMismatch)] {
+        &self.mismatches
+    }
+}
+
+/// Pass the contents of a single binary record to a visitor without
+/// building a [`Record`].
+///
+/// Content is decoded per the encoding declared in leader/09.  As with
+/// [`Record::from_binary`], the leader of a MARC-8 record is reported
+/// as UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::Record;
+/// use marctk::binary::visit_binary;
+/// use marctk::visitor::RecordVisitor;
+///
+/// #[derive(Default)]
+/// struct Tags(Vec<String>);
+///
+/// impl RecordVisitor for Tags {
+///     fn control_field(&mut self, tag: &str, _content: &str) {
+///         self.0.push(tag.to_string());
+///     }
+///     fn start_field(&mut self, tag: &str, _ind1: &str, _ind2: &str) {
+///         self.0.push(tag.to_string());
+///     }
+/// }
+///
+/// let record = Record::from_breaker("=001 123\n=245 10$aCaf\u{00E9}").unwrap();
+///
+/// let mut tags = Tags::default();
+/// visit_binary(&record.to_binary_marc8().unwrap(), &mut tags).unwrap();
+///
+/// assert_eq!(tags.0, vec!["001", "245"]);
+/// assert!(visit_binary(b"12345", &mut tags).is_err());
+/// ```
+pub fn visit_binary(rec_bytes: &[u8], visitor: &mut impl RecordVisitor) -> Result<(), String> {
+    let rec_byte_count = rec_bytes.len();
+
+    if rec_byte_count < LEADER_SIZE {
+        return Err(format!(
+            "Binary record is too short: {rec_byte_count} bytes"
+        ));
+    }
+
+    let rec_size = bytes_to_usize(&rec_bytes[0..RECORD_SIZE_ENTRY])?;
+
+    if rec_byte_count != rec_size {
+        return Err(format!(
+            "Record has incorrect size reported={rec_size} real={rec_byte_count}"
+        ));
+    }
+
+    let is_marc8 = Encoding::from_leader_byte(rec_bytes[CHAR_CODING_IDX]) == Encoding::Marc8;
+
+    let mut leader = std::str::from_utf8(&rec_bytes[0..LEADER_SIZE])
+        .map_err(|e| format!("Leader is not a valid UTF-8 string: {e}"))?
+        .to_string();
+
+    if is_marc8 {
+        // Our content will be UTF-8 once translated.
+        leader.replace_range(
+            CHAR_CODING_IDX..CHAR_CODING_IDX + 1,
+            &marc8::LEADER_UNICODE.to_string(),
+        );
+    }
+
+    let data_start_idx =
+        bytes_to_usize(&rec_bytes[DATA_OFFSET_START..(DATA_OFFSET_START + DATA_OFFSET_SIZE)])?;
+
+    if data_start_idx <= LEADER_SIZE || data_start_idx > rec_byte_count {
+        return Err(format!("Invalid base address of data {data_start_idx}"));
+    }
+
+    let dir_bytes = &rec_bytes[LEADER_SIZE..(data_start_idx - 1)];
+
+    if dir_bytes.is_empty() || !dir_bytes.len().is_multiple_of(DIRECTORY_ENTRY_LEN) {
+        return Err(format!("Invalid directory length {}", dir_bytes.len()));
+    }
+
+    visitor.start_record();
+    visitor.leader(&leader);
+
+    for dir_idx in 0..(dir_bytes.len() / DIRECTORY_ENTRY_LEN) {
+        let entry = DirectoryEntry::new(dir_idx, data_start_idx, dir_bytes)?;
+        let tag = entry.tag.as_str();
+
+        if entry.field_end_idx >= rec_byte_count {
+            return Err(format!(
+                "Field length exceeds length of record for tag={tag}"
+            ));
+        }
+
+        let field_bytes = &rec_bytes[entry.field_start_idx..entry.field_end_idx];
+
+        let decoded;
+        let field_str = if is_marc8 {
+            decoded = marc8::decode(field_bytes)
+                .map_err(|e| format!("Field data is not valid MARC-8 in tag={tag}: {e}"))?;
+            decoded.as_str()
+        } else {
+            std::str::from_utf8(field_bytes)
+                .map_err(|e| format!("Field data is not UTF-8 compatible in tag={tag}: {e}"))?
+        };
+
+        if tag < "010" {
+            visitor.control_field(tag, field_str);
+            continue;
+        }
+
+        let (Some(ind1), Some(ind2)) = (field_str.get(..1), field_str.get(1..2)) else {
+            return Err(format!("Missing indicators for tag={tag}"));
+        };
+
+        visitor.start_field(tag, ind1, ind2);
+
+        for part in field_str.split(SUBFIELD_SEPARATOR).skip(1) {
+            let Some(code) = part.chars().next() else {
+                return Err(format!("Empty subfield in tag={tag}"));
+            };
+
+            let (code, content) = part.split_at(code.len_utf8());
+            visitor.subfield(code, content);
+        }
+
+        visitor.end_field();
     }
+
+    visitor.end_record();
+
+    Ok(())
 }
 
 /// Translates a slice of bytes into a String which represents a number,
@@ -145,6 +576,10 @@ impl DirectoryEntry {
             }
         };
 
+        if field_len == 0 {
+            return Err(format!("Invalid data length value {}", field_len_str));
+        }
+
         let start = field_start_idx + data_start_idx;
         let last = start + field_len - 1; // Discard END_OF_FIELD char
 
@@ -156,6 +591,82 @@ impl DirectoryEntry {
     }
 }
 
+/// Start and end index of each field in the data portion of a record,
+/// as delimited by field terminators.  The end index is the index of
+/// the terminator.
+fn field_bounds(rec_bytes: &[u8], data_start_idx: usize) -> Vec<(usize, usize)> {
+    let mut bounds = Vec::new();
+    let mut start = data_start_idx;
+
+    for (idx, byte) in rec_bytes.iter().enumerate().skip(data_start_idx) {
+        if *byte == END_OF_FIELD {
+            bounds.push((start, idx));
+            start = idx + 1;
+        }
+    }
+
+    bounds
+}
+
+/// Build a directory entry whose position and length match the field
+/// terminators, repairing the entry if needed.
+fn repair_directory_entry(
+    which: usize,
+    dir_count: usize,
+    data_start_idx: usize,
+    dir_bytes: &[u8],
+    field_bounds: &[(usize, usize)],
+    repairs: &mut Vec<String>,
+) -> Result<DirectoryEntry, String> {
+    let entry = DirectoryEntry::new(which, data_start_idx, dir_bytes);
+
+    let start = which * DIRECTORY_ENTRY_LEN;
+    let tag = String::from_utf8_lossy(&dir_bytes[start..start + 3]).to_string();
+
+    if let Ok(e) = entry.as_ref() {
+        if field_bounds.contains(&(e.field_start_idx, e.field_end_idx)) {
+            return entry;
+        }
+
+        // The field starts at the correct position but its length
+        // is off.
+        if let Some((s, end)) = field_bounds.iter().find(|(s, _)| *s == e.field_start_idx) {
+            repairs.push(format!(
+                "Field length for tag={tag} reported={} real={}",
+                e.field_end_idx + 1 - e.field_start_idx,
+                end + 1 - s
+            ));
+
+            return Ok(DirectoryEntry {
+                tag,
+                field_start_idx: *s,
+                field_end_idx: *end,
+            });
+        }
+    }
+
+    // Assume the fields appear in directory order, provided the
+    // number of fields matches the directory.
+    if field_bounds.len() == dir_count {
+        let (s, end) = field_bounds[which];
+
+        repairs.push(format!(
+            "Field position for tag={tag} taken from directory order"
+        ));
+
+        return Ok(DirectoryEntry {
+            tag,
+            field_start_idx: s,
+            field_end_idx: end,
+        });
+    }
+
+    match entry {
+        Ok(_) => Err(format!("Cannot locate data for tag={tag}")),
+        Err(e) => Err(e),
+    }
+}
+
 impl Record {
     /// Returns an iterator over MARC records produced from a binary file.
     pub fn from_binary_file(filename: &str) -> Result<BinaryRecordIterator, String> {
@@ -164,47 +675,253 @@ impl Record {
 
     /// Creates a single MARC Record from a series of bytes.
     ///
+    /// Records whose leader position 09 is blank are MARC-8 encoded,
+    /// unless their content is already valid multibyte UTF-8.  Likewise,
+    /// records declared as UTF-8 whose content is not valid UTF-8 are
+    /// read as MARC-8.  MARC-8 content is translated to UTF-8 and the
+    /// leader is updated to match.
+    ///
+    /// See [`Record::from_binary_with_encoding`] to control how
+    /// mismatched encodings are handled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let mut record = Record::new();
+    /// record.add_data_field("245").unwrap().add_subfield("a", "Caf\u{00E9}").unwrap();
+    ///
+    /// let bytes = record.to_binary_marc8().unwrap();
+    /// assert_eq!(bytes[9], b' ');
+    ///
+    /// let record = Record::from_binary(&bytes).unwrap();
+    /// assert_eq!(record.get_field_values("245", "a"), vec!["Caf\u{00E9}"]);
+    /// assert_eq!(&record.leader()[9..10], "a");
+    /// ```
+    ///
     /// # References
     ///
     /// * <https://www.loc.gov/marc/bibliographic/bdleader.html>
     /// * <https://www.loc.gov/marc/bibliographic/bddirectory.html>
     pub fn from_binary(rec_bytes: &[u8]) -> Result<Record, String> {
+        Record::from_binary_with_encoding(rec_bytes, EncodingPolicy::default()).map(|(r, _)| r)
+    }
+
+    /// Creates a single MARC Record from a series of bytes, applying
+    /// the policy if the content does not match the encoding declared
+    /// in leader position 09.
+    ///
+    /// Returns the record along with the mismatch, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::binary::{Encoding, EncodingPolicy};
+    ///
+    /// let record = Record::from_breaker("=001 123\n=245 10$aCaf\u{00E9}").unwrap();
+    ///
+    /// // MARC-8 content mislabeled as UTF-8.
+    /// let mut bytes = record.to_binary_marc8().unwrap();
+    /// bytes[9] = b'a';
+    ///
+    /// let (record2, mismatch) =
+    ///     Record::from_binary_with_encoding(&bytes, EncodingPolicy::TrustContent).unwrap();
+    ///
+    /// assert_eq!(record2.get_field_values("245", "a"), vec!["Caf\u{00E9}"]);
+    ///
+    /// let mismatch = mismatch.unwrap();
+    /// assert_eq!(mismatch.declared, Encoding::Utf8);
+    /// assert_eq!(mismatch.detected, Encoding::Marc8);
+    /// assert_eq!(mismatch.control_number.as_deref(), Some("123"));
+    ///
+    /// assert!(Record::from_binary_with_encoding(&bytes, EncodingPolicy::Error).is_err());
+    /// assert!(Record::from_binary_with_encoding(&bytes, EncodingPolicy::TrustLeader).is_err());
+    /// ```
+    pub fn from_binary_with_encoding(
+        rec_bytes: &[u8],
+        policy: EncodingPolicy,
+    ) -> Result<(Record, Option<EncodingMismatch>), String> {
+        Record::parse_binary(rec_bytes, policy, None)
+    }
+
+    /// Creates a single MARC Record from a series of bytes, repairing
+    /// common structural problems instead of failing.
+    ///
+    /// * Bytes preceding the leader, e.g. line breaks between records,
+    ///   are skipped.  Skipped whitespace is not reported as a repair.
+    /// * Incorrect record lengths and base addresses of data are
+    ///   replaced with the real values.
+    /// * Directory entries whose length or position does not match the
+    ///   field terminators in the data are corrected.
+    /// * Invalid UTF-8 is replaced with U+FFFD and empty subfields are
+    ///   discarded.
+    /// * Fields which still cannot be read are discarded.
+    ///
+    /// Returns the record along with a description of each repair.
+    /// Records which cannot be repaired result in an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker("=001 123\n=245 10$aOne$bTwo").unwrap();
+    /// let mut bytes = record.to_binary().unwrap();
+    /// let record = Record::from_binary(&bytes).unwrap();
+    ///
+    /// // Record length is off by one.
+    /// bytes[4] += 1;
+    ///
+    /// assert!(Record::from_binary(&bytes).is_err());
+    ///
+    /// let (record2, repairs) = Record::from_binary_lenient(&bytes).unwrap();
+    ///
+    /// assert_eq!(record2.to_breaker(), record.to_breaker());
+    /// assert_eq!(repairs.len(), 1);
+    /// assert!(repairs[0].starts_with("Record length"));
+    /// ```
+    pub fn from_binary_lenient(rec_bytes: &[u8]) -> Result<(Record, Vec<String>), String> {
+        let mut repairs = Vec::new();
+
+        let (record, _) =
+            Record::parse_binary(rec_bytes, EncodingPolicy::default(), Some(&mut repairs))?;
+
+        Ok((record, repairs))
+    }
+
+    /// Parse a binary record.  Structural problems are repaired and
+    /// added to the list of repairs, when provided, instead of failing.
+    fn parse_binary(
+        mut rec_bytes: &[u8],
+        policy: EncodingPolicy,
+        mut repairs: Option<&mut Vec<String>>,
+    ) -> Result<(Record, Option<EncodingMismatch>), String> {
         let mut record = Record::new();
 
+        if let Some(repairs) = repairs.as_deref_mut() {
+            // Records begin with the numeric record length.
+            let skip = rec_bytes
+                .iter()
+                .position(|b| b.is_ascii_digit())
+                .unwrap_or(rec_bytes.len());
+
+            // Line breaks between records are common enough to
+            // skip without comment.
+            if rec_bytes[..skip].iter().any(|b| !b.is_ascii_whitespace()) {
+                repairs.push(format!("Skipped {skip} byte(s) preceding the leader"));
+            }
+
+            rec_bytes = &rec_bytes[skip..];
+        }
+
         let rec_byte_count = rec_bytes.len();
 
         if rec_byte_count < LEADER_SIZE {
             return Err(format!("Binary record is too short: {:?}", rec_bytes));
         }
 
-        let leader_bytes = &rec_bytes[0..LEADER_SIZE];
+        let mut leader = rec_bytes[0..LEADER_SIZE].to_vec();
 
         // Reported size of the record byte chunk
-        let size_bytes = &leader_bytes[0..RECORD_SIZE_ENTRY];
+        let size_bytes = &rec_bytes[0..RECORD_SIZE_ENTRY];
+
+        match repairs.as_deref_mut() {
+            Some(repairs) => {
+                if bytes_to_usize(size_bytes).ok() != Some(rec_byte_count) {
+                    repairs.push(format!(
+                        "Record length reported={} real={rec_byte_count}",
+                        String::from_utf8_lossy(size_bytes)
+                    ));
+
+                    if rec_byte_count <= MAX_RECORD_BYTES {
+                        leader[0..RECORD_SIZE_ENTRY]
+                            .copy_from_slice(format!("{rec_byte_count:05}").as_bytes());
+                    }
+                }
+            }
+            None => {
+                // Repported size of the record as a number
+                let rec_size = bytes_to_usize(size_bytes)?;
+
+                if rec_byte_count != rec_size {
+                    return Err(format!(
+                        "Record has incorrect size reported={} real={}",
+                        rec_size, rec_byte_count
+                    ));
+                }
+            }
+        }
 
-        // Repported size of the record as a number
-        let rec_size = bytes_to_usize(size_bytes)?;
+        // Records are often mislabeled, most often as MARC-8.
+        let declared = Encoding::from_leader_byte(leader[CHAR_CODING_IDX]);
+        let detected = Encoding::detect(rec_bytes).unwrap_or(declared);
 
-        if rec_byte_count != rec_size {
+        if detected != declared && policy == EncodingPolicy::Error {
             return Err(format!(
-                "Record has incorrect size reported={} real={}",
-                rec_size, rec_byte_count
+                "Record declares {declared} encoding but contains {detected}"
             ));
         }
 
-        record.set_leader_bytes(leader_bytes)?;
+        let is_marc8 = match policy {
+            EncodingPolicy::TrustLeader => declared == Encoding::Marc8,
+            _ => detected == Encoding::Marc8,
+        };
+
+        if declared == Encoding::Marc8 {
+            // Our content will be UTF-8 once translated.
+            leader[CHAR_CODING_IDX] = marc8::LEADER_UNICODE as u8;
+        }
 
         // Where in this pile of bytes do the control/data fields tart.
-        let data_offset_bytes =
-            &leader_bytes[DATA_OFFSET_START..(DATA_OFFSET_START + DATA_OFFSET_SIZE)];
+        let data_offset_range = DATA_OFFSET_START..(DATA_OFFSET_START + DATA_OFFSET_SIZE);
+        let data_offset_bytes = &rec_bytes[data_offset_range.clone()];
+
+        let data_start_idx = match repairs.as_deref_mut() {
+            Some(repairs) => {
+                // The directory ends at the first field terminator.
+                let real = rec_bytes[LEADER_SIZE..]
+                    .iter()
+                    .position(|b| *b == END_OF_FIELD)
+                    .map(|pos| LEADER_SIZE + pos + 1)
+                    .ok_or("Record has no directory terminator")?;
+
+                if bytes_to_usize(data_offset_bytes).ok() != Some(real) {
+                    repairs.push(format!(
+                        "Base address of data reported={} real={real}",
+                        String::from_utf8_lossy(data_offset_bytes)
+                    ));
+
+                    leader[data_offset_range].copy_from_slice(format!("{real:05}").as_bytes());
+                }
+
+                real
+            }
+            None => bytes_to_usize(data_offset_bytes)?,
+        };
+
+        if data_start_idx <= LEADER_SIZE || data_start_idx > rec_byte_count {
+            return Err(format!("Invalid base address of data {data_start_idx}"));
+        }
 
-        let data_start_idx = bytes_to_usize(data_offset_bytes)?;
+        record.set_leader_bytes(&leader)?;
 
         // The full directory as bytes.
         // -1 to skip the END_OF_FIELD
-        let dir_bytes = &rec_bytes[LEADER_SIZE..(data_start_idx - 1)];
+        let mut dir_bytes = &rec_bytes[LEADER_SIZE..(data_start_idx - 1)];
 
         // Directory byte length should be divisible by the directry entry length.
+        let dir_len = dir_bytes.len();
+        let extra = dir_len % DIRECTORY_ENTRY_LEN;
+
+        if extra != 0 && dir_len > DIRECTORY_ENTRY_LEN {
+            if let Some(repairs) = repairs.as_deref_mut() {
+                repairs.push(format!("Ignored {extra} trailing directory byte(s)"));
+                dir_bytes = &dir_bytes[..(dir_len - extra)];
+            }
+        }
+
         let dir_len = dir_bytes.len();
         if dir_len == 0 || dir_len % DIRECTORY_ENTRY_LEN != 0 {
             return Err(format!("Invalid directory length {}", dir_len));
@@ -212,27 +929,83 @@ impl Record {
 
         // How many directory entries are in this record.
         let dir_count = dir_bytes.len() / DIRECTORY_ENTRY_LEN;
+
+        // Start and end of each field, per the field terminators.
+        let field_bounds = if repairs.is_some() {
+            field_bounds(rec_bytes, data_start_idx)
+        } else {
+            Vec::new()
+        };
+
         let mut dir_idx = 0;
 
         while dir_idx < dir_count {
-            let dir_entry = DirectoryEntry::new(dir_idx, data_start_idx, dir_bytes)?;
-
-            if let Err(e) = record.process_directory_entry(rec_bytes, rec_byte_count, &dir_entry) {
-                return Err(format!(
-                    "Error processing directory entry index={} {}",
-                    dir_idx, e
-                ));
+            let dir_entry = match repairs.as_deref_mut() {
+                Some(repairs) => {
+                    match repair_directory_entry(
+                        dir_idx,
+                        dir_count,
+                        data_start_idx,
+                        dir_bytes,
+                        &field_bounds,
+                        repairs,
+                    ) {
+                        Ok(e) => e,
+                        Err(e) => {
+                            repairs.push(format!("Dropped directory entry index={dir_idx}: {e}"));
+                            dir_idx += 1;
+                            continue;
+                        }
+                    }
+                }
+                None => DirectoryEntry::new(dir_idx, data_start_idx, dir_bytes)?,
+            };
+
+            if let Err(e) = record.process_directory_entry(
+                rec_bytes,
+                rec_byte_count,
+                &dir_entry,
+                is_marc8,
+                repairs.as_deref_mut(),
+            ) {
+                match repairs.as_deref_mut() {
+                    Some(repairs) => {
+                        repairs.push(format!("Dropped field tag={}: {e}", dir_entry.tag))
+                    }
+                    None => {
+                        return Err(format!(
+                            "Error processing directory entry index={} {}",
+                            dir_idx, e
+                        ));
+                    }
+                }
             }
 
             dir_idx += 1;
         }
 
-        Ok(record)
+        let mismatch = if detected != declared {
+            Some(EncodingMismatch {
+                declared,
+                detected,
+                control_number: record
+                    .get_control_fields("001")
+                    .first()
+                    .map(|cf| cf.content().to_string()),
+            })
+        } else {
+            None
+        };
+
+        Ok((record, mismatch))
     }
 
     /// Unpack a single control field / data field and append to the
     /// record in progress.
     ///
+    /// Invalid UTF-8 and empty subfields are repaired when a list of
+    /// repairs is provided.
+    ///
     /// # References
     ///
     /// * <https://www.loc.gov/marc/bibliographic/bddirectory.html>
@@ -241,6 +1014,8 @@ impl Record {
         rec_bytes: &[u8],      // full record as bytes
         rec_byte_count: usize, // full size of record
         dir_entry: &DirectoryEntry,
+        is_marc8: bool,
+        mut repairs: Option<&mut Vec<String>>,
     ) -> Result<(), String> {
         if (dir_entry.field_end_idx) >= rec_byte_count {
             return Err(format!(
@@ -253,16 +1028,29 @@ impl Record {
         let field_bytes = &rec_bytes[dir_entry.field_start_idx..dir_entry.field_end_idx];
 
         // Turn said bytes into a string
-        let field_str = match std::str::from_utf8(field_bytes) {
-            Ok(s) => s,
-            Err(e) => {
-                return Err(format!(
-                    "Field data is not UTF-8 compatible: {:?} {}",
-                    field_bytes, e
-                ));
+        let field_string = if is_marc8 {
+            marc8::decode(field_bytes)
+                .map_err(|e| format!("Field data is not valid MARC-8: {:?} {}", field_bytes, e))?
+        } else {
+            match std::str::from_utf8(field_bytes) {
+                Ok(s) => s.to_string(),
+                Err(e) => match repairs.as_deref_mut() {
+                    Some(repairs) => {
+                        repairs.push(format!("Replaced invalid UTF-8 in tag={}", dir_entry.tag));
+                        String::from_utf8_lossy(field_bytes).to_string()
+                    }
+                    None => {
+                        return Err(format!(
+                            "Field data is not UTF-8 compatible: {:?} {}",
+                            field_bytes, e
+                        ));
+                    }
+                },
             }
         };
 
+        let field_str = field_string.as_str();
+
         if dir_entry.tag.as_str() < "010" {
             let content = if !field_str.is_empty() { field_str } else { "" };
 
@@ -276,8 +1064,12 @@ impl Record {
         // 1 byte for indicator 2
         let mut field = Field::new(&dir_entry.tag)?;
 
-        field.set_ind1(&field_str[..1])?;
-        field.set_ind2(&field_str[1..2])?;
+        let (Some(ind1), Some(ind2)) = (field_str.get(..1), field_str.get(1..2)) else {
+            return Err(format!("Missing indicators for tag={}", dir_entry.tag));
+        };
+
+        field.set_ind1(ind1)?;
+        field.set_ind2(ind2)?;
 
         // Split the remainder on the subfield separator and
         // build Field's from them.
@@ -285,7 +1077,17 @@ impl Record {
 
         for part in &field_parts[1..] {
             // skip the initial SUBFIELD_SEPARATOR
-            let sf = Subfield::new(&part[..1], if part.len() > 1 { &part[1..] } else { "" })?;
+            let Some(code) = part.chars().next() else {
+                match repairs.as_deref_mut() {
+                    Some(repairs) => {
+                        repairs.push(format!("Removed empty subfield in tag={}", dir_entry.tag));
+                        continue;
+                    }
+                    None => return Err(format!("Empty subfield in tag={}", dir_entry.tag)),
+                }
+            };
+
+            let sf = Subfield::new(code, &part[code.len_utf8()..])?;
             field.subfields_mut().push(sf);
         }
 
@@ -310,18 +1112,204 @@ impl Record {
     ///     "00059       00037       245002100000\x1E  \x1FaMy favorite book\x1E\x1D".as_bytes()
     /// );
     /// ```
+    ///
+    /// Records larger than 99,999 bytes, or with fields larger than
+    /// 9,999 bytes, cannot be encoded and result in an error.  See
+    /// [`Record::to_binary_with_policy`].
     pub fn to_binary(&self) -> Result<Vec<u8>, String> {
+        self.to_binary_encoded(false)
+    }
+
+    /// Generates the binary form of a MARC record, first removing or
+    /// truncating fields per the [`SizePolicy`] as needed to fit the
+    /// binary size limits.
+    ///
+    /// The source record is unchanged.  See [`Record::fit_binary_size`].
+    ///
+    /// # Examples
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::binary::SizePolicy;
+    ///
+    /// let mut builder = Record::builder().field("245", "1", "0", &[("a", "Title")]);
+    ///
+    /// let note = "x".repeat(5000);
+    /// for _ in 0..30 {
+    ///     builder = builder.field("500", " ", " ", &[("a", note.as_str())]);
+    /// }
+    ///
+    /// let record = builder.build().unwrap();
+    ///
+    /// assert!(record.to_binary().is_err());
+    ///
+    /// let policy = SizePolicy::new().expendable("5xx");
+    /// let bytes = record.to_binary_with_policy(&policy).unwrap();
+    /// assert!(bytes.len() <= 99999);
+    ///
+    /// let fitted = Record::from_binary(&bytes).unwrap();
+    /// assert_eq!(fitted.get_fields("245").len(), 1);
+    /// assert_eq!(fitted.get_fields("500").len(), 19);
+    ///
+    /// // No expendable fields.
+    /// assert!(record.to_binary_with_policy(&SizePolicy::new()).is_err());
+    /// ```
+    pub fn to_binary_with_policy(&self, policy: &SizePolicy) -> Result<Vec<u8>, String> {
+        let mut record = self.clone();
+        record.fit_binary_size(policy)?;
+        record.to_binary()
+    }
+
+    /// Remove or truncate fields per the [`SizePolicy`] until the
+    /// record fits the binary MARC size limits.
+    ///
+    /// Returns the removed fields.  Returns an error if the record
+    /// cannot be made to fit, in which case the record may be
+    /// partially modified.
+    ///
+    /// # Examples
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::binary::SizePolicy;
+    ///
+    /// let note = "x".repeat(12000);
+    /// let mut record = Record::builder()
+    ///     .field("520", " ", " ", &[("a", note.as_str())])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut truncated = record.clone();
+    /// let removed = truncated.fit_binary_size(&SizePolicy::new().expendable("520").truncate()).unwrap();
+    /// assert!(removed.is_empty());
+    /// assert_eq!(truncated.get_field_values("520", "a")[0].len(), 9994);
+    ///
+    /// let removed = record.fit_binary_size(&SizePolicy::new().expendable("520")).unwrap();
+    /// assert_eq!(removed.len(), 1);
+    /// assert!(record.fields().is_empty());
+    /// ```
+    pub fn fit_binary_size(&mut self, policy: &SizePolicy) -> Result<Vec<Field>, String> {
+        let encode = |s: &str| s.as_bytes().to_vec();
+        let mut removed = Vec::new();
+
+        for field in self.control_fields() {
+            let len = control_field_len(field, &encode);
+            if len > MAX_FIELD_BYTES {
+                return Err(oversized_field_error(field.tag(), len));
+            }
+        }
+
+        let mut idx = 0;
+        while idx < self.fields().len() {
+            let field = &self.fields()[idx];
+            let len = data_field_len(field, &encode);
+
+            if len <= MAX_FIELD_BYTES {
+                idx += 1;
+                continue;
+            }
+
+            if !policy.is_expendable(field) {
+                return Err(oversized_field_error(field.tag(), len));
+            }
+
+            if policy.truncate {
+                truncate_field(&mut self.fields_mut()[idx], len - MAX_FIELD_BYTES);
+                idx += 1;
+            } else {
+                removed.push(self.fields_mut().remove(idx));
+            }
+        }
+
+        while self.binary_len(&encode) > MAX_RECORD_BYTES {
+            let pos = self.fields().iter().rposition(|f| policy.is_expendable(f));
+
+            match pos {
+                Some(idx) => removed.push(self.fields_mut().remove(idx)),
+                None => {
+                    return Err(format!(
+                        "MARC byte count {} too large for binary encoding",
+                        self.binary_len(&encode)
+                    ))
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Size in bytes of the binary form of the record.
+    fn binary_len(&self, encode: &dyn Fn(&str) -> Vec<u8>) -> usize {
+        let num_dirs = self.control_fields().len() + self.fields().len();
+
+        let mut len = LEADER_SIZE + (num_dirs * DIRECTORY_ENTRY_LEN) + 2; // end-of-field + end-of-record
+
+        for field in self.control_fields() {
+            len += control_field_len(field, encode);
+        }
+
+        for field in self.fields() {
+            len += data_field_len(field, encode);
+        }
+
+        len
+    }
+
+    /// Generates the binary form of a MARC record as a vector of bytes
+    /// with content encoded as MARC-8.
+    ///
+    /// Leader position 09 is set to blank in the output.
+    pub fn to_binary_marc8(&self) -> Result<Vec<u8>, String> {
+        self.to_binary_encoded(true)
+    }
+
+    /// Generates the binary form of a MARC record with fields written
+    /// in the provided order.
+    ///
+    /// # Examples
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::order::FieldOrder;
+    ///
+    /// let record = Record::from_breaker(
+    ///     "=LDR 00000nam a2200000 a 4500\n=901 \\\\$aLocal\n=245 10$aTitle"
+    /// ).unwrap();
+    ///
+    /// let bytes = record.to_binary_ordered(&FieldOrder::new()).unwrap();
+    /// let sorted = Record::from_binary(&bytes).unwrap();
+    ///
+    /// assert_eq!(sorted.fields()[0].tag(), "245");
+    /// // The source record is unchanged.
+    /// assert_eq!(record.fields()[0].tag(), "901");
+    /// ```
+    pub fn to_binary_ordered(&self, order: &FieldOrder) -> Result<Vec<u8>, String> {
+        let mut record = self.clone();
+        record.sort_fields_with(order);
+        record.to_binary()
+    }
+
+    fn to_binary_encoded(&self, as_marc8: bool) -> Result<Vec<u8>, String> {
         let mut bytes: Vec<u8> = Vec::new();
 
         bytes.append(&mut self.leader().as_bytes().to_vec());
 
+        if as_marc8 {
+            bytes[CHAR_CODING_IDX] = marc8::LEADER_MARC8 as u8;
+        }
+
+        let encode = |s: &str| -> Vec<u8> {
+            if as_marc8 {
+                marc8::encode(s)
+            } else {
+                s.as_bytes().to_vec()
+            }
+        };
+
         // Directory
-        let num_dirs = self.build_directory(&mut bytes);
+        let num_dirs = self.build_directory(&mut bytes, &encode)?;
 
         // End-of-field after Directory
         bytes.push(END_OF_FIELD);
 
-        self.add_data_fields(&mut bytes);
+        self.add_data_fields(&mut bytes, &encode);
 
         // End-of-record after all data fields are added
         bytes.push(END_OF_RECORD);
@@ -337,16 +1325,23 @@ impl Record {
     /// # References
     ///
     /// * <https://www.loc.gov/marc/bibliographic/bddirectory.html>
-    fn build_directory(&self, bytes: &mut Vec<u8>) -> usize {
+    fn build_directory(
+        &self,
+        bytes: &mut Vec<u8>,
+        encode: &dyn Fn(&str) -> Vec<u8>,
+    ) -> Result<usize, String> {
         let mut num_dirs = 0;
         let mut prev_end_idx = 0;
 
         for field in self.control_fields() {
             num_dirs += 1;
 
-            let mut field_len = field.content().as_bytes().len();
+            let field_len = control_field_len(field, encode);
 
-            field_len += 1; // end of field terminator
+            // Larger values would overflow the directory entry.
+            if field_len > MAX_FIELD_BYTES {
+                return Err(oversized_field_error(field.tag(), field_len));
+            }
 
             // Our directory entry as a string.
             let s = format!(
@@ -366,10 +1361,10 @@ impl Record {
         for field in self.fields() {
             num_dirs += 1;
 
-            let mut field_len = 3; // ind1 + ind2 + field terminator
-            for sf in field.subfields() {
-                field_len += 2; // sf code + separator
-                field_len += sf.content().as_bytes().len();
+            let field_len = data_field_len(field, encode);
+
+            if field_len > MAX_FIELD_BYTES {
+                return Err(oversized_field_error(field.tag(), field_len));
             }
 
             // Our directory entry as a string.
@@ -387,14 +1382,14 @@ impl Record {
             prev_end_idx += field_len;
         }
 
-        num_dirs
+        Ok(num_dirs)
     }
 
     /// Appends the binary forms of the control fields and data fields.
-    fn add_data_fields(&self, bytes: &mut Vec<u8>) {
+    fn add_data_fields(&self, bytes: &mut Vec<u8>, encode: &dyn Fn(&str) -> Vec<u8>) {
         // Now append the actual data
         for field in self.control_fields() {
-            bytes.append(&mut field.content().as_bytes().to_vec());
+            bytes.append(&mut encode(field.content()));
             bytes.push(END_OF_FIELD);
         }
 
@@ -403,8 +1398,9 @@ impl Record {
             bytes.append(&mut s.as_bytes().to_vec());
 
             for sf in field.subfields() {
-                let s = format!("{}{}{}", SUBFIELD_SEPARATOR, sf.code(), sf.content());
+                let s = format!("{}{}", SUBFIELD_SEPARATOR, sf.code());
                 bytes.append(&mut s.as_bytes().to_vec());
+                bytes.append(&mut encode(sf.content()));
             }
 
             bytes.push(END_OF_FIELD);
@@ -439,3 +1435,51 @@ impl Record {
         Ok(())
     }
 }
+
+/// Encoded length of a control field, including its terminator.
+fn control_field_len(field: &Controlfield, encode: &dyn Fn(&str) -> Vec<u8>) -> usize {
+    encode(field.content()).len() + 1
+}
+
+/// Encoded length of a data field, including its terminator.
+fn data_field_len(field: &Field, encode: &dyn Fn(&str) -> Vec<u8>) -> usize {
+    let mut field_len = 3; // ind1 + ind2 + field terminator
+    for sf in field.subfields() {
+        field_len += 2; // sf code + separator
+        field_len += encode(sf.content()).len();
+    }
+    field_len
+}
+
+fn oversized_field_error(tag: &str, len: usize) -> String {
+    format!("Field {tag} byte count {len} too large for binary encoding")
+}
+
+/// Remove at least `excess` bytes from the field, taken from the end
+/// of its longest subfields.
+fn truncate_field(field: &mut Field, mut excess: usize) {
+    while excess > 0 {
+        let Some(sf) = field
+            .subfields_mut()
+            .iter_mut()
+            .max_by_key(|sf| sf.content().len())
+        else {
+            return;
+        };
+
+        let content = sf.content();
+        if content.is_empty() {
+            return;
+        }
+
+        let mut keep = content.len().saturating_sub(excess);
+        while !content.is_char_boundary(keep) {
+            keep -= 1;
+        }
+
+        excess = excess.saturating_sub(content.len() - keep);
+
+        let truncated = content[..keep].to_string();
+        sf.set_content(&truncated);
+    }
+}
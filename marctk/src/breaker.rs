@@ -1,8 +1,15 @@
 //! Routines for reading and writing MARC Breaker text
+//!
+//! Files may contain many records separated by blank lines, e.g. the
+//! .mrk files produced by MarcEdit.  Lines may separate the tag from
+//! the rest of the field with one space, as generated here, or two
+//! spaces, as generated by MarcEdit.
 use super::Controlfield;
 use super::Field;
 use super::Record;
 use super::Subfield;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
 
 const MARC_BREAKER_SF_DELIMITER: &str = "$";
 const MARC_BREAKER_SF_DELIMITER_ESCAPE: &str = "{dollar}";
@@ -17,11 +24,103 @@ pub fn unescape_from_breaker(value: &str) -> String {
     value.replace(MARC_BREAKER_SF_DELIMITER_ESCAPE, MARC_BREAKER_SF_DELIMITER)
 }
 
+/// Conventions for reading and writing breaker text.
+///
+/// The defaults match the LC and MarcEdit conventions.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::Record;
+/// use marctk::breaker::BreakerOptions;
+///
+/// let options = BreakerOptions {
+///     indicator_placeholder: ' ',
+///     subfield_delimiter: '\u{2021}',
+///     escape_dollar: false,
+/// };
+///
+/// let record = Record::from_breaker_ops(
+///     "=LDR 00000nam a2200000 a 4500\n=245  0\u{2021}aPrice: $5\u{2021}cAnon.",
+///     &options,
+/// ).unwrap();
+///
+/// let field = &record.get_fields("245")[0];
+/// assert_eq!(field.ind1(), " ");
+/// assert_eq!(field.ind2(), "0");
+/// assert_eq!(record.get_field_values("245", "a"), vec!["Price: $5"]);
+///
+/// assert_eq!(
+///     record.to_breaker(),
+///     "=LDR 00000nam a2200000 a 4500\n=245 \\0$aPrice: {dollar}5$cAnon."
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BreakerOptions {
+    /// Character which stands for a blank indicator.
+    pub indicator_placeholder: char,
+    /// Character which precedes each subfield code.
+    pub subfield_delimiter: char,
+    /// Write literal "$" characters as "{dollar}" and read "{dollar}"
+    /// as "$".
+    pub escape_dollar: bool,
+}
+
+impl Default for BreakerOptions {
+    fn default() -> Self {
+        BreakerOptions {
+            indicator_placeholder: '\\',
+            subfield_delimiter: '$',
+            escape_dollar: true,
+        }
+    }
+}
+
+impl BreakerOptions {
+    fn escape(&self, value: &str) -> String {
+        if self.escape_dollar {
+            escape_to_breaker(value)
+        } else {
+            value.to_string()
+        }
+    }
+
+    fn unescape(&self, value: &str) -> String {
+        if self.escape_dollar {
+            unescape_from_breaker(value)
+        } else {
+            value.to_string()
+        }
+    }
+
+    fn indicator_to_breaker(&self, ind: &str) -> String {
+        if ind == " " {
+            self.indicator_placeholder.to_string()
+        } else {
+            ind.to_string()
+        }
+    }
+
+    fn indicator_from_breaker(&self, ind: char) -> String {
+        if ind == self.indicator_placeholder {
+            " ".to_string()
+        } else {
+            ind.to_string()
+        }
+    }
+}
+
 impl Controlfield {
     /// Generate breaker text for a [`Controlfield`]
     pub fn to_breaker(&self) -> String {
+        self.to_breaker_ops(&BreakerOptions::default())
+    }
+
+    /// Generate breaker text for a [`Controlfield`] using the provided
+    /// conventions.
+    pub fn to_breaker_ops(&self, options: &BreakerOptions) -> String {
         if !self.content().is_empty() {
-            format!("={} {}", self.tag(), escape_to_breaker(self.content()))
+            format!("={} {}", self.tag(), options.escape(self.content()))
         } else {
             format!("={}", self.tag())
         }
@@ -38,10 +137,17 @@ impl Subfield {
     /// assert_eq!(sf.to_breaker(), "$qHowdy, folks");
     /// ```
     pub fn to_breaker(&self) -> String {
+        self.to_breaker_ops(&BreakerOptions::default())
+    }
+
+    /// Generate breaker text for a [`Subfield`] using the provided
+    /// conventions.
+    pub fn to_breaker_ops(&self, options: &BreakerOptions) -> String {
         format!(
-            "${}{}",
-            escape_to_breaker(self.code()),
-            escape_to_breaker(self.content()),
+            "{}{}{}",
+            options.subfield_delimiter,
+            options.escape(self.code()),
+            options.escape(self.content()),
         )
     }
 }
@@ -58,29 +164,236 @@ impl Field {
     /// assert_eq!(field.to_breaker(), "=856 1\\$qhttps://example.org");
     /// ```
     pub fn to_breaker(&self) -> String {
+        self.to_breaker_ops(&BreakerOptions::default())
+    }
+
+    /// Generate breaker text for a [`Field`] using the provided
+    /// conventions.
+    pub fn to_breaker_ops(&self, options: &BreakerOptions) -> String {
         let mut s = format!(
             "={} {}{}",
             self.tag(),
-            if self.ind1() == " " {
-                "\\"
-            } else {
-                self.ind1()
-            },
-            if self.ind2() == " " {
-                "\\"
-            } else {
-                self.ind2()
-            },
+            options.indicator_to_breaker(self.ind1()),
+            options.indicator_to_breaker(self.ind2()),
         );
 
         for sf in self.subfields() {
-            s += sf.to_breaker().as_str();
+            s += sf.to_breaker_ops(options).as_str();
         }
 
         s
     }
 }
 
+/// Iterates over the records in a file of MARC Breaker text.
+///
+/// Records are separated by one or more blank lines.  The file is read
+/// one line at a time, so memory use does not grow with the size of
+/// the file.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::breaker::BreakerRecordIterator;
+///
+/// let text = "\u{FEFF}=LDR  00000nam a2200000 a 4500\r\n=001  1\r\n=245  10$aOne\r\n\r\n\
+///     =LDR 00000nam a2200000 a 4500\n=001 2\n=245 10$aTwo\n";
+///
+/// let records: Vec<marctk::Record> = BreakerRecordIterator::from_reader(text.as_bytes())
+///     .map(|r| r.unwrap())
+///     .collect();
+///
+/// assert_eq!(records.len(), 2);
+/// assert_eq!(records[0].get_control_fields("001")[0].content(), "1");
+/// assert_eq!(records[0].get_field_values("245", "a"), vec!["One"]);
+/// assert_eq!(records[1].get_field_values("245", "a"), vec!["Two"]);
+/// ```
+pub struct BreakerRecordIterator {
+    reader: Box<dyn BufRead>,
+    line_num: usize,
+    options: BreakerOptions,
+}
+
+impl Iterator for BreakerRecordIterator {
+    type Item = Result<Record, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record: Option<Record> = None;
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+
+            match self.reader.read_line(&mut line) {
+                Ok(0) => break,
+                Ok(_) => self.line_num += 1,
+                Err(e) => {
+                    return Some(Err(format!(
+                        "Error reading breaker text at line {}: {e}",
+                        self.line_num + 1
+                    )))
+                }
+            }
+
+            let mut text = line.trim_end_matches(['\r', '\n']);
+
+            if self.line_num == 1 {
+                text = text.trim_start_matches('\u{FEFF}');
+            }
+
+            if text.trim().is_empty() {
+                if record.is_some() {
+                    break;
+                }
+                // Skip blank lines between records.
+                continue;
+            }
+
+            let rec = record.get_or_insert_with(Record::new);
+
+            if let Err(e) = rec.add_breaker_line(text, &self.options) {
+                // Discard the remainder of the bad record so the next
+                // call starts with a new record.
+                self.skip_record();
+                return Some(Err(format!(
+                    "Invalid breaker text at line {}: {e}",
+                    self.line_num
+                )));
+            }
+        }
+
+        record.map(Ok)
+    }
+}
+
+impl BreakerRecordIterator {
+    /// Create a new iterator from a MARC Breaker file.
+    pub fn from_file(filename: &str) -> Result<Self, String> {
+        match File::open(filename) {
+            Ok(file) => Ok(BreakerRecordIterator::from_reader(file)),
+            Err(e) => Err(format!("Cannot read breaker file: {filename} {e}")),
+        }
+    }
+
+    /// Create a new iterator from a MARC Breaker source, e.g. STDIN.
+    pub fn from_reader(reader: impl std::io::Read + 'static) -> Self {
+        BreakerRecordIterator {
+            reader: Box::new(BufReader::new(reader)),
+            line_num: 0,
+            options: BreakerOptions::default(),
+        }
+    }
+
+    /// Read breaker text using these conventions instead of the
+    /// defaults.
+    pub fn set_options(&mut self, options: BreakerOptions) {
+        self.options = options;
+    }
+
+    /// Read up to and including the next blank line.
+    fn skip_record(&mut self) {
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+
+            match self.reader.read_line(&mut line) {
+                Ok(0) | Err(_) => return,
+                Ok(_) => self.line_num += 1,
+            }
+
+            if line.trim().is_empty() {
+                return;
+            }
+        }
+    }
+}
+
+/// Writes records as MARC Breaker text, one at a time.
+///
+/// Each record is followed by a blank line, so records may be appended
+/// to an existing file.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::Record;
+/// use marctk::breaker::{BreakerRecordIterator, BreakerWriter};
+///
+/// let mut writer = BreakerWriter::new(Vec::new());
+///
+/// for title in ["One", "Two"] {
+///     let record = Record::from_breaker(&format!("=245 10$a{title}")).unwrap();
+///     writer.write_record(&record).unwrap();
+/// }
+///
+/// assert_eq!(writer.written(), 2);
+///
+/// let text = writer.into_inner();
+/// let titles: Vec<String> = BreakerRecordIterator::from_reader(std::io::Cursor::new(text))
+///     .map(|r| r.unwrap().get_field_values("245", "a")[0].to_string())
+///     .collect();
+///
+/// assert_eq!(titles, vec!["One", "Two"]);
+/// ```
+pub struct BreakerWriter<W: Write> {
+    writer: W,
+    written: usize,
+    options: BreakerOptions,
+}
+
+impl BreakerWriter<BufWriter<File>> {
+    /// Create a writer which appends to a file, creating the file if
+    /// it does not exist.
+    pub fn append_to_file(filename: &str) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(filename)
+            .map_err(|e| format!("Cannot open breaker file: {filename} {e}"))?;
+
+        Ok(BreakerWriter::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write> BreakerWriter<W> {
+    pub fn new(writer: W) -> Self {
+        BreakerWriter::with_options(writer, BreakerOptions::default())
+    }
+
+    pub fn with_options(writer: W, options: BreakerOptions) -> Self {
+        BreakerWriter {
+            writer,
+            written: 0,
+            options,
+        }
+    }
+
+    pub fn write_record(&mut self, record: &Record) -> Result<(), String> {
+        writeln!(self.writer, "{}\n", record.to_breaker_ops(&self.options))
+            .map_err(|e| format!("Error writing breaker text: {e}"))?;
+
+        self.written += 1;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.writer
+            .flush()
+            .map_err(|e| format!("Error writing breaker text: {e}"))
+    }
+
+    /// Number of records written so far.
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
 impl Record {
     /// Generate breaker text for a [`Record`]
     ///
@@ -88,14 +401,20 @@ impl Record {
     ///
     /// * <https://www.loc.gov/marc/makrbrkr.html>
     pub fn to_breaker(&self) -> String {
-        let mut s = format!("=LDR {}", &escape_to_breaker(self.leader()));
+        self.to_breaker_ops(&BreakerOptions::default())
+    }
+
+    /// Generate breaker text for a [`Record`] using the provided
+    /// conventions.
+    pub fn to_breaker_ops(&self, options: &BreakerOptions) -> String {
+        let mut s = format!("=LDR {}", &options.escape(self.leader()));
 
         for cfield in self.control_fields() {
-            s += format!("\n{}", cfield.to_breaker()).as_str();
+            s += format!("\n{}", cfield.to_breaker_ops(options)).as_str();
         }
 
         for field in self.fields() {
-            s += format!("\n{}", field.to_breaker()).as_str();
+            s += format!("\n{}", field.to_breaker_ops(options)).as_str();
         }
 
         s
@@ -105,10 +424,16 @@ impl Record {
     ///
     /// Assumes one record per input string.
     pub fn from_breaker(breaker: &str) -> Result<Self, String> {
+        Record::from_breaker_ops(breaker, &BreakerOptions::default())
+    }
+
+    /// Create a MARC [`Record`] from a MARC Breaker string using the
+    /// provided conventions.
+    pub fn from_breaker_ops(breaker: &str, options: &BreakerOptions) -> Result<Self, String> {
         let mut record = Record::new();
 
         for line in breaker.lines() {
-            record.add_breaker_line(line)?;
+            record.add_breaker_line(line, options)?;
         }
 
         Ok(record)
@@ -116,7 +441,8 @@ impl Record {
 
     /// Create a MARC [`Record`] from a file containing MARC Breaker text.
     ///
-    /// Assumes one record per file.
+    /// Assumes one record per file.  See [`BreakerRecordIterator`] for
+    /// files containing multiple records.
     pub fn from_breaker_file(filename: &str) -> Result<Self, String> {
         let breaker = std::fs::read_to_string(filename)
             .map_err(|e| format!("Error reading breaker file: {e}"))?;
@@ -124,7 +450,7 @@ impl Record {
     }
 
     /// Process one line of breaker text and append the result to [`self`]
-    fn add_breaker_line(&mut self, line: &str) -> Result<(), String> {
+    fn add_breaker_line(&mut self, line: &str, options: &BreakerOptions) -> Result<(), String> {
         let mut len = line.len();
         if len < 4 {
             // Skip unusable lines
@@ -132,10 +458,27 @@ impl Record {
         }
 
         // Step past the opening '=' character
-        let line = &line[1..];
+        let mut line = &line[1..];
         len -= 1;
 
-        let tag = &line[..3];
+        let tag = line.get(..3).ok_or("Invalid breaker tag")?;
+        let is_data_field = tag != "LDR" && tag >= "010";
+
+        // MarcEdit separates the tag from the content with two spaces.
+        // Drop the extra space so the offsets below line up.  Blank
+        // indicators are escaped, so this is never ambiguous for data
+        // fields, unless the indicator placeholder is itself a space.
+        // Content which starts with a space, e.g. a blank leader, is
+        // left as-is.
+        let collapsed;
+        if line.get(3..5) == Some("  ")
+            && !line[5..].starts_with(' ')
+            && !(is_data_field && options.indicator_placeholder == ' ')
+        {
+            collapsed = format!("{}{}", &line[..3], &line[4..]);
+            line = &collapsed;
+            len -= 1;
+        }
 
         if tag.eq("LDR") {
             if len > 4 {
@@ -144,9 +487,9 @@ impl Record {
             return Ok(());
         }
 
-        if tag < "010" {
+        if !is_data_field {
             let content = if len > 4 {
-                unescape_from_breaker(&line[4..])
+                options.unescape(&line[4..])
             } else {
                 "".to_string()
             };
@@ -158,24 +501,25 @@ impl Record {
         let mut field = Field::new(tag)?;
 
         // There is a space between the tag and the 1st indicator.
+        let mut chars = line.get(4..).unwrap_or("").chars();
 
-        if len > 4 {
-            field.set_ind1(line[4..5].replace('\\', " "))?;
+        if let Some(ind) = chars.next() {
+            field.set_ind1(options.indicator_from_breaker(ind))?;
         }
 
-        if len > 5 {
-            field.set_ind2(line[5..6].replace('\\', " "))?;
+        if let Some(ind) = chars.next() {
+            field.set_ind2(options.indicator_from_breaker(ind))?;
         }
 
-        if len > 6 {
-            for sf in line[6..].split(MARC_BREAKER_SF_DELIMITER) {
-                if sf.is_empty() {
-                    continue;
-                }
-                let code = &sf[..1];
-                let content = &sf[1..]; // maybe ""
-                field.subfields_mut().push(Subfield::new(code, content)?);
-            }
+        for sf in chars.as_str().split(options.subfield_delimiter) {
+            let mut sf_chars = sf.chars();
+            let Some(code) = sf_chars.next() else {
+                continue;
+            };
+            let content = options.unescape(sf_chars.as_str()); // maybe ""
+            field
+                .subfields_mut()
+                .push(Subfield::new(code.to_string(), content)?);
         }
 
         self.fields_mut().push(field);
@@ -186,16 +530,55 @@ impl Record {
 
 #[cfg(test)]
 mod breaker_tests {
+    use super::BreakerOptions;
+
     #[test]
     fn test_add_breaker_line() {
         let mut record = crate::Record::default();
+        let ops = BreakerOptions::default();
 
-        assert!(record.add_breaker_line("=LDR too short").is_err());
+        assert!(record.add_breaker_line("=LDR too short", &ops).is_err());
 
         record
-            .add_breaker_line("=100 11$aSunshine$b$csquashes")
+            .add_breaker_line("=100 11$aSunshine$b$csquashes", &ops)
             .unwrap();
         assert_eq!(record.get_field_values("100", "a")[0], "Sunshine");
         assert_eq!(record.get_field_values("100", "b")[0], "");
     }
+
+    #[test]
+    fn test_add_marcedit_breaker_line() {
+        let mut record = crate::Record::default();
+        let ops = BreakerOptions::default();
+
+        record.add_breaker_line("=001  ocm123", &ops).unwrap();
+        record.add_breaker_line("=245  10$aTitle", &ops).unwrap();
+        record.add_breaker_line("=650  \\0$aSubject", &ops).unwrap();
+
+        assert_eq!(record.get_control_fields("001")[0].content(), "ocm123");
+        assert_eq!(record.get_fields("245")[0].ind1(), "1");
+        assert_eq!(record.get_fields("245")[0].ind2(), "0");
+        assert_eq!(record.get_field_values("245", "a")[0], "Title");
+        assert_eq!(record.get_fields("650")[0].ind1(), " ");
+        assert_eq!(record.get_field_values("650", "a")[0], "Subject");
+    }
+
+    #[test]
+    fn test_breaker_dollar_escape() {
+        let mut record = crate::Record::default();
+        let mut ops = BreakerOptions::default();
+
+        record
+            .add_breaker_line("=020 \\$c{dollar}10", &ops)
+            .unwrap();
+        assert_eq!(record.get_field_values("020", "c")[0], "$10");
+
+        ops.escape_dollar = false;
+        ops.subfield_delimiter = '|';
+
+        record
+            .add_breaker_line("=020 \\|c{dollar}10", &ops)
+            .unwrap();
+        assert_eq!(record.get_field_values("020", "c")[1], "{dollar}10");
+    }
 }
@@ -0,0 +1,160 @@
+//! Fluent API for constructing records.
+use super::validate::Validator;
+use super::Controlfield;
+use super::Field;
+use super::Record;
+
+/// Builds a [`Record`] one field at a time.
+///
+/// Invalid values (bad tags, indicators, subfield codes, etc.) do not
+/// interrupt the chain.  They are collected and reported together by
+/// [`RecordBuilder::build`].
+///
+/// # Examples
+///
+/// ```
+/// use marctk::Record;
+///
+/// let record = Record::builder()
+///     .control("001", "123")
+///     .field("245", "1", "0", &[("a", "Title :"), ("b", "subtitle")])
+///     .field("100", "1", " ", &[("a", "Author, A.")])
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(record.get_control_fields("001")[0].content(), "123");
+///
+/// // Fields are added in tag order.
+/// assert_eq!(record.fields()[0].tag(), "100");
+/// assert_eq!(record.get_field_values("245", "b"), vec!["subtitle"]);
+/// assert_eq!(record.fields()[1].ind1(), "1");
+///
+/// let result = Record::builder()
+///     .control("010", "123")
+///     .field("245", "10", "0", &[("ab", "Title")])
+///     .build();
+///
+/// assert_eq!(
+///     result.unwrap_err(),
+///     "Invalid Controlfield tag: 010; \
+///     245: Invalid byte count for string s=10 wanted=1 found=2; \
+///     245: Invalid byte count for string s=ab wanted=1 found=2"
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RecordBuilder {
+    record: Record,
+    errors: Vec<String>,
+    validate: bool,
+}
+
+impl RecordBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Set the leader.
+    pub fn leader(mut self, leader: &str) -> Self {
+        if let Err(e) = self.record.set_leader(leader) {
+            self.errors.push(e);
+        }
+        self
+    }
+
+    /// Add a control field.
+    pub fn control(mut self, tag: &str, content: &str) -> Self {
+        match Controlfield::new(tag, content) {
+            Ok(cf) => self.record.insert_control_field(cf),
+            Err(e) => self.errors.push(e),
+        }
+        self
+    }
+
+    /// Add a data field with the provided indicators and
+    /// (code, content) subfield pairs.
+    pub fn field(mut self, tag: &str, ind1: &str, ind2: &str, subfields: &[(&str, &str)]) -> Self {
+        let mut field = match Field::new(tag) {
+            Ok(f) => f,
+            Err(e) => {
+                self.errors.push(e);
+                return self;
+            }
+        };
+
+        let mut errors = Vec::new();
+
+        if let Err(e) = field.set_ind1(ind1) {
+            errors.push(e);
+        }
+
+        if let Err(e) = field.set_ind2(ind2) {
+            errors.push(e);
+        }
+
+        for (code, content) in subfields {
+            if let Err(e) = field.add_subfield(*code, *content) {
+                errors.push(e);
+            }
+        }
+
+        if errors.is_empty() {
+            self.record.insert_data_field(field);
+        } else {
+            self.errors
+                .extend(errors.into_iter().map(|e| format!("{tag}: {e}")));
+        }
+
+        self
+    }
+
+    /// Add an already constructed data field.
+    pub fn data_field(mut self, field: Field) -> Self {
+        self.record.insert_data_field(field);
+        self
+    }
+
+    /// Also check the finished record against the MARC21
+    /// bibliographic format (see [`Validator`]).  Error-level
+    /// issues cause [`RecordBuilder::build`] to fail.
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// // No 245
+    /// let result = Record::builder()
+    ///     .field("100", "1", " ", &[("a", "Author, A.")])
+    ///     .validate()
+    ///     .build();
+    ///
+    /// assert!(result.is_err());
+    /// ```
+    pub fn validate(mut self) -> Self {
+        self.validate = true;
+        self
+    }
+
+    /// Returns the record or a "; "-separated list of every error
+    /// encountered while building it.
+    pub fn build(mut self) -> Result<Record, String> {
+        if self.validate && self.errors.is_empty() {
+            for issue in Validator::new().validate(&self.record) {
+                if issue.is_error() {
+                    self.errors.push(issue.to_string());
+                }
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(self.record)
+        } else {
+            Err(self.errors.join("; "))
+        }
+    }
+}
+
+impl Record {
+    /// Start building a new record.  See [`RecordBuilder`].
+    pub fn builder() -> RecordBuilder {
+        RecordBuilder::new()
+    }
+}
@@ -0,0 +1,392 @@
+//! Call number extraction and normalization.
+//!
+//! Bibliographic records carry classification numbers in 050 (Library
+//! of Congress), 082 (Dewey), and 086 (government documents), plus the
+//! locally assigned 090 (LC) and 092 (Dewey) fields.
+//!
+//! Sort keys are built so that a plain string comparison puts call
+//! numbers in shelf order, e.g. "QA76" before "QA100" and "813.5"
+//! before "813.54".
+//!
+//! # References
+//!
+//! * <https://www.loc.gov/marc/bibliographic/bd050.html>
+//! * <https://www.loc.gov/marc/bibliographic/bd082.html>
+//! * <https://www.loc.gov/marc/bibliographic/bd086.html>
+use super::Field;
+use super::Record;
+use std::fmt;
+
+/// Call number tags in order of preference.
+///
+/// Local fields are listed before their national counterparts since
+/// they reflect the library's own shelving practice.
+pub const CALL_NUMBER_TAGS: [&str; 5] = ["090", "050", "092", "082", "086"];
+
+/// Width of the zero-padded LC class number, e.g. "0076" in "QA 0076".
+const LC_CLASS_WIDTH: usize = 4;
+
+/// Width of the zero-padded Dewey class number, e.g. "005" in "005.133".
+const DEWEY_CLASS_WIDTH: usize = 3;
+
+/// Classification scheme of a call number.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CallNumberScheme {
+    LibraryOfCongress,
+    Dewey,
+    /// SuDoc and other government document numbers.
+    GovernmentDocument,
+}
+
+impl CallNumberScheme {
+    /// Scheme implied by a call number tag.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "050" | "090" => Some(Self::LibraryOfCongress),
+            "082" | "092" => Some(Self::Dewey),
+            "086" => Some(Self::GovernmentDocument),
+            _ => None,
+        }
+    }
+
+    /// Guess whether a call number is LC or Dewey from its shape.
+    ///
+    /// ```
+    /// use marctk::callnumber::CallNumberScheme;
+    ///
+    /// assert_eq!(CallNumberScheme::detect("QA76.73.J38"), Some(CallNumberScheme::LibraryOfCongress));
+    /// assert_eq!(CallNumberScheme::detect("813/.54"), Some(CallNumberScheme::Dewey));
+    /// assert_eq!(CallNumberScheme::detect("FIC SMITH"), None);
+    /// ```
+    pub fn detect(value: &str) -> Option<Self> {
+        let value = value.trim();
+
+        if parse_lc(value).is_some() {
+            Some(Self::LibraryOfCongress)
+        } else if value.len() >= DEWEY_CLASS_WIDTH
+            && value
+                .chars()
+                .take(DEWEY_CLASS_WIDTH)
+                .all(|c| c.is_ascii_digit())
+        {
+            Some(Self::Dewey)
+        } else {
+            None
+        }
+    }
+}
+
+/// Call number from a single 050, 082, 086, 090, or 092 field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallNumber<'a> {
+    field: &'a Field,
+    scheme: CallNumberScheme,
+}
+
+impl<'a> CallNumber<'a> {
+    /// Returns None if the field is not a call number field or has
+    /// no classification number.
+    ///
+    /// The scheme of the local 090 and 092 fields is detected from
+    /// their contents, since they are not always used as intended.
+    pub fn from_field(field: &'a Field) -> Option<Self> {
+        let mut scheme = CallNumberScheme::from_tag(field.tag())?;
+
+        let class = field.first_subfield("a")?.content();
+
+        if class.trim().is_empty() {
+            return None;
+        }
+
+        if matches!(field.tag(), "090" | "092") {
+            scheme = CallNumberScheme::detect(class).unwrap_or(scheme);
+        }
+
+        Some(CallNumber { field, scheme })
+    }
+
+    pub fn field(&self) -> &'a Field {
+        self.field
+    }
+
+    pub fn tag(&self) -> &str {
+        self.field.tag()
+    }
+
+    pub fn scheme(&self) -> CallNumberScheme {
+        self.scheme
+    }
+
+    /// True for locally assigned call numbers (090, 092).
+    pub fn is_local(&self) -> bool {
+        matches!(self.tag(), "090" | "092")
+    }
+
+    /// Classification number from the first $a.
+    ///
+    /// Repeated $a's contain alternate numbers and are ignored.
+    pub fn classification(&self) -> String {
+        self.field
+            .first_subfield("a")
+            .map(|sf| self.clean(sf.content()))
+            .unwrap_or_default()
+    }
+
+    /// Item number (cutter, date, etc.) from $b.
+    pub fn item_number(&self) -> Option<String> {
+        self.field
+            .first_subfield("b")
+            .map(|sf| self.clean(sf.content()))
+            .filter(|v| !v.is_empty())
+    }
+
+    /// Display form, i.e. the classification and item numbers.
+    pub fn label(&self) -> String {
+        match self.item_number() {
+            Some(item) => format!("{} {item}", self.classification()),
+            None => self.classification(),
+        }
+    }
+
+    /// Normalized form of the label suitable for sorting.
+    pub fn sort_key(&self) -> String {
+        sort_key(&self.label(), self.scheme)
+    }
+
+    /// Collapse whitespace and, for Dewey, remove the prime marks
+    /// which show where a number may be segmented, e.g. "813/.54".
+    fn clean(&self, value: &str) -> String {
+        let value = value.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+        match self.scheme {
+            CallNumberScheme::Dewey => value.replace(['/', '\''], ""),
+            _ => value,
+        }
+    }
+}
+
+impl fmt::Display for CallNumber<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+/// Class letters, class number, decimal, and remaining components
+/// of an LC call number.
+struct LcParts<'a> {
+    letters: &'a str,
+    number: &'a str,
+    decimal: Option<&'a str>,
+    remainder: &'a str,
+}
+
+/// Split an LC call number into its parts.
+///
+/// Returns None if the value does not start with 1-3 class letters
+/// followed by a class number.
+fn parse_lc(value: &str) -> Option<LcParts<'_>> {
+    let letter_count = value
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+
+    if letter_count == 0 || letter_count > 3 {
+        return None;
+    }
+
+    let letters = &value[..letter_count];
+    let rest = value[letter_count..].trim_start();
+
+    let digit_count = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+
+    if digit_count == 0 || digit_count > LC_CLASS_WIDTH {
+        return None;
+    }
+
+    let number = &rest[..digit_count];
+    let mut rest = &rest[digit_count..];
+    let mut decimal = None;
+
+    if let Some(after) = rest.strip_prefix('.') {
+        let count = after
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(after.len());
+
+        if count > 0 {
+            decimal = Some(&after[..count]);
+            rest = &after[count..];
+        }
+    }
+
+    Some(LcParts {
+        letters,
+        number,
+        decimal,
+        remainder: rest,
+    })
+}
+
+/// Split the cutters, dates, etc. following a class number into
+/// separate tokens.  A period before a letter introduces a cutter.
+fn remainder_tokens(value: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let is_break =
+            c.is_whitespace() || (c == '.' && chars.peek().is_some_and(|n| n.is_alphabetic()));
+
+        if is_break {
+            if !token.is_empty() {
+                tokens.push(std::mem::take(&mut token));
+            }
+        } else {
+            token.push(c);
+        }
+    }
+
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    tokens
+}
+
+/// Uppercase with whitespace collapsed.
+fn normalize_generic(value: &str) -> String {
+    value
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_uppercase()
+}
+
+/// Build a sortable form of a call number.
+///
+/// LC class numbers are zero-padded to 4 digits and cutters are
+/// separated by spaces.  Dewey class numbers are zero-padded to 3
+/// digits with prime marks removed.  Anything which does not look
+/// like its scheme is uppercased with whitespace collapsed.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::callnumber::{sort_key, CallNumberScheme};
+///
+/// let lc = CallNumberScheme::LibraryOfCongress;
+/// assert_eq!(sort_key("QA76.73.J38 2020", lc), "QA 0076.73 J38 2020");
+/// assert_eq!(sort_key("pn 1995.9 .W4 S6", lc), "PN 1995.9 W4 S6");
+/// assert!(sort_key("QA76", lc) < sort_key("QA100", lc));
+/// assert!(sort_key("Q300", lc) < sort_key("QA76", lc));
+///
+/// let dewey = CallNumberScheme::Dewey;
+/// assert_eq!(sort_key("813/.54 r62", dewey), "813.54 R62");
+/// assert_eq!(sort_key("5.133 J38", dewey), "005.133 J38");
+/// assert!(sort_key("813.5", dewey) < sort_key("813.54", dewey));
+///
+/// assert_eq!(sort_key("FIC  Smith", dewey), "FIC SMITH");
+/// ```
+pub fn sort_key(value: &str, scheme: CallNumberScheme) -> String {
+    let value = normalize_generic(value);
+
+    match scheme {
+        CallNumberScheme::LibraryOfCongress => lc_sort_key(&value).unwrap_or(value),
+        CallNumberScheme::Dewey => dewey_sort_key(&value).unwrap_or(value),
+        CallNumberScheme::GovernmentDocument => value,
+    }
+}
+
+fn lc_sort_key(value: &str) -> Option<String> {
+    let parts = parse_lc(value)?;
+
+    let mut key = format!(
+        "{} {:0>width$}",
+        parts.letters,
+        parts.number,
+        width = LC_CLASS_WIDTH
+    );
+
+    if let Some(decimal) = parts.decimal {
+        key.push('.');
+        key.push_str(decimal);
+    }
+
+    for token in remainder_tokens(parts.remainder) {
+        key.push(' ');
+        key.push_str(&token);
+    }
+
+    Some(key)
+}
+
+fn dewey_sort_key(value: &str) -> Option<String> {
+    let value = value.replace(['/', '\''], "");
+
+    let digit_count = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+
+    if digit_count == 0 || digit_count > DEWEY_CLASS_WIDTH {
+        return None;
+    }
+
+    Some(format!(
+        "{:0>width$}{}",
+        &value[..digit_count],
+        &value[digit_count..],
+        width = DEWEY_CLASS_WIDTH
+    ))
+}
+
+impl Record {
+    /// Call numbers from the 090, 050, 092, 082, and 086 fields, in
+    /// that order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::callnumber::CallNumberScheme;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =050 00$aQA76.73.J38$bS65 2020
+    /// =082 04$a005.13/3$223
+    /// =092 \\$aFIC$bSMITH
+    /// =086 0\$aY 4.J 89/1:117-32"#
+    /// ).unwrap();
+    ///
+    /// let call_numbers = record.call_numbers();
+    /// assert_eq!(call_numbers.len(), 4);
+    /// assert_eq!(call_numbers[0].tag(), "050");
+    /// assert_eq!(call_numbers[0].label(), "QA76.73.J38 S65 2020");
+    /// assert_eq!(call_numbers[0].sort_key(), "QA 0076.73 J38 S65 2020");
+    ///
+    /// // Local Dewey field with a non-numeric class.
+    /// assert!(call_numbers[1].is_local());
+    /// assert_eq!(call_numbers[1].sort_key(), "FIC SMITH");
+    ///
+    /// let dewey = record.call_number(CallNumberScheme::Dewey).unwrap();
+    /// assert_eq!(dewey.tag(), "092");
+    /// assert_eq!(call_numbers[2].label(), "005.133");
+    ///
+    /// let gov = record.call_number(CallNumberScheme::GovernmentDocument).unwrap();
+    /// assert_eq!(gov.label(), "Y 4.J 89/1:117-32");
+    /// ```
+    pub fn call_numbers(&self) -> Vec<CallNumber<'_>> {
+        CALL_NUMBER_TAGS
+            .iter()
+            .flat_map(|tag| self.get_fields(tag))
+            .filter_map(CallNumber::from_field)
+            .collect()
+    }
+
+    /// The preferred call number for the requested scheme.
+    pub fn call_number(&self, scheme: CallNumberScheme) -> Option<CallNumber<'_>> {
+        self.call_numbers()
+            .into_iter()
+            .find(|cn| cn.scheme() == scheme)
+    }
+}
@@ -0,0 +1,273 @@
+//! Record control numbers (001/003) and system control numbers (035).
+//!
+//! The 001 holds the control number assigned by the organization
+//! identified in the 003.  When a record moves between systems, the
+//! previous control number is typically retained in an 035 $a as
+//! "(ORG)number", e.g. "(OCoLC)953985896", and the 001/003 are replaced
+//! with the new system's values.
+use super::Field;
+use super::Record;
+use std::fmt;
+
+/// A control number and the code of the organization which assigned it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlNumber {
+    number: String,
+    source: Option<String>,
+}
+
+impl ControlNumber {
+    pub fn new(number: &str, source: Option<&str>) -> Self {
+        ControlNumber {
+            number: number.trim().to_string(),
+            source: source
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty()),
+        }
+    }
+
+    /// Parse an 035 $a value, e.g. "(OCoLC)953985896".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::control_number::ControlNumber;
+    ///
+    /// let cn = ControlNumber::parse("(OCoLC)ocn953985896");
+    /// assert_eq!(cn.source(), Some("OCoLC"));
+    /// assert_eq!(cn.number(), "ocn953985896");
+    /// assert_eq!(cn.to_string(), "(OCoLC)ocn953985896");
+    ///
+    /// let cn = ControlNumber::parse("12345");
+    /// assert_eq!(cn.source(), None);
+    /// ```
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+
+        if let Some(rest) = value.strip_prefix('(') {
+            if let Some((source, number)) = rest.split_once(')') {
+                return ControlNumber::new(number, Some(source));
+            }
+        }
+
+        ControlNumber::new(value, None)
+    }
+
+    pub fn number(&self) -> &str {
+        &self.number
+    }
+
+    /// MARC organization code of the assigning organization.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// True if both refer to the same number from the same source.
+    ///
+    /// Sources are compared case-insensitively.  OCLC numbers ignore
+    /// their "ocm", "ocn", and "on" prefixes and leading zeros.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::control_number::ControlNumber;
+    ///
+    /// let a = ControlNumber::parse("(OCoLC)ocm00012345");
+    /// let b = ControlNumber::parse("(ocolc)12345");
+    /// assert!(a.matches(&b));
+    ///
+    /// let c = ControlNumber::parse("(CONS)12345");
+    /// assert!(!a.matches(&c));
+    /// ```
+    pub fn matches(&self, other: &ControlNumber) -> bool {
+        let source = |cn: &ControlNumber| cn.source().map(|s| s.to_lowercase());
+        source(self) == source(other) && self.match_number() == other.match_number()
+    }
+
+    fn match_number(&self) -> &str {
+        if !self.is_oclc() {
+            return &self.number;
+        }
+
+        let number = ["ocm", "ocn", "on"]
+            .iter()
+            .find_map(|p| self.number.strip_prefix(p))
+            .unwrap_or(&self.number);
+
+        number.trim_start_matches('0')
+    }
+
+    fn is_oclc(&self) -> bool {
+        self.source()
+            .map(|s| s.eq_ignore_ascii_case("OCoLC"))
+            .unwrap_or(false)
+    }
+}
+
+/// Renders the number as "(source)number", or just the number when
+/// the source is unknown.
+impl fmt::Display for ControlNumber {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.source() {
+            Some(s) => write!(f, "({s}){}", self.number),
+            None => write!(f, "{}", self.number),
+        }
+    }
+}
+
+impl Record {
+    /// The record's control number from the 001 field.
+    pub fn control_number(&self) -> Option<&str> {
+        self.get_control_fields("001")
+            .first()
+            .map(|cf| cf.content().trim())
+            .filter(|c| !c.is_empty())
+    }
+
+    /// The control number identifier from the 003 field.
+    pub fn control_number_source(&self) -> Option<&str> {
+        self.get_control_fields("003")
+            .first()
+            .map(|cf| cf.content().trim())
+            .filter(|c| !c.is_empty())
+    }
+
+    /// The 001 control number along with its 003 source.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let mut record = Record::from_breaker("=001 ocn953985896\n=003 OCoLC").unwrap();
+    ///
+    /// let cn = record.record_control_number().unwrap();
+    /// assert_eq!(cn.to_string(), "(OCoLC)ocn953985896");
+    ///
+    /// record.set_control_number("1705072", Some("KCLS")).unwrap();
+    /// assert_eq!(record.control_number(), Some("1705072"));
+    /// assert_eq!(record.control_number_source(), Some("KCLS"));
+    ///
+    /// record.set_control_number("1705073", None).unwrap();
+    /// assert!(record.get_control_fields("003").is_empty());
+    /// ```
+    pub fn record_control_number(&self) -> Option<ControlNumber> {
+        self.control_number()
+            .map(|n| ControlNumber::new(n, self.control_number_source()))
+    }
+
+    /// Replace the 001 and 003 fields.
+    ///
+    /// The 003 is removed when no source is provided.
+    pub fn set_control_number(&mut self, number: &str, source: Option<&str>) -> Result<(), String> {
+        if number.trim().is_empty() {
+            return Err("Control number cannot be empty".to_string());
+        }
+
+        self.remove_control_fields("001");
+        self.remove_control_fields("003");
+
+        self.add_control_field("001", number.trim())?;
+
+        if let Some(source) = source.map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            self.add_control_field("003", source)?;
+        }
+
+        Ok(())
+    }
+
+    /// System control numbers from 035 $a.
+    pub fn system_control_numbers(&self) -> Vec<ControlNumber> {
+        self.get_field_values("035", "a")
+            .into_iter()
+            .filter(|v| !v.trim().is_empty())
+            .map(ControlNumber::parse)
+            .collect()
+    }
+
+    /// Copy the 001/003 control number into an 035 as "(source)number".
+    ///
+    /// `default_source` is used when the record has no 003.  The 001
+    /// and 003 are left as-is, typically to be replaced via
+    /// [`Record::set_control_number`].
+    ///
+    /// Returns true if an 035 was added.  No 035 is added if a matching
+    /// one already exists.  Err if the record has no 001, or no source
+    /// is available.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let mut record = Record::from_breaker("=001 ocm00012345\n=003 OCoLC").unwrap();
+    ///
+    /// assert_eq!(record.migrate_control_number(None), Ok(true));
+    /// assert_eq!(record.get_field_values("035", "a"), ["(OCoLC)ocm00012345"]);
+    ///
+    /// // Already present.
+    /// assert_eq!(record.migrate_control_number(None), Ok(false));
+    ///
+    /// let mut record = Record::from_breaker("=001 98765").unwrap();
+    /// assert!(record.migrate_control_number(None).is_err());
+    /// assert_eq!(record.migrate_control_number(Some("CONS")), Ok(true));
+    /// assert_eq!(record.get_field_values("035", "a"), ["(CONS)98765"]);
+    /// ```
+    pub fn migrate_control_number(&mut self, default_source: Option<&str>) -> Result<bool, String> {
+        let number = self
+            .control_number()
+            .ok_or("Record has no control number")?
+            .to_string();
+
+        let source = self
+            .control_number_source()
+            .or(default_source)
+            .ok_or_else(|| format!("No source for control number {number}"))?
+            .to_string();
+
+        let cn = ControlNumber::new(&number, Some(&source));
+
+        if self.system_control_numbers().iter().any(|c| c.matches(&cn)) {
+            return Ok(false);
+        }
+
+        let mut field = Field::new("035")?;
+        field.add_subfield("a", cn.to_string())?;
+        self.insert_data_field(field);
+
+        Ok(true)
+    }
+
+    /// 035 system control numbers which repeat an earlier 035 value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=035 \\$a(OCoLC)953985896
+    /// =035 \\$a(CONS)12345
+    /// =035 \\$a(OCoLC)ocn953985896"#
+    /// ).unwrap();
+    ///
+    /// let dupes = record.duplicate_system_control_numbers();
+    ///
+    /// assert_eq!(dupes.len(), 1);
+    /// assert_eq!(dupes[0].to_string(), "(OCoLC)ocn953985896");
+    /// ```
+    pub fn duplicate_system_control_numbers(&self) -> Vec<ControlNumber> {
+        let mut seen: Vec<ControlNumber> = Vec::new();
+        let mut dupes = Vec::new();
+
+        for cn in self.system_control_numbers() {
+            if seen.iter().any(|s| s.matches(&cn)) {
+                dupes.push(cn);
+            } else {
+                seen.push(cn);
+            }
+        }
+
+        dupes
+    }
+}
@@ -0,0 +1,558 @@
+//! MARC21 to MODS and Dublin Core crosswalks.
+//!
+//! These follow the Library of Congress MARC to MODS and MARC to
+//! Dublin Core mappings for the commonly used elements, producing
+//! records suitable for OAI-PMH harvesters and discovery layers.
+//!
+//! # References
+//!
+//! * <https://www.loc.gov/standards/mods/mods-mapping.html>
+//! * <https://www.loc.gov/marc/marc2dc.html>
+use super::display::{clean_value, join_subfields};
+use super::xml::escape_xml;
+use super::Field;
+use super::Record;
+
+pub const MODS_NAMESPACE: &str = "http://www.loc.gov/mods/v3";
+pub const MODS_SCHEMA_LOCATION: &str =
+    "http://www.loc.gov/mods/v3 http://www.loc.gov/standards/mods/v3/mods-3-8.xsd";
+
+pub const OAI_DC_NAMESPACE: &str = "http://www.openarchives.org/OAI/2.0/oai_dc/";
+pub const DC_NAMESPACE: &str = "http://purl.org/dc/elements/1.1/";
+pub const OAI_DC_SCHEMA_LOCATION: &str =
+    "http://www.openarchives.org/OAI/2.0/oai_dc/ http://www.openarchives.org/OAI/2.0/oai_dc.xsd";
+
+const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// Name tags and their MODS name type.
+const NAME_TAGS: [(&str, &str); 6] = [
+    ("100", "personal"),
+    ("110", "corporate"),
+    ("111", "conference"),
+    ("700", "personal"),
+    ("710", "corporate"),
+    ("711", "conference"),
+];
+
+/// Subject tags and the subfield codes forming the main heading.
+const SUBJECT_TAGS: [(&str, &str); 8] = [
+    ("600", "abcdq"),
+    ("610", "abcd"),
+    ("611", "acdnq"),
+    ("630", "adfklmnoprst"),
+    ("648", "a"),
+    ("650", "abcd"),
+    ("651", "a"),
+    ("655", "a"),
+];
+
+/// Minimal builder for compact (unformatted) XML.
+struct XmlBuilder {
+    xml: String,
+}
+
+impl XmlBuilder {
+    fn new() -> Self {
+        XmlBuilder { xml: String::new() }
+    }
+
+    fn open(&mut self, name: &str, attrs: &[(&str, &str)]) {
+        self.xml += &format!("<{name}");
+        for (attr, value) in attrs {
+            self.xml += &format!(r#" {attr}="{}""#, escape_xml(value, true));
+        }
+        self.xml += ">";
+    }
+
+    fn close(&mut self, name: &str) {
+        self.xml += &format!("</{name}>");
+    }
+
+    /// Adds an element with text content.  Empty values are skipped.
+    fn element(&mut self, name: &str, attrs: &[(&str, &str)], value: &str) {
+        if value.is_empty() {
+            return;
+        }
+        self.open(name, attrs);
+        self.xml += &escape_xml(value, false);
+        self.close(name);
+    }
+}
+
+/// MODS typeOfResource / DC type for leader/06.
+fn type_of_resource(record: &Record) -> Option<&'static str> {
+    let t = match record.leader().as_bytes()[6] {
+        b'a' | b't' => "text",
+        b'c' | b'd' => "notated music",
+        b'e' | b'f' => "cartographic",
+        b'g' => "moving image",
+        b'i' => "sound recording-nonmusical",
+        b'j' => "sound recording-musical",
+        b'k' => "still image",
+        b'm' => "software, multimedia",
+        b'o' => "kit",
+        b'p' => "mixed material",
+        b'r' => "three dimensional object",
+        _ => return None,
+    };
+
+    Some(t)
+}
+
+/// Date 1 from the 008, if it's a usable year.
+fn fixed_field_year(record: &Record) -> Option<&str> {
+    record
+        .get_control_fields("008")
+        .first()
+        .and_then(|cf| cf.content().get(7..11))
+        .filter(|y| y.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Language code from 008/35-37.
+fn language_code(record: &Record) -> Option<&str> {
+    record
+        .get_control_fields("008")
+        .first()
+        .and_then(|cf| cf.content().get(35..38))
+        .filter(|l| l.chars().all(|c| c.is_ascii_lowercase()))
+}
+
+/// Publication statement, preferring 264 over 260.
+fn publication_field(record: &Record) -> Option<&Field> {
+    record
+        .get_fields("264")
+        .into_iter()
+        .find(|f| f.ind2() == "1")
+        .or_else(|| record.get_fields("260").into_iter().next())
+}
+
+/// Cleaned values for a subfield across all fields with the tag.
+fn values(record: &Record, tag: &str, code: &str) -> Vec<String> {
+    record
+        .get_field_values(tag, code)
+        .into_iter()
+        .map(clean_value)
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+/// Standard numbers from $a with any qualifying text removed.
+fn standard_numbers(record: &Record, tag: &str) -> Vec<String> {
+    record
+        .get_field_values(tag, "a")
+        .into_iter()
+        .filter_map(|v| v.split_whitespace().next())
+        .map(clean_value)
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+/// Title parts from the 245, with the nonfiling characters (ind2)
+/// split off the title proper.
+fn title_parts(field: &Field) -> (Option<String>, Option<String>) {
+    let Some(title) = join_subfields(field, "a") else {
+        return (None, None);
+    };
+
+    let skip = field.ind2().parse::<usize>().unwrap_or(0);
+
+    match title.char_indices().nth(skip) {
+        Some((idx, _)) if skip > 0 => (
+            Some(title[..idx].trim_end().to_string()),
+            Some(title[idx..].to_string()),
+        ),
+        _ => (None, Some(title)),
+    }
+}
+
+impl Record {
+    /// Create a MODS (v3) XML string from a bibliographic record.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 i 4500
+    /// =001 12345
+    /// =008 170101s2017    flu           000 0 spa d
+    /// =020 \\$a9781945540035 (hardcover)
+    /// =100 1\$aCala, Ismael,$eauthor.
+    /// =245 14$aThe awakening :$binspirations /$cIsmael Cala.
+    /// =264 \1$aMiami, FL :$bAguilar,$c2017.
+    /// =300 \\$a195 pages ;$c22 cm.
+    /// =650 \0$aSelf-actualization (Psychology)$vJuvenile literature."#
+    /// ).unwrap();
+    ///
+    /// let mods = record.to_mods_xml();
+    ///
+    /// assert!(mods.starts_with(r#"<mods xmlns="http://www.loc.gov/mods/v3""#));
+    /// assert!(mods.contains(
+    ///     "<titleInfo><nonSort>The</nonSort><title>awakening</title><subTitle>inspirations</subTitle></titleInfo>"
+    /// ));
+    /// assert!(mods.contains(
+    ///     r#"<name type="personal" usage="primary"><namePart>Cala, Ismael</namePart><role><roleTerm type="text">author</roleTerm></role></name>"#
+    /// ));
+    /// assert!(mods.contains("<typeOfResource>text</typeOfResource>"));
+    /// assert!(mods.contains("<publisher>Aguilar</publisher><dateIssued>2017</dateIssued>"));
+    /// assert!(mods.contains(r#"<languageTerm authority="iso639-2b" type="code">spa</languageTerm>"#));
+    /// assert!(mods.contains(
+    ///     "<subject><topic>Self-actualization (Psychology)</topic><genre>Juvenile literature</genre></subject>"
+    /// ));
+    /// assert!(mods.contains(r#"<identifier type="isbn">9781945540035</identifier>"#));
+    /// assert!(mods.contains("<recordIdentifier>12345</recordIdentifier>"));
+    /// ```
+    pub fn to_mods_xml(&self) -> String {
+        let mut b = XmlBuilder::new();
+
+        b.open(
+            "mods",
+            &[
+                ("xmlns", MODS_NAMESPACE),
+                ("xmlns:xsi", XSI_NAMESPACE),
+                ("xsi:schemaLocation", MODS_SCHEMA_LOCATION),
+                ("version", "3.8"),
+            ],
+        );
+
+        if let Some(field) = self.get_fields("245").first() {
+            let (non_sort, title) = title_parts(field);
+
+            b.open("titleInfo", &[]);
+            b.element("nonSort", &[], non_sort.as_deref().unwrap_or(""));
+            b.element("title", &[], title.as_deref().unwrap_or(""));
+            for (code, element) in [("b", "subTitle"), ("n", "partNumber"), ("p", "partName")] {
+                for value in field.get_subfields(code) {
+                    b.element(element, &[], &clean_value(value.content()));
+                }
+            }
+            b.close("titleInfo");
+        }
+
+        for field in self.fields() {
+            let Some((_, name_type)) = NAME_TAGS.iter().find(|(t, _)| *t == field.tag()) else {
+                continue;
+            };
+
+            let is_meeting = *name_type == "conference";
+            let name_codes = if is_meeting { "acdnq" } else { "abcdq" };
+            let relator_codes = if is_meeting { "j4" } else { "e4" };
+
+            let Some(name) = join_subfields(field, name_codes) else {
+                continue;
+            };
+
+            if field.tag().starts_with('1') {
+                b.open("name", &[("type", name_type), ("usage", "primary")]);
+            } else {
+                b.open("name", &[("type", name_type)]);
+            }
+
+            b.element("namePart", &[], &name);
+
+            for sf in field.subfields() {
+                if !relator_codes.contains(sf.code()) {
+                    continue;
+                }
+
+                let term_type = if sf.code() == "4" { "code" } else { "text" };
+                let role = clean_value(sf.content());
+
+                if !role.is_empty() {
+                    b.open("role", &[]);
+                    b.element("roleTerm", &[("type", term_type)], &role);
+                    b.close("role");
+                }
+            }
+
+            b.close("name");
+        }
+
+        if let Some(t) = type_of_resource(self) {
+            b.element("typeOfResource", &[], t);
+        }
+
+        for genre in values(self, "655", "a") {
+            b.element("genre", &[], &genre);
+        }
+
+        let publication = publication_field(self);
+        let edition = self
+            .get_fields("250")
+            .first()
+            .and_then(|f| join_subfields(f, "ab"));
+
+        if publication.is_some() || edition.is_some() {
+            b.open("originInfo", &[]);
+
+            if let Some(field) = publication {
+                for sf in field.get_subfields("a") {
+                    b.open("place", &[]);
+                    b.element("placeTerm", &[("type", "text")], &clean_value(sf.content()));
+                    b.close("place");
+                }
+                for sf in field.get_subfields("b") {
+                    b.element("publisher", &[], &clean_value(sf.content()));
+                }
+                for sf in field.get_subfields("c") {
+                    b.element("dateIssued", &[], &clean_value(sf.content()));
+                }
+            }
+
+            if let Some(year) = fixed_field_year(self) {
+                b.element(
+                    "dateIssued",
+                    &[("encoding", "marc"), ("keyDate", "yes")],
+                    year,
+                );
+            }
+
+            b.element("edition", &[], edition.as_deref().unwrap_or(""));
+            b.close("originInfo");
+        }
+
+        if let Some(lang) = language_code(self) {
+            b.open("language", &[]);
+            b.element(
+                "languageTerm",
+                &[("authority", "iso639-2b"), ("type", "code")],
+                lang,
+            );
+            b.close("language");
+        }
+
+        if let Some(extent) = self
+            .get_fields("300")
+            .first()
+            .and_then(|f| join_subfields(f, "abce"))
+        {
+            b.open("physicalDescription", &[]);
+            b.element("extent", &[], &extent);
+            b.close("physicalDescription");
+        }
+
+        for value in values(self, "520", "a") {
+            b.element("abstract", &[], &value);
+        }
+
+        for value in values(self, "505", "a") {
+            b.element("tableOfContents", &[], &value);
+        }
+
+        for value in values(self, "500", "a") {
+            b.element("note", &[], &value);
+        }
+
+        for field in self.fields() {
+            let Some((_, codes)) = SUBJECT_TAGS.iter().find(|(t, _)| *t == field.tag()) else {
+                continue;
+            };
+
+            let Some(heading) = join_subfields(field, codes) else {
+                continue;
+            };
+
+            b.open("subject", &[]);
+
+            match field.tag() {
+                "600" | "610" | "611" => {
+                    b.open("name", &[]);
+                    b.element("namePart", &[], &heading);
+                    b.close("name");
+                }
+                "630" => {
+                    b.open("titleInfo", &[]);
+                    b.element("title", &[], &heading);
+                    b.close("titleInfo");
+                }
+                "648" => b.element("temporal", &[], &heading),
+                "651" => b.element("geographic", &[], &heading),
+                "655" => b.element("genre", &[], &heading),
+                _ => b.element("topic", &[], &heading),
+            }
+
+            for sf in field.subfields() {
+                let element = match sf.code() {
+                    "v" => "genre",
+                    "x" => "topic",
+                    "y" => "temporal",
+                    "z" => "geographic",
+                    _ => continue,
+                };
+                b.element(element, &[], &clean_value(sf.content()));
+            }
+
+            b.close("subject");
+        }
+
+        for (tag, authority) in [("050", "lcc"), ("082", "ddc")] {
+            if let Some(class) = self
+                .get_fields(tag)
+                .first()
+                .and_then(|f| join_subfields(f, "ab"))
+            {
+                b.element("classification", &[("authority", authority)], &class);
+            }
+        }
+
+        for field in self.extract_fields("490:830") {
+            let codes = if field.tag() == "490" { "av" } else { "anp" };
+            if let Some(series) = join_subfields(field, codes) {
+                b.open("relatedItem", &[("type", "series")]);
+                b.open("titleInfo", &[]);
+                b.element("title", &[], &series);
+                b.close("titleInfo");
+                b.close("relatedItem");
+            }
+        }
+
+        for (tag, id_type) in [("020", "isbn"), ("022", "issn"), ("010", "lccn")] {
+            for value in standard_numbers(self, tag) {
+                b.element("identifier", &[("type", id_type)], &value);
+            }
+        }
+
+        for value in self.get_field_values("856", "u") {
+            b.open("location", &[]);
+            b.element("url", &[], value.trim());
+            b.close("location");
+        }
+
+        b.open("recordInfo", &[]);
+        if let Some(source) = self.get_control_fields("003").first() {
+            b.element("recordContentSource", &[], source.content().trim());
+        }
+        if let Some(id) = self.get_control_fields("001").first() {
+            b.element("recordIdentifier", &[], id.content().trim());
+        }
+        b.element("recordOrigin", &[], "Converted from MARCXML to MODS");
+        b.close("recordInfo");
+
+        b.close("mods");
+
+        b.xml
+    }
+
+    /// Create an OAI Dublin Core (oai_dc) XML string from a
+    /// bibliographic record.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 i 4500
+    /// =008 170101s2017    flu           000 0 spa d
+    /// =020 \\$a9781945540035
+    /// =100 1\$aCala, Ismael,$eauthor.
+    /// =245 10$aDespierta con Cala :$binspiraciones /$cIsmael Cala.
+    /// =264 \1$aMiami, FL :$bAguilar,$c2017.
+    /// =650 \0$aSelf-actualization (Psychology)$vJuvenile literature."#
+    /// ).unwrap();
+    ///
+    /// let dc = record.to_dublin_core();
+    ///
+    /// assert!(dc.starts_with("<oai_dc:dc "));
+    /// assert!(dc.contains("<dc:title>Despierta con Cala : inspiraciones</dc:title>"));
+    /// assert!(dc.contains("<dc:creator>Cala, Ismael</dc:creator>"));
+    /// assert!(dc.contains("<dc:type>text</dc:type>"));
+    /// assert!(dc.contains("<dc:publisher>Aguilar</dc:publisher>"));
+    /// assert!(dc.contains("<dc:date>2017</dc:date>"));
+    /// assert!(dc.contains("<dc:language>spa</dc:language>"));
+    /// assert!(dc.contains(
+    ///     "<dc:subject>Self-actualization (Psychology) -- Juvenile literature</dc:subject>"
+    /// ));
+    /// assert!(dc.contains("<dc:identifier>URN:ISBN:9781945540035</dc:identifier>"));
+    /// ```
+    pub fn to_dublin_core(&self) -> String {
+        let mut b = XmlBuilder::new();
+        let view = self.brief_view();
+
+        b.open(
+            "oai_dc:dc",
+            &[
+                ("xmlns:oai_dc", OAI_DC_NAMESPACE),
+                ("xmlns:dc", DC_NAMESPACE),
+                ("xmlns:xsi", XSI_NAMESPACE),
+                ("xsi:schemaLocation", OAI_DC_SCHEMA_LOCATION),
+            ],
+        );
+
+        b.element("dc:title", &[], view.title().unwrap_or(""));
+
+        for author in view.authors() {
+            let element = if author.is_main_entry() {
+                "dc:creator"
+            } else {
+                "dc:contributor"
+            };
+            b.element(element, &[], author.name());
+        }
+
+        if let Some(t) = type_of_resource(self) {
+            b.element("dc:type", &[], t);
+        }
+
+        for genre in values(self, "655", "a") {
+            b.element("dc:type", &[], &genre);
+        }
+
+        if let Some(field) = publication_field(self) {
+            for sf in field.get_subfields("b") {
+                b.element("dc:publisher", &[], &clean_value(sf.content()));
+            }
+            for sf in field.get_subfields("c") {
+                b.element("dc:date", &[], &clean_value(sf.content()));
+            }
+        }
+
+        if let Some(lang) = language_code(self) {
+            b.element("dc:language", &[], lang);
+        }
+
+        b.element("dc:format", &[], view.physical_description().unwrap_or(""));
+
+        for tag in ["500", "505", "520"] {
+            for value in values(self, tag, "a") {
+                b.element("dc:description", &[], &value);
+            }
+        }
+
+        for subject in view.subjects() {
+            b.element("dc:subject", &[], subject);
+        }
+
+        for value in values(self, "651", "a") {
+            b.element("dc:coverage", &[], &value);
+        }
+
+        for series in view.series() {
+            b.element("dc:relation", &[], series);
+        }
+
+        for isbn in view.isbns() {
+            b.element("dc:identifier", &[], &format!("URN:ISBN:{isbn}"));
+        }
+
+        for issn in view.issns() {
+            b.element("dc:identifier", &[], &format!("URN:ISSN:{issn}"));
+        }
+
+        for value in self.get_field_values("856", "u") {
+            b.element("dc:identifier", &[], value.trim());
+        }
+
+        for value in values(self, "506", "a")
+            .into_iter()
+            .chain(values(self, "540", "a"))
+        {
+            b.element("dc:rights", &[], &value);
+        }
+
+        b.close("oai_dc:dc");
+
+        b.xml
+    }
+}
@@ -0,0 +1,350 @@
+//! Compare two records and report what changed.
+//!
+//! Fields are matched by tag.  Identical fields are paired first, then
+//! each remaining field is paired with the most similar remaining field
+//! of the same tag.  Paired fields which differ are reported as
+//! modified, with subfield-level changes.  Anything left over was
+//! added or removed.
+use super::breaker::escape_to_breaker;
+use super::Controlfield;
+use super::Field;
+use super::Record;
+use super::Subfield;
+use std::fmt;
+
+/// A change to a single subfield within a modified field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubfieldChange {
+    Added(Subfield),
+    Removed(Subfield),
+    Modified { old: Subfield, new: Subfield },
+}
+
+/// A change to a record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Leader {
+        old: String,
+        new: String,
+    },
+    ControlFieldAdded(Controlfield),
+    ControlFieldRemoved(Controlfield),
+    ControlFieldModified {
+        old: Controlfield,
+        new: Controlfield,
+    },
+    FieldAdded(Field),
+    FieldRemoved(Field),
+    FieldModified {
+        old: Field,
+        new: Field,
+        /// Subfield changes.  Indicator changes are visible by
+        /// comparing the old and new fields.
+        subfields: Vec<SubfieldChange>,
+    },
+}
+
+impl Change {
+    /// Tag of the changed field or "LDR" for the leader.
+    pub fn tag(&self) -> &str {
+        match self {
+            Change::Leader { .. } => "LDR",
+            Change::ControlFieldAdded(f) | Change::ControlFieldRemoved(f) => f.tag(),
+            Change::ControlFieldModified { new, .. } => new.tag(),
+            Change::FieldAdded(f) | Change::FieldRemoved(f) => f.tag(),
+            Change::FieldModified { new, .. } => new.tag(),
+        }
+    }
+
+    /// Breaker-style lines describing the change, prefixed with
+    /// "-" for removed content and "+" for added content.
+    fn to_breaker_lines(&self) -> Vec<String> {
+        match self {
+            Change::Leader { old, new } => vec![
+                format!("-=LDR {}", escape_to_breaker(old)),
+                format!("+=LDR {}", escape_to_breaker(new)),
+            ],
+            Change::ControlFieldAdded(f) => vec![format!("+{}", f.to_breaker())],
+            Change::ControlFieldRemoved(f) => vec![format!("-{}", f.to_breaker())],
+            Change::ControlFieldModified { old, new } => vec![
+                format!("-{}", old.to_breaker()),
+                format!("+{}", new.to_breaker()),
+            ],
+            Change::FieldAdded(f) => vec![format!("+{}", f.to_breaker())],
+            Change::FieldRemoved(f) => vec![format!("-{}", f.to_breaker())],
+            Change::FieldModified { old, new, .. } => vec![
+                format!("-{}", old.to_breaker()),
+                format!("+{}", new.to_breaker()),
+            ],
+        }
+    }
+}
+
+/// The set of changes needed to turn one record into another.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RecordDiff {
+    changes: Vec<Change>,
+}
+
+impl RecordDiff {
+    pub fn changes(&self) -> &Vec<Change> {
+        &self.changes
+    }
+
+    /// True if the records are equivalent.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Breaker-style rendering of the changes.
+    ///
+    /// Removed lines are prefixed with "-" and added lines with "+".
+    /// A modified field is displayed as its old version followed by
+    /// its new version.
+    pub fn to_breaker(&self) -> String {
+        self.changes
+            .iter()
+            .flat_map(|c| c.to_breaker_lines())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl fmt::Display for RecordDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_breaker())
+    }
+}
+
+/// Length of the longest common subsequence of two subfield lists.
+fn lcs_table(old: &[Subfield], new: &[Subfield]) -> Vec<Vec<usize>> {
+    let mut table = vec![vec![0; new.len() + 1]; old.len() + 1];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            table[i][j] = if old[i] == new[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    table
+}
+
+/// Number of subfields two fields have in common, in order.
+fn similarity(old: &Field, new: &Field) -> usize {
+    lcs_table(old.subfields(), new.subfields())[0][0]
+}
+
+/// Subfield-level changes between two fields.
+fn diff_subfields(old: &[Subfield], new: &[Subfield]) -> Vec<SubfieldChange> {
+    let table = lcs_table(old, new);
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || table[i][j + 1] >= table[i + 1][j]) {
+            changes.push(SubfieldChange::Added(new[j].clone()));
+            j += 1;
+        } else {
+            changes.push(SubfieldChange::Removed(old[i].clone()));
+            i += 1;
+        }
+    }
+
+    // A removal and an addition of the same subfield code side by side
+    // is a modification.
+    let mut merged: Vec<SubfieldChange> = Vec::new();
+
+    for change in changes {
+        if let Some(prev) = merged.pop() {
+            match (prev, change) {
+                (SubfieldChange::Removed(o), SubfieldChange::Added(n))
+                | (SubfieldChange::Added(n), SubfieldChange::Removed(o))
+                    if o.code() == n.code() =>
+                {
+                    merged.push(SubfieldChange::Modified { old: o, new: n });
+                }
+                (prev, change) => {
+                    merged.push(prev);
+                    merged.push(change);
+                }
+            }
+        } else {
+            merged.push(change);
+        }
+    }
+
+    merged
+}
+
+/// Sorted list of unique tags appearing in either list.
+fn tags<'a>(a: impl Iterator<Item = &'a str>, b: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut tags: Vec<&str> = a.chain(b).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Removes items from both lists which have an identical counterpart.
+fn remove_identical<T: PartialEq>(old: &mut Vec<&T>, new: &mut Vec<&T>) {
+    new.retain(|n| match old.iter().position(|o| o == n) {
+        Some(pos) => {
+            old.remove(pos);
+            false
+        }
+        None => true,
+    });
+}
+
+fn diff_control_fields(old: &Record, new: &Record, changes: &mut Vec<Change>) {
+    let old_tags = old.control_fields().iter().map(|f| f.tag());
+    let new_tags = new.control_fields().iter().map(|f| f.tag());
+
+    for tag in tags(old_tags, new_tags) {
+        let mut old_fields = old.get_control_fields(tag);
+        let mut new_fields = new.get_control_fields(tag);
+
+        remove_identical(&mut old_fields, &mut new_fields);
+
+        // Control fields have no subfields to compare, so any
+        // remaining fields are paired by position.
+        let mut old_iter = old_fields.into_iter();
+        let mut new_iter = new_fields.into_iter();
+
+        loop {
+            match (old_iter.next(), new_iter.next()) {
+                (Some(o), Some(n)) => changes.push(Change::ControlFieldModified {
+                    old: o.clone(),
+                    new: n.clone(),
+                }),
+                (Some(o), None) => changes.push(Change::ControlFieldRemoved(o.clone())),
+                (None, Some(n)) => changes.push(Change::ControlFieldAdded(n.clone())),
+                (None, None) => break,
+            }
+        }
+    }
+}
+
+fn diff_data_fields(old: &Record, new: &Record, changes: &mut Vec<Change>) {
+    let old_tags = old.fields().iter().map(|f| f.tag());
+    let new_tags = new.fields().iter().map(|f| f.tag());
+
+    for tag in tags(old_tags, new_tags) {
+        let mut old_fields = old.get_fields(tag);
+        let mut new_fields = new.get_fields(tag);
+
+        remove_identical(&mut old_fields, &mut new_fields);
+
+        let mut modified = Vec::new();
+        let mut added = Vec::new();
+
+        // If exactly one field of this tag changed, it was modified,
+        // regardless of how little it resembles the original.
+        let pair_any = old_fields.len() == 1 && new_fields.len() == 1;
+
+        for n in new_fields {
+            let best = old_fields
+                .iter()
+                .enumerate()
+                .map(|(idx, o)| (idx, similarity(o, n)))
+                .filter(|(_, score)| pair_any || *score > 0)
+                // Ties go to the earliest field.
+                .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)));
+
+            match best {
+                Some((idx, _)) => {
+                    let o = old_fields.remove(idx);
+                    modified.push(Change::FieldModified {
+                        old: o.clone(),
+                        new: n.clone(),
+                        subfields: diff_subfields(o.subfields(), n.subfields()),
+                    });
+                }
+                None => added.push(Change::FieldAdded(n.clone())),
+            }
+        }
+
+        changes.extend(
+            old_fields
+                .into_iter()
+                .map(|o| Change::FieldRemoved(o.clone())),
+        );
+        changes.extend(modified);
+        changes.extend(added);
+    }
+}
+
+impl Record {
+    /// Compare this record to another record.
+    ///
+    /// The result describes the changes needed to turn this record
+    /// into `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::diff::{Change, SubfieldChange};
+    ///
+    /// let old = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =001 123
+    /// =245 10$aOld title /$cSmith.
+    /// =500 \\$aA note."#
+    /// ).unwrap();
+    ///
+    /// let new = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =001 123
+    /// =245 10$aNew title /$cSmith.
+    /// =650 \0$aCats."#
+    /// ).unwrap();
+    ///
+    /// let diff = old.diff(&new);
+    ///
+    /// assert_eq!(diff.changes().len(), 3);
+    ///
+    /// if let Change::FieldModified { subfields, .. } = &diff.changes()[0] {
+    ///     assert_eq!(subfields.len(), 1);
+    ///     if let SubfieldChange::Modified { old, new } = &subfields[0] {
+    ///         assert_eq!(old.content(), "Old title /");
+    ///         assert_eq!(new.content(), "New title /");
+    ///     } else {
+    ///         panic!("Subfield should be modified");
+    ///     }
+    /// } else {
+    ///     panic!("245 should be modified");
+    /// }
+    ///
+    /// assert_eq!(
+    ///     diff.to_breaker(),
+    ///     r#"-=245 10$aOld title /$cSmith.
+    /// +=245 10$aNew title /$cSmith.
+    /// -=500 \\$aA note.
+    /// +=650 \0$aCats."#
+    /// );
+    ///
+    /// assert!(old.diff(&old).is_empty());
+    /// ```
+    pub fn diff(&self, other: &Record) -> RecordDiff {
+        let mut changes = Vec::new();
+
+        if self.leader() != other.leader() {
+            changes.push(Change::Leader {
+                old: self.leader().to_string(),
+                new: other.leader().to_string(),
+            });
+        }
+
+        diff_control_fields(self, other, &mut changes);
+        diff_data_fields(self, other, &mut changes);
+
+        RecordDiff { changes }
+    }
+}
@@ -0,0 +1,374 @@
+//! Brief display summaries of bibliographic records.
+//!
+//! Extracts the values typically shown in an OPAC brief view, receipt
+//! slip, or plain-text (SUTRS) rendering of a record.  All values are
+//! NFC-normalized and stripped of trailing ISBD punctuation.
+use super::Field;
+use super::Record;
+use std::fmt;
+use unicode_normalization::UnicodeNormalization;
+
+/// Subfield codes whose content contributes to a subject heading.
+const SUBJECT_SUBFIELDS: &str = "abcdfgklmnopqrstvxyz";
+
+/// Subfield codes which represent subject subdivisions.
+const SUBJECT_SUBDIVISIONS: &str = "vxyz";
+
+/// Subject heading separator.
+const SUBJECT_SEPARATOR: &str = " -- ";
+
+/// Apply NFC normalization, collapse whitespace, and remove trailing
+/// ISBD punctuation.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::display::clean_value;
+/// assert_eq!(clean_value("Despierta con Cala :"), "Despierta con Cala");
+/// assert_eq!(clean_value("  Cala,   Ismael. "), "Cala, Ismael");
+/// assert_eq!(clean_value("Miami, FL ;"), "Miami, FL");
+/// // Abbreviations and initials retain their periods.
+/// assert_eq!(clean_value("23 cm."), "23 cm.");
+/// assert_eq!(clean_value("Smith, J."), "Smith, J.");
+/// ```
+pub fn clean_value(value: &str) -> String {
+    let value: String = value.nfc().collect();
+    let mut value = value.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    loop {
+        let trimmed = value.trim_end_matches([' ', '/', ':', ';', ',', '=']);
+
+        let trimmed = match trimmed.strip_suffix('.') {
+            // Retain the period on abbreviations and initials,
+            // e.g. "cm." and "J."
+            Some(t) if !ends_with_abbreviation(t) => t,
+            _ => trimmed,
+        };
+
+        if trimmed.len() == value.len() {
+            break;
+        }
+
+        value = trimmed.to_string();
+    }
+
+    value
+}
+
+/// True if the final word is short enough to be an abbreviation or initial.
+fn ends_with_abbreviation(value: &str) -> bool {
+    match value.rsplit(' ').next() {
+        Some(word) => {
+            let count = word.chars().count();
+            count == 1 || (count == 2 && word.chars().all(|c| c.is_lowercase()))
+        }
+        None => false,
+    }
+}
+
+/// Join the cleaned contents of the requested subfields with a space.
+pub(crate) fn join_subfields(field: &Field, codes: &str) -> Option<String> {
+    let parts: Vec<&str> = field
+        .subfields()
+        .iter()
+        .filter(|sf| codes.contains(sf.code()))
+        .map(|sf| sf.content().trim())
+        .filter(|c| !c.is_empty())
+        .collect();
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(clean_value(&parts.join(" ")))
+    }
+}
+
+/// A personal, corporate, or meeting name with its relator terms/codes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Author {
+    name: String,
+    relators: Vec<String>,
+    is_main_entry: bool,
+}
+
+impl Author {
+    /// Name of the agent, e.g. "Cala, Ismael"
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Relator terms and codes, e.g. "illustrator" or "ill"
+    pub fn relators(&self) -> &Vec<String> {
+        &self.relators
+    }
+
+    /// True if this name comes from a 1XX field.
+    pub fn is_main_entry(&self) -> bool {
+        self.is_main_entry
+    }
+
+    fn from_field(field: &Field) -> Option<Author> {
+        let is_meeting = field.tag().ends_with("11");
+
+        let name_codes = if is_meeting { "acdnq" } else { "abcdq" };
+        let relator_codes = if is_meeting { "j4" } else { "e4" };
+
+        let name = join_subfields(field, name_codes)?;
+
+        let relators = field
+            .subfields()
+            .iter()
+            .filter(|sf| relator_codes.contains(sf.code()))
+            .map(|sf| clean_value(sf.content()))
+            .filter(|r| !r.is_empty())
+            .collect();
+
+        Some(Author {
+            name,
+            relators,
+            is_main_entry: field.tag().starts_with('1'),
+        })
+    }
+}
+
+impl fmt::Display for Author {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.relators.is_empty() {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{} ({})", self.name, self.relators.join(", "))
+        }
+    }
+}
+
+/// Brief summary of a bibliographic record for display.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BriefView {
+    title: Option<String>,
+    authors: Vec<Author>,
+    edition: Option<String>,
+    imprint: Option<String>,
+    physical_description: Option<String>,
+    series: Vec<String>,
+    isbns: Vec<String>,
+    issns: Vec<String>,
+    subjects: Vec<String>,
+    material_types: Vec<String>,
+}
+
+impl BriefView {
+    /// Title proper with remainder of title, part number, and part name.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Main entry name (if any) followed by added entry names.
+    pub fn authors(&self) -> &Vec<Author> {
+        &self.authors
+    }
+
+    pub fn edition(&self) -> Option<&str> {
+        self.edition.as_deref()
+    }
+
+    /// Place, publisher, and date of publication.
+    pub fn imprint(&self) -> Option<&str> {
+        self.imprint.as_deref()
+    }
+
+    pub fn physical_description(&self) -> Option<&str> {
+        self.physical_description.as_deref()
+    }
+
+    pub fn series(&self) -> &Vec<String> {
+        &self.series
+    }
+
+    pub fn isbns(&self) -> &Vec<String> {
+        &self.isbns
+    }
+
+    pub fn issns(&self) -> &Vec<String> {
+        &self.issns
+    }
+
+    /// Subject headings with subdivisions separated by " -- ".
+    pub fn subjects(&self) -> &Vec<String> {
+        &self.subjects
+    }
+
+    /// Material type codes, e.g. "book" or "dvd".  See [`crate::format`].
+    pub fn material_types(&self) -> &Vec<String> {
+        &self.material_types
+    }
+
+    /// Build a brief view from a bibliographic record.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::display::BriefView;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=100 1\$aCala, Ismael.$eauthor.
+    /// =245 10$aDespierta con Cala :$binspiraciones para una vida en equilibrio /$cIsmael Cala.
+    /// =264 \1$aMiami, FL :$bAguilar,$c2017.
+    /// =650 \0$aSelf-actualization (Psychology)$vJuvenile literature."#
+    /// ).unwrap();
+    ///
+    /// let view = BriefView::from_record(&record);
+    ///
+    /// assert_eq!(view.title(), Some("Despierta con Cala : inspiraciones para una vida en equilibrio"));
+    /// assert_eq!(view.authors()[0].to_string(), "Cala, Ismael (author)");
+    /// assert_eq!(view.imprint(), Some("Miami, FL : Aguilar, 2017"));
+    /// assert_eq!(view.subjects()[0], "Self-actualization (Psychology) -- Juvenile literature");
+    /// ```
+    pub fn from_record(record: &Record) -> BriefView {
+        let mut view = BriefView {
+            title: record
+                .get_fields("245")
+                .first()
+                .and_then(|f| join_subfields(f, "abnp")),
+            edition: record
+                .get_fields("250")
+                .first()
+                .and_then(|f| join_subfields(f, "ab")),
+            physical_description: record
+                .get_fields("300")
+                .first()
+                .and_then(|f| join_subfields(f, "abce")),
+            ..Default::default()
+        };
+
+        for field in record.extract_fields("100:110:111:700:710:711") {
+            if let Some(author) = Author::from_field(field) {
+                view.authors.push(author);
+            }
+        }
+
+        // Prefer the 264 publication statement, falling back to 260.
+        let imprint = record
+            .get_fields("264")
+            .into_iter()
+            .find(|f| f.ind2() == "1")
+            .or_else(|| record.get_fields("260").into_iter().next());
+
+        view.imprint = imprint.and_then(|f| join_subfields(f, "abc"));
+
+        for field in record.extract_fields("490:800:810:811:830") {
+            let codes = if field.tag() == "490" { "av" } else { "atnpv" };
+            if let Some(series) = join_subfields(field, codes) {
+                if !view.series.contains(&series) {
+                    view.series.push(series);
+                }
+            }
+        }
+
+        view.material_types = record.material_types();
+
+        view.isbns = Self::standard_numbers(record, "020");
+        view.issns = Self::standard_numbers(record, "022");
+
+        for field in record.extract_fields("600:610:611:630:648:650:651:655") {
+            if let Some(subject) = Self::subject_heading(field) {
+                if !view.subjects.contains(&subject) {
+                    view.subjects.push(subject);
+                }
+            }
+        }
+
+        view
+    }
+
+    /// Standard numbers from $a with any qualifying text removed.
+    fn standard_numbers(record: &Record, tag: &str) -> Vec<String> {
+        let mut numbers = Vec::new();
+
+        for value in record.get_field_values(tag, "a") {
+            if let Some(num) = value.split_whitespace().next() {
+                let num = clean_value(num);
+                if !num.is_empty() && !numbers.contains(&num) {
+                    numbers.push(num);
+                }
+            }
+        }
+
+        numbers
+    }
+
+    fn subject_heading(field: &Field) -> Option<String> {
+        let mut heading = String::new();
+
+        for sf in field.subfields() {
+            if !SUBJECT_SUBFIELDS.contains(sf.code()) {
+                continue;
+            }
+
+            let content = clean_value(sf.content());
+            if content.is_empty() {
+                continue;
+            }
+
+            if !heading.is_empty() {
+                if SUBJECT_SUBDIVISIONS.contains(sf.code()) {
+                    heading += SUBJECT_SEPARATOR;
+                } else {
+                    heading += " ";
+                }
+            }
+
+            heading += &content;
+        }
+
+        if heading.is_empty() {
+            None
+        } else {
+            Some(heading)
+        }
+    }
+}
+
+/// Renders the brief view as labeled lines of plain text.
+impl fmt::Display for BriefView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(v) = self.title() {
+            writeln!(f, "Title: {v}")?;
+        }
+        for author in self.authors() {
+            writeln!(f, "Author: {author}")?;
+        }
+        if let Some(v) = self.edition() {
+            writeln!(f, "Edition: {v}")?;
+        }
+        if let Some(v) = self.imprint() {
+            writeln!(f, "Published: {v}")?;
+        }
+        if let Some(v) = self.physical_description() {
+            writeln!(f, "Description: {v}")?;
+        }
+        for v in self.series() {
+            writeln!(f, "Series: {v}")?;
+        }
+        if !self.material_types().is_empty() {
+            writeln!(f, "Format: {}", self.material_types().join(", "))?;
+        }
+        for v in self.isbns() {
+            writeln!(f, "ISBN: {v}")?;
+        }
+        for v in self.issns() {
+            writeln!(f, "ISSN: {v}")?;
+        }
+        for v in self.subjects() {
+            writeln!(f, "Subject: {v}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Record {
+    /// Shortcut for [`BriefView::from_record`].
+    pub fn brief_view(&self) -> BriefView {
+        BriefView::from_record(self)
+    }
+}
@@ -0,0 +1,710 @@
+//! Declarative batch edits, in the style of MarcEdit's batch tasks.
+//!
+//! An [`EditTasks`] list is applied in order to each record, either
+//! directly via [`EditTasks::apply`] or to a stream of records read
+//! from any record iterator via [`EditTasks::edit_records`].
+//!
+//! # Task syntax
+//!
+//! Tasks may be parsed from text containing one task per line.  Blank
+//! lines and lines starting with "#" are ignored.
+//!
+//! ```text
+//! # Add a field, using MARC Breaker syntax.
+//! add =949 \\$aProcessed
+//!
+//! # Delete fields matching a tag spec, e.g. "9xx".
+//! delete 9xx
+//!
+//! # Copy or move subfields into new fields, or into the same field
+//! # when the tags match.
+//! copy 020$a 024$a
+//! move 245$h 245$k
+//!
+//! # Add text to the start or end of subfield values.
+//! prepend 245$a "The "
+//! append 500$a .
+//! ```
+//!
+//! Tasks other than "add" may be limited to fields matching every
+//! condition which follows the task:
+//!
+//! * `ind1=<value>` / `ind2=<value>` -- Indicator equals the value.
+//!   Use "\\" for a blank indicator.
+//! * `$<code>=<value>` -- The field has a subfield with exactly this
+//!   value.
+//! * `$<code>~<value>` -- The field has a subfield containing this
+//!   value.
+//!
+//! ```text
+//! delete 650 ind2=7 $2=fast
+//! delete 856 $u~ebrary.com
+//! ```
+//!
+//! Values containing spaces may be wrapped in double quotes.  Use
+//! `\"` for a literal quote and `\\` for a literal backslash within
+//! quotes.
+use super::Controlfield;
+use super::Field;
+use super::Record;
+use super::Subfield;
+
+/// Field selection condition for a task.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Ind1(String),
+    Ind2(String),
+    /// Subfield code and exact value.
+    SubfieldEquals(String, String),
+    /// Subfield code and substring.
+    SubfieldContains(String, String),
+}
+
+impl Condition {
+    /// Parse a condition, e.g. "ind2=7" or "$2=fast".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::edit::Condition;
+    ///
+    /// assert_eq!(Condition::parse("ind1=\\").unwrap(), Condition::Ind1(" ".to_string()));
+    /// assert_eq!(
+    ///     Condition::parse("$u~ebrary").unwrap(),
+    ///     Condition::SubfieldContains("u".to_string(), "ebrary".to_string())
+    /// );
+    ///
+    /// assert!(Condition::parse("ind3=1").is_err());
+    /// assert!(Condition::parse("$ab=1").is_err());
+    /// ```
+    pub fn parse(text: &str) -> Result<Condition, String> {
+        let invalid = || format!("Invalid edit condition: {text}");
+
+        if let Some(code_value) = text.strip_prefix('$') {
+            let (code, value, exact) =
+                match (code_value.split_once('='), code_value.split_once('~')) {
+                    (Some((c, v)), None) => (c, v, true),
+                    (None, Some((c, v))) => (c, v, false),
+                    // Use whichever operator comes first.
+                    (Some((ce, ve)), Some((cc, vc))) => {
+                        if ce.len() < cc.len() {
+                            (ce, ve, true)
+                        } else {
+                            (cc, vc, false)
+                        }
+                    }
+                    (None, None) => return Err(invalid()),
+                };
+
+            if code.len() != 1 {
+                return Err(invalid());
+            }
+
+            return Ok(if exact {
+                Condition::SubfieldEquals(code.to_string(), value.to_string())
+            } else {
+                Condition::SubfieldContains(code.to_string(), value.to_string())
+            });
+        }
+
+        let (name, value) = text.split_once('=').ok_or_else(invalid)?;
+
+        let value = if value == "\\" { " " } else { value };
+
+        if value.len() != 1 {
+            return Err(invalid());
+        }
+
+        match name {
+            "ind1" => Ok(Condition::Ind1(value.to_string())),
+            "ind2" => Ok(Condition::Ind2(value.to_string())),
+            _ => Err(invalid()),
+        }
+    }
+
+    pub fn matches(&self, field: &Field) -> bool {
+        match self {
+            Condition::Ind1(v) => field.ind1() == v,
+            Condition::Ind2(v) => field.ind2() == v,
+            Condition::SubfieldEquals(code, v) => {
+                field.get_subfields(code).iter().any(|sf| sf.content() == v)
+            }
+            Condition::SubfieldContains(code, v) => field
+                .get_subfields(code)
+                .iter()
+                .any(|sf| sf.content().contains(v.as_str())),
+        }
+    }
+}
+
+/// A tag spec plus subfield code, e.g. "650$a" or "6xx$a".
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubfieldTarget {
+    pub spec: String,
+    pub code: String,
+}
+
+impl SubfieldTarget {
+    pub fn parse(text: &str) -> Result<SubfieldTarget, String> {
+        match text.split_once('$') {
+            Some((spec, code)) if spec.len() == 3 && code.len() == 1 => Ok(SubfieldTarget {
+                spec: spec.to_string(),
+                code: code.to_string(),
+            }),
+            _ => Err(format!("Invalid subfield target: {text}")),
+        }
+    }
+}
+
+/// A single batch edit task.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditTask {
+    AddControlField(Controlfield),
+    AddField(Field),
+    /// Delete fields matching the tag spec and conditions.
+    DeleteFields {
+        spec: String,
+        conditions: Vec<Condition>,
+    },
+    /// Copy subfield values from matching fields.
+    ///
+    /// When the source and target tags are the same, values are added
+    /// to the source field with the target code.  Otherwise, each
+    /// source field's values are added to a new field with blank
+    /// indicators, inserted in tag order.
+    CopySubfields {
+        from: SubfieldTarget,
+        to: SubfieldTarget,
+        conditions: Vec<Condition>,
+    },
+    /// Like [`EditTask::CopySubfields`], but the source subfields are
+    /// removed.  Source fields left with no subfields are removed.
+    MoveSubfields {
+        from: SubfieldTarget,
+        to: SubfieldTarget,
+        conditions: Vec<Condition>,
+    },
+    /// Add text to the start of matching subfield values.
+    Prepend {
+        target: SubfieldTarget,
+        text: String,
+        conditions: Vec<Condition>,
+    },
+    /// Add text to the end of matching subfield values.
+    Append {
+        target: SubfieldTarget,
+        text: String,
+        conditions: Vec<Condition>,
+    },
+}
+
+impl EditTask {
+    /// Parse a single task.  See the module documentation for syntax.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::edit::EditTask;
+    ///
+    /// assert!(EditTask::parse("delete 9xx").is_ok());
+    /// assert!(EditTask::parse(r#"prepend 245$a "The ""#).is_ok());
+    /// assert!(EditTask::parse("copy 020$a 02x$a").is_err());
+    /// assert!(EditTask::parse("add =LDR 00000nam a2200000 a 4500").is_err());
+    /// assert!(EditTask::parse("rename 245").is_err());
+    /// ```
+    pub fn parse(line: &str) -> Result<EditTask, String> {
+        let line = line.trim();
+
+        let (action, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+        if action == "add" {
+            return EditTask::parse_add(rest.trim());
+        }
+
+        let tokens = tokenize(rest)?;
+        let mut tokens = tokens.iter().map(|t| t.as_str());
+
+        let mut next = |what: &str| {
+            tokens
+                .next()
+                .map(|t| t.to_string())
+                .ok_or_else(|| format!("Edit task requires {what}: {line}"))
+        };
+
+        let task = match action {
+            "delete" => {
+                let spec = next("a tag spec")?;
+                if spec.len() != 3 {
+                    return Err(format!("Invalid tag spec: {spec}"));
+                }
+                EditTask::DeleteFields {
+                    spec,
+                    conditions: Vec::new(),
+                }
+            }
+            "copy" | "move" => {
+                let from = SubfieldTarget::parse(&next("a source")?)?;
+                let to = SubfieldTarget::parse(&next("a target")?)?;
+
+                // New fields need a real tag.
+                Field::new(&to.spec)?;
+                if to.spec.contains(['x', 'X']) {
+                    return Err(format!("Invalid target tag: {}", to.spec));
+                }
+
+                if action == "copy" {
+                    EditTask::CopySubfields {
+                        from,
+                        to,
+                        conditions: Vec::new(),
+                    }
+                } else {
+                    EditTask::MoveSubfields {
+                        from,
+                        to,
+                        conditions: Vec::new(),
+                    }
+                }
+            }
+            "prepend" | "append" => {
+                let target = SubfieldTarget::parse(&next("a target")?)?;
+                let text = next("text")?;
+
+                if action == "prepend" {
+                    EditTask::Prepend {
+                        target,
+                        text,
+                        conditions: Vec::new(),
+                    }
+                } else {
+                    EditTask::Append {
+                        target,
+                        text,
+                        conditions: Vec::new(),
+                    }
+                }
+            }
+            _ => return Err(format!("Invalid edit task: {line}")),
+        };
+
+        let conditions = tokens
+            .map(Condition::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(task.with_conditions(conditions))
+    }
+
+    fn parse_add(breaker: &str) -> Result<EditTask, String> {
+        if !breaker.starts_with('=') || breaker.starts_with("=LDR") {
+            return Err(format!("Invalid field for add task: {breaker}"));
+        }
+
+        let mut record = Record::from_breaker(breaker)?;
+
+        if let Some(cf) = record.control_fields_mut().pop() {
+            return Ok(EditTask::AddControlField(cf));
+        }
+
+        record
+            .fields_mut()
+            .pop()
+            .map(EditTask::AddField)
+            .ok_or_else(|| format!("Invalid field for add task: {breaker}"))
+    }
+
+    fn with_conditions(mut self, conds: Vec<Condition>) -> EditTask {
+        match &mut self {
+            EditTask::AddControlField(_) | EditTask::AddField(_) => {}
+            EditTask::DeleteFields { conditions, .. }
+            | EditTask::CopySubfields { conditions, .. }
+            | EditTask::MoveSubfields { conditions, .. }
+            | EditTask::Prepend { conditions, .. }
+            | EditTask::Append { conditions, .. } => *conditions = conds,
+        }
+        self
+    }
+
+    /// Short description of the task, used as the rule name in a
+    /// record's change journal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::edit::EditTask;
+    ///
+    /// assert_eq!(EditTask::parse("delete 9xx $a=x").unwrap().label(), "delete 9xx");
+    /// assert_eq!(EditTask::parse("move 245$h 245$k").unwrap().label(), "move 245$h 245$k");
+    /// assert_eq!(EditTask::parse(r"add =949 \\$aX").unwrap().label(), "add 949");
+    /// ```
+    pub fn label(&self) -> String {
+        match self {
+            EditTask::AddControlField(cf) => format!("add {}", cf.tag()),
+            EditTask::AddField(field) => format!("add {}", field.tag()),
+            EditTask::DeleteFields { spec, .. } => format!("delete {spec}"),
+            EditTask::CopySubfields { from, to, .. } => {
+                format!("copy {}${} {}${}", from.spec, from.code, to.spec, to.code)
+            }
+            EditTask::MoveSubfields { from, to, .. } => {
+                format!("move {}${} {}${}", from.spec, from.code, to.spec, to.code)
+            }
+            EditTask::Prepend { target, .. } => {
+                format!("prepend {}${}", target.spec, target.code)
+            }
+            EditTask::Append { target, .. } => {
+                format!("append {}${}", target.spec, target.code)
+            }
+        }
+    }
+
+    /// Apply the task to a record.
+    ///
+    /// Returns the number of fields added, removed, or modified.
+    pub fn apply(&self, record: &mut Record) -> usize {
+        match self {
+            EditTask::AddControlField(cf) => {
+                record.insert_control_field(cf.clone());
+                1
+            }
+            EditTask::AddField(field) => {
+                record.insert_data_field(field.clone());
+                1
+            }
+            EditTask::DeleteFields { spec, conditions } => {
+                let before = record.fields().len();
+                record
+                    .fields_mut()
+                    .retain(|f| !selects(f, spec, conditions));
+                before - record.fields().len()
+            }
+            EditTask::CopySubfields {
+                from,
+                to,
+                conditions,
+            } => copy_subfields(record, from, to, conditions, false),
+            EditTask::MoveSubfields {
+                from,
+                to,
+                conditions,
+            } => copy_subfields(record, from, to, conditions, true),
+            EditTask::Prepend {
+                target,
+                text,
+                conditions,
+            } => edit_values(record, target, conditions, |v| format!("{text}{v}")),
+            EditTask::Append {
+                target,
+                text,
+                conditions,
+            } => edit_values(record, target, conditions, |v| format!("{v}{text}")),
+        }
+    }
+}
+
+/// True if the field matches the tag spec and every condition.
+fn selects(field: &Field, spec: &str, conditions: &[Condition]) -> bool {
+    field.matches_spec(spec) && conditions.iter().all(|c| c.matches(field))
+}
+
+fn copy_subfields(
+    record: &mut Record,
+    from: &SubfieldTarget,
+    to: &SubfieldTarget,
+    conditions: &[Condition],
+    remove: bool,
+) -> usize {
+    let mut changes = 0;
+    let mut new_fields = Vec::new();
+
+    for field in record.fields_mut().iter_mut() {
+        if !selects(field, &from.spec, conditions) || !field.has_subfield(&from.code) {
+            continue;
+        }
+
+        if field.tag() == to.spec {
+            changes += 1;
+
+            if remove {
+                for sf in field.get_subfields_mut(&from.code) {
+                    // Codes are validated when the task is parsed.
+                    sf.set_code(&to.code).ok();
+                }
+            } else {
+                let values: Vec<String> = field
+                    .get_subfields(&from.code)
+                    .iter()
+                    .map(|sf| sf.content().to_string())
+                    .collect();
+
+                for value in values {
+                    field.add_subfield(&to.code, value).ok();
+                }
+            }
+            continue;
+        }
+
+        let Ok(mut new_field) = Field::new(&to.spec) else {
+            continue;
+        };
+
+        for sf in field.get_subfields(&from.code) {
+            if let Ok(sf) = Subfield::new(&to.code, sf.content()) {
+                new_field.subfields_mut().push(sf);
+            }
+        }
+
+        if remove {
+            field.remove_subfields(&from.code);
+            changes += 1;
+        }
+
+        new_fields.push(new_field);
+    }
+
+    if remove {
+        record.fields_mut().retain(|f| !f.subfields().is_empty());
+    }
+
+    for field in new_fields {
+        record.insert_data_field(field);
+        changes += 1;
+    }
+
+    changes
+}
+
+fn edit_values(
+    record: &mut Record,
+    target: &SubfieldTarget,
+    conditions: &[Condition],
+    edit: impl Fn(&str) -> String,
+) -> usize {
+    let mut changes = 0;
+
+    for field in record.fields_mut().iter_mut() {
+        if !selects(field, &target.spec, conditions) || !field.has_subfield(&target.code) {
+            continue;
+        }
+
+        for sf in field.get_subfields_mut(&target.code) {
+            let value = edit(sf.content());
+            sf.set_content(value);
+        }
+
+        changes += 1;
+    }
+
+    changes
+}
+
+/// Split text on whitespace, honoring double-quoted values.
+fn tokenize(text: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut in_token = false;
+    let mut in_quotes = false;
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                in_token = true;
+            }
+            '\\' if in_quotes => match chars.next() {
+                Some(e) => token.push(e),
+                None => break,
+            },
+            c if c.is_whitespace() && !in_quotes => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut token));
+                    in_token = false;
+                }
+            }
+            c => {
+                token.push(c);
+                in_token = true;
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err(format!("Unterminated quote: {text}"));
+    }
+
+    if in_token {
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// An ordered list of edit tasks.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::Record;
+/// use marctk::edit::EditTasks;
+///
+/// let tasks = EditTasks::from_text(r#"
+///     delete 9xx
+///     delete 650 ind2=7 $2=fast
+///     add =949 \\$aProcessed
+///     prepend 245$a "The "
+///     copy 020$a 024$a
+/// "#).unwrap();
+///
+/// let mut record = Record::from_breaker(
+///     "=020 \\\\$a9780000000002
+/// =245 10$aTitle
+/// =650 \\0$aCats.
+/// =650 \\7$aCats.$2fast
+/// =901 \\\\$a123"
+/// ).unwrap();
+///
+/// assert_eq!(tasks.apply(&mut record), 5);
+///
+/// let tags: Vec<&str> = record.fields().iter().map(|f| f.tag()).collect();
+/// assert_eq!(tags, vec!["020", "024", "245", "650", "949"]);
+///
+/// assert_eq!(record.get_field_values("245", "a"), vec!["The Title"]);
+/// assert_eq!(record.get_field_values("024", "a"), vec!["9780000000002"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EditTasks {
+    tasks: Vec<EditTask>,
+}
+
+impl EditTasks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse tasks from text containing one task per line.
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut tasks = EditTasks::new();
+
+        for (idx, line) in text.lines().map(|l| l.trim()).enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let task =
+                EditTask::parse(line).map_err(|e| format!("Edit task line {}: {e}", idx + 1))?;
+
+            tasks.add_task(task);
+        }
+
+        Ok(tasks)
+    }
+
+    /// Read tasks from a file containing one task per line.
+    pub fn from_file(filename: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(filename)
+            .map_err(|e| format!("Cannot read edit tasks file: {filename} {e}"))?;
+
+        EditTasks::from_text(&text)
+    }
+
+    pub fn tasks(&self) -> &Vec<EditTask> {
+        &self.tasks
+    }
+
+    pub fn add_task(&mut self, task: EditTask) {
+        self.tasks.push(task);
+    }
+
+    /// Apply every task, in order, to the record.
+    ///
+    /// Each task runs within [`Record::edit`], so records with a
+    /// change journal log each change with the task's
+    /// [`EditTask::label`] as the rule name.
+    ///
+    /// Returns the number of fields added, removed, or modified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::edit::EditTasks;
+    ///
+    /// let mut record = Record::from_breaker("=901 \\\\$aLocal\n=500 \\\\$aNote").unwrap();
+    /// record.enable_journal();
+    ///
+    /// let tasks = EditTasks::from_text("delete 9xx\nappend 500$a .").unwrap();
+    /// assert_eq!(tasks.apply(&mut record), 2);
+    ///
+    /// let rules: Vec<Option<&str>> = record
+    ///     .journal()
+    ///     .unwrap()
+    ///     .entries()
+    ///     .iter()
+    ///     .map(|e| e.rule())
+    ///     .collect();
+    ///
+    /// assert_eq!(rules, vec![Some("delete 9xx"), Some("append 500$a")]);
+    /// ```
+    pub fn apply(&self, record: &mut Record) -> usize {
+        self.tasks
+            .iter()
+            .map(|t| record.edit(&t.label(), |r| t.apply(r)))
+            .sum()
+    }
+
+    /// Apply the tasks to each record produced by a record iterator,
+    /// e.g. a [`crate::binary::BinaryRecordIterator`] or
+    /// [`crate::xml::XmlRecordIterator`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::edit::EditTasks;
+    ///
+    /// let tasks = EditTasks::from_text("append 245$a .").unwrap();
+    ///
+    /// let records = Record::from_xml(r#"<collection>
+    ///   <record><datafield tag="245" ind1="1" ind2="0"><subfield code="a">One</subfield></datafield></record>
+    ///   <record><datafield tag="245" ind1="1" ind2="0"><subfield code="a">Two</subfield></datafield></record>
+    /// </collection>"#);
+    ///
+    /// let titles: Vec<String> = tasks
+    ///     .edit_records(records)
+    ///     .map(|r| r.unwrap().get_field_values("245", "a")[0].to_string())
+    ///     .collect();
+    ///
+    /// assert_eq!(titles, vec!["One.", "Two."]);
+    /// ```
+    pub fn edit_records<I>(self, records: I) -> EditedRecords<I>
+    where
+        I: Iterator<Item = Result<Record, String>>,
+    {
+        EditedRecords {
+            tasks: self,
+            records,
+        }
+    }
+}
+
+/// Iterator which applies [`EditTasks`] to the records produced by
+/// another iterator.  Errors are passed through unchanged.
+pub struct EditedRecords<I> {
+    tasks: EditTasks,
+    records: I,
+}
+
+impl<I> Iterator for EditedRecords<I>
+where
+    I: Iterator<Item = Result<Record, String>>,
+{
+    type Item = Result<Record, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.records.next().map(|result| {
+            result.map(|mut record| {
+                self.tasks.apply(&mut record);
+                record
+            })
+        })
+    }
+}
@@ -0,0 +1,380 @@
+//! Record fingerprints for finding duplicate records.
+//!
+//! A fingerprint is a match key built from normalized pieces of a
+//! record, e.g. title, author, and date, in the style of the OCLC and
+//! Evergreen metarecord fingerprints.  Records with equal fingerprints
+//! are likely to describe the same resource, which makes fingerprints
+//! useful for deduplicating large files before import.
+//!
+//! Fingerprints depend only on the record and the [`Profile`], so they
+//! are stable across runs and may be stored for later comparison.
+//!
+//! Custom profiles are written as a space-separated list of key parts:
+//!
+//! ```text
+//! title author date 020$a:isbn
+//! ```
+//!
+//! See [`KeyPart`] for the available parts.  Subfield parts use the
+//! [`Normalizer`] names from the matcher module, defaulting to "text".
+use super::matcher::Normalizer;
+use super::Record;
+use std::fmt;
+use std::str::FromStr;
+
+/// Separates key parts within a fingerprint.
+const PART_SEPARATOR: char = '|';
+
+/// One piece of a fingerprint.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyPart {
+    /// 245 $a, $n, and $p, without nonfiling characters.
+    Title,
+    /// 100, 110, or 111 $a, falling back to the first 700, 710, or
+    /// 711 $a.
+    Author,
+    /// 008 Date 1, falling back to the first year in 264 or 260 $c.
+    Date,
+    /// Largest number in the 300 $a, e.g. the page count.
+    Pagination,
+    /// First number in the 250 $a, e.g. "2" for "2nd ed.", or the
+    /// normalized edition statement if it contains no number.
+    Edition,
+    /// Leader type of record and bibliographic level.
+    Format,
+    /// First value of a subfield with the given normalizer.
+    Subfield {
+        tag: String,
+        code: String,
+        normalizer: Normalizer,
+    },
+}
+
+impl FromStr for KeyPart {
+    type Err = String;
+
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::fingerprint::KeyPart;
+    /// use marctk::matcher::Normalizer;
+    ///
+    /// assert_eq!("title".parse::<KeyPart>(), Ok(KeyPart::Title));
+    /// assert_eq!(
+    ///     "020$a:isbn".parse::<KeyPart>(),
+    ///     Ok(KeyPart::Subfield {
+    ///         tag: "020".to_string(),
+    ///         code: "a".to_string(),
+    ///         normalizer: Normalizer::Isbn,
+    ///     })
+    /// );
+    /// assert!("colour".parse::<KeyPart>().is_err());
+    /// assert!("20$a".parse::<KeyPart>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "title" => return Ok(Self::Title),
+            "author" => return Ok(Self::Author),
+            "date" => return Ok(Self::Date),
+            "pagination" => return Ok(Self::Pagination),
+            "edition" => return Ok(Self::Edition),
+            "format" => return Ok(Self::Format),
+            _ => {}
+        }
+
+        let (target, normalizer) = match s.split_once(':') {
+            Some((t, n)) => (t, n.parse::<Normalizer>()?),
+            None => (s, Normalizer::Text),
+        };
+
+        let (tag, code) = target
+            .split_once('$')
+            .ok_or_else(|| format!("Invalid fingerprint key part: {s}"))?;
+
+        if tag.len() != 3 || code.len() != 1 {
+            return Err(format!("Invalid fingerprint key part: {s}"));
+        }
+
+        Ok(Self::Subfield {
+            tag: tag.to_string(),
+            code: code.to_string(),
+            normalizer,
+        })
+    }
+}
+
+impl fmt::Display for KeyPart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Title => write!(f, "title"),
+            Self::Author => write!(f, "author"),
+            Self::Date => write!(f, "date"),
+            Self::Pagination => write!(f, "pagination"),
+            Self::Edition => write!(f, "edition"),
+            Self::Format => write!(f, "format"),
+            Self::Subfield {
+                tag,
+                code,
+                normalizer,
+            } => write!(f, "{tag}${code}:{normalizer}"),
+        }
+    }
+}
+
+impl KeyPart {
+    /// Normalized value for this part, or an empty string if the
+    /// record has no value.
+    pub fn value(&self, record: &Record) -> String {
+        match self {
+            Self::Title => title_key(record),
+            Self::Author => author_key(record),
+            Self::Date => date_key(record),
+            Self::Pagination => pagination_key(record),
+            Self::Edition => edition_key(record),
+            Self::Format => record.leader().get(6..8).unwrap_or("").to_string(),
+            Self::Subfield {
+                tag,
+                code,
+                normalizer,
+            } => record
+                .get_field_values(tag, code)
+                .iter()
+                .find_map(|v| normalizer.normalize(v))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// An ordered list of key parts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    parts: Vec<KeyPart>,
+}
+
+impl Profile {
+    pub fn new(parts: Vec<KeyPart>) -> Profile {
+        Profile { parts }
+    }
+
+    /// Title, author, and date.
+    ///
+    /// Groups different editions and printings of a work with the
+    /// same publication year.
+    pub fn standard() -> Profile {
+        Profile::new(vec![KeyPart::Title, KeyPart::Author, KeyPart::Date])
+    }
+
+    /// Title, author, date, edition, pagination, and format.
+    ///
+    /// Only groups records for the same manifestation.
+    pub fn strict() -> Profile {
+        Profile::new(vec![
+            KeyPart::Title,
+            KeyPart::Author,
+            KeyPart::Date,
+            KeyPart::Edition,
+            KeyPart::Pagination,
+            KeyPart::Format,
+        ])
+    }
+
+    /// Parse a built-in profile name ("standard" or "strict") or a
+    /// custom space-separated list of key parts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::fingerprint::{KeyPart, Profile};
+    ///
+    /// assert_eq!(Profile::parse("strict").unwrap(), Profile::strict());
+    ///
+    /// let profile = Profile::parse("title 020$a:isbn").unwrap();
+    /// assert_eq!(profile.parts().len(), 2);
+    /// assert_eq!(profile.parts()[0], KeyPart::Title);
+    /// assert_eq!(profile.to_string(), "title 020$a:isbn");
+    ///
+    /// assert!(Profile::parse("").is_err());
+    /// assert!(Profile::parse("title 020$a:blah").is_err());
+    /// ```
+    pub fn parse(spec: &str) -> Result<Profile, String> {
+        match spec.trim() {
+            "standard" => return Ok(Profile::standard()),
+            "strict" => return Ok(Profile::strict()),
+            _ => {}
+        }
+
+        let parts = spec
+            .split_whitespace()
+            .map(|p| p.parse::<KeyPart>())
+            .collect::<Result<Vec<KeyPart>, String>>()?;
+
+        if parts.is_empty() {
+            return Err("Fingerprint profile has no key parts".to_string());
+        }
+
+        Ok(Profile::new(parts))
+    }
+
+    pub fn parts(&self) -> &Vec<KeyPart> {
+        &self.parts
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile::standard()
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts: Vec<String> = self.parts.iter().map(|p| p.to_string()).collect();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl Record {
+    /// Match key for this record built from the profile's key parts.
+    ///
+    /// Returns None if the record has no value for the first key part
+    /// of the profile, e.g. no title for the built-in profiles, since
+    /// such records cannot be meaningfully grouped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::fingerprint::Profile;
+    ///
+    /// let a = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =008 170101s2017    flua   j      000 1 eng d
+    /// =100 1\$aSmith, Jane,$d1970-
+    /// =245 14$aThe Cat in the Hat /$cJane Smith.
+    /// =300 \\$axii, 245 p. ;$c24 cm."#
+    /// ).unwrap();
+    ///
+    /// let b = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =100 1\$aSMITH, JANE.
+    /// =245 00$aCat in the hat.
+    /// =264 \1$aNew York :$bRandom House,$c[2017]
+    /// =300 \\$a250 pages"#
+    /// ).unwrap();
+    ///
+    /// let standard = Profile::standard();
+    ///
+    /// assert_eq!(a.fingerprint(&standard).unwrap(), "cat in the hat|smith jane|2017");
+    /// assert_eq!(a.fingerprint(&standard), b.fingerprint(&standard));
+    ///
+    /// // Different page counts
+    /// let strict = Profile::strict();
+    /// assert_ne!(a.fingerprint(&strict), b.fingerprint(&strict));
+    ///
+    /// let untitled = Record::from_breaker("=100 1\\$aSmith, Jane").unwrap();
+    /// assert_eq!(untitled.fingerprint(&standard), None);
+    /// ```
+    pub fn fingerprint(&self, profile: &Profile) -> Option<String> {
+        let values: Vec<String> = profile.parts.iter().map(|p| p.value(self)).collect();
+
+        if values.first().map(|v| v.is_empty()).unwrap_or(true) {
+            return None;
+        }
+
+        Some(values.join(&PART_SEPARATOR.to_string()))
+    }
+}
+
+fn normalize_text(value: &str) -> String {
+    Normalizer::Text.normalize(value).unwrap_or_default()
+}
+
+fn title_key(record: &Record) -> String {
+    let field = match record.get_fields("245").first() {
+        Some(f) => *f,
+        None => return String::new(),
+    };
+
+    let nonfiling = field.ind2().parse::<usize>().unwrap_or(0);
+
+    let mut title = String::new();
+    for sf in field.subfields() {
+        if !matches!(sf.code(), "a" | "n" | "p") {
+            continue;
+        }
+
+        let content = if title.is_empty() {
+            sf.content().chars().skip(nonfiling).collect()
+        } else {
+            sf.content().to_string()
+        };
+
+        title += " ";
+        title += &content;
+    }
+
+    normalize_text(&title)
+}
+
+fn author_key(record: &Record) -> String {
+    ["100", "110", "111", "700", "710", "711"]
+        .iter()
+        .flat_map(|tag| record.get_field_values(tag, "a"))
+        .map(normalize_text)
+        .find(|v| !v.is_empty())
+        .unwrap_or_default()
+}
+
+fn date_key(record: &Record) -> String {
+    if let Some(fixed) = record.fixed_008() {
+        if fixed.date1.len() == 4 && fixed.date1.chars().all(|c| c.is_ascii_digit()) {
+            return fixed.date1;
+        }
+    }
+
+    ["264", "260"]
+        .iter()
+        .flat_map(|tag| record.get_field_values(tag, "c"))
+        .find_map(first_year)
+        .unwrap_or_default()
+}
+
+/// First run of exactly 4 digits in the value.
+fn first_year(value: &str) -> Option<String> {
+    numbers(value).into_iter().find(|n| n.len() == 4)
+}
+
+/// Runs of ASCII digits in the value.
+fn numbers(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|n| !n.is_empty())
+        .map(|n| n.to_string())
+        .collect()
+}
+
+fn pagination_key(record: &Record) -> String {
+    record
+        .get_field_values("300", "a")
+        .first()
+        .and_then(|v| {
+            numbers(v)
+                .iter()
+                .filter_map(|n| n.parse::<u64>().ok())
+                .max()
+        })
+        .map(|n| n.to_string())
+        .unwrap_or_default()
+}
+
+fn edition_key(record: &Record) -> String {
+    let edition = match record.get_field_values("250", "a").first() {
+        Some(e) => *e,
+        None => return String::new(),
+    };
+
+    match numbers(edition).first().and_then(|n| n.parse::<u64>().ok()) {
+        Some(n) => n.to_string(),
+        None => normalize_text(edition),
+    }
+}
@@ -0,0 +1,671 @@
+//! Typed access to the 008, 006, and 007 fixed-length control fields.
+//!
+//! Positions 18-34 of the 008 (and 01-17 of the 006) are defined
+//! differently for each type of material, as determined by the leader
+//! (or 006/00).  Parsing is lenient: short values are padded with
+//! blanks and unknown codes are retained as-is, so any control field
+//! can be parsed and regenerated.  Values are stored as found, with
+//! blanks represented as ' ' and fill characters as '|'.
+//!
+//! # References
+//!
+//! * <https://www.loc.gov/marc/bibliographic/bd008.html>
+//! * <https://www.loc.gov/marc/bibliographic/bd006.html>
+//! * <https://www.loc.gov/marc/bibliographic/bd007.html>
+use super::leader::{BibliographicLevel, Leader, TypeOfRecord};
+use super::Controlfield;
+use super::Record;
+use std::fmt;
+
+/// Length of the 008 field.
+pub const FIXED_008_SIZE: usize = 40;
+
+/// Length of the 006 field.
+pub const FIXED_006_SIZE: usize = 18;
+
+/// Length of the material specific portion of the 008/006.
+const MATERIAL_SIZE: usize = 17;
+
+/// Fixed-length value as a list of characters, padded or truncated
+/// to the requested length.
+fn to_chars(value: &str, len: usize) -> Vec<char> {
+    let mut chars: Vec<char> = value.chars().take(len).collect();
+    chars.resize(len, ' ');
+    chars
+}
+
+fn get(chars: &[char], start: usize, len: usize) -> String {
+    chars[start..start + len].iter().collect()
+}
+
+/// Copy a value into a fixed-length buffer, padding with blanks.
+fn put(chars: &mut [char], start: usize, len: usize, value: &str) {
+    for (idx, c) in to_chars(value, len).into_iter().enumerate() {
+        chars[start + idx] = c;
+    }
+}
+
+/// Type of material, which determines the layout of 008/18-34.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaterialType {
+    Books,
+    ComputerFiles,
+    Maps,
+    Music,
+    ContinuingResources,
+    VisualMaterials,
+    MixedMaterials,
+}
+
+impl MaterialType {
+    /// Material type from leader/06 and leader/07.
+    ///
+    /// Returns None for non-bibliographic records.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::leader::Leader;
+    /// use marctk::fixed_fields::MaterialType;
+    ///
+    /// let leader = Leader::new("00000nas a2200000 a 4500").unwrap();
+    /// assert_eq!(MaterialType::from_leader(&leader), Some(MaterialType::ContinuingResources));
+    ///
+    /// let leader = Leader::new("00000ngm a2200000 a 4500").unwrap();
+    /// assert_eq!(MaterialType::from_leader(&leader), Some(MaterialType::VisualMaterials));
+    /// ```
+    pub fn from_leader(leader: &Leader) -> Option<MaterialType> {
+        match leader.type_of_record()? {
+            TypeOfRecord::LanguageMaterial | TypeOfRecord::ManuscriptLanguageMaterial => {
+                match leader.bibliographic_level() {
+                    Some(BibliographicLevel::SerialComponentPart)
+                    | Some(BibliographicLevel::IntegratingResource)
+                    | Some(BibliographicLevel::Serial) => Some(Self::ContinuingResources),
+                    _ => Some(Self::Books),
+                }
+            }
+            t => Self::from_code(t.as_char()),
+        }
+    }
+
+    /// Material type from a type of record code or 006/00 form of
+    /// material code.
+    pub fn from_code(code: char) -> Option<MaterialType> {
+        match code {
+            'a' | 't' => Some(Self::Books),
+            's' => Some(Self::ContinuingResources),
+            'm' => Some(Self::ComputerFiles),
+            'e' | 'f' => Some(Self::Maps),
+            'c' | 'd' | 'i' | 'j' => Some(Self::Music),
+            'g' | 'k' | 'o' | 'r' => Some(Self::VisualMaterials),
+            'p' => Some(Self::MixedMaterials),
+            _ => None,
+        }
+    }
+}
+
+/// 008/18-34 for books.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BooksFields {
+    pub illustrations: String,
+    pub target_audience: char,
+    pub form_of_item: char,
+    pub nature_of_contents: String,
+    pub government_publication: char,
+    pub conference_publication: char,
+    pub festschrift: char,
+    pub index: char,
+    pub literary_form: char,
+    pub biography: char,
+}
+
+/// 008/18-34 for continuing resources.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContinuingResourcesFields {
+    pub frequency: char,
+    pub regularity: char,
+    pub type_of_continuing_resource: char,
+    pub form_of_original_item: char,
+    pub form_of_item: char,
+    pub nature_of_entire_work: char,
+    pub nature_of_contents: String,
+    pub government_publication: char,
+    pub conference_publication: char,
+    pub original_alphabet: char,
+    pub entry_convention: char,
+}
+
+/// 008/18-34 for music.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MusicFields {
+    pub form_of_composition: String,
+    pub format_of_music: char,
+    pub music_parts: char,
+    pub target_audience: char,
+    pub form_of_item: char,
+    pub accompanying_matter: String,
+    pub literary_text: String,
+    pub transposition_arrangement: char,
+}
+
+/// 008/18-34 for maps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapsFields {
+    pub relief: String,
+    pub projection: String,
+    pub type_of_cartographic_material: char,
+    pub government_publication: char,
+    pub form_of_item: char,
+    pub index: char,
+    pub special_format: String,
+}
+
+/// 008/18-34 for visual materials.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VisualMaterialsFields {
+    pub running_time: String,
+    pub target_audience: char,
+    pub government_publication: char,
+    pub form_of_item: char,
+    pub type_of_visual_material: char,
+    pub technique: char,
+}
+
+/// 008/18-34 for computer files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputerFilesFields {
+    pub target_audience: char,
+    pub form_of_item: char,
+    pub type_of_computer_file: char,
+    pub government_publication: char,
+}
+
+/// 008/18-34 for mixed materials.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MixedMaterialsFields {
+    pub form_of_item: char,
+}
+
+/// Material specific values from 008/18-34 or 006/01-17.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MaterialFields {
+    Books(BooksFields),
+    ComputerFiles(ComputerFilesFields),
+    Maps(MapsFields),
+    Music(MusicFields),
+    ContinuingResources(ContinuingResourcesFields),
+    VisualMaterials(VisualMaterialsFields),
+    MixedMaterials(MixedMaterialsFields),
+    /// Material type could not be determined.  The raw values are
+    /// retained as-is.
+    Unknown(String),
+}
+
+impl MaterialFields {
+    /// Parse the 17 material specific characters.
+    ///
+    /// Offsets below are relative to 008/18.
+    fn parse(material_type: Option<MaterialType>, c: &[char]) -> Self {
+        let Some(material_type) = material_type else {
+            return Self::Unknown(c.iter().collect());
+        };
+
+        match material_type {
+            MaterialType::Books => Self::Books(BooksFields {
+                illustrations: get(c, 0, 4),
+                target_audience: c[4],
+                form_of_item: c[5],
+                nature_of_contents: get(c, 6, 4),
+                government_publication: c[10],
+                conference_publication: c[11],
+                festschrift: c[12],
+                index: c[13],
+                literary_form: c[15],
+                biography: c[16],
+            }),
+            MaterialType::ContinuingResources => {
+                Self::ContinuingResources(ContinuingResourcesFields {
+                    frequency: c[0],
+                    regularity: c[1],
+                    type_of_continuing_resource: c[3],
+                    form_of_original_item: c[4],
+                    form_of_item: c[5],
+                    nature_of_entire_work: c[6],
+                    nature_of_contents: get(c, 7, 3),
+                    government_publication: c[10],
+                    conference_publication: c[11],
+                    original_alphabet: c[15],
+                    entry_convention: c[16],
+                })
+            }
+            MaterialType::Music => Self::Music(MusicFields {
+                form_of_composition: get(c, 0, 2),
+                format_of_music: c[2],
+                music_parts: c[3],
+                target_audience: c[4],
+                form_of_item: c[5],
+                accompanying_matter: get(c, 6, 6),
+                literary_text: get(c, 12, 2),
+                transposition_arrangement: c[15],
+            }),
+            MaterialType::Maps => Self::Maps(MapsFields {
+                relief: get(c, 0, 4),
+                projection: get(c, 4, 2),
+                type_of_cartographic_material: c[7],
+                government_publication: c[10],
+                form_of_item: c[11],
+                index: c[13],
+                special_format: get(c, 15, 2),
+            }),
+            MaterialType::VisualMaterials => Self::VisualMaterials(VisualMaterialsFields {
+                running_time: get(c, 0, 3),
+                target_audience: c[4],
+                government_publication: c[10],
+                form_of_item: c[11],
+                type_of_visual_material: c[15],
+                technique: c[16],
+            }),
+            MaterialType::ComputerFiles => Self::ComputerFiles(ComputerFilesFields {
+                target_audience: c[4],
+                form_of_item: c[5],
+                type_of_computer_file: c[8],
+                government_publication: c[10],
+            }),
+            MaterialType::MixedMaterials => {
+                Self::MixedMaterials(MixedMaterialsFields { form_of_item: c[5] })
+            }
+        }
+    }
+
+    /// Write the 17 material specific characters.  Positions which
+    /// are undefined for the material type are left unchanged.
+    fn write(&self, c: &mut [char]) {
+        match self {
+            Self::Books(f) => {
+                put(c, 0, 4, &f.illustrations);
+                c[4] = f.target_audience;
+                c[5] = f.form_of_item;
+                put(c, 6, 4, &f.nature_of_contents);
+                c[10] = f.government_publication;
+                c[11] = f.conference_publication;
+                c[12] = f.festschrift;
+                c[13] = f.index;
+                c[15] = f.literary_form;
+                c[16] = f.biography;
+            }
+            Self::ContinuingResources(f) => {
+                c[0] = f.frequency;
+                c[1] = f.regularity;
+                c[3] = f.type_of_continuing_resource;
+                c[4] = f.form_of_original_item;
+                c[5] = f.form_of_item;
+                c[6] = f.nature_of_entire_work;
+                put(c, 7, 3, &f.nature_of_contents);
+                c[10] = f.government_publication;
+                c[11] = f.conference_publication;
+                c[15] = f.original_alphabet;
+                c[16] = f.entry_convention;
+            }
+            Self::Music(f) => {
+                put(c, 0, 2, &f.form_of_composition);
+                c[2] = f.format_of_music;
+                c[3] = f.music_parts;
+                c[4] = f.target_audience;
+                c[5] = f.form_of_item;
+                put(c, 6, 6, &f.accompanying_matter);
+                put(c, 12, 2, &f.literary_text);
+                c[15] = f.transposition_arrangement;
+            }
+            Self::Maps(f) => {
+                put(c, 0, 4, &f.relief);
+                put(c, 4, 2, &f.projection);
+                c[7] = f.type_of_cartographic_material;
+                c[10] = f.government_publication;
+                c[11] = f.form_of_item;
+                c[13] = f.index;
+                put(c, 15, 2, &f.special_format);
+            }
+            Self::VisualMaterials(f) => {
+                put(c, 0, 3, &f.running_time);
+                c[4] = f.target_audience;
+                c[10] = f.government_publication;
+                c[11] = f.form_of_item;
+                c[15] = f.type_of_visual_material;
+                c[16] = f.technique;
+            }
+            Self::ComputerFiles(f) => {
+                c[4] = f.target_audience;
+                c[5] = f.form_of_item;
+                c[8] = f.type_of_computer_file;
+                c[10] = f.government_publication;
+            }
+            Self::MixedMaterials(f) => c[5] = f.form_of_item,
+            Self::Unknown(raw) => put(c, 0, MATERIAL_SIZE, raw),
+        }
+    }
+
+    /// Form of item, for material types which define it.
+    pub fn form_of_item(&self) -> Option<char> {
+        match self {
+            Self::Books(f) => Some(f.form_of_item),
+            Self::ContinuingResources(f) => Some(f.form_of_item),
+            Self::Music(f) => Some(f.form_of_item),
+            Self::Maps(f) => Some(f.form_of_item),
+            Self::VisualMaterials(f) => Some(f.form_of_item),
+            Self::ComputerFiles(f) => Some(f.form_of_item),
+            Self::MixedMaterials(f) => Some(f.form_of_item),
+            Self::Unknown(_) => None,
+        }
+    }
+
+    /// Target audience, for material types which define it.
+    pub fn target_audience(&self) -> Option<char> {
+        match self {
+            Self::Books(f) => Some(f.target_audience),
+            Self::Music(f) => Some(f.target_audience),
+            Self::VisualMaterials(f) => Some(f.target_audience),
+            Self::ComputerFiles(f) => Some(f.target_audience),
+            _ => None,
+        }
+    }
+}
+
+/// Parsed 008 field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixed008 {
+    /// 00-05, YYMMDD
+    pub date_entered: String,
+    /// 06
+    pub date_type: char,
+    /// 07-10
+    pub date1: String,
+    /// 11-14
+    pub date2: String,
+    /// 15-17, MARC country code
+    pub place: String,
+    /// 18-34
+    pub material: MaterialFields,
+    /// 35-37, MARC language code
+    pub language: String,
+    /// 38
+    pub modified_record: char,
+    /// 39
+    pub cataloging_source: char,
+    /// Original value, retained for undefined positions.
+    source: Vec<char>,
+}
+
+impl Fixed008 {
+    /// Parse an 008 value, using the leader to determine the layout
+    /// of the material specific positions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::leader::Leader;
+    /// use marctk::fixed_fields::{Fixed008, MaterialFields};
+    ///
+    /// let leader = Leader::new("00000nam a2200000 a 4500").unwrap();
+    /// let value = "170101s2017    flua   j      000 1 spa d";
+    ///
+    /// let mut fixed = Fixed008::parse(value, &leader);
+    ///
+    /// assert_eq!(fixed.date1, "2017");
+    /// assert_eq!(fixed.place, "flu");
+    /// assert_eq!(fixed.language, "spa");
+    /// assert_eq!(fixed.material.target_audience(), Some('j'));
+    ///
+    /// let MaterialFields::Books(books) = &mut fixed.material else {
+    ///     panic!("Should be a book");
+    /// };
+    ///
+    /// assert_eq!(books.illustrations, "a   ");
+    /// assert_eq!(books.literary_form, '1');
+    ///
+    /// books.literary_form = 'f';
+    /// fixed.date1 = "2018".to_string();
+    ///
+    /// assert_eq!(fixed.to_string(), "170101s2018    flua   j      000 f spa d");
+    ///
+    /// // Short values are padded.
+    /// assert_eq!(Fixed008::parse("170101s2017", &leader).to_string().len(), 40);
+    /// ```
+    pub fn parse(value: &str, leader: &Leader) -> Self {
+        let c = to_chars(value, FIXED_008_SIZE);
+
+        Fixed008 {
+            date_entered: get(&c, 0, 6),
+            date_type: c[6],
+            date1: get(&c, 7, 4),
+            date2: get(&c, 11, 4),
+            place: get(&c, 15, 3),
+            material: MaterialFields::parse(MaterialType::from_leader(leader), &c[18..35]),
+            language: get(&c, 35, 3),
+            modified_record: c[38],
+            cataloging_source: c[39],
+            source: c,
+        }
+    }
+}
+
+impl fmt::Display for Fixed008 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut c = self.source.clone();
+        let c = &mut c;
+
+        put(c, 0, 6, &self.date_entered);
+        c[6] = self.date_type;
+        put(c, 7, 4, &self.date1);
+        put(c, 11, 4, &self.date2);
+        put(c, 15, 3, &self.place);
+        self.material.write(&mut c[18..35]);
+        put(c, 35, 3, &self.language);
+        c[38] = self.modified_record;
+        c[39] = self.cataloging_source;
+
+        write!(f, "{}", c.iter().collect::<String>())
+    }
+}
+
+/// Parsed 006 field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixed006 {
+    /// 00
+    pub form_of_material: char,
+    /// 01-17
+    pub material: MaterialFields,
+    /// Original value, retained for undefined positions.
+    source: Vec<char>,
+}
+
+impl Fixed006 {
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::fixed_fields::{Fixed006, MaterialFields};
+    ///
+    /// let fixed = Fixed006::parse("m     o  d        ");
+    /// assert!(matches!(
+    ///     &fixed.material,
+    ///     MaterialFields::ComputerFiles(f) if f.type_of_computer_file == 'd'
+    /// ));
+    /// assert_eq!(fixed.material.form_of_item(), Some('o'));
+    /// assert_eq!(fixed.to_string(), "m     o  d        ");
+    /// ```
+    pub fn parse(value: &str) -> Self {
+        let c = to_chars(value, FIXED_006_SIZE);
+
+        Fixed006 {
+            form_of_material: c[0],
+            material: MaterialFields::parse(MaterialType::from_code(c[0]), &c[1..]),
+            source: c,
+        }
+    }
+}
+
+impl fmt::Display for Fixed006 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut c = self.source.clone();
+        c[0] = self.form_of_material;
+        self.material.write(&mut c[1..]);
+        write!(f, "{}", c.into_iter().collect::<String>())
+    }
+}
+
+/// 007/00
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PhysicalCategory {
+    Map,
+    ElectronicResource,
+    Globe,
+    TactileMaterial,
+    ProjectedGraphic,
+    Microform,
+    NonprojectedGraphic,
+    MotionPicture,
+    Kit,
+    NotatedMusic,
+    RemoteSensingImage,
+    SoundRecording,
+    Text,
+    Videorecording,
+    Unspecified,
+}
+
+impl PhysicalCategory {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'a' => Some(Self::Map),
+            'c' => Some(Self::ElectronicResource),
+            'd' => Some(Self::Globe),
+            'f' => Some(Self::TactileMaterial),
+            'g' => Some(Self::ProjectedGraphic),
+            'h' => Some(Self::Microform),
+            'k' => Some(Self::NonprojectedGraphic),
+            'm' => Some(Self::MotionPicture),
+            'o' => Some(Self::Kit),
+            'q' => Some(Self::NotatedMusic),
+            'r' => Some(Self::RemoteSensingImage),
+            's' => Some(Self::SoundRecording),
+            't' => Some(Self::Text),
+            'v' => Some(Self::Videorecording),
+            'z' => Some(Self::Unspecified),
+            _ => None,
+        }
+    }
+}
+
+/// Parsed 007 field.
+///
+/// The length and layout of the 007 vary by category of material.
+/// The positions following the specific material designation are
+/// retained as-is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixed007 {
+    /// 00
+    pub category: char,
+    /// 01
+    pub material_designation: char,
+    /// 02 onward
+    pub details: String,
+}
+
+impl Fixed007 {
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::fixed_fields::{Fixed007, PhysicalCategory};
+    ///
+    /// let fixed = Fixed007::parse("vd cvaizq");
+    /// assert_eq!(fixed.category(), Some(PhysicalCategory::Videorecording));
+    /// assert_eq!(fixed.material_designation, 'd');
+    /// assert_eq!(fixed.details, " cvaizq");
+    /// assert_eq!(fixed.to_string(), "vd cvaizq");
+    /// ```
+    pub fn parse(value: &str) -> Self {
+        let mut chars = value.chars();
+
+        Fixed007 {
+            category: chars.next().unwrap_or(' '),
+            material_designation: chars.next().unwrap_or(' '),
+            details: chars.collect(),
+        }
+    }
+
+    pub fn category(&self) -> Option<PhysicalCategory> {
+        PhysicalCategory::from_char(self.category)
+    }
+}
+
+impl fmt::Display for Fixed007 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.category, self.material_designation, self.details
+        )
+    }
+}
+
+impl Record {
+    /// Parsed 008 field, if present.
+    pub fn fixed_008(&self) -> Option<Fixed008> {
+        self.get_control_fields("008")
+            .first()
+            .map(|cf| Fixed008::parse(cf.content(), self.typed_leader()))
+    }
+
+    /// Replace the 008 field with the regenerated value, adding an 008
+    /// if needed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let mut record = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =001 123
+    /// =008 170101s2017    flua   j      000 1 spa d"#
+    /// ).unwrap();
+    ///
+    /// let mut fixed = record.fixed_008().unwrap();
+    /// fixed.language = "eng".to_string();
+    /// record.set_fixed_008(&fixed);
+    ///
+    /// assert_eq!(
+    ///     record.get_control_fields("008")[0].content(),
+    ///     "170101s2017    flua   j      000 1 eng d"
+    /// );
+    /// ```
+    pub fn set_fixed_008(&mut self, fixed: &Fixed008) {
+        let value = fixed.to_string();
+
+        if let Some(cf) = self
+            .control_fields_mut()
+            .iter_mut()
+            .find(|cf| cf.tag() == "008")
+        {
+            cf.set_content(value);
+        } else {
+            // Tag is known to be valid.
+            self.insert_control_field(Controlfield::new("008", value).unwrap());
+        }
+    }
+
+    /// Parsed 006 fields.
+    pub fn fixed_006s(&self) -> Vec<Fixed006> {
+        self.get_control_fields("006")
+            .iter()
+            .map(|cf| Fixed006::parse(cf.content()))
+            .collect()
+    }
+
+    /// Parsed 007 fields.
+    pub fn fixed_007s(&self) -> Vec<Fixed007> {
+        self.get_control_fields("007")
+            .iter()
+            .map(|cf| Fixed007::parse(cf.content()))
+            .collect()
+    }
+}
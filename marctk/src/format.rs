@@ -0,0 +1,347 @@
+//! Material type / format classification of bibliographic records.
+//!
+//! Formats are derived from the leader, 007, 008, and RDA 33X/347
+//! fields using an ordered table of rules.  Each rule names a material
+//! type and lists conditions which must all match.  Multiple rules
+//! may share a material type, in which case any one of them matching
+//! is sufficient.
+//!
+//! Rules are written one per line as:
+//!
+//! ```text
+//! <material type> = <condition>; <condition>; ...
+//! ```
+//!
+//! where a condition is one of:
+//!
+//! * `LDR/06:at` -- Leader position 06 is one of "a" or "t".
+//! * `008/23:d` -- Position 23 of any 008 is "d".
+//! * `338$b:sd|sz` -- Any 338 $b is "sd" or "sz" (case-insensitive).
+//!
+//! Use `\` to represent a blank in leader and control field values,
+//! as in MARC breaker.  Lines starting with `#` are ignored.
+//!
+//! The material type codes in the default table match the Evergreen
+//! icon_format codes.
+use super::Record;
+use std::sync::OnceLock;
+
+/// Default classification table.  Rules for more specific formats
+/// precede the general formats they would otherwise overlap with.
+const DEFAULT_MAP: &str = r#"
+# Language material
+ebook = LDR/06:at; LDR/07:acdm; 008/23:oqs
+ebook = LDR/06:at; LDR/07:acdm; 338$b:cr
+lpbook = LDR/06:at; LDR/07:acdm; 008/23:d
+braille = LDR/06:at; LDR/07:acdm; 008/23:f
+microform = LDR/06:at; LDR/07:acdm; 008/23:abc
+book = LDR/06:at; LDR/07:acdm; 008/23:\r|
+serial = LDR/06:a; LDR/07:bis
+
+# Spoken word sound recordings
+cdaudiobook = LDR/06:i; 007/00:s; 007/03:f
+cdaudiobook = LDR/06:i; 338$b:sd; 347$b:CD audio
+casaudiobook = LDR/06:i; 007/00:s; 007/01:s
+preloadedaudio = LDR/06:i; 007/00:s; 007/01:z
+eaudio = LDR/06:i; 338$b:cr
+eaudio = LDR/06:i; 347$a:audio file
+phonospoken = LDR/06:i; 007/00:s; 007/01:d; 007/03:abcde
+
+# Music sound recordings
+cdmusic = LDR/06:j; 007/00:s; 007/03:f
+cdmusic = LDR/06:j; 338$b:sd; 347$b:CD audio
+casmusic = LDR/06:j; 007/00:s; 007/01:s
+emusic = LDR/06:j; 338$b:cr
+emusic = LDR/06:j; 347$a:audio file
+phonomusic = LDR/06:j; 007/00:s; 007/01:d; 007/03:abcde
+
+# Projected media
+blu-ray = LDR/06:g; 007/00:v; 007/04:s
+blu-ray = LDR/06:g; 347$b:Blu-ray
+dvd = LDR/06:g; 007/00:v; 007/04:v
+dvd = LDR/06:g; 347$b:DVD video
+vhs = LDR/06:g; 007/00:v; 007/04:b
+evideo = LDR/06:g; 338$b:cr
+evideo = LDR/06:g; 347$a:video file
+
+# Other
+score = LDR/06:cd
+map = LDR/06:ef
+software = LDR/06:m
+picture = LDR/06:k
+kit = LDR/06:op
+equip = LDR/06:r
+"#;
+
+/// A single requirement of a classification rule.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// Leader position contains one of the listed characters.
+    Leader { position: usize, values: String },
+    /// Position of any control field with the tag contains one of
+    /// the listed characters.
+    Control {
+        tag: String,
+        position: usize,
+        values: String,
+    },
+    /// Any subfield with the code in any field with the tag matches
+    /// one of the listed values, ignoring case.
+    Subfield {
+        tag: String,
+        code: String,
+        values: Vec<String>,
+    },
+}
+
+impl Condition {
+    /// Parse a single condition, e.g. "LDR/06:at" or "338$b:sd|sz"
+    pub fn parse(spec: &str) -> Result<Condition, String> {
+        let (target, values) = spec
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid material type condition: {spec}"))?;
+
+        let target = target.trim();
+        let values = values.trim();
+
+        if values.is_empty() {
+            return Err(format!("Material type condition has no values: {spec}"));
+        }
+
+        if let Some((tag, code)) = target.split_once('$') {
+            if tag.len() != 3 || code.len() != 1 {
+                return Err(format!("Invalid material type condition: {spec}"));
+            }
+
+            return Ok(Condition::Subfield {
+                tag: tag.to_string(),
+                code: code.to_string(),
+                values: values.split('|').map(|v| v.trim().to_string()).collect(),
+            });
+        }
+
+        let (tag, position) = target
+            .split_once('/')
+            .ok_or_else(|| format!("Invalid material type condition: {spec}"))?;
+
+        let position = position
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid position in material type condition: {spec}"))?;
+
+        let values = values.replace('\\', " ");
+
+        match tag {
+            "LDR" => Ok(Condition::Leader { position, values }),
+            _ if tag.len() == 3 && tag.as_bytes()[0..2] == *b"00" => Ok(Condition::Control {
+                tag: tag.to_string(),
+                position,
+                values,
+            }),
+            _ => Err(format!("Invalid material type condition: {spec}")),
+        }
+    }
+
+    /// True if the record satisfies this condition.
+    pub fn matches(&self, record: &Record) -> bool {
+        match self {
+            Condition::Leader { position, values } => {
+                position_matches(record.leader(), *position, values)
+            }
+            Condition::Control {
+                tag,
+                position,
+                values,
+            } => record
+                .get_control_fields(tag)
+                .iter()
+                .any(|cf| position_matches(cf.content(), *position, values)),
+            Condition::Subfield { tag, code, values } => {
+                record.get_field_values(tag, code).iter().any(|content| {
+                    let content = content.trim();
+                    values.iter().any(|v| v.eq_ignore_ascii_case(content))
+                })
+            }
+        }
+    }
+}
+
+/// Control fields read from MARC breaker retain their `\` blanks.
+fn position_matches(content: &str, position: usize, values: &str) -> bool {
+    content
+        .chars()
+        .nth(position)
+        .map(|c| if c == '\\' { ' ' } else { c })
+        .map(|c| values.contains(c))
+        .unwrap_or(false)
+}
+
+/// Maps a set of conditions to a material type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialRule {
+    material_type: String,
+    conditions: Vec<Condition>,
+}
+
+impl MaterialRule {
+    pub fn new(material_type: &str, conditions: Vec<Condition>) -> MaterialRule {
+        MaterialRule {
+            material_type: material_type.to_string(),
+            conditions,
+        }
+    }
+
+    /// Parse a rule from its text form.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::format::MaterialRule;
+    ///
+    /// let rule = MaterialRule::parse("dvd = LDR/06:g; 007/00:v; 007/04:v").unwrap();
+    /// assert_eq!(rule.material_type(), "dvd");
+    /// assert_eq!(rule.conditions().len(), 3);
+    ///
+    /// assert!(MaterialRule::parse("dvd = LDR/xx:g").is_err());
+    /// assert!(MaterialRule::parse("dvd").is_err());
+    /// ```
+    pub fn parse(spec: &str) -> Result<MaterialRule, String> {
+        let (material_type, conditions) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid material type rule: {spec}"))?;
+
+        let material_type = material_type.trim();
+
+        if material_type.is_empty() {
+            return Err(format!("Material type rule has no type: {spec}"));
+        }
+
+        let conditions = conditions
+            .split(';')
+            .map(|c| c.trim())
+            .filter(|c| !c.is_empty())
+            .map(Condition::parse)
+            .collect::<Result<Vec<Condition>, String>>()?;
+
+        if conditions.is_empty() {
+            return Err(format!("Material type rule has no conditions: {spec}"));
+        }
+
+        Ok(MaterialRule::new(material_type, conditions))
+    }
+
+    pub fn material_type(&self) -> &str {
+        &self.material_type
+    }
+
+    pub fn conditions(&self) -> &Vec<Condition> {
+        &self.conditions
+    }
+
+    /// True if the record satisfies all of our conditions.
+    pub fn matches(&self, record: &Record) -> bool {
+        self.conditions.iter().all(|c| c.matches(record))
+    }
+}
+
+/// Ordered list of material type rules.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialTypeMap {
+    rules: Vec<MaterialRule>,
+}
+
+impl Default for MaterialTypeMap {
+    /// The built-in classification table.
+    fn default() -> Self {
+        MaterialTypeMap::parse(DEFAULT_MAP).expect("Default material type map is valid")
+    }
+}
+
+impl MaterialTypeMap {
+    /// Create an empty map.
+    pub fn new() -> MaterialTypeMap {
+        MaterialTypeMap { rules: Vec::new() }
+    }
+
+    /// Parse a map from its text form, one rule per line.
+    pub fn parse(text: &str) -> Result<MaterialTypeMap, String> {
+        let mut map = MaterialTypeMap::new();
+
+        for line in text.lines().map(|l| l.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            map.add_rule(MaterialRule::parse(line)?);
+        }
+
+        Ok(map)
+    }
+
+    pub fn rules(&self) -> &Vec<MaterialRule> {
+        &self.rules
+    }
+
+    /// Append a rule to the end of the table.
+    pub fn add_rule(&mut self, rule: MaterialRule) {
+        self.rules.push(rule);
+    }
+
+    /// Returns the distinct material types whose rules match the
+    /// record, in table order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::format::MaterialTypeMap;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=LDR 01234cgm a2200301 i 4500
+    /// =007 vd\cvaizq
+    /// =008 170301s2017\\\\cau060\\\\\\\\\\\\vleng\d"#
+    /// ).unwrap();
+    ///
+    /// let map = MaterialTypeMap::default();
+    /// assert_eq!(map.classify(&record), vec!["dvd"]);
+    ///
+    /// let map = MaterialTypeMap::parse("video = LDR/06:g\nmovie = 008/33:v").unwrap();
+    /// assert_eq!(map.classify(&record), vec!["video", "movie"]);
+    /// ```
+    pub fn classify(&self, record: &Record) -> Vec<String> {
+        let mut types: Vec<String> = Vec::new();
+
+        for rule in self.rules.iter() {
+            if types.iter().any(|t| t == rule.material_type()) {
+                continue;
+            }
+            if rule.matches(record) {
+                types.push(rule.material_type().to_string());
+            }
+        }
+
+        types
+    }
+}
+
+impl Record {
+    /// Material types for this record as determined by the default
+    /// [`MaterialTypeMap`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=LDR 01234nim a2200301 i 4500
+    /// =007 sd\fsngnnmmned
+    /// =008 170301s2017\\\\nyunnn\\\\\\\\\\\\\f\eng\d
+    /// =338 \\$aaudio disc$bsd$2rdacarrier"#
+    /// ).unwrap();
+    ///
+    /// assert_eq!(record.material_types(), vec!["cdaudiobook"]);
+    /// ```
+    pub fn material_types(&self) -> Vec<String> {
+        static DEFAULT: OnceLock<MaterialTypeMap> = OnceLock::new();
+        DEFAULT.get_or_init(MaterialTypeMap::default).classify(self)
+    }
+}
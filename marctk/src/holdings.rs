@@ -0,0 +1,421 @@
+//! MARC21 Format for Holdings Data (MFHD) helpers.
+//!
+//! Holdings records describe where a title is held (852) and which
+//! parts of it are held.  Captions and patterns (853-855) define the
+//! labels for each enumeration and chronology level, e.g. "v." and
+//! "no.", while the paired enumeration and chronology fields (863-865)
+//! contain the values.  A caption field is paired with its values via
+//! subfield $8, e.g. "1" in the 853 and "1.1", "1.2", etc. in the 863s.
+//!
+//! # References
+//!
+//! * <https://www.loc.gov/marc/holdings/>
+use super::display::clean_value;
+use super::Field;
+use super::Record;
+use std::fmt;
+
+/// Tag for location data.
+pub const LOCATION_TAG: &str = "852";
+
+/// Subfield code containing the field link and sequence number.
+pub const LINK_SUBFIELD: &str = "8";
+
+/// Enumeration subfield codes, from highest to lowest level.
+const ENUMERATION_SUBFIELDS: [&str; 6] = ["a", "b", "c", "d", "e", "f"];
+
+/// Chronology subfield codes, from highest to lowest level.
+const CHRONOLOGY_SUBFIELDS: [&str; 4] = ["i", "j", "k", "l"];
+
+/// Month and season abbreviations for chronology codes.
+const CHRONOLOGY_NAMES: [(&str, &str); 16] = [
+    ("01", "Jan."),
+    ("02", "Feb."),
+    ("03", "Mar."),
+    ("04", "Apr."),
+    ("05", "May"),
+    ("06", "June"),
+    ("07", "July"),
+    ("08", "Aug."),
+    ("09", "Sept."),
+    ("10", "Oct."),
+    ("11", "Nov."),
+    ("12", "Dec."),
+    ("21", "Spring"),
+    ("22", "Summer"),
+    ("23", "Autumn"),
+    ("24", "Winter"),
+];
+
+/// Holdings statements come in three flavors, each with its own
+/// caption, enumeration, and textual holdings tags.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HoldingsKind {
+    Basic,
+    Supplement,
+    Index,
+}
+
+impl HoldingsKind {
+    /// Captions and pattern tag.
+    pub fn caption_tag(&self) -> &'static str {
+        match self {
+            Self::Basic => "853",
+            Self::Supplement => "854",
+            Self::Index => "855",
+        }
+    }
+
+    /// Enumeration and chronology tag.
+    pub fn enumeration_tag(&self) -> &'static str {
+        match self {
+            Self::Basic => "863",
+            Self::Supplement => "864",
+            Self::Index => "865",
+        }
+    }
+
+    /// Textual holdings tag.
+    pub fn textual_tag(&self) -> &'static str {
+        match self {
+            Self::Basic => "866",
+            Self::Supplement => "867",
+            Self::Index => "868",
+        }
+    }
+}
+
+/// Location data from an 852 field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location<'a> {
+    field: &'a Field,
+}
+
+impl<'a> Location<'a> {
+    /// Returns None if the field is not an 852.
+    pub fn from_field(field: &'a Field) -> Option<Location<'a>> {
+        if field.tag() == LOCATION_TAG {
+            Some(Location { field })
+        } else {
+            None
+        }
+    }
+
+    pub fn field(&self) -> &'a Field {
+        self.field
+    }
+
+    fn value(&self, code: &str) -> Option<&'a str> {
+        self.field
+            .first_subfield(code)
+            .map(|sf| sf.content().trim())
+            .filter(|v| !v.is_empty())
+    }
+
+    /// Holding institution from $a.
+    pub fn institution(&self) -> Option<&'a str> {
+        self.value("a")
+    }
+
+    /// Sublocation or collection from $b.
+    pub fn sublocation(&self) -> Option<&'a str> {
+        self.value("b")
+    }
+
+    /// Shelving location from $c.
+    pub fn shelving_location(&self) -> Option<&'a str> {
+        self.value("c")
+    }
+
+    /// Copy number from $t.
+    pub fn copy_number(&self) -> Option<&'a str> {
+        self.value("t")
+    }
+
+    /// Full call number built from the prefix ($k), classification
+    /// part ($h), item part ($i), and suffix ($m).
+    pub fn call_number(&self) -> Option<String> {
+        let parts: Vec<&str> = ["k", "h", "i", "m"]
+            .iter()
+            .flat_map(|c| self.field.get_subfields(c))
+            .map(|sf| sf.content().trim())
+            .filter(|v| !v.is_empty())
+            .collect();
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join(" "))
+        }
+    }
+
+    /// Public notes from $z.
+    pub fn public_notes(&self) -> Vec<&'a str> {
+        self.field
+            .get_subfields("z")
+            .iter()
+            .map(|sf| sf.content().trim())
+            .filter(|v| !v.is_empty())
+            .collect()
+    }
+}
+
+/// Parse a $8 value into its link number and optional sequence number.
+fn parse_link(value: &str) -> Option<(u32, Option<u32>)> {
+    let mut parts = value.trim().split('.');
+
+    let link = parts.next()?.parse::<u32>().ok()?;
+    let seq = match parts.next() {
+        Some(s) => Some(s.parse::<u32>().ok()?),
+        None => None,
+    };
+
+    Some((link, seq))
+}
+
+fn field_link(field: &Field) -> Option<(u32, Option<u32>)> {
+    field
+        .first_subfield(LINK_SUBFIELD)
+        .and_then(|sf| parse_link(sf.content()))
+}
+
+/// One level of a holdings statement, e.g. the volume.
+struct Level<'a> {
+    caption: &'a str,
+    start: &'a str,
+    /// Empty for open-ended ranges.
+    end: &'a str,
+}
+
+impl Level<'_> {
+    /// Captions in parentheses, e.g. "(year)", are not displayed.
+    fn caption_hidden(&self) -> bool {
+        self.caption.starts_with('(') && self.caption.ends_with(')')
+    }
+
+    fn render(&self, value: &str) -> String {
+        if !self.caption_hidden() {
+            return format!("{}{value}", self.caption);
+        }
+
+        let caption = self.caption.to_lowercase();
+
+        if caption.contains("month") || caption.contains("season") {
+            if let Some((_, name)) = CHRONOLOGY_NAMES.iter().find(|(c, _)| *c == value) {
+                return name.to_string();
+            }
+        }
+
+        value.to_string()
+    }
+}
+
+/// A holdings enumeration/chronology field paired with its captions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enumeration<'a> {
+    caption: &'a Field,
+    holding: &'a Field,
+}
+
+impl<'a> Enumeration<'a> {
+    /// Captions and pattern field, e.g. 853.
+    pub fn caption_field(&self) -> &'a Field {
+        self.caption
+    }
+
+    /// Enumeration and chronology field, e.g. 863.
+    pub fn holding_field(&self) -> &'a Field {
+        self.holding
+    }
+
+    /// Sequence number from the holding field's $8.
+    pub fn sequence(&self) -> Option<u32> {
+        field_link(self.holding).and_then(|(_, seq)| seq)
+    }
+
+    fn levels(&self, codes: &[&str]) -> Vec<Level<'a>> {
+        let mut levels = Vec::new();
+
+        for code in codes {
+            let Some(caption) = self.caption.first_subfield(code) else {
+                continue;
+            };
+            let Some(value) = self.holding.first_subfield(code) else {
+                continue;
+            };
+
+            let value = value.content().trim();
+            if value.is_empty() {
+                continue;
+            }
+
+            let (start, end) = value.split_once('-').unwrap_or((value, value));
+
+            levels.push(Level {
+                caption: caption.content().trim(),
+                start,
+                end,
+            });
+        }
+
+        levels
+    }
+
+    /// Display form of the holdings, e.g. "v.1:no.1 (2020:Jan.)".
+    ///
+    /// Ranges are displayed as the first and last issue separated
+    /// by "-".  Open-ended ranges end with "-".
+    pub fn statement(&self) -> String {
+        let enumeration = self.levels(&ENUMERATION_SUBFIELDS);
+        let chronology = self.levels(&CHRONOLOGY_SUBFIELDS);
+
+        let side = |start: bool| {
+            let render = |levels: &[Level]| {
+                levels
+                    .iter()
+                    .map(|l| l.render(if start { l.start } else { l.end }))
+                    .collect::<Vec<String>>()
+                    .join(":")
+            };
+
+            match (render(&enumeration), render(&chronology)) {
+                (e, c) if c.is_empty() => e,
+                (e, c) if e.is_empty() => format!("({c})"),
+                (e, c) => format!("{e} ({c})"),
+            }
+        };
+
+        let first = side(true);
+
+        let open = enumeration
+            .iter()
+            .chain(chronology.iter())
+            .any(|l| l.end.is_empty());
+
+        if open {
+            return format!("{first}-");
+        }
+
+        let last = side(false);
+
+        if first == last {
+            first
+        } else {
+            format!("{first}-{last}")
+        }
+    }
+}
+
+impl fmt::Display for Enumeration<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.statement())
+    }
+}
+
+impl Record {
+    /// True if leader/06 identifies this as a holdings record.
+    pub fn is_holdings(&self) -> bool {
+        self.typed_leader()
+            .type_of_record()
+            .is_some_and(|t| t.is_holdings())
+    }
+
+    /// Location data from each 852 field.
+    pub fn holdings_locations(&self) -> Vec<Location<'_>> {
+        self.get_fields(LOCATION_TAG)
+            .into_iter()
+            .filter_map(Location::from_field)
+            .collect()
+    }
+
+    /// Enumeration fields paired with their captions via $8, sorted
+    /// by link and sequence number.
+    ///
+    /// Enumeration fields with no matching caption field are skipped.
+    pub fn holdings_enumerations(&self, kind: HoldingsKind) -> Vec<Enumeration<'_>> {
+        let captions: Vec<(u32, &Field)> = self
+            .get_fields(kind.caption_tag())
+            .into_iter()
+            .filter_map(|f| field_link(f).map(|(link, _)| (link, f)))
+            .collect();
+
+        let mut enumerations: Vec<((u32, Option<u32>), Enumeration)> = Vec::new();
+
+        for holding in self.get_fields(kind.enumeration_tag()) {
+            let Some((link, seq)) = field_link(holding) else {
+                continue;
+            };
+
+            if let Some((_, caption)) = captions.iter().find(|(l, _)| *l == link) {
+                enumerations.push(((link, seq), Enumeration { caption, holding }));
+            }
+        }
+
+        enumerations.sort_by_key(|(k, _)| *k);
+        enumerations.into_iter().map(|(_, e)| e).collect()
+    }
+
+    /// Summary holdings statement.
+    ///
+    /// Textual holdings (e.g. 866 $a) are used when present, since
+    /// they are the cataloger's own summary.  Otherwise, the statement
+    /// is generated from the paired caption and enumeration fields.
+    /// Statements are separated by "; ".
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::holdings::HoldingsKind;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=LDR 00000ny  a22000004n 4500
+    /// =852 01$aKCLS$bCEN$cPER$hPN1$iS6$zCurrent issues only
+    /// =853 20$81$av.$bno.$u12$vr$i(year)$j(month)
+    /// =863 40$81.2$a3$b1-$i2022$j01-
+    /// =863 40$81.1$a1-2$b1-12$i2020-2021$j01-12
+    /// =853 20$82$av.$i(year)
+    /// =863 40$82.1$a5$i2024"#
+    /// ).unwrap();
+    ///
+    /// assert!(record.is_holdings());
+    ///
+    /// let locations = record.holdings_locations();
+    /// assert_eq!(locations[0].institution(), Some("KCLS"));
+    /// assert_eq!(locations[0].shelving_location(), Some("PER"));
+    /// assert_eq!(locations[0].call_number().as_deref(), Some("PN1 S6"));
+    /// assert_eq!(locations[0].public_notes(), vec!["Current issues only"]);
+    ///
+    /// let enums = record.holdings_enumerations(HoldingsKind::Basic);
+    /// assert_eq!(enums.len(), 3);
+    /// assert_eq!(enums[0].sequence(), Some(1));
+    ///
+    /// assert_eq!(
+    ///     record.summary_holdings(HoldingsKind::Basic),
+    ///     "v.1:no.1 (2020:Jan.)-v.2:no.12 (2021:Dec.); v.3:no.1 (2022:Jan.)-; v.5 (2024)"
+    /// );
+    ///
+    /// assert_eq!(record.summary_holdings(HoldingsKind::Index), "");
+    /// ```
+    pub fn summary_holdings(&self, kind: HoldingsKind) -> String {
+        let textual: Vec<String> = self
+            .get_fields(kind.textual_tag())
+            .into_iter()
+            .flat_map(|f| f.get_subfields("a"))
+            .map(|sf| clean_value(sf.content()))
+            .filter(|v| !v.is_empty())
+            .collect();
+
+        let statements = if textual.is_empty() {
+            self.holdings_enumerations(kind)
+                .iter()
+                .map(|e| e.statement())
+                .filter(|s| !s.is_empty())
+                .collect()
+        } else {
+            textual
+        };
+
+        statements.join("; ")
+    }
+}
@@ -0,0 +1,169 @@
+//! Record wrapper with constant-time field lookup by tag.
+//!
+//! [`Record::get_fields`] and friends scan every field on each call,
+//! which adds up when a record is queried many times, e.g. while
+//! indexing.  [`IndexedRecord`] builds a tag index on first lookup and
+//! reuses it until the record is modified.
+use super::Controlfield;
+use super::Field;
+use super::Record;
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::ops::Deref;
+
+#[derive(Debug, Clone, Default)]
+struct TagIndex {
+    control_fields: HashMap<String, Vec<usize>>,
+    fields: HashMap<String, Vec<usize>>,
+}
+
+impl TagIndex {
+    fn build(record: &Record) -> TagIndex {
+        let mut index = TagIndex::default();
+
+        for (idx, cf) in record.control_fields().iter().enumerate() {
+            index
+                .control_fields
+                .entry(cf.tag().to_string())
+                .or_default()
+                .push(idx);
+        }
+
+        for (idx, field) in record.fields().iter().enumerate() {
+            index
+                .fields
+                .entry(field.tag().to_string())
+                .or_default()
+                .push(idx);
+        }
+
+        index
+    }
+}
+
+/// A [`Record`] with a tag index for repeated lookups.
+///
+/// Read-only [`Record`] methods are available via `Deref`.  Lookups by
+/// tag defined here use the index.  Modify the record via
+/// [`IndexedRecord::record_mut`], which discards the index so it is
+/// rebuilt on the next lookup.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::Record;
+/// use marctk::indexed::IndexedRecord;
+///
+/// let record = Record::from_breaker(
+///     "=001 123\n=650 \\0$aCats.\n=245 10$aTitle\n=650 \\0$aDogs."
+/// ).unwrap();
+///
+/// let mut indexed = IndexedRecord::from(record);
+///
+/// assert_eq!(indexed.get_field_values("650", "a"), vec!["Cats.", "Dogs."]);
+/// assert_eq!(indexed.get_control_fields("001")[0].content(), "123");
+/// assert!(indexed.has_field("245"));
+///
+/// // Non-indexed accessors are available as well.
+/// assert_eq!(indexed.fields().len(), 3);
+///
+/// indexed.record_mut().remove_fields("650");
+/// assert!(indexed.get_fields("650").is_empty());
+/// assert_eq!(indexed.into_record().fields().len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct IndexedRecord {
+    record: Record,
+    index: OnceCell<TagIndex>,
+}
+
+impl From<Record> for IndexedRecord {
+    fn from(record: Record) -> Self {
+        IndexedRecord::new(record)
+    }
+}
+
+impl Deref for IndexedRecord {
+    type Target = Record;
+
+    fn deref(&self) -> &Record {
+        &self.record
+    }
+}
+
+impl IndexedRecord {
+    pub fn new(record: Record) -> Self {
+        IndexedRecord {
+            record,
+            index: OnceCell::new(),
+        }
+    }
+
+    fn index(&self) -> &TagIndex {
+        self.index.get_or_init(|| TagIndex::build(&self.record))
+    }
+
+    pub fn record(&self) -> &Record {
+        &self.record
+    }
+
+    /// Mutable access to the record.  The tag index is discarded and
+    /// rebuilt on the next lookup.
+    pub fn record_mut(&mut self) -> &mut Record {
+        self.index = OnceCell::new();
+        &mut self.record
+    }
+
+    pub fn into_record(self) -> Record {
+        self.record
+    }
+
+    /// Return a list of control fields with the provided tag.
+    pub fn get_control_fields(&self, tag: &str) -> Vec<&Controlfield> {
+        let cfs = self.record.control_fields();
+
+        self.index()
+            .control_fields
+            .get(tag)
+            .map(|idxs| idxs.iter().map(|i| &cfs[*i]).collect())
+            .unwrap_or_default()
+    }
+
+    /// Return a list of fields with the provided tag.
+    pub fn get_fields(&self, tag: &str) -> Vec<&Field> {
+        let fields = self.record.fields();
+
+        self.index()
+            .fields
+            .get(tag)
+            .map(|idxs| idxs.iter().map(|i| &fields[*i]).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns a list of values for the specified tag and subfield.
+    pub fn get_field_values(&self, tag: &str, sfcode: &str) -> Vec<&str> {
+        self.get_fields(tag)
+            .into_iter()
+            .flat_map(|f| f.get_subfields(sfcode))
+            .map(|sf| sf.content())
+            .collect()
+    }
+
+    /// True if the record has a control field or field with the tag.
+    pub fn has_field(&self, tag: &str) -> bool {
+        let index = self.index();
+        index.fields.contains_key(tag) || index.control_fields.contains_key(tag)
+    }
+
+    /// Tags present in the record, in no particular order.
+    pub fn tags(&self) -> Vec<&str> {
+        let index = self.index();
+
+        index
+            .control_fields
+            .keys()
+            .chain(index.fields.keys())
+            .map(|t| t.as_str())
+            .collect()
+    }
+}
@@ -0,0 +1,260 @@
+//! Change journal for tracking what a pipeline did to a record.
+//!
+//! The journal is disabled by default.  Once enabled on a record,
+//! each change made within [`Record::edit`] is logged along with the
+//! name of the rule which made it.  Changes are detected by comparing
+//! the record before and after the edit (see [`crate::diff`]), so
+//! edits made via any API, including [`Record::fields_mut`], are
+//! captured.
+use super::diff::Change;
+use super::Record;
+
+/// Kind of change recorded in the journal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JournalAction {
+    LeaderChanged,
+    FieldAdded,
+    FieldRemoved,
+    FieldChanged,
+}
+
+impl JournalAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::LeaderChanged => "leader_changed",
+            Self::FieldAdded => "field_added",
+            Self::FieldRemoved => "field_removed",
+            Self::FieldChanged => "field_changed",
+        }
+    }
+}
+
+/// A single journaled change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    action: JournalAction,
+    tag: String,
+    before: Option<String>,
+    after: Option<String>,
+    rule: Option<String>,
+}
+
+impl JournalEntry {
+    fn from_change(change: &Change, rule: Option<&str>) -> Self {
+        let (action, before, after) = match change {
+            Change::Leader { old, new } => (
+                JournalAction::LeaderChanged,
+                Some(old.to_string()),
+                Some(new.to_string()),
+            ),
+            Change::ControlFieldAdded(f) => (JournalAction::FieldAdded, None, Some(f.to_breaker())),
+            Change::ControlFieldRemoved(f) => {
+                (JournalAction::FieldRemoved, Some(f.to_breaker()), None)
+            }
+            Change::ControlFieldModified { old, new } => (
+                JournalAction::FieldChanged,
+                Some(old.to_breaker()),
+                Some(new.to_breaker()),
+            ),
+            Change::FieldAdded(f) => (JournalAction::FieldAdded, None, Some(f.to_breaker())),
+            Change::FieldRemoved(f) => (JournalAction::FieldRemoved, Some(f.to_breaker()), None),
+            Change::FieldModified { old, new, .. } => (
+                JournalAction::FieldChanged,
+                Some(old.to_breaker()),
+                Some(new.to_breaker()),
+            ),
+        };
+
+        JournalEntry {
+            action,
+            tag: change.tag().to_string(),
+            before,
+            after,
+            rule: rule.map(|r| r.to_string()),
+        }
+    }
+
+    pub fn action(&self) -> JournalAction {
+        self.action
+    }
+
+    /// Tag of the changed field or "LDR" for the leader.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Breaker text of the field (or leader value) before the change.
+    pub fn before(&self) -> Option<&str> {
+        self.before.as_deref()
+    }
+
+    /// Breaker text of the field (or leader value) after the change.
+    pub fn after(&self) -> Option<&str> {
+        self.after.as_deref()
+    }
+
+    /// Name of the rule which made the change.
+    pub fn rule(&self) -> Option<&str> {
+        self.rule.as_deref()
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            r#"{{"action":{},"tag":{},"before":{},"after":{},"rule":{}}}"#,
+            json_string(Some(self.action.as_str())),
+            json_string(Some(&self.tag)),
+            json_string(self.before()),
+            json_string(self.after()),
+            json_string(self.rule()),
+        )
+    }
+}
+
+/// Ordered list of changes made to a record.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChangeJournal {
+    entries: Vec<JournalEntry>,
+}
+
+impl ChangeJournal {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn entries(&self) -> &Vec<JournalEntry> {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Log the changes needed to turn one version of a record into
+    /// another.
+    pub fn record(&mut self, before: &Record, after: &Record, rule: Option<&str>) {
+        for change in before.diff(after).changes() {
+            self.entries.push(JournalEntry::from_change(change, rule));
+        }
+    }
+
+    /// Add an entry from a diff [`Change`].
+    pub fn record_change(&mut self, change: &Change, rule: Option<&str>) {
+        self.entries.push(JournalEntry::from_change(change, rule));
+    }
+
+    /// JSON array of entries.
+    pub fn to_json(&self) -> String {
+        let entries: Vec<String> = self.entries.iter().map(|e| e.to_json()).collect();
+        format!("[{}]", entries.join(","))
+    }
+}
+
+/// JSON string literal or null.
+fn json_string(value: Option<&str>) -> String {
+    let Some(value) = value else {
+        return "null".to_string();
+    };
+
+    let mut s = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if (c as u32) < 0x20 => s.push_str(&format!("\\u{:04x}", c as u32)),
+            c => s.push(c),
+        }
+    }
+
+    s.push('"');
+    s
+}
+
+impl Record {
+    /// Start journaling changes made via [`Record::edit`].
+    ///
+    /// Has no effect if the journal is already enabled.
+    pub fn enable_journal(&mut self) {
+        if self.journal.is_none() {
+            self.journal = Some(ChangeJournal::new());
+        }
+    }
+
+    /// Stop journaling and discard any journal entries.
+    pub fn disable_journal(&mut self) {
+        self.journal = None;
+    }
+
+    /// The journal, if enabled.
+    pub fn journal(&self) -> Option<&ChangeJournal> {
+        self.journal.as_ref()
+    }
+
+    /// Mutable access to the journal, if enabled.
+    pub fn journal_mut(&mut self) -> Option<&mut ChangeJournal> {
+        self.journal.as_mut()
+    }
+
+    /// Remove and return the journal, disabling journaling.
+    pub fn take_journal(&mut self) -> Option<ChangeJournal> {
+        self.journal.take()
+    }
+
+    /// Apply an edit to this record, logging the changes it makes
+    /// under the provided rule name if the journal is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let mut record = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =245 10$aTitle.
+    /// =650 \0$aCats.
+    /// =901 \\$aLocal"#
+    /// ).unwrap();
+    ///
+    /// record.enable_journal();
+    ///
+    /// record.edit("strip-local", |r| r.remove_fields("901"));
+    ///
+    /// record.edit("fix-subject", |r| {
+    ///     r.get_fields_mut("650")[0]
+    ///         .first_subfield_mut("a")
+    ///         .unwrap()
+    ///         .set_content("Felines.");
+    /// });
+    ///
+    /// let journal = record.journal().unwrap();
+    /// assert_eq!(journal.entries().len(), 2);
+    /// assert_eq!(journal.entries()[0].rule(), Some("strip-local"));
+    /// assert_eq!(journal.entries()[1].before(), Some(r#"=650 \0$aCats."#));
+    ///
+    /// assert_eq!(
+    ///     journal.to_json(),
+    ///     r#"[{"action":"field_removed","tag":"901","before":"=901 \\\\$aLocal","after":null,"rule":"strip-local"},{"action":"field_changed","tag":"650","before":"=650 \\0$aCats.","after":"=650 \\0$aFelines.","rule":"fix-subject"}]"#
+    /// );
+    ///
+    /// // The journal does not affect record equality.
+    /// let mut other = record.clone();
+    /// other.disable_journal();
+    /// assert_eq!(record, other);
+    /// ```
+    pub fn edit<T>(&mut self, rule: &str, edit: impl FnOnce(&mut Record) -> T) -> T {
+        let Some(mut journal) = self.journal.take() else {
+            return edit(self);
+        };
+
+        let before = self.clone();
+        let result = edit(self);
+
+        journal.record(&before, self, Some(rule));
+        self.journal = Some(journal);
+
+        result
+    }
+}
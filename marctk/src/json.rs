@@ -0,0 +1,169 @@
+//! Routines for reading and writing MARC-in-JSON.
+//!
+//! Each record is a JSON object containing the leader and an ordered
+//! list of fields.  Each field is an object with a single key, the tag.
+//! Control field values are strings.  Data field values are objects
+//! with ind1, ind2, and an ordered list of single-key subfield objects.
+//!
+//! ```text
+//! {
+//!   "leader": "00000nam a2200000 a 4500",
+//!   "fields": [
+//!     {"001": "123"},
+//!     {"245": {"ind1": "1", "ind2": "0", "subfields": [{"a": "A title"}]}}
+//!   ]
+//! }
+//! ```
+//!
+//! # References
+//!
+//! * <https://github.com/marc4j/marc4j/wiki/MARC-in-JSON-Description>
+use super::Controlfield;
+use super::Field;
+use super::Record;
+use super::Subfield;
+use json::JsonValue;
+
+impl Record {
+    /// Generate a MARC-in-JSON value for a [`Record`].
+    pub fn to_json(&self) -> JsonValue {
+        let mut fields = JsonValue::new_array();
+
+        for cf in self.control_fields() {
+            let mut field = JsonValue::new_object();
+            field[cf.tag()] = JsonValue::from(cf.content());
+            fields.push(field).ok();
+        }
+
+        for df in self.fields() {
+            let mut subfields = JsonValue::new_array();
+
+            for sf in df.subfields() {
+                let mut subfield = JsonValue::new_object();
+                subfield[sf.code()] = JsonValue::from(sf.content());
+                subfields.push(subfield).ok();
+            }
+
+            let mut field = JsonValue::new_object();
+            field[df.tag()] = json::object! {
+                "ind1": df.ind1(),
+                "ind2": df.ind2(),
+                "subfields": subfields,
+            };
+
+            fields.push(field).ok();
+        }
+
+        json::object! {
+            "leader": self.leader(),
+            "fields": fields,
+        }
+    }
+
+    /// Generate a compact MARC-in-JSON string for a [`Record`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     "=LDR 00000nam a2200000 a 4500\n=001 123\n=245 10$aA title"
+    /// ).unwrap();
+    ///
+    /// assert_eq!(
+    ///     record.to_json_string(),
+    ///     r#"{"leader":"00000nam a2200000 a 4500","fields":[{"001":"123"},{"245":{"ind1":"1","ind2":"0","subfields":[{"a":"A title"}]}}]}"#
+    /// );
+    /// ```
+    pub fn to_json_string(&self) -> String {
+        self.to_json().dump()
+    }
+
+    /// Create a MARC [`Record`] from a MARC-in-JSON string.
+    ///
+    /// Assumes one record per input string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     "=LDR 00000nam a2200000 a 4500\n=008 123\n=245 1\\$aA title$cMe"
+    /// ).unwrap();
+    ///
+    /// let record2 = Record::from_json(&record.to_json_string()).unwrap();
+    /// assert_eq!(record.to_breaker(), record2.to_breaker());
+    ///
+    /// assert!(Record::from_json(r#"{"fields": [{"245": "oops"}]}"#).is_err());
+    /// assert!(Record::from_json("[]").is_err());
+    /// ```
+    pub fn from_json(text: &str) -> Result<Record, String> {
+        let value = json::parse(text).map_err(|e| format!("Invalid JSON: {e}"))?;
+        Record::from_json_value(&value)
+    }
+
+    /// Create a MARC [`Record`] from a parsed MARC-in-JSON value.
+    pub fn from_json_value(value: &JsonValue) -> Result<Record, String> {
+        if !value.is_object() {
+            return Err(format!("MARC-in-JSON record must be an object: {value}"));
+        }
+
+        let mut record = Record::new();
+
+        if let Some(leader) = value["leader"].as_str() {
+            record.set_leader(leader)?;
+        }
+
+        for field in value["fields"].members() {
+            let (tag, content) = field
+                .entries()
+                .next()
+                .ok_or_else(|| format!("Invalid MARC-in-JSON field: {field}"))?;
+
+            if tag < "010" {
+                let content = content
+                    .as_str()
+                    .ok_or_else(|| format!("Invalid MARC-in-JSON control field: {field}"))?;
+
+                record
+                    .control_fields_mut()
+                    .push(Controlfield::new(tag, content)?);
+
+                continue;
+            }
+
+            if !content.is_object() {
+                return Err(format!("Invalid MARC-in-JSON data field: {field}"));
+            }
+
+            let mut df = Field::new(tag)?;
+
+            if let Some(ind) = content["ind1"].as_str() {
+                df.set_ind1(ind)?;
+            }
+
+            if let Some(ind) = content["ind2"].as_str() {
+                df.set_ind2(ind)?;
+            }
+
+            for subfield in content["subfields"].members() {
+                let (code, value) = subfield
+                    .entries()
+                    .next()
+                    .ok_or_else(|| format!("Invalid MARC-in-JSON subfield: {subfield}"))?;
+
+                let value = value
+                    .as_str()
+                    .ok_or_else(|| format!("Invalid MARC-in-JSON subfield: {subfield}"))?;
+
+                df.subfields_mut().push(Subfield::new(code, value)?);
+            }
+
+            record.fields_mut().push(df);
+        }
+
+        Ok(record)
+    }
+}
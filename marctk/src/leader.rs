@@ -0,0 +1,411 @@
+//! Typed access to the record leader.
+//!
+//! The leader is stored as its raw 24-byte string.  Accessors parse
+//! the relevant position on demand, returning None for values not
+//! defined by MARC21, so records with nonstandard leaders can still
+//! be read and written unchanged.
+//!
+//! # References
+//!
+//! * <https://www.loc.gov/marc/bibliographic/bdleader.html>
+use std::fmt;
+
+pub const LEADER_SIZE: usize = 24;
+pub const DEFAULT_LEADER: &str = "                        ";
+
+const RECORD_STATUS_IDX: usize = 5;
+const TYPE_OF_RECORD_IDX: usize = 6;
+const BIB_LEVEL_IDX: usize = 7;
+const CHAR_CODING_IDX: usize = 9;
+const ENCODING_LEVEL_IDX: usize = 17;
+
+/// Leader/05
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordStatus {
+    IncreaseInEncodingLevel,
+    Corrected,
+    Deleted,
+    New,
+    IncreaseFromPrepublication,
+}
+
+impl RecordStatus {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'a' => Some(Self::IncreaseInEncodingLevel),
+            'c' => Some(Self::Corrected),
+            'd' => Some(Self::Deleted),
+            'n' => Some(Self::New),
+            'p' => Some(Self::IncreaseFromPrepublication),
+            _ => None,
+        }
+    }
+
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::IncreaseInEncodingLevel => 'a',
+            Self::Corrected => 'c',
+            Self::Deleted => 'd',
+            Self::New => 'n',
+            Self::IncreaseFromPrepublication => 'p',
+        }
+    }
+}
+
+/// Leader/06
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TypeOfRecord {
+    LanguageMaterial,
+    NotatedMusic,
+    ManuscriptNotatedMusic,
+    CartographicMaterial,
+    ManuscriptCartographicMaterial,
+    ProjectedMedium,
+    NonmusicalSoundRecording,
+    MusicalSoundRecording,
+    NonprojectableGraphic,
+    ComputerFile,
+    Kit,
+    MixedMaterials,
+    CommunityInformation,
+    ThreeDimensionalArtifact,
+    ManuscriptLanguageMaterial,
+    UnknownHoldings,
+    MultipartItemHoldings,
+    ClassificationData,
+    SinglePartItemHoldings,
+    SerialItemHoldings,
+    Authority,
+}
+
+impl TypeOfRecord {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'a' => Some(Self::LanguageMaterial),
+            'c' => Some(Self::NotatedMusic),
+            'd' => Some(Self::ManuscriptNotatedMusic),
+            'e' => Some(Self::CartographicMaterial),
+            'f' => Some(Self::ManuscriptCartographicMaterial),
+            'g' => Some(Self::ProjectedMedium),
+            'i' => Some(Self::NonmusicalSoundRecording),
+            'j' => Some(Self::MusicalSoundRecording),
+            'k' => Some(Self::NonprojectableGraphic),
+            'm' => Some(Self::ComputerFile),
+            'o' => Some(Self::Kit),
+            'p' => Some(Self::MixedMaterials),
+            'q' => Some(Self::CommunityInformation),
+            'r' => Some(Self::ThreeDimensionalArtifact),
+            't' => Some(Self::ManuscriptLanguageMaterial),
+            'u' => Some(Self::UnknownHoldings),
+            'v' => Some(Self::MultipartItemHoldings),
+            'w' => Some(Self::ClassificationData),
+            'x' => Some(Self::SinglePartItemHoldings),
+            'y' => Some(Self::SerialItemHoldings),
+            'z' => Some(Self::Authority),
+            _ => None,
+        }
+    }
+
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::LanguageMaterial => 'a',
+            Self::NotatedMusic => 'c',
+            Self::ManuscriptNotatedMusic => 'd',
+            Self::CartographicMaterial => 'e',
+            Self::ManuscriptCartographicMaterial => 'f',
+            Self::ProjectedMedium => 'g',
+            Self::NonmusicalSoundRecording => 'i',
+            Self::MusicalSoundRecording => 'j',
+            Self::NonprojectableGraphic => 'k',
+            Self::ComputerFile => 'm',
+            Self::Kit => 'o',
+            Self::MixedMaterials => 'p',
+            Self::CommunityInformation => 'q',
+            Self::ThreeDimensionalArtifact => 'r',
+            Self::ManuscriptLanguageMaterial => 't',
+            Self::UnknownHoldings => 'u',
+            Self::MultipartItemHoldings => 'v',
+            Self::ClassificationData => 'w',
+            Self::SinglePartItemHoldings => 'x',
+            Self::SerialItemHoldings => 'y',
+            Self::Authority => 'z',
+        }
+    }
+
+    /// True for the bibliographic record types.
+    pub fn is_bibliographic(&self) -> bool {
+        "acdefgijkmoprt".contains(self.as_char())
+    }
+
+    /// True for the holdings record types.
+    pub fn is_holdings(&self) -> bool {
+        "uvxy".contains(self.as_char())
+    }
+}
+
+/// Leader/07
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BibliographicLevel {
+    MonographicComponentPart,
+    SerialComponentPart,
+    Collection,
+    Subunit,
+    IntegratingResource,
+    Monograph,
+    Serial,
+}
+
+impl BibliographicLevel {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'a' => Some(Self::MonographicComponentPart),
+            'b' => Some(Self::SerialComponentPart),
+            'c' => Some(Self::Collection),
+            'd' => Some(Self::Subunit),
+            'i' => Some(Self::IntegratingResource),
+            'm' => Some(Self::Monograph),
+            's' => Some(Self::Serial),
+            _ => None,
+        }
+    }
+
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::MonographicComponentPart => 'a',
+            Self::SerialComponentPart => 'b',
+            Self::Collection => 'c',
+            Self::Subunit => 'd',
+            Self::IntegratingResource => 'i',
+            Self::Monograph => 'm',
+            Self::Serial => 's',
+        }
+    }
+}
+
+/// Leader/09
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CharacterCoding {
+    Marc8,
+    Unicode,
+}
+
+impl CharacterCoding {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            ' ' => Some(Self::Marc8),
+            'a' => Some(Self::Unicode),
+            _ => None,
+        }
+    }
+
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::Marc8 => ' ',
+            Self::Unicode => 'a',
+        }
+    }
+}
+
+/// Leader/17
+///
+/// Only the MARC21 values are supported.  Locally defined values,
+/// like OCLC's "I" and "K", are reported as None.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EncodingLevel {
+    Full,
+    FullNotExamined,
+    LessThanFullNotExamined,
+    Abbreviated,
+    Core,
+    Partial,
+    Minimal,
+    Prepublication,
+    Unknown,
+    NotApplicable,
+}
+
+impl EncodingLevel {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            ' ' => Some(Self::Full),
+            '1' => Some(Self::FullNotExamined),
+            '2' => Some(Self::LessThanFullNotExamined),
+            '3' => Some(Self::Abbreviated),
+            '4' => Some(Self::Core),
+            '5' => Some(Self::Partial),
+            '7' => Some(Self::Minimal),
+            '8' => Some(Self::Prepublication),
+            'u' => Some(Self::Unknown),
+            'z' => Some(Self::NotApplicable),
+            _ => None,
+        }
+    }
+
+    pub fn as_char(&self) -> char {
+        match self {
+            Self::Full => ' ',
+            Self::FullNotExamined => '1',
+            Self::LessThanFullNotExamined => '2',
+            Self::Abbreviated => '3',
+            Self::Core => '4',
+            Self::Partial => '5',
+            Self::Minimal => '7',
+            Self::Prepublication => '8',
+            Self::Unknown => 'u',
+            Self::NotApplicable => 'z',
+        }
+    }
+}
+
+/// A record leader.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leader {
+    value: String,
+}
+
+impl Default for Leader {
+    fn default() -> Self {
+        Leader {
+            value: DEFAULT_LEADER.to_string(),
+        }
+    }
+}
+
+impl Leader {
+    /// Create a leader from a string value.
+    ///
+    /// Returns Err if the value is not composed of the correct number
+    /// of bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::leader::Leader;
+    ///
+    /// assert!(Leader::new("too short").is_err());
+    /// assert!(Leader::new("00000nam a2200000 a 4500").is_ok());
+    /// ```
+    pub fn new(value: impl Into<String>) -> Result<Self, String> {
+        let value = value.into();
+        let byte_len = value.len();
+
+        if byte_len != LEADER_SIZE {
+            return Err(format!(
+                "Invalid byte count for leader {value} wanted={LEADER_SIZE} found={byte_len}"
+            ));
+        }
+
+        Ok(Leader { value })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+
+    /// Character at the provided position, if it's an ASCII character.
+    pub fn position(&self, idx: usize) -> Option<char> {
+        self.value
+            .as_bytes()
+            .get(idx)
+            .filter(|b| b.is_ascii())
+            .map(|b| *b as char)
+    }
+
+    /// Replace the ASCII character at the provided position.
+    ///
+    /// Returns Err if the position is out of range or if either the
+    /// new or the existing value is not ASCII.
+    pub fn set_position(&mut self, idx: usize, value: char) -> Result<(), String> {
+        if !value.is_ascii() {
+            return Err(format!("Leader values must be ASCII: '{value}'"));
+        }
+
+        if self.position(idx).is_none() {
+            return Err(format!(
+                "Leader position {idx} is invalid for leader '{}'",
+                self.value
+            ));
+        }
+
+        self.value
+            .replace_range(idx..idx + 1, value.encode_utf8(&mut [0; 1]));
+
+        Ok(())
+    }
+
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::leader::{Leader, RecordStatus};
+    ///
+    /// let mut leader = Leader::new("00000nam a2200000 a 4500").unwrap();
+    /// assert_eq!(leader.record_status(), Some(RecordStatus::New));
+    ///
+    /// leader.set_record_status(RecordStatus::Corrected).unwrap();
+    /// assert_eq!(leader.as_str(), "00000cam a2200000 a 4500");
+    /// ```
+    pub fn record_status(&self) -> Option<RecordStatus> {
+        self.position(RECORD_STATUS_IDX)
+            .and_then(RecordStatus::from_char)
+    }
+
+    pub fn set_record_status(&mut self, value: RecordStatus) -> Result<(), String> {
+        self.set_position(RECORD_STATUS_IDX, value.as_char())
+    }
+
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::leader::{Leader, TypeOfRecord};
+    ///
+    /// let mut leader = Leader::new("00000nam a2200000 a 4500").unwrap();
+    /// assert_eq!(leader.type_of_record(), Some(TypeOfRecord::LanguageMaterial));
+    ///
+    /// leader.set_type_of_record(TypeOfRecord::ProjectedMedium).unwrap();
+    /// assert_eq!(leader.as_str(), "00000ngm a2200000 a 4500");
+    ///
+    /// let leader = Leader::new("00000n#m a2200000 a 4500").unwrap();
+    /// assert_eq!(leader.type_of_record(), None);
+    /// ```
+    pub fn type_of_record(&self) -> Option<TypeOfRecord> {
+        self.position(TYPE_OF_RECORD_IDX)
+            .and_then(TypeOfRecord::from_char)
+    }
+
+    pub fn set_type_of_record(&mut self, value: TypeOfRecord) -> Result<(), String> {
+        self.set_position(TYPE_OF_RECORD_IDX, value.as_char())
+    }
+
+    pub fn bibliographic_level(&self) -> Option<BibliographicLevel> {
+        self.position(BIB_LEVEL_IDX)
+            .and_then(BibliographicLevel::from_char)
+    }
+
+    pub fn set_bibliographic_level(&mut self, value: BibliographicLevel) -> Result<(), String> {
+        self.set_position(BIB_LEVEL_IDX, value.as_char())
+    }
+
+    pub fn character_coding(&self) -> Option<CharacterCoding> {
+        self.position(CHAR_CODING_IDX)
+            .and_then(CharacterCoding::from_char)
+    }
+
+    pub fn set_character_coding(&mut self, value: CharacterCoding) -> Result<(), String> {
+        self.set_position(CHAR_CODING_IDX, value.as_char())
+    }
+
+    pub fn encoding_level(&self) -> Option<EncodingLevel> {
+        self.position(ENCODING_LEVEL_IDX)
+            .and_then(EncodingLevel::from_char)
+    }
+
+    pub fn set_encoding_level(&mut self, value: EncodingLevel) -> Result<(), String> {
+        self.set_position(ENCODING_LEVEL_IDX, value.as_char())
+    }
+}
+
+impl fmt::Display for Leader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
@@ -1,7 +1,8 @@
 #![forbid(unsafe_code)]
 
 //! Tools for managing MARC21 records and reading/writing records as
-//! binary, XML, and MARC breaker.
+//! binary, XML, MARC breaker, and MARC-in-JSON, with basic UNIMARC
+//! support.
 
 pub use self::record::Controlfield;
 pub use self::record::Field;
@@ -11,8 +12,43 @@ pub use self::xml::MARCXML_NAMESPACE;
 pub use self::xml::MARCXML_SCHEMA_LOCATION;
 pub use self::xml::MARCXML_XSI_NAMESPACE;
 
+pub mod aleph;
+#[cfg(feature = "async")]
+pub mod async_io;
+#[cfg(feature = "marc21_authority")]
+pub mod authority;
+#[cfg(feature = "marc21_bibliographic")]
+pub mod bibliographic;
 pub mod binary;
 pub mod breaker;
+pub mod builder;
+pub mod callnumber;
+pub mod control_number;
+pub mod crosswalk;
+pub mod diff;
+pub mod display;
+pub mod edit;
+pub mod fingerprint;
+pub mod fixed_fields;
+pub mod format;
+pub mod holdings;
+pub mod indexed;
+pub mod journal;
+pub mod json;
+pub mod leader;
+pub mod linkage;
+pub mod marc8;
+pub mod matcher;
+pub mod merge;
+pub mod normalize;
+pub mod order;
 mod query;
 pub mod record;
+pub mod replace;
+#[cfg(feature = "serde")]
+mod serde_support;
+pub mod standard_numbers;
+pub mod unimarc;
+pub mod validate;
+pub mod visitor;
 pub mod xml;
@@ -0,0 +1,563 @@
+//! Alternate graphic representation (880) linkage.
+//!
+//! Fields containing non-Latin script are recorded in 880 fields which
+//! link to their regular (romanized) counterparts via subfield $6, e.g.
+//! "880-01/(N" in a 245 and "245-01/(N" in the matching 880.  880s with
+//! no regular counterpart use occurrence number "00".
+use super::Field;
+use super::Record;
+use std::fmt;
+
+/// Tag used for alternate graphic representation fields.
+pub const ALT_GRAPHIC_TAG: &str = "880";
+
+/// Subfield code containing linkage data.
+pub const LINKAGE_SUBFIELD: &str = "6";
+
+/// Occurrence number used by 880s that have no regular counterpart.
+pub const UNLINKED_OCCURRENCE: u16 = 0;
+
+/// Highest occurrence number, which is limited to two digits.
+pub const MAX_OCCURRENCE: u16 = 99;
+
+/// Parsed contents of a $6 linkage subfield.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Linkage {
+    tag: String,
+    occurrence: u16,
+    script: Option<String>,
+    right_to_left: bool,
+}
+
+impl Linkage {
+    pub fn new(tag: &str, occurrence: u16) -> Self {
+        Linkage {
+            tag: tag.to_string(),
+            occurrence,
+            script: None,
+            right_to_left: false,
+        }
+    }
+
+    /// Parse a $6 value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::linkage::Linkage;
+    ///
+    /// let link = Linkage::parse("245-01/(N").unwrap();
+    /// assert_eq!(link.tag(), "245");
+    /// assert_eq!(link.occurrence(), 1);
+    /// assert_eq!(link.script(), Some("(N"));
+    /// assert!(!link.right_to_left());
+    ///
+    /// let link = Linkage::parse("880-12/(3/r").unwrap();
+    /// assert_eq!(link.to_string(), "880-12/(3/r");
+    ///
+    /// assert!(Linkage::parse("245").is_err());
+    /// assert!(Linkage::parse("245-xx").is_err());
+    /// ```
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut parts = value.trim().split('/');
+
+        let link = parts.next().unwrap_or("");
+        let (tag, occurrence) = link
+            .split_once('-')
+            .ok_or_else(|| format!("Invalid linkage: '{value}'"))?;
+
+        if tag.len() != 3 {
+            return Err(format!("Invalid linkage tag: '{value}'"));
+        }
+
+        let occurrence = occurrence
+            .parse::<u16>()
+            .map_err(|_| format!("Invalid linkage occurrence: '{value}'"))?;
+
+        let mut linkage = Linkage::new(tag, occurrence);
+
+        for part in parts {
+            if part == "r" {
+                linkage.right_to_left = true;
+            } else if !part.is_empty() {
+                linkage.script = Some(part.to_string());
+            }
+        }
+
+        Ok(linkage)
+    }
+
+    /// Tag of the linked field.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn set_tag(&mut self, tag: &str) {
+        self.tag = tag.to_string();
+    }
+
+    pub fn occurrence(&self) -> u16 {
+        self.occurrence
+    }
+
+    pub fn set_occurrence(&mut self, occurrence: u16) {
+        self.occurrence = occurrence;
+    }
+
+    /// Script identification code, e.g. "(N" for Cyrillic.
+    pub fn script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    pub fn set_script(&mut self, script: Option<&str>) {
+        self.script = script.map(|s| s.to_string());
+    }
+
+    pub fn right_to_left(&self) -> bool {
+        self.right_to_left
+    }
+
+    pub fn set_right_to_left(&mut self, rtl: bool) {
+        self.right_to_left = rtl;
+    }
+}
+
+impl fmt::Display for Linkage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{:02}", self.tag, self.occurrence)?;
+        if let Some(s) = self.script.as_deref() {
+            write!(f, "/{s}")?;
+        }
+        if self.right_to_left {
+            write!(f, "/r")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the MARC script identification code for the first non-Latin
+/// script found in the text, or None if the text only contains
+/// Latin (or unidentified) characters.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::linkage::detect_script;
+///
+/// assert_eq!(detect_script("Война и мир"), Some("(N"));
+/// assert_eq!(detect_script("1984 — 東京"), Some("$1"));
+/// assert_eq!(detect_script("שלום"), Some("(2"));
+/// assert_eq!(detect_script("War and peace"), None);
+/// ```
+pub fn detect_script(text: &str) -> Option<&'static str> {
+    text.chars().find_map(|c| match c as u32 {
+        0x0600..=0x06FF | 0x0750..=0x077F | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => Some("(3"),
+        0x0590..=0x05FF | 0xFB1D..=0xFB4F => Some("(2"),
+        0x0400..=0x04FF => Some("(N"),
+        0x0370..=0x03FF => Some("(S"),
+        0x1100..=0x11FF
+        | 0x3040..=0x30FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xAC00..=0xD7AF
+        | 0xF900..=0xFAFF => Some("$1"),
+        _ => None,
+    })
+}
+
+/// True if the script identification code represents a
+/// right-to-left script.
+fn is_rtl_script(script: &str) -> bool {
+    script == "(3" || script == "(2"
+}
+
+impl Field {
+    /// Parsed linkage from the first $6 in this field, if present
+    /// and valid.
+    pub fn linkage(&self) -> Option<Linkage> {
+        self.first_subfield(LINKAGE_SUBFIELD)
+            .and_then(|sf| Linkage::parse(sf.content()).ok())
+    }
+
+    /// Set the $6 linkage, replacing any existing $6.
+    ///
+    /// Linkage is always the first subfield in the field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Field;
+    /// use marctk::linkage::Linkage;
+    ///
+    /// let mut field = Field::new("245").unwrap();
+    /// field.add_subfield("a", "Voĭna i mir").unwrap();
+    /// field.set_linkage(&Linkage::new("880", 3));
+    ///
+    /// assert_eq!(field.subfields()[0].code(), "6");
+    /// assert_eq!(field.subfields()[0].content(), "880-03");
+    /// ```
+    pub fn set_linkage(&mut self, linkage: &Linkage) {
+        self.remove_subfields(LINKAGE_SUBFIELD);
+
+        // Subfield code "6" always passes validation.
+        if let Ok(sf) = super::Subfield::new(LINKAGE_SUBFIELD, linkage.to_string()) {
+            self.subfields_mut().insert(0, sf);
+        }
+    }
+
+    /// Remove the $6 linkage from this field.
+    pub fn remove_linkage(&mut self) {
+        self.remove_subfields(LINKAGE_SUBFIELD);
+    }
+
+    /// Text content of the field, excluding control subfields (digits).
+    fn text(&self) -> String {
+        self.subfields()
+            .iter()
+            .filter(|sf| !sf.code().chars().all(|c| c.is_ascii_digit()))
+            .map(|sf| sf.content())
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+}
+
+impl Record {
+    /// Returns the 880 linked to the provided regular field, or the
+    /// regular field linked to the provided 880.
+    pub fn linked_field(&self, field: &Field) -> Option<&Field> {
+        let link = field.linkage()?;
+
+        if link.occurrence() == UNLINKED_OCCURRENCE {
+            return None;
+        }
+
+        self.get_fields(link.tag()).into_iter().find(|f| {
+            f.linkage()
+                .map(|l| l.tag() == field.tag() && l.occurrence() == link.occurrence())
+                .unwrap_or(false)
+        })
+    }
+
+    /// Returns the next unused linkage occurrence number.
+    ///
+    /// Numbers follow the highest occurrence in use.  Once 99 is in
+    /// use, the lowest unused number is returned instead, or None if
+    /// every number from 01 to 99 is in use.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     "=245 10$6880-01$aVoĭna i mir\n=880 10$6245-01/(N$aВойна и мир"
+    /// ).unwrap();
+    ///
+    /// assert_eq!(record.next_linkage_occurrence(), Some(2));
+    ///
+    /// let record = Record::from_breaker(
+    ///     "=245 10$6880-99$aVoĭna i mir\n=880 10$6245-99/(N$aВойна и мир"
+    /// ).unwrap();
+    ///
+    /// assert_eq!(record.next_linkage_occurrence(), Some(1));
+    /// ```
+    pub fn next_linkage_occurrence(&self) -> Option<u16> {
+        let in_use: Vec<u16> = self
+            .fields()
+            .iter()
+            .filter_map(|f| f.linkage())
+            .map(|l| l.occurrence())
+            .collect();
+
+        let next = in_use.iter().max().copied().unwrap_or(0) + 1;
+
+        if next <= MAX_OCCURRENCE {
+            return Some(next);
+        }
+
+        (1..=MAX_OCCURRENCE).find(|o| !in_use.contains(o))
+    }
+
+    /// Add an 880 field as the alternate graphic representation of the
+    /// regular field at the provided index in [`Record::fields`].
+    ///
+    /// A new occurrence number is allocated and $6 is set on both
+    /// fields.  The script identification code (and right-to-left
+    /// orientation) are derived from the content of the 880.  Any tag
+    /// on the provided field is replaced with "880".
+    ///
+    /// Returns the occurrence number, or an error if every occurrence
+    /// number is already in use.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::{Field, Record};
+    ///
+    /// let mut record = Record::from_breaker("=245 10$aVoĭna i mir").unwrap();
+    ///
+    /// let mut alt = Field::new("245").unwrap();
+    /// alt.set_ind1("1").unwrap();
+    /// alt.add_subfield("a", "Война и мир").unwrap();
+    ///
+    /// assert_eq!(record.add_linked_field(0, alt), Ok(1));
+    ///
+    /// assert_eq!(record.get_field_values("245", "6"), ["880-01"]);
+    /// assert_eq!(record.get_field_values("880", "6"), ["245-01/(N"]);
+    /// ```
+    pub fn add_linked_field(&mut self, field_index: usize, alt: Field) -> Result<u16, String> {
+        let regular_tag = match self.fields().get(field_index) {
+            Some(f) => f.tag().to_string(),
+            None => return Err(format!("No field at index {field_index}")),
+        };
+
+        if regular_tag.as_str() >= ALT_GRAPHIC_TAG {
+            return Err(format!(
+                "Field {regular_tag} cannot have alternate graphics"
+            ));
+        }
+
+        let Some(occurrence) = self.next_linkage_occurrence() else {
+            return Err("No unused linkage occurrence numbers remain".to_string());
+        };
+
+        let mut alt_link = Linkage::new(&regular_tag, occurrence);
+        if let Some(script) = detect_script(&alt.text()) {
+            alt_link.set_script(Some(script));
+            alt_link.set_right_to_left(is_rtl_script(script));
+        }
+
+        let mut field = Field::new(ALT_GRAPHIC_TAG)?;
+        field.set_ind1(alt.ind1())?;
+        field.set_ind2(alt.ind2())?;
+        *field.subfields_mut() = alt.subfields().clone();
+        field.set_linkage(&alt_link);
+
+        // Regular fields keep any script code they already have.
+        let mut link = self.fields()[field_index]
+            .linkage()
+            .unwrap_or_else(|| Linkage::new(ALT_GRAPHIC_TAG, occurrence));
+
+        link.set_tag(ALT_GRAPHIC_TAG);
+        link.set_occurrence(occurrence);
+
+        self.fields_mut()[field_index].set_linkage(&link);
+
+        // 880s sort after every linkable field, so field_index
+        // remains valid.
+        let position = self.alt_graphic_position(&regular_tag);
+        self.fields_mut().insert(position, field);
+
+        Ok(occurrence)
+    }
+
+    /// Add a regular field and its alternate graphic representation
+    /// together, linking them with a new occurrence number.
+    ///
+    /// The regular field is inserted in tag order.  See
+    /// [`Record::add_linked_field`] for details on the 880.
+    ///
+    /// Returns the index of the regular field and the occurrence number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::{Field, Record};
+    ///
+    /// let mut record = Record::from_breaker(
+    ///     "=245 10$6880-01$aVoĭna i mir\n=880 10$6245-01/(N$aВойна и мир"
+    /// ).unwrap();
+    ///
+    /// let mut field = Field::new("100").unwrap();
+    /// field.set_ind1("1").unwrap();
+    /// field.add_subfield("a", "Tolstoy, Leo,").unwrap();
+    ///
+    /// let mut alt = Field::new("100").unwrap();
+    /// alt.set_ind1("1").unwrap();
+    /// alt.add_subfield("a", "Толстой, Лев,").unwrap();
+    ///
+    /// assert_eq!(record.add_field_with_alternate(field, alt), Ok((0, 2)));
+    ///
+    /// assert_eq!(record.get_field_values("100", "6"), ["880-02"]);
+    ///
+    /// // 880s are ordered by the tag of their regular field.
+    /// assert_eq!(
+    ///     record.get_field_values("880", "6"),
+    ///     ["100-02/(N", "245-01/(N"]
+    /// );
+    /// ```
+    pub fn add_field_with_alternate(
+        &mut self,
+        field: Field,
+        alt: Field,
+    ) -> Result<(usize, u16), String> {
+        if field.tag() >= ALT_GRAPHIC_TAG {
+            return Err(format!(
+                "Field {} cannot have alternate graphics",
+                field.tag()
+            ));
+        }
+
+        let field_index = self.insert_data_field(field);
+
+        match self.add_linked_field(field_index, alt) {
+            Ok(occurrence) => Ok((field_index, occurrence)),
+            Err(e) => {
+                self.fields_mut().remove(field_index);
+                Err(e)
+            }
+        }
+    }
+
+    /// Index at which to insert an 880 linked to a field with the
+    /// provided tag.
+    ///
+    /// 880s follow all other fields through 87X, ordered by the tag of
+    /// their linked field.
+    fn alt_graphic_position(&self, linked_tag: &str) -> usize {
+        let fields = self.fields();
+
+        let mut position = fields
+            .iter()
+            .position(|f| f.tag() >= ALT_GRAPHIC_TAG)
+            .unwrap_or(fields.len());
+
+        while position < fields.len()
+            && fields[position].tag() == ALT_GRAPHIC_TAG
+            && fields[position]
+                .linkage()
+                .map(|l| l.tag() <= linked_tag)
+                .unwrap_or(true)
+        {
+            position += 1;
+        }
+
+        position
+    }
+
+    /// Re-sequence linkage occurrence numbers and repair broken links.
+    ///
+    /// * Linked pairs are renumbered sequentially in record order.
+    /// * 880s whose $6 tag disagrees with a partner sharing its
+    ///   occurrence number are pointed at the partner's tag.
+    /// * Regular fields with no matching 880 lose their $6.
+    /// * 880s with no matching regular field become unlinked ("00").
+    ///
+    /// Returns the number of fields modified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let mut record = Record::from_breaker(r#"=100 1\$6880-05$aTolstoy, Leo
+    /// =245 10$6880-07$aVoĭna i mir
+    /// =500 \\$6880-09$aNote
+    /// =880 1\$6700-05/(N$aТолстой, Лев
+    /// =880 10$6245-07/(N$aВойна и мир
+    /// =880 \\$6650-08/(N$aИстория"#).unwrap();
+    ///
+    /// assert_eq!(record.repair_linkage(), 6);
+    ///
+    /// assert_eq!(record.get_field_values("100", "6"), ["880-01"]);
+    /// assert_eq!(record.get_field_values("245", "6"), ["880-02"]);
+    /// assert!(record.get_field_values("500", "6").is_empty());
+    /// assert_eq!(
+    ///     record.get_field_values("880", "6"),
+    ///     ["100-01/(N", "245-02/(N", "650-00/(N"]
+    /// );
+    /// ```
+    pub fn repair_linkage(&mut self) -> usize {
+        let mut regulars: Vec<(usize, Linkage)> = Vec::new();
+        let mut alternates: Vec<(usize, Linkage)> = Vec::new();
+
+        for (idx, field) in self.fields().iter().enumerate() {
+            let Some(link) = field.linkage() else {
+                continue;
+            };
+            if field.tag() == ALT_GRAPHIC_TAG {
+                alternates.push((idx, link));
+            } else if link.tag() == ALT_GRAPHIC_TAG {
+                regulars.push((idx, link));
+            }
+        }
+
+        let mut paired = vec![false; alternates.len()];
+        let mut pairs: Vec<(usize, Option<usize>)> = Vec::new();
+
+        for (reg_idx, reg_link) in regulars.iter() {
+            let reg_tag = self.fields()[*reg_idx].tag();
+            let occ = reg_link.occurrence();
+
+            let candidate = |by_tag: bool| {
+                alternates.iter().enumerate().position(|(i, (_, l))| {
+                    !paired[i]
+                        && l.occurrence() == occ
+                        && occ != UNLINKED_OCCURRENCE
+                        && (!by_tag || l.tag() == reg_tag)
+                })
+            };
+
+            // Prefer an exact tag match, then any 880 sharing the
+            // occurrence number.
+            let found = candidate(true).or_else(|| candidate(false));
+
+            if let Some(i) = found {
+                paired[i] = true;
+            }
+
+            pairs.push((*reg_idx, found));
+        }
+
+        let mut changes: Vec<(usize, Option<Linkage>)> = Vec::new();
+        let mut occurrence = 0;
+
+        for (reg_idx, alt_pos) in pairs {
+            let Some(alt_pos) = alt_pos else {
+                changes.push((reg_idx, None));
+                continue;
+            };
+
+            occurrence += 1;
+
+            let reg_tag = self.fields()[reg_idx].tag().to_string();
+            let (alt_idx, alt_link) = &alternates[alt_pos];
+
+            let mut reg_link = self.fields()[reg_idx].linkage().unwrap();
+            reg_link.set_occurrence(occurrence);
+            changes.push((reg_idx, Some(reg_link)));
+
+            let mut alt_link = alt_link.clone();
+            alt_link.set_tag(&reg_tag);
+            alt_link.set_occurrence(occurrence);
+            changes.push((*alt_idx, Some(alt_link)));
+        }
+
+        for (i, (alt_idx, alt_link)) in alternates.iter().enumerate() {
+            if !paired[i] {
+                let mut alt_link = alt_link.clone();
+                alt_link.set_occurrence(UNLINKED_OCCURRENCE);
+                changes.push((*alt_idx, Some(alt_link)));
+            }
+        }
+
+        let mut modified = 0;
+
+        for (idx, link) in changes {
+            let field = &mut self.fields_mut()[idx];
+
+            if field.linkage() == link {
+                continue;
+            }
+
+            match link {
+                Some(l) => field.set_linkage(&l),
+                None => field.remove_linkage(),
+            }
+
+            modified += 1;
+        }
+
+        modified
+    }
+}
@@ -0,0 +1,413 @@
+//! MARC-8 character encoding.
+//!
+//! Supports the character sets most often found in legacy Latin
+//! script records: Basic Latin (ASCII), Extended Latin (ANSEL), and
+//! the subscript, superscript, and Greek symbol sets.  Records using
+//! other sets (e.g. Cyrillic, Hebrew, or CJK) are rejected.
+//!
+//! MARC-8 combining diacritics precede the character they modify,
+//! whereas Unicode combining marks follow it.  Diacritics are
+//! reordered during conversion and decoded values are normalized to
+//! NFC.
+//!
+//! Characters with no MARC-8 equivalent are encoded as numeric
+//! character references (e.g. `&#x263A;`) per the LC lossless
+//! conversion model.  Such references are decoded when reading.
+//!
+//! # References
+//!
+//! * <https://www.loc.gov/marc/specifications/speccharmarc8.html>
+//! * <https://www.loc.gov/marc/specifications/speccharconversion.html>
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+const ESC: u8 = 0x1B;
+
+/// Leader position 09 value for MARC-8 records.
+pub const LEADER_MARC8: char = ' ';
+
+/// Leader position 09 value for UCS/Unicode records.
+pub const LEADER_UNICODE: char = 'a';
+
+/// Graphic character sets we know how to translate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Charset {
+    BasicLatin,
+    ExtendedLatin,
+    Subscript,
+    Superscript,
+    GreekSymbol,
+}
+
+impl Charset {
+    /// Map an escape sequence final character to a character set.
+    fn from_final(byte: u8) -> Result<Charset, String> {
+        match byte {
+            0x42 | 0x73 => Ok(Charset::BasicLatin),
+            0x45 => Ok(Charset::ExtendedLatin),
+            0x62 => Ok(Charset::Subscript),
+            0x70 => Ok(Charset::Superscript),
+            0x67 => Ok(Charset::GreekSymbol),
+            _ => Err(format!("Unsupported MARC-8 character set: 0x{byte:02X}")),
+        }
+    }
+
+    /// Translate a 7-bit code point (0x21-0x7E) from this set.
+    fn decode(&self, byte: u8) -> Option<char> {
+        match self {
+            Charset::BasicLatin => Some(byte as char),
+            Charset::ExtendedLatin => ANSEL
+                .iter()
+                .find(|(b, _)| *b == byte | 0x80)
+                .map(|(_, c)| *c),
+            Charset::Subscript => SUBSCRIPT.iter().find(|(b, _)| *b == byte).map(|(_, c)| *c),
+            Charset::Superscript => SUPERSCRIPT
+                .iter()
+                .find(|(b, _)| *b == byte)
+                .map(|(_, c)| *c),
+            Charset::GreekSymbol => GREEK_SYMBOL
+                .iter()
+                .find(|(b, _)| *b == byte)
+                .map(|(_, c)| *c),
+        }
+    }
+
+    /// Technique 1 escape sequence which selects this set as G0.
+    fn escape(&self) -> &'static [u8] {
+        match self {
+            Charset::BasicLatin => &[ESC, 0x73],
+            Charset::ExtendedLatin => &[ESC, 0x28, 0x21, 0x45],
+            Charset::Subscript => &[ESC, 0x62],
+            Charset::Superscript => &[ESC, 0x70],
+            Charset::GreekSymbol => &[ESC, 0x67],
+        }
+    }
+}
+
+/// Extended Latin (ANSEL) as G1, including combining diacritics (0xE0+).
+const ANSEL: &[(u8, char)] = &[
+    (0x88, '\u{0098}'), // non-sort begin
+    (0x89, '\u{009C}'), // non-sort end
+    (0x8D, '\u{200D}'), // joiner
+    (0x8E, '\u{200C}'), // non-joiner
+    (0xA1, '\u{0141}'),
+    (0xA2, '\u{00D8}'),
+    (0xA3, '\u{0110}'),
+    (0xA4, '\u{00DE}'),
+    (0xA5, '\u{00C6}'),
+    (0xA6, '\u{0152}'),
+    (0xA7, '\u{02B9}'),
+    (0xA8, '\u{00B7}'),
+    (0xA9, '\u{266D}'),
+    (0xAA, '\u{00AE}'),
+    (0xAB, '\u{00B1}'),
+    (0xAC, '\u{01A0}'),
+    (0xAD, '\u{01AF}'),
+    (0xAE, '\u{02BC}'),
+    (0xB0, '\u{02BB}'),
+    (0xB1, '\u{0142}'),
+    (0xB2, '\u{00F8}'),
+    (0xB3, '\u{0111}'),
+    (0xB4, '\u{00FE}'),
+    (0xB5, '\u{00E6}'),
+    (0xB6, '\u{0153}'),
+    (0xB7, '\u{02BA}'),
+    (0xB8, '\u{0131}'),
+    (0xB9, '\u{00A3}'),
+    (0xBA, '\u{00F0}'),
+    (0xBC, '\u{01A1}'),
+    (0xBD, '\u{01B0}'),
+    (0xC0, '\u{00B0}'),
+    (0xC1, '\u{2113}'),
+    (0xC2, '\u{2117}'),
+    (0xC3, '\u{00A9}'),
+    (0xC4, '\u{266F}'),
+    (0xC5, '\u{00BF}'),
+    (0xC6, '\u{00A1}'),
+    (0xC7, '\u{00DF}'),
+    (0xC8, '\u{20AC}'),
+    (0xE0, '\u{0309}'),
+    (0xE1, '\u{0300}'),
+    (0xE2, '\u{0301}'),
+    (0xE3, '\u{0302}'),
+    (0xE4, '\u{0303}'),
+    (0xE5, '\u{0304}'),
+    (0xE6, '\u{0306}'),
+    (0xE7, '\u{0307}'),
+    (0xE8, '\u{0308}'),
+    (0xE9, '\u{030C}'),
+    (0xEA, '\u{030A}'),
+    (0xEB, '\u{FE20}'),
+    (0xEC, '\u{FE21}'),
+    (0xED, '\u{0315}'),
+    (0xEE, '\u{030B}'),
+    (0xEF, '\u{0310}'),
+    (0xF0, '\u{0327}'),
+    (0xF1, '\u{0328}'),
+    (0xF2, '\u{0323}'),
+    (0xF3, '\u{0324}'),
+    (0xF4, '\u{0325}'),
+    (0xF5, '\u{0333}'),
+    (0xF6, '\u{0332}'),
+    (0xF7, '\u{0326}'),
+    (0xF8, '\u{031C}'),
+    (0xF9, '\u{032E}'),
+    (0xFA, '\u{FE22}'),
+    (0xFB, '\u{FE23}'),
+    (0xFE, '\u{0313}'),
+];
+
+const SUBSCRIPT: &[(u8, char)] = &[
+    (0x28, '\u{208D}'),
+    (0x29, '\u{208E}'),
+    (0x2B, '\u{208A}'),
+    (0x2D, '\u{208B}'),
+    (0x30, '\u{2080}'),
+    (0x31, '\u{2081}'),
+    (0x32, '\u{2082}'),
+    (0x33, '\u{2083}'),
+    (0x34, '\u{2084}'),
+    (0x35, '\u{2085}'),
+    (0x36, '\u{2086}'),
+    (0x37, '\u{2087}'),
+    (0x38, '\u{2088}'),
+    (0x39, '\u{2089}'),
+];
+
+const SUPERSCRIPT: &[(u8, char)] = &[
+    (0x28, '\u{207D}'),
+    (0x29, '\u{207E}'),
+    (0x2B, '\u{207A}'),
+    (0x2D, '\u{207B}'),
+    (0x30, '\u{2070}'),
+    (0x31, '\u{00B9}'),
+    (0x32, '\u{00B2}'),
+    (0x33, '\u{00B3}'),
+    (0x34, '\u{2074}'),
+    (0x35, '\u{2075}'),
+    (0x36, '\u{2076}'),
+    (0x37, '\u{2077}'),
+    (0x38, '\u{2078}'),
+    (0x39, '\u{2079}'),
+];
+
+const GREEK_SYMBOL: &[(u8, char)] = &[(0x61, '\u{03B1}'), (0x62, '\u{03B2}'), (0x63, '\u{03B3}')];
+
+/// Decode a numeric character reference (e.g. "&#x00E9;") at the
+/// start of the bytes, returning the character and the number of
+/// bytes consumed.
+fn decode_ncr(bytes: &[u8]) -> Option<(char, usize)> {
+    if !bytes.starts_with(b"&#x") {
+        return None;
+    }
+
+    let end = bytes.iter().take(12).position(|b| *b == b';')?;
+    let hex = std::str::from_utf8(&bytes[3..end]).ok()?;
+    let c = char::from_u32(u32::from_str_radix(hex, 16).ok()?)?;
+
+    Some((c, end + 1))
+}
+
+/// Translate MARC-8 bytes into a UTF-8 string.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::marc8;
+///
+/// // "Ecole" with an acute accent on the E and a trailing pound sign.
+/// let bytes = b"\xE2Ecole \xB9";
+/// assert_eq!(marc8::decode(bytes).unwrap(), "\u{00C9}cole \u{00A3}");
+///
+/// // Superscript 2 via escape sequences.
+/// assert_eq!(marc8::decode(b"x\x1Bp2\x1Bs").unwrap(), "x\u{00B2}");
+///
+/// // Unsupported character sets are rejected.
+/// assert!(marc8::decode(b"\x1B(N\x41").is_err());
+/// ```
+pub fn decode(bytes: &[u8]) -> Result<String, String> {
+    let mut g0 = Charset::BasicLatin;
+    let mut g1 = Charset::ExtendedLatin;
+
+    let mut value = String::new();
+    let mut diacritics: Vec<char> = Vec::new();
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        let byte = bytes[idx];
+
+        if byte == ESC {
+            idx += 1;
+
+            let Some(&next) = bytes.get(idx) else {
+                return Err("MARC-8 escape sequence is truncated".to_string());
+            };
+
+            match next {
+                // Technique 1: single character G0 shortcuts
+                0x73 | 0x62 | 0x70 | 0x67 => {
+                    g0 = Charset::from_final(next)?;
+                    idx += 1;
+                }
+                // Technique 2: 94 character sets
+                0x28 | 0x2C | 0x29 | 0x2D => {
+                    idx += 1;
+                    // Skip the optional "!" intermediate (e.g. ESC ) ! E)
+                    if bytes.get(idx) == Some(&0x21) {
+                        idx += 1;
+                    }
+                    let Some(&fin) = bytes.get(idx) else {
+                        return Err("MARC-8 escape sequence is truncated".to_string());
+                    };
+                    let charset = Charset::from_final(fin)?;
+                    if next == 0x28 || next == 0x2C {
+                        g0 = charset;
+                    } else {
+                        g1 = charset;
+                    }
+                    idx += 1;
+                }
+                0x24 => return Err("Unsupported MARC-8 multibyte (CJK) character set".to_string()),
+                _ => return Err(format!("Invalid MARC-8 escape sequence: 0x{next:02X}")),
+            }
+
+            continue;
+        }
+
+        let c = if byte < 0x21 || byte == 0x7F {
+            // Spaces and control characters are shared by all sets.
+            byte as char
+        } else if byte < 0x80 {
+            if g0 == Charset::BasicLatin && byte == b'&' {
+                if let Some((c, len)) = decode_ncr(&bytes[idx..]) {
+                    value.push(c);
+                    value.extend(diacritics.drain(..));
+                    idx += len;
+                    continue;
+                }
+            }
+
+            g0.decode(byte)
+                .ok_or_else(|| format!("Invalid MARC-8 byte 0x{byte:02X} for {g0:?}"))?
+        } else if byte < 0xA1 || byte == 0xFF {
+            ANSEL
+                .iter()
+                .find(|(b, _)| *b == byte)
+                .map(|(_, c)| *c)
+                .ok_or_else(|| format!("Invalid MARC-8 control byte 0x{byte:02X}"))?
+        } else {
+            g1.decode(byte & 0x7F)
+                .ok_or_else(|| format!("Invalid MARC-8 byte 0x{byte:02X} for {g1:?}"))?
+        };
+
+        if is_combining_mark(c) {
+            // Applies to the next base character.
+            diacritics.push(c);
+        } else {
+            value.push(c);
+            value.extend(diacritics.drain(..));
+        }
+
+        idx += 1;
+    }
+
+    // Dangling diacritics.
+    value.extend(diacritics.drain(..));
+
+    Ok(value.nfc().collect())
+}
+
+/// Translate a UTF-8 string into MARC-8 bytes.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::marc8;
+///
+/// let bytes = marc8::encode("\u{00C9}cole \u{00A3}");
+/// assert_eq!(bytes, b"\xE2Ecole \xB9");
+///
+/// assert_eq!(marc8::encode("x\u{00B2}"), b"x\x1Bp2\x1Bs");
+///
+/// // No MARC-8 equivalent
+/// assert_eq!(marc8::encode("\u{263A}"), b"&#x263A;");
+///
+/// let value = "Bront\u{00EB}, \u{0141}\u{00F3}d\u{017A}";
+/// assert_eq!(marc8::decode(&marc8::encode(value)).unwrap(), value);
+/// ```
+pub fn encode(value: &str) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut g0 = Charset::BasicLatin;
+
+    // Base characters in MARC-8 are preceded by their diacritics.
+    let mut base: Option<char> = None;
+    let mut diacritics: Vec<char> = Vec::new();
+
+    let mut flush = |bytes: &mut Vec<u8>, base: Option<char>, diacritics: &mut Vec<char>| {
+        for d in diacritics.drain(..) {
+            encode_char(bytes, &mut g0, d);
+        }
+        if let Some(b) = base {
+            encode_char(bytes, &mut g0, b);
+        }
+    };
+
+    for c in value.nfd() {
+        if is_combining_mark(c) {
+            diacritics.push(c);
+        } else {
+            flush(&mut bytes, base.take(), &mut diacritics);
+            base = Some(c);
+        }
+    }
+
+    flush(&mut bytes, base, &mut diacritics);
+
+    if g0 != Charset::BasicLatin {
+        bytes.extend_from_slice(Charset::BasicLatin.escape());
+    }
+
+    bytes
+}
+
+/// Append the MARC-8 form of a single character, switching G0 as needed.
+fn encode_char(bytes: &mut Vec<u8>, g0: &mut Charset, c: char) {
+    let mut select = |bytes: &mut Vec<u8>, charset: Charset| {
+        if *g0 != charset {
+            bytes.extend_from_slice(charset.escape());
+            *g0 = charset;
+        }
+    };
+
+    if c.is_ascii() && !c.is_ascii_control() && c != ' ' {
+        select(bytes, Charset::BasicLatin);
+        bytes.push(c as u8);
+        return;
+    }
+
+    if c.is_ascii() {
+        // Spaces and control characters are shared by all sets.
+        bytes.push(c as u8);
+        return;
+    }
+
+    if let Some((b, _)) = ANSEL.iter().find(|(_, a)| *a == c) {
+        bytes.push(*b);
+        return;
+    }
+
+    for (charset, table) in [
+        (Charset::Subscript, SUBSCRIPT),
+        (Charset::Superscript, SUPERSCRIPT),
+        (Charset::GreekSymbol, GREEK_SYMBOL),
+    ] {
+        if let Some((b, _)) = table.iter().find(|(_, a)| *a == c) {
+            select(bytes, charset);
+            bytes.push(*b);
+            return;
+        }
+    }
+
+    select(bytes, Charset::BasicLatin);
+    bytes.extend_from_slice(format!("&#x{:04X};", c as u32).as_bytes());
+}
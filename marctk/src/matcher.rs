@@ -0,0 +1,380 @@
+//! Weighted matchpoint rules for finding matching records.
+//!
+//! A matchpoint names a subfield (e.g. 020 $a), how to normalize its
+//! values, and a weight.  Two records share a matchpoint when any
+//! normalized value from one record equals any normalized value from
+//! the other.  A record pair's score is the sum of the weights of its
+//! shared matchpoints, and the pair matches when the score reaches the
+//! matcher's threshold.
+//!
+//! Matchpoints are written one per line as:
+//!
+//! ```text
+//! <tag>$<code>[:<normalizer>] = <weight>
+//! ```
+//!
+//! e.g. `020$a:isbn = 10`.  Lines starting with `#` are ignored.  See
+//! [`Normalizer`] for the available normalizers.
+use super::standard_numbers::{Isbn, Issn};
+use super::Record;
+use std::fmt;
+use std::str::FromStr;
+
+/// How subfield values are normalized before comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Normalizer {
+    /// Compare values with only surrounding whitespace removed.
+    #[default]
+    Exact,
+    /// Ignore case, punctuation, and repeated whitespace.
+    Text,
+    /// Compare the ISBN-13 form of ISBNs, ignoring qualifiers.
+    Isbn,
+    /// Compare ISSNs without hyphens or qualifiers.
+    Issn,
+    /// Normalize LCCNs per the Library of Congress rules.
+    Lccn,
+}
+
+impl FromStr for Normalizer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exact" => Ok(Self::Exact),
+            "text" => Ok(Self::Text),
+            "isbn" => Ok(Self::Isbn),
+            "issn" => Ok(Self::Issn),
+            "lccn" => Ok(Self::Lccn),
+            _ => Err(format!("Invalid matchpoint normalizer: {s}")),
+        }
+    }
+}
+
+impl fmt::Display for Normalizer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::Exact => "exact",
+            Self::Text => "text",
+            Self::Isbn => "isbn",
+            Self::Issn => "issn",
+            Self::Lccn => "lccn",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl Normalizer {
+    /// Normalize a single value.
+    ///
+    /// Returns None if the value is empty after normalization or, for
+    /// standard numbers, not a valid number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::matcher::Normalizer;
+    ///
+    /// assert_eq!(Normalizer::Text.normalize("The  Cat, (Hat)."), Some("the cat hat".to_string()));
+    /// assert_eq!(Normalizer::Isbn.normalize("0-306-40615-2 (pbk.)"), Some("9780306406157".to_string()));
+    /// assert_eq!(Normalizer::Isbn.normalize("0306406153"), None);
+    /// assert_eq!(Normalizer::Issn.normalize("0378-5955"), Some("03785955".to_string()));
+    /// assert_eq!(Normalizer::Lccn.normalize("n 79-21164"), Some("n79021164".to_string()));
+    /// assert_eq!(Normalizer::Lccn.normalize("2001-000002 /AC"), Some("2001000002".to_string()));
+    /// assert_eq!(Normalizer::Exact.normalize("  "), None);
+    /// ```
+    pub fn normalize(&self, value: &str) -> Option<String> {
+        let normalized = match self {
+            Self::Exact => value.trim().to_string(),
+            Self::Text => normalize_text(value),
+            Self::Isbn => Isbn::parse(value)
+                .filter(|i| i.is_valid())
+                .map(|i| i.to_isbn13())?,
+            Self::Issn => Issn::parse(value)
+                .filter(|i| i.is_valid())
+                .map(|i| i.as_str().to_string())?,
+            Self::Lccn => normalize_lccn(value),
+        };
+
+        Some(normalized).filter(|n| !n.is_empty())
+    }
+}
+
+/// Lowercase, with punctuation replaced by spaces and runs of
+/// whitespace collapsed.
+fn normalize_text(value: &str) -> String {
+    value
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// <https://www.loc.gov/marc/lccn-namespace.html#normalization>
+fn normalize_lccn(value: &str) -> String {
+    let mut lccn: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+
+    if let Some(idx) = lccn.find('/') {
+        lccn.truncate(idx);
+    }
+
+    if let Some((prefix, serial)) = lccn.split_once('-') {
+        if !serial.is_empty() && serial.len() <= 6 && serial.chars().all(|c| c.is_ascii_digit()) {
+            lccn = format!("{prefix}{serial:0>6}");
+        } else {
+            lccn = lccn.replace('-', "");
+        }
+    }
+
+    lccn
+}
+
+/// A weighted subfield to compare between records.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matchpoint {
+    tag: String,
+    code: String,
+    normalizer: Normalizer,
+    weight: u32,
+}
+
+impl Matchpoint {
+    pub fn new(tag: &str, code: &str, normalizer: Normalizer, weight: u32) -> Matchpoint {
+        Matchpoint {
+            tag: tag.to_string(),
+            code: code.to_string(),
+            normalizer,
+            weight,
+        }
+    }
+
+    /// Parse a matchpoint from its text form.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::matcher::{Matchpoint, Normalizer};
+    ///
+    /// let mp = Matchpoint::parse("020$a:isbn = 10").unwrap();
+    /// assert_eq!(mp.tag(), "020");
+    /// assert_eq!(mp.normalizer(), Normalizer::Isbn);
+    /// assert_eq!(mp.weight(), 10);
+    ///
+    /// let mp = Matchpoint::parse("035$a = 5").unwrap();
+    /// assert_eq!(mp.normalizer(), Normalizer::Exact);
+    ///
+    /// assert!(Matchpoint::parse("035$a").is_err());
+    /// assert!(Matchpoint::parse("035$a:blah = 5").is_err());
+    /// assert!(Matchpoint::parse("35$a = 5").is_err());
+    /// ```
+    pub fn parse(spec: &str) -> Result<Matchpoint, String> {
+        let (target, weight) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid matchpoint: {spec}"))?;
+
+        let weight = weight
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid matchpoint weight: {spec}"))?;
+
+        let (target, normalizer) = match target.trim().split_once(':') {
+            Some((t, n)) => (t, n.trim().parse::<Normalizer>()?),
+            None => (target.trim(), Normalizer::Exact),
+        };
+
+        let (tag, code) = target
+            .trim()
+            .split_once('$')
+            .ok_or_else(|| format!("Invalid matchpoint: {spec}"))?;
+
+        if tag.len() != 3 || code.len() != 1 {
+            return Err(format!("Invalid matchpoint: {spec}"));
+        }
+
+        Ok(Matchpoint::new(tag, code, normalizer, weight))
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn normalizer(&self) -> Normalizer {
+        self.normalizer
+    }
+
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+
+    /// Distinct normalized values for this matchpoint in the record.
+    pub fn values(&self, record: &Record) -> Vec<String> {
+        let mut values: Vec<String> = Vec::new();
+
+        for value in record.get_field_values(&self.tag, &self.code) {
+            if let Some(v) = self.normalizer.normalize(value) {
+                if !values.contains(&v) {
+                    values.push(v);
+                }
+            }
+        }
+
+        values
+    }
+
+    /// True if the records share at least one normalized value.
+    pub fn matches(&self, a: &Record, b: &Record) -> bool {
+        let a_values = self.values(a);
+        self.values(b).iter().any(|v| a_values.contains(v))
+    }
+}
+
+/// A set of matchpoints and the score required for a match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matcher {
+    matchpoints: Vec<Matchpoint>,
+    threshold: u32,
+}
+
+impl Matcher {
+    /// Create a matcher with no matchpoints and a threshold of 1.
+    pub fn new() -> Matcher {
+        Matcher {
+            matchpoints: Vec::new(),
+            threshold: 1,
+        }
+    }
+
+    /// Parse a set of matchpoints, one per line.
+    pub fn parse(text: &str) -> Result<Matcher, String> {
+        let mut matcher = Matcher::new();
+
+        for line in text.lines().map(|l| l.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            matcher.add_matchpoint(Matchpoint::parse(line)?);
+        }
+
+        Ok(matcher)
+    }
+
+    pub fn matchpoints(&self) -> &Vec<Matchpoint> {
+        &self.matchpoints
+    }
+
+    pub fn add_matchpoint(&mut self, matchpoint: Matchpoint) {
+        self.matchpoints.push(matchpoint);
+    }
+
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    /// Minimum score for two records to match.  A score of zero never
+    /// matches, regardless of the threshold.
+    pub fn set_threshold(&mut self, threshold: u32) {
+        self.threshold = threshold;
+    }
+
+    /// Sum of the weights of the matchpoints shared by the records.
+    pub fn score(&self, a: &Record, b: &Record) -> u32 {
+        self.matchpoints
+            .iter()
+            .filter(|mp| mp.matches(a, b))
+            .map(|mp| mp.weight())
+            .sum()
+    }
+
+    /// True if the records score at least the threshold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::matcher::Matcher;
+    ///
+    /// let mut matcher = Matcher::parse(
+    ///     "010$a:lccn = 10\n020$a:isbn = 10\n245$a:text = 2"
+    /// ).unwrap();
+    /// matcher.set_threshold(10);
+    ///
+    /// let a = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =020 \\$a0306406152 (pbk.)
+    /// =245 10$aThe title :"#
+    /// ).unwrap();
+    ///
+    /// let b = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =020 \\$a978-0-306-40615-7
+    /// =245 10$aThe Title"#
+    /// ).unwrap();
+    ///
+    /// assert_eq!(matcher.score(&a, &b), 12);
+    /// assert!(matcher.is_match(&a, &b));
+    ///
+    /// let c = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =245 10$aThe title"#
+    /// ).unwrap();
+    ///
+    /// assert_eq!(matcher.score(&a, &c), 2);
+    /// assert!(!matcher.is_match(&a, &c));
+    /// ```
+    pub fn is_match(&self, a: &Record, b: &Record) -> bool {
+        let score = self.score(a, b);
+        score > 0 && score >= self.threshold
+    }
+
+    /// Score each candidate against the record.
+    ///
+    /// Returns (candidate index, score) for each matching candidate,
+    /// highest score first.  Candidates with equal scores retain their
+    /// relative order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::matcher::Matcher;
+    ///
+    /// let matcher = Matcher::parse("035$a = 5\n020$a:isbn = 10").unwrap();
+    ///
+    /// let record = Record::from_breaker(
+    ///     "=020 \\\\$a0306406152\n=035 \\\\$a(OCoLC)123"
+    /// ).unwrap();
+    ///
+    /// let candidates = vec![
+    ///     Record::from_breaker("=035 \\\\$a(OCoLC)123").unwrap(),
+    ///     Record::from_breaker("=035 \\\\$a(OCoLC)456").unwrap(),
+    ///     Record::from_breaker("=020 \\\\$a9780306406157\n=035 \\\\$a(OCoLC)123").unwrap(),
+    /// ];
+    ///
+    /// assert_eq!(matcher.rank(&record, &candidates), vec![(2, 15), (0, 5)]);
+    /// ```
+    pub fn rank(&self, record: &Record, candidates: &[Record]) -> Vec<(usize, u32)> {
+        let mut scores: Vec<(usize, u32)> = candidates
+            .iter()
+            .enumerate()
+            .map(|(idx, c)| (idx, self.score(record, c)))
+            .filter(|(_, score)| *score > 0 && *score >= self.threshold)
+            .collect();
+
+        scores.sort_by_key(|s| std::cmp::Reverse(s.1));
+
+        scores
+    }
+}
+
+impl Default for Matcher {
+    fn default() -> Self {
+        Matcher::new()
+    }
+}
@@ -0,0 +1,402 @@
+//! Combine two records according to field-precedence rules.
+//!
+//! Merging is the core of overlay workflows, where an incoming record
+//! (e.g. from a vendor or Z39.50 search) replaces an existing catalog
+//! record while retaining selected local data.
+//!
+//! Each rule maps one or more tag specs to a [`MergeAction`].  Specs
+//! use the same syntax as [`Field::matches_spec`], with multiple specs
+//! separated by ":", e.g. "6xx:7xx".  The first matching rule wins.
+//! Tags matching no rule use the default action.
+use super::diff::Change;
+use super::Field;
+use super::Record;
+use std::fmt;
+use std::str::FromStr;
+
+/// How to combine the fields for a given tag.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergeAction {
+    /// Use only the existing record's fields.
+    KeepExisting,
+    /// Use only the incoming record's fields.
+    KeepIncoming,
+    /// Use the existing record's fields if it has any, otherwise
+    /// the incoming record's fields.
+    PreferExisting,
+    /// Use the incoming record's fields if it has any, otherwise
+    /// the existing record's fields.
+    PreferIncoming,
+    /// Use the fields from both records, skipping incoming fields
+    /// which duplicate an existing field.
+    Union,
+    /// Discard the fields from both records.
+    Drop,
+}
+
+impl FromStr for MergeAction {
+    type Err = String;
+
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::merge::MergeAction;
+    ///
+    /// assert_eq!("union".parse::<MergeAction>(), Ok(MergeAction::Union));
+    /// assert!("blah".parse::<MergeAction>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep_existing" => Ok(Self::KeepExisting),
+            "keep_incoming" => Ok(Self::KeepIncoming),
+            "prefer_existing" => Ok(Self::PreferExisting),
+            "prefer_incoming" => Ok(Self::PreferIncoming),
+            "union" => Ok(Self::Union),
+            "drop" => Ok(Self::Drop),
+            _ => Err(format!("Invalid merge action: {s}")),
+        }
+    }
+}
+
+impl fmt::Display for MergeAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::KeepExisting => "keep_existing",
+            Self::KeepIncoming => "keep_incoming",
+            Self::PreferExisting => "prefer_existing",
+            Self::PreferIncoming => "prefer_incoming",
+            Self::Union => "union",
+            Self::Drop => "drop",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Which record supplies the leader of the merged record.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LeaderSource {
+    Existing,
+    Incoming,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct MergeRule {
+    specs: Vec<String>,
+    action: MergeAction,
+}
+
+/// Ordered set of merge rules.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeRules {
+    rules: Vec<MergeRule>,
+    default_action: MergeAction,
+    leader: LeaderSource,
+}
+
+impl Default for MergeRules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MergeRules {
+    /// Create a rule set with no rules, where the incoming record's
+    /// fields (and leader) are preferred.
+    pub fn new() -> Self {
+        MergeRules {
+            rules: Vec::new(),
+            default_action: MergeAction::PreferIncoming,
+            leader: LeaderSource::Incoming,
+        }
+    }
+
+    /// Typical overlay rules.
+    ///
+    /// Keeps the existing record's 001, 003, and local (9xx) fields,
+    /// combines subject headings, and otherwise uses the incoming
+    /// record's fields.
+    pub fn overlay() -> Self {
+        MergeRules::new()
+            .rule("001:003", MergeAction::KeepExisting)
+            .rule("9xx", MergeAction::KeepExisting)
+            .rule("6xx", MergeAction::Union)
+            .default_action(MergeAction::KeepIncoming)
+    }
+
+    /// Add a rule.  Rules are applied in the order they are added.
+    pub fn rule(mut self, specs: &str, action: MergeAction) -> Self {
+        self.add_rule(specs, action);
+        self
+    }
+
+    /// Add a rule.  Rules are applied in the order they are added.
+    pub fn add_rule(&mut self, specs: &str, action: MergeAction) {
+        self.rules.push(MergeRule {
+            specs: specs.split(':').map(|s| s.trim().to_string()).collect(),
+            action,
+        });
+    }
+
+    /// Action for tags which match no rule.
+    pub fn default_action(mut self, action: MergeAction) -> Self {
+        self.default_action = action;
+        self
+    }
+
+    /// Which record supplies the leader.
+    pub fn leader(mut self, source: LeaderSource) -> Self {
+        self.leader = source;
+        self
+    }
+
+    /// Parse rules from text containing one "specs=action" rule per
+    /// line.  The special spec "default" sets the default action and
+    /// "LDR" may be "existing" or "incoming".  Blank lines and lines
+    /// starting with "#" are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::merge::{MergeAction, MergeRules};
+    ///
+    /// let rules = MergeRules::from_text(
+    ///     "# Local fields
+    ///     9xx = keep_existing
+    ///     1xx:245 = prefer_incoming
+    ///     650 = union
+    ///     default = keep_incoming"
+    /// ).unwrap();
+    ///
+    /// assert_eq!(rules.action_for("901"), MergeAction::KeepExisting);
+    /// assert_eq!(rules.action_for("100"), MergeAction::PreferIncoming);
+    /// assert_eq!(rules.action_for("650"), MergeAction::Union);
+    /// assert_eq!(rules.action_for("500"), MergeAction::KeepIncoming);
+    ///
+    /// assert!(MergeRules::from_text("650 union").is_err());
+    /// ```
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut rules = MergeRules::new();
+
+        for line in text.lines().map(|l| l.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (specs, action) = line
+                .split_once('=')
+                .map(|(s, a)| (s.trim(), a.trim()))
+                .ok_or_else(|| format!("Invalid merge rule: {line}"))?;
+
+            match specs {
+                "default" => rules.default_action = action.parse()?,
+                "LDR" => {
+                    rules.leader = match action {
+                        "existing" => LeaderSource::Existing,
+                        "incoming" => LeaderSource::Incoming,
+                        _ => return Err(format!("Invalid leader source: {action}")),
+                    }
+                }
+                _ => rules.add_rule(specs, action.parse()?),
+            }
+        }
+
+        Ok(rules)
+    }
+
+    /// The action applied to fields with the provided tag.
+    pub fn action_for(&self, tag: &str) -> MergeAction {
+        self.rules
+            .iter()
+            .find(|r| r.specs.iter().any(|s| tag_matches_spec(tag, s)))
+            .map(|r| r.action)
+            .unwrap_or(self.default_action)
+    }
+}
+
+/// Same logic as [`Field::matches_spec`], usable for control fields.
+fn tag_matches_spec(tag: &str, spec: &str) -> bool {
+    spec.len() == 3
+        && spec
+            .chars()
+            .zip(tag.chars())
+            .all(|(s, t)| s.eq_ignore_ascii_case(&'x') || s == t)
+}
+
+/// Comparison key for detecting duplicate fields.
+///
+/// Ignores case, surrounding whitespace, and trailing punctuation
+/// so e.g. "Cats." and "cats" are considered the same heading.
+fn dedup_key(field: &Field) -> String {
+    let mut key = format!("{}{}", field.ind1(), field.ind2());
+
+    for sf in field.subfields() {
+        let content = sf
+            .content()
+            .trim()
+            .trim_end_matches(['.', ',', ';', ':', '/'])
+            .trim_end()
+            .to_lowercase();
+
+        key += &format!("\x1f{}{content}", sf.code());
+    }
+
+    key
+}
+
+/// Select the values for a tag based on the action.
+fn merge_values<T: Clone>(
+    action: MergeAction,
+    existing: Vec<&T>,
+    incoming: Vec<&T>,
+    is_duplicate: impl Fn(&T, &T) -> bool,
+) -> Vec<T> {
+    let selected: Vec<&T> = match action {
+        MergeAction::KeepExisting => existing,
+        MergeAction::KeepIncoming => incoming,
+        MergeAction::PreferExisting if !existing.is_empty() => existing,
+        MergeAction::PreferExisting => incoming,
+        MergeAction::PreferIncoming if !incoming.is_empty() => incoming,
+        MergeAction::PreferIncoming => existing,
+        MergeAction::Drop => Vec::new(),
+        MergeAction::Union => {
+            let mut values = existing.clone();
+            for inc in incoming {
+                if !values.iter().any(|v| is_duplicate(v, inc)) {
+                    values.push(inc);
+                }
+            }
+            values
+        }
+    };
+
+    selected.into_iter().cloned().collect()
+}
+
+/// Sorted list of unique tags appearing in either list.
+fn tags<'a>(a: impl Iterator<Item = &'a str>, b: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut tags: Vec<&str> = a.chain(b).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+impl Record {
+    /// Merge an incoming record into this (existing) record, producing
+    /// a new record.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::merge::{MergeAction, MergeRules};
+    ///
+    /// let existing = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =001 123
+    /// =245 10$aOld title.
+    /// =650 \0$aCats.
+    /// =901 \\$aLocal data"#
+    /// ).unwrap();
+    ///
+    /// let incoming = Record::from_breaker(
+    ///     r#"=LDR 00000cam a2200000 i 4500
+    /// =001 ocm999
+    /// =100 1\$aSmith, Jane.
+    /// =245 10$aNew title.
+    /// =650 \0$acats
+    /// =650 \0$aDogs."#
+    /// ).unwrap();
+    ///
+    /// let merged = existing.merge(&incoming, &MergeRules::overlay());
+    ///
+    /// assert_eq!(
+    ///     merged.to_breaker(),
+    ///     r#"=LDR 00000cam a2200000 i 4500
+    /// =001 123
+    /// =100 1\$aSmith, Jane.
+    /// =245 10$aNew title.
+    /// =650 \0$aCats.
+    /// =650 \0$aDogs.
+    /// =901 \\$aLocal data"#
+    /// );
+    ///
+    /// let rules = MergeRules::new().rule("245", MergeAction::KeepExisting);
+    /// let merged = existing.merge(&incoming, &rules);
+    ///
+    /// assert_eq!(merged.get_field_values("245", "a"), vec!["Old title."]);
+    /// assert_eq!(merged.get_field_values("901", "a"), vec!["Local data"]);
+    ///
+    /// // Journaled records log the changes made by the merge.
+    /// let mut existing = existing.clone();
+    /// existing.enable_journal();
+    ///
+    /// let merged = existing.merge(&incoming, &MergeRules::overlay());
+    /// let entries = merged.journal().unwrap().entries();
+    ///
+    /// assert_eq!(entries.len(), 4);
+    /// assert_eq!(entries[0].rule(), Some("merge:leader"));
+    /// assert_eq!(entries[1].tag(), "100");
+    /// assert_eq!(entries[1].rule(), Some("merge:keep_incoming"));
+    /// ```
+    pub fn merge(&self, incoming: &Record, rules: &MergeRules) -> Record {
+        let mut merged = Record::new();
+
+        let leader = match rules.leader {
+            LeaderSource::Existing => self.leader(),
+            LeaderSource::Incoming => incoming.leader(),
+        };
+
+        // Leaders from valid records are always valid.
+        merged.set_leader(leader).ok();
+
+        let cf_tags = tags(
+            self.control_fields().iter().map(|f| f.tag()),
+            incoming.control_fields().iter().map(|f| f.tag()),
+        );
+
+        for tag in cf_tags {
+            let values = merge_values(
+                rules.action_for(tag),
+                self.get_control_fields(tag),
+                incoming.get_control_fields(tag),
+                |a, b| a == b,
+            );
+
+            merged.control_fields_mut().extend(values);
+        }
+
+        let df_tags = tags(
+            self.fields().iter().map(|f| f.tag()),
+            incoming.fields().iter().map(|f| f.tag()),
+        );
+
+        for tag in df_tags {
+            let values = merge_values(
+                rules.action_for(tag),
+                self.get_fields(tag),
+                incoming.get_fields(tag),
+                |a, b| dedup_key(a) == dedup_key(b),
+            );
+
+            merged.fields_mut().extend(values);
+        }
+
+        // The merged record continues the existing record's journal,
+        // with each change attributed to the action for its tag.
+        if let Some(journal) = self.journal() {
+            let mut journal = journal.clone();
+
+            for change in self.diff(&merged).changes() {
+                let rule = match change {
+                    Change::Leader { .. } => "merge:leader".to_string(),
+                    _ => format!("merge:{}", rules.action_for(change.tag())),
+                };
+                journal.record_change(change, Some(&rule));
+            }
+
+            merged.journal = Some(journal);
+        }
+
+        merged
+    }
+}
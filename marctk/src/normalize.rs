@@ -0,0 +1,246 @@
+//! Unicode normalization of record content.
+//!
+//! Records assembled from several sources often mix composed and
+//! decomposed characters, e.g. "é" as one code point or as "e" plus a
+//! combining acute accent.  The two render the same but do not compare
+//! equal, which breaks matching and indexing.
+//!
+//! [`Record::equivalent`] compares records after normalizing their
+//! content, e.g. to detect overlays which would change nothing.
+use super::Record;
+use unicode_normalization::UnicodeNormalization;
+
+/// Unicode normalization forms.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalizationForm {
+    /// Canonical composition.
+    NFC,
+    /// Canonical decomposition.
+    NFD,
+}
+
+impl NormalizationForm {
+    /// Normalize a value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::normalize::NormalizationForm;
+    ///
+    /// assert_eq!(NormalizationForm::NFC.apply("e\u{301}"), "\u{e9}");
+    /// assert_eq!(NormalizationForm::NFD.apply("\u{e9}"), "e\u{301}");
+    /// ```
+    pub fn apply(&self, value: &str) -> String {
+        match self {
+            NormalizationForm::NFC => value.nfc().collect(),
+            NormalizationForm::NFD => value.nfd().collect(),
+        }
+    }
+}
+
+/// The normalized value, if it differs from the original.
+fn normalized(form: NormalizationForm, value: &str) -> Option<String> {
+    let new_value = form.apply(value);
+    if new_value != value {
+        Some(new_value)
+    } else {
+        None
+    }
+}
+
+impl Record {
+    /// Normalize the content of every control field and subfield.
+    ///
+    /// The leader is normalized only if the result is still a valid
+    /// leader.  Tags, indicators, and subfield codes are left as-is.
+    ///
+    /// Returns the number of values changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::normalize::NormalizationForm;
+    ///
+    /// let mut record = Record::from_breaker(
+    ///     "=100 1\\$aCala, Isma\u{e9}l.\n=245 10$aPrimera edicio\u{301}n."
+    /// ).unwrap();
+    ///
+    /// assert_eq!(record.normalize(NormalizationForm::NFC), 1);
+    /// assert_eq!(record.get_field_values("245", "a"), vec!["Primera edici\u{f3}n."]);
+    ///
+    /// assert_eq!(record.normalize(NormalizationForm::NFD), 2);
+    /// assert_eq!(record.get_field_values("100", "a"), vec!["Cala, Isma\u{65}\u{301}l."]);
+    /// ```
+    pub fn normalize(&mut self, form: NormalizationForm) -> usize {
+        let mut changed = 0;
+
+        if let Some(leader) = normalized(form, self.leader()) {
+            if self.set_leader(leader).is_ok() {
+                changed += 1;
+            }
+        }
+
+        for cf in self.control_fields_mut() {
+            if let Some(value) = normalized(form, cf.content()) {
+                cf.set_content(value);
+                changed += 1;
+            }
+        }
+
+        for field in self.fields_mut() {
+            for sf in field.subfields_mut() {
+                if let Some(value) = normalized(form, sf.content()) {
+                    sf.set_content(value);
+                    changed += 1;
+                }
+            }
+        }
+
+        changed
+    }
+}
+
+/// True if the tag matches the spec, e.g. "9xx".  See
+/// [`crate::Field::matches_spec`].
+fn tag_matches_spec(tag: &str, spec: &str) -> bool {
+    spec.len() == tag.len()
+        && spec
+            .chars()
+            .zip(tag.chars())
+            .all(|(s, t)| s.eq_ignore_ascii_case(&'x') || s == t)
+}
+
+/// Trim and collapse runs of whitespace, then apply NFC.
+fn canonical_value(value: &str) -> String {
+    NormalizationForm::NFC.apply(&value.split_whitespace().collect::<Vec<&str>>().join(" "))
+}
+
+/// Tag, indicators, and subfield codes and values.
+type CanonicalField = (String, String, String, Vec<(String, String)>);
+
+/// Record content reduced to a form where insignificant differences
+/// disappear.
+#[derive(PartialEq)]
+struct CanonicalRecord {
+    leader: Option<String>,
+    control_fields: Vec<(String, String)>,
+    fields: Vec<CanonicalField>,
+}
+
+impl CanonicalRecord {
+    fn new(record: &Record, ignore: &[&str]) -> Self {
+        let ignored = |tag: &str| ignore.iter().any(|s| tag_matches_spec(tag, s));
+
+        // The record length and base address of data are computed
+        // when the record is written, so they are not compared.
+        let leader = if ignored("LDR") {
+            None
+        } else {
+            Some(
+                record
+                    .leader()
+                    .char_indices()
+                    .filter(|(i, _)| !(0..5).contains(i) && !(12..17).contains(i))
+                    .map(|(_, c)| c)
+                    .collect(),
+            )
+        };
+
+        let mut control_fields: Vec<(String, String)> = record
+            .control_fields()
+            .iter()
+            .filter(|cf| !ignored(cf.tag()))
+            .map(|cf| {
+                // Control field values are positional, so only
+                // trailing whitespace is insignificant.
+                let content = NormalizationForm::NFC.apply(cf.content().trim_end());
+                (cf.tag().to_string(), content)
+            })
+            .collect();
+
+        let mut fields: Vec<CanonicalField> = record
+            .fields()
+            .iter()
+            .filter(|f| !ignored(f.tag()))
+            .map(|f| {
+                let subfields = f
+                    .subfields()
+                    .iter()
+                    .map(|sf| (sf.code().to_string(), canonical_value(sf.content())))
+                    .collect();
+
+                (
+                    f.tag().to_string(),
+                    f.ind1().to_string(),
+                    f.ind2().to_string(),
+                    subfields,
+                )
+            })
+            .collect();
+
+        control_fields.sort();
+        fields.sort();
+
+        CanonicalRecord {
+            leader,
+            control_fields,
+            fields,
+        }
+    }
+}
+
+impl Record {
+    /// True if the records have the same content, ignoring field order,
+    /// insignificant whitespace, and Unicode normalization form.
+    ///
+    /// Subfield order is significant.  Leader positions which are
+    /// computed when the record is written, i.e. the record length and
+    /// base address of data, are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let a = Record::from_breaker(
+    ///     "=650 \\0$aCats.\n=245 10$aPrimera edicio\u{301}n."
+    /// ).unwrap();
+    ///
+    /// let b = Record::from_breaker(
+    ///     "=245 10$a Primera  edici\u{f3}n.\n=650 \\0$aCats."
+    /// ).unwrap();
+    ///
+    /// assert!(a.equivalent(&b));
+    ///
+    /// let c = Record::from_breaker("=245 10$aPrimera edici\u{f3}n.").unwrap();
+    /// assert!(!a.equivalent(&c));
+    /// ```
+    pub fn equivalent(&self, other: &Record) -> bool {
+        self.equivalent_ignoring(other, &[])
+    }
+
+    /// Same as [`Record::equivalent`], but fields whose tags match one
+    /// of the tag specs, e.g. "005" or "9xx", are not compared.  Use
+    /// "LDR" to skip the leader.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let a = Record::from_breaker(
+    ///     "=005 20240101120000.0\n=245 10$aTitle\n=901 \\\\$c123"
+    /// ).unwrap();
+    ///
+    /// let b = Record::from_breaker(
+    ///     "=005 20250601093000.0\n=245 10$aTitle"
+    /// ).unwrap();
+    ///
+    /// assert!(!a.equivalent(&b));
+    /// assert!(a.equivalent_ignoring(&b, &["005", "9xx"]));
+    /// ```
+    pub fn equivalent_ignoring(&self, other: &Record, ignore: &[&str]) -> bool {
+        CanonicalRecord::new(self, ignore) == CanonicalRecord::new(other, ignore)
+    }
+}
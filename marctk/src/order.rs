@@ -0,0 +1,144 @@
+//! Canonical field ordering.
+//!
+//! Fields are ordered numerically by tag.  Repeated tags retain their
+//! relative order.  Fields matching one of the trailing patterns are
+//! moved after all other fields, also retaining their relative order.
+use super::Controlfield;
+use super::Field;
+use super::Record;
+
+/// Describes how to order the fields in a record.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::order::FieldOrder;
+///
+/// let order = FieldOrder::new().keep_last("9XX");
+/// assert!(order.is_trailing("945"));
+/// assert!(!order.is_trailing("245"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldOrder {
+    /// Tag patterns, where "X" matches any character, e.g. "9XX".
+    trailing: Vec<String>,
+}
+
+impl FieldOrder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Keep fields whose tags match the pattern at the end of the
+    /// record, in their original order.
+    ///
+    /// "X" or "x" in the pattern matches any character.
+    pub fn keep_last(mut self, pattern: &str) -> Self {
+        self.trailing.push(pattern.to_string());
+        self
+    }
+
+    pub fn trailing(&self) -> &Vec<String> {
+        &self.trailing
+    }
+
+    /// True if the tag matches one of the trailing patterns.
+    pub fn is_trailing(&self, tag: &str) -> bool {
+        self.trailing.iter().any(|p| tag_matches(p, tag))
+    }
+
+    /// Control fields in canonical order.
+    pub fn order_control_fields<'a>(&self, fields: &'a [Controlfield]) -> Vec<&'a Controlfield> {
+        let mut ordered: Vec<&Controlfield> = fields.iter().collect();
+        ordered.sort_by(|a, b| a.tag().cmp(b.tag()));
+        ordered
+    }
+
+    /// Data fields in canonical order.
+    pub fn order_fields<'a>(&self, fields: &'a [Field]) -> Vec<&'a Field> {
+        let mut ordered: Vec<&Field> = fields.iter().collect();
+        // sort_by_key is stable, so repeated tags keep their order.
+        ordered.sort_by_key(|f| (self.is_trailing(f.tag()), self.sort_tag(f.tag())));
+        ordered
+    }
+
+    /// Trailing fields sort together in their original order.
+    fn sort_tag<'a>(&self, tag: &'a str) -> &'a str {
+        if self.is_trailing(tag) {
+            ""
+        } else {
+            tag
+        }
+    }
+}
+
+fn tag_matches(pattern: &str, tag: &str) -> bool {
+    pattern.len() == tag.len()
+        && pattern
+            .chars()
+            .zip(tag.chars())
+            .all(|(p, t)| p == 'X' || p == 'x' || p == t)
+}
+
+impl Record {
+    /// Sort fields into canonical tag order.
+    ///
+    /// Repeated tags retain their relative order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let mut record = Record::new();
+    /// record.fields_mut().push(marctk::Field::new("650").unwrap());
+    /// record.fields_mut().push(marctk::Field::new("245").unwrap());
+    ///
+    /// record.sort_fields();
+    /// assert_eq!(record.fields()[0].tag(), "245");
+    /// ```
+    pub fn sort_fields(&mut self) {
+        self.sort_fields_with(&FieldOrder::new());
+    }
+
+    /// Sort fields per the provided [`FieldOrder`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::order::FieldOrder;
+    ///
+    /// let mut record = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =245 10$aTitle.
+    /// =945 \\$aFirst
+    /// =650 \0$aCats.
+    /// =100 1\$aAuthor.
+    /// =901 \\$aSecond
+    /// =650 \0$aDogs."#
+    /// ).unwrap();
+    ///
+    /// record.sort_fields_with(&FieldOrder::new().keep_last("9XX"));
+    ///
+    /// let tags: Vec<&str> = record.fields().iter().map(|f| f.tag()).collect();
+    /// assert_eq!(tags, vec!["100", "245", "650", "650", "945", "901"]);
+    /// assert_eq!(record.get_field_values("650", "a"), vec!["Cats.", "Dogs."]);
+    /// ```
+    pub fn sort_fields_with(&mut self, order: &FieldOrder) {
+        let control_fields: Vec<Controlfield> = order
+            .order_control_fields(self.control_fields())
+            .into_iter()
+            .cloned()
+            .collect();
+
+        let fields: Vec<Field> = order
+            .order_fields(self.fields())
+            .into_iter()
+            .cloned()
+            .collect();
+
+        *self.control_fields_mut() = control_fields;
+        *self.fields_mut() = fields;
+    }
+}
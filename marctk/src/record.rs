@@ -1,8 +1,9 @@
 //! Base MARC record model and associated components.
+use super::journal::ChangeJournal;
+use super::leader::Leader;
+
 const TAG_SIZE: usize = 3;
-const LEADER_SIZE: usize = 24;
 const CODE_SIZE: usize = 1;
-const DEFAULT_LEADER: &str = "                        ";
 const DEFAULT_INDICATOR: &str = " ";
 
 /// Verifies the provided string is composed of 'len' number of bytes.
@@ -459,11 +460,22 @@ impl Field {
 }
 
 /// A MARC record with leader, control fields, and data fields.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct Record {
-    leader: String,
+    leader: Leader,
     control_fields: Vec<Controlfield>,
     fields: Vec<Field>,
+    /// Optional log of changes.  See [`crate::journal`].
+    pub(crate) journal: Option<ChangeJournal>,
+}
+
+/// Records are equal if their content is equal, regardless of journal.
+impl PartialEq for Record {
+    fn eq(&self, other: &Self) -> bool {
+        self.leader == other.leader
+            && self.control_fields == other.control_fields
+            && self.fields == other.fields
+    }
 }
 
 impl Default for Record {
@@ -476,17 +488,50 @@ impl Record {
     /// Create a new Record with a default leader and no content.
     pub fn new() -> Self {
         Record {
-            leader: DEFAULT_LEADER.to_string(),
+            leader: Leader::default(),
             control_fields: Vec::new(),
             fields: Vec::new(),
+            journal: None,
         }
     }
 
     /// Get the leader as a string.
     pub fn leader(&self) -> &str {
+        self.leader.as_str()
+    }
+
+    /// Get the leader with typed accessors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::leader::{EncodingLevel, TypeOfRecord};
+    ///
+    /// let mut record = Record::default();
+    /// record.set_leader("00000nam a2200000 a 4500").unwrap();
+    ///
+    /// assert_eq!(
+    ///     record.typed_leader().type_of_record(),
+    ///     Some(TypeOfRecord::LanguageMaterial)
+    /// );
+    ///
+    /// record
+    ///     .typed_leader_mut()
+    ///     .set_encoding_level(EncodingLevel::Minimal)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(record.leader(), "00000nam a22000007a 4500");
+    /// ```
+    pub fn typed_leader(&self) -> &Leader {
         &self.leader
     }
 
+    /// Mutable variant of [`Record::typed_leader`].
+    pub fn typed_leader_mut(&mut self) -> &mut Leader {
+        &mut self.leader
+    }
+
     /// Apply a leader value.
     ///
     /// Returns Err if the value is not composed of the correct number
@@ -501,9 +546,7 @@ impl Record {
     /// assert!(record.set_leader("just right              ").is_ok());
     /// ```
     pub fn set_leader(&mut self, leader: impl Into<String>) -> Result<(), String> {
-        let leader = leader.into();
-        check_byte_count(&leader, LEADER_SIZE)?;
-        self.leader = leader;
+        self.leader = Leader::new(leader)?;
         Ok(())
     }
 
@@ -599,13 +642,158 @@ impl Record {
     }
 
     /// Insert a [`Field`] in tag order
+    ///
+    /// Returns the index of the new field.
     pub fn insert_data_field(&mut self, field: Field) -> usize {
         if let Some(idx) = self.fields().iter().position(|f| f.tag() > field.tag()) {
             self.fields_mut().insert(idx, field);
             idx
         } else {
             self.fields_mut().push(field);
-            0
+            self.fields().len() - 1
+        }
+    }
+
+    /// Insert a [`Controlfield`] at the provided index, regardless of
+    /// tag order.
+    ///
+    /// Err if the index is greater than the number of control fields.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::{Controlfield, Record};
+    /// let mut record = Record::default();
+    /// record.add_control_field("001", "123").unwrap();
+    /// record.add_control_field("008", "abc").unwrap();
+    ///
+    /// let cf = Controlfield::new("005", "20240101000000.0").unwrap();
+    /// record.insert_control_field_at(2, cf).unwrap();
+    ///
+    /// let tags: Vec<&str> = record.control_fields().iter().map(|f| f.tag()).collect();
+    /// assert_eq!(tags, vec!["001", "008", "005"]);
+    ///
+    /// let cf = Controlfield::new("003", "CONS").unwrap();
+    /// assert!(record.insert_control_field_at(4, cf).is_err());
+    /// ```
+    pub fn insert_control_field_at(
+        &mut self,
+        index: usize,
+        field: Controlfield,
+    ) -> Result<(), String> {
+        if index > self.control_fields().len() {
+            return Err(format!("Invalid control field index {index}"));
+        }
+
+        self.control_fields_mut().insert(index, field);
+
+        Ok(())
+    }
+
+    /// Replace the [`Controlfield`] at the provided index, returning
+    /// the replaced field.
+    ///
+    /// Err if there is no control field at the index.
+    pub fn replace_control_field(
+        &mut self,
+        index: usize,
+        field: Controlfield,
+    ) -> Result<Controlfield, String> {
+        match self.control_fields_mut().get_mut(index) {
+            Some(f) => Ok(std::mem::replace(f, field)),
+            None => Err(format!("No control field at index {index}")),
+        }
+    }
+
+    /// Insert a [`Field`] at the provided index, regardless of tag
+    /// order, then return a mut ref to the new field.
+    ///
+    /// Err if the index is greater than the number of fields.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::{Field, Record};
+    /// let mut record = Record::from_breaker("=245 10$aTitle\n=650 \\0$aCats.").unwrap();
+    ///
+    /// // Keep the 880 adjacent to the field it links to.
+    /// let field = record.insert_field_at(1, Field::new("880").unwrap()).unwrap();
+    /// field.add_subfield("6", "245-01").unwrap();
+    ///
+    /// let tags: Vec<&str> = record.fields().iter().map(|f| f.tag()).collect();
+    /// assert_eq!(tags, vec!["245", "880", "650"]);
+    ///
+    /// assert!(record.insert_field_at(4, Field::new("500").unwrap()).is_err());
+    /// ```
+    pub fn insert_field_at(&mut self, index: usize, field: Field) -> Result<&mut Field, String> {
+        if index > self.fields().len() {
+            return Err(format!("Invalid field index {index}"));
+        }
+
+        self.fields_mut().insert(index, field);
+
+        Ok(&mut self.fields_mut()[index])
+    }
+
+    /// Replace the [`Field`] at the provided index, returning the
+    /// replaced field.
+    ///
+    /// Err if there is no field at the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::{Field, Record};
+    /// let mut record = Record::from_breaker("=100 1\\$aSmith\n=245 10$aTitle").unwrap();
+    ///
+    /// let mut field = Field::new("110").unwrap();
+    /// field.add_subfield("a", "Smith & Co.").unwrap();
+    ///
+    /// let old = record.replace_field(0, field).unwrap();
+    /// assert_eq!(old.tag(), "100");
+    ///
+    /// let tags: Vec<&str> = record.fields().iter().map(|f| f.tag()).collect();
+    /// assert_eq!(tags, vec!["110", "245"]);
+    ///
+    /// assert!(record.replace_field(2, Field::new("500").unwrap()).is_err());
+    /// ```
+    pub fn replace_field(&mut self, index: usize, field: Field) -> Result<Field, String> {
+        match self.fields_mut().get_mut(index) {
+            Some(f) => Ok(std::mem::replace(f, field)),
+            None => Err(format!("No field at index {index}")),
+        }
+    }
+
+    /// Insert a [`Field`] directly after the last field with the
+    /// provided tag.  If the record has no fields with the tag, the
+    /// field is inserted in tag order.
+    ///
+    /// Returns the index of the new field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::{Field, Record};
+    /// let mut record = Record::from_breaker(
+    ///     "=245 10$aTitle\n=650 \\0$aCats.\n=590 \\\\$aLocal note"
+    /// ).unwrap();
+    ///
+    /// let idx = record.insert_field_after_tag("650", Field::new("650").unwrap());
+    /// assert_eq!(idx, 2);
+    ///
+    /// let idx = record.insert_field_after_tag("500", Field::new("500").unwrap());
+    /// assert_eq!(idx, 1);
+    ///
+    /// let tags: Vec<&str> = record.fields().iter().map(|f| f.tag()).collect();
+    /// assert_eq!(tags, vec!["245", "500", "650", "650", "590"]);
+    /// ```
+    pub fn insert_field_after_tag(&mut self, tag: &str, field: Field) -> usize {
+        match self.fields().iter().rposition(|f| f.tag() == tag) {
+            Some(idx) => {
+                self.fields_mut().insert(idx + 1, field);
+                idx + 1
+            }
+            None => self.insert_data_field(field),
         }
     }
 
@@ -622,6 +810,9 @@ impl Record {
     /// assert!(record.add_data_field("1234").is_err());
     ///
     /// assert_eq!(record.fields()[0].tag(), "240");
+    ///
+    /// // Fields appended to the end of the record are returned as well.
+    /// assert_eq!(record.add_data_field("500").unwrap().tag(), "500");
     /// ```
     pub fn add_data_field(&mut self, tag: impl Into<String>) -> Result<&mut Field, String> {
         let pos = self.insert_data_field(Field::new(tag)?);
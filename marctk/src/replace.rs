@@ -0,0 +1,259 @@
+//! Regular expression find/replace within record values.
+//!
+//! Replacements are limited to a [`Scope`], e.g. specific subfields of
+//! specific fields, and report each value they change.  Replacement
+//! text may refer to capture groups as `$1`, `${name}`, etc.  Use `$$`
+//! for a literal "$".
+//!
+//! # Examples
+//!
+//! ```
+//! use marctk::Record;
+//! use marctk::replace::Scope;
+//!
+//! let mut record = Record::from_breaker("=245 10$aTitle ;$bSubtitle /$cAuthor.").unwrap();
+//!
+//! let changes = record
+//!     .replace(r"\s+[;/]$", "", Scope::Subfields("245", &["a", "b"]))
+//!     .unwrap();
+//!
+//! assert_eq!(changes.len(), 2);
+//! assert_eq!(changes[0].to_string(), "245 $a: \"Title ;\" => \"Title\"");
+//! assert_eq!(record.to_breaker(), "=LDR                         \n=245 10$aTitle$bSubtitle$cAuthor.");
+//! ```
+use super::Field;
+use super::Record;
+use regex::Regex;
+use std::fmt;
+
+/// Which values a find/replace applies to.
+///
+/// Tags may be specs as used by [`Field::matches_spec`], e.g. "6xx".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope<'a> {
+    /// Every control field and subfield.
+    All,
+    /// Control fields matching the tag spec.
+    ControlFields(&'a str),
+    /// Every subfield of fields matching the tag spec.
+    Fields(&'a str),
+    /// Subfields with the provided codes of fields matching the tag spec.
+    Subfields(&'a str, &'a [&'a str]),
+}
+
+impl Scope<'_> {
+    fn includes_control_field(&self, tag: &str) -> bool {
+        match self {
+            Scope::All => true,
+            Scope::ControlFields(spec) => tag_matches(tag, spec),
+            _ => false,
+        }
+    }
+
+    fn includes_field(&self, field: &Field) -> bool {
+        match self {
+            Scope::All => true,
+            Scope::Fields(spec) | Scope::Subfields(spec, _) => field.matches_spec(spec),
+            Scope::ControlFields(_) => false,
+        }
+    }
+
+    fn includes_code(&self, code: &str) -> bool {
+        match self {
+            Scope::Subfields(_, codes) => codes.contains(&code),
+            _ => true,
+        }
+    }
+}
+
+fn tag_matches(tag: &str, spec: &str) -> bool {
+    spec.len() == 3
+        && spec
+            .chars()
+            .zip(tag.chars())
+            .all(|(s, t)| s.eq_ignore_ascii_case(&'x') || s == t)
+}
+
+/// A value changed, or which would be changed, by a find/replace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replacement {
+    pub tag: String,
+    /// Position of the field within the record's control fields or
+    /// data fields.
+    pub field_index: usize,
+    /// Subfield code, or None for control fields.
+    pub code: Option<String>,
+    /// Position of the subfield within its field.
+    pub subfield_index: Option<usize>,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+impl fmt::Display for Replacement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.code.as_deref() {
+            Some(code) => write!(f, "{} ${code}: ", self.tag)?,
+            None => write!(f, "{}: ", self.tag)?,
+        }
+        write!(f, "{:?} => {:?}", self.old_value, self.new_value)
+    }
+}
+
+/// A compiled find/replace which may be applied to many records.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::Record;
+/// use marctk::replace::{FindReplace, Scope};
+///
+/// let fr = FindReplace::new(r"^(\d{3})(\d{3})$", "$1-$2", Scope::ControlFields("001")).unwrap();
+///
+/// let mut record = Record::from_breaker("=001 123456").unwrap();
+///
+/// // Preview the changes without applying them.
+/// let changes = fr.preview(&record);
+/// assert_eq!(changes[0].new_value, "123-456");
+/// assert_eq!(record.get_control_fields("001")[0].content(), "123456");
+///
+/// fr.apply(&mut record);
+/// assert_eq!(record.get_control_fields("001")[0].content(), "123-456");
+///
+/// assert!(FindReplace::new("(", "", Scope::All).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct FindReplace<'a> {
+    regex: Regex,
+    replacement: String,
+    scope: Scope<'a>,
+}
+
+impl<'a> FindReplace<'a> {
+    pub fn new(pattern: &str, replacement: &str, scope: Scope<'a>) -> Result<Self, String> {
+        let regex = Regex::new(pattern).map_err(|e| format!("Invalid pattern {pattern}: {e}"))?;
+
+        Ok(FindReplace {
+            regex,
+            replacement: replacement.to_string(),
+            scope,
+        })
+    }
+
+    /// Returns the changes which [`FindReplace::apply`] would make,
+    /// without modifying the record.
+    pub fn preview(&self, record: &Record) -> Vec<Replacement> {
+        self.apply(&mut record.clone())
+    }
+
+    /// Replace every match within the scope.
+    ///
+    /// Returns the changed values.
+    pub fn apply(&self, record: &mut Record) -> Vec<Replacement> {
+        let mut changes = Vec::new();
+
+        for (idx, cf) in record.control_fields_mut().iter_mut().enumerate() {
+            if !self.scope.includes_control_field(cf.tag()) {
+                continue;
+            }
+
+            if let Some(value) = self.replace_value(cf.content()) {
+                changes.push(Replacement {
+                    tag: cf.tag().to_string(),
+                    field_index: idx,
+                    code: None,
+                    subfield_index: None,
+                    old_value: cf.content().to_string(),
+                    new_value: value.clone(),
+                });
+
+                cf.set_content(value);
+            }
+        }
+
+        for (idx, field) in record.fields_mut().iter_mut().enumerate() {
+            if !self.scope.includes_field(field) {
+                continue;
+            }
+
+            let tag = field.tag().to_string();
+
+            for (sf_idx, sf) in field.subfields_mut().iter_mut().enumerate() {
+                if !self.scope.includes_code(sf.code()) {
+                    continue;
+                }
+
+                if let Some(value) = self.replace_value(sf.content()) {
+                    changes.push(Replacement {
+                        tag: tag.clone(),
+                        field_index: idx,
+                        code: Some(sf.code().to_string()),
+                        subfield_index: Some(sf_idx),
+                        old_value: sf.content().to_string(),
+                        new_value: value.clone(),
+                    });
+
+                    sf.set_content(value);
+                }
+            }
+        }
+
+        changes
+    }
+
+    /// The new value, if it differs from the original.
+    fn replace_value(&self, value: &str) -> Option<String> {
+        let new_value = self.regex.replace_all(value, self.replacement.as_str());
+
+        if new_value != value {
+            Some(new_value.into_owned())
+        } else {
+            None
+        }
+    }
+}
+
+impl Record {
+    /// Replace every match of the regular expression within the scope.
+    ///
+    /// Returns the changed values, or Err if the pattern is invalid.
+    /// See [`FindReplace`] to preview changes or to apply the same
+    /// find/replace to many records.
+    pub fn replace(
+        &mut self,
+        pattern: &str,
+        replacement: &str,
+        scope: Scope,
+    ) -> Result<Vec<Replacement>, String> {
+        Ok(FindReplace::new(pattern, replacement, scope)?.apply(self))
+    }
+
+    /// Returns the values [`Record::replace`] would change, without
+    /// modifying the record.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::replace::Scope;
+    ///
+    /// let record = Record::from_breaker("=650 \\0$aCats$xHistory.\n=651 \\0$aOhio.").unwrap();
+    ///
+    /// let changes = record.replace_dry_run(r"\.$", "", Scope::Fields("65x")).unwrap();
+    ///
+    /// let found: Vec<String> = changes.iter().map(|c| c.to_string()).collect();
+    /// assert_eq!(found, vec![
+    ///     "650 $x: \"History.\" => \"History\"",
+    ///     "651 $a: \"Ohio.\" => \"Ohio\"",
+    /// ]);
+    ///
+    /// assert_eq!(record.get_field_values("651", "a"), vec!["Ohio."]);
+    /// ```
+    pub fn replace_dry_run(
+        &self,
+        pattern: &str,
+        replacement: &str,
+        scope: Scope,
+    ) -> Result<Vec<Replacement>, String> {
+        Ok(FindReplace::new(pattern, replacement, scope)?.preview(self))
+    }
+}
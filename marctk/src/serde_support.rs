@@ -0,0 +1,146 @@
+//! serde Serialize/Deserialize implementations.
+//!
+//! Records serialize as structs of their leader, control fields, and
+//! data fields:
+//!
+//! ```text
+//! {
+//!   "leader": "00000nam a2200000 a 4500",
+//!   "control_fields": [{"tag": "001", "content": "123"}],
+//!   "fields": [{
+//!     "tag": "245", "ind1": "1", "ind2": "0",
+//!     "subfields": [{"code": "a", "content": "Title"}]
+//!   }]
+//! }
+//! ```
+//!
+//! Values are validated when deserialized, the same as when created
+//! via the constructors.  Any change journal is not serialized.
+use super::Controlfield;
+use super::Field;
+use super::Record;
+use super::Subfield;
+use serde::de::{Deserializer, Error};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+
+impl Serialize for Controlfield {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Controlfield", 2)?;
+        s.serialize_field("tag", self.tag())?;
+        s.serialize_field("content", self.content())?;
+        s.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct ControlfieldData {
+    tag: String,
+    #[serde(default)]
+    content: String,
+}
+
+impl<'de> Deserialize<'de> for Controlfield {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = ControlfieldData::deserialize(deserializer)?;
+        Controlfield::new(data.tag, data.content).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for Subfield {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Subfield", 2)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("content", self.content())?;
+        s.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct SubfieldData {
+    code: String,
+    #[serde(default)]
+    content: String,
+}
+
+impl<'de> Deserialize<'de> for Subfield {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = SubfieldData::deserialize(deserializer)?;
+        Subfield::new(data.code, data.content).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for Field {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Field", 4)?;
+        s.serialize_field("tag", self.tag())?;
+        s.serialize_field("ind1", self.ind1())?;
+        s.serialize_field("ind2", self.ind2())?;
+        s.serialize_field("subfields", self.subfields())?;
+        s.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct FieldData {
+    tag: String,
+    ind1: Option<String>,
+    ind2: Option<String>,
+    #[serde(default)]
+    subfields: Vec<Subfield>,
+}
+
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = FieldData::deserialize(deserializer)?;
+
+        let mut field = Field::new(data.tag).map_err(D::Error::custom)?;
+
+        if let Some(ind) = data.ind1 {
+            field.set_ind1(ind).map_err(D::Error::custom)?;
+        }
+        if let Some(ind) = data.ind2 {
+            field.set_ind2(ind).map_err(D::Error::custom)?;
+        }
+
+        *field.subfields_mut() = data.subfields;
+
+        Ok(field)
+    }
+}
+
+impl Serialize for Record {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Record", 3)?;
+        s.serialize_field("leader", self.leader())?;
+        s.serialize_field("control_fields", self.control_fields())?;
+        s.serialize_field("fields", self.fields())?;
+        s.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct RecordData {
+    leader: Option<String>,
+    #[serde(default)]
+    control_fields: Vec<Controlfield>,
+    #[serde(default)]
+    fields: Vec<Field>,
+}
+
+impl<'de> Deserialize<'de> for Record {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = RecordData::deserialize(deserializer)?;
+
+        let mut record = Record::new();
+
+        if let Some(leader) = data.leader {
+            record.set_leader(leader).map_err(D::Error::custom)?;
+        }
+
+        *record.control_fields_mut() = data.control_fields;
+        *record.fields_mut() = data.fields;
+
+        Ok(record)
+    }
+}
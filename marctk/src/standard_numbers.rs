@@ -0,0 +1,365 @@
+//! ISBN (020) and ISSN (022) extraction and normalization.
+//!
+//! Values are normalized by removing hyphens and spaces, uppercasing
+//! any "X" check digit, and setting aside trailing qualifiers such as
+//! "(pbk.)".  Parsing only checks the shape of a number.  Use
+//! `is_valid()` to also verify the check digit.
+//!
+//! # References
+//!
+//! * <https://www.loc.gov/marc/bibliographic/bd020.html>
+//! * <https://www.loc.gov/marc/bibliographic/bd022.html>
+use super::Record;
+use std::fmt;
+
+/// Check digit for the first 9 digits of an ISBN-10.
+///
+/// ```
+/// assert_eq!(marctk::standard_numbers::isbn10_check_digit("030640615"), Some('2'));
+/// assert_eq!(marctk::standard_numbers::isbn10_check_digit("12345"), None);
+/// ```
+pub fn isbn10_check_digit(body: &str) -> Option<char> {
+    let digits = ascii_digits(body, 9)?;
+
+    let sum: u32 = digits
+        .iter()
+        .enumerate()
+        .map(|(i, d)| (10 - i as u32) * d)
+        .sum();
+
+    match (11 - sum % 11) % 11 {
+        10 => Some('X'),
+        d => char::from_digit(d, 10),
+    }
+}
+
+/// Check digit for the first 12 digits of an ISBN-13.
+///
+/// ```
+/// assert_eq!(marctk::standard_numbers::isbn13_check_digit("978030640615"), Some('7'));
+/// ```
+pub fn isbn13_check_digit(body: &str) -> Option<char> {
+    let digits = ascii_digits(body, 12)?;
+
+    let sum: u32 = digits
+        .iter()
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { *d } else { d * 3 })
+        .sum();
+
+    char::from_digit((10 - sum % 10) % 10, 10)
+}
+
+/// Check digit for the first 7 digits of an ISSN.
+///
+/// ```
+/// assert_eq!(marctk::standard_numbers::issn_check_digit("0378595"), Some('5'));
+/// ```
+pub fn issn_check_digit(body: &str) -> Option<char> {
+    let digits = ascii_digits(body, 7)?;
+
+    let sum: u32 = digits
+        .iter()
+        .enumerate()
+        .map(|(i, d)| (8 - i as u32) * d)
+        .sum();
+
+    match (11 - sum % 11) % 11 {
+        10 => Some('X'),
+        d => char::from_digit(d, 10),
+    }
+}
+
+/// Digit values if the string is exactly `len` ASCII digits.
+fn ascii_digits(value: &str, len: usize) -> Option<Vec<u32>> {
+    if value.len() != len {
+        return None;
+    }
+    value.chars().map(|c| c.to_digit(10)).collect()
+}
+
+/// Split a raw value into its number, with hyphens and spaces
+/// removed, and its cleaned qualifier, if any.
+///
+/// The number ends at the first character which is not a digit, X,
+/// hyphen, or space between digits.
+fn split_qualifier(value: &str) -> (String, Option<String>) {
+    let value = value.trim();
+
+    let end = value
+        .char_indices()
+        .find(|(i, c)| {
+            !(c.is_ascii_digit()
+                || matches!(c, 'X' | 'x' | '-')
+                || (*c == ' ' && value[i + 1..].starts_with(|n: char| n.is_ascii_digit())))
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(value.len());
+
+    let number: String = value[..end]
+        .chars()
+        .filter(|c| !matches!(c, '-' | ' '))
+        .collect::<String>()
+        .to_uppercase();
+
+    let qualifier = value[end..]
+        .trim_matches(['(', ')', ' ', ':', ';'])
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    (number, Some(qualifier).filter(|q| !q.is_empty()))
+}
+
+/// True if all chars are digits, except the last which may be "X".
+fn is_check_x_number(value: &str) -> bool {
+    let len = value.len();
+    value
+        .chars()
+        .enumerate()
+        .all(|(i, c)| c.is_ascii_digit() || (c == 'X' && i == len - 1))
+}
+
+/// A normalized ISBN-10 or ISBN-13.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Isbn {
+    number: String,
+    qualifier: Option<String>,
+}
+
+impl Isbn {
+    /// Parse an ISBN, e.g. from 020 $a.
+    ///
+    /// Returns None if the value does not have the shape of an ISBN.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::standard_numbers::Isbn;
+    ///
+    /// let isbn = Isbn::parse("0-306-40615-2 (pbk.)").unwrap();
+    /// assert_eq!(isbn.as_str(), "0306406152");
+    /// assert_eq!(isbn.qualifier(), Some("pbk."));
+    /// assert!(isbn.is_valid());
+    /// assert_eq!(isbn.to_isbn13(), "9780306406157");
+    ///
+    /// let isbn = Isbn::parse("978 0 306 40615 7").unwrap();
+    /// assert_eq!(isbn.to_isbn10().as_deref(), Some("0306406152"));
+    ///
+    /// // Bad check digit
+    /// assert!(!Isbn::parse("0306406153").unwrap().is_valid());
+    ///
+    /// // 979 ISBNs have no ISBN-10 form
+    /// assert_eq!(Isbn::parse("9791234567896").unwrap().to_isbn10(), None);
+    ///
+    /// assert!(Isbn::parse("12345").is_none());
+    /// ```
+    pub fn parse(value: &str) -> Option<Isbn> {
+        let (number, qualifier) = split_qualifier(value);
+
+        let valid_shape = match number.len() {
+            10 => is_check_x_number(&number),
+            13 => number.chars().all(|c| c.is_ascii_digit()),
+            _ => false,
+        };
+
+        if valid_shape {
+            Some(Isbn { number, qualifier })
+        } else {
+            None
+        }
+    }
+
+    /// The number without hyphens or qualifiers.
+    pub fn as_str(&self) -> &str {
+        &self.number
+    }
+
+    /// Qualifying information, e.g. "pbk." or "v. 1".
+    pub fn qualifier(&self) -> Option<&str> {
+        self.qualifier.as_deref()
+    }
+
+    pub fn is_isbn13(&self) -> bool {
+        self.number.len() == 13
+    }
+
+    /// True if the check digit is correct.
+    pub fn is_valid(&self) -> bool {
+        let (body, check) = self.number.split_at(self.number.len() - 1);
+
+        let expected = if self.is_isbn13() {
+            isbn13_check_digit(body)
+        } else {
+            isbn10_check_digit(body)
+        };
+
+        expected.is_some_and(|c| check.starts_with(c))
+    }
+
+    /// ISBN-13 form with a recalculated check digit.
+    pub fn to_isbn13(&self) -> String {
+        if self.is_isbn13() {
+            return self.number.clone();
+        }
+
+        let body = format!("978{}", &self.number[..9]);
+        let check = isbn13_check_digit(&body).unwrap_or('0');
+
+        format!("{body}{check}")
+    }
+
+    /// ISBN-10 form with a recalculated check digit.
+    ///
+    /// Returns None for ISBN-13s outside the 978 prefix.
+    pub fn to_isbn10(&self) -> Option<String> {
+        if !self.is_isbn13() {
+            return Some(self.number.clone());
+        }
+
+        let body = self.number.strip_prefix("978")?;
+        let body = &body[..9];
+
+        Some(format!("{body}{}", isbn10_check_digit(body)?))
+    }
+}
+
+impl fmt::Display for Isbn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.number)
+    }
+}
+
+/// A normalized ISSN.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issn {
+    number: String,
+    qualifier: Option<String>,
+}
+
+impl Issn {
+    /// Parse an ISSN, e.g. from 022 $a.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::standard_numbers::Issn;
+    ///
+    /// let issn = Issn::parse("0378-5955").unwrap();
+    /// assert_eq!(issn.as_str(), "03785955");
+    /// assert_eq!(issn.to_string(), "0378-5955");
+    /// assert!(issn.is_valid());
+    ///
+    /// assert!(Issn::parse("2049-3630 (Print)").unwrap().is_valid());
+    /// assert!(!Issn::parse("0378-5956").unwrap().is_valid());
+    /// assert!(Issn::parse("0378-595").is_none());
+    /// ```
+    pub fn parse(value: &str) -> Option<Issn> {
+        let (number, qualifier) = split_qualifier(value);
+
+        if number.len() == 8 && is_check_x_number(&number) {
+            Some(Issn { number, qualifier })
+        } else {
+            None
+        }
+    }
+
+    /// The number without hyphens or qualifiers.
+    pub fn as_str(&self) -> &str {
+        &self.number
+    }
+
+    pub fn qualifier(&self) -> Option<&str> {
+        self.qualifier.as_deref()
+    }
+
+    /// True if the check digit is correct.
+    pub fn is_valid(&self) -> bool {
+        issn_check_digit(&self.number[..7]).is_some_and(|c| self.number.ends_with(c))
+    }
+}
+
+/// Hyphenated form, e.g. "0378-5955".
+impl fmt::Display for Issn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", &self.number[..4], &self.number[4..])
+    }
+}
+
+impl Record {
+    /// ISBNs from each 020 $a, in field order, without duplicates.
+    ///
+    /// Values which do not look like ISBNs are skipped.  Numbers with
+    /// bad check digits are included; see [`Isbn::is_valid`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =020 \\$a0306406152 (pbk.)$z0306406153
+    /// =020 \\$a9780306406157
+    /// =020 \\$aNot an ISBN
+    /// =022 0\$a0378-5955$y0378-5956"#
+    /// ).unwrap();
+    ///
+    /// let isbns: Vec<String> = record.isbns().iter().map(|i| i.to_string()).collect();
+    /// assert_eq!(isbns, vec!["0306406152", "9780306406157"]);
+    ///
+    /// assert_eq!(record.canceled_isbns()[0].as_str(), "0306406153");
+    ///
+    /// // Both forms of every ISBN, e.g. for matching.
+    /// assert_eq!(record.isbn13s(), vec!["9780306406157"]);
+    ///
+    /// assert_eq!(record.issns()[0].to_string(), "0378-5955");
+    /// ```
+    pub fn isbns(&self) -> Vec<Isbn> {
+        self.standard_numbers("020", "a", Isbn::parse, |a, b| a.number == b.number)
+    }
+
+    /// Canceled or invalid ISBNs from each 020 $z.
+    pub fn canceled_isbns(&self) -> Vec<Isbn> {
+        self.standard_numbers("020", "z", Isbn::parse, |a, b| a.number == b.number)
+    }
+
+    /// The ISBN-13 form of each valid 020 $a, without duplicates.
+    pub fn isbn13s(&self) -> Vec<String> {
+        let mut numbers: Vec<String> = Vec::new();
+
+        for isbn in self.isbns().iter().filter(|i| i.is_valid()) {
+            let num = isbn.to_isbn13();
+            if !numbers.contains(&num) {
+                numbers.push(num);
+            }
+        }
+
+        numbers
+    }
+
+    /// ISSNs from each 022 $a, in field order, without duplicates.
+    pub fn issns(&self) -> Vec<Issn> {
+        self.standard_numbers("022", "a", Issn::parse, |a, b| a.number == b.number)
+    }
+
+    fn standard_numbers<T>(
+        &self,
+        tag: &str,
+        code: &str,
+        parse: fn(&str) -> Option<T>,
+        same: fn(&T, &T) -> bool,
+    ) -> Vec<T> {
+        let mut numbers: Vec<T> = Vec::new();
+
+        for value in self.get_field_values(tag, code) {
+            if let Some(num) = parse(value) {
+                if !numbers.iter().any(|n| same(n, &num)) {
+                    numbers.push(num);
+                }
+            }
+        }
+
+        numbers
+    }
+}
@@ -0,0 +1,667 @@
+//! UNIMARC records and basic UNIMARC / MARC21 conversion.
+//!
+//! UNIMARC records share the ISO 2709 binary structure, the MARC XML
+//! and breaker formats, and the [`Record`] model with MARC21.  They
+//! differ in their leader and in their tag and subfield assignments.
+//!
+//! The UNIMARC leader has no character coding value at position 09;
+//! the character set is declared in 100 $a positions 26-29 instead.
+//! Use [`Record::from_binary_unimarc`], or [`MarcFormat::Unimarc`] with
+//! the binary record iterator, to read binary UNIMARC without treating
+//! the blank leader position as a MARC-8 declaration.  Only UTF-8
+//! UNIMARC is supported.
+//!
+//! The conversions cover the leader, the coded data in the MARC21 008
+//! and UNIMARC 100 fields, and the commonly used descriptive, name,
+//! subject, and classification fields.  Fields with no mapping are
+//! dropped.
+//!
+//! # References
+//!
+//! * <https://www.ifla.org/references/best-practice-for-national-bibliographic-agencies-in-a-digital-age/resource-description-and-standards/bibliographic-control/unimarc/>
+//! * <https://www.loc.gov/marc/unimarctomarc21.html>
+use super::Controlfield;
+use super::Field;
+use super::Record;
+use super::Subfield;
+use std::str::FromStr;
+
+/// Character set value for UTF-8 in 100 $a positions 26-29.
+pub const UNIMARC_CHARSET_UTF8: &str = "50  ";
+
+/// Leader position 09 in UNIMARC is undefined.
+const CHAR_CODING_IDX: usize = 9;
+
+/// Record formats which share the binary record structure.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MarcFormat {
+    #[default]
+    Marc21,
+    Unimarc,
+}
+
+impl FromStr for MarcFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "marc21" => Ok(Self::Marc21),
+            "unimarc" => Ok(Self::Unimarc),
+            _ => Err(format!("Invalid MARC format: {s}")),
+        }
+    }
+}
+
+/// Tag mappings for fields which map one-to-one.
+struct FieldMap {
+    unimarc: &'static str,
+    unimarc_inds: &'static str,
+    marc21: &'static str,
+    marc21_inds: &'static str,
+    /// Pairs of UNIMARC and MARC21 subfield codes.
+    subfields: &'static [(&'static str, &'static str)],
+}
+
+const FIELD_MAP: &[FieldMap] = &[
+    FieldMap {
+        unimarc: "010",
+        unimarc_inds: "  ",
+        marc21: "020",
+        marc21_inds: "  ",
+        subfields: &[("a", "a"), ("b", "q"), ("z", "z")],
+    },
+    FieldMap {
+        unimarc: "011",
+        unimarc_inds: "  ",
+        marc21: "022",
+        marc21_inds: "  ",
+        subfields: &[("a", "a"), ("y", "y"), ("z", "z")],
+    },
+    FieldMap {
+        unimarc: "101",
+        unimarc_inds: "0 ",
+        marc21: "041",
+        marc21_inds: "0 ",
+        subfields: &[("a", "a"), ("c", "h")],
+    },
+    FieldMap {
+        unimarc: "200",
+        unimarc_inds: "1 ",
+        marc21: "245",
+        marc21_inds: "10",
+        subfields: &[("a", "a"), ("e", "b"), ("f", "c"), ("h", "n"), ("i", "p")],
+    },
+    FieldMap {
+        unimarc: "205",
+        unimarc_inds: "  ",
+        marc21: "250",
+        marc21_inds: "  ",
+        subfields: &[("a", "a"), ("f", "b")],
+    },
+    FieldMap {
+        unimarc: "210",
+        unimarc_inds: "  ",
+        marc21: "260",
+        marc21_inds: "  ",
+        subfields: &[("a", "a"), ("c", "b"), ("d", "c")],
+    },
+    FieldMap {
+        unimarc: "215",
+        unimarc_inds: "  ",
+        marc21: "300",
+        marc21_inds: "  ",
+        subfields: &[("a", "a"), ("c", "b"), ("d", "c"), ("e", "e")],
+    },
+    FieldMap {
+        unimarc: "225",
+        unimarc_inds: "2 ",
+        marc21: "490",
+        marc21_inds: "0 ",
+        subfields: &[("a", "a"), ("v", "v"), ("x", "x")],
+    },
+    FieldMap {
+        unimarc: "300",
+        unimarc_inds: "  ",
+        marc21: "500",
+        marc21_inds: "  ",
+        subfields: &[("a", "a")],
+    },
+    FieldMap {
+        unimarc: "330",
+        unimarc_inds: "  ",
+        marc21: "520",
+        marc21_inds: "  ",
+        subfields: &[("a", "a")],
+    },
+    // UNIMARC uses $y for geographic and $z for chronological
+    // subdivisions, the reverse of MARC21.
+    FieldMap {
+        unimarc: "606",
+        unimarc_inds: "  ",
+        marc21: "650",
+        marc21_inds: " 4",
+        subfields: &[("a", "a"), ("x", "x"), ("y", "z"), ("z", "y"), ("j", "v")],
+    },
+    FieldMap {
+        unimarc: "607",
+        unimarc_inds: "  ",
+        marc21: "651",
+        marc21_inds: " 4",
+        subfields: &[("a", "a"), ("x", "x"), ("y", "z"), ("z", "y"), ("j", "v")],
+    },
+    FieldMap {
+        unimarc: "608",
+        unimarc_inds: "  ",
+        marc21: "655",
+        marc21_inds: " 4",
+        subfields: &[("a", "a")],
+    },
+    FieldMap {
+        unimarc: "676",
+        unimarc_inds: "  ",
+        marc21: "082",
+        marc21_inds: "04",
+        subfields: &[("a", "a"), ("v", "2")],
+    },
+    FieldMap {
+        unimarc: "680",
+        unimarc_inds: "  ",
+        marc21: "050",
+        marc21_inds: " 4",
+        subfields: &[("a", "a"), ("b", "b")],
+    },
+    FieldMap {
+        unimarc: "856",
+        unimarc_inds: "4 ",
+        marc21: "856",
+        marc21_inds: "4 ",
+        subfields: &[("u", "u"), ("y", "y"), ("z", "z")],
+    },
+];
+
+/// Name tag mappings.
+///
+/// Personal names are split into entry element ($a) and remainder ($b)
+/// in UNIMARC and combined in MARC21 $a.
+struct NameMap {
+    unimarc: &'static str,
+    marc21: &'static str,
+    personal: bool,
+}
+
+const NAME_MAP: &[NameMap] = &[
+    NameMap {
+        unimarc: "700",
+        marc21: "100",
+        personal: true,
+    },
+    NameMap {
+        unimarc: "701",
+        marc21: "700",
+        personal: true,
+    },
+    NameMap {
+        unimarc: "702",
+        marc21: "700",
+        personal: true,
+    },
+    NameMap {
+        unimarc: "600",
+        marc21: "600",
+        personal: true,
+    },
+    NameMap {
+        unimarc: "710",
+        marc21: "110",
+        personal: false,
+    },
+    NameMap {
+        unimarc: "711",
+        marc21: "710",
+        personal: false,
+    },
+    NameMap {
+        unimarc: "712",
+        marc21: "710",
+        personal: false,
+    },
+    NameMap {
+        unimarc: "601",
+        marc21: "610",
+        personal: false,
+    },
+];
+
+/// UNIMARC 100 $a and MARC21 008 type of date codes.
+const DATE_TYPES: &[(char, char)] = &[
+    ('a', 'c'),
+    ('b', 'd'),
+    ('c', 'u'),
+    ('d', 's'),
+    ('e', 'r'),
+    ('f', 'q'),
+    ('g', 'm'),
+    ('h', 't'),
+    ('i', 'p'),
+    ('j', 'e'),
+    ('u', 'n'),
+];
+
+/// UNIMARC and MARC21 leader encoding level codes.
+const ENCODING_LEVELS: &[(char, char)] = &[(' ', ' '), ('1', '1'), ('2', '8'), ('3', '3')];
+
+/// UNIMARC and MARC21 leader type of record codes which differ.
+const RECORD_TYPES: &[(char, char)] = &[('b', 't'), ('l', 'm')];
+
+fn map_code(map: &[(char, char)], value: char, reverse: bool, default: char) -> char {
+    map.iter()
+        .find(|(u, m)| if reverse { *m == value } else { *u == value })
+        .map(|(u, m)| if reverse { *u } else { *m })
+        .unwrap_or(default)
+}
+
+/// Substring of a fixed field value by character position, padded
+/// with blanks if the value is too short.
+fn fixed(value: &str, start: usize, len: usize) -> String {
+    let mut s: String = value.chars().skip(start).take(len).collect();
+    while s.chars().count() < len {
+        s.push(' ');
+    }
+    s
+}
+
+fn new_field(tag: &str, inds: &str) -> Result<Field, String> {
+    let mut field = Field::new(tag)?;
+    field.set_ind1(&inds[..1])?;
+    field.set_ind2(&inds[1..])?;
+    Ok(field)
+}
+
+/// Copy subfields from one field to another per the code mappings.
+fn map_subfields(
+    source: &Field,
+    target: &mut Field,
+    codes: &[(&str, &str)],
+    reverse: bool,
+) -> Result<(), String> {
+    for sf in source.subfields() {
+        let code = codes
+            .iter()
+            .find(|(u, m)| sf.code() == if reverse { *m } else { *u })
+            .map(|(u, m)| if reverse { *u } else { *m });
+
+        if let Some(code) = code {
+            target
+                .subfields_mut()
+                .push(Subfield::new(code, sf.content())?);
+        }
+    }
+
+    Ok(())
+}
+
+impl Record {
+    /// Creates a single UNIMARC Record from a series of UTF-8 bytes.
+    ///
+    /// The leader is kept as-is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     "=LDR 00000nam  22000001  450 \n=001 1\n=200 1\\$aCaf\u{00E9}"
+    /// ).unwrap();
+    ///
+    /// let bytes = record.to_binary_unimarc().unwrap();
+    /// let record2 = Record::from_binary_unimarc(&bytes).unwrap();
+    ///
+    /// assert_eq!(&record2.leader()[9..10], " ");
+    /// assert_eq!(record2.get_field_values("200", "a"), vec!["Caf\u{00E9}"]);
+    /// ```
+    pub fn from_binary_unimarc(rec_bytes: &[u8]) -> Result<Record, String> {
+        if std::str::from_utf8(rec_bytes).is_err() {
+            return Err("UNIMARC record content is not UTF-8".to_string());
+        }
+
+        let original = rec_bytes
+            .get(CHAR_CODING_IDX)
+            .map(|b| *b as char)
+            .ok_or_else(|| format!("Binary record is too short: {:?}", rec_bytes))?;
+
+        // Read as UTF-8 regardless of leader/09.
+        let mut bytes = rec_bytes.to_vec();
+        bytes[CHAR_CODING_IDX] = b'a';
+
+        let mut record = Record::from_binary(&bytes)?;
+
+        record
+            .typed_leader_mut()
+            .set_position(CHAR_CODING_IDX, original)?;
+
+        Ok(record)
+    }
+
+    /// Generates the binary form of a UNIMARC record.
+    ///
+    /// Leader positions 09 and 23, which are undefined in UNIMARC, are
+    /// set to blanks.
+    pub fn to_binary_unimarc(&self) -> Result<Vec<u8>, String> {
+        let mut record = self.clone();
+
+        let leader = record.typed_leader_mut();
+        leader.set_position(CHAR_CODING_IDX, ' ')?;
+        leader.set_position(23, ' ')?;
+
+        record.to_binary()
+    }
+
+    /// Create a MARC21 record from a UNIMARC record.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let mut unimarc = Record::from_breaker(
+    ///     r#"=001 123
+    /// =010 \\$a9780306406157$bpbk.
+    /// =100 \\$a20170101d2017    u  y0frey50      ba
+    /// =101 0\$afre
+    /// =200 1\$aLes misérables$fVictor Hugo
+    /// =606 \\$aFrance$xHistoire$z1815-1830
+    /// =700 \1$aHugo$bVictor$f1802-1885"#
+    /// ).unwrap();
+    ///
+    /// unimarc.set_leader("00000nam  22000001  450 ").unwrap();
+    ///
+    /// let marc21 = unimarc.unimarc_to_marc21().unwrap();
+    ///
+    /// assert_eq!(marc21.leader(), "00000nam a22000001i 4500");
+    /// assert_eq!(marc21.get_control_fields("001")[0].content(), "123");
+    /// assert_eq!(&marc21.get_control_fields("008")[0].content()[..15], "170101s2017    ");
+    /// assert_eq!(&marc21.get_control_fields("008")[0].content()[35..38], "fre");
+    /// assert_eq!(marc21.get_field_values("020", "q"), vec!["pbk."]);
+    /// assert_eq!(marc21.get_field_values("100", "a"), vec!["Hugo, Victor"]);
+    /// assert_eq!(marc21.get_field_values("100", "d"), vec!["1802-1885"]);
+    /// assert_eq!(marc21.get_fields("100")[0].ind1(), "1");
+    /// assert_eq!(marc21.get_field_values("245", "c"), vec!["Victor Hugo"]);
+    /// assert_eq!(marc21.get_field_values("650", "y"), vec!["1815-1830"]);
+    ///
+    /// // And back again
+    /// let unimarc2 = marc21.marc21_to_unimarc().unwrap();
+    ///
+    /// assert_eq!(unimarc2.leader(), "00000nam  22000001  450 ");
+    /// assert_eq!(unimarc2.get_field_values("200", "a"), vec!["Les misérables"]);
+    /// assert_eq!(unimarc2.get_field_values("700", "a"), vec!["Hugo"]);
+    /// assert_eq!(unimarc2.get_field_values("700", "b"), vec!["Victor"]);
+    /// assert_eq!(unimarc2.get_field_values("606", "z"), vec!["1815-1830"]);
+    /// assert_eq!(&unimarc2.get_field_values("100", "a")[0][8..17], "d2017    ");
+    /// ```
+    pub fn unimarc_to_marc21(&self) -> Result<Record, String> {
+        let mut record = Record::new();
+
+        record.set_leader(unimarc_leader_to_marc21(self.leader()))?;
+
+        for cf in self.control_fields() {
+            if cf.tag() == "001" || cf.tag() == "005" {
+                record.insert_control_field(cf.clone());
+            }
+        }
+
+        if let Some(data) = self.get_field_values("100", "a").first() {
+            let lang = self
+                .get_field_values("101", "a")
+                .first()
+                .map(|l| fixed(l, 0, 3))
+                .unwrap_or_else(|| "und".to_string());
+
+            record
+                .insert_control_field(Controlfield::new("008", general_data_to_008(data, &lang))?);
+        }
+
+        for field in self.fields() {
+            if let Some(map) = FIELD_MAP.iter().find(|m| m.unimarc == field.tag()) {
+                let mut target = new_field(map.marc21, map.marc21_inds)?;
+                map_subfields(field, &mut target, map.subfields, false)?;
+                record.insert_data_field(target);
+            } else if let Some(map) = NAME_MAP.iter().find(|m| m.unimarc == field.tag()) {
+                record.insert_data_field(unimarc_name_to_marc21(field, map)?);
+            }
+        }
+
+        Ok(record)
+    }
+
+    /// Create a UNIMARC record from a MARC21 record.
+    ///
+    /// See [`Record::unimarc_to_marc21`] for examples.
+    pub fn marc21_to_unimarc(&self) -> Result<Record, String> {
+        let mut record = Record::new();
+
+        record.set_leader(marc21_leader_to_unimarc(self.leader()))?;
+
+        for cf in self.control_fields() {
+            if cf.tag() == "001" || cf.tag() == "005" {
+                record.insert_control_field(cf.clone());
+            }
+        }
+
+        let lang = self
+            .get_field_values("040", "b")
+            .first()
+            .map(|l| fixed(l, 0, 3))
+            .unwrap_or_else(|| "eng".to_string());
+
+        let fixed_data = self
+            .get_control_fields("008")
+            .first()
+            .map(|cf| cf.content().to_string())
+            .unwrap_or_default();
+
+        let mut general = new_field("100", "  ")?;
+        general.subfields_mut().push(Subfield::new(
+            "a",
+            fixed_008_to_general_data(&fixed_data, &lang),
+        )?);
+        record.insert_data_field(general);
+
+        for field in self.fields() {
+            if let Some(map) = FIELD_MAP.iter().find(|m| m.marc21 == field.tag()) {
+                let mut target = new_field(map.unimarc, map.unimarc_inds)?;
+                map_subfields(field, &mut target, map.subfields, true)?;
+                record.insert_data_field(target);
+            } else if let Some((map, meeting)) = NameMap::for_marc21_tag(field.tag()) {
+                record.insert_data_field(marc21_name_to_unimarc(field, map, meeting)?);
+            }
+        }
+
+        Ok(record)
+    }
+}
+
+fn unimarc_leader_to_marc21(leader: &str) -> String {
+    let pos = |idx: usize| leader.chars().nth(idx).unwrap_or(' ');
+
+    let rtype = map_code(RECORD_TYPES, pos(6), false, pos(6));
+    let level = map_code(ENCODING_LEVELS, pos(17), false, 'u');
+
+    // Full or partial ISBD vs. non-ISBD.
+    let form = if pos(18) == 'n' { ' ' } else { 'i' };
+
+    format!(
+        "00000{}{}{} a2200000{}{} 4500",
+        pos(5),
+        rtype,
+        pos(7),
+        level,
+        form
+    )
+}
+
+fn marc21_leader_to_unimarc(leader: &str) -> String {
+    let pos = |idx: usize| leader.chars().nth(idx).unwrap_or(' ');
+
+    // UNIMARC has no "increase in encoding level" or "revised" status.
+    let status = match pos(5) {
+        'a' | 'p' => 'c',
+        s => s,
+    };
+
+    let rtype = map_code(RECORD_TYPES, pos(6), true, pos(6));
+    let level = map_code(ENCODING_LEVELS, pos(17), true, '3');
+
+    let form = match pos(18) {
+        ' ' => 'n',
+        'c' => 'i',
+        _ => ' ',
+    };
+
+    format!(
+        "00000{}{}{}  2200000{}{} 450 ",
+        status,
+        rtype,
+        pos(7),
+        level,
+        form
+    )
+}
+
+/// Build a MARC21 008 from UNIMARC 100 $a general processing data.
+fn general_data_to_008(data: &str, lang: &str) -> String {
+    let date_type = map_code(DATE_TYPES, data.chars().nth(8).unwrap_or('u'), false, 'n');
+
+    format!(
+        "{}{}{}{}xx {}{} d",
+        fixed(data, 2, 6),
+        date_type,
+        fixed(data, 9, 4),
+        fixed(data, 13, 4),
+        "|".repeat(17),
+        lang,
+    )
+}
+
+/// Build UNIMARC 100 $a general processing data from a MARC21 008.
+fn fixed_008_to_general_data(fixed_data: &str, lang: &str) -> String {
+    let entered = fixed(fixed_data, 0, 6);
+
+    // MARC21 uses 2-digit years.
+    let century = match entered[..2].parse::<u8>() {
+        Ok(y) if y < 50 => "20",
+        _ => "19",
+    };
+
+    let date_type = map_code(
+        DATE_TYPES,
+        fixed_data.chars().nth(6).unwrap_or('n'),
+        true,
+        'u',
+    );
+
+    format!(
+        "{century}{entered}{date_type}{}{}   y0{lang}y{UNIMARC_CHARSET_UTF8}    ba",
+        fixed(fixed_data, 7, 4),
+        fixed(fixed_data, 11, 4),
+    )
+}
+
+fn unimarc_name_to_marc21(field: &Field, map: &NameMap) -> Result<Field, String> {
+    let mut target = if map.personal {
+        // UNIMARC ind2 "1" means entered under surname.
+        let ind1 = if field.ind2() == "0" { "0" } else { "1" };
+        new_field(map.marc21, &format!("{ind1} "))?
+    } else {
+        // UNIMARC ind1 "1" means a meeting.
+        let tag = if field.ind1() == "1" {
+            map.marc21.replace("10", "11")
+        } else {
+            map.marc21.to_string()
+        };
+        new_field(&tag, "2 ")?
+    };
+
+    if map.marc21 == "600" || map.marc21 == "610" {
+        target.set_ind2("4")?;
+    }
+
+    let mut name = String::new();
+
+    for sf in field.subfields() {
+        match sf.code() {
+            "a" => name = sf.content().to_string(),
+            "b" if map.personal => {
+                name = format!("{name}, {}", sf.content());
+            }
+            "b" => target
+                .subfields_mut()
+                .push(Subfield::new("b", sf.content())?),
+            "f" if map.personal => target
+                .subfields_mut()
+                .push(Subfield::new("d", sf.content())?),
+            "4" => target
+                .subfields_mut()
+                .push(Subfield::new("4", sf.content())?),
+            _ => {}
+        }
+    }
+
+    target.subfields_mut().insert(0, Subfield::new("a", name)?);
+
+    Ok(target)
+}
+
+fn marc21_name_to_unimarc(field: &Field, map: &NameMap, meeting: bool) -> Result<Field, String> {
+    let mut target = if map.personal {
+        let ind2 = if field.ind1() == "0" { "0" } else { "1" };
+        new_field(map.unimarc, &format!(" {ind2}"))?
+    } else {
+        new_field(map.unimarc, if meeting { "12" } else { "02" })?
+    };
+
+    for sf in field.subfields() {
+        match sf.code() {
+            "a" if map.personal => {
+                let name = sf.content().trim_end_matches([',', '.', ' ']);
+
+                match name.split_once(", ") {
+                    Some((surname, forename)) => {
+                        target.subfields_mut().push(Subfield::new("a", surname)?);
+                        target.subfields_mut().push(Subfield::new("b", forename)?);
+                    }
+                    None => target.subfields_mut().push(Subfield::new("a", name)?),
+                }
+            }
+            "a" | "b" | "4" => target
+                .subfields_mut()
+                .push(Subfield::new(sf.code(), sf.content())?),
+            "d" if map.personal => target
+                .subfields_mut()
+                .push(Subfield::new("f", sf.content())?),
+            _ => {}
+        }
+    }
+
+    Ok(target)
+}
+
+impl NameMap {
+    /// Name mapping for a MARC21 tag.  MARC21 meeting names share the
+    /// UNIMARC corporate name tags.
+    ///
+    /// Added entries map to the first matching UNIMARC tag.
+    fn for_marc21_tag(tag: &str) -> Option<(&'static NameMap, bool)> {
+        let (tag, meeting) = match tag {
+            "111" => ("110", true),
+            "711" => ("710", true),
+            "611" => ("610", true),
+            t => (t, false),
+        };
+
+        NAME_MAP
+            .iter()
+            .find(|m| m.marc21 == tag)
+            .map(|m| (m, meeting))
+    }
+}
@@ -0,0 +1,711 @@
+//! MARC21 bibliographic record validation.
+//!
+//! Checks a [`Record`] against the structural rules of the MARC21
+//! bibliographic format: leader values, known tags, indicator values,
+//! field and subfield repeatability, and required fields.
+//!
+//! Local fields (9XX and X9X) and 880 alternate graphic representations
+//! are not checked beyond their basic structure.
+//!
+//! # References
+//!
+//! * <https://www.loc.gov/marc/bibliographic/>
+use super::Field;
+use super::Record;
+use std::collections::HashMap;
+use std::fmt;
+
+/// How serious a validation issue is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Questionable, but the record is usable.
+    Warning,
+    /// The record does not conform to MARC21.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "WARNING"),
+            Severity::Error => write!(f, "ERROR"),
+        }
+    }
+}
+
+/// A single problem found in a record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    severity: Severity,
+    /// Tag of the offending field or "LDR" for the leader.
+    tag: Option<String>,
+    /// Subfield code of the offending subfield.
+    code: Option<String>,
+    message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.severity)?;
+
+        if let Some(tag) = self.tag.as_deref() {
+            write!(f, " {tag}")?;
+            if let Some(code) = self.code.as_deref() {
+                write!(f, "${code}")?;
+            }
+        }
+
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl Issue {
+    fn new(severity: Severity, tag: Option<&str>, code: Option<&str>, message: String) -> Issue {
+        Issue {
+            severity,
+            tag: tag.map(|t| t.to_string()),
+            code: code.map(|c| c.to_string()),
+            message,
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+/// Structural rules for a single data field tag.
+struct FieldRule {
+    tag: &'static str,
+    repeatable: bool,
+    /// Valid first indicator values.  ' ' is blank.
+    ind1: &'static str,
+    /// Valid second indicator values.  ' ' is blank.
+    ind2: &'static str,
+    /// Subfield codes which may appear at most once.
+    nr_subfields: &'static str,
+}
+
+const fn rule(
+    tag: &'static str,
+    repeatable: bool,
+    ind1: &'static str,
+    ind2: &'static str,
+    nr_subfields: &'static str,
+) -> FieldRule {
+    FieldRule {
+        tag,
+        repeatable,
+        ind1,
+        ind2,
+        nr_subfields,
+    }
+}
+
+const DIGITS: &str = "0123456789";
+
+/// Control field tags and whether they may repeat.
+const CONTROL_RULES: &[(&str, bool)] = &[
+    ("001", false),
+    ("003", false),
+    ("005", false),
+    ("006", true),
+    ("007", true),
+    ("008", false),
+];
+
+/// Rules for commonly used bibliographic data fields.
+const FIELD_RULES: &[FieldRule] = &[
+    rule("010", false, " ", " ", "a"),
+    rule("013", true, " ", " ", "abcdef"),
+    rule("015", true, " ", " ", "2"),
+    rule("016", true, " 7", " ", "a2"),
+    rule("017", true, " ", " 8", "bd2"),
+    rule("018", false, " ", " ", "a"),
+    rule("020", true, " ", " ", "ac"),
+    rule("022", true, " 01", " ", "alm2"),
+    rule("024", true, "0123478", " 01", "acd2"),
+    rule("025", true, " ", " ", ""),
+    rule("026", true, " ", " ", "e25"),
+    rule("027", true, " ", " ", "a"),
+    rule("028", true, "0123456", "0123", "ab"),
+    rule("030", true, " ", " ", "a"),
+    rule("032", true, " ", " ", "ab"),
+    rule("033", true, " 012", " 012", "3"),
+    rule("034", true, "013", " 01", "abhdefgjkmnprstxyz2"),
+    rule("035", true, " ", " ", "a"),
+    rule("036", false, " ", " ", "ab"),
+    rule("037", true, " 23", " ", "ab"),
+    rule("038", false, " ", " ", "a"),
+    rule("040", false, " ", " ", "abc"),
+    rule("041", true, " 01", " 7", "2"),
+    rule("042", false, " ", " ", ""),
+    rule("043", false, " ", " ", ""),
+    rule("044", false, " ", " ", ""),
+    rule("045", false, " 012", " ", ""),
+    rule("046", true, " 123", " ", "abcdeop2"),
+    rule("047", true, " ", " 7", "2"),
+    rule("048", true, " ", " 7", "2"),
+    rule("050", true, " 01", "04", "3"),
+    rule("051", true, " ", " ", "abc"),
+    rule("052", true, " 17", " ", "a2"),
+    rule("055", true, " 01", DIGITS, "ab2"),
+    rule("060", true, " 01", "04", ""),
+    rule("061", true, " ", " ", "c"),
+    rule("066", false, " ", " ", "ab"),
+    rule("070", true, "01", " ", ""),
+    rule("071", true, " ", " ", ""),
+    rule("072", true, " ", "07", "a2"),
+    rule("074", true, " ", " ", "a"),
+    rule("080", true, " 01", " ", "ax2"),
+    rule("082", true, "017", " 04", "b2q"),
+    rule("083", true, "017", " ", "cq2"),
+    rule("084", true, " ", " ", "bq2"),
+    rule("086", true, " 0", " ", "a2"),
+    rule("088", true, " ", " ", "a"),
+    rule("100", false, "013", " ", "abdgq"),
+    rule("110", false, "012", " ", "ab"),
+    rule("111", false, "012", " ", "acdgjq"),
+    rule("130", false, DIGITS, " ", "adfhklmorst"),
+    rule("210", true, "01", " 0", "ab2"),
+    rule("222", true, " ", DIGITS, "ab"),
+    rule("240", false, "01", DIGITS, "adfhklorst"),
+    rule("242", true, "01", DIGITS, "abchy"),
+    rule("243", false, "01", DIGITS, "adfhklorst"),
+    rule("245", false, "01", DIGITS, "abcfghs"),
+    rule("246", true, "0123", " 012345678", "abfgh5"),
+    rule("247", true, "01", "01", "abfghx"),
+    rule("250", true, " ", " ", "ab"),
+    rule("251", true, " ", " ", ""),
+    rule("254", false, " ", " ", "a"),
+    rule("255", true, " ", " ", "abcdefg"),
+    rule("256", false, " ", " ", "a"),
+    rule("257", true, " ", " ", "2"),
+    rule("258", true, " ", " ", "ab"),
+    rule("260", true, " 23", " ", ""),
+    rule("263", false, " ", " ", "a"),
+    rule("264", true, " 23", "01234", "3"),
+    rule("270", true, " 12", " 07", "jklmnpqrz"),
+    rule("300", true, " ", " ", "bei"),
+    rule("306", false, " ", " ", ""),
+    rule("307", true, " 8", " ", "ab"),
+    rule("310", false, " ", " ", "ab"),
+    rule("321", true, " ", " ", "a"),
+    rule("334", true, " ", " ", "a2"),
+    rule("335", true, " ", " ", "a2"),
+    rule("336", true, " ", " ", "2"),
+    rule("337", true, " ", " ", "2"),
+    rule("338", true, " ", " ", "2"),
+    rule("340", true, " ", " ", "2"),
+    rule("341", true, " 01", " ", "a2"),
+    rule("342", true, "01", "012345678", "abcdefghijklmnopqrstuvw2"),
+    rule("343", true, " ", " ", "abcdefghi"),
+    rule("344", true, " ", " ", "2"),
+    rule("345", true, " ", " ", "2"),
+    rule("346", true, " ", " ", "2"),
+    rule("347", true, " ", " ", "2"),
+    rule("348", true, " ", " ", "2"),
+    rule("351", true, " ", " ", ""),
+    rule("352", true, " ", " ", "abcdefgiq"),
+    rule("355", true, "012345678", " ", "abcdeghj"),
+    rule("357", false, " ", " ", "a"),
+    rule("362", true, "01", " ", "az"),
+    rule("363", true, " 01", " 01", "abcdefghijklmuv"),
+    rule("365", true, " ", " ", "abcdefghijkm2"),
+    rule("366", true, " ", " ", "abcdefgjkm2"),
+    rule("370", true, " ", " ", "2"),
+    rule("377", true, " ", " 7", "2"),
+    rule("380", true, " ", " ", "2"),
+    rule("381", true, " ", " ", "2"),
+    rule("382", true, " 0123", " 01", "s2"),
+    rule("383", true, " ", " ", "d2"),
+    rule("384", true, " 01", " ", "a"),
+    rule("385", true, " ", " ", "m2"),
+    rule("386", true, " ", " ", "m2"),
+    rule("388", true, " 12", " ", "2"),
+    rule("490", true, "01", " ", "l"),
+    rule("500", true, " ", " ", "a"),
+    rule("501", true, " ", " ", "a"),
+    rule("502", true, " ", " ", "abcd"),
+    rule("504", true, " ", " ", "ab"),
+    rule("505", true, "0128", " 0", "a"),
+    rule("506", true, " 01", " ", ""),
+    rule("507", false, " ", " ", "ab"),
+    rule("508", true, " ", " ", "a"),
+    rule("510", true, "01234", " ", "abcx"),
+    rule("511", true, "01", " ", "a"),
+    rule("513", true, " ", " ", "ab"),
+    rule("514", false, " ", " ", "cdefgkmz"),
+    rule("515", true, " ", " ", "a"),
+    rule("516", true, " 8", " ", "a"),
+    rule("518", true, " ", " ", "a"),
+    rule("520", true, " 012348", " ", "abc2"),
+    rule("521", true, " 012348", " ", "b"),
+    rule("522", true, " 8", " ", "a"),
+    rule("524", true, " 8", " ", "a2"),
+    rule("525", true, " ", " ", "a"),
+    rule("526", true, "08", " ", "abcdi"),
+    rule("530", true, " ", " ", "abcd"),
+    rule("532", true, "0128", " ", "a"),
+    rule("533", true, " ", " ", "abcdeny"),
+    rule("534", true, " ", " ", "abceflmpt"),
+    rule("535", true, "12", " ", "cd"),
+    rule("536", true, " ", " ", "a"),
+    rule("538", true, " ", " ", "ai"),
+    rule("540", true, " ", " ", "abcdfgqu2"),
+    rule("541", true, " 01", " ", "abcdefhno"),
+    rule("542", true, " 01", " ", "abcdefghijklmnopqrs"),
+    rule("544", true, " 01", " ", ""),
+    rule("545", true, " 01", " ", "a"),
+    rule("546", true, " ", " ", "a"),
+    rule("547", true, " ", " ", "a"),
+    rule("550", true, " ", " ", "a"),
+    rule("552", true, " ", " ", "abcdefghijklmnop"),
+    rule("555", true, " 08", " ", "abcd"),
+    rule("556", true, " 8", " ", "a"),
+    rule("561", true, " 01", " ", "a"),
+    rule("562", true, " ", " ", ""),
+    rule("563", true, " ", " ", "a"),
+    rule("565", true, " 08", " ", "a"),
+    rule("567", true, " 8", " ", "a"),
+    rule("580", true, " ", " ", "a"),
+    rule("581", true, " 8", " ", "a"),
+    rule("583", true, " 01", " ", "2"),
+    rule("584", true, " ", " ", ""),
+    rule("585", true, " ", " ", "a"),
+    rule("586", true, " 8", " ", "a"),
+    rule("588", true, " 01", " ", "a"),
+    rule("600", true, "013", DIGITS, "abdfghklqst"),
+    rule("610", true, "012", DIGITS, "dfghklst"),
+    rule("611", true, "012", DIGITS, "acfghklpqst"),
+    rule("630", true, DIGITS, DIGITS, "adfhklmorst"),
+    rule("647", true, " ", "01234567", "ag2"),
+    rule("648", true, " ", "01234567", "a2"),
+    rule("650", true, " 012", DIGITS, "abcdeg2"),
+    rule("651", true, " ", DIGITS, "ag2"),
+    rule("653", true, " 012", " 0123456", ""),
+    rule("654", true, " 012", " ", "2"),
+    rule("655", true, " 0", DIGITS, "a2"),
+    rule("656", true, " ", "7", "ak2"),
+    rule("657", true, " ", "7", "a2"),
+    rule("658", true, " ", " ", "a2"),
+    rule("662", true, " ", " ", "2"),
+    rule("688", true, " ", " ", "a2"),
+    rule("700", true, "013", " 2", "abdgiq"),
+    rule("710", true, "012", " 2", "ab"),
+    rule("711", true, "012", " 2", "acdgijq"),
+    rule("720", true, " 12", " ", "a"),
+    rule("730", true, DIGITS, " 2", "adfhiklmorst"),
+    rule("740", true, DIGITS, " 2", "ah"),
+    rule("751", true, " ", " ", "a2"),
+    rule("752", true, " ", " ", "abcdfgh2"),
+    rule("753", true, " ", " ", "abc2"),
+    rule("754", true, " ", " ", "2"),
+    rule("758", true, " ", " ", "a"),
+    rule("760", true, "01", " 8", "abcdghimnstxy"),
+    rule("762", true, "01", " 8", "abcdghimnstxy"),
+    rule("765", true, "01", " 8", "abcdehkmstuxyz"),
+    rule("767", true, "01", " 8", "abcdehkmstuxyz"),
+    rule("770", true, "01", " 8", "abcdehkmstuxyz"),
+    rule("772", true, "01", " 08", "abcdehkmstuxyz"),
+    rule("773", true, "01", " 8", "abdhkmpqstuxyz"),
+    rule("774", true, "01", " 8", "abcdehkmstuxyz"),
+    rule("775", true, "01", " 8", "abcdefhkmstuxyz"),
+    rule("776", true, "01", " 8", "abcdehkmstuxyz"),
+    rule("777", true, "01", " 8", "abcdhkmstxy"),
+    rule("780", true, "01", "01234567", "abcdhkmstuxyz"),
+    rule("785", true, "01", "012345678", "abcdhkmstuxyz"),
+    rule("786", true, "01", " 8", "abcdhjkmpstuvxy"),
+    rule("787", true, "01", " 8", "abcdhkmstuxyz"),
+    rule("800", true, "013", " ", "abdgqv"),
+    rule("810", true, "012", " ", "abv"),
+    rule("811", true, "012", " ", "acdgjqv"),
+    rule("830", true, " ", DIGITS, "adfhklmorstv"),
+    rule("841", false, " ", " ", "abe"),
+    rule("842", false, " ", " ", "a"),
+    rule("843", true, " ", " ", "abcdeflmn"),
+    rule("844", false, " ", " ", "a"),
+    rule("845", true, " ", " ", ""),
+    rule("850", true, " ", " ", ""),
+    rule("852", true, " 012345678", " 012", "bcdefgjnpqt2"),
+    rule("853", true, "0123", "0123", ""),
+    rule("854", true, "0123", "0123", ""),
+    rule("855", true, "0123", "0123", ""),
+    rule("856", true, " 012347", " 01278", "ry2"),
+    rule("863", true, " 345", " 0123456", "jtvw"),
+    rule("864", true, " 345", " 0123456", "jtvw"),
+    rule("865", true, " 45", " 13", "jtvw"),
+    rule("866", true, " 345", "0127", "a"),
+    rule("867", true, " 345", "0127", "a"),
+    rule("868", true, " 345", "0127", "a"),
+    rule("876", true, " ", " ", ""),
+    rule("877", true, " ", " ", ""),
+    rule("878", true, " ", " ", ""),
+    rule("880", true, "", "", ""),
+    rule("881", true, " ", " ", ""),
+    rule("882", false, " ", " ", ""),
+    rule("883", true, " 012", " ", "acdqx"),
+    rule("884", true, " ", " ", "agkq"),
+    rule("885", true, " ", " ", "a2"),
+    rule("886", true, "012", " ", "ab2"),
+    rule("887", true, " ", " ", "a2"),
+];
+
+/// Valid values for selected leader positions.  ' ' is blank.
+const LEADER_RULES: &[(usize, &str, &str)] = &[
+    (5, "acdnp", "record status"),
+    (6, "acdefgijkmoprt", "type of record"),
+    (7, "abcdims", "bibliographic level"),
+    (8, " a", "type of control"),
+    (9, " a", "character coding scheme"),
+    (10, "2", "indicator count"),
+    (11, "2", "subfield code count"),
+    (17, " 1234578uzIJKLM", "encoding level"),
+    (18, " acinu", "descriptive cataloging form"),
+    (19, " abc", "multipart resource record level"),
+    (20, "4", "length of the length-of-field portion"),
+    (21, "5", "length of the starting-character-position portion"),
+    (22, "0", "length of the implementation-defined portion"),
+    (23, "0", "undefined entry map position"),
+];
+
+/// Local and other fields whose content we don't check.
+fn is_unchecked_tag(tag: &str) -> bool {
+    tag.starts_with('9') || tag.as_bytes().get(1) == Some(&b'9')
+}
+
+/// Checks records against the MARC21 bibliographic format.
+#[derive(Debug, Clone, Default)]
+pub struct Validator {
+    /// Report unknown tags as warnings.
+    pub warn_unknown_tags: bool,
+}
+
+impl Validator {
+    pub fn new() -> Validator {
+        Validator {
+            warn_unknown_tags: true,
+        }
+    }
+
+    /// Returns all issues found in the record, errors first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use marctk::validate::{Severity, Validator};
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =008 070101s2007\\\\nyu\\\\\\\\\\\000\0\eng\d
+    /// =100 1\$aSmith, Jane.
+    /// =100 1\$aSmith, John.
+    /// =650 \0$aCats$aDogs."#
+    /// ).unwrap();
+    ///
+    /// let issues = Validator::new().validate(&record);
+    ///
+    /// let messages: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+    ///
+    /// assert_eq!(
+    ///     messages,
+    ///     vec![
+    ///         "ERROR: Record has no 245 field",
+    ///         "ERROR 100: Non-repeatable field occurs 2 times",
+    ///         "ERROR 650$a: Non-repeatable subfield occurs 2 times",
+    ///     ]
+    /// );
+    ///
+    /// assert!(issues.iter().all(|i| i.severity() == Severity::Error));
+    /// ```
+    pub fn validate(&self, record: &Record) -> Vec<Issue> {
+        let mut issues = Vec::new();
+
+        self.check_leader(record, &mut issues);
+        self.check_required(record, &mut issues);
+        self.check_control_fields(record, &mut issues);
+        self.check_data_fields(record, &mut issues);
+
+        // Stable sort keeps issues of the same severity in record order.
+        issues.sort_by_key(|i| std::cmp::Reverse(i.severity));
+
+        issues
+    }
+
+    fn check_leader(&self, record: &Record, issues: &mut Vec<Issue>) {
+        let leader = record.leader().as_bytes();
+
+        // Record length and base address of data are recalculated
+        // when records are written, but must be numeric when present.
+        for (range, label) in [(0..5, "record length"), (12..17, "base address of data")] {
+            let value = &leader[range];
+            if !value.iter().all(|b| b.is_ascii_digit()) && !value.iter().all(|b| *b == b' ') {
+                issues.push(Issue::new(
+                    Severity::Error,
+                    Some("LDR"),
+                    None,
+                    format!("Invalid {label}: '{}'", String::from_utf8_lossy(value)),
+                ));
+            }
+        }
+
+        for (pos, values, label) in LEADER_RULES {
+            let c = leader[*pos] as char;
+            if !values.contains(c) {
+                issues.push(Issue::new(
+                    Severity::Error,
+                    Some("LDR"),
+                    None,
+                    format!("Invalid {label} at position {pos:02}: '{c}'"),
+                ));
+            }
+        }
+    }
+
+    fn check_required(&self, record: &Record, issues: &mut Vec<Issue>) {
+        if record.get_fields("245").is_empty() {
+            issues.push(Issue::new(
+                Severity::Error,
+                None,
+                None,
+                "Record has no 245 field".to_string(),
+            ));
+        }
+
+        if record.get_control_fields("008").is_empty() {
+            issues.push(Issue::new(
+                Severity::Warning,
+                None,
+                None,
+                "Record has no 008 field".to_string(),
+            ));
+        }
+    }
+
+    fn check_control_fields(&self, record: &Record, issues: &mut Vec<Issue>) {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+
+        for cf in record.control_fields() {
+            *counts.entry(cf.tag()).or_default() += 1;
+
+            match cf.tag() {
+                "008" if cf.content().chars().count() != 40 => issues.push(Issue::new(
+                    Severity::Error,
+                    Some("008"),
+                    None,
+                    format!("Invalid length: {}", cf.content().chars().count()),
+                )),
+                "006" if cf.content().chars().count() != 18 => issues.push(Issue::new(
+                    Severity::Error,
+                    Some("006"),
+                    None,
+                    format!("Invalid length: {}", cf.content().chars().count()),
+                )),
+                "005" if !is_valid_005(cf.content()) => issues.push(Issue::new(
+                    Severity::Warning,
+                    Some("005"),
+                    None,
+                    format!("Invalid date/time: '{}'", cf.content()),
+                )),
+                _ => {}
+            }
+        }
+
+        let mut tags: Vec<&&str> = counts.keys().collect();
+        tags.sort();
+
+        for tag in tags {
+            match CONTROL_RULES.iter().find(|(t, _)| t == tag) {
+                Some((_, repeatable)) => {
+                    let count = counts[*tag];
+                    if !repeatable && count > 1 {
+                        issues.push(Issue::new(
+                            Severity::Error,
+                            Some(tag),
+                            None,
+                            format!("Non-repeatable field occurs {count} times"),
+                        ));
+                    }
+                }
+                None => {
+                    if self.warn_unknown_tags {
+                        issues.push(Issue::new(
+                            Severity::Warning,
+                            Some(tag),
+                            None,
+                            "Unknown control field tag".to_string(),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_data_fields(&self, record: &Record, issues: &mut Vec<Issue>) {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+
+        for field in record.fields() {
+            let tag = field.tag();
+
+            if field.subfields().is_empty() {
+                issues.push(Issue::new(
+                    Severity::Error,
+                    Some(tag),
+                    None,
+                    "Field has no subfields".to_string(),
+                ));
+            }
+
+            self.check_subfield_codes(field, issues);
+
+            if !tag.bytes().all(|b| b.is_ascii_digit()) || tag < "010" {
+                issues.push(Issue::new(
+                    Severity::Error,
+                    Some(tag),
+                    None,
+                    "Invalid data field tag".to_string(),
+                ));
+                continue;
+            }
+
+            if is_unchecked_tag(tag) {
+                continue;
+            }
+
+            let count = counts.entry(tag).or_default();
+            *count += 1;
+
+            let Some(rule) = FIELD_RULES.iter().find(|r| r.tag == tag) else {
+                if self.warn_unknown_tags {
+                    issues.push(Issue::new(
+                        Severity::Warning,
+                        Some(tag),
+                        None,
+                        "Unknown data field tag".to_string(),
+                    ));
+                }
+                continue;
+            };
+
+            if *count == 2 && !rule.repeatable {
+                let total = record.get_fields(tag).len();
+                issues.push(Issue::new(
+                    Severity::Error,
+                    Some(tag),
+                    None,
+                    format!("Non-repeatable field occurs {total} times"),
+                ));
+            }
+
+            // 880's mirror the indicators of the field they link to.
+            if tag != "880" {
+                for (which, value, valid) in
+                    [(1, field.ind1(), rule.ind1), (2, field.ind2(), rule.ind2)]
+                {
+                    if !value.chars().all(|c| valid.contains(c)) {
+                        issues.push(Issue::new(
+                            Severity::Error,
+                            Some(tag),
+                            None,
+                            format!("Invalid indicator {which} value: '{value}'"),
+                        ));
+                    }
+                }
+            }
+
+            for code in rule.nr_subfields.chars() {
+                let code = code.to_string();
+                let count = field.get_subfields(&code).len();
+                if count > 1 {
+                    issues.push(Issue::new(
+                        Severity::Error,
+                        Some(tag),
+                        Some(&code),
+                        format!("Non-repeatable subfield occurs {count} times"),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn check_subfield_codes(&self, field: &Field, issues: &mut Vec<Issue>) {
+        for sf in field.subfields() {
+            let code = sf.code();
+
+            if !code
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+            {
+                issues.push(Issue::new(
+                    Severity::Warning,
+                    Some(field.tag()),
+                    Some(code),
+                    "Subfield code is not a lowercase letter or digit".to_string(),
+                ));
+            }
+
+            if sf.content().trim().is_empty() {
+                issues.push(Issue::new(
+                    Severity::Warning,
+                    Some(field.tag()),
+                    Some(code),
+                    "Subfield is empty".to_string(),
+                ));
+            }
+        }
+    }
+}
+
+/// True if the value has the form yyyymmddhhmmss.f
+fn is_valid_005(value: &str) -> bool {
+    let bytes = value.as_bytes();
+
+    bytes.len() == 16
+        && bytes[14] == b'.'
+        && bytes[..14].iter().all(|b| b.is_ascii_digit())
+        && bytes[15].is_ascii_digit()
+}
+
+impl Record {
+    /// Validate the record against the MARC21 bibliographic format
+    /// using the default [`Validator`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let record = Record::from_breaker(
+    ///     r#"=LDR 00000nam a2200000 a 4500
+    /// =008 070101s2007\\\\nyu\\\\\\\\\\\000\0\eng\d
+    /// =245 10$aMy title."#
+    /// ).unwrap();
+    ///
+    /// assert!(record.validate().is_empty());
+    ///
+    /// let record = Record::from_breaker("=LDR 00000xam a2200000 a 4500").unwrap();
+    /// let issues = record.validate();
+    ///
+    /// assert!(issues.iter().any(|i| i.is_error() && i.tag() == Some("LDR")));
+    /// ```
+    pub fn validate(&self) -> Vec<Issue> {
+        Validator::new().validate(self)
+    }
+}
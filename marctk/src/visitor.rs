@@ -0,0 +1,124 @@
+//! Event-based record processing.
+//!
+//! Visitors receive the parts of each record as they are read, without
+//! a [`crate::Record`] being built.  This suits indexing and reporting
+//! code which only inspects a few fields of each record.
+//!
+//! Events arrive in record order:
+//!
+//! ```text
+//! start_record
+//!   leader
+//!   control_field ...
+//!   start_field
+//!     subfield ...
+//!   end_field
+//!   ...
+//! end_record
+//! ```
+//!
+//! A record which cannot be read ends with an error, possibly after
+//! some of its events have been delivered.
+use super::binary;
+use super::xml;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+/// Receives record content from [`visit_binary_file`], [`visit_xml_file`],
+/// etc.  Every method does nothing by default.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::Record;
+/// use marctk::visitor::RecordVisitor;
+///
+/// /// Collects 650 $a values.
+/// #[derive(Default)]
+/// struct Subjects {
+///     in_650: bool,
+///     values: Vec<String>,
+/// }
+///
+/// impl RecordVisitor for Subjects {
+///     fn start_field(&mut self, tag: &str, _ind1: &str, _ind2: &str) {
+///         self.in_650 = tag == "650";
+///     }
+///
+///     fn subfield(&mut self, code: &str, content: &str) {
+///         if self.in_650 && code == "a" {
+///             self.values.push(content.to_string());
+///         }
+///     }
+/// }
+///
+/// let record = Record::from_breaker(
+///     "=245 10$aTitle\n=650 \\0$aCats.\n=650 \\0$aDogs."
+/// ).unwrap();
+///
+/// let mut subjects = Subjects::default();
+///
+/// marctk::binary::visit_binary(&record.to_binary().unwrap(), &mut subjects).unwrap();
+/// marctk::xml::visit_xml(record.to_xml_string().as_bytes(), &mut subjects).unwrap();
+///
+/// assert_eq!(subjects.values, vec!["Cats.", "Dogs.", "Cats.", "Dogs."]);
+/// ```
+pub trait RecordVisitor {
+    fn start_record(&mut self) {}
+
+    fn leader(&mut self, _leader: &str) {}
+
+    fn control_field(&mut self, _tag: &str, _content: &str) {}
+
+    fn start_field(&mut self, _tag: &str, _ind1: &str, _ind2: &str) {}
+
+    fn subfield(&mut self, _code: &str, _content: &str) {}
+
+    fn end_field(&mut self) {}
+
+    fn end_record(&mut self) {}
+}
+
+/// Visit every record in a binary MARC file.
+///
+/// Returns the number of records visited.  Stops at the first record
+/// which cannot be read.
+pub fn visit_binary_file(
+    filename: &str,
+    visitor: &mut impl RecordVisitor,
+) -> Result<usize, String> {
+    let file =
+        File::open(filename).map_err(|e| format!("Cannot read MARC file: {filename} {e}"))?;
+
+    let mut reader = BufReader::new(file);
+    let mut bytes = Vec::new();
+    let mut count = 0;
+
+    loop {
+        bytes.clear();
+
+        reader
+            .read_until(binary::END_OF_RECORD, &mut bytes)
+            .map_err(|e| format!("Error reading file: {filename} {e}"))?;
+
+        // Trailing line breaks, etc. after the final record.
+        if bytes.iter().all(|b| b.is_ascii_whitespace()) {
+            return Ok(count);
+        }
+
+        count += 1;
+
+        binary::visit_binary(&bytes, visitor)
+            .map_err(|e| format!("Error processing record {count}: {e}"))?;
+    }
+}
+
+/// Visit every record in a MARC XML file.
+///
+/// Returns the number of records visited.
+pub fn visit_xml_file(filename: &str, visitor: &mut impl RecordVisitor) -> Result<usize, String> {
+    let file =
+        File::open(filename).map_err(|e| format!("Cannot read MARCXML file: {filename} {e}"))?;
+
+    xml::visit_xml(BufReader::new(file), visitor)
+}
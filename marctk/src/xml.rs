@@ -2,9 +2,13 @@
 use std::fs::File;
 use std::io::BufReader;
 use std::io::Cursor;
+use std::io::Read;
+use std::mem;
 use xml::attribute::OwnedAttribute;
 use xml::reader::{EventReader, XmlEvent};
 
+use super::order::FieldOrder;
+use super::visitor::RecordVisitor;
 use super::Controlfield;
 use super::Field;
 use super::Record;
@@ -49,22 +53,141 @@ pub fn escape_xml(value: &str, is_attr: bool) -> String {
     buf
 }
 
-/// Append leading spaces for formatted XML.
-fn format(formatted: bool, value: &mut String, depth: u8) {
-    if formatted {
-        value.push('\n');
-        for _ in 0..depth {
-            value.push(' ');
-        }
-    }
-}
-
 /// Options for controling the format of XML output
+///
+/// # Examples
+///
+/// ```
+/// use marctk::Record;
+/// use marctk::xml::XmlOptions;
+///
+/// let record = Record::from_breaker("=LDR 00000nam a2200000 a 4500\n=001 123").unwrap();
+///
+/// let options = XmlOptions {
+///     namespace_prefix: Some("marc".to_string()),
+///     schema_location: false,
+///     collection: true,
+///     ..Default::default()
+/// };
+///
+/// assert_eq!(
+///     record.to_xml_string_ops(&options),
+///     concat!(
+///         r#"<marc:collection xmlns:marc="http://www.loc.gov/MARC21/slim">"#,
+///         r#"<marc:record><marc:leader>00000nam a2200000 a 4500</marc:leader>"#,
+///         r#"<marc:controlfield tag="001">123</marc:controlfield>"#,
+///         r#"</marc:record></marc:collection>"#,
+///     )
+/// );
+/// ```
+#[derive(Debug, Clone)]
 pub struct XmlOptions {
-    /// Format generated with 2-space indent.
+    /// Format generated XML with one element per line.
     pub formatted: bool,
+    /// Number of spaces per level of indentation in formatted XML.
+    pub indent: u8,
     /// Include an XML declaration in the generated XML.
     pub with_xml_declaration: bool,
+    /// Write fields in this order instead of their order in the record.
+    pub field_order: Option<FieldOrder>,
+    /// Wrap the record in a `<collection/>` element.
+    pub collection: bool,
+    /// Prefix elements with this namespace prefix, e.g. "marc",
+    /// instead of declaring MARCXML as the default namespace.
+    pub namespace_prefix: Option<String>,
+    /// Include an `xsi:schemaLocation` attribute pointing to the
+    /// MARCXML schema.
+    pub schema_location: bool,
+}
+
+impl Default for XmlOptions {
+    fn default() -> Self {
+        XmlOptions {
+            formatted: false,
+            indent: 2,
+            with_xml_declaration: false,
+            field_order: None,
+            collection: false,
+            namespace_prefix: None,
+            schema_location: true,
+        }
+    }
+}
+
+impl XmlOptions {
+    /// Element name with the namespace prefix applied.
+    fn qname(&self, name: &str) -> String {
+        match self.namespace_prefix.as_deref() {
+            Some(p) if !p.is_empty() => format!("{p}:{name}"),
+            _ => name.to_string(),
+        }
+    }
+
+    /// Append a newline and indentation for formatted XML.
+    fn indent(&self, xml: &mut String, level: u8) {
+        if self.formatted {
+            xml.push('\n');
+            for _ in 0..(level as usize * self.indent as usize) {
+                xml.push(' ');
+            }
+        }
+    }
+
+    /// Opening tag for the document root, including namespace
+    /// declarations.
+    fn root_start(&self, name: &str) -> String {
+        let mut attrs = vec![match self.namespace_prefix.as_deref() {
+            Some(p) if !p.is_empty() => format!(r#"xmlns:{p}="{MARCXML_NAMESPACE}""#),
+            _ => format!(r#"xmlns="{MARCXML_NAMESPACE}""#),
+        }];
+
+        if self.schema_location {
+            attrs.push(format!(r#"xmlns:xsi="{MARCXML_XSI_NAMESPACE}""#));
+            attrs.push(format!(r#"xsi:schemaLocation="{MARCXML_SCHEMA_LOCATION}""#));
+        }
+
+        let mut tag = format!("<{}", self.qname(name));
+
+        for attr in attrs {
+            self.indent(&mut tag, 1);
+            if !self.formatted {
+                tag.push(' ');
+            }
+            tag += &attr;
+        }
+
+        tag.push('>');
+        tag
+    }
+
+    /// Opening `<collection>` tag for documents containing multiple
+    /// records, including namespace declarations.
+    ///
+    /// Records written within the collection should be generated with
+    /// the same options, minus the XML declaration and collection.
+    ///
+    /// ```
+    /// use marctk::xml::XmlOptions;
+    ///
+    /// let options = XmlOptions {
+    ///     schema_location: false,
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(
+    ///     options.collection_start(),
+    ///     r#"<collection xmlns="http://www.loc.gov/MARC21/slim">"#
+    /// );
+    /// assert_eq!(options.collection_end(), "</collection>");
+    /// ```
+    pub fn collection_start(&self) -> String {
+        self.root_start("collection")
+    }
+
+    /// Closing `</collection>` tag.
+    pub fn collection_end(&self) -> String {
+        format!("</{}>", self.qname("collection"))
+    }
 }
 
 struct XmlParseContext {
@@ -76,15 +199,201 @@ struct XmlParseContext {
     doc_complete: bool,
 }
 
-pub enum XmlRecordIterator {
-    FileReader(EventReader<BufReader<File>>),
-    ByteReader(EventReader<Cursor<Vec<u8>>>),
+/// Size of each read from the source while splitting a document into
+/// records for recovery.
+const CHUNK_READ_SIZE: usize = 65536;
+
+/// Splits a MARC XML document into the raw text of its <record>
+/// elements without parsing the XML, so that invalid XML in one record
+/// does not prevent reading the next.
+struct RecordChunks {
+    reader: Box<dyn Read>,
+    buf: Vec<u8>,
+    /// Scan position within buf.
+    pos: usize,
+    eof: bool,
+    /// Start tag of the root element, e.g. <collection xmlns="...">,
+    /// which carries the namespace declarations each record needs.
+    root: Option<(String, String)>,
+}
+
+impl RecordChunks {
+    fn new(reader: Box<dyn Read>) -> Self {
+        RecordChunks {
+            reader,
+            buf: Vec::new(),
+            pos: 0,
+            eof: false,
+            root: None,
+        }
+    }
+
+    /// Read more data into the buffer.  Returns false at end of input.
+    fn fill(&mut self) -> Result<bool, String> {
+        if self.eof {
+            return Ok(false);
+        }
+
+        let mut bytes = [0u8; CHUNK_READ_SIZE];
+        let count = self
+            .reader
+            .read(&mut bytes)
+            .map_err(|e| format!("Error reading XML: {e}"))?;
+
+        if count == 0 {
+            self.eof = true;
+            return Ok(false);
+        }
+
+        self.buf.extend_from_slice(&bytes[..count]);
+
+        Ok(true)
+    }
+
+    fn find(&mut self, from: usize, needle: &[u8]) -> Result<Option<usize>, String> {
+        loop {
+            if let Some(p) = self.buf[from..]
+                .windows(needle.len())
+                .position(|w| w == needle)
+            {
+                return Ok(Some(from + p));
+            }
+            if !self.fill()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Byte range of the next complete tag, comment, etc. at or after
+    /// the scan position.
+    fn next_tag(&mut self) -> Result<Option<(usize, usize)>, String> {
+        let Some(start) = self.find(self.pos, b"<")? else {
+            return Ok(None);
+        };
+
+        // Make sure we can see far enough to identify comments and CDATA.
+        while self.buf.len() < start + 9 && self.fill()? {}
+
+        let rest = &self.buf[start..];
+        let terminator: &[u8] = if rest.starts_with(b"<!--") {
+            b"-->"
+        } else if rest.starts_with(b"<![CDATA[") {
+            b"]]>"
+        } else {
+            b">"
+        };
+
+        match self.find(start + 1, terminator)? {
+            Some(end) => Ok(Some((start, end + terminator.len()))),
+            None => Ok(None),
+        }
+    }
+
+    /// Raw text of the next <record> element.
+    ///
+    /// A record with no end tag runs until the next record or the end
+    /// of the document.
+    fn next_record(&mut self) -> Result<Option<String>, String> {
+        let mut record_start: Option<usize> = None;
+
+        loop {
+            let Some((start, end)) = self.next_tag()? else {
+                // Return any partial record so it can be reported.
+                let chunk = record_start.map(|s| self.take(s, self.buf.len()));
+                self.pos = self.buf.len();
+                return Ok(chunk);
+            };
+
+            let tag = String::from_utf8_lossy(&self.buf[start..end]).to_string();
+
+            if tag.starts_with("<?") || tag.starts_with("<!") {
+                self.pos = end;
+                continue;
+            }
+
+            let closing = tag.starts_with("</");
+            let self_closing = tag.ends_with("/>");
+
+            let name = tag
+                .trim_start_matches(['<', '/'])
+                .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                .next()
+                .unwrap_or("")
+                .to_string();
+
+            let is_record = name.rsplit(':').next() == Some("record");
+
+            match record_start {
+                Some(s) if is_record && closing => return Ok(Some(self.take(s, end))),
+                // A new record began before the previous one ended.
+                Some(s) if is_record => return Ok(Some(self.take(s, start))),
+                Some(_) => {}
+                None if is_record && !closing => {
+                    if self_closing {
+                        return Ok(Some(self.take(start, end)));
+                    }
+                    record_start = Some(start);
+                }
+                None if !closing && !self_closing && self.root.is_none() => {
+                    self.root = Some((tag, format!("</{name}>")));
+                }
+                None => {}
+            }
+
+            self.pos = end;
+        }
+    }
+
+    /// Remove the range from the buffer, along with everything before
+    /// it, and return its text.
+    fn take(&mut self, start: usize, end: usize) -> String {
+        let text = String::from_utf8_lossy(&self.buf[start..end]).to_string();
+        self.buf.drain(..end);
+        self.pos = 0;
+        text
+    }
+}
+
+/// Source of XML events, or raw record text when recovering from
+/// errors.
+enum XmlSource {
+    Events(Box<EventReader<Box<dyn Read>>>),
+    Chunks(RecordChunks),
+}
+
+/// Called with the raw XML of a record which could not be read and the
+/// error encountered.
+type RecoveryHandler = Box<dyn FnMut(&str, &str)>;
+
+/// Iterates over the records in a MARC XML document.
+///
+/// Records are parsed one at a time as the document is read, so
+/// memory use does not grow with the size of the document.
+pub struct XmlRecordIterator {
+    source: XmlSource,
+    recovery: Option<RecoveryHandler>,
+    started: bool,
+    skip_malformed: bool,
+    skipped: usize,
+    finished: bool,
+    /// Discarding the remainder of a malformed record.
+    in_bad_record: bool,
 }
 
 impl Iterator for XmlRecordIterator {
     type Item = Result<Record, String>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        self.started = true;
+
+        if let XmlSource::Chunks(_) = self.source {
+            return self.read_next_recovering().transpose();
+        }
+
         let mut context = XmlParseContext {
             record: Record::new(),
             in_cfield: false,
@@ -100,31 +409,215 @@ impl Iterator for XmlRecordIterator {
 
 impl XmlRecordIterator {
     /// Create a new iterator from a MARC XML file
-    fn from_file(filename: &str) -> Result<Self, String> {
+    ///
+    /// The file is read incrementally.
+    pub fn from_file(filename: &str) -> Result<Self, String> {
         match File::open(filename) {
-            Ok(file) => Ok(XmlRecordIterator::FileReader(EventReader::new(
-                BufReader::new(file),
-            ))),
+            Ok(file) => Ok(XmlRecordIterator::from_reader(file)),
             Err(e) => Err(format!("Cannot read MARCXML file: {filename} {e}")),
         }
     }
 
+    /// Create a new iterator from a MARC XML source, e.g. STDIN.
+    pub fn from_reader(reader: impl Read + 'static) -> Self {
+        XmlRecordIterator::new(Box::new(BufReader::new(reader)))
+    }
+
     /// Create a new iterator from a MARC string
     fn from_string(xml: &str) -> Self {
-        XmlRecordIterator::ByteReader(EventReader::new(Cursor::new(xml.as_bytes().to_vec())))
+        XmlRecordIterator::new(Box::new(Cursor::new(xml.as_bytes().to_vec())))
+    }
+
+    fn new(reader: Box<dyn Read>) -> Self {
+        XmlRecordIterator {
+            source: XmlSource::Events(Box::new(EventReader::new(reader))),
+            recovery: None,
+            started: false,
+            skip_malformed: false,
+            skipped: 0,
+            finished: false,
+            in_bad_record: false,
+        }
+    }
+
+    /// When true, records which cannot be parsed (e.g. a data field
+    /// with an invalid tag) are skipped instead of returned as errors.
+    ///
+    /// Errors in the XML itself are not recoverable and always end
+    /// the iteration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    ///
+    /// let xml = r#"<collection>
+    ///   <record><datafield tag="2450" ind1=" " ind2=" "><subfield code="a">Bad tag</subfield></datafield></record>
+    ///   <record><datafield tag="245" ind1=" " ind2=" "><subfield code="a">Good tag</subfield></datafield></record>
+    /// </collection>"#;
+    ///
+    /// let mut iter = Record::from_xml(xml);
+    /// iter.set_skip_malformed(true);
+    ///
+    /// let records: Vec<Record> = iter.by_ref().map(|r| r.unwrap()).collect();
+    ///
+    /// assert_eq!(records.len(), 1);
+    /// assert_eq!(records[0].get_field_values("245", "a"), vec!["Good tag"]);
+    /// assert_eq!(iter.skipped(), 1);
+    /// ```
+    pub fn set_skip_malformed(&mut self, skip: bool) {
+        self.skip_malformed = skip;
+    }
+
+    /// Number of malformed records skipped so far.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Skip records which cannot be read, including records containing
+    /// invalid XML, passing the raw XML of each and the error to the
+    /// handler.
+    ///
+    /// Records are located by scanning for <record> tags before they
+    /// are parsed, so a bad record does not prevent reading the rest of
+    /// the document.
+    ///
+    /// Err if records have already been read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use marctk::Record;
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// let xml = r#"<marc:collection xmlns:marc="http://www.loc.gov/MARC21/slim">
+    ///   <marc:record><marc:datafield tag="245" ind1=" " ind2=" "><marc:subfield code="a">One</marc:subfield></marc:datafield></marc:record>
+    ///   <marc:record><marc:datafield tag="245" ind1=" " ind2=" "><marc:subfield code="a">Oops</marc:datafield></marc:record>
+    ///   <marc:record><marc:datafield tag="245" ind1=" " ind2=" "><marc:subfield code="a">Three</marc:subfield></marc:datafield></marc:record>
+    /// </marc:collection>"#;
+    ///
+    /// let bad = Rc::new(RefCell::new(Vec::new()));
+    /// let bad_records = bad.clone();
+    ///
+    /// let mut iter = Record::from_xml(xml);
+    /// iter.set_recovery_handler(move |raw, _error| bad_records.borrow_mut().push(raw.to_string()))
+    ///     .unwrap();
+    ///
+    /// let titles: Vec<String> = iter
+    ///     .by_ref()
+    ///     .map(|r| r.unwrap().get_field_values("245", "a")[0].to_string())
+    ///     .collect();
+    ///
+    /// assert_eq!(titles, vec!["One", "Three"]);
+    /// assert_eq!(iter.skipped(), 1);
+    /// assert!(bad.borrow()[0].contains("Oops"));
+    /// ```
+    pub fn set_recovery_handler(
+        &mut self,
+        handler: impl FnMut(&str, &str) + 'static,
+    ) -> Result<(), String> {
+        if self.started {
+            return Err("Recovery must be enabled before reading records".to_string());
+        }
+
+        if let XmlSource::Events(_) = self.source {
+            let placeholder = XmlSource::Chunks(RecordChunks::new(Box::new(std::io::empty())));
+
+            if let XmlSource::Events(reader) = mem::replace(&mut self.source, placeholder) {
+                self.source = XmlSource::Chunks(RecordChunks::new(reader.into_inner()));
+            }
+        }
+
+        self.recovery = Some(Box::new(handler));
+
+        Ok(())
+    }
+
+    /// Parse records one chunk at a time, reporting any which fail.
+    fn read_next_recovering(&mut self) -> Result<Option<Record>, String> {
+        let XmlSource::Chunks(ref mut chunks) = self.source else {
+            return Ok(None);
+        };
+
+        loop {
+            let raw = match chunks.next_record() {
+                Ok(Some(r)) => r,
+                Ok(None) => {
+                    self.finished = true;
+                    return Ok(None);
+                }
+                Err(e) => {
+                    self.finished = true;
+                    return Err(e);
+                }
+            };
+
+            // Wrap the record in the root element to retain its
+            // namespace declarations.
+            let xml = match chunks.root.as_ref() {
+                Some((start, end)) => format!("{start}{raw}{end}"),
+                None => raw.clone(),
+            };
+
+            let error = match XmlRecordIterator::from_string(&xml).next() {
+                Some(Ok(record)) => return Ok(Some(record)),
+                Some(Err(e)) => e,
+                None => "Record element contains no record".to_string(),
+            };
+
+            self.skipped += 1;
+
+            if let Some(handler) = self.recovery.as_mut() {
+                handler(&raw, &error);
+            }
+        }
     }
 
     /// Pull the next Record from the data source.
     fn read_next(&mut self, context: &mut XmlParseContext) -> Result<Option<Record>, String> {
         loop {
-            let evt_res = match *self {
-                XmlRecordIterator::FileReader(ref mut reader) => reader.next(),
-                XmlRecordIterator::ByteReader(ref mut reader) => reader.next(),
+            let XmlSource::Events(ref mut reader) = self.source else {
+                return Ok(None);
+            };
+
+            let evt = match reader.next() {
+                Ok(e) => e,
+                Err(e) => {
+                    // The reader cannot recover from invalid XML.
+                    self.finished = true;
+                    return Err(format!("Error processing XML: {e}"));
+                }
             };
 
-            let evt = evt_res.map_err(|e| format!("Error processing XML: {e}"))?;
+            if self.in_bad_record {
+                match evt {
+                    XmlEvent::EndElement { ref name, .. } if name.local_name == "record" => {
+                        self.in_bad_record = false;
+                        context.record = Record::new();
+                    }
+                    XmlEvent::EndDocument => {
+                        self.finished = true;
+                        return Ok(None);
+                    }
+                    _ => {}
+                }
+                continue;
+            }
 
             if let Err(e) = self.handle_xml_event(context, evt) {
+                if self.skip_malformed {
+                    self.skipped += 1;
+                    self.in_bad_record = true;
+                    context.in_cfield = false;
+                    context.in_subfield = false;
+                    context.in_leader = false;
+                    continue;
+                }
+
+                // Discard the remainder of the bad record so the
+                // caller can continue with the next one.
+                self.in_bad_record = true;
                 return Err(format!("Error processing XML: {e}"));
             }
 
@@ -134,6 +627,7 @@ impl XmlRecordIterator {
             } else if context.doc_complete {
                 // If we had a doc in progress, discard it.
                 context.record = Record::new();
+                self.finished = true;
 
                 // All done.  Get outta here.
                 return Ok(None);
@@ -255,6 +749,127 @@ impl XmlRecordIterator {
     }
 }
 
+/// Element whose text is being collected by [`visit_xml`].
+enum VisitText {
+    Leader,
+    Controlfield(String),
+    Subfield(String),
+}
+
+/// Value of the named attribute.
+fn attr_value<'a>(attributes: &'a [OwnedAttribute], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|a| a.name.local_name == name)
+        .map(|a| a.value.as_str())
+}
+
+/// Pass the contents of each record in a MARC XML document to a visitor
+/// without building [`Record`]s.
+///
+/// Returns the number of records visited.
+///
+/// # Examples
+///
+/// ```
+/// use marctk::visitor::RecordVisitor;
+/// use marctk::xml::visit_xml;
+///
+/// #[derive(Default)]
+/// struct Titles(Vec<String>);
+///
+/// impl RecordVisitor for Titles {
+///     fn subfield(&mut self, code: &str, content: &str) {
+///         if code == "a" {
+///             self.0.push(content.to_string());
+///         }
+///     }
+/// }
+///
+/// let xml = r#"<collection xmlns="http://www.loc.gov/MARC21/slim">
+///   <record><leader>                        </leader>
+///     <datafield tag="245" ind1="1" ind2="0"><subfield code="a">One</subfield></datafield>
+///   </record>
+///   <record><datafield tag="245" ind1="1" ind2="0"><subfield code="a">Two</subfield></datafield></record>
+/// </collection>"#;
+///
+/// let mut titles = Titles::default();
+///
+/// assert_eq!(visit_xml(xml.as_bytes(), &mut titles), Ok(2));
+/// assert_eq!(titles.0, vec!["One", "Two"]);
+/// ```
+pub fn visit_xml(reader: impl Read, visitor: &mut impl RecordVisitor) -> Result<usize, String> {
+    let reader = EventReader::new(reader);
+
+    let mut count = 0;
+    let mut pending: Option<VisitText> = None;
+    let mut text = String::new();
+
+    for evt in reader {
+        let evt = evt.map_err(|e| format!("Error processing XML: {e}"))?;
+
+        match evt {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                text.clear();
+
+                match name.local_name.as_str() {
+                    "record" => {
+                        count += 1;
+                        visitor.start_record();
+                    }
+                    "leader" => pending = Some(VisitText::Leader),
+                    "controlfield" => {
+                        let tag =
+                            attr_value(&attributes, "tag").ok_or("Controlfield has no tag")?;
+                        pending = Some(VisitText::Controlfield(tag.to_string()));
+                    }
+                    "datafield" => {
+                        let tag = attr_value(&attributes, "tag").ok_or("Data field has no tag")?;
+                        visitor.start_field(
+                            tag,
+                            attr_value(&attributes, "ind1").unwrap_or(" "),
+                            attr_value(&attributes, "ind2").unwrap_or(" "),
+                        );
+                    }
+                    "subfield" => {
+                        let code = attr_value(&attributes, "code").ok_or("Subfield has no code")?;
+                        pending = Some(VisitText::Subfield(code.to_string()));
+                    }
+                    _ => {}
+                }
+            }
+
+            XmlEvent::Characters(ref chars)
+            | XmlEvent::Whitespace(ref chars)
+            | XmlEvent::CData(ref chars)
+                if pending.is_some() =>
+            {
+                text.push_str(chars)
+            }
+
+            XmlEvent::EndElement { name } => match name.local_name.as_str() {
+                "leader" | "controlfield" | "subfield" => match pending.take() {
+                    Some(VisitText::Leader) => visitor.leader(&text),
+                    Some(VisitText::Controlfield(tag)) => visitor.control_field(&tag, &text),
+                    Some(VisitText::Subfield(code)) => visitor.subfield(&code, &text),
+                    None => {}
+                },
+                "datafield" => visitor.end_field(),
+                "record" => visitor.end_record(),
+                _ => {}
+            },
+
+            XmlEvent::EndDocument => break,
+
+            _ => {}
+        }
+    }
+
+    Ok(count)
+}
+
 impl Record {
     /// Returns an iterator over the XML file which emits Records.
     pub fn from_xml_file(filename: &str) -> Result<XmlRecordIterator, String> {
@@ -291,10 +906,7 @@ impl Record {
 
     /// Creates an XML string from a [`Record`]
     pub fn to_xml_string(&self) -> String {
-        self.to_xml_string_ops(&XmlOptions {
-            formatted: false,
-            with_xml_declaration: false,
-        })
+        self.to_xml_string_ops(&XmlOptions::default())
     }
 
     #[deprecated(note = "See to_xml_string_formatted()")]
@@ -306,7 +918,7 @@ impl Record {
     pub fn to_xml_string_formatted(&self) -> String {
         self.to_xml_string_ops(&XmlOptions {
             formatted: true,
-            with_xml_declaration: false,
+            ..Default::default()
         })
     }
 
@@ -325,32 +937,47 @@ impl Record {
             _ => String::new(),
         };
 
-        // Document root
+        // Document root.  Namespaces are declared on the outermost
+        // element.
 
-        if options.formatted {
-            xml += &format!(
-                "\n<record\n  xmlns=\"{}\"\n  xmlns:xsi=\"{}\"\n  xsi:schemaLocation=\"{}\">",
-                MARCXML_NAMESPACE, MARCXML_XSI_NAMESPACE, MARCXML_SCHEMA_LOCATION
-            );
+        options.indent(&mut xml, 0);
+
+        let depth = if options.collection {
+            xml += &options.collection_start();
+            options.indent(&mut xml, 1);
+            xml += &format!("<{}>", options.qname("record"));
+            1
         } else {
-            xml += &format!(
-                r#"<record xmlns="{}" xmlns:xsi="{}" xsi:schemaLocation="{}">"#,
-                MARCXML_NAMESPACE, MARCXML_XSI_NAMESPACE, MARCXML_SCHEMA_LOCATION
-            );
-        }
+            xml += &options.root_start("record");
+            0
+        };
 
         // Leader
 
-        format(options.formatted, &mut xml, 2);
-        xml += &format!("<leader>{}</leader>", &escape_xml(self.leader(), false));
+        let leader = options.qname("leader");
+        options.indent(&mut xml, depth + 1);
+        xml += &format!("<{leader}>{}</{leader}>", &escape_xml(self.leader(), false));
+
+        let (control_fields, fields) = match options.field_order.as_ref() {
+            Some(order) => (
+                order.order_control_fields(self.control_fields()),
+                order.order_fields(self.fields()),
+            ),
+            None => (
+                self.control_fields().iter().collect(),
+                self.fields().iter().collect(),
+            ),
+        };
 
         // Control Fields
 
-        for cfield in self.control_fields() {
-            format(options.formatted, &mut xml, 2);
+        let controlfield = options.qname("controlfield");
+
+        for cfield in control_fields {
+            options.indent(&mut xml, depth + 1);
 
             xml += &format!(
-                r#"<controlfield tag="{}">{}</controlfield>"#,
+                r#"<{controlfield} tag="{}">{}</{controlfield}>"#,
                 escape_xml(cfield.tag(), true),
                 escape_xml(cfield.content(), false),
             );
@@ -358,34 +985,42 @@ impl Record {
 
         // Data Fields
 
-        for field in self.fields() {
-            format(options.formatted, &mut xml, 2);
+        let datafield = options.qname("datafield");
+        let subfield = options.qname("subfield");
+
+        for field in fields {
+            options.indent(&mut xml, depth + 1);
 
             xml += &format!(
-                r#"<datafield tag="{}" ind1="{}" ind2="{}">"#,
+                r#"<{datafield} tag="{}" ind1="{}" ind2="{}">"#,
                 escape_xml(field.tag(), true),
                 escape_xml(field.ind1(), true),
                 escape_xml(field.ind2(), true),
             );
 
             for sf in field.subfields() {
-                format(options.formatted, &mut xml, 4);
+                options.indent(&mut xml, depth + 2);
 
                 xml += &format!(
-                    r#"<subfield code="{}">{}</subfield>"#,
+                    r#"<{subfield} code="{}">{}</{subfield}>"#,
                     &escape_xml(sf.code(), true),
                     &escape_xml(sf.content(), false)
                 );
             }
 
-            format(options.formatted, &mut xml, 2);
+            options.indent(&mut xml, depth + 1);
 
-            xml += "</datafield>";
+            xml += &format!("</{datafield}>");
         }
 
-        format(options.formatted, &mut xml, 0);
+        options.indent(&mut xml, depth);
 
-        xml += "</record>";
+        xml += &format!("</{}>", options.qname("record"));
+
+        if options.collection {
+            options.indent(&mut xml, 0);
+            xml += &options.collection_end();
+        }
 
         xml
     }
@@ -429,6 +1064,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_malformed_record_is_returned_as_error() {
+        let iterator = Record::from_xml(
+            r#"<collection>
+                <record><datafield tag="2450" ind1="1" ind2="0"><subfield code="a">Bad tag</subfield></datafield></record>
+                <record><datafield tag="245" ind1="1" ind2="0"><subfield code="a">Second title</subfield></datafield></record>
+            </collection>"#,
+        );
+        let results: Vec<Result<Record, String>> = iterator.collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_err());
+        assert_eq!(
+            results[1].as_ref().unwrap().get_field_values("245", "a"),
+            vec!["Second title"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_xml_ends_iteration() {
+        let iterator = Record::from_xml(
+            r#"<collection>
+                <record><datafield tag="245" ind1="1" ind2="0"><subfield code="a">First title</subfield></datafield></record>
+                <record><datafield tag="245" ind1="1" ind2="0"><subfield code="a">Oops</datafield></record>
+            </collection>"#,
+        );
+        let results: Vec<Result<Record, String>> = iterator.collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+    }
+
+    #[test]
+    fn test_recovery_skips_invalid_xml() {
+        let mut iterator = Record::from_xml(
+            r#"<?xml version="1.0"?>
+            <!-- <record> in a comment -->
+            <collection xmlns="http://www.loc.gov/MARC21/slim">
+                <record><datafield tag="245" ind1="1" ind2="0"><subfield code="a">First title</subfield></datafield></record>
+                <record><datafield tag="2450" ind1="1" ind2="0"><subfield code="a">Bad tag</subfield></datafield></record>
+                <record><datafield tag="245" ind1="1" ind2="0"><subfield code="a">Unclosed</subfield></datafield>
+                <record><datafield tag="245" ind1="1" ind2="0"><subfield code="a">Second title</subfield></datafield></record>
+                <record><datafield tag="245" ind1="1" ind2="0"><subfield code="a">Truncated</subfield>"#,
+        );
+
+        let errors = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let errors2 = errors.clone();
+
+        iterator
+            .set_recovery_handler(move |raw, e| {
+                errors2.borrow_mut().push((raw.to_string(), e.to_string()))
+            })
+            .unwrap();
+
+        let values: Vec<String> = iterator
+            .by_ref()
+            .map(|item| item.unwrap().get_field_values("245", "a")[0].to_owned())
+            .collect();
+
+        assert_eq!(values, ["First title", "Second title"]);
+        assert_eq!(iterator.skipped(), 3);
+
+        let errors = errors.borrow();
+        assert!(errors[0].0.contains("Bad tag"));
+        assert!(errors[1].0.contains("Unclosed"));
+        assert!(errors[2].0.contains("Truncated"));
+
+        assert!(iterator.set_recovery_handler(|_, _| {}).is_err());
+    }
+
     #[test]
     fn test_can_parse_xml_string_without_collection() {
         let iterator = Record::from_xml(
@@ -1,3 +1,6 @@
+use marctk::edit::EditTasks;
+use marctk::xml::XmlOptions;
+use marctk::Field;
 use marctk::Record;
 
 // Avoiding newlines / formatting for testing purposes.
@@ -112,6 +115,36 @@ fn xml_round_trip() {
     assert_eq!(MARC_XML, xml);
 }
 
+#[test]
+fn xml_options_round_trip() {
+    let record = Record::from_xml(MARC_XML)
+        .next()
+        .unwrap()
+        .expect("Parse Failed");
+
+    let options = XmlOptions {
+        formatted: true,
+        indent: 4,
+        with_xml_declaration: true,
+        collection: true,
+        namespace_prefix: Some("marc".to_string()),
+        schema_location: false,
+        ..Default::default()
+    };
+
+    let xml = record.to_xml_string_ops(&options);
+
+    assert!(xml.starts_with(
+        "<?xml version=\"1.0\"?>\n<marc:collection\n    xmlns:marc=\"http://www.loc.gov/MARC21/slim\">\n    <marc:record>\n        <marc:leader>"
+    ));
+    assert!(xml.ends_with("    </marc:record>\n</marc:collection>"));
+    assert!(!xml.contains("schemaLocation"));
+
+    let record2 = Record::from_xml(&xml).next().unwrap().unwrap();
+
+    assert_eq!(MARC_XML, record2.to_xml_string());
+}
+
 #[test]
 fn all_round_trip() {
     let record = Record::from_xml(MARC_XML)
@@ -127,6 +160,23 @@ fn all_round_trip() {
     assert_eq!(MARC_XML, xml);
 }
 
+#[test]
+fn aleph_round_trip() {
+    let record = Record::from_xml(MARC_XML)
+        .next()
+        .unwrap()
+        .expect("Parse Failed");
+
+    let aleph = record.to_aleph("1234").unwrap();
+
+    assert!(aleph.starts_with("000001234 FMT   L MU\n000001234 LDR   L 00305cim^a2200133^i^4500"));
+    assert!(record.to_aleph("1234567890").is_err());
+
+    let record2 = Record::from_aleph(&aleph).unwrap();
+
+    assert_eq!(MARC_XML, record2.to_xml_string());
+}
+
 #[test]
 fn odd_records() {
     let record = Record::from_xml(EMPTY_MARC_XML)
@@ -229,3 +279,478 @@ fn delete_fields() {
 
     assert_eq!(record.get_fields("200").len(), 0);
 }
+
+#[test]
+fn brief_view() {
+    let record = Record::from_breaker(MARK_BREAKER).unwrap();
+    let view = record.brief_view();
+
+    assert_eq!(
+        view.title(),
+        Some(r#"Despierta con Cala : inspiraciones para "una vida" en equilibrio"#)
+    );
+    assert_eq!(view.authors().len(), 1);
+    assert_eq!(view.authors()[0].name(), "Cala, Ismael");
+    assert!(view.authors()[0].is_main_entry());
+    assert_eq!(view.edition(), Some("Primera edición"));
+    assert_eq!(
+        view.imprint(),
+        Some("Miami, FL : Aguilar : Penguin Random House Grupo Editorial USA LLC, 2017")
+    );
+    assert_eq!(
+        view.physical_description(),
+        Some("333 pages : color illustrations ; 23 cm")
+    );
+    assert_eq!(view.isbns(), &["9781945540042", "1945540044"]);
+    assert_eq!(view.material_types(), &["book"]);
+
+    // Duplicate headings from different thesauri are collapsed.
+    assert_eq!(view.subjects().len(), 6);
+    assert_eq!(view.subjects()[5], "Spanish language edition -- Nonfiction");
+}
+
+#[test]
+fn linkage() {
+    let mut record = Record::from_breaker(MARK_BREAKER).unwrap();
+
+    let idx = record
+        .fields()
+        .iter()
+        .position(|f| f.tag() == "245")
+        .unwrap();
+
+    let mut alt = Field::new("245").unwrap();
+    alt.add_subfield("a", "صحوة مع كالا").unwrap();
+
+    let occurrence = record.add_linked_field(idx, alt).unwrap();
+    assert_eq!(occurrence, 1);
+
+    let regular = &record.fields()[idx];
+    let linked = record.linked_field(regular).unwrap();
+
+    assert_eq!(linked.tag(), "880");
+    assert_eq!(linked.first_subfield("6").unwrap().content(), "245-01/(3/r");
+    assert_eq!(record.linked_field(linked).unwrap().tag(), "245");
+
+    // Already well-formed linkage is left alone.
+    assert_eq!(record.repair_linkage(), 0);
+
+    // Occurrence numbers are limited to two digits.
+    let mut breaker = String::new();
+    for occurrence in 1..=99 {
+        breaker += &format!("=500 \\\\$6880-{occurrence:02}$aNote {occurrence}\n");
+        breaker += &format!("=880 \\\\$6500-{occurrence:02}$aПримечание {occurrence}\n");
+    }
+
+    let mut record = Record::from_breaker(&breaker).unwrap();
+    assert_eq!(record.next_linkage_occurrence(), None);
+
+    let mut alt = Field::new("500").unwrap();
+    alt.add_subfield("a", "Примечание").unwrap();
+
+    assert!(record.add_linked_field(0, alt).is_err());
+
+    // Freed numbers are reused once 99 is in use.
+    record
+        .fields_mut()
+        .retain(|f| f.linkage().map(|l| l.occurrence()) != Some(42));
+    assert_eq!(record.next_linkage_occurrence(), Some(42));
+}
+
+#[test]
+fn material_types() {
+    let mut record = Record::from_breaker(MARK_BREAKER).unwrap();
+    assert_eq!(record.material_types(), vec!["book"]);
+
+    // Large print per 008/23 form of item.
+    let mut cf = record.get_control_fields("008")[0].clone();
+    let mut content = cf.content().to_string();
+    content.replace_range(23..24, "d");
+    cf.set_content(content);
+    record.remove_control_fields("008");
+    record.insert_control_field(cf);
+
+    assert_eq!(record.material_types(), vec!["lpbook"]);
+
+    // Online resource per the RDA carrier type.
+    record.remove_control_fields("008");
+    record.get_fields_mut("338")[0]
+        .first_subfield_mut("b")
+        .unwrap()
+        .set_content("cr");
+
+    assert_eq!(record.material_types(), vec!["ebook"]);
+}
+
+#[test]
+fn marc8_binary() {
+    let src = "=LDR 00000nam a2200000 a 4500\n=008 070101s2007\\\\\\\\nyu\\\\\\\\\\\\\\\\\\\\\\\\000\\0\\eng\\d\n=100 1\\$aBront\u{00EB}, Charlotte.\n=245 10$a\u{0141}\u{00F3}d\u{017A} \u{00A9} x\u{00B2}";
+
+    let record = Record::from_breaker(src).unwrap();
+
+    let bytes = record.to_binary_marc8().unwrap();
+    assert_eq!(bytes[9], b' ');
+
+    // Diacritics precede the base character in MARC-8.
+    let needle = b"Bront\xE8e";
+    assert!(bytes.windows(needle.len()).any(|w| w == needle));
+
+    let record2 = Record::from_binary(&bytes).unwrap();
+    assert_eq!(&record2.leader()[9..10], "a");
+    assert_eq!(record2.fields(), record.fields());
+    assert_eq!(record2.control_fields(), record.control_fields());
+
+    // UTF-8 content mislabeled as MARC-8 is read as UTF-8.
+    let mut utf8_bytes = record.to_binary().unwrap();
+    utf8_bytes[9] = b' ';
+
+    let record3 = Record::from_binary(&utf8_bytes).unwrap();
+    assert_eq!(
+        record3.get_field_values("100", "a"),
+        vec!["Bront\u{00EB}, Charlotte."]
+    );
+    assert_eq!(&record3.leader()[9..10], "a");
+
+    // MARC-8 content mislabeled as UTF-8 is read as MARC-8.
+    let mut marc8_bytes = bytes.clone();
+    marc8_bytes[9] = b'a';
+
+    let record4 = Record::from_binary(&marc8_bytes).unwrap();
+    assert_eq!(record4.fields(), record.fields());
+}
+
+#[test]
+fn lenient_binary() {
+    let src = "=LDR 00000nam a2200000 a 4500\n=001 123\n=245 10$aOne$bTwo\n=500 \\\\$aNote";
+    let record = Record::from_breaker(src).unwrap();
+    let bytes = record.to_binary().unwrap();
+
+    // Record with its leader updated to match the binary data.
+    let record = Record::from_binary(&bytes).unwrap();
+
+    // Leading line break and off-by-N base address of data.
+    let mut bad = b"\r\n".to_vec();
+    bad.extend_from_slice(&bytes);
+    bad[2 + 16] = b'0';
+
+    assert!(Record::from_binary(&bad).is_err());
+
+    let (record2, repairs) = Record::from_binary_lenient(&bad).unwrap();
+    assert_eq!(record2.to_breaker(), record.to_breaker());
+    assert_eq!(repairs.len(), 1, "{repairs:?}");
+    assert!(repairs[0].starts_with("Base address of data"));
+
+    // Directory entry 245 (the second entry) reports the wrong length.
+    let mut bad = bytes.clone();
+    let len_idx = 24 + 12 + 3;
+    assert_eq!(&bad[len_idx..len_idx + 4], b"0013");
+    bad[len_idx..len_idx + 4].copy_from_slice(b"0010");
+
+    let (record2, repairs) = Record::from_binary_lenient(&bad).unwrap();
+    assert_eq!(record2.to_breaker(), record.to_breaker());
+    assert_eq!(
+        repairs,
+        vec!["Field length for tag=245 reported=10 real=13"]
+    );
+
+    // Directory entry 245 points into the middle of another field.
+    let mut bad = bytes.clone();
+    let pos_idx = len_idx + 4;
+    bad[pos_idx..pos_idx + 5].copy_from_slice(b"00002");
+
+    let (record2, repairs) = Record::from_binary_lenient(&bad).unwrap();
+    assert_eq!(record2.to_breaker(), record.to_breaker());
+    assert_eq!(repairs.len(), 1, "{repairs:?}");
+
+    // Empty subfield.
+    let mut bad = bytes.clone();
+    let idx = bad.windows(3).position(|w| w == b"One").unwrap();
+    bad[idx - 1] = 0x1F;
+
+    assert!(Record::from_binary(&bad).is_err());
+
+    let (record2, repairs) = Record::from_binary_lenient(&bad).unwrap();
+    assert_eq!(record2.get_field_values("245", "b"), vec!["Two"]);
+    assert_eq!(repairs, vec!["Removed empty subfield in tag=245"]);
+}
+
+#[test]
+fn edit_tasks() {
+    let tasks = EditTasks::from_text(
+        r#"
+        # Move 090 call numbers into 099, dropping the emptied 090.
+        move 090$a 099$a
+        move 245$h 245$k
+        copy 100$a 100$q
+        append 500$a " (local)" $5~KCLS
+        delete 856 ind2=\ $u~example.org
+        "#,
+    )
+    .unwrap();
+
+    let mut record = Record::from_breaker(
+        r#"=090 \\$aPS3537
+=100 1\$aSmith, J.
+=245 10$aTitle$h[sound recording]
+=500 \\$aNote$5KCLS
+=500 \\$aOther note
+=856 40$uhttp://example.org/1
+=856 4\$uhttp://example.org/2"#,
+    )
+    .unwrap();
+
+    assert_eq!(tasks.apply(&mut record), 6);
+
+    let tags: Vec<&str> = record.fields().iter().map(|f| f.tag()).collect();
+    assert_eq!(tags, vec!["099", "100", "245", "500", "500", "856"]);
+
+    assert_eq!(record.get_field_values("099", "a"), vec!["PS3537"]);
+    assert_eq!(record.get_field_values("100", "q"), vec!["Smith, J."]);
+    assert_eq!(
+        record.get_field_values("245", "k"),
+        vec!["[sound recording]"]
+    );
+    assert!(record.get_field_values("245", "h").is_empty());
+    assert_eq!(
+        record.get_field_values("500", "a"),
+        vec!["Note (local)", "Other note"]
+    );
+    assert_eq!(record.get_fields("856")[0].ind2(), "0");
+
+    assert!(EditTasks::from_text("delete 650\nappend 245$a \"open").is_err());
+}
+
+#[test]
+fn validate() {
+    let mut record = Record::from_breaker(MARK_BREAKER).unwrap();
+
+    let issues = record.validate();
+    assert!(!issues.iter().any(|i| i.is_error()), "{issues:?}");
+
+    record.set_leader("02677xam a2200481Ii 4500").unwrap();
+    record.remove_fields("245");
+    record.get_fields_mut("020")[0].set_ind1("9").unwrap();
+
+    let issues = record.validate();
+    let errors: Vec<String> = issues
+        .iter()
+        .filter(|i| i.is_error())
+        .map(|i| i.to_string())
+        .collect();
+
+    assert_eq!(
+        errors,
+        vec![
+            "ERROR LDR: Invalid record status at position 05: 'x'",
+            "ERROR: Record has no 245 field",
+            "ERROR 020: Invalid indicator 1 value: '9'",
+        ]
+    );
+}
+
+#[test]
+fn diff() {
+    use marctk::diff::{Change, SubfieldChange};
+
+    let old = Record::from_breaker(MARK_BREAKER).unwrap();
+    let mut new = old.clone();
+
+    assert!(old.diff(&new).is_empty());
+
+    // Modify the 3rd 650 and drop the 2nd 020.
+    new.get_fields_mut("650")[2]
+        .first_subfield_mut("a")
+        .unwrap()
+        .set_content("Prosperity.");
+    new.fields_mut()
+        .retain(|f| f.to_breaker() != "=020 \\\\$a1945540044$q(paperback)");
+
+    let diff = old.diff(&new);
+
+    assert_eq!(diff.changes().len(), 2);
+    assert!(matches!(&diff.changes()[0], Change::FieldRemoved(f) if f.tag() == "020"));
+
+    match &diff.changes()[1] {
+        Change::FieldModified { old, subfields, .. } => {
+            assert_eq!(old.get_subfields("a")[0].content(), "Success.");
+            assert!(matches!(
+                &subfields[..],
+                [SubfieldChange::Modified { new, .. }] if new.content() == "Prosperity."
+            ));
+        }
+        c => panic!("Unexpected change: {c:?}"),
+    }
+
+    assert_eq!(
+        diff.to_breaker(),
+        r#"-=020 \\$a1945540044$q(paperback)
+-=650 \0$aSuccess.$0(DLC)540413
++=650 \0$aProsperity.$0(DLC)540413"#
+    );
+}
+
+#[test]
+fn summary_holdings() {
+    use marctk::holdings::HoldingsKind;
+
+    let mut record = Record::from_breaker(
+        r#"=LDR 00000nx  a22000001n 4500
+=852 01$aKCLS$bREG$kREF$h016.973$iM123$mv.2
+=854 20$81$av.$bpt.
+=864 40$81.1$a2$b3"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        record.holdings_locations()[0].call_number().as_deref(),
+        Some("REF 016.973 M123 v.2")
+    );
+    assert_eq!(record.summary_holdings(HoldingsKind::Basic), "");
+    assert_eq!(
+        record.summary_holdings(HoldingsKind::Supplement),
+        "v.2:pt.3"
+    );
+
+    // Textual holdings take precedence over generated statements.
+    let mut field = Field::new("867").unwrap();
+    field.add_subfield("a", "Supplements 1990-1995.").unwrap();
+    record.insert_data_field(field);
+
+    assert_eq!(
+        record.summary_holdings(HoldingsKind::Supplement),
+        "Supplements 1990-1995"
+    );
+}
+
+#[test]
+fn fixed_fields_round_trip() {
+    use marctk::fixed_fields::MaterialFields;
+
+    let record = Record::from_breaker(MARK_BREAKER).unwrap();
+    let fixed = record.fixed_008().unwrap();
+
+    assert_eq!(fixed.date_type, 's');
+    assert_eq!(fixed.material.target_audience(), Some('e'));
+    assert!(matches!(&fixed.material, MaterialFields::Books(b) if b.literary_form == '0'));
+
+    assert_eq!(
+        fixed.to_string(),
+        record.get_control_fields("008")[0].content()
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    use marctk::Subfield;
+
+    let record = Record::from_breaker(MARK_BREAKER).unwrap();
+
+    let text = serde_json::to_string(&record).unwrap();
+    let record2: Record = serde_json::from_str(&text).unwrap();
+
+    assert_eq!(record, record2);
+
+    let value = serde_json::to_value(&record).unwrap();
+    assert_eq!(value["leader"], "02677cam a2200481Ii 4500");
+    assert_eq!(value["control_fields"][0]["tag"], "001");
+    assert_eq!(value["fields"][0]["ind1"], " ");
+    assert_eq!(value["fields"][0]["subfields"][0]["code"], "a");
+
+    let sf: Subfield = serde_json::from_str(r#"{"code": "a", "content": "Title"}"#).unwrap();
+    assert_eq!(sf.content(), "Title");
+
+    // Values are validated.
+    assert!(serde_json::from_str::<Subfield>(r#"{"code": "ab"}"#).is_err());
+    assert!(serde_json::from_str::<Record>(r#"{"leader": "too short"}"#).is_err());
+    assert!(serde_json::from_str::<Record>(
+        r#"{"control_fields": [{"tag": "245", "content": "x"}]}"#
+    )
+    .is_err());
+}
+
+/// Rebuilds records from visitor events.
+#[derive(Default)]
+struct RecordCollector {
+    records: Vec<Record>,
+}
+
+impl marctk::visitor::RecordVisitor for RecordCollector {
+    fn start_record(&mut self) {
+        self.records.push(Record::new());
+    }
+
+    fn leader(&mut self, leader: &str) {
+        self.records.last_mut().unwrap().set_leader(leader).unwrap();
+    }
+
+    fn control_field(&mut self, tag: &str, content: &str) {
+        let record = self.records.last_mut().unwrap();
+        record
+            .control_fields_mut()
+            .push(marctk::Controlfield::new(tag, content).unwrap());
+    }
+
+    fn start_field(&mut self, tag: &str, ind1: &str, ind2: &str) {
+        let mut field = Field::new(tag).unwrap();
+        field.set_ind1(ind1).unwrap();
+        field.set_ind2(ind2).unwrap();
+        self.records.last_mut().unwrap().fields_mut().push(field);
+    }
+
+    fn subfield(&mut self, code: &str, content: &str) {
+        let record = self.records.last_mut().unwrap();
+        let field = record.fields_mut().last_mut().unwrap();
+        field.add_subfield(code, content).unwrap();
+    }
+}
+
+#[test]
+fn visitor() {
+    use marctk::visitor;
+
+    let record = Record::from_breaker(MARK_BREAKER).unwrap();
+    let record = Record::from_binary(&record.to_binary().unwrap()).unwrap();
+
+    let mut collector = RecordCollector::default();
+
+    marctk::binary::visit_binary(&record.to_binary().unwrap(), &mut collector).unwrap();
+    marctk::binary::visit_binary(&record.to_binary_marc8().unwrap(), &mut collector).unwrap();
+    marctk::xml::visit_xml(record.to_xml_string().as_bytes(), &mut collector).unwrap();
+
+    assert_eq!(collector.records.len(), 3);
+    for visited in &collector.records {
+        // The MARC-8 record length differs.
+        assert_eq!(&visited.leader()[5..], &record.leader()[5..]);
+        assert_eq!(visited.control_fields(), record.control_fields());
+        assert_eq!(visited.fields(), record.fields());
+    }
+
+    for (filename, is_xml) in [
+        ("examples/bib-marc-sample1.mrc", false),
+        ("examples/auth-marc-sample1.xml", true),
+    ] {
+        let mut collector = RecordCollector::default();
+
+        let (count, expected): (usize, Vec<Record>) = if is_xml {
+            (
+                visitor::visit_xml_file(filename, &mut collector).unwrap(),
+                Record::from_xml_file(filename)
+                    .unwrap()
+                    .map(|r| r.unwrap())
+                    .collect(),
+            )
+        } else {
+            (
+                visitor::visit_binary_file(filename, &mut collector).unwrap(),
+                Record::from_binary_file(filename)
+                    .unwrap()
+                    .map(|r| r.unwrap())
+                    .collect(),
+            )
+        };
+
+        assert_eq!(count, expected.len());
+        assert_eq!(collector.records, expected);
+    }
+}
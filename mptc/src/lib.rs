@@ -1,5 +1,6 @@
 #![forbid(unsafe_code)]
 
+pub mod metrics;
 pub mod server;
 pub mod signals;
 pub mod worker;
@@ -10,6 +11,11 @@ pub use server::Server;
 /// etc. signals.
 pub const SIGNAL_POLL_INTERVAL: u64 = 3;
 
+/// Default number of seconds between request latency log entries.
+///
+/// A value of 0 disables latency logging.
+pub const DEFAULT_METRICS_LOG_INTERVAL: u64 = 300;
+
 /// Default minimum number of worker threads.
 pub const DEFAULT_MIN_WORKERS: usize = 5;
 
@@ -44,6 +50,9 @@ pub trait RequestHandler: Send {
     ///
     /// Returns Err of String if request processing failed.  The error
     /// string will be logged.
+    ///
+    /// The time spent in process() is tracked by request label.  See
+    /// metrics::set_request_label().
     fn process(&mut self, request: Box<dyn Request>) -> Result<(), String>;
 }
 
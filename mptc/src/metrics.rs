@@ -0,0 +1,458 @@
+//! Request latency tracking.
+//!
+//! Workers time each call to RequestHandler::process() and add the
+//! duration to a histogram for the request's label.  Handlers label
+//! a request (e.g. with an API name) by calling set_request_label()
+//! from within process().  Unlabeled requests are tracked under
+//! DEFAULT_LABEL.
+//!
+//! Handlers which relay many messages per request, e.g. one SIP
+//! message after another on a single connection, may also time each
+//! message themselves and add it via record().
+//!
+//! Labels become metric label values, so handlers should only use
+//! labels from a bounded set, e.g. known API names, never raw client
+//! input.
+//!
+//! The histograms may be served in the Prometheus text format via
+//! serve().
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Path of the metrics endpoint.
+pub const METRICS_PATH: &str = "/metrics";
+
+/// Metric name used for request durations.
+const METRIC_NAME: &str = "mptc_request_duration_ms";
+
+/// Label applied to requests whose handler did not provide one.
+pub const DEFAULT_LABEL: &str = "default";
+
+/// Upper bound in milliseconds of each histogram bucket.
+///
+/// Durations beyond the last bound land in a final overflow bucket.
+pub const BUCKET_BOUNDS_MS: &[u64] = &[
+    1, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000,
+];
+
+// Set by the handler while processing a request, then collected by
+// the worker once process() returns.
+thread_local! {
+    static REQUEST_LABEL: RefCell<Option<String>> = const { RefCell::new(None) };
+    static WORKER_METRICS: RefCell<Option<Metrics>> = const { RefCell::new(None) };
+}
+
+/// Label the request currently being processed by this thread.
+///
+/// Calling this more than once for the same request replaces the
+/// previous label.
+pub fn set_request_label(label: &str) {
+    REQUEST_LABEL.with(|l| *l.borrow_mut() = Some(label.to_string()));
+}
+
+/// Returns and clears the label for the current request.
+pub(crate) fn take_request_label() -> Option<String> {
+    REQUEST_LABEL.with(|l| l.borrow_mut().take())
+}
+
+/// Set the metrics the current worker thread records to.
+pub(crate) fn set_worker_metrics(metrics: Metrics) {
+    WORKER_METRICS.with(|m| *m.borrow_mut() = Some(metrics));
+}
+
+/// Record a duration under a label from within a worker thread,
+/// e.g. for each message relayed during a long-lived request.
+///
+/// Does nothing when called outside of a worker thread.
+pub fn record(label: &str, duration: Duration) {
+    WORKER_METRICS.with(|m| {
+        if let Some(metrics) = m.borrow().as_ref() {
+            metrics.record(label, duration);
+        }
+    });
+}
+
+/// Bucketed request durations.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    /// One entry per BUCKET_BOUNDS_MS value plus the overflow bucket.
+    buckets: Vec<u64>,
+    count: u64,
+    total_ms: u64,
+    max_ms: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Histogram {
+    pub fn new() -> Histogram {
+        Histogram {
+            buckets: vec![0; BUCKET_BOUNDS_MS.len() + 1],
+            count: 0,
+            total_ms: 0,
+            max_ms: 0,
+        }
+    }
+
+    /// Add a duration to the histogram.
+    ///
+    /// ```
+    /// use mptc::metrics::Histogram;
+    /// use std::time::Duration;
+    ///
+    /// let mut hist = Histogram::new();
+    /// hist.record(Duration::from_millis(3));
+    /// hist.record(Duration::from_millis(40));
+    /// hist.record(Duration::from_secs(60));
+    ///
+    /// assert_eq!(hist.count(), 3);
+    /// assert_eq!(hist.max_ms(), 60_000);
+    ///
+    /// let buckets: Vec<(Option<u64>, u64)> = hist.buckets().collect();
+    /// assert_eq!(buckets[1], (Some(5), 1));
+    /// assert_eq!(buckets[4], (Some(50), 1));
+    /// assert_eq!(buckets.last(), Some(&(None, 1)));
+    /// ```
+    pub fn record(&mut self, duration: Duration) {
+        let ms = duration.as_millis() as u64;
+
+        let index = BUCKET_BOUNDS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(BUCKET_BOUNDS_MS.len());
+
+        self.buckets[index] += 1;
+        self.count += 1;
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+    }
+
+    /// Number of requests recorded.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Longest request duration in milliseconds.
+    pub fn max_ms(&self) -> u64 {
+        self.max_ms
+    }
+
+    /// Average request duration in milliseconds.
+    pub fn mean_ms(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.total_ms as f64 / self.count as f64
+    }
+
+    /// Iterator of (bucket upper bound in milliseconds, request count).
+    ///
+    /// The upper bound of the final overflow bucket is None.
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<u64>, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(idx, count)| (BUCKET_BOUNDS_MS.get(idx).copied(), *count))
+    }
+
+    /// Returns the upper bound of the bucket containing the requested
+    /// percentile (0-100), i.e. the percentile value is at most this
+    /// many milliseconds.
+    ///
+    /// Returns None if no requests have been recorded or the
+    /// percentile falls within the overflow bucket.
+    ///
+    /// ```
+    /// use mptc::metrics::Histogram;
+    /// use std::time::Duration;
+    ///
+    /// let mut hist = Histogram::new();
+    /// for ms in 1..=100 {
+    ///     hist.record(Duration::from_millis(ms));
+    /// }
+    ///
+    /// assert_eq!(hist.percentile_ms(50.0), Some(50));
+    /// assert_eq!(hist.percentile_ms(99.0), Some(100));
+    /// ```
+    pub fn percentile_ms(&self, percentile: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let target = ((percentile / 100.0) * self.count as f64).ceil() as u64;
+        let mut seen = 0;
+
+        for (bound, count) in self.buckets() {
+            seen += count;
+            if seen >= target {
+                return bound;
+            }
+        }
+
+        None
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pct = |p| match self.percentile_ms(p) {
+            Some(ms) => format!("<={ms}ms"),
+            None => format!(">{}ms", BUCKET_BOUNDS_MS[BUCKET_BOUNDS_MS.len() - 1]),
+        };
+
+        write!(
+            f,
+            "count={} mean={:.1}ms max={}ms p50{} p95{} p99{}",
+            self.count,
+            self.mean_ms(),
+            self.max_ms,
+            pct(50.0),
+            pct(95.0),
+            pct(99.0),
+        )
+    }
+}
+
+/// Latency histograms by request label, shared by the server and
+/// all of its workers.
+///
+/// Cloning a Metrics produces a handle to the same underlying data.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    histograms: Arc<Mutex<HashMap<String, Histogram>>>,
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Default::default()
+    }
+
+    /// Record the duration of a request with the provided label.
+    ///
+    /// ```
+    /// use mptc::metrics::Metrics;
+    /// use std::time::Duration;
+    ///
+    /// let metrics = Metrics::new();
+    /// let handle = metrics.clone();
+    ///
+    /// handle.record("23", Duration::from_millis(12));
+    /// handle.record("23", Duration::from_millis(30));
+    /// handle.record("11", Duration::from_millis(700));
+    ///
+    /// let snapshot = metrics.snapshot();
+    /// assert_eq!(snapshot["23"].count(), 2);
+    /// assert_eq!(snapshot["11"].max_ms(), 700);
+    /// ```
+    pub fn record(&self, label: &str, duration: Duration) {
+        let mut histograms = match self.histograms.lock() {
+            Ok(h) => h,
+            Err(e) => {
+                log::error!("Metrics lock is poisoned: {e}");
+                return;
+            }
+        };
+
+        if let Some(hist) = histograms.get_mut(label) {
+            hist.record(duration);
+        } else {
+            let mut hist = Histogram::new();
+            hist.record(duration);
+            histograms.insert(label.to_string(), hist);
+        }
+    }
+
+    /// Returns a copy of the current histograms by request label.
+    pub fn snapshot(&self) -> HashMap<String, Histogram> {
+        match self.histograms.lock() {
+            Ok(h) => h.clone(),
+            Err(_) => HashMap::new(),
+        }
+    }
+
+    /// Total number of requests recorded across all labels.
+    pub fn request_count(&self) -> u64 {
+        match self.histograms.lock() {
+            Ok(h) => h.values().map(|hist| hist.count()).sum(),
+            Err(_) => 0,
+        }
+    }
+
+    /// Discard all recorded data.
+    pub fn reset(&self) {
+        if let Ok(mut h) = self.histograms.lock() {
+            h.clear();
+        }
+    }
+
+    /// Histograms in the Prometheus text exposition format, sorted
+    /// by label.
+    ///
+    /// Bucket counts are cumulative, per the format.
+    ///
+    /// ```
+    /// use mptc::metrics::Metrics;
+    /// use std::time::Duration;
+    ///
+    /// let metrics = Metrics::new();
+    /// metrics.record("23", Duration::from_millis(3));
+    /// metrics.record("23", Duration::from_millis(40));
+    ///
+    /// let text = metrics.to_prometheus();
+    /// assert!(text.contains("mptc_request_duration_ms_bucket{label=\"23\",le=\"5\"} 1\n"));
+    /// assert!(text.contains("mptc_request_duration_ms_bucket{label=\"23\",le=\"+Inf\"} 2\n"));
+    /// assert!(text.contains("mptc_request_duration_ms_sum{label=\"23\"} 43\n"));
+    /// assert!(text.contains("mptc_request_duration_ms_count{label=\"23\"} 2\n"));
+    /// ```
+    pub fn to_prometheus(&self) -> String {
+        let snapshot = self.snapshot();
+
+        let mut labels: Vec<&String> = snapshot.keys().collect();
+        labels.sort();
+
+        let mut text = format!(
+            "# HELP {METRIC_NAME} Request processing time in milliseconds.\n\
+             # TYPE {METRIC_NAME} histogram\n"
+        );
+
+        for label in labels {
+            let hist = &snapshot[label];
+            let label = escape_label(label);
+            let mut cumulative = 0;
+
+            for (bound, count) in hist.buckets() {
+                cumulative += count;
+
+                let le = match bound {
+                    Some(b) => b.to_string(),
+                    None => "+Inf".to_string(),
+                };
+
+                text += &format!(
+                    "{METRIC_NAME}_bucket{{label=\"{label}\",le=\"{le}\"}} {cumulative}\n"
+                );
+            }
+
+            text += &format!("{METRIC_NAME}_sum{{label=\"{label}\"}} {}\n", hist.total_ms);
+            text += &format!("{METRIC_NAME}_count{{label=\"{label}\"}} {}\n", hist.count);
+        }
+
+        text
+    }
+
+    /// Log one line per request label, sorted by label.
+    pub fn log_summary(&self) {
+        let snapshot = self.snapshot();
+
+        let mut labels: Vec<&String> = snapshot.keys().collect();
+        labels.sort();
+
+        for label in labels {
+            log::info!("MPTC latency label={label} {}", snapshot[label]);
+        }
+    }
+}
+
+/// Escape a Prometheus label value.
+fn escape_label(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Serve the metrics in the Prometheus text format over HTTP at
+/// METRICS_PATH from a background thread.
+///
+/// Returns Err if the address cannot be bound.
+///
+/// ```
+/// use mptc::metrics::{self, Metrics};
+/// use std::io::{Read, Write};
+/// use std::net::TcpStream;
+/// use std::time::Duration;
+///
+/// let metrics = Metrics::new();
+/// metrics.record("23", Duration::from_millis(3));
+///
+/// let addr = metrics::serve(metrics, "127.0.0.1:0").unwrap();
+///
+/// let mut stream = TcpStream::connect(addr).unwrap();
+/// stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+///
+/// let mut response = String::new();
+/// stream.read_to_string(&mut response).unwrap();
+///
+/// assert!(response.starts_with("HTTP/1.1 200 OK"));
+/// assert!(response.contains("mptc_request_duration_ms_count{label=\"23\"} 1"));
+/// ```
+pub fn serve(metrics: Metrics, address: &str) -> Result<std::net::SocketAddr, String> {
+    let listener =
+        TcpListener::bind(address).map_err(|e| format!("Cannot bind metrics to {address}: {e}"))?;
+
+    let local_addr = listener
+        .local_addr()
+        .map_err(|e| format!("Cannot read metrics address: {e}"))?;
+
+    log::info!("Serving metrics at http://{local_addr}{METRICS_PATH}");
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(s) => {
+                    if let Err(e) = respond(&metrics, s) {
+                        log::warn!("Error responding to metrics request: {e}");
+                    }
+                }
+                Err(e) => log::warn!("Error accepting metrics connection: {e}"),
+            }
+        }
+    });
+
+    Ok(local_addr)
+}
+
+/// Answer a single metrics HTTP request.
+fn respond(metrics: &Metrics, stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Discard the headers.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+
+    let response = if method == "GET" && path == METRICS_PATH {
+        let body = metrics.to_prometheus();
+        format!(
+            "HTTP/1.1 200 OK\r\n\
+             Content-Type: text/plain; version=0.0.4\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\r\n{body}",
+            body.len()
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+
+    (&stream).write_all(response.as_bytes())
+}
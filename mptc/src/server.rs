@@ -1,3 +1,4 @@
+use super::metrics::Metrics;
 use super::signals::SignalTracker;
 use super::worker::{Worker, WorkerInstance, WorkerState, WorkerStateEvent};
 use super::{Request, RequestStream};
@@ -33,6 +34,18 @@ pub struct Server {
 
     sig_tracker: SignalTracker,
 
+    /// Request latency histograms shared with our workers.
+    metrics: Metrics,
+
+    /// Seconds between latency log entries.  0 disables logging.
+    metrics_log_interval: u64,
+
+    /// Request count as of the last latency log entry.
+    metrics_logged_count: u64,
+
+    /// If set, serve the metrics over HTTP at this address.
+    metrics_address: Option<String>,
+
     /// All inbound requests arrive via this stream.
     stream: Box<dyn RequestStream>,
 }
@@ -45,6 +58,10 @@ impl Server {
             stream,
             workers: HashMap::new(),
             sig_tracker: SignalTracker::new(),
+            metrics: Metrics::new(),
+            metrics_log_interval: super::DEFAULT_METRICS_LOG_INTERVAL,
+            metrics_logged_count: 0,
+            metrics_address: None,
             worker_id_gen: 0,
             to_parent_tx: tx,
             to_parent_rx: rx,
@@ -67,6 +84,21 @@ impl Server {
     pub fn set_max_worker_requests(&mut self, v: usize) {
         self.max_worker_reqs = v;
     }
+    pub fn set_metrics_log_interval(&mut self, secs: u64) {
+        self.metrics_log_interval = secs;
+    }
+
+    /// Serve the request latency histograms in the Prometheus text
+    /// format at this address (e.g. "127.0.0.1:9100"), path /metrics.
+    pub fn set_metrics_address(&mut self, address: &str) {
+        self.metrics_address = Some(address.to_string());
+    }
+
+    /// Handle to the request latency histograms, e.g. for reporting
+    /// via a metrics/status endpoint.
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.clone()
+    }
 
     fn next_worker_id(&mut self) -> u64 {
         self.worker_id_gen += 1;
@@ -92,6 +124,7 @@ impl Server {
         let max_reqs = self.max_worker_reqs;
         let handler = self.stream.new_handler();
         let sig_tracker = self.sig_tracker.clone();
+        let metrics = self.metrics.clone();
 
         log::trace!(
             "Starting worker with idle={} active={}",
@@ -102,7 +135,15 @@ impl Server {
         let (tx, rx): (RequestSendChannel, RequestReceiveChannel) = mpsc::channel();
 
         let handle = thread::spawn(move || {
-            let mut w = Worker::new(worker_id, max_reqs, sig_tracker, to_parent_tx, rx, handler);
+            let mut w = Worker::new(
+                worker_id,
+                max_reqs,
+                sig_tracker,
+                metrics,
+                to_parent_tx,
+                rx,
+                handler,
+            );
             w.run();
         });
 
@@ -271,7 +312,15 @@ impl Server {
 
         self.start_workers();
 
+        if let Some(address) = self.metrics_address.as_deref() {
+            // Metrics are optional.  Keep serving requests without them.
+            if let Err(e) = super::metrics::serve(self.metrics.clone(), address) {
+                log::error!("{e}");
+            }
+        }
+
         let mut log_timer = Instant::now();
+        let mut metrics_timer = Instant::now();
 
         loop {
             match self.stream.next() {
@@ -291,6 +340,7 @@ impl Server {
             }
 
             self.log_thread_counts(&mut log_timer);
+            self.log_metrics(&mut metrics_timer);
         }
 
         self.stop_workers();
@@ -325,6 +375,27 @@ impl Server {
         *timer = Instant::now();
     }
 
+    /// Periodically log request latency by request label.
+    ///
+    /// Nothing is logged if no requests were processed since the
+    /// previous log entry.
+    fn log_metrics(&mut self, timer: &mut Instant) {
+        if self.metrics_log_interval == 0 || timer.elapsed().as_secs() < self.metrics_log_interval {
+            return;
+        }
+
+        *timer = Instant::now();
+
+        let count = self.metrics.request_count();
+
+        if count == self.metrics_logged_count {
+            return;
+        }
+
+        self.metrics_logged_count = count;
+        self.metrics.log_summary();
+    }
+
     fn dispatch_request(&mut self, request: Box<dyn Request>) {
         let wid = self.next_idle_worker();
         if let Some(worker) = self.workers.get_mut(&wid) {
@@ -1,9 +1,11 @@
+use super::metrics::{self, Metrics};
 use super::signals::SignalTracker;
 use super::{Request, RequestHandler};
 use std::fmt;
 use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
+use std::time::Instant;
 use std::time::SystemTime;
 
 const SHUTDOWN_POLL_INTERVAL: u64 = 5;
@@ -103,6 +105,7 @@ pub struct Worker {
     to_worker_rx: mpsc::Receiver<Box<dyn Request>>,
     handler: Box<dyn RequestHandler>,
     sig_tracker: SignalTracker,
+    metrics: Metrics,
 }
 
 impl Worker {
@@ -110,6 +113,7 @@ impl Worker {
         worker_id: u64,
         max_requests: usize,
         sig_tracker: SignalTracker,
+        metrics: Metrics,
         to_parent_tx: mpsc::Sender<WorkerStateEvent>,
         to_worker_rx: mpsc::Receiver<Box<dyn Request>>,
         handler: Box<dyn RequestHandler>,
@@ -123,6 +127,7 @@ impl Worker {
             worker_id,
             max_requests,
             sig_tracker,
+            metrics,
             start_time_epoch: epoch,
             to_parent_tx,
             to_worker_rx,
@@ -176,6 +181,9 @@ impl Worker {
     pub fn run(&mut self) {
         log::trace!("{self} starting");
 
+        // Let handlers record their own timings, see metrics::record().
+        metrics::set_worker_metrics(self.metrics.clone());
+
         if let Err(e) = self.handler.worker_start() {
             log::error!("Error starting worker: {e}.  Exiting");
             return;
@@ -251,12 +259,21 @@ impl Worker {
         // server, since it applies the Active state to this worker's
         // metadata just before sending us this request.
 
+        let start = Instant::now();
+
         if let Err(e) = self.handler.process(request) {
             // This is not necessarily an existential crisis, probably
             // just a malformed request, etc.
             log::error!("{self} error processing request: {e}");
         }
 
+        let label = metrics::take_request_label();
+
+        self.metrics.record(
+            label.as_deref().unwrap_or(metrics::DEFAULT_LABEL),
+            start.elapsed(),
+        );
+
         Ok(true)
     }
 }
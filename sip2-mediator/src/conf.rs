@@ -1,8 +1,74 @@
+use eg::osrf;
 use eg::EgResult;
 use evergreen as eg;
 use std::fs;
 use yaml_rust::YamlLoader;
 
+/// Connection settings for one Evergreen brick domain.
+///
+/// Unset values are taken from the OpenSRF client configuration.
+#[derive(Debug, Clone, Default)]
+pub struct Backend {
+    pub domain: String,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Backend {
+    /// OpenSRF bus client settings for connecting to this backend.
+    pub fn bus_config(&self) -> osrf::conf::BusClient {
+        let mut conf = osrf::conf::config().client().clone();
+
+        conf.set_domain(&self.domain);
+
+        if let Some(port) = self.port {
+            conf.set_port(port);
+        }
+        if let Some(username) = self.username.as_deref() {
+            conf.set_username(username);
+        }
+        if let Some(password) = self.password.as_deref() {
+            conf.set_password(password);
+        }
+
+        conf
+    }
+}
+
+/// Backend health monitoring and failover settings.
+#[derive(Debug, Clone)]
+pub struct Failover {
+    /// Seconds between backend health checks.
+    pub check_interval: u64,
+
+    /// Seconds to wait for a health check response.
+    pub check_timeout: u64,
+
+    /// Consecutive failed checks before a backend is considered down.
+    pub failure_threshold: u32,
+
+    /// If true, return to a preferred backend once it recovers.
+    /// Otherwise, remain on the failover backend until it fails.
+    pub failback: bool,
+
+    /// Seconds a preferred backend must remain healthy before we
+    /// fail back to it.
+    pub failback_delay: u64,
+}
+
+impl Default for Failover {
+    fn default() -> Failover {
+        Failover {
+            check_interval: 10,
+            check_timeout: 5,
+            failure_threshold: 3,
+            failback: true,
+            failback_delay: 300,
+        }
+    }
+}
+
 /// SIP configuration
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -14,6 +80,24 @@ pub struct Config {
     pub ascii: bool,
     pub heartbeat_account: Option<String>,
     pub start_in_ready_mode: bool,
+
+    /// Recover what we can from malformed SIP messages instead of
+    /// ending the session.
+    pub lenient_parsing: bool,
+
+    /// In lenient mode, relay messages which lack fields required
+    /// by the SIP specification.
+    pub allow_missing_fields: bool,
+
+    /// If set, serve SIP message latency metrics over HTTP at this
+    /// address.
+    pub metrics_address: Option<String>,
+
+    /// Evergreen domains in order of preference.  If empty, connect
+    /// to the domain from the OpenSRF client configuration.
+    pub backends: Vec<Backend>,
+
+    pub failover: Failover,
 }
 
 impl Default for Config {
@@ -27,6 +111,11 @@ impl Default for Config {
             ascii: true,
             heartbeat_account: None,
             start_in_ready_mode: true,
+            lenient_parsing: false,
+            allow_missing_fields: false,
+            metrics_address: None,
+            backends: Vec::new(),
+            failover: Failover::default(),
         }
     }
 }
@@ -83,7 +172,53 @@ impl Config {
             conf.start_in_ready_mode = v;
         }
 
+        if let Some(v) = root["lenient-parsing"].as_bool() {
+            conf.lenient_parsing = v;
+        }
+
+        if let Some(v) = root["allow-missing-fields"].as_bool() {
+            conf.allow_missing_fields = v;
+        }
+
         conf.heartbeat_account = root["heartbeat-account"].as_str().map(|s| s.to_string());
+        conf.metrics_address = root["metrics-address"].as_str().map(|s| s.to_string());
+
+        if let Some(backends) = root["backends"].as_vec() {
+            for backend in backends {
+                let domain = backend["domain"]
+                    .as_str()
+                    .ok_or("SIP config backends require a domain")?;
+
+                conf.backends.push(Backend {
+                    domain: domain.to_string(),
+                    port: backend["port"].as_i64().map(|v| v as u16),
+                    username: backend["username"].as_str().map(|s| s.to_string()),
+                    password: backend["password"].as_str().map(|s| s.to_string()),
+                });
+            }
+        }
+
+        let failover = &root["failover"];
+
+        if let Some(v) = failover["check-interval"].as_i64() {
+            conf.failover.check_interval = v as u64;
+        }
+
+        if let Some(v) = failover["check-timeout"].as_i64() {
+            conf.failover.check_timeout = v as u64;
+        }
+
+        if let Some(v) = failover["failure-threshold"].as_i64() {
+            conf.failover.failure_threshold = v as u32;
+        }
+
+        if let Some(v) = failover["failback"].as_bool() {
+            conf.failover.failback = v;
+        }
+
+        if let Some(v) = failover["failback-delay"].as_i64() {
+            conf.failover.failback_delay = v as u64;
+        }
 
         Ok(conf)
     }
@@ -0,0 +1,339 @@
+//! Backend health monitoring and failover between Evergreen domains.
+use super::conf;
+use eg::osrf;
+use evergreen as eg;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Sent to each backend to verify it is responding.
+const HEALTH_CHECK_SERVICE: &str = "open-ils.rs-sip2";
+const HEALTH_CHECK_METHOD: &str = "opensrf.system.echo";
+
+/// Health of a single backend.
+#[derive(Debug, Clone, Default)]
+struct BackendStatus {
+    healthy: bool,
+
+    /// Consecutive failed checks.
+    failures: u32,
+
+    /// When the backend most recently became healthy.
+    healthy_since: Option<Instant>,
+}
+
+/// Tracks backend health and decides which backend new sessions use.
+#[derive(Debug)]
+pub struct FailoverState {
+    config: conf::Failover,
+    statuses: Vec<BackendStatus>,
+
+    /// Index of the backend new sessions connect to.
+    active: usize,
+}
+
+impl FailoverState {
+    pub fn new(config: conf::Failover, backend_count: usize) -> Self {
+        // Assume all backends are healthy until proven otherwise.
+        let now = Instant::now();
+        let status = BackendStatus {
+            healthy: true,
+            failures: 0,
+            healthy_since: Some(now),
+        };
+
+        FailoverState {
+            config,
+            statuses: vec![status; backend_count],
+            active: 0,
+        }
+    }
+
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// Record the outcome of a health check (or a live request) and
+    /// select the active backend.
+    pub fn record(&mut self, index: usize, success: bool, now: Instant) {
+        let threshold = self.config.failure_threshold.max(1);
+
+        let Some(status) = self.statuses.get_mut(index) else {
+            return;
+        };
+
+        if success {
+            status.failures = 0;
+            if !status.healthy {
+                log::info!("SIP backend {index} is healthy again");
+                status.healthy = true;
+                status.healthy_since = Some(now);
+            }
+        } else {
+            status.failures += 1;
+            if status.healthy && status.failures >= threshold {
+                log::warn!(
+                    "SIP backend {index} marked down after {} failures",
+                    status.failures
+                );
+                status.healthy = false;
+                status.healthy_since = None;
+            }
+        }
+
+        self.select_active(now);
+    }
+
+    /// Most preferred healthy backend other than `exclude`.
+    pub fn healthy_alternative(&self, exclude: usize) -> Option<usize> {
+        self.statuses
+            .iter()
+            .enumerate()
+            .position(|(index, s)| index != exclude && s.healthy)
+    }
+
+    fn select_active(&mut self, now: Instant) {
+        let current = self.active;
+
+        let next = if !self.statuses[current].healthy {
+            // Fail over to the most preferred healthy backend.  If
+            // none are healthy, stay put and hope for the best.
+            self.statuses
+                .iter()
+                .position(|s| s.healthy)
+                .unwrap_or(current)
+        } else if self.config.failback {
+            // Sticky failback: only return to a more preferred backend
+            // once it has been healthy for the failback delay.
+            let delay = Duration::from_secs(self.config.failback_delay);
+
+            self.statuses[..current]
+                .iter()
+                .position(|s| {
+                    s.healthy
+                        && s.healthy_since
+                            .map(|t| now.duration_since(t) >= delay)
+                            .unwrap_or(false)
+                })
+                .unwrap_or(current)
+        } else {
+            current
+        };
+
+        if next != current {
+            log::warn!("SIP backend failover: switching from backend {current} to {next}");
+            self.active = next;
+        }
+    }
+}
+
+/// Shared handle for reading the active backend and reporting failures.
+#[derive(Debug, Clone)]
+pub struct Failover {
+    backends: Arc<Vec<conf::Backend>>,
+    state: Arc<Mutex<FailoverState>>,
+}
+
+impl Failover {
+    pub fn new(config: &conf::Config) -> Self {
+        Failover {
+            backends: Arc::new(config.backends.clone()),
+            state: Arc::new(Mutex::new(FailoverState::new(
+                config.failover.clone(),
+                config.backends.len(),
+            ))),
+        }
+    }
+
+    /// Returns the index and settings of the backend new sessions
+    /// should use, or None if no backends are configured.
+    pub fn active_backend(&self) -> Option<(usize, &conf::Backend)> {
+        if self.backends.is_empty() {
+            return None;
+        }
+
+        let index = match self.state.lock() {
+            Ok(s) => s.active(),
+            Err(_) => 0,
+        };
+
+        Some((index, &self.backends[index]))
+    }
+
+    /// Record a request failure seen by a session, which counts the
+    /// same as a failed health check.
+    pub fn report_failure(&self, index: usize) {
+        if let Ok(mut state) = self.state.lock() {
+            state.record(index, false, Instant::now());
+        }
+    }
+
+    /// Returns the index and settings of the most preferred healthy
+    /// backend other than `exclude`, for moving a session off of a
+    /// failing backend.
+    pub fn alternative_backend(&self, exclude: usize) -> Option<(usize, &conf::Backend)> {
+        let index = self.state.lock().ok()?.healthy_alternative(exclude)?;
+        Some((index, &self.backends[index]))
+    }
+
+    fn record(&self, index: usize, success: bool) {
+        if let Ok(mut state) = self.state.lock() {
+            state.record(index, success, Instant::now());
+        }
+    }
+
+    /// Start a thread which periodically checks the health of each
+    /// backend until shutdown is set.
+    pub fn start_monitor(&self, config: conf::Failover, shutdown: Arc<AtomicBool>) {
+        if self.backends.len() < 2 {
+            // Nothing to fail over to.
+            return;
+        }
+
+        let failover = self.clone();
+
+        thread::spawn(move || failover.monitor(config, shutdown));
+    }
+
+    fn monitor(&self, config: conf::Failover, shutdown: Arc<AtomicBool>) {
+        let mut clients: Vec<Option<eg::Client>> = self.backends.iter().map(|_| None).collect();
+
+        while !shutdown.load(Ordering::Relaxed) {
+            for (index, backend) in self.backends.iter().enumerate() {
+                let success = match self.check(backend, &mut clients[index], config.check_timeout) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        log::warn!("SIP backend {} health check failed: {e}", backend.domain);
+                        // Reconnect on the next check.
+                        clients[index] = None;
+                        false
+                    }
+                };
+
+                self.record(index, success);
+            }
+
+            // Sleep in short increments so we notice shutdown promptly.
+            for _ in 0..config.check_interval.max(1) {
+                if shutdown.load(Ordering::Relaxed) {
+                    break;
+                }
+                thread::sleep(Duration::from_secs(1));
+            }
+        }
+
+        log::debug!("SIP backend health monitor exiting");
+    }
+
+    /// Send an echo request to the SIP service on a backend.
+    fn check(
+        &self,
+        backend: &conf::Backend,
+        client: &mut Option<eg::Client>,
+        timeout: u64,
+    ) -> eg::EgResult<()> {
+        if client.is_none() {
+            let bus = osrf::bus::Bus::new(&backend.bus_config())?;
+            *client = Some(eg::Client::from_bus(bus));
+        }
+
+        let Some(client) = client.as_ref() else {
+            return Err("No health check client".into());
+        };
+
+        let mut ses = client.session(HEALTH_CHECK_SERVICE);
+        let mut req = ses.request(HEALTH_CHECK_METHOD, "ping")?;
+
+        match req.first_with_timeout(timeout)? {
+            Some(_) => Ok(()),
+            None => Err(format!("No response within {timeout} seconds").into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(failback: bool) -> conf::Failover {
+        conf::Failover {
+            check_interval: 1,
+            check_timeout: 1,
+            failure_threshold: 2,
+            failback,
+            failback_delay: 60,
+        }
+    }
+
+    #[test]
+    fn failover_and_sticky_failback() {
+        let start = Instant::now();
+        let mut state = FailoverState::new(config(true), 2);
+
+        // One failure is not enough to fail over.
+        state.record(0, false, start);
+        assert_eq!(state.active(), 0);
+
+        state.record(0, false, start);
+        assert_eq!(state.active(), 1);
+
+        // Primary recovers, but must stay healthy for the delay.
+        state.record(0, true, start + Duration::from_secs(10));
+        assert_eq!(state.active(), 1);
+
+        state.record(0, true, start + Duration::from_secs(69));
+        assert_eq!(state.active(), 1);
+
+        state.record(0, true, start + Duration::from_secs(70));
+        assert_eq!(state.active(), 0);
+    }
+
+    #[test]
+    fn no_failback() {
+        let start = Instant::now();
+        let mut state = FailoverState::new(config(false), 2);
+
+        state.record(0, false, start);
+        state.record(0, false, start);
+        assert_eq!(state.active(), 1);
+
+        state.record(0, true, start + Duration::from_secs(3600));
+        assert_eq!(state.active(), 1);
+
+        // Fail back only when the secondary fails.
+        state.record(1, false, start + Duration::from_secs(3601));
+        state.record(1, false, start + Duration::from_secs(3602));
+        assert_eq!(state.active(), 0);
+    }
+
+    #[test]
+    fn healthy_alternative() {
+        let start = Instant::now();
+        let mut state = FailoverState::new(config(true), 3);
+
+        assert_eq!(state.healthy_alternative(0), Some(1));
+        assert_eq!(state.healthy_alternative(1), Some(0));
+
+        state.record(1, false, start);
+        state.record(1, false, start);
+        assert_eq!(state.healthy_alternative(0), Some(2));
+
+        state.record(2, false, start);
+        state.record(2, false, start);
+        assert_eq!(state.healthy_alternative(0), None);
+    }
+
+    #[test]
+    fn all_down_stays_put() {
+        let start = Instant::now();
+        let mut state = FailoverState::new(config(true), 2);
+
+        for _ in 0..2 {
+            state.record(1, false, start);
+            state.record(0, false, start);
+        }
+
+        assert_eq!(state.active(), 0);
+    }
+}
@@ -6,6 +6,8 @@ use std::env;
 use std::path::Path;
 
 mod conf;
+mod failover;
+mod parse_stats;
 mod server;
 mod session;
 
@@ -29,6 +31,7 @@ fn main() -> EgResult<()> {
     let max_workers = conf.max_workers;
     let min_workers = conf.min_workers;
     let min_idle_workers = conf.min_idle_workers;
+    let metrics_address = conf.metrics_address.clone();
 
     let options = eg::init::InitOptions {
         skip_logging: false,
@@ -50,6 +53,10 @@ fn main() -> EgResult<()> {
     s.set_min_workers(min_workers);
     s.set_min_idle_workers(min_idle_workers);
 
+    if let Some(addr) = metrics_address.as_deref() {
+        s.set_metrics_address(addr);
+    }
+
     s.run();
 
     Ok(())
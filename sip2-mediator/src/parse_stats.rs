@@ -0,0 +1,102 @@
+//! Per-device counts of malformed SIP messages.
+//!
+//! Counts are kept per SIP login so the worst-behaved devices can be
+//! identified.  Counts live for the life of the process.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Device key used for messages received before a SIP login.
+const ANONYMOUS_DEVICE: &str = "(no login)";
+
+/// Number of devices listed in the summary log.
+const SUMMARY_SIZE: usize = 10;
+
+/// Parse problem counts for a single device.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceCounts {
+    /// Messages with at least one diagnostic.
+    pub malformed: u64,
+
+    /// Malformed messages which were not relayed to the ILS.
+    pub rejected: u64,
+
+    /// Diagnostic counts by kind, e.g. "missing-field".
+    pub diagnostics: HashMap<&'static str, u64>,
+}
+
+/// Parse problem counts shared between all sessions.
+#[derive(Debug, Clone, Default)]
+pub struct ParseStats {
+    devices: Arc<Mutex<HashMap<String, DeviceCounts>>>,
+}
+
+impl ParseStats {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Record the diagnostics for one message from a device.
+    pub fn record(&self, device: Option<&str>, diagnostics: &[sip2::Diagnostic], rejected: bool) {
+        if diagnostics.is_empty() {
+            return;
+        }
+
+        let device = device.unwrap_or(ANONYMOUS_DEVICE);
+
+        let mut devices = self.devices.lock().unwrap();
+
+        let counts = devices.entry(device.to_string()).or_default();
+
+        counts.malformed += 1;
+
+        if rejected {
+            counts.rejected += 1;
+        }
+
+        for diag in diagnostics {
+            *counts.diagnostics.entry(diag.kind()).or_insert(0) += 1;
+        }
+    }
+
+    /// Devices sorted by number of malformed messages, most first.
+    pub fn worst_devices(&self, limit: usize) -> Vec<(String, DeviceCounts)> {
+        let devices = self.devices.lock().unwrap();
+
+        let mut list: Vec<(String, DeviceCounts)> = devices
+            .iter()
+            .map(|(d, c)| (d.to_string(), c.clone()))
+            .collect();
+
+        list.sort_by(|a, b| b.1.malformed.cmp(&a.1.malformed).then(a.0.cmp(&b.0)));
+        list.truncate(limit);
+
+        list
+    }
+
+    /// Log the devices sending the most malformed messages.
+    pub fn log_summary(&self) {
+        let worst = self.worst_devices(SUMMARY_SIZE);
+
+        if worst.is_empty() {
+            log::info!("No malformed SIP messages received");
+            return;
+        }
+
+        for (device, counts) in worst {
+            let mut kinds: Vec<String> = counts
+                .diagnostics
+                .iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect();
+
+            kinds.sort();
+
+            log::info!(
+                "Malformed SIP messages from {device}: total={} rejected={} {}",
+                counts.malformed,
+                counts.rejected,
+                kinds.join(" ")
+            );
+        }
+    }
+}
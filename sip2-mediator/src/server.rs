@@ -1,4 +1,6 @@
 use super::conf::Config;
+use super::failover::Failover;
+use super::parse_stats::ParseStats;
 use super::session::Session;
 use eg::osrf;
 use evergreen as eg;
@@ -40,14 +42,40 @@ pub struct SessionFactory {
     /// OpenSRF bus.
     osrf_bus: Option<eg::osrf::bus::Bus>,
 
+    /// Index of the backend our bus is connected to, if backends
+    /// are configured.
+    osrf_bus_backend: Option<usize>,
+
+    failover: Failover,
+
     is_ready: Arc<AtomicBool>,
+
+    parse_stats: ParseStats,
+}
+
+impl SessionFactory {
+    /// Connect to the active backend, or to the domain from the
+    /// OpenSRF config when no backends are configured.
+    fn connect_bus(&mut self) -> Result<(), String> {
+        let bus = match self.failover.active_backend() {
+            Some((index, backend)) => {
+                log::debug!("SessionFactory connecting to backend {}", backend.domain);
+                self.osrf_bus_backend = Some(index);
+                eg::osrf::bus::Bus::new(&backend.bus_config())?
+            }
+            None => eg::osrf::bus::Bus::new(osrf::conf::config().client())?,
+        };
+
+        self.osrf_bus = Some(bus);
+
+        Ok(())
+    }
 }
 
 impl mptc::RequestHandler for SessionFactory {
     fn worker_start(&mut self) -> Result<(), String> {
         // Connect to Evergreen when each thread first starts.
-        let bus = eg::osrf::bus::Bus::new(osrf::conf::config().client())?;
-        self.osrf_bus = Some(bus);
+        self.connect_bus()?;
 
         log::debug!("SessionFactory connected OK to opensrf");
 
@@ -65,8 +93,19 @@ impl mptc::RequestHandler for SessionFactory {
     fn process(&mut self, mut request: Box<dyn mptc::Request>) -> Result<(), String> {
         let request = SipConnectRequest::downcast(&mut request);
 
+        // Each request is a whole SIP client connection.  Individual
+        // SIP messages are timed by the Session under their codes.
+        mptc::metrics::set_request_label("session");
+
         let shutdown = self.shutdown.clone();
 
+        // If we failed over (or back) since our bus connected,
+        // reconnect to the now-active backend.
+        let active = self.failover.active_backend().map(|(i, _)| i);
+        if active != self.osrf_bus_backend {
+            self.connect_bus()?;
+        }
+
         // Set in worker_start
         let osrf_bus = self.osrf_bus.take().unwrap();
 
@@ -78,7 +117,18 @@ impl mptc::RequestHandler for SessionFactory {
 
         let is_ready = self.is_ready.clone();
 
-        let mut session = Session::new(sip_config, osrf_bus, stream, shutdown, is_ready)?;
+        let mut session = Session::new(
+            sip_config,
+            osrf_bus,
+            stream,
+            shutdown,
+            is_ready,
+            self.parse_stats.clone(),
+        )?;
+
+        if let Some(index) = self.osrf_bus_backend {
+            session.set_failover(self.failover.clone(), index);
+        }
 
         if let Err(e) = session.start() {
             // This is not necessarily an error.  The client may simply
@@ -91,6 +141,9 @@ impl mptc::RequestHandler for SessionFactory {
         // SIP clients.  This SIP Session is done with it.
         let mut bus = session.take_bus();
 
+        // The session may have failed over to another backend.
+        self.osrf_bus_backend = session.backend();
+
         // Remove any trailing data on the Bus.
         bus.clear_bus()?;
 
@@ -121,7 +174,13 @@ pub struct Server {
 
     sig_tracker: SignalTracker,
 
+    /// Backend health, shared with our Sessions.
+    failover: Failover,
+
     is_ready: Arc<AtomicBool>,
+
+    /// Malformed message counts, shared with our Sessions.
+    parse_stats: ParseStats,
 }
 
 impl mptc::RequestStream for Server {
@@ -160,12 +219,20 @@ impl mptc::RequestStream for Server {
             is_ready: self.is_ready.clone(),
             sip_config: self.sip_config.clone(),
             osrf_bus: None, // set in worker_start
+            osrf_bus_backend: None,
+            failover: self.failover.clone(),
+            parse_stats: self.parse_stats.clone(),
         };
 
         Box::new(sf)
     }
 
     fn reload(&mut self) -> Result<(), String> {
+        // There is nothing to reload, but a reload signal is a handy
+        // way to request a report of the worst-behaved devices.
+        if self.sip_config.lenient_parsing {
+            self.parse_stats.log_summary();
+        }
         Ok(())
     }
 
@@ -176,6 +243,10 @@ impl mptc::RequestStream for Server {
         // own idle workers.
         log::info!("Server received mptc shutdown request");
 
+        if self.sip_config.lenient_parsing {
+            self.parse_stats.log_summary();
+        }
+
         self.shutdown.store(true, Ordering::Relaxed);
     }
 }
@@ -226,13 +297,19 @@ impl Server {
         sig_tracker.track_usr2();
 
         let ready = config.start_in_ready_mode;
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let failover = Failover::new(&config);
+        failover.start_monitor(config.failover.clone(), shutdown.clone());
 
         let server = Server {
             tcp_listener,
             sig_tracker,
+            failover,
+            shutdown,
+            parse_stats: ParseStats::new(),
             sip_config: Arc::new(config),
             is_ready: Arc::new(AtomicBool::new(ready)),
-            shutdown: Arc::new(AtomicBool::new(false)),
         };
 
         Ok(server)
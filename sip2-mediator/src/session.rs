@@ -1,4 +1,6 @@
 use super::conf;
+use super::failover::Failover;
+use super::parse_stats::ParseStats;
 use eg::osrf::logging;
 use eg::EgEvent;
 use eg::EgResult;
@@ -8,6 +10,7 @@ use std::fmt;
 use std::net;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
 /// How often do we wake up from blocking on our sip socket socket to check
 /// for shutdown, etc. signals.
@@ -40,6 +43,19 @@ pub struct Session {
     login_failed_msg: sip2::Message,
 
     heartbeat_account: Option<String>,
+
+    /// Backend health tracker and the index of our backend, used
+    /// to report backend failures and move to another backend.
+    failover: Option<(Failover, usize)>,
+
+    /// The login request which started the SIP session, replayed to
+    /// log in again when the session moves to another backend.
+    login_req: Option<sip2::Message>,
+
+    /// Relay leniently parsed messages which lack required fields.
+    allow_missing_fields: bool,
+
+    parse_stats: ParseStats,
 }
 
 impl Session {
@@ -52,6 +68,7 @@ impl Session {
         stream: net::TcpStream,
         shutdown: Arc<AtomicBool>,
         is_ready: Arc<AtomicBool>,
+        parse_stats: ParseStats,
     ) -> EgResult<Session> {
         match stream.peer_addr() {
             Ok(a) => log::info!("New SIP connection from {a}"),
@@ -63,6 +80,7 @@ impl Session {
 
         let mut con = sip2::Connection::from_stream(stream);
         con.set_ascii(sip_config.ascii);
+        con.set_lenient(sip_config.lenient_parsing);
 
         let client = eg::Client::from_bus(osrf_bus);
 
@@ -81,11 +99,26 @@ impl Session {
             sip_user: None,
             login_failed_msg,
             heartbeat_account,
+            failover: None,
+            login_req: None,
+            allow_missing_fields: sip_config.allow_missing_fields,
+            parse_stats,
         };
 
         Ok(ses)
     }
 
+    /// Report backend request failures to the failover tracker.
+    pub fn set_failover(&mut self, failover: Failover, backend: usize) {
+        self.failover = Some((failover, backend));
+    }
+
+    /// Index of the backend the session is connected to, if backends
+    /// are configured.  This changes if the session fails over.
+    pub fn backend(&self) -> Option<usize> {
+        self.failover.as_ref().map(|(_, index)| *index)
+    }
+
     /// Main listen loop
     pub fn start(&mut self) -> EgResult<()> {
         log::debug!("{self} starting");
@@ -119,6 +152,11 @@ impl Session {
 
             log::trace!("{} Read SIP message: {:?}", self, sip_req);
 
+            if !self.accept_diagnostics(&sip_req) {
+                log::warn!("{self} ending session after malformed SIP message");
+                break;
+            }
+
             if sip_req.spec() == &sip2::spec::M_LOGIN && !self.login_should_continue(&sip_req)? {
                 // Login should not continue.  Reply with a login
                 // failed message an break the loop.
@@ -128,17 +166,37 @@ impl Session {
                 break;
             }
 
+            // SIP message codes come from the spec, so they make for
+            // a bounded set of metric labels.
+            let code = sip_req.spec().code;
+            let start = Instant::now();
+
+            let login_req = if code == sip2::spec::M_LOGIN.code {
+                Some(sip_req.clone())
+            } else {
+                None
+            };
+
             // Relay the request to the Evergreen backend and wait for a
             // response.  If an error occurs, all we can do is exit and
             // cleanup, since SIP has no concept of an error response.
-            let sip_resp = match self.osrf_round_trip(sip_req) {
-                Ok(r) => r,
+            let sip_resp = match self.relay(sip_req) {
+                Ok(r) => {
+                    mptc::metrics::record(code, start.elapsed());
+                    r
+                }
                 Err(e) => {
                     log::error!("{self} error routing ILS message: {e}");
                     break;
                 }
             };
 
+            if let Some(req) = login_req {
+                if Self::login_succeeded(&sip_resp) {
+                    self.login_req = Some(req);
+                }
+            }
+
             log::trace!("{self} EG server replied with {sip_resp:?}");
 
             // Send the response back to the SIP client as a SIP message.
@@ -171,6 +229,29 @@ impl Session {
         }
     }
 
+    /// Count any problems found while parsing the message and decide
+    /// whether the message is complete enough to relay to the ILS.
+    fn accept_diagnostics(&self, sip_req: &sip2::Message) -> bool {
+        let diagnostics = self.sip_connection.diagnostics();
+
+        // The login message has not been processed yet, so use its
+        // SIP username directly.
+        let device = if sip_req.spec() == &sip2::spec::M_LOGIN {
+            sip_req.get_field_value("CN")
+        } else {
+            self.sip_user.as_deref()
+        };
+
+        let rejected = !self.allow_missing_fields
+            && diagnostics
+                .iter()
+                .any(|d| matches!(d, sip2::Diagnostic::MissingField(_)));
+
+        self.parse_stats.record(device, diagnostics, rejected);
+
+        !rejected
+    }
+
     /// Returns true if the login should continue.
     /// Returns false if the message is malformed or we are in non-ready
     /// mode and this is a login attempt by the heartbeat-account.
@@ -213,6 +294,70 @@ impl Session {
         self.osrf_round_trip(msg).map(|_| ())
     }
 
+    /// True if a login response reports success.
+    fn login_succeeded(resp: &sip2::Message) -> bool {
+        resp.fixed_fields()
+            .first()
+            .map(|ff| ff.value() == "1")
+            .unwrap_or(false)
+    }
+
+    /// Relay a request to the ILS.
+    ///
+    /// If the backend fails to answer, report the failure, move the
+    /// session to the next healthy backend, log in there again, and
+    /// retry the request once.
+    fn relay(&mut self, sip_req: sip2::Message) -> EgResult<sip2::Message> {
+        let err = match self.osrf_round_trip(sip_req.clone()) {
+            Ok(resp) => return Ok(resp),
+            Err(e) => e,
+        };
+
+        let Some((failover, backend)) = self.failover.clone() else {
+            return Err(err);
+        };
+
+        failover.report_failure(backend);
+
+        log::warn!("{self} request failed on backend {backend}: {err}; trying another");
+
+        if let Err(e) = self.move_to_backend(&failover, backend) {
+            log::error!("{self} cannot move session off of backend {backend}: {e}");
+            return Err(err);
+        }
+
+        self.osrf_round_trip(sip_req)
+    }
+
+    /// Connect to the most preferred healthy backend other than the
+    /// current one and replay the session's login there.
+    fn move_to_backend(&mut self, failover: &Failover, current: usize) -> EgResult<()> {
+        let (index, backend) = failover
+            .alternative_backend(current)
+            .ok_or("No healthy backend available")?;
+
+        log::info!("{self} moving session to backend {}", backend.domain);
+
+        let bus = eg::osrf::bus::Bus::new(&backend.bus_config())?;
+
+        self.client = eg::Client::from_bus(bus);
+        self.failover = Some((failover.clone(), index));
+
+        // The new backend knows nothing of our SIP session.
+        let Some(login_req) = self.login_req.clone() else {
+            // Not logged in yet.  Nothing to replay.
+            return Ok(());
+        };
+
+        let resp = self.osrf_round_trip(login_req)?;
+
+        if !Self::login_succeeded(&resp) {
+            return Err(format!("{self} login failed on backend {}", backend.domain).into());
+        }
+
+        Ok(())
+    }
+
     /// Send a SIP client request to the ILS backend for processing.
     ///
     /// Blocks waiting for a response.
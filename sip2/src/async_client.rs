@@ -0,0 +1,154 @@
+use super::async_connection::AsyncConnection;
+use super::client::*;
+use super::error::Error;
+use super::params::*;
+use super::spec::SipVersion;
+use super::typed::AcsStatusResponse;
+use super::Message;
+use std::time::Duration;
+
+/// Async version of [`crate::Client`] for use within a tokio runtime.
+///
+/// Available with the "async" feature.  Requests and responses are the
+/// same as those of [`crate::Client`].
+///
+/// ```no_run
+/// use sip2::{AsyncClient, ParamSet};
+///
+/// # async fn example() {
+/// let mut client = AsyncClient::new("127.0.0.1:6001").await.expect("Cannot Connect");
+///
+/// let mut params = ParamSet::new();
+/// params.set_sip_user("sip-server-login");
+/// params.set_sip_pass("sip-server-password");
+///
+/// // Login to the SIP server
+/// match client.login(&params).await.expect("Login Error").ok() {
+///     true => println!("Login OK"),
+///     false => eprintln!("Login Failed"),
+/// }
+/// # }
+/// ```
+pub struct AsyncClient {
+    connection: AsyncConnection,
+}
+
+impl AsyncClient {
+    /// Creates a new SIP client and opens the TCP connection to the server.
+    pub async fn new(host: &str) -> Result<Self, Error> {
+        Ok(AsyncClient {
+            connection: AsyncConnection::new(host).await?,
+        })
+    }
+
+    /// See [`crate::Client::set_version`].
+    pub fn set_version(&mut self, version: SipVersion) {
+        self.connection.set_version(version);
+    }
+
+    /// Shutdown the TCP connection with the SIP server.
+    pub async fn disconnect(&mut self) -> Result<(), Error> {
+        self.connection.disconnect().await
+    }
+
+    /// See [`crate::Client::login`].
+    pub async fn login(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
+        self.apply_params(params);
+        let req = login_request(params)?;
+        let resp = self.sendrecv(&req, params.timeout()).await?;
+        Ok(login_response(resp))
+    }
+
+    /// See [`crate::Client::sc_status`].
+    pub async fn sc_status(&mut self) -> Result<SipResponse, Error> {
+        let req = sc_status_request(self.connection.version());
+        let resp = self.sendrecv(&req, None).await?;
+        Ok(sc_status_response(resp))
+    }
+
+    /// Send the SC status message and parse the ACS status response.
+    ///
+    /// Unlike [`crate::Client::status`], the ACS's timeout and retry
+    /// hints are not applied.
+    pub async fn status(&mut self) -> Result<AcsStatusResponse, Error> {
+        let resp = self
+            .sendrecv(&sc_status_request(self.connection.version()), None)
+            .await?;
+        AcsStatusResponse::from_message(&resp)
+    }
+
+    /// See [`crate::Client::patron_status`].
+    pub async fn patron_status(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
+        self.apply_params(params);
+        let req = patron_status_request(params)?;
+        let resp = self.sendrecv(&req, params.timeout()).await?;
+        Ok(valid_patron_response(resp))
+    }
+
+    /// See [`crate::Client::patron_info`].
+    pub async fn patron_info(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
+        self.apply_params(params);
+        let req = patron_info_request(params)?;
+        let resp = self.sendrecv(&req, params.timeout()).await?;
+        Ok(valid_patron_response(resp))
+    }
+
+    /// See [`crate::Client::item_info`].
+    pub async fn item_info(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
+        self.apply_params(params);
+        let req = item_info_request(params)?;
+        let resp = self.sendrecv(&req, params.timeout()).await?;
+        Ok(item_info_response(resp))
+    }
+
+    /// Send a CHECKOUT request
+    pub async fn checkout(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
+        self.apply_params(params);
+        let req = checkout_request(params)?;
+        let resp = self.sendrecv(&req, params.timeout()).await?;
+        Ok(status_response(resp))
+    }
+
+    /// Send a CHECKIN request
+    pub async fn checkin(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
+        self.apply_params(params);
+        let req = checkin_request(params)?;
+        let resp = self.sendrecv(&req, params.timeout()).await?;
+        Ok(status_response(resp))
+    }
+
+    pub async fn fee_paid(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
+        self.apply_params(params);
+        let req = fee_paid_request(params)?;
+        let resp = self.sendrecv(&req, params.timeout()).await?;
+        Ok(status_response(resp))
+    }
+
+    /// Send a request and receive the response, failing with
+    /// [`Error::Timeout`] if `timeout` is set and expires first.
+    async fn sendrecv(
+        &mut self,
+        req: &Message,
+        timeout: Option<Duration>,
+    ) -> Result<Message, Error> {
+        let timeout = match timeout {
+            Some(t) => t,
+            None => return self.connection.sendrecv(req).await,
+        };
+
+        match tokio::time::timeout(timeout, self.connection.sendrecv(req)).await {
+            Ok(result) => result,
+            Err(_) => {
+                log::error!("SIP request timed out: timeout={timeout:?}");
+                Err(Error::Timeout)
+            }
+        }
+    }
+
+    /// Apply connection settings from the request parameters.
+    fn apply_params(&mut self, params: &ParamSet) {
+        if let Some(enabled) = params.error_detection() {
+            self.connection.set_error_detection(enabled);
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use super::connection::{
+    check_version, outbound_sip, parse_inbound, resend_request, ErrorDetection, Inbound,
+    MAX_RESEND_REQUESTS,
+};
+use super::diagnostic::Diagnostic;
+use super::encoding::Encoding;
+use super::error::Error;
+use super::spec;
+use super::spec::SipVersion;
+use super::Message;
+use std::fmt;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+// Read data from the socket in chunks this size.
+const READ_BUFSIZE: usize = 256;
+
+/// Async version of [`crate::Connection`] for use within a tokio runtime.
+///
+/// Available with the "async" feature.
+///
+/// ```
+/// use sip2::{AsyncConnection, Message};
+/// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+/// use tokio::net::TcpListener;
+///
+/// let rt = tokio::runtime::Builder::new_current_thread()
+///     .enable_all()
+///     .build()
+///     .unwrap();
+///
+/// rt.block_on(async {
+///     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+///     let addr = listener.local_addr().unwrap().to_string();
+///
+///     // Stand-in SIP server which accepts any login.
+///     tokio::spawn(async move {
+///         let (mut stream, _) = listener.accept().await.unwrap();
+///         let mut buf = [0; 256];
+///         stream.read(&mut buf).await.unwrap();
+///         stream.write_all(b"941\r").await.unwrap();
+///     });
+///
+///     let mut con = AsyncConnection::new(&addr).await.unwrap();
+///
+///     let req = Message::from_values("93", &["0", "0"], &[("CN", "sip-user"), ("CO", "sip-pass")])
+///         .unwrap();
+///
+///     let resp = con.sendrecv(&req).await.unwrap();
+///
+///     assert_eq!(resp.to_sip(), "941");
+/// });
+/// ```
+pub struct AsyncConnection {
+    tcp_stream: TcpStream,
+
+    // If set, non-ASCII chars are removed from outbound messages.
+    ascii: bool,
+
+    log_prefix: Option<String>,
+
+    // If set, inbound messages are parsed with Message::from_sip_lenient.
+    lenient: bool,
+
+    // Diagnostics from the most recently received message.
+    diagnostics: Vec<Diagnostic>,
+
+    // Set when the error detection extension is enabled.
+    error_detection: Option<ErrorDetection>,
+
+    encoding: Encoding,
+
+    version: SipVersion,
+}
+
+impl fmt::Display for AsyncConnection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(log_prefix) = self.log_prefix.as_ref() {
+            write!(f, "{log_prefix} ")
+        } else {
+            write!(f, "")
+        }
+    }
+}
+
+impl AsyncConnection {
+    /// Creates a new SIP client and opens the TCP connection to the server
+    ///
+    /// * `sip_host` - SIP server host/ip and port
+    /// * E.g. "127.0.0.1:6001"
+    pub async fn new(sip_host: &str) -> Result<Self, Error> {
+        log::debug!("AsyncConnection::new() connecting to: {}", sip_host);
+
+        match TcpStream::connect(sip_host).await {
+            Ok(stream) => Ok(AsyncConnection::from_stream(stream)),
+            Err(s) => {
+                log::error!("AsyncConnection::new() failed: {s}");
+                Err(Error::NetworkError(s.to_string()))
+            }
+        }
+    }
+
+    /// Create a new SIP connection from an existing TCP stream.
+    pub fn from_stream(tcp_stream: TcpStream) -> Self {
+        AsyncConnection {
+            ascii: false,
+            tcp_stream,
+            log_prefix: None,
+            lenient: false,
+            diagnostics: Vec::new(),
+            error_detection: None,
+            encoding: Encoding::default(),
+            version: SipVersion::default(),
+        }
+    }
+
+    /// Add a string that will be prepended to all log:: calls where
+    /// a self exists.
+    pub fn set_log_prefix(&mut self, prefix: impl Into<String>) {
+        self.log_prefix = Some(prefix.into());
+    }
+
+    /// Set the ascii flag
+    pub fn set_ascii(&mut self, ascii: bool) {
+        self.ascii = ascii;
+    }
+
+    /// Set the lenient flag.
+    ///
+    /// See [`crate::Connection::set_lenient`].
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Enable the SIP error detection extension.
+    ///
+    /// See [`crate::Connection::set_error_detection`].
+    pub fn set_error_detection(&mut self, enabled: bool) {
+        if !enabled {
+            self.error_detection = None;
+        } else if self.error_detection.is_none() {
+            self.error_detection = Some(ErrorDetection::default());
+        }
+    }
+
+    /// True if the error detection extension is enabled.
+    pub fn error_detection(&self) -> bool {
+        self.error_detection.is_some()
+    }
+
+    /// See [`crate::Connection::set_encoding`].
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    /// Character encoding used on the wire.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// See [`crate::Connection::set_version`].
+    pub fn set_version(&mut self, version: SipVersion) {
+        self.version = version;
+    }
+
+    pub fn version(&self) -> SipVersion {
+        self.version
+    }
+
+    /// Problems found while parsing the most recently received message.
+    ///
+    /// Always empty unless the lenient flag is set.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Shutdown the TCP connection with the SIP server.
+    pub async fn disconnect(&mut self) -> Result<(), Error> {
+        log::debug!("{self}AsyncConnection::disconnect()");
+
+        if let Err(s) = self.tcp_stream.shutdown().await {
+            // Disconnect will fail if the other end already disconnected.
+            log::info!("{self}disconnect() failed: {s}");
+            return Err(Error::NetworkError(s.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Send a SIP message
+    pub async fn send(&mut self, msg: &Message) -> Result<(), Error> {
+        check_version(msg, self.version)?;
+
+        let msg_sip = match self.error_detection.as_mut() {
+            Some(ed) => ed.outbound_sip(msg, self.ascii),
+            None => outbound_sip(msg, self.ascii),
+        };
+
+        self.send_text(msg_sip).await
+    }
+
+    /// Send SIP text, adding the line terminator.
+    async fn send_text(&mut self, msg_sip: String) -> Result<(), Error> {
+        // No need to redact here since SIP replies do not include passwords.
+        log::info!("{self}OUTBOUND: {}", msg_sip);
+
+        let mut bytes = self.encoding.encode_sip(&msg_sip);
+        bytes.push(spec::LINE_TERMINATOR as u8);
+
+        if let Err(s) = self.tcp_stream.write_all(&bytes).await {
+            log::error!("{self}send() failed: {}", s);
+            return Err(Error::NetworkError(s.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Send a message with a write timeout.
+    ///
+    /// Returns Err() if the send/write times out.
+    pub async fn send_with_timeout(&mut self, msg: &Message, timeout: u64) -> Result<(), Error> {
+        let time = Duration::from_secs(timeout);
+
+        match tokio::time::timeout(time, self.send(msg)).await {
+            Ok(result) => result,
+            Err(_) => {
+                log::error!("{self}send() timed out: timeout={timeout}");
+                Err(Error::Timeout)
+            }
+        }
+    }
+
+    /// Receive a SIP response.
+    ///
+    /// Waits until a response is received.
+    pub async fn recv(&mut self) -> Result<Message, Error> {
+        self.recv_internal().await
+    }
+
+    /// Receive a message, waiting at most `timeout` seconds.
+    ///
+    /// Returns None if no message arrives in time.  Data received
+    /// before the timeout expired is discarded.
+    pub async fn recv_with_timeout(&mut self, timeout: u64) -> Result<Option<Message>, Error> {
+        let time = Duration::from_secs(timeout);
+
+        match tokio::time::timeout(time, self.recv_internal()).await {
+            Ok(result) => result.map(Some),
+            Err(_) => {
+                log::trace!("{self}SIP tcp read timed out.  Returning None");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Receive a message, handling resend requests if the error
+    /// detection extension is enabled.
+    async fn recv_internal(&mut self) -> Result<Message, Error> {
+        self.diagnostics.clear();
+
+        let mut resends = 0;
+
+        loop {
+            let text = self.recv_text().await?;
+
+            let inbound = match self.error_detection.as_ref() {
+                Some(ed) => ed.check_inbound(&text),
+                None => Inbound::Accept,
+            };
+
+            if inbound != Inbound::Accept && resends == MAX_RESEND_REQUESTS {
+                log::error!("{self}Giving up after {resends} resends: {text}");
+                return Err(Error::ChecksumError);
+            }
+
+            match inbound {
+                Inbound::Accept => {
+                    let (msg, diagnostics) = parse_inbound(&text, self.lenient, self.version)?;
+                    self.diagnostics = diagnostics;
+
+                    log::info!("{self}INBOUND: {}", msg.to_sip_redacted());
+
+                    if let Some(ed) = self.error_detection.as_ref() {
+                        ed.check_sequence(&msg);
+                    }
+
+                    return Ok(msg);
+                }
+                Inbound::RequestResend => {
+                    log::warn!("{self}Invalid checksum.  Requesting resend: {text}");
+                    self.send_text(resend_request()).await?;
+                }
+                Inbound::Resend(last_sent) => {
+                    log::info!("{self}Server requested a resend");
+                    self.send_text(last_sent).await?;
+                }
+            }
+
+            resends += 1;
+        }
+    }
+
+    /// Read one message worth of text from the socket.
+    async fn recv_text(&mut self) -> Result<String, Error> {
+        let mut bytes: Vec<u8> = Vec::new();
+
+        loop {
+            let mut buf: [u8; READ_BUFSIZE] = [0; READ_BUFSIZE];
+
+            let num_bytes = match self.tcp_stream.read(&mut buf).await {
+                Ok(num) => num,
+                Err(e) => match e.kind() {
+                    std::io::ErrorKind::ConnectionReset => {
+                        log::info!("{self}remote disconnected in recv()");
+                        return Err(Error::NetworkError(e.to_string()));
+                    }
+                    _ => {
+                        log::error!("{self}recv() failed: {e}");
+                        return Err(Error::NetworkError(e.to_string()));
+                    }
+                },
+            };
+
+            if num_bytes == 0 {
+                break;
+            }
+
+            bytes.extend_from_slice(&buf[..num_bytes]);
+
+            if bytes.contains(&(spec::LINE_TERMINATOR as u8)) {
+                // We've read a whole message.
+                break;
+            }
+        }
+
+        if bytes.is_empty() {
+            // Receiving no content here indicates either an error
+            // or the client simply disconnected.
+            log::debug!("{self}Reading TCP stream returned 0 bytes");
+            return Err(Error::NoResponseError);
+        }
+
+        self.encoding.decode_sip(&bytes)
+    }
+
+    /// Shortcut for:  self.send(msg).await; resp = self.recv().await;
+    pub async fn sendrecv(&mut self, msg: &Message) -> Result<Message, Error> {
+        self.send(msg).await?;
+        self.recv().await
+    }
+}
@@ -0,0 +1,281 @@
+use sip2::spec::SipVersion;
+use sip2::typed::*;
+use sip2::{Connection, Encoding, Message};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::process;
+
+const DEFAULT_HOST: &str = "localhost:6001";
+
+const HELP_TEXT: &str = r#"
+Log in to a SIP server, send requests, and print the responses.
+
+Synopsis:
+
+sip2-cli --sip-user sip-user --sip-pass sip-pass --institution example \
+    status                                                             \
+    "patron-info patron=394902"                                        \
+    "checkout patron=394902 item=30000017113634"
+
+sip2-cli --sip-user sip-user --sip-pass sip-pass --script requests.txt
+
+Parameters:
+
+    --sip-host <host:port> [default="localhost:6001"]
+    --sip-user <username>
+    --sip-pass <password>
+        When set, a Login request is sent before any other requests.
+
+    --institution <institution>
+    --terminal-password <password>
+    --location-code <code>
+
+    --script <file>
+        Read requests from a file, one per line, after those from the
+        command line.  Blank lines and lines starting with '#' are
+        ignored.
+
+    --encoding <utf8|latin1|cp850> [default="utf8"]
+    --sip-version <2|3> [default="2"]
+
+    --error-detection
+        Enable the checksum and sequence number extension.
+
+    --print-raw-messages
+        Also print the unformatted SIP request and response messages.
+
+Requests:
+
+    Each request is a request name followed by optional name=value
+    parameters.
+
+    status
+    login
+    patron-info patron=<barcode> [patron-pass=<password>] [summary=<index>]
+    item-info item=<barcode>
+    checkout patron=<barcode> item=<barcode> [patron-pass=<password>]
+    checkin item=<barcode>
+    raw <SIP message>
+        Send any message as-is, e.g. "raw 9900302.00"
+
+"#;
+
+struct Settings {
+    sip_user: Option<String>,
+    sip_pass: Option<String>,
+    institution: String,
+    terminal_pwd: Option<String>,
+    location: Option<String>,
+    version: SipVersion,
+    print_raw: bool,
+}
+
+fn main() {
+    let options = read_options();
+
+    if options.opt_present("help") {
+        println!("{HELP_TEXT}");
+        return;
+    }
+
+    let settings = Settings {
+        sip_user: options.opt_str("sip-user"),
+        sip_pass: options.opt_str("sip-pass"),
+        institution: options.opt_str("institution").unwrap_or_default(),
+        terminal_pwd: options.opt_str("terminal-password"),
+        location: options.opt_str("location-code"),
+        version: match options.opt_str("sip-version").as_deref() {
+            None | Some("2") => SipVersion::V2,
+            Some("3") => SipVersion::V3,
+            Some(v) => exit_with(&format!("Unsupported SIP version: {v}")),
+        },
+        print_raw: options.opt_present("print-raw-messages"),
+    };
+
+    let mut requests = options.free.clone();
+
+    if let Some(path) = options.opt_str("script") {
+        let script = fs::read_to_string(&path)
+            .unwrap_or_else(|e| exit_with(&format!("Cannot read script {path}: {e}")));
+
+        requests.extend(
+            script
+                .lines()
+                .map(|l| l.trim())
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(|l| l.to_string()),
+        );
+    }
+
+    let host = options
+        .opt_str("sip-host")
+        .unwrap_or(DEFAULT_HOST.to_string());
+
+    let mut con = Connection::new(&host)
+        .unwrap_or_else(|e| exit_with(&format!("Cannot connect to {host}: {e}")));
+
+    con.set_encoding(match options.opt_str("encoding").as_deref() {
+        None | Some("utf8") => Encoding::Utf8,
+        Some("latin1") => Encoding::Latin1,
+        Some("cp850") => Encoding::Cp850,
+        Some(e) => exit_with(&format!("Unsupported encoding: {e}")),
+    });
+
+    con.set_version(settings.version);
+
+    con.set_error_detection(options.opt_present("error-detection"));
+
+    if settings.sip_user.is_some() {
+        requests.insert(0, "login".to_string());
+    }
+
+    let mut failures = 0;
+
+    for request in requests.iter() {
+        if let Err(e) = send_request(&mut con, &settings, request) {
+            eprintln!("{request}: {e}\n");
+            failures += 1;
+        }
+    }
+
+    let _ = con.disconnect();
+
+    if failures > 0 {
+        process::exit(1);
+    }
+}
+
+/// Build, send, and print a single request.
+fn send_request(con: &mut Connection, settings: &Settings, request: &str) -> Result<(), String> {
+    let req = build_request(settings, request)?;
+
+    if settings.print_raw {
+        println!("{}\n", req.to_sip_redacted());
+    }
+
+    let resp = con.sendrecv(&req).map_err(|e| e.to_string())?;
+
+    if settings.print_raw {
+        println!("{}\n", resp.to_sip());
+    }
+
+    println!("{resp}");
+
+    Ok(())
+}
+
+/// Translate a request line into a SIP message.
+fn build_request(settings: &Settings, request: &str) -> Result<Message, String> {
+    let (name, args) = request.split_once(' ').unwrap_or((request, ""));
+
+    if name == "raw" {
+        return Message::from_sip(args.trim()).map_err(|e| e.to_string());
+    }
+
+    let mut params = HashMap::new();
+
+    for arg in args.split_whitespace() {
+        match arg.split_once('=') {
+            Some((k, v)) => params.insert(k, v.to_string()),
+            None => return Err(format!("Invalid parameter: {arg}")),
+        };
+    }
+
+    let param = |name: &str| -> Result<String, String> {
+        params
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Parameter '{name}' is required"))
+    };
+
+    let institution = settings.institution.clone();
+    let terminal_pwd = settings.terminal_pwd.clone();
+
+    let msg = match name {
+        "status" => ScStatusRequest {
+            protocol_version: settings.version.protocol_version().to_string(),
+            ..Default::default()
+        }
+        .to_message(),
+
+        "login" => LoginRequest {
+            sip_user: settings.sip_user.clone().unwrap_or_default(),
+            sip_pass: settings.sip_pass.clone().unwrap_or_default(),
+            location: settings.location.clone(),
+        }
+        .to_message(),
+
+        "patron-info" => PatronInformationRequest {
+            institution,
+            terminal_pwd,
+            patron_id: param("patron")?,
+            patron_pwd: params.get("patron-pass").cloned(),
+            summary: match params.get("summary") {
+                Some(s) => Some(s.parse().map_err(|_| format!("Invalid summary: {s}"))?),
+                None => None,
+            },
+            ..Default::default()
+        }
+        .to_message(),
+
+        "item-info" => ItemInformationRequest {
+            institution,
+            terminal_pwd,
+            item_id: param("item")?,
+            ..Default::default()
+        }
+        .to_message(),
+
+        "checkout" => CheckoutRequest {
+            institution,
+            terminal_pwd,
+            patron_id: param("patron")?,
+            item_id: param("item")?,
+            patron_pwd: params.get("patron-pass").cloned(),
+            ..Default::default()
+        }
+        .to_message(),
+
+        "checkin" => CheckinRequest {
+            institution,
+            terminal_pwd,
+            item_id: param("item")?,
+            current_location: settings.location.clone().unwrap_or_default(),
+            ..Default::default()
+        }
+        .to_message(),
+
+        _ => return Err(format!("Unknown request: {name}")),
+    };
+
+    msg.map_err(|e| e.to_string())
+}
+
+fn exit_with(msg: &str) -> ! {
+    eprintln!("{msg}");
+    process::exit(1);
+}
+
+/// Read the command line arguments
+fn read_options() -> getopts::Matches {
+    let args: Vec<String> = env::args().collect();
+    let mut opts = getopts::Options::new();
+
+    opts.optopt("", "sip-host", "SIP Host", "");
+    opts.optopt("", "sip-user", "SIP User", "");
+    opts.optopt("", "sip-pass", "SIP pass", "");
+    opts.optopt("", "institution", "Institution", "");
+    opts.optopt("", "terminal-password", "Terminal Password", "");
+    opts.optopt("", "location-code", "Location Code", "");
+    opts.optopt("", "script", "Request Script File", "");
+    opts.optopt("", "encoding", "Character Encoding", "");
+    opts.optopt("", "sip-version", "SIP Protocol Version", "");
+
+    opts.optflag("h", "help", "");
+    opts.optflag("", "error-detection", "");
+    opts.optflag("", "print-raw-messages", "");
+
+    opts.parse(&args[1..]) // skip the command name
+        .expect("Error parsing command line options")
+}
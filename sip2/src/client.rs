@@ -1,8 +1,66 @@
-use super::connection::Connection;
+use super::connection::{Connection, Timeouts};
+use super::encoding::Encoding;
 use super::error::Error;
 use super::params::*;
+use super::typed::AcsStatusResponse;
 use super::{spec, util, Field, FixedField, Message};
 use std::str;
+use std::thread;
+use std::time::Duration;
+
+/// How a [`Client`] recovers when the connection to the SIP server
+/// is lost.
+///
+/// The client reconnects, logs in again with the parameters from its
+/// most recent successful login, and resends the failed request.
+///
+/// Note a request whose response was lost may have been processed by
+/// the server, in which case it is processed again when resent.
+///
+/// ```
+/// use sip2::ReconnectPolicy;
+/// use std::time::Duration;
+///
+/// let policy = ReconnectPolicy {
+///     max_retries: 4,
+///     initial_delay: Duration::from_secs(1),
+///     max_delay: Duration::from_secs(5),
+/// };
+///
+/// assert_eq!(policy.delay(0), Duration::from_secs(1));
+/// assert_eq!(policy.delay(1), Duration::from_secs(2));
+/// assert_eq!(policy.delay(2), Duration::from_secs(4));
+/// assert_eq!(policy.delay(3), Duration::from_secs(5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// Maximum number of connection attempts per failed request.
+    pub max_retries: u32,
+    /// Delay before the first connection attempt.  The delay doubles
+    /// with each subsequent attempt.
+    pub initial_delay: Duration,
+    /// Upper limit on the delay between attempts.
+    pub max_delay: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            max_retries: 5,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before the connection attempt, starting from zero.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+}
 
 /// Wrapper for Connection which provides a simpler interface for some
 /// common SIP2 actions.
@@ -25,16 +83,61 @@ use std::str;
 /// ```
 pub struct Client {
     connection: Connection,
+
+    host: String,
+
+    reconnect_policy: Option<ReconnectPolicy>,
+
+    // Parameters from the most recent successful login, used to log
+    // in again after reconnecting.
+    login_params: Option<ParamSet>,
+
+    // Response to the most recent SC Status request.
+    acs_status: Option<AcsStatusResponse>,
 }
 
 impl Client {
     /// Creates a new SIP client and opens the TCP connection to the server.
     pub fn new(host: &str) -> Result<Self, Error> {
+        Client::with_timeouts(host, Timeouts::default())
+    }
+
+    /// Creates a new SIP client with the provided network timeouts.
+    ///
+    /// Individual requests may override the read and write timeouts
+    /// via [`ParamSet::set_timeout`].
+    pub fn with_timeouts(host: &str, timeouts: Timeouts) -> Result<Self, Error> {
         Ok(Client {
-            connection: Connection::new(host)?,
+            connection: Connection::with_timeouts(host, timeouts)?,
+            host: host.to_string(),
+            reconnect_policy: None,
+            login_params: None,
+            acs_status: None,
         })
     }
 
+    /// Reconnect automatically when the connection is lost.
+    ///
+    /// By default, requests fail when the connection is lost.
+    pub fn set_reconnect_policy(&mut self, policy: ReconnectPolicy) {
+        self.reconnect_policy = Some(policy);
+    }
+
+    /// Change the network timeouts for this and any future connections.
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) -> Result<(), Error> {
+        self.connection.set_timeouts(timeouts)
+    }
+
+    /// Character encoding used on the wire.  See [`Connection::set_encoding`].
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.connection.set_encoding(encoding);
+    }
+
+    /// SIP protocol version.  See [`Connection::set_version`].
+    pub fn set_version(&mut self, version: spec::SipVersion) {
+        self.connection.set_version(version);
+    }
+
     /// Shutdown the TCP connection with the SIP server.
     pub fn disconnect(&self) -> Result<(), Error> {
         self.connection.disconnect()
@@ -44,280 +147,506 @@ impl Client {
     ///
     /// Sets ok=true if the OK fixed field is true.
     pub fn login(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
-        let user = match params.sip_user() {
-            Some(u) => u,
-            _ => return Err(Error::MissingParamsError),
-        };
+        self.apply_params(params);
+        let req = login_request(params)?;
+        let resp = login_response(self.sendrecv(&req, params.timeout())?);
 
-        let pass = match params.sip_pass() {
-            Some(u) => u,
-            _ => return Err(Error::MissingParamsError),
-        };
-
-        let mut req = Message::new(
-            &spec::M_LOGIN,
-            vec![
-                FixedField::new(&spec::FF_UID_ALGO, "0").unwrap(),
-                FixedField::new(&spec::FF_PWD_ALGO, "0").unwrap(),
-            ],
-            vec![
-                Field::new(spec::F_LOGIN_UID.code, user),
-                Field::new(spec::F_LOGIN_PWD.code, pass),
-            ],
-        );
-
-        req.maybe_add_field(spec::F_LOCATION_CODE.code, params.location());
-
-        let resp = self.connection.sendrecv(&req)?;
-
-        if resp.spec().code == spec::M_LOGIN_RESP.code
-            && resp.fixed_fields().len() == 1
-            && resp.fixed_fields()[0].value() == "1"
-        {
-            Ok(SipResponse::new(resp, true))
-        } else {
-            Ok(SipResponse::new(resp, false))
+        if resp.ok() {
+            self.login_params = Some(params.clone());
         }
+
+        Ok(resp)
     }
 
     /// Send the SC status message
     ///
     /// Sets ok=true if the server reports that it's online.
     pub fn sc_status(&mut self) -> Result<SipResponse, Error> {
-        let req = Message::new(
-            &spec::M_SC_STATUS,
-            vec![
-                FixedField::new(&spec::FF_STATUS_CODE, "0").unwrap(),
-                FixedField::new(&spec::FF_MAX_PRINT_WIDTH, "999").unwrap(),
-                FixedField::new(&spec::FF_PROTOCOL_VERSION, spec::SIP_PROTOCOL_VERSION).unwrap(),
-            ],
-            vec![],
-        );
-
-        let resp = self.connection.sendrecv(&req)?;
-
-        if !resp.fixed_fields().is_empty() && resp.fixed_fields()[0].value() == "Y" {
-            Ok(SipResponse::new(resp, true))
-        } else {
-            Ok(SipResponse::new(resp, false))
-        }
+        let req = sc_status_request(self.connection.version());
+        let resp = self.sendrecv(&req, None)?;
+        Ok(sc_status_response(resp))
     }
 
-    /// Send a patron status request
+    /// Send the SC status message and apply the ACS's hints.
     ///
-    /// Sets ok=true if the "valid patron" (BL) field is "Y"
-    pub fn patron_status(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
-        let patron_id = match params.patron_id() {
-            Some(p) => p,
-            _ => return Err(Error::MissingParamsError),
-        };
+    /// The response timeout suggested by the ACS becomes the read
+    /// timeout, unless a different read timeout was set on the client.
+    /// The number of retries allowed by the ACS limits the reconnect
+    /// policy's retries.
+    ///
+    /// The response is kept and is available via
+    /// [`Client::acs_status`].
+    pub fn status(&mut self) -> Result<&AcsStatusResponse, Error> {
+        let version = self.connection.version();
+        let resp = self.sendrecv(&sc_status_request(version), None)?;
+        let status = AcsStatusResponse::from_message(&resp)?;
+
+        if spec::SipVersion::from_protocol_version(&status.protocol_version) < Some(version) {
+            log::warn!(
+                "ACS reports protocol version {}; requested {}",
+                status.protocol_version,
+                version.protocol_version()
+            );
+        }
 
-        let mut req = Message::new(
-            &spec::M_PATRON_STATUS,
-            vec![
-                FixedField::new(&spec::FF_LANGUAGE, "000").unwrap(),
-                FixedField::new(&spec::FF_DATE, &util::sip_date_now()).unwrap(),
-            ],
-            vec![Field::new(spec::F_PATRON_ID.code, patron_id)],
-        );
+        self.apply_acs_status(&status)?;
 
-        req.maybe_add_field(spec::F_INSTITUTION_ID.code, params.institution());
-        req.maybe_add_field(spec::F_PATRON_PWD.code, params.patron_pwd());
-        req.maybe_add_field(spec::F_TERMINAL_PWD.code, params.terminal_pwd());
+        Ok(self.acs_status.insert(status))
+    }
 
-        let resp = self.connection.sendrecv(&req)?;
+    /// Response to the most recent [`Client::status`] request.
+    pub fn acs_status(&self) -> Option<&AcsStatusResponse> {
+        self.acs_status.as_ref()
+    }
 
-        if let Some(bl_val) = resp.get_field_value(spec::F_VALID_PATRON.code) {
-            if bl_val == "Y" {
-                return Ok(SipResponse::new(resp, true));
-            }
-        }
+    /// True if the ACS reported support for the request message in
+    /// its most recent status response.
+    ///
+    /// Always true when no status response has been received.
+    pub fn supports(&self, message: &spec::Message) -> bool {
+        self.acs_status
+            .as_ref()
+            .map(|s| s.supports(message))
+            .unwrap_or(true)
+    }
 
-        Ok(SipResponse::new(resp, false))
+    /// Send a patron status request
+    ///
+    /// Sets ok=true if the "valid patron" (BL) field is "Y"
+    pub fn patron_status(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
+        self.apply_params(params);
+        let req = patron_status_request(params)?;
+        let resp = self.sendrecv(&req, params.timeout())?;
+        Ok(valid_patron_response(resp))
     }
 
     /// Send a patron information request
     ///
     /// Sets ok=true if the "valid patron" (BL) field is "Y"
     pub fn patron_info(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
-        let patron_id = match params.patron_id() {
-            Some(p) => p,
-            None => return Err(Error::MissingParamsError),
-        };
+        self.apply_params(params);
+        let req = patron_info_request(params)?;
+        let resp = self.sendrecv(&req, params.timeout())?;
+        Ok(valid_patron_response(resp))
+    }
 
-        let mut summary: [char; 10] = [' '; 10];
+    /// Send a item information request
+    ///
+    /// Sets ok=true if a title (AJ) value is present.  Oddly, there's no
+    /// specific "item does not exist" value in the Item Info Response.
+    pub fn item_info(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
+        self.apply_params(params);
+        let req = item_info_request(params)?;
+        let resp = self.sendrecv(&req, params.timeout())?;
+        Ok(item_info_response(resp))
+    }
 
-        if let Some(idx) = params.summary() {
-            if idx < 10 {
-                summary[idx] = 'Y';
-            }
+    /// Send a CHECKOUT request
+    pub fn checkout(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
+        self.apply_params(params);
+        let req = checkout_request(params)?;
+        let resp = self.sendrecv(&req, params.timeout())?;
+        Ok(status_response(resp))
+    }
+
+    /// Send a CHECKIN request
+    pub fn checkin(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
+        self.apply_params(params);
+        let req = checkin_request(params)?;
+        let resp = self.sendrecv(&req, params.timeout())?;
+        Ok(status_response(resp))
+    }
+
+    pub fn fee_paid(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
+        self.apply_params(params);
+        let req = fee_paid_request(params)?;
+        let resp = self.sendrecv(&req, params.timeout())?;
+        Ok(status_response(resp))
+    }
+
+    /// Apply connection settings from the request parameters.
+    fn apply_params(&mut self, params: &ParamSet) {
+        if let Some(enabled) = params.error_detection() {
+            self.connection.set_error_detection(enabled);
         }
+    }
 
-        let sum_str: String = summary.iter().collect::<String>();
+    /// Apply the timeout and retry hints from an ACS status response.
+    fn apply_acs_status(&mut self, status: &AcsStatusResponse) -> Result<(), Error> {
+        let timeouts = self.connection.timeouts();
 
-        let mut req = Message::new(
-            &spec::M_PATRON_INFO,
-            vec![
-                FixedField::new(&spec::FF_LANGUAGE, "000").unwrap(),
-                FixedField::new(&spec::FF_DATE, &util::sip_date_now()).unwrap(),
-                FixedField::new(&spec::FF_SUMMARY, &sum_str).unwrap(),
-            ],
-            vec![Field::new(spec::F_PATRON_ID.code, patron_id)],
-        );
+        // Leave read timeouts set by the caller alone.
+        let hinted = self.acs_status.as_ref().and_then(|s| s.timeout());
 
-        req.maybe_add_field(spec::F_INSTITUTION_ID.code, params.institution());
-        req.maybe_add_field(spec::F_PATRON_PWD.code, params.patron_pwd());
-        req.maybe_add_field(spec::F_TERMINAL_PWD.code, params.terminal_pwd());
+        if let Some(timeout) = status.timeout() {
+            if timeouts.read.is_none() || timeouts.read == hinted {
+                log::debug!("Using read timeout of {timeout:?} from the ACS");
 
-        if let Some(v) = params.start_item() {
-            req.add_field(spec::F_START_ITEM.code, &v.to_string());
+                self.connection.set_timeouts(Timeouts {
+                    read: Some(timeout),
+                    ..timeouts
+                })?;
+            }
         }
 
-        if let Some(v) = params.end_item() {
-            req.add_field(spec::F_END_ITEM.code, &v.to_string());
+        if let (Some(retries), Some(policy)) =
+            (status.retries_allowed, self.reconnect_policy.as_mut())
+        {
+            policy.max_retries = policy.max_retries.min(retries as u32);
         }
 
-        let resp = self.connection.sendrecv(&req)?;
+        Ok(())
+    }
+
+    /// Send a request and receive the response, reconnecting and
+    /// resending the request if the connection was lost and a
+    /// reconnect policy is set.
+    ///
+    /// `timeout`, when set, replaces the connection's read and write
+    /// timeouts for this request.
+    fn sendrecv(&mut self, req: &Message, timeout: Option<Duration>) -> Result<Message, Error> {
+        let err = match self.sendrecv_once(req, timeout) {
+            Ok(resp) => return Ok(resp),
+            Err(e) => e,
+        };
 
-        if let Some(bl_val) = resp.get_field_value(spec::F_VALID_PATRON.code) {
-            if bl_val == "Y" {
-                return Ok(SipResponse::new(resp, true));
-            }
+        let policy = match self.reconnect_policy.as_ref() {
+            Some(p) => p.clone(),
+            None => return Err(err),
+        };
+
+        // A response which timed out may still arrive later and be
+        // mistaken for the response to the next request, so timeouts
+        // also require a new connection.
+        if !matches!(
+            err,
+            Error::NetworkError(_) | Error::NoResponseError | Error::Timeout
+        ) {
+            return Err(err);
         }
 
-        Ok(SipResponse::new(resp, false))
+        log::warn!("SIP request failed: {err}.  Reconnecting to {}", self.host);
+
+        self.reconnect(&policy, err)?;
+
+        self.sendrecv_once(req, timeout)
     }
 
-    /// Send a item information request
-    ///
-    /// Sets ok=true if a title (AJ) value is present.  Oddly, there's no
-    /// specific "item does not exist" value in the Item Info Response.
-    pub fn item_info(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
-        let item_id = match params.item_id() {
-            Some(id) => id,
-            None => return Err(Error::MissingParamsError),
+    fn sendrecv_once(
+        &mut self,
+        req: &Message,
+        timeout: Option<Duration>,
+    ) -> Result<Message, Error> {
+        let timeout = match timeout {
+            Some(t) => t,
+            None => return self.connection.sendrecv(req),
         };
 
-        let mut req = Message::new(
-            &spec::M_ITEM_INFO,
-            vec![FixedField::new(&spec::FF_DATE, &util::sip_date_now()).unwrap()],
-            vec![Field::new(spec::F_ITEM_IDENT.code, item_id)],
-        );
+        let timeouts = self.connection.timeouts();
 
-        req.maybe_add_field(spec::F_INSTITUTION_ID.code, params.institution());
-        req.maybe_add_field(spec::F_TERMINAL_PWD.code, params.terminal_pwd());
+        self.connection.set_timeouts(Timeouts {
+            read: Some(timeout),
+            write: Some(timeout),
+            ..timeouts
+        })?;
 
-        let resp = self.connection.sendrecv(&req)?;
+        let result = self.connection.sendrecv(req);
 
-        if let Some(title_val) = resp.get_field_value(spec::F_TITLE_IDENT.code) {
-            if !title_val.is_empty() {
-                return Ok(SipResponse::new(resp, true));
-            }
-        }
+        self.connection.set_timeouts(timeouts)?;
 
-        Ok(SipResponse::new(resp, false))
+        result
     }
 
-    /// Send a CHECKOUT request
-    pub fn checkout(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
-        let item_id = params.item_id().ok_or(Error::MissingParamsError)?;
-        let patron_id = params.patron_id().ok_or(Error::MissingParamsError)?;
-
-        let mut req = Message::from_values(
-            spec::M_CHECKOUT.code,
-            &[
-                "N",                   // renewal policy
-                "N",                   // no block
-                &util::sip_date_now(), // transaction date
-                &util::sip_date_now(), // no block due date
-            ],
-            &[
-                (spec::F_ITEM_IDENT.code, item_id),
-                (spec::F_PATRON_IDENT.code, patron_id),
-            ],
-        )?;
-
-        req.maybe_add_field(spec::F_INSTITUTION_ID.code, params.institution());
-        req.maybe_add_field(spec::F_TERMINAL_PWD.code, params.terminal_pwd());
-        req.maybe_add_field(spec::F_PATRON_PWD.code, params.patron_pwd());
-
-        let resp = self.connection.sendrecv(&req)?;
-
-        if let Some(status) = resp.fixed_fields().first() {
-            if status.value() == "1" {
-                return Ok(SipResponse::new(resp, true));
+    /// Try to reconnect until an attempt succeeds or the policy's
+    /// retries are exhausted.
+    fn reconnect(&mut self, policy: &ReconnectPolicy, mut err: Error) -> Result<(), Error> {
+        for attempt in 0..policy.max_retries {
+            thread::sleep(policy.delay(attempt));
+
+            match self.try_reconnect() {
+                Ok(()) => {
+                    log::info!(
+                        "Reconnected to {} after {} attempt(s)",
+                        self.host,
+                        attempt + 1
+                    );
+                    return Ok(());
+                }
+                Err(e) => {
+                    log::warn!("Reconnect attempt {} failed: {e}", attempt + 1);
+                    err = e;
+                }
             }
         }
 
-        Ok(SipResponse::new(resp, false))
+        Err(err)
     }
 
-    /// Send a CHECKIN request
-    pub fn checkin(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
-        let item_id = params.item_id().ok_or(Error::MissingParamsError)?;
-
-        let mut req = Message::from_values(
-            spec::M_CHECKIN.code,
-            &[
-                "N",                   // no block
-                &util::sip_date_now(), // transaction date
-                &util::sip_date_now(), // no block due date
-            ],
-            &[(spec::F_ITEM_IDENT.code, item_id)],
-        )?;
-
-        req.maybe_add_field(spec::F_INSTITUTION_ID.code, params.institution());
-        req.maybe_add_field(spec::F_TERMINAL_PWD.code, params.terminal_pwd());
-
-        let resp = self.connection.sendrecv(&req)?;
-
-        if let Some(status) = resp.fixed_fields().first() {
-            if status.value() == "1" {
-                return Ok(SipResponse::new(resp, true));
+    /// Open a new connection and log in again if needed.
+    fn try_reconnect(&mut self) -> Result<(), Error> {
+        // The old connection is likely already closed.
+        let _ = self.connection.disconnect();
+
+        let error_detection = self.connection.error_detection();
+        let encoding = self.connection.encoding();
+        let version = self.connection.version();
+
+        self.connection = Connection::with_timeouts(&self.host, self.connection.timeouts())?;
+        self.connection.set_error_detection(error_detection);
+        self.connection.set_version(version);
+        self.connection.set_encoding(encoding);
+
+        if let Some(params) = self.login_params.as_ref() {
+            let resp = login_response(self.connection.sendrecv(&login_request(params)?)?);
+
+            if !resp.ok() {
+                return Err(Error::NetworkError(
+                    "Login failed after reconnecting".to_string(),
+                ));
             }
         }
 
-        Ok(SipResponse::new(resp, false))
+        Ok(())
     }
+}
 
-    pub fn fee_paid(&mut self, params: &ParamSet) -> Result<SipResponse, Error> {
-        let patron_id = params.patron_id().ok_or(Error::MissingParamsError)?;
-        let pay_amount = params.pay_amount().ok_or(Error::MissingParamsError)?;
-
-        let pay_amount = pay_amount.to_string();
-
-        let fee_type = params.fee_type().unwrap_or(spec::FeeType::OtherUnknown);
-        let pay_type = params.pay_type().unwrap_or(spec::PayType::Cash);
-
-        let mut req = Message::from_values(
-            spec::M_FEE_PAID.code,
-            &[
-                &util::sip_date_now(), // transaction date
-                fee_type.into(),
-                pay_type.into(),
-                "USD", // TODO
-            ],
-            &[
-                (spec::F_PATRON_ID.code, patron_id),
-                (spec::F_FEE_AMOUNT.code, &pay_amount),
-            ],
-        )?;
-
-        req.maybe_add_field(spec::F_INSTITUTION_ID.code, params.institution());
-        req.maybe_add_field(spec::F_TERMINAL_PWD.code, params.terminal_pwd());
-        req.maybe_add_field(spec::F_TRANSACTION_ID.code, params.transaction_id());
-        req.maybe_add_field(spec::F_FEE_IDENTIFIER.code, params.fee_id());
-
-        let resp = self.connection.sendrecv(&req)?;
-
-        if let Some(status) = resp.fixed_fields().first() {
-            if status.value() == "1" {
-                return Ok(SipResponse::new(resp, true));
-            }
+// Request builders and response checks are shared with the async
+// client so both send the same messages.
+
+pub(crate) fn login_request(params: &ParamSet) -> Result<Message, Error> {
+    let user = match params.sip_user() {
+        Some(u) => u,
+        _ => return Err(Error::MissingParamsError),
+    };
+
+    let pass = match params.sip_pass() {
+        Some(u) => u,
+        _ => return Err(Error::MissingParamsError),
+    };
+
+    let mut req = Message::new(
+        &spec::M_LOGIN,
+        vec![
+            FixedField::new(&spec::FF_UID_ALGO, "0").unwrap(),
+            FixedField::new(&spec::FF_PWD_ALGO, "0").unwrap(),
+        ],
+        vec![
+            Field::new(spec::F_LOGIN_UID.code, user),
+            Field::new(spec::F_LOGIN_PWD.code, pass),
+        ],
+    );
+
+    req.maybe_add_field(spec::F_LOCATION_CODE.code, params.location());
+
+    Ok(req)
+}
+
+pub(crate) fn login_response(resp: Message) -> SipResponse {
+    let ok = resp.spec().code == spec::M_LOGIN_RESP.code
+        && resp.fixed_fields().len() == 1
+        && resp.fixed_fields()[0].value() == "1";
+
+    SipResponse::new(resp, ok)
+}
+
+pub(crate) fn sc_status_request(version: spec::SipVersion) -> Message {
+    Message::new(
+        &spec::M_SC_STATUS,
+        vec![
+            FixedField::new(&spec::FF_STATUS_CODE, "0").unwrap(),
+            FixedField::new(&spec::FF_MAX_PRINT_WIDTH, "999").unwrap(),
+            FixedField::new(&spec::FF_PROTOCOL_VERSION, version.protocol_version()).unwrap(),
+        ],
+        vec![],
+    )
+}
+
+pub(crate) fn sc_status_response(resp: Message) -> SipResponse {
+    let ok = !resp.fixed_fields().is_empty() && resp.fixed_fields()[0].value() == "Y";
+    SipResponse::new(resp, ok)
+}
+
+pub(crate) fn patron_status_request(params: &ParamSet) -> Result<Message, Error> {
+    let patron_id = match params.patron_id() {
+        Some(p) => p,
+        _ => return Err(Error::MissingParamsError),
+    };
+
+    let mut req = Message::new(
+        &spec::M_PATRON_STATUS,
+        vec![
+            FixedField::new(&spec::FF_LANGUAGE, "000").unwrap(),
+            FixedField::new(&spec::FF_DATE, &util::sip_date_now()).unwrap(),
+        ],
+        vec![Field::new(spec::F_PATRON_ID.code, patron_id)],
+    );
+
+    req.maybe_add_field(spec::F_INSTITUTION_ID.code, params.institution());
+    req.maybe_add_field(spec::F_PATRON_PWD.code, params.patron_pwd());
+    req.maybe_add_field(spec::F_TERMINAL_PWD.code, params.terminal_pwd());
+
+    Ok(req)
+}
+
+pub(crate) fn patron_info_request(params: &ParamSet) -> Result<Message, Error> {
+    let patron_id = match params.patron_id() {
+        Some(p) => p,
+        None => return Err(Error::MissingParamsError),
+    };
+
+    let mut summary: [char; 10] = [' '; 10];
+
+    if let Some(idx) = params.summary() {
+        if idx < 10 {
+            summary[idx] = 'Y';
         }
+    }
+
+    let sum_str: String = summary.iter().collect::<String>();
 
-        Ok(SipResponse::new(resp, false))
+    let mut req = Message::new(
+        &spec::M_PATRON_INFO,
+        vec![
+            FixedField::new(&spec::FF_LANGUAGE, "000").unwrap(),
+            FixedField::new(&spec::FF_DATE, &util::sip_date_now()).unwrap(),
+            FixedField::new(&spec::FF_SUMMARY, &sum_str).unwrap(),
+        ],
+        vec![Field::new(spec::F_PATRON_ID.code, patron_id)],
+    );
+
+    req.maybe_add_field(spec::F_INSTITUTION_ID.code, params.institution());
+    req.maybe_add_field(spec::F_PATRON_PWD.code, params.patron_pwd());
+    req.maybe_add_field(spec::F_TERMINAL_PWD.code, params.terminal_pwd());
+
+    if let Some(v) = params.start_item() {
+        req.add_field(spec::F_START_ITEM.code, &v.to_string());
     }
+
+    if let Some(v) = params.end_item() {
+        req.add_field(spec::F_END_ITEM.code, &v.to_string());
+    }
+
+    Ok(req)
+}
+
+/// Sets ok=true if the "valid patron" (BL) field is "Y"
+pub(crate) fn valid_patron_response(resp: Message) -> SipResponse {
+    let ok = resp.get_field_value(spec::F_VALID_PATRON.code) == Some("Y");
+    SipResponse::new(resp, ok)
+}
+
+pub(crate) fn item_info_request(params: &ParamSet) -> Result<Message, Error> {
+    let item_id = match params.item_id() {
+        Some(id) => id,
+        None => return Err(Error::MissingParamsError),
+    };
+
+    let mut req = Message::new(
+        &spec::M_ITEM_INFO,
+        vec![FixedField::new(&spec::FF_DATE, &util::sip_date_now()).unwrap()],
+        vec![Field::new(spec::F_ITEM_IDENT.code, item_id)],
+    );
+
+    req.maybe_add_field(spec::F_INSTITUTION_ID.code, params.institution());
+    req.maybe_add_field(spec::F_TERMINAL_PWD.code, params.terminal_pwd());
+
+    Ok(req)
+}
+
+pub(crate) fn item_info_response(resp: Message) -> SipResponse {
+    let ok = resp
+        .get_field_value(spec::F_TITLE_IDENT.code)
+        .map(|v| !v.is_empty())
+        .unwrap_or(false);
+
+    SipResponse::new(resp, ok)
+}
+
+pub(crate) fn checkout_request(params: &ParamSet) -> Result<Message, Error> {
+    let item_id = params.item_id().ok_or(Error::MissingParamsError)?;
+    let patron_id = params.patron_id().ok_or(Error::MissingParamsError)?;
+
+    let mut req = Message::from_values(
+        spec::M_CHECKOUT.code,
+        &[
+            "N",                   // renewal policy
+            "N",                   // no block
+            &util::sip_date_now(), // transaction date
+            &util::sip_date_now(), // no block due date
+        ],
+        &[
+            (spec::F_ITEM_IDENT.code, item_id),
+            (spec::F_PATRON_IDENT.code, patron_id),
+        ],
+    )?;
+
+    req.maybe_add_field(spec::F_INSTITUTION_ID.code, params.institution());
+    req.maybe_add_field(spec::F_TERMINAL_PWD.code, params.terminal_pwd());
+    req.maybe_add_field(spec::F_PATRON_PWD.code, params.patron_pwd());
+
+    Ok(req)
+}
+
+pub(crate) fn checkin_request(params: &ParamSet) -> Result<Message, Error> {
+    let item_id = params.item_id().ok_or(Error::MissingParamsError)?;
+
+    let mut req = Message::from_values(
+        spec::M_CHECKIN.code,
+        &[
+            "N",                   // no block
+            &util::sip_date_now(), // transaction date
+            &util::sip_date_now(), // no block due date
+        ],
+        &[(spec::F_ITEM_IDENT.code, item_id)],
+    )?;
+
+    req.maybe_add_field(spec::F_INSTITUTION_ID.code, params.institution());
+    req.maybe_add_field(spec::F_TERMINAL_PWD.code, params.terminal_pwd());
+
+    Ok(req)
+}
+
+pub(crate) fn fee_paid_request(params: &ParamSet) -> Result<Message, Error> {
+    let patron_id = params.patron_id().ok_or(Error::MissingParamsError)?;
+    let pay_amount = params.pay_amount().ok_or(Error::MissingParamsError)?;
+
+    let pay_amount = pay_amount.to_string();
+
+    let fee_type = params.fee_type().unwrap_or(spec::FeeType::OtherUnknown);
+    let pay_type = params.pay_type().unwrap_or(spec::PayType::Cash);
+
+    let mut req = Message::from_values(
+        spec::M_FEE_PAID.code,
+        &[
+            &util::sip_date_now(), // transaction date
+            fee_type.into(),
+            pay_type.into(),
+            "USD", // TODO
+        ],
+        &[
+            (spec::F_PATRON_ID.code, patron_id),
+            (spec::F_FEE_AMOUNT.code, &pay_amount),
+        ],
+    )?;
+
+    req.maybe_add_field(spec::F_INSTITUTION_ID.code, params.institution());
+    req.maybe_add_field(spec::F_TERMINAL_PWD.code, params.terminal_pwd());
+    req.maybe_add_field(spec::F_TRANSACTION_ID.code, params.transaction_id());
+    req.maybe_add_field(spec::F_FEE_IDENTIFIER.code, params.fee_id());
+
+    Ok(req)
+}
+
+/// Sets ok=true if the first fixed field, e.g. the checkout "ok" flag,
+/// is "1".
+pub(crate) fn status_response(resp: Message) -> SipResponse {
+    let ok = resp
+        .fixed_fields()
+        .first()
+        .map(|f| f.value() == "1")
+        .unwrap_or(false);
+
+    SipResponse::new(resp, ok)
 }
 
 /// Wrapper for holding the SIP response message and a simplistic
@@ -1,16 +1,40 @@
+use super::diagnostic::Diagnostic;
+use super::encoding::Encoding;
 use super::error::Error;
 use super::spec;
-use super::Message;
+use super::spec::SipVersion;
+use super::util;
+use super::{Field, Message};
 use deunicode::deunicode;
 use std::fmt;
 use std::io::prelude::*;
-use std::net::{Shutdown, TcpStream};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
 use std::str;
 use std::time::Duration;
 
 // Read data from the socket in chunks this size.
 const READ_BUFSIZE: usize = 256;
 
+// Give up after requesting this many resends of a single message.
+pub(crate) const MAX_RESEND_REQUESTS: usize = 3;
+
+/// Network timeouts for a [`Connection`].
+///
+/// `None` values wait indefinitely.  Operations which exceed a timeout
+/// fail with [`Error::Timeout`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Timeouts {
+    /// Maximum time to wait while opening the TCP connection.
+    pub connect: Option<Duration>,
+
+    /// Maximum time to wait for each read from the socket, including
+    /// reads in the middle of a partially received response.
+    pub read: Option<Duration>,
+
+    /// Maximum time to wait for each write to the socket.
+    pub write: Option<Duration>,
+}
+
 /// Manages a TCP connection to a SIP server and handles message sending
 /// and receiving.
 pub struct Connection {
@@ -20,6 +44,21 @@ pub struct Connection {
     ascii: bool,
 
     log_prefix: Option<String>,
+
+    // If set, inbound messages are parsed with Message::from_sip_lenient.
+    lenient: bool,
+
+    // Diagnostics from the most recently received message.
+    diagnostics: Vec<Diagnostic>,
+
+    // Set when the error detection extension is enabled.
+    error_detection: Option<ErrorDetection>,
+
+    encoding: Encoding,
+
+    version: SipVersion,
+
+    timeouts: Timeouts,
 }
 
 impl fmt::Display for Connection {
@@ -43,19 +82,51 @@ impl Connection {
     /// assert_eq!(Connection::new("JUNK0+..-*z$@").is_err(), true);
     /// ```
     pub fn new(sip_host: &str) -> Result<Self, Error> {
+        Connection::with_timeouts(sip_host, Timeouts::default())
+    }
+
+    /// Creates a new SIP client with the provided network timeouts.
+    ///
+    /// ```
+    /// use sip2::{Connection, Error, Timeouts};
+    /// use std::net::TcpListener;
+    /// use std::time::Duration;
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let addr = listener.local_addr().unwrap().to_string();
+    ///
+    /// let timeouts = Timeouts {
+    ///     connect: Some(Duration::from_secs(5)),
+    ///     read: Some(Duration::from_millis(50)),
+    ///     write: None,
+    /// };
+    ///
+    /// let mut con = Connection::with_timeouts(&addr, timeouts).unwrap();
+    /// assert_eq!(con.timeouts(), timeouts);
+    ///
+    /// // The listener never responds.
+    /// assert!(matches!(con.recv(), Err(Error::Timeout)));
+    /// ```
+    pub fn with_timeouts(sip_host: &str, timeouts: Timeouts) -> Result<Self, Error> {
         log::debug!("Connection::new() connecting to: {}", sip_host);
 
-        match TcpStream::connect(sip_host) {
-            Ok(stream) => Ok(Connection {
-                tcp_stream: stream,
-                ascii: false,
-                log_prefix: None,
-            }),
-            Err(s) => {
-                log::error!("Connection::new() failed: {s}");
-                Err(Error::NetworkError(s.to_string()))
+        let stream = match timeouts.connect {
+            Some(timeout) => connect_with_timeout(sip_host, timeout),
+            None => TcpStream::connect(sip_host).map_err(|e| Error::NetworkError(e.to_string())),
+        };
+
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("Connection::new() failed: {e}");
+                return Err(e);
             }
-        }
+        };
+
+        let mut con = Connection::from_stream(stream);
+        con.set_timeouts(timeouts)?;
+
+        Ok(con)
     }
 
     /// Create a new SIP connection from an existing TCP stream.
@@ -64,9 +135,29 @@ impl Connection {
             ascii: false,
             tcp_stream,
             log_prefix: None,
+            lenient: false,
+            diagnostics: Vec::new(),
+            error_detection: None,
+            encoding: Encoding::default(),
+            version: SipVersion::default(),
+            timeouts: Timeouts::default(),
         }
     }
 
+    /// Apply read and write timeouts to the TCP socket.
+    ///
+    /// The connect timeout only applies to new connections.
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) -> Result<(), Error> {
+        self.apply_read_timeout(timeouts.read)?;
+        self.apply_write_timeout(timeouts.write)?;
+        self.timeouts = timeouts;
+        Ok(())
+    }
+
+    pub fn timeouts(&self) -> Timeouts {
+        self.timeouts
+    }
+
     /// Add a string that will be prepended to all log:: calls where
     /// a self exists.
     pub fn set_log_prefix(&mut self, prefix: impl Into<String>) {
@@ -78,6 +169,64 @@ impl Connection {
         self.ascii = ascii;
     }
 
+    /// Set the lenient flag.
+    ///
+    /// When set, malformed inbound messages are recovered where
+    /// possible instead of rejected.  See [`Connection::diagnostics`].
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Enable the SIP error detection extension.
+    ///
+    /// Outbound messages are sent with sequence numbers and checksums.
+    /// When a received message has an invalid checksum, a resend is
+    /// requested (Request ACS Resend), and when the server requests a
+    /// resend (Request SC Resend), the last message is sent again.
+    pub fn set_error_detection(&mut self, enabled: bool) {
+        if !enabled {
+            self.error_detection = None;
+        } else if self.error_detection.is_none() {
+            self.error_detection = Some(ErrorDetection::default());
+        }
+    }
+
+    /// True if the error detection extension is enabled.
+    pub fn error_detection(&self) -> bool {
+        self.error_detection.is_some()
+    }
+
+    /// Character encoding used on the wire.
+    ///
+    /// Defaults to UTF-8.  Checksums for the error detection extension
+    /// are computed over the encoded bytes.
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    /// Character encoding used on the wire.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// SIP protocol version spoken with the server.
+    ///
+    /// Defaults to 2.00.
+    pub fn set_version(&mut self, version: SipVersion) {
+        self.version = version;
+    }
+
+    pub fn version(&self) -> SipVersion {
+        self.version
+    }
+
+    /// Problems found while parsing the most recently received message.
+    ///
+    /// Always empty unless the lenient flag is set.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
     /// Shutdown the TCP connection with the SIP server.
     pub fn disconnect(&self) -> Result<(), Error> {
         log::debug!("{self}Connection::disconnect()");
@@ -94,21 +243,30 @@ impl Connection {
 
     /// Send a SIP message
     pub fn send(&mut self, msg: &Message) -> Result<(), Error> {
-        let mut msg_sip = msg.to_sip();
+        check_version(msg, self.version)?;
 
-        if self.ascii {
-            // https://crates.io/crates/deunicode
-            // "Some transliterations do produce \n characters."
-            msg_sip = deunicode(&msg_sip).replace('\n', "");
-        }
+        let msg_sip = match self.error_detection.as_mut() {
+            Some(ed) => ed.outbound_sip(msg, self.ascii),
+            None => outbound_sip(msg, self.ascii),
+        };
 
+        self.send_text(msg_sip)
+    }
+
+    /// Send SIP text, adding the line terminator.
+    fn send_text(&mut self, msg_sip: String) -> Result<(), Error> {
         // No need to redact here since SIP replies do not include passwords.
         log::info!("{self}OUTBOUND: {}", msg_sip);
 
-        msg_sip.push(spec::LINE_TERMINATOR);
+        let mut bytes = self.encoding.encode_sip(&msg_sip);
+        bytes.push(spec::LINE_TERMINATOR as u8);
 
-        match self.tcp_stream.write_all(msg_sip.as_bytes()) {
+        match self.tcp_stream.write_all(&bytes) {
             Ok(_) => Ok(()),
+            Err(s) if is_timeout(&s) => {
+                log::error!("{self}send() timed out");
+                Err(Error::Timeout)
+            }
             Err(s) => {
                 log::error!("{self}send() failed: {}", s);
                 Err(Error::NetworkError(s.to_string()))
@@ -118,70 +276,115 @@ impl Connection {
 
     /// Send a message with a write timeout.
     ///
-    /// Returns Err() if the send/write times out.  Clears the TCP socket
-    /// timeout upon completion.
+    /// Returns Err() if the send/write times out.  Restores the
+    /// connection's write timeout upon completion.
     pub fn send_with_timeout(&mut self, msg: &Message, timeout: u64) -> Result<(), Error> {
-        let time = Some(Duration::from_secs(timeout));
-
-        if let Err(e) = self.tcp_stream.set_write_timeout(time) {
-            log::error!("{self}Cannot set TCP write timeout: timeout={timeout} {e}");
-            return Err(Error::NetworkError(e.to_string()));
-        }
+        self.apply_write_timeout(Some(Duration::from_secs(timeout)))?;
 
         let result = self.send(msg);
 
-        // Clear the timeout
-        if let Err(e) = self.tcp_stream.set_write_timeout(None) {
-            log::error!("{self}Cannot set TCP write timeout: {e}");
-            return Err(Error::NetworkError(e.to_string()));
-        }
+        self.apply_write_timeout(self.timeouts.write)?;
 
         result
     }
 
     /// Receive a SIP response.
     ///
-    /// Blocks until a response is received.
+    /// Blocks until a response is received or the read timeout expires.
     pub fn recv(&mut self) -> Result<Message, Error> {
-        self.recv_internal()?
-            .ok_or_else(|| Error::NetworkError("Receive timed out on vanilla recv()?".to_string()))
+        self.recv_internal()?.ok_or(Error::Timeout)
     }
 
-    /// Receive a message, waiting at most `timeout` seconds.  Clears the
-    /// TCP socket timeout upon completion.
+    /// Receive a message, waiting at most `timeout` seconds.  Restores
+    /// the connection's read timeout upon completion.
     pub fn recv_with_timeout(&mut self, timeout: u64) -> Result<Option<Message>, Error> {
-        let time = Some(Duration::from_secs(timeout));
-
-        if let Err(e) = self.tcp_stream.set_read_timeout(time) {
-            log::error!("{self}Cannot set TCP read timeout: timeout={timeout} {e}");
-            return Err(Error::NetworkError(e.to_string()));
-        }
+        self.apply_read_timeout(Some(Duration::from_secs(timeout)))?;
 
         let result = self.recv_internal();
 
-        // Clear the timeout
-        if let Err(e) = self.tcp_stream.set_read_timeout(None) {
-            log::error!("{self}Cannot set TCP read timeout: {e}");
-            return Err(Error::NetworkError(e.to_string()));
-        }
+        self.apply_read_timeout(self.timeouts.read)?;
 
         result
     }
 
-    /// Do the actual receiving from the socket.
+    fn apply_read_timeout(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        self.tcp_stream.set_read_timeout(timeout).map_err(|e| {
+            log::error!("{self}Cannot set TCP read timeout: timeout={timeout:?} {e}");
+            Error::NetworkError(e.to_string())
+        })
+    }
+
+    fn apply_write_timeout(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        self.tcp_stream.set_write_timeout(timeout).map_err(|e| {
+            log::error!("{self}Cannot set TCP write timeout: timeout={timeout:?} {e}");
+            Error::NetworkError(e.to_string())
+        })
+    }
+
+    /// Receive a message, handling resend requests if the error
+    /// detection extension is enabled.
     fn recv_internal(&mut self) -> Result<Option<Message>, Error> {
-        let mut text = String::from("");
+        self.diagnostics.clear();
+
+        let mut resends = 0;
+
+        loop {
+            let text = match self.recv_text()? {
+                Some(t) => t,
+                None => return Ok(None),
+            };
+
+            let inbound = match self.error_detection.as_ref() {
+                Some(ed) => ed.check_inbound(&text),
+                None => Inbound::Accept,
+            };
+
+            if inbound != Inbound::Accept && resends == MAX_RESEND_REQUESTS {
+                log::error!("{self}Giving up after {resends} resends: {text}");
+                return Err(Error::ChecksumError);
+            }
+
+            match inbound {
+                Inbound::Accept => {
+                    let (msg, diagnostics) = parse_inbound(&text, self.lenient, self.version)?;
+                    self.diagnostics = diagnostics;
+
+                    log::info!("{self}INBOUND: {}", msg.to_sip_redacted());
+
+                    if let Some(ed) = self.error_detection.as_ref() {
+                        ed.check_sequence(&msg);
+                    }
+
+                    return Ok(Some(msg));
+                }
+                Inbound::RequestResend => {
+                    log::warn!("{self}Invalid checksum.  Requesting resend: {text}");
+                    self.send_text(resend_request())?;
+                }
+                Inbound::Resend(last_sent) => {
+                    log::info!("{self}Server requested a resend");
+                    self.send_text(last_sent)?;
+                }
+            }
+
+            resends += 1;
+        }
+    }
+
+    /// Read one message worth of text from the socket.
+    fn recv_text(&mut self) -> Result<Option<String>, Error> {
+        let mut bytes: Vec<u8> = Vec::new();
 
         loop {
             let mut buf: [u8; READ_BUFSIZE] = [0; READ_BUFSIZE];
 
             let num_bytes = match self.tcp_stream.read(&mut buf) {
                 Ok(num) => num,
+                Err(e) if is_timeout(&e) => {
+                    log::trace!("{self}SIP tcp read timed out.  Returning None");
+                    return Ok(None);
+                }
                 Err(e) => match e.kind() {
-                    std::io::ErrorKind::WouldBlock => {
-                        log::trace!("{self}SIP tcp read timed out.  Returning None");
-                        return Ok(None);
-                    }
                     std::io::ErrorKind::ConnectionReset => {
                         log::info!("{self}remote disconnected in recv()");
                         return Err(Error::NetworkError(e.to_string()));
@@ -197,41 +400,22 @@ impl Connection {
                 break;
             }
 
-            let chunk = match str::from_utf8(&buf) {
-                Ok(s) => s,
-                Err(s) => {
-                    log::error!("{self}recv() got non-utf data: {}", s);
-                    return Err(Error::MessageFormatError);
-                }
-            };
-
-            text += chunk;
+            bytes.extend_from_slice(&buf[..num_bytes]);
 
-            if text.contains(spec::LINE_TERMINATOR) {
+            if bytes.contains(&(spec::LINE_TERMINATOR as u8)) {
                 // We've read a whole message.
                 break;
             }
         }
 
-        if text.is_empty() {
+        if bytes.is_empty() {
             // Receiving no content here indicates either an error
             // or the client simply disconnected.
             log::debug!("{self}Reading TCP stream returned 0 bytes");
             return Err(Error::NoResponseError);
         }
 
-        // SIP requests should always arrive one at a time.  Discard the
-        // line/message terminator and any data that exists beyond it.
-        let mut parts = text.split(spec::LINE_TERMINATOR);
-
-        match parts.next() {
-            Some(s) => {
-                let msg = Message::from_sip(s)?;
-                log::info!("{self}INBOUND: {}", msg.to_sip_redacted());
-                Ok(Some(msg))
-            }
-            None => Err(Error::MessageFormatError),
-        }
+        self.encoding.decode_sip(&bytes).map(Some)
     }
 
     /// Shortcut for:  self.send(msg); resp = self.recv();
@@ -240,3 +424,177 @@ impl Connection {
         self.recv()
     }
 }
+
+/// Open a TCP connection, trying each address `sip_host` resolves to
+/// for at most `timeout`.
+fn connect_with_timeout(sip_host: &str, timeout: Duration) -> Result<TcpStream, Error> {
+    let addrs = sip_host
+        .to_socket_addrs()
+        .map_err(|e| Error::NetworkError(e.to_string()))?;
+
+    let mut err = Error::NetworkError(format!("No addresses found for {sip_host}"));
+
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) if is_timeout(&e) => err = Error::Timeout,
+            Err(e) => err = Error::NetworkError(e.to_string()),
+        }
+    }
+
+    Err(err)
+}
+
+/// True if the I/O error is the result of a socket timeout.
+///
+/// Depending on the platform, socket timeouts are reported as either
+/// WouldBlock or TimedOut.
+fn is_timeout(err: &std::io::Error) -> bool {
+    matches!(
+        err.kind(),
+        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+    )
+}
+
+/// SIP text for an outbound message, without the line terminator.
+///
+/// If `ascii` is set, non-ASCII characters are transliterated.
+pub(crate) fn outbound_sip(msg: &Message, ascii: bool) -> String {
+    let msg_sip = msg.to_sip();
+
+    if !ascii {
+        return msg_sip;
+    }
+
+    // https://crates.io/crates/deunicode
+    // "Some transliterations do produce \n characters."
+    let mut ascii_sip = deunicode(&msg_sip).replace('\n', "");
+
+    // Transliteration invalidates the checksum.
+    if util::verify_checksum(&ascii_sip).is_some() {
+        ascii_sip.truncate(ascii_sip.len() - 4);
+        let checksum = util::checksum(&ascii_sip);
+        ascii_sip.push_str(&checksum);
+    }
+
+    ascii_sip
+}
+
+/// Returns an error if the message was introduced by a protocol
+/// version newer than the one the connection uses.
+pub(crate) fn check_version(msg: &Message, version: SipVersion) -> Result<(), Error> {
+    let msg_version = msg.spec().version();
+
+    if msg_version > version {
+        log::error!(
+            "{} {} requires SIP {} but the connection uses SIP {}",
+            msg.spec().code,
+            msg.spec().label,
+            msg_version.protocol_version(),
+            version.protocol_version()
+        );
+        return Err(Error::UnknownMessageError);
+    }
+
+    Ok(())
+}
+
+/// Parse received text as a message.
+///
+/// Diagnostics are only collected when `lenient` is set.  Messages
+/// from a newer protocol version than `version` are rejected.
+pub(crate) fn parse_inbound(
+    text: &str,
+    lenient: bool,
+    version: SipVersion,
+) -> Result<(Message, Vec<Diagnostic>), Error> {
+    // SIP requests should always arrive one at a time.  Discard the
+    // line/message terminator and any data that exists beyond it.
+    let s = text
+        .split(spec::LINE_TERMINATOR)
+        .next()
+        .ok_or(Error::MessageFormatError)?;
+
+    let parsed = if lenient {
+        Message::from_sip_lenient(s)?
+    } else {
+        (Message::from_sip(s)?, Vec::new())
+    };
+
+    check_version(&parsed.0, version)?;
+
+    Ok(parsed)
+}
+
+/// What to do with a message received while error detection is enabled.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Inbound {
+    Accept,
+    /// The checksum is invalid.  Ask the server to send it again.
+    RequestResend,
+    /// The server asked for a resend.  Send it this text.
+    Resend(String),
+}
+
+/// SC-side state for the SIP error detection extension.
+#[derive(Default)]
+pub(crate) struct ErrorDetection {
+    /// Sequence number of the most recently sent message.
+    sequence: Option<u8>,
+
+    /// Most recently sent message text.
+    last_sent: Option<String>,
+}
+
+impl ErrorDetection {
+    /// SIP text for an outbound message with the next sequence number
+    /// and a checksum applied.
+    pub(crate) fn outbound_sip(&mut self, msg: &Message, ascii: bool) -> String {
+        let seq = self.sequence.map(|s| (s + 1) % 10).unwrap_or(0);
+
+        let mut msg = msg.clone();
+        msg.set_sequence_number(seq);
+
+        let msg_sip = outbound_sip(&msg, ascii);
+
+        self.sequence = Some(seq);
+        self.last_sent = Some(msg_sip.clone());
+
+        msg_sip
+    }
+
+    pub(crate) fn check_inbound(&self, text: &str) -> Inbound {
+        let text = text.split(spec::LINE_TERMINATOR).next().unwrap_or("");
+
+        if text.starts_with(spec::M_REQUEST_SC_RESEND.code) {
+            if let Some(last_sent) = self.last_sent.as_ref() {
+                return Inbound::Resend(last_sent.clone());
+            }
+        } else if util::verify_checksum(text) == Some(false) {
+            return Inbound::RequestResend;
+        }
+
+        Inbound::Accept
+    }
+
+    /// Responses should carry the sequence number of their request.
+    pub(crate) fn check_sequence(&self, msg: &Message) {
+        if let (Some(sent), Some(received)) = (self.sequence, msg.sequence_number()) {
+            if sent != received {
+                log::warn!("Response sequence number {received} does not match request {sent}");
+            }
+        }
+    }
+}
+
+/// Request ACS Resend message text.
+///
+/// This message carries a checksum but never a sequence number.
+pub(crate) fn resend_request() -> String {
+    Message::new(
+        &spec::M_REQUEST_ACS_RESEND,
+        vec![],
+        vec![Field::new(spec::F_CHECKSUM.code, "")],
+    )
+    .to_sip()
+}
@@ -0,0 +1,62 @@
+//! Problems found while leniently parsing a SIP message.
+use std::fmt;
+
+/// A problem found by [`Message::from_sip_lenient`](crate::Message::from_sip_lenient).
+///
+/// Each diagnostic describes something a strict parser would have
+/// rejected (or silently dropped) and how it was recovered.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    /// A fixed field was shorter than its spec length, or missing
+    /// entirely, and was padded with spaces.
+    FixedFieldLength {
+        label: &'static str,
+        expected: usize,
+        found: usize,
+    },
+
+    /// A field required by the specification was not present.
+    MissingField(&'static str),
+
+    /// Text between field delimiters was too short to contain a field
+    /// code and was discarded.
+    MalformedField(String),
+
+    /// The message checksum (AZ) does not match the message content.
+    InvalidChecksum,
+}
+
+impl Diagnostic {
+    /// Short name for the kind of diagnostic, useful for counters.
+    ///
+    /// ```
+    /// use sip2::Diagnostic;
+    /// assert_eq!(Diagnostic::MissingField("AO").kind(), "missing-field");
+    /// ```
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::FixedFieldLength { .. } => "fixed-field-length",
+            Self::MissingField(_) => "missing-field",
+            Self::MalformedField(_) => "malformed-field",
+            Self::InvalidChecksum => "invalid-checksum",
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FixedFieldLength {
+                label,
+                expected,
+                found,
+            } => write!(
+                f,
+                "fixed field '{label}' has length {found}; expected {expected}"
+            ),
+            Self::MissingField(code) => write!(f, "required field {code} is missing"),
+            Self::MalformedField(text) => write!(f, "malformed field '{text}'"),
+            Self::InvalidChecksum => write!(f, "checksum does not match message content"),
+        }
+    }
+}
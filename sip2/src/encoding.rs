@@ -0,0 +1,148 @@
+//! Character encodings for SIP messages on the wire.
+use super::error::Error;
+use super::spec;
+use super::util;
+
+/// Character encoding used to send and receive SIP messages.
+///
+/// Messages are handled as UTF-8 text internally.  Text is encoded
+/// when sent and decoded when received.  Characters which cannot be
+/// represented in the encoding are sent as "?".
+///
+/// ```
+/// use sip2::Encoding;
+///
+/// let bytes = Encoding::Latin1.encode("Café");
+/// assert_eq!(bytes, b"Caf\xe9");
+/// assert_eq!(Encoding::Latin1.decode(&bytes).unwrap(), "Café");
+///
+/// let bytes = Encoding::Cp850.encode("Café");
+/// assert_eq!(bytes, b"Caf\x82");
+/// assert_eq!(Encoding::Cp850.decode(&bytes).unwrap(), "Café");
+///
+/// assert!(Encoding::Utf8.decode(b"Caf\xe9").is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// ISO-8859-1
+    Latin1,
+    /// IBM code page 850 (DOS Latin-1)
+    Cp850,
+}
+
+impl Encoding {
+    /// Decode received bytes.
+    ///
+    /// Only UTF-8 can fail, since every byte has a meaning in the
+    /// single-byte encodings.
+    pub fn decode(&self, bytes: &[u8]) -> Result<String, Error> {
+        match self {
+            Encoding::Utf8 => String::from_utf8(bytes.to_vec()).map_err(|e| {
+                log::error!("Received invalid UTF-8 data: {e}");
+                Error::MessageFormatError
+            }),
+            Encoding::Latin1 => Ok(bytes.iter().map(|b| *b as char).collect()),
+            Encoding::Cp850 => Ok(bytes.iter().map(|b| cp850_char(*b)).collect()),
+        }
+    }
+
+    /// Encode text for sending.
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        match self {
+            Encoding::Utf8 => text.as_bytes().to_vec(),
+            Encoding::Latin1 => text
+                .chars()
+                .map(|c| u8::try_from(c).unwrap_or(b'?'))
+                .collect(),
+            Encoding::Cp850 => text.chars().map(cp850_byte).collect(),
+        }
+    }
+
+    /// Encode a SIP message for sending.
+    ///
+    /// Checksums (error detection extension) are computed over the
+    /// bytes sent, so any checksum is recomputed for the encoded bytes.
+    pub(crate) fn encode_sip(&self, text: &str) -> Vec<u8> {
+        let mut bytes = self.encode(text);
+
+        if *self != Encoding::Utf8 && util::verify_checksum(text).is_some() {
+            bytes.truncate(bytes.len() - 4);
+            let checksum = util::checksum_bytes(&bytes);
+            bytes.extend_from_slice(checksum.as_bytes());
+        }
+
+        bytes
+    }
+
+    /// Decode a received SIP message.
+    ///
+    /// A checksum which is valid for the bytes received is replaced
+    /// with the checksum of the decoded text, so the text may be
+    /// verified like any other message.
+    pub(crate) fn decode_sip(&self, bytes: &[u8]) -> Result<String, Error> {
+        if *self == Encoding::Utf8 {
+            return self.decode(bytes);
+        }
+
+        let end = bytes
+            .iter()
+            .position(|b| *b == spec::LINE_TERMINATOR as u8)
+            .unwrap_or(bytes.len());
+
+        let (msg_bytes, rest) = bytes.split_at(end);
+
+        let mut text = self.decode(msg_bytes)?;
+
+        if util::verify_checksum_bytes(msg_bytes) == Some(true) {
+            // The checksum itself is ASCII.
+            text.truncate(text.len() - 4);
+            let checksum = util::checksum(&text);
+            text.push_str(&checksum);
+        }
+
+        text.push_str(&self.decode(rest)?);
+
+        Ok(text)
+    }
+}
+
+/// Characters for CP850 bytes 0x80 - 0xFF.  Lower bytes match ASCII.
+const CP850_HIGH: [char; 128] = [
+    '\u{00C7}', '\u{00FC}', '\u{00E9}', '\u{00E2}', '\u{00E4}', '\u{00E0}', '\u{00E5}', '\u{00E7}',
+    '\u{00EA}', '\u{00EB}', '\u{00E8}', '\u{00EF}', '\u{00EE}', '\u{00EC}', '\u{00C4}', '\u{00C5}',
+    '\u{00C9}', '\u{00E6}', '\u{00C6}', '\u{00F4}', '\u{00F6}', '\u{00F2}', '\u{00FB}', '\u{00F9}',
+    '\u{00FF}', '\u{00D6}', '\u{00DC}', '\u{00F8}', '\u{00A3}', '\u{00D8}', '\u{00D7}', '\u{0192}',
+    '\u{00E1}', '\u{00ED}', '\u{00F3}', '\u{00FA}', '\u{00F1}', '\u{00D1}', '\u{00AA}', '\u{00BA}',
+    '\u{00BF}', '\u{00AE}', '\u{00AC}', '\u{00BD}', '\u{00BC}', '\u{00A1}', '\u{00AB}', '\u{00BB}',
+    '\u{2591}', '\u{2592}', '\u{2593}', '\u{2502}', '\u{2524}', '\u{00C1}', '\u{00C2}', '\u{00C0}',
+    '\u{00A9}', '\u{2563}', '\u{2551}', '\u{2557}', '\u{255D}', '\u{00A2}', '\u{00A5}', '\u{2510}',
+    '\u{2514}', '\u{2534}', '\u{252C}', '\u{251C}', '\u{2500}', '\u{253C}', '\u{00E3}', '\u{00C3}',
+    '\u{255A}', '\u{2554}', '\u{2569}', '\u{2566}', '\u{2560}', '\u{2550}', '\u{256C}', '\u{00A4}',
+    '\u{00F0}', '\u{00D0}', '\u{00CA}', '\u{00CB}', '\u{00C8}', '\u{0131}', '\u{00CD}', '\u{00CE}',
+    '\u{00CF}', '\u{2518}', '\u{250C}', '\u{2588}', '\u{2584}', '\u{00A6}', '\u{00CC}', '\u{2580}',
+    '\u{00D3}', '\u{00DF}', '\u{00D4}', '\u{00D2}', '\u{00F5}', '\u{00D5}', '\u{00B5}', '\u{00FE}',
+    '\u{00DE}', '\u{00DA}', '\u{00DB}', '\u{00D9}', '\u{00FD}', '\u{00DD}', '\u{00AF}', '\u{00B4}',
+    '\u{00AD}', '\u{00B1}', '\u{2017}', '\u{00BE}', '\u{00B6}', '\u{00A7}', '\u{00F7}', '\u{00B8}',
+    '\u{00B0}', '\u{00A8}', '\u{00B7}', '\u{00B9}', '\u{00B3}', '\u{00B2}', '\u{25A0}', '\u{00A0}',
+];
+
+fn cp850_char(byte: u8) -> char {
+    match byte {
+        0..=0x7F => byte as char,
+        _ => CP850_HIGH[(byte - 0x80) as usize],
+    }
+}
+
+fn cp850_byte(c: char) -> u8 {
+    if c.is_ascii() {
+        return c as u8;
+    }
+
+    CP850_HIGH
+        .iter()
+        .position(|h| *h == c)
+        .map(|pos| pos as u8 + 0x80)
+        .unwrap_or(b'?')
+}
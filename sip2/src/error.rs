@@ -11,6 +11,8 @@ pub enum Error {
     NetworkError(String),
     NoResponseError,
     MissingParamsError,
+    ChecksumError,
+    Timeout,
 }
 
 use self::Error::*;
@@ -31,6 +33,8 @@ impl fmt::Display for Error {
             UnknownMessageError => write!(f, "unknown sip message type"),
             NoResponseError => write!(f, "no message was received"),
             MissingParamsError => write!(f, "missing needed parameter values"),
+            ChecksumError => write!(f, "message checksum error"),
+            Timeout => write!(f, "network operation timed out"),
         }
     }
 }
@@ -1,25 +1,51 @@
 #![forbid(unsafe_code)]
 
 pub use self::connection::Connection;
+pub use self::connection::Timeouts;
+pub use self::diagnostic::Diagnostic;
+pub use self::encoding::Encoding;
 pub use self::error::Error;
 pub use self::message::Field;
 pub use self::message::FixedField;
 pub use self::message::Message;
+pub use self::validation::ValidationError;
 
 pub use self::client::Client;
+pub use self::client::ReconnectPolicy;
 pub use self::params::ParamSet;
+pub use self::pool::ClientPool;
+pub use self::pool::PoolOptions;
+pub use self::pool::PooledClient;
+
+#[cfg(feature = "async")]
+pub use self::async_client::AsyncClient;
+#[cfg(feature = "async")]
+pub use self::async_connection::AsyncConnection;
 
 pub mod spec;
+pub mod typed;
 pub mod util;
 
 mod client;
 mod connection;
+mod diagnostic;
+mod encoding;
 mod error;
 mod message;
 mod params;
+mod pool;
+mod validation;
+
+#[cfg(feature = "async")]
+mod async_client;
+#[cfg(feature = "async")]
+mod async_connection;
 
 #[cfg(feature = "json")]
 mod message_json;
 
+#[cfg(feature = "serde")]
+mod serde_support;
+
 #[cfg(test)]
 mod tests;
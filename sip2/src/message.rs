@@ -1,6 +1,8 @@
+use super::diagnostic::Diagnostic;
 use super::error::Error;
 use super::spec;
 use super::util;
+use super::validation::ValidationError;
 use log::{error, warn};
 use std::fmt;
 
@@ -10,7 +12,7 @@ const PASSWORD_REDACTED: &str = "REDACTED";
 ///
 /// Since fixed fields have specific length requirements, a well-known
 /// spec::FixedField is required
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct FixedField {
     spec: &'static spec::FixedField,
     value: String,
@@ -92,7 +94,7 @@ impl FixedField {
 ///
 /// To support passing field types that are not known at compile time,
 /// store the message code instead of a ref to a well-known spec::Field.
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct Field {
     /// 2-character code
     // Note we could link to the static spec::Field here, like
@@ -147,7 +149,7 @@ impl Field {
 }
 
 /// SIP message complete with message code, fixed fields, and fields.
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct Message {
     /// Link to the specification for this message type
     spec: &'static spec::Message,
@@ -289,6 +291,35 @@ impl Message {
         }
     }
 
+    /// Sequence number (AY) used by the error detection extension.
+    pub fn sequence_number(&self) -> Option<u8> {
+        self.get_field_value(spec::F_SEQUENCE_NUMBER.code)
+            .and_then(|v| v.parse().ok())
+    }
+
+    /// Apply a sequence number, 0-9, and a checksum to the message.
+    ///
+    /// The checksum is computed when the message is converted to SIP
+    /// text.
+    ///
+    /// ```
+    /// use sip2::Message;
+    ///
+    /// let mut msg = Message::from_values("93", &["0", "0"], &[("CN", "user"), ("CO", "pass")])
+    ///     .unwrap();
+    ///
+    /// msg.set_sequence_number(1);
+    ///
+    /// assert_eq!(msg.sequence_number(), Some(1));
+    /// assert_eq!(msg.to_sip(), "9300CNuser|COpass|AY1AZF83D");
+    /// ```
+    pub fn set_sequence_number(&mut self, seq: u8) {
+        self.remove_field(spec::F_SEQUENCE_NUMBER.code, true);
+        self.remove_field(spec::F_CHECKSUM.code, true);
+        self.add_field(spec::F_SEQUENCE_NUMBER.code, &(seq % 10).to_string());
+        self.add_field(spec::F_CHECKSUM.code, "");
+    }
+
     /// Returns our message spec.
     pub fn spec(&self) -> &'static spec::Message {
         self.spec
@@ -341,10 +372,12 @@ impl Message {
             s.push_str(&ff.to_sip());
         }
 
-        for f in self.fields.iter() {
+        for f in self.fields.iter().filter(|f| !is_error_detection(f)) {
             s.push_str(&f.to_sip());
         }
 
+        self.append_error_detection(&mut s);
+
         s
     }
 
@@ -359,7 +392,7 @@ impl Message {
             s.push_str(&ff.to_sip());
         }
 
-        for f in self.fields.iter() {
+        for f in self.fields.iter().filter(|f| !is_error_detection(f)) {
             if f.code() == spec::F_PATRON_PWD.code {
                 s += f.code();
                 s += PASSWORD_REDACTED;
@@ -369,9 +402,117 @@ impl Message {
             }
         }
 
+        self.append_error_detection(&mut s);
+
         s
     }
 
+    /// Append the sequence number and checksum fields, if the message
+    /// has them.
+    ///
+    /// These always end the message and are not "|"-terminated.
+    fn append_error_detection(&self, s: &mut String) {
+        let seq = self.get_field_value(spec::F_SEQUENCE_NUMBER.code);
+
+        if let Some(seq) = seq {
+            s.push_str(spec::F_SEQUENCE_NUMBER.code);
+            s.push_str(seq);
+        }
+
+        if seq.is_some() || self.get_field_value(spec::F_CHECKSUM.code).is_some() {
+            s.push_str(spec::F_CHECKSUM.code);
+            let checksum = util::checksum(s);
+            s.push_str(&checksum);
+        }
+    }
+
+    /// Check the message against the SIP2 specification.
+    ///
+    /// Verifies the message has the fixed fields its spec defines, in
+    /// order, contains the required fields (see
+    /// [`spec::Message::required_fields`]), and repeats only repeatable
+    /// fields (see [`spec::Field::repeatable`]).  Fields unknown to
+    /// the spec may repeat.
+    ///
+    /// Returns every problem found.
+    ///
+    /// ```
+    /// use sip2::{Message, ValidationError};
+    ///
+    /// let msg = Message::from_sip("09N20240101    12000020240101    120000AOexample|ABitem1|")
+    ///     .unwrap();
+    /// assert!(msg.validate().is_ok());
+    ///
+    /// let msg = Message::from_sip("09N20240101    12000020240101    120000AOexample|AOother|")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     msg.validate(),
+    ///     Err(vec![
+    ///         ValidationError::MissingField("AB"),
+    ///         ValidationError::RepeatedField("AO"),
+    ///     ])
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        if self.fixed_fields.len() != self.spec.fixed_fields.len() {
+            errors.push(ValidationError::FixedFieldCount {
+                expected: self.spec.fixed_fields.len(),
+                found: self.fixed_fields.len(),
+            });
+        }
+
+        for (position, (ff, ff_spec)) in self
+            .fixed_fields
+            .iter()
+            .zip(self.spec.fixed_fields.iter())
+            .enumerate()
+        {
+            if ff.spec != *ff_spec {
+                errors.push(ValidationError::UnexpectedFixedField {
+                    position,
+                    expected: ff_spec.label,
+                    found: ff.spec.label,
+                });
+            }
+        }
+
+        for field in self.spec.required_fields() {
+            if self.get_field_value(field.code).is_none() {
+                errors.push(ValidationError::MissingField(field.code));
+            }
+        }
+
+        for (idx, field) in self.fields.iter().enumerate() {
+            if field.code().len() != 2 {
+                errors.push(ValidationError::InvalidFieldCode(field.code().to_string()));
+                continue;
+            }
+
+            let Some(field_spec) = spec::Field::from_code(field.code()) else {
+                continue;
+            };
+
+            // Report each repeated field once.
+            let first = self.fields.iter().position(|f| f.code() == field.code());
+            let repeats = self.fields[idx + 1..]
+                .iter()
+                .any(|f| f.code() == field.code());
+
+            if first == Some(idx) && repeats && !field_spec.repeatable() {
+                errors.push(ValidationError::RepeatedField(field_spec.code));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     /// Turns a SIP string into a Message
     ///
     /// Assumes the trailing message terminator character has been removed.
@@ -381,6 +522,10 @@ impl Message {
     /// a known spec::Field.  Any value of 3 or more characters will be
     /// treated as a valid field.
     ///
+    /// A trailing sequence number and checksum, e.g. "AY1AZF83D", are
+    /// stored as AY and AZ fields.  The checksum is not verified here.
+    /// See [`util::verify_checksum`].
+    ///
     /// ```
     /// use sip2::{Message, Field, FixedField};
     /// let sip_text = "9300CNsip_username|COsip_password|";
@@ -410,6 +555,8 @@ impl Message {
             fields: vec![],
         };
 
+        let (text, error_detection) = split_error_detection(text);
+
         // Remove the message code
         let mut msg_text = &text[2..];
 
@@ -432,11 +579,6 @@ impl Message {
                 .push(FixedField::new(ff_spec, value).unwrap());
         }
 
-        // Not all messages have fixed fields and/or fields
-        if msg_text.is_empty() {
-            return Ok(msg);
-        }
-
         // Free-text fields are separated by "|" characters.
         for part in msg_text.split('|') {
             if part.len() > 1 {
@@ -448,8 +590,192 @@ impl Message {
             }
         }
 
+        msg.fields.extend(error_detection);
+
         Ok(msg)
     }
+
+    /// Turns a SIP string into a Message, recovering what it can from
+    /// malformed messages.
+    ///
+    /// Fixed fields which are short or missing are padded with spaces,
+    /// fields too short to contain a field code are discarded, and
+    /// missing required fields (see [`spec::Message::required_fields`])
+    /// are noted.  Each problem is reported as a [`Diagnostic`] so the
+    /// caller can decide whether to proceed.
+    ///
+    /// Unknown message types are still an error.
+    ///
+    /// ```
+    /// use sip2::{Diagnostic, Message};
+    ///
+    /// // Patron info request with a 12-character date and no summary.
+    /// let (msg, diags) = Message::from_sip_lenient("63001202401011200AOexample|AA123|").unwrap();
+    ///
+    /// assert_eq!(msg.fixed_fields()[1].value(), "202401011200      ");
+    /// assert_eq!(msg.fixed_fields()[2].value(), "          ");
+    /// assert_eq!(msg.get_field_value("AA"), Some("123"));
+    /// assert_eq!(diags.len(), 2);
+    /// assert_eq!(
+    ///     diags[0],
+    ///     Diagnostic::FixedFieldLength {label: "transaction date", expected: 18, found: 12}
+    /// );
+    ///
+    /// // Well-formed checkin missing its item barcode.
+    /// let (_, diags) =
+    ///     Message::from_sip_lenient("09N20240101    12000020240101    120000AOexample|X|").unwrap();
+    ///
+    /// assert_eq!(
+    ///     diags,
+    ///     vec![Diagnostic::MalformedField("X".to_string()), Diagnostic::MissingField("AB")]
+    /// );
+    ///
+    /// assert!(Message::from_sip_lenient("XX").is_err());
+    /// ```
+    pub fn from_sip_lenient(text: &str) -> Result<(Message, Vec<Diagnostic>), Error> {
+        if text.len() < 2 || !text.is_char_boundary(2) {
+            log::warn!("SIP message is incomplete: {text}");
+            return Err(Error::MessageFormatError);
+        }
+
+        let msg_spec = match spec::Message::from_code(&text[0..2]) {
+            Some(m) => m,
+            None => {
+                error!("Unknown message type: {}", &text[0..2]);
+                return Err(Error::MessageFormatError);
+            }
+        };
+
+        let mut msg = Message {
+            spec: msg_spec,
+            fixed_fields: vec![],
+            fields: vec![],
+        };
+
+        let mut diagnostics = Vec::new();
+
+        if util::verify_checksum(text) == Some(false) {
+            diagnostics.push(Diagnostic::InvalidChecksum);
+        }
+
+        let (text, error_detection) = split_error_detection(text);
+
+        let msg_text = &text[2..];
+        let fixed_end = fixed_fields_end(msg_spec, msg_text);
+        let mut ff_text = &msg_text[..fixed_end];
+
+        for ff_spec in msg_spec.fixed_fields.iter() {
+            let mut len = ff_spec.length.min(ff_text.len());
+            while !ff_text.is_char_boundary(len) {
+                len -= 1;
+            }
+
+            let value = &ff_text[..len];
+            ff_text = &ff_text[len..];
+
+            if value.len() < ff_spec.length {
+                diagnostics.push(Diagnostic::FixedFieldLength {
+                    label: ff_spec.label,
+                    expected: ff_spec.length,
+                    found: value.len(),
+                });
+            }
+
+            let value = format!("{value:<width$}", width = ff_spec.length);
+
+            // Padding ensures the length matches.
+            msg.fixed_fields
+                .push(FixedField::new(ff_spec, &value).unwrap());
+        }
+
+        for part in msg_text[fixed_end..].split('|') {
+            if part.len() > 1 && part.is_char_boundary(2) {
+                msg.fields.push(Field::new(&part[0..2], &part[2..]));
+            } else if !part.is_empty() {
+                diagnostics.push(Diagnostic::MalformedField(part.to_string()));
+            }
+        }
+
+        msg.fields.extend(error_detection);
+
+        for field in msg_spec.required_fields() {
+            if msg.get_field_value(field.code).is_none() {
+                diagnostics.push(Diagnostic::MissingField(field.code));
+            }
+        }
+
+        for diag in diagnostics.iter() {
+            warn!("SIP message {} {diag}: {text}", msg_spec.code);
+        }
+
+        Ok((msg, diagnostics))
+    }
+}
+
+/// True for the sequence number and checksum fields, which are
+/// positioned at the end of the message.
+fn is_error_detection(field: &Field) -> bool {
+    field.code() == spec::F_SEQUENCE_NUMBER.code || field.code() == spec::F_CHECKSUM.code
+}
+
+/// Split the trailing sequence number and checksum, e.g. "AY1AZF83D",
+/// from message text.
+///
+/// Returns the remaining text and the fields found.
+fn split_error_detection(text: &str) -> (&str, Vec<Field>) {
+    let mut fields = Vec::new();
+
+    if util::verify_checksum(text).is_none() {
+        return (text, fields);
+    }
+
+    let mut rest = &text[..text.len() - 6];
+    let checksum = Field::new(spec::F_CHECKSUM.code, &text[text.len() - 4..]);
+
+    let len = rest.len();
+    if len >= 5
+        && rest.is_char_boundary(len - 3)
+        && &rest[len - 3..len - 1] == spec::F_SEQUENCE_NUMBER.code
+        && rest[len - 1..].chars().all(|c| c.is_ascii_digit())
+    {
+        fields.push(Field::new(spec::F_SEQUENCE_NUMBER.code, &rest[len - 1..]));
+        rest = &rest[..len - 3];
+    }
+
+    fields.push(checksum);
+
+    (rest, fields)
+}
+
+/// Locate the end of the fixed fields within the message text (minus
+/// the message code).
+///
+/// If the fixed fields are short, variable-length fields start before
+/// the expected end of the fixed fields, which we detect by looking for
+/// a field delimiter within the fixed field area.  In that case, the
+/// fixed fields end where the first known field code begins.
+fn fixed_fields_end(msg_spec: &spec::Message, msg_text: &str) -> usize {
+    let fixed_len: usize = msg_spec.fixed_fields.iter().map(|ff| ff.length).sum();
+
+    let delimiter = match msg_text.find('|') {
+        Some(d) if d < fixed_len => d,
+        _ => {
+            let mut end = fixed_len.min(msg_text.len());
+            while !msg_text.is_char_boundary(end) {
+                end -= 1;
+            }
+            return end;
+        }
+    };
+
+    (0..delimiter.saturating_sub(1))
+        .find(|idx| {
+            msg_text
+                .get(*idx..*idx + 2)
+                .and_then(spec::Field::from_code)
+                .is_some()
+        })
+        .unwrap_or(delimiter)
 }
 
 /// Message display support for logging / debugging.
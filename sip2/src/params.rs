@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 use crate::spec;
+use std::time::Duration;
 
 /// Collection of friendly-named SIP request parameters for common tasks.
 ///
@@ -34,6 +35,14 @@ pub struct ParamSet {
     /// that should be set to 'Y' (i.e. activated).  Only one summary
     /// index may be activated per message.  Positions are zero-based.
     summary: Option<usize>,
+
+    /// Enable or disable the error detection extension (sequence
+    /// numbers and checksums) on the connection.
+    error_detection: Option<bool>,
+
+    /// Network timeout for a single request, overriding the timeouts
+    /// of the connection.
+    timeout: Option<Duration>,
 }
 
 impl Default for ParamSet {
@@ -61,6 +70,8 @@ impl ParamSet {
             fee_id: None,
             pay_type: None,
             fee_type: None,
+            error_detection: None,
+            timeout: None,
         }
     }
 
@@ -112,6 +123,12 @@ impl ParamSet {
     pub fn fee_type(&self) -> Option<spec::FeeType> {
         self.fee_type
     }
+    pub fn error_detection(&self) -> Option<bool> {
+        self.error_detection
+    }
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
 
     // ---
 
@@ -179,4 +196,12 @@ impl ParamSet {
         self.fee_type = Some(pt);
         self
     }
+    pub fn set_error_detection(&mut self, enabled: bool) -> &mut Self {
+        self.error_detection = Some(enabled);
+        self
+    }
+    pub fn set_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
 }
@@ -0,0 +1,336 @@
+use super::client::Client;
+use super::connection::Timeouts;
+use super::encoding::Encoding;
+use super::error::Error;
+use super::params::ParamSet;
+use super::spec::SipVersion;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Settings for a [`ClientPool`].
+///
+/// ```
+/// use sip2::PoolOptions;
+/// use std::time::Duration;
+///
+/// let options = PoolOptions {
+///     max_size: 20,
+///     checkout_timeout: Some(Duration::from_secs(5)),
+///     ..Default::default()
+/// };
+///
+/// assert_eq!(options.idle_timeout, Duration::from_secs(300));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    /// Maximum number of connections, idle or in use.
+    pub max_size: usize,
+
+    /// Idle connections are closed after this long.
+    pub idle_timeout: Duration,
+
+    /// Connections idle for at least this long are verified with an
+    /// SC Status request before they are handed out.
+    pub health_check_after: Duration,
+
+    /// Maximum time to wait for a connection when all are in use.
+    /// None waits indefinitely.
+    pub checkout_timeout: Option<Duration>,
+
+    /// Network timeouts for each connection.
+    pub timeouts: Timeouts,
+
+    /// Character encoding for each connection.
+    pub encoding: Encoding,
+
+    /// SIP protocol version for each connection.
+    pub version: SipVersion,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        PoolOptions {
+            max_size: 10,
+            idle_timeout: Duration::from_secs(300),
+            health_check_after: Duration::from_secs(30),
+            checkout_timeout: None,
+            timeouts: Timeouts::default(),
+            encoding: Encoding::default(),
+            version: SipVersion::default(),
+        }
+    }
+}
+
+struct IdleClient {
+    client: Client,
+    since: Instant,
+}
+
+struct PoolState {
+    idle: Vec<IdleClient>,
+
+    /// Number of connections, idle or in use.
+    size: usize,
+}
+
+/// Pool of logged-in SIP clients connected to one SIP server.
+///
+/// Connections are created as needed, up to the configured maximum,
+/// and are reused by later requests.  The pool may be shared between
+/// threads, e.g. via an Arc.
+///
+/// ```no_run
+/// use sip2::{ClientPool, ParamSet, PoolOptions};
+///
+/// let mut params = ParamSet::new();
+/// params.set_sip_user("sip-server-login");
+/// params.set_sip_pass("sip-server-password");
+///
+/// let pool = ClientPool::new("127.0.0.1:6001", params, PoolOptions::default());
+///
+/// let mut params = ParamSet::new();
+/// params.set_institution("example").set_patron_id("2222200000001");
+///
+/// let mut client = pool.get().expect("No SIP connection available");
+/// let resp = client.patron_status(&params).expect("Patron Status Failed");
+///
+/// // The client returns to the pool when dropped.
+/// ```
+pub struct ClientPool {
+    host: String,
+    login_params: ParamSet,
+    options: PoolOptions,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+impl ClientPool {
+    /// Create a pool which logs each new connection in with
+    /// `login_params`.
+    ///
+    /// No connections are opened until needed.
+    pub fn new(host: &str, login_params: ParamSet, options: PoolOptions) -> Self {
+        ClientPool {
+            host: host.to_string(),
+            login_params,
+            options,
+            state: Mutex::new(PoolState {
+                idle: Vec::new(),
+                size: 0,
+            }),
+            available: Condvar::new(),
+        }
+    }
+
+    /// Number of connections, idle or in use.
+    pub fn size(&self) -> usize {
+        self.lock().size
+    }
+
+    /// Number of connections waiting to be used.
+    pub fn idle_count(&self) -> usize {
+        self.lock().idle.len()
+    }
+
+    /// Check out a client.
+    ///
+    /// Reuses an idle connection when one is available, otherwise
+    /// opens a new one.  When the pool is full, waits for a client to
+    /// be returned, failing with [`Error::Timeout`] if the checkout
+    /// timeout expires first.
+    pub fn get(&self) -> Result<PooledClient<'_>, Error> {
+        let deadline = self.options.checkout_timeout.map(|t| Instant::now() + t);
+
+        loop {
+            let mut state = self.lock();
+            self.evict_expired(&mut state);
+
+            if let Some(idle) = state.idle.pop() {
+                drop(state);
+
+                if let Some(client) = self.check_health(idle) {
+                    return Ok(PooledClient::new(self, client));
+                }
+
+                continue;
+            }
+
+            if state.size < self.options.max_size {
+                state.size += 1;
+                drop(state);
+
+                return match self.connect() {
+                    Ok(client) => Ok(PooledClient::new(self, client)),
+                    Err(e) => {
+                        self.release_slot();
+                        Err(e)
+                    }
+                };
+            }
+
+            // Wait for a client to be returned or a slot to open up.
+            let state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+
+                    if now >= deadline {
+                        log::warn!("Timed out waiting for a SIP connection to {}", self.host);
+                        return Err(Error::Timeout);
+                    }
+
+                    self.available
+                        .wait_timeout(state, deadline - now)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+                None => self
+                    .available
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+
+            drop(state);
+        }
+    }
+
+    /// Close connections which have been idle longer than the idle
+    /// timeout.
+    ///
+    /// This happens on every checkout.  Call it periodically to also
+    /// release connections while the pool is not in use.
+    pub fn evict_idle(&self) {
+        let mut state = self.lock();
+        self.evict_expired(&mut state);
+    }
+
+    fn evict_expired(&self, state: &mut PoolState) {
+        let idle_timeout = self.options.idle_timeout;
+        let before = state.idle.len();
+
+        state.idle.retain(|idle| {
+            let keep = idle.since.elapsed() < idle_timeout;
+            if !keep {
+                let _ = idle.client.disconnect();
+            }
+            keep
+        });
+
+        let evicted = before - state.idle.len();
+
+        if evicted > 0 {
+            log::debug!("Closed {evicted} idle SIP connection(s) to {}", self.host);
+            state.size -= evicted;
+            self.available.notify_all();
+        }
+    }
+
+    /// Returns the client if it's still usable.
+    fn check_health(&self, idle: IdleClient) -> Option<Client> {
+        let mut client = idle.client;
+
+        if idle.since.elapsed() < self.options.health_check_after {
+            return Some(client);
+        }
+
+        match client.sc_status() {
+            Ok(resp) if resp.ok() => Some(client),
+            result => {
+                if let Err(e) = result {
+                    log::info!("Discarding failed SIP connection to {}: {e}", self.host);
+                }
+                let _ = client.disconnect();
+                self.release_slot();
+                None
+            }
+        }
+    }
+
+    fn connect(&self) -> Result<Client, Error> {
+        log::debug!("Opening new pooled SIP connection to {}", self.host);
+
+        let mut client = Client::with_timeouts(&self.host, self.options.timeouts)?;
+        client.set_encoding(self.options.encoding);
+        client.set_version(self.options.version);
+
+        if !client.login(&self.login_params)?.ok() {
+            let _ = client.disconnect();
+            return Err(Error::NetworkError(format!(
+                "Login to {} failed for pooled connection",
+                self.host
+            )));
+        }
+
+        Ok(client)
+    }
+
+    fn checkin(&self, client: Client) {
+        let mut state = self.lock();
+
+        state.idle.push(IdleClient {
+            client,
+            since: Instant::now(),
+        });
+
+        self.available.notify_one();
+    }
+
+    fn release_slot(&self) {
+        self.lock().size -= 1;
+        self.available.notify_one();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A [`Client`] checked out from a [`ClientPool`].
+///
+/// Returned to the pool when dropped.
+pub struct PooledClient<'a> {
+    pool: &'a ClientPool,
+    client: Option<Client>,
+}
+
+impl<'a> PooledClient<'a> {
+    fn new(pool: &'a ClientPool, client: Client) -> Self {
+        PooledClient {
+            pool,
+            client: Some(client),
+        }
+    }
+
+    /// Close the connection instead of returning it to the pool.
+    ///
+    /// Use when a request failed in a way that leaves the connection
+    /// unusable, e.g. a network error.
+    pub fn discard(mut self) {
+        if let Some(client) = self.client.take() {
+            let _ = client.disconnect();
+            self.pool.release_slot();
+        }
+    }
+}
+
+impl Deref for PooledClient<'_> {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.client.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledClient<'_> {
+    fn deref_mut(&mut self) -> &mut Client {
+        self.client.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledClient<'_> {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take() {
+            self.pool.checkin(client);
+        }
+    }
+}
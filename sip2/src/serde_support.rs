@@ -0,0 +1,126 @@
+//! serde Serialize/Deserialize implementations.
+//!
+//! Messages use the same layout as the "json" feature routines, with
+//! fixed fields in message order and each field as a single-entry map:
+//!
+//! ```text
+//! {
+//!   "code": "93",
+//!   "fixed_fields": ["0", "0"],
+//!   "fields": [{"CN": "sip_username"}, {"CO": "sip_password"}]
+//! }
+//! ```
+//!
+//! A standalone fixed field is serialized with its spec label, e.g.
+//! `{"label": "ok", "value": "1"}`, which is used to find the spec
+//! when deserializing.
+//!
+//! Values are validated when deserialized, the same as when created
+//! via the constructors.
+use super::spec;
+use super::Field;
+use super::FixedField;
+use super::Message;
+use serde::de::{Deserializer, Error, MapAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+impl Serialize for FixedField {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("FixedField", 2)?;
+        s.serialize_field("label", self.spec().label)?;
+        s.serialize_field("value", self.value())?;
+        s.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct FixedFieldData {
+    label: String,
+    value: String,
+}
+
+impl<'de> Deserialize<'de> for FixedField {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = FixedFieldData::deserialize(deserializer)?;
+
+        let ff_spec = spec::FixedField::from_label(&data.label)
+            .ok_or_else(|| D::Error::custom(format!("Unknown fixed field: {}", data.label)))?;
+
+        FixedField::new(ff_spec, &data.value).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for Field {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(self.code(), self.value())?;
+        map.end()
+    }
+}
+
+struct FieldVisitor;
+
+impl<'de> Visitor<'de> for FieldVisitor {
+    type Value = Field;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a map with a single field code and value")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Field, A::Error> {
+        let (code, value): (String, String) = map
+            .next_entry()?
+            .ok_or_else(|| A::Error::custom("Field requires a code and value"))?;
+
+        if map.next_key::<String>()?.is_some() {
+            return Err(A::Error::custom("Field contains multiple codes"));
+        }
+
+        Ok(Field::new(&code, &value))
+    }
+}
+
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(FieldVisitor)
+    }
+}
+
+impl Serialize for Message {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let ff: Vec<&str> = self.fixed_fields().iter().map(|f| f.value()).collect();
+
+        let mut s = serializer.serialize_struct("Message", 3)?;
+        s.serialize_field("code", self.spec().code)?;
+        s.serialize_field("fixed_fields", &ff)?;
+        s.serialize_field("fields", self.fields())?;
+        s.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct MessageData {
+    code: String,
+    #[serde(default)]
+    fixed_fields: Vec<String>,
+    #[serde(default)]
+    fields: Vec<Field>,
+}
+
+impl<'de> Deserialize<'de> for Message {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = MessageData::deserialize(deserializer)?;
+
+        let ff: Vec<&str> = data.fixed_fields.iter().map(|f| f.as_str()).collect();
+
+        let mut msg = Message::from_ff_values(&data.code, &ff).map_err(D::Error::custom)?;
+
+        for field in data.fields {
+            msg.add_field(field.code(), field.value());
+        }
+
+        Ok(msg)
+    }
+}
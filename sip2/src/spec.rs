@@ -1,10 +1,59 @@
 //! SIP2 Specification as a collection of static values.
+//!
+//! Vendor-specific fields and messages may be added at runtime via
+//! [`register_field`] and [`register_message`].
+use super::error::Error;
 use std::fmt;
+use std::sync::{PoisonError, RwLock};
 
 pub const SIP_PROTOCOL_VERSION: &str = "2.00";
+pub const SIP3_PROTOCOL_VERSION: &str = "3.00";
 pub const LINE_TERMINATOR: char = '\r';
 pub const SIP_DATE_FORMAT: &str = "%Y%m%d    %H%M%S";
 
+/// SIP protocol version used by a connection.
+///
+/// Version 2.00 is the default.  The 2.00 message and field
+/// definitions are shared by both versions.  Messages introduced by
+/// 3.00, e.g. [`M_HOLD_LIST`], and messages registered via
+/// [`register_message_for_version`] are only sent or accepted by
+/// connections using that version.
+///
+/// ```
+/// use sip2::spec::SipVersion;
+///
+/// assert_eq!(SipVersion::default().protocol_version(), "2.00");
+/// assert_eq!(SipVersion::V3.protocol_version(), "3.00");
+/// assert_eq!(SipVersion::from_protocol_version("3.00"), Some(SipVersion::V3));
+/// assert_eq!(SipVersion::from_protocol_version("1.00"), None);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SipVersion {
+    #[default]
+    V2,
+    V3,
+}
+
+impl SipVersion {
+    /// Value for the "protocol version" fixed field.
+    pub fn protocol_version(&self) -> &'static str {
+        match self {
+            SipVersion::V2 => SIP_PROTOCOL_VERSION,
+            SipVersion::V3 => SIP3_PROTOCOL_VERSION,
+        }
+    }
+
+    /// Find the version from a "protocol version" fixed field value,
+    /// e.g. "2.00".
+    pub fn from_protocol_version(version: &str) -> Option<Self> {
+        match version.trim().split('.').next() {
+            Some("2") => Some(SipVersion::V2),
+            Some("3") => Some(SipVersion::V3),
+            _ => None,
+        }
+    }
+}
+
 /// Fee Paid Payment Types
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum PayType {
@@ -103,6 +152,25 @@ pub struct FixedField {
     pub length: usize,
 }
 
+impl FixedField {
+    /// Get a FixedField from its label.
+    ///
+    /// Includes fixed fields of messages added via [`register_message`].
+    ///
+    /// ```
+    /// use sip2::spec;
+    /// let ff = spec::FixedField::from_label("transaction date").unwrap();
+    /// assert_eq!(ff, &spec::FF_DATE);
+    /// ```
+    pub fn from_label(label: &str) -> Option<&'static FixedField> {
+        FIXED_FIELDS
+            .iter()
+            .copied()
+            .find(|ff| ff.label == label)
+            .or_else(|| custom_fixed_field(label))
+    }
+}
+
 impl fmt::Display for FixedField {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{} ({})", self.label, self.length)
@@ -128,8 +196,34 @@ impl fmt::Display for Field {
 }
 
 impl Field {
+    /// True if the field may appear more than once in a message.
+    ///
+    /// ```
+    /// use sip2::spec;
+    /// assert!(spec::F_SCREEN_MSG.repeatable());
+    /// assert!(!spec::F_PATRON_ID.repeatable());
+    /// ```
+    pub fn repeatable(&self) -> bool {
+        [
+            &F_SCREEN_MSG,
+            &F_PRINT_LINE,
+            &F_HOLD_ITEMS,
+            &F_OVERDUE_ITEMS,
+            &F_CHARGED_ITEMS,
+            &F_FINE_ITEMS,
+            &F_RECALL_ITEMS,
+            &F_UNAVAIL_HOLD_ITEMS,
+            &F_HOLD_ID,
+        ]
+        .iter()
+        .any(|f| f.code == self.code)
+            || custom_field(self.code).map(|(_, r)| r) == Some(true)
+    }
+
     /// Get a Field from its 2-character code.
     ///
+    /// Includes fields added via [`register_field`].
+    ///
     /// ```
     /// use sip2::spec;
     /// let f = &spec::F_LOGIN_UID;
@@ -211,7 +305,13 @@ impl Field {
             f if f == F_PATRON_CLASS.code => Some(&F_PATRON_CLASS),
             f if f == F_REGISTER_LOGIN.code => Some(&F_REGISTER_LOGIN),
             f if f == F_CHECK_NUMBER.code => Some(&F_CHECK_NUMBER),
-            _ => None,
+            f if f == F_HOLD_ID.code => Some(&F_HOLD_ID),
+            f if f == F_HOLD_STATUS.code => Some(&F_HOLD_STATUS),
+            f if f == F_HOLD_EXPIRE_DATE.code => Some(&F_HOLD_EXPIRE_DATE),
+            f if f == F_HOLD_POSITION.code => Some(&F_HOLD_POSITION),
+            f if f == F_ITEM_CIRC_MODIFIER.code => Some(&F_ITEM_CIRC_MODIFIER),
+            f if f == F_ITEM_NOTE.code => Some(&F_ITEM_NOTE),
+            _ => custom_field(code).map(|(f, _)| f),
         }
     }
 }
@@ -239,6 +339,8 @@ pub struct Message {
 impl Message {
     /// Maps a message code to a message spec.
     ///
+    /// Includes messages added via [`register_message`].
+    ///
     /// ```
     /// use sip2::spec;
     /// let msg = &spec::M_LOGIN;
@@ -274,8 +376,73 @@ impl Message {
             m if m == M_END_SESSION.code => Some(&M_END_SESSION),
             m if m == M_END_SESSION_RESP.code => Some(&M_END_SESSION_RESP),
             m if m == M_BLOCK_PATRON.code => Some(&M_BLOCK_PATRON),
+            m if m == M_ITEM_STATUS_UPDATE.code => Some(&M_ITEM_STATUS_UPDATE),
+            m if m == M_ITEM_STATUS_UPDATE_RESP.code => Some(&M_ITEM_STATUS_UPDATE_RESP),
+            m if m == M_PATRON_ENABLE.code => Some(&M_PATRON_ENABLE),
+            m if m == M_PATRON_ENABLE_RESP.code => Some(&M_PATRON_ENABLE_RESP),
+            m if m == M_REQUEST_SC_RESEND.code => Some(&M_REQUEST_SC_RESEND),
             m if m == M_REQUEST_ACS_RESEND.code => Some(&M_REQUEST_ACS_RESEND),
-            _ => None,
+            m if m == M_HOLD_LIST.code => Some(&M_HOLD_LIST),
+            m if m == M_HOLD_LIST_RESP.code => Some(&M_HOLD_LIST_RESP),
+            m if m == M_HOLD_STATUS.code => Some(&M_HOLD_STATUS),
+            m if m == M_HOLD_STATUS_RESP.code => Some(&M_HOLD_STATUS_RESP),
+            m if m == M_ITEM_UPDATE.code => Some(&M_ITEM_UPDATE),
+            m if m == M_ITEM_UPDATE_RESP.code => Some(&M_ITEM_UPDATE_RESP),
+            _ => custom_message(code),
+        }
+    }
+
+    /// Protocol version which introduced this message.
+    ///
+    /// ```
+    /// use sip2::spec;
+    /// assert_eq!(spec::M_CHECKOUT.version(), spec::SipVersion::V2);
+    /// assert_eq!(spec::M_HOLD_LIST.version(), spec::SipVersion::V3);
+    /// ```
+    pub fn version(&self) -> SipVersion {
+        if SIP3_MESSAGES.iter().any(|m| m.code == self.code) {
+            return SipVersion::V3;
+        }
+
+        CUSTOM_MESSAGES
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .find(|c| c.message.code == self.code)
+            .map(|c| c.version)
+            .unwrap_or_default()
+    }
+
+    /// Fields the SIP2 specification requires in this message.
+    ///
+    /// Only request messages (those sent by the SC) are listed, since
+    /// those are the messages a server needs to verify.
+    ///
+    /// ```
+    /// use sip2::spec;
+    /// let codes: Vec<&str> = spec::M_CHECKOUT.required_fields().iter().map(|f| f.code).collect();
+    /// assert_eq!(codes, vec!["AO", "AA", "AB"]);
+    /// assert!(spec::M_CHECKOUT_RESP.required_fields().is_empty());
+    /// ```
+    pub fn required_fields(&self) -> &'static [&'static Field] {
+        match self.code {
+            c if c == M_LOGIN.code => &[&F_LOGIN_UID, &F_LOGIN_PWD],
+            c if c == M_ITEM_INFO.code => &[&F_INSTITUTION_ID, &F_ITEM_IDENT],
+            c if c == M_PATRON_STATUS.code => &[&F_INSTITUTION_ID, &F_PATRON_ID],
+            c if c == M_PATRON_INFO.code => &[&F_INSTITUTION_ID, &F_PATRON_ID],
+            c if c == M_CHECKOUT.code => &[&F_INSTITUTION_ID, &F_PATRON_ID, &F_ITEM_IDENT],
+            c if c == M_RENEW.code => &[&F_INSTITUTION_ID, &F_PATRON_ID],
+            c if c == M_RENEW_ALL.code => &[&F_INSTITUTION_ID, &F_PATRON_ID],
+            c if c == M_CHECKIN.code => &[&F_INSTITUTION_ID, &F_ITEM_IDENT],
+            c if c == M_HOLD.code => &[&F_INSTITUTION_ID, &F_PATRON_ID],
+            c if c == M_END_PATRON_SESSION.code => &[&F_INSTITUTION_ID, &F_PATRON_ID],
+            c if c == M_FEE_PAID.code => &[&F_INSTITUTION_ID, &F_PATRON_ID, &F_FEE_AMOUNT],
+            c if c == M_BLOCK_PATRON.code => &[&F_INSTITUTION_ID, &F_PATRON_ID],
+            c if c == M_PATRON_ENABLE.code => &[&F_INSTITUTION_ID, &F_PATRON_ID],
+            c if c == M_HOLD_LIST.code => &[&F_INSTITUTION_ID, &F_PATRON_ID],
+            c if c == M_HOLD_STATUS.code => &[&F_INSTITUTION_ID, &F_HOLD_ID],
+            c if c == M_ITEM_UPDATE.code => &[&F_INSTITUTION_ID, &F_ITEM_IDENT],
+            _ => &[],
         }
     }
 }
@@ -474,6 +641,74 @@ pub const FF_END_PATRON_SESSION: FF = FF {
     length: 1,
     label: "end session",
 };
+pub const FF_ITEM_PROPS_OK: FF = FF {
+    length: 1,
+    label: "item properties ok",
+};
+pub const FF_HOLD_COUNT: FF = FF {
+    length: 4,
+    label: "hold count",
+};
+pub const FF_ITEM_UPDATED: FF = FF {
+    length: 1,
+    label: "item updated",
+};
+
+const FIXED_FIELDS: &[&FF] = &[
+    &FF_DATE,
+    &FF_OK,
+    &FF_UID_ALGO,
+    &FF_PWD_ALGO,
+    &FF_FEE_TYPE,
+    &FF_PAYMENT_TYPE,
+    &FF_CURRENCY,
+    &FF_PAYMENT_ACCEPTED,
+    &FF_CIRCULATION_STATUS,
+    &FF_SECURITY_MARKER,
+    &FF_LANGUAGE,
+    &FF_PATRON_STATUS,
+    &FF_SUMMARY,
+    &FF_HOLD_ITEMS_COUNT,
+    &FF_OD_ITEMS_COUNT,
+    &FF_CH_ITEMS_COUNT,
+    &FF_FINE_ITEMS_COUNT,
+    &FF_RECALL_ITEMS_COUNT,
+    &FF_UNAVAIL_HOLDS_COUNT,
+    &FF_SC_RENEWAL_POLICY,
+    &FF_NO_BLOCK,
+    &FF_NB_DUE_DATE,
+    &FF_STATUS_CODE,
+    &FF_MAX_PRINT_WIDTH,
+    &FF_PROTOCOL_VERSION,
+    &FF_RENEW_OK,
+    &FF_MAGNETIC_MEDIA,
+    &FF_DESENSITIZE,
+    &FF_RESENSITIZE,
+    &FF_RETURN_DATE,
+    &FF_ALERT,
+    &FF_ONLINE_STATUS,
+    &FF_CHECKIN_OK,
+    &FF_CHECKOUT_OK,
+    &FF_ACS_RENEWAL_POLICY,
+    &FF_STATUS_UPDATE_OK,
+    &FF_OFFLINE_OK,
+    &FF_TIMEOUT_PERIOD,
+    &FF_RETRIES_ALLOWED,
+    &FF_DATETIME_SYNC,
+    &FF_THIRD_PARTY_ALLOWED,
+    &FF_RENEWED_COUNT,
+    &FF_UNRENEWED_COUNT,
+    &FF_HOLD_MODE,
+    &FF_HOLD_AVAILABLE,
+    &FF_CARD_RETAINED,
+    &FF_END_PATRON_SESSION,
+    &FF_ITEM_PROPS_OK,
+    &FF_HOLD_COUNT,
+    &FF_ITEM_UPDATED,
+];
+
+// NOTE: when adding new fixed fields, be sure to also add the new
+// fixed field to FIXED_FIELDS
 
 // -------------------------------------------------------------------------
 // Fields
@@ -776,6 +1011,32 @@ pub const F_CHECK_NUMBER: F = F {
     label: "check number",
 };
 
+//  SIP 3.00 Fields
+pub const F_HOLD_ID: F = F {
+    code: "HI",
+    label: "hold identifier",
+};
+pub const F_HOLD_STATUS: F = F {
+    code: "HS",
+    label: "hold status",
+};
+pub const F_HOLD_EXPIRE_DATE: F = F {
+    code: "HE",
+    label: "hold expiration date",
+};
+pub const F_HOLD_POSITION: F = F {
+    code: "HP",
+    label: "hold queue position",
+};
+pub const F_ITEM_CIRC_MODIFIER: F = F {
+    code: "IM",
+    label: "item circulation modifier",
+};
+pub const F_ITEM_NOTE: F = F {
+    code: "IN",
+    label: "item note",
+};
+
 // NOTE: when adding new fields, be sure to also add the new
 // to Field::from_code()
 
@@ -1007,6 +1268,13 @@ pub const M_FEE_PAID_RESP: Message = Message {
     fixed_fields: &[&FF_PAYMENT_ACCEPTED, &FF_DATE],
 };
 
+/// Message 96
+pub const M_REQUEST_SC_RESEND: Message = Message {
+    code: "96",
+    label: "Request SC Resend",
+    fixed_fields: &[],
+};
+
 /// Message 97
 pub const M_REQUEST_ACS_RESEND: Message = Message {
     code: "97",
@@ -1021,6 +1289,34 @@ pub const M_BLOCK_PATRON: Message = Message {
     fixed_fields: &[&FF_CARD_RETAINED, &FF_DATE],
 };
 
+/// Message 19
+pub const M_ITEM_STATUS_UPDATE: Message = Message {
+    code: "19",
+    label: "Item Status Update",
+    fixed_fields: &[&FF_DATE],
+};
+
+/// Message 20
+pub const M_ITEM_STATUS_UPDATE_RESP: Message = Message {
+    code: "20",
+    label: "Item Status Update Response",
+    fixed_fields: &[&FF_ITEM_PROPS_OK, &FF_DATE],
+};
+
+/// Message 25
+pub const M_PATRON_ENABLE: Message = Message {
+    code: "25",
+    label: "Patron Enable",
+    fixed_fields: &[&FF_DATE],
+};
+
+/// Message 26
+pub const M_PATRON_ENABLE_RESP: Message = Message {
+    code: "26",
+    label: "Patron Enable Response",
+    fixed_fields: &[&FF_PATRON_STATUS, &FF_LANGUAGE, &FF_DATE],
+};
+
 // Custom "end session" messages for SIP2Mediator.
 // This differs from the "End Patron Session" (35) message in that it's
 // not about a patron but about a SIP client session, which can involve
@@ -1040,8 +1336,259 @@ pub const M_END_SESSION_RESP: Message = Message {
     fixed_fields: &[],
 };
 
+// SIP 3.00 messages.  Connections using SIP 2.00 refuse to send or
+// accept these.
+
+/// Message 27
+pub const M_HOLD_LIST: Message = Message {
+    code: "27",
+    label: "Hold List Request",
+    fixed_fields: &[&FF_DATE],
+};
+
+/// Message 28
+pub const M_HOLD_LIST_RESP: Message = Message {
+    code: "28",
+    label: "Hold List Response",
+    fixed_fields: &[&FF_OK, &FF_HOLD_COUNT, &FF_DATE],
+};
+
+/// Message 39
+pub const M_HOLD_STATUS: Message = Message {
+    code: "39",
+    label: "Hold Status Request",
+    fixed_fields: &[&FF_DATE],
+};
+
+/// Message 40
+pub const M_HOLD_STATUS_RESP: Message = Message {
+    code: "40",
+    label: "Hold Status Response",
+    fixed_fields: &[&FF_OK, &FF_HOLD_AVAILABLE, &FF_DATE],
+};
+
+/// Message 21
+pub const M_ITEM_UPDATE: Message = Message {
+    code: "21",
+    label: "Item Update",
+    fixed_fields: &[&FF_DATE],
+};
+
+/// Message 22
+pub const M_ITEM_UPDATE_RESP: Message = Message {
+    code: "22",
+    label: "Item Update Response",
+    fixed_fields: &[&FF_ITEM_UPDATED, &FF_DATE],
+};
+
+const SIP3_MESSAGES: &[&Message] = &[
+    &M_HOLD_LIST,
+    &M_HOLD_LIST_RESP,
+    &M_HOLD_STATUS,
+    &M_HOLD_STATUS_RESP,
+    &M_ITEM_UPDATE,
+    &M_ITEM_UPDATE_RESP,
+];
+
 // NOTE: when adding new message types, be sure to also add the new
-// message to Message::from_code()
+// message to Message::from_code(), and SIP 3.00 messages to
+// SIP3_MESSAGES
+
+// -------------------------------------------------------------------------
+// Custom Fields and Messages
+// -------------------------------------------------------------------------
+
+struct CustomField {
+    field: &'static Field,
+    repeatable: bool,
+}
+
+struct CustomMessage {
+    message: &'static Message,
+    version: SipVersion,
+}
+
+static CUSTOM_FIELDS: RwLock<Vec<CustomField>> = RwLock::new(Vec::new());
+static CUSTOM_MESSAGES: RwLock<Vec<CustomMessage>> = RwLock::new(Vec::new());
+
+/// Register a vendor-specific field.
+///
+/// Registered fields are labeled like standard fields when messages
+/// are displayed and are accepted by [`crate::Message::validate`].
+/// Registering a code again replaces the previous registration.
+/// Standard field codes cannot be registered.
+///
+/// Registrations are global and last for the life of the process.
+///
+/// ```
+/// use sip2::{spec, Message};
+///
+/// let field = spec::register_field("ZE", "envisionware account", false).unwrap();
+/// assert_eq!(spec::Field::from_code("ZE"), Some(field));
+///
+/// let msg = Message::from_sip("941ZE12345|").unwrap();
+/// assert!(msg.to_string().contains("envisionware account"));
+///
+/// // Standard fields cannot be replaced.
+/// assert!(spec::register_field("AA", "patron", false).is_err());
+/// ```
+pub fn register_field(code: &str, label: &str, repeatable: bool) -> Result<&'static Field, Error> {
+    check_custom_code(code)?;
+
+    // Anything found which was not registered is a standard field.
+    if custom_field(code).is_none() {
+        if let Some(f) = Field::from_code(code) {
+            log::error!("Cannot register standard field: {f}");
+            return Err(Error::MessageFormatError);
+        }
+    }
+
+    let field: &'static Field = Box::leak(Box::new(Field {
+        code: leak(code),
+        label: leak(label),
+    }));
+
+    let mut fields = CUSTOM_FIELDS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    fields.retain(|c| c.field.code != code);
+    fields.push(CustomField { field, repeatable });
+
+    Ok(field)
+}
+
+/// Register a vendor-specific message type.
+///
+/// `fixed_fields` lists the label and length of each fixed field in
+/// the order they appear in the message.  Once registered, messages
+/// of this type can be created and parsed like standard messages.
+/// Registering a code again replaces the previous registration.
+/// Standard message codes cannot be registered.
+///
+/// Registrations are global and last for the life of the process.
+///
+/// ```
+/// use sip2::{spec, Message};
+///
+/// spec::register_message("ZA", "Vendor Status", &[("vendor flag", 1), ("transaction date", 18)])
+///     .unwrap();
+///
+/// let msg = Message::from_sip("ZAY20240101    120000AOexample|").unwrap();
+/// assert_eq!(msg.spec().label, "Vendor Status");
+/// assert_eq!(msg.fixed_fields()[0].value(), "Y");
+/// ```
+pub fn register_message(
+    code: &str,
+    label: &str,
+    fixed_fields: &[(&str, usize)],
+) -> Result<&'static Message, Error> {
+    register_message_for_version(code, label, fixed_fields, SipVersion::V2)
+}
+
+/// Register a message type introduced by a later protocol version.
+///
+/// Works like [`register_message`], except connections using an
+/// older protocol version refuse to send or accept the message.
+///
+/// ```
+/// use sip2::{spec, Message};
+/// use sip2::spec::SipVersion;
+///
+/// let spec = spec::register_message_for_version(
+///     "ZB",
+///     "Vendor Hold Status",
+///     &[("transaction date", 18)],
+///     SipVersion::V3,
+/// )
+/// .unwrap();
+///
+/// assert_eq!(spec.version(), SipVersion::V3);
+///
+/// let msg = Message::from_sip("ZB20240101    120000AOexample|").unwrap();
+/// assert_eq!(msg.spec().version(), SipVersion::V3);
+/// ```
+pub fn register_message_for_version(
+    code: &str,
+    label: &str,
+    fixed_fields: &[(&str, usize)],
+    version: SipVersion,
+) -> Result<&'static Message, Error> {
+    check_custom_code(code)?;
+
+    if custom_message(code).is_none() {
+        if let Some(m) = Message::from_code(code) {
+            log::error!("Cannot register standard message: {} {}", m.code, m.label);
+            return Err(Error::MessageFormatError);
+        }
+    }
+
+    let fixed_fields: Vec<&'static FixedField> = fixed_fields
+        .iter()
+        .map(|(label, length)| {
+            &*Box::leak(Box::new(FixedField {
+                label: leak(label),
+                length: *length,
+            }))
+        })
+        .collect();
+
+    let message: &'static Message = Box::leak(Box::new(Message {
+        code: leak(code),
+        label: leak(label),
+        fixed_fields: Box::leak(fixed_fields.into_boxed_slice()),
+    }));
+
+    let mut messages = CUSTOM_MESSAGES
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    messages.retain(|c| c.message.code != code);
+    messages.push(CustomMessage { message, version });
+
+    Ok(message)
+}
+
+/// A registered field and whether it's repeatable.
+fn custom_field(code: &str) -> Option<(&'static Field, bool)> {
+    CUSTOM_FIELDS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .find(|c| c.field.code == code)
+        .map(|c| (c.field, c.repeatable))
+}
+
+fn custom_message(code: &str) -> Option<&'static Message> {
+    CUSTOM_MESSAGES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .find(|c| c.message.code == code)
+        .map(|c| c.message)
+}
+
+fn custom_fixed_field(label: &str) -> Option<&'static FixedField> {
+    CUSTOM_MESSAGES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .flat_map(|c| c.message.fixed_fields.iter().copied())
+        .find(|ff| ff.label == label)
+}
+
+/// Custom codes are 2 ASCII letters or digits.
+fn check_custom_code(code: &str) -> Result<(), Error> {
+    if code.len() == 2 && code.chars().all(|c| c.is_ascii_alphanumeric()) {
+        Ok(())
+    } else {
+        log::error!("Invalid custom field or message code: '{code}'");
+        Err(Error::MessageFormatError)
+    }
+}
+
+fn leak(s: &str) -> &'static str {
+    Box::leak(s.to_string().into_boxed_str())
+}
 
 #[derive(Debug, PartialEq)]
 pub enum CheckinAlert {
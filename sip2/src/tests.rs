@@ -1,7 +1,18 @@
+use super::connection::check_version;
 use super::message::Field;
 use super::message::FixedField;
 use super::message::Message;
 use super::spec;
+use super::typed;
+use super::util;
+use super::{
+    Client, ClientPool, Connection, Diagnostic, Encoding, Error, ParamSet, PoolOptions,
+    ReconnectPolicy, ValidationError,
+};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
 
 #[test]
 fn invalid_fixed_field() {
@@ -51,3 +62,483 @@ fn fixed_field_to_str() {
     let ff = FixedField::new(&spec::FF_MAX_PRINT_WIDTH, "999").unwrap();
     assert_eq!(ff.to_sip(), "999");
 }
+
+#[test]
+fn client_reconnects() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let host = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let mut requests = Vec::new();
+
+        // Drop the first connection after the login.  Answer
+        // everything on the second.
+        for responses in [
+            vec!["941"],
+            vec!["941", "98YYYYNN99900320240101    1200002.00"],
+        ] {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            for resp in responses {
+                let mut req = Vec::new();
+                reader.read_until(b'\r', &mut req).unwrap();
+                requests.push(String::from_utf8(req).unwrap()[..2].to_string());
+
+                (&stream).write_all(format!("{resp}\r").as_bytes()).unwrap();
+            }
+        }
+
+        requests
+    });
+
+    let mut client = Client::new(&host).unwrap();
+
+    client.set_reconnect_policy(ReconnectPolicy {
+        max_retries: 3,
+        initial_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(10),
+    });
+
+    let mut params = ParamSet::new();
+    params.set_sip_user("sip-user");
+    params.set_sip_pass("sip-pass");
+
+    assert!(client.login(&params).unwrap().ok());
+    assert!(client.sc_status().unwrap().ok());
+
+    // Login, then login again and the resent SC status.
+    assert_eq!(server.join().unwrap(), vec!["93", "93", "99"]);
+}
+
+#[test]
+fn client_acs_status() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let host = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+        let mut req = Vec::new();
+        reader.read_until(b'\r', &mut req).unwrap();
+
+        // Timeout period of 0.1 seconds.
+        (&stream)
+            .write_all(b"98YYYNYN00100320240101    1200002.00AOexample|BXYYYYYNYYYYYNNNYY|\r")
+            .unwrap();
+
+        // Respond to the patron status request too late.
+        reader.read_until(b'\r', &mut req).unwrap();
+        thread::sleep(Duration::from_millis(500));
+    });
+
+    let mut client = Client::new(&host).unwrap();
+    assert!(client.supports(&spec::M_HOLD));
+
+    let status = client.status().unwrap();
+
+    assert!(status.online);
+    assert!(!status.renewal_policy);
+    assert_eq!(status.timeout(), Some(Duration::from_millis(100)));
+    assert_eq!(status.retries_allowed, Some(3));
+    assert_eq!(status.institution, "example");
+
+    assert!(client.supports(&spec::M_CHECKOUT));
+    assert!(!client.supports(&spec::M_HOLD));
+
+    let mut params = ParamSet::new();
+    params.set_institution("example").set_patron_id("123");
+
+    assert!(matches!(client.patron_status(&params), Err(Error::Timeout)));
+
+    server.join().unwrap();
+}
+
+#[test]
+fn client_request_timeout() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let host = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+        // Answer the login, then stall partway through the next response.
+        let mut req = Vec::new();
+        reader.read_until(b'\r', &mut req).unwrap();
+        (&stream).write_all(b"941\r").unwrap();
+
+        reader.read_until(b'\r', &mut req).unwrap();
+        (&stream).write_all(b"98YYYY").unwrap();
+
+        // Hold the connection open until the client gives up.
+        let _ = reader.read_until(b'\r', &mut req);
+    });
+
+    let mut client = Client::new(&host).unwrap();
+
+    let mut params = ParamSet::new();
+    params.set_sip_user("sip-user");
+    params.set_sip_pass("sip-pass");
+    params.set_patron_id("123");
+    params.set_timeout(Duration::from_millis(50));
+
+    assert!(client.login(&params).unwrap().ok());
+    assert!(matches!(client.patron_status(&params), Err(Error::Timeout)));
+
+    client.disconnect().unwrap();
+    server.join().unwrap();
+}
+
+#[test]
+fn client_pool() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let host = listener.local_addr().unwrap().to_string();
+
+    // Answer logins and SC status requests on each connection.
+    let server = thread::spawn(move || {
+        let mut handlers = Vec::new();
+
+        for _ in 0..2 {
+            let (stream, _) = listener.accept().unwrap();
+
+            handlers.push(thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut req = Vec::new();
+
+                while reader.read_until(b'\r', &mut req).unwrap() > 0 {
+                    let resp = match &req[..2] {
+                        b"93" => "941",
+                        _ => "98YYYYNN99900320240101    1200002.00",
+                    };
+                    (&stream).write_all(format!("{resp}\r").as_bytes()).unwrap();
+                    req.clear();
+                }
+            }));
+        }
+
+        for handler in handlers {
+            handler.join().unwrap();
+        }
+    });
+
+    let mut params = ParamSet::new();
+    params.set_sip_user("sip-user");
+    params.set_sip_pass("sip-pass");
+
+    let pool = ClientPool::new(
+        &host,
+        params,
+        PoolOptions {
+            max_size: 2,
+            checkout_timeout: Some(Duration::from_millis(50)),
+            ..Default::default()
+        },
+    );
+
+    let mut client1 = pool.get().unwrap();
+    let client2 = pool.get().unwrap();
+    assert_eq!(pool.size(), 2);
+
+    // Pool is full.
+    assert!(matches!(pool.get(), Err(Error::Timeout)));
+
+    assert!(client1.sc_status().unwrap().ok());
+    drop(client1);
+    assert_eq!(pool.idle_count(), 1);
+
+    // Reuses the idle connection.
+    let mut client1 = pool.get().unwrap();
+    assert!(client1.sc_status().unwrap().ok());
+    assert_eq!(pool.size(), 2);
+
+    client1.discard();
+    client2.discard();
+    assert_eq!(pool.size(), 0);
+
+    server.join().unwrap();
+}
+
+#[test]
+fn error_detection_fields() {
+    let text = format!("941AY3AZ{}", util::checksum("941AY3AZ"));
+
+    let msg = Message::from_sip(&text).unwrap();
+    assert_eq!(msg.fixed_fields()[0].value(), "1");
+    assert_eq!(msg.sequence_number(), Some(3));
+    assert_eq!(msg.to_sip(), text);
+
+    let (_, diags) = Message::from_sip_lenient("941AY3AZ0000").unwrap();
+    assert_eq!(diags, vec![Diagnostic::InvalidChecksum]);
+}
+
+#[test]
+fn connection_resends() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let host = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut requests = Vec::new();
+
+        let good = format!("941AY0AZ{}", util::checksum("941AY0AZ"));
+
+        // Ask for a resend, then send a bad checksum, then a good one.
+        for resp in ["96", "941AY0AZ0000", &good] {
+            let mut req = Vec::new();
+            reader.read_until(b'\r', &mut req).unwrap();
+            req.pop();
+            requests.push(String::from_utf8(req).unwrap());
+
+            (&stream).write_all(format!("{resp}\r").as_bytes()).unwrap();
+        }
+
+        requests
+    });
+
+    let mut con = Connection::new(&host).unwrap();
+    con.set_error_detection(true);
+
+    let req = Message::from_values("93", &["0", "0"], &[("CN", "user"), ("CO", "pass")]).unwrap();
+    let resp = con.sendrecv(&req).unwrap();
+
+    assert_eq!(resp.sequence_number(), Some(0));
+
+    let login = format!(
+        "9300CNuser|COpass|AY0AZ{}",
+        util::checksum("9300CNuser|COpass|AY0AZ")
+    );
+    let resend = format!("97AZ{}", util::checksum("97AZ"));
+
+    assert_eq!(server.join().unwrap(), vec![login.clone(), login, resend]);
+}
+
+#[test]
+fn connection_latin1() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let host = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+        let mut req = Vec::new();
+        reader.read_until(b'\r', &mut req).unwrap();
+
+        // "AEJos\u{e9}" in Latin-1, which is not valid UTF-8.
+        (&stream)
+            .write_all(b"24              00020240101    101112AEJos\xe9|\r")
+            .unwrap();
+
+        req
+    });
+
+    let mut con = Connection::new(&host).unwrap();
+    con.set_encoding(Encoding::Latin1);
+
+    let req =
+        Message::from_values("93", &["0", "0"], &[("CN", "Jos\u{e9}"), ("CO", "pass")]).unwrap();
+    let resp = con.sendrecv(&req).unwrap();
+
+    assert_eq!(resp.get_field_value("AE"), Some("Jos\u{e9}"));
+    assert_eq!(server.join().unwrap(), b"9300CNJos\xe9|COpass|\r");
+}
+
+#[test]
+fn validate_fixed_fields() {
+    let msg = Message::new(
+        &spec::M_LOGIN,
+        vec![FixedField::new(&spec::FF_PWD_ALGO, "0").unwrap()],
+        vec![
+            Field::new(spec::F_LOGIN_UID.code, "sip_username"),
+            Field::new(spec::F_LOGIN_PWD.code, "sip_password"),
+        ],
+    );
+
+    assert_eq!(
+        msg.validate(),
+        Err(vec![
+            ValidationError::FixedFieldCount {
+                expected: 2,
+                found: 1
+            },
+            ValidationError::UnexpectedFixedField {
+                position: 0,
+                expected: spec::FF_UID_ALGO.label,
+                found: spec::FF_PWD_ALGO.label,
+            },
+        ])
+    );
+}
+
+#[test]
+fn typed_patron_info_response() {
+    let resp = typed::PatronInformationResponse {
+        patron_status: "Y".to_string(),
+        date: "20240101    120000".to_string(),
+        hold_items_count: 2,
+        institution: "example".to_string(),
+        patron_id: "2222200000001".to_string(),
+        personal_name: "Doe, Jane".to_string(),
+        valid_patron: Some(true),
+        hold_items: vec!["Title 1".to_string(), "Title 2".to_string()],
+        screen_msg: vec!["Line 1".to_string(), "Line 2".to_string()],
+        ..Default::default()
+    };
+
+    let msg = resp.to_message().unwrap();
+
+    assert_eq!(
+        msg.to_sip(),
+        "64Y             00020240101    120000000200000000000000000000\
+         AA2222200000001|AEDoe, Jane|AFLine 1|AFLine 2|AOexample|\
+         ASTitle 1|ASTitle 2|BLY|"
+    );
+
+    let msg = Message::from_sip(&msg.to_sip()).unwrap();
+    assert_eq!(
+        typed::PatronInformationResponse::from_message(&msg).unwrap(),
+        resp
+    );
+
+    // Wrong message type.
+    assert!(typed::CheckoutResponse::from_message(&msg).is_err());
+}
+
+#[test]
+fn custom_fields_and_messages() {
+    spec::register_field("ZR", "vendor repeatable", true).unwrap();
+    spec::register_message("ZM", "Vendor Message", &[("vendor flag", 1)]).unwrap();
+
+    let msg = Message::from_sip("ZMYZRone|ZRtwo|").unwrap();
+    assert_eq!(msg.spec().label, "Vendor Message");
+    assert_eq!(msg.fields().len(), 2);
+    assert!(msg.validate().is_ok());
+    assert!(msg.to_string().contains("vendor repeatable"));
+
+    assert_eq!(msg.fixed_fields()[0].value(), "Y");
+
+    assert!(spec::register_field("Z", "too short", false).is_err());
+    assert!(spec::register_message("11", "checkout", &[]).is_err());
+}
+
+#[test]
+fn version_gated_messages() {
+    spec::register_message_for_version("ZV", "Vendor V3 Message", &[], spec::SipVersion::V3)
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let host = listener.local_addr().unwrap().to_string();
+
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+        let mut req = Vec::new();
+        reader.read_until(b'\r', &mut req).unwrap();
+
+        (&stream).write_all(b"ZVAOexample|\r").unwrap();
+
+        req
+    });
+
+    let msg = Message::from_sip("ZVAOexample|").unwrap();
+
+    let mut con = Connection::new(&host).unwrap();
+
+    // SIP 2.00 connections refuse to send 3.00 messages.
+    assert!(con.send(&msg).is_err());
+
+    con.set_version(spec::SipVersion::V3);
+    let resp = con.sendrecv(&msg).unwrap();
+
+    assert_eq!(resp.spec().code, "ZV");
+    assert_eq!(server.join().unwrap(), b"ZVAOexample|\r");
+}
+
+#[test]
+fn sip3_hold_list_message() {
+    let msg = Message::new(
+        &spec::M_HOLD_LIST,
+        vec![FixedField::new(&spec::FF_DATE, "20240101    120000").unwrap()],
+        vec![
+            Field::new(spec::F_INSTITUTION_ID.code, "example"),
+            Field::new(spec::F_PATRON_ID.code, "p123"),
+        ],
+    );
+
+    let text = msg.to_sip();
+    assert_eq!(text, "2720240101    120000AAp123|AOexample|");
+    assert_eq!(Message::from_sip(&text).unwrap(), msg);
+    assert!(msg.validate().is_ok());
+    assert_eq!(msg.spec().version(), spec::SipVersion::V3);
+
+    // Only SIP 3.00 connections send or accept it.
+    assert!(check_version(&msg, spec::SipVersion::V2).is_err());
+    assert!(check_version(&msg, spec::SipVersion::V3).is_ok());
+
+    let text = "281000220240101    120000AAp123|AOexample|HI17|HI18|";
+    let resp = Message::from_sip(text).unwrap();
+
+    assert_eq!(resp.spec(), &spec::M_HOLD_LIST_RESP);
+    assert_eq!(resp.fixed_fields()[1].value(), "0002");
+    assert_eq!(resp.fields().len(), 4);
+    assert!(resp.validate().is_ok());
+    assert!(resp.to_string().contains("hold identifier"));
+    assert_eq!(resp.to_sip(), text);
+}
+
+#[test]
+fn sip3_hold_status_and_item_update_messages() {
+    let text = "3920240101    120000AOexample|HI17|";
+    let msg = Message::from_sip(text).unwrap();
+    assert_eq!(msg.spec(), &spec::M_HOLD_STATUS);
+    assert!(msg.validate().is_ok());
+    assert_eq!(msg.to_sip(), text);
+
+    let text = "401Y20240101    120000AOexample|HI17|HSready|HE20240108    120000|HP1|";
+    let resp = Message::from_sip(text).unwrap();
+    assert_eq!(resp.spec(), &spec::M_HOLD_STATUS_RESP);
+    assert_eq!(resp.fixed_fields()[1].value(), "Y");
+    assert_eq!(resp.to_sip(), text);
+
+    let text = "2120240101    120000AOexample|AB30001|IMbook|INMended|";
+    let msg = Message::from_sip(text).unwrap();
+    assert_eq!(msg.spec(), &spec::M_ITEM_UPDATE);
+    assert!(msg.validate().is_ok());
+    assert_eq!(msg.to_sip(), text);
+
+    let resp = Message::from_sip("22120240101    120000AOexample|AB30001|").unwrap();
+    assert_eq!(resp.spec(), &spec::M_ITEM_UPDATE_RESP);
+    assert_eq!(resp.spec().version(), spec::SipVersion::V3);
+
+    // Item Update requires an item identifier.
+    let msg = Message::from_sip("2120240101    120000AOexample|").unwrap();
+    assert!(msg.validate().is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_message() {
+    let msg = Message::from_sip("941AFHello|AFWorld|AOexample|").unwrap();
+
+    let text = serde_json::to_string(&msg).unwrap();
+    assert_eq!(
+        text,
+        r#"{"code":"94","fixed_fields":["1"],"fields":[{"AF":"Hello"},{"AF":"World"},{"AO":"example"}]}"#
+    );
+
+    assert_eq!(serde_json::from_str::<Message>(&text).unwrap(), msg);
+
+    // Fixed field values are validated.
+    assert!(serde_json::from_str::<Message>(r#"{"code":"94","fixed_fields":["11"]}"#).is_err());
+
+    let ff = &msg.fixed_fields()[0];
+    let text = serde_json::to_string(ff).unwrap();
+    assert_eq!(text, r#"{"label":"ok","value":"1"}"#);
+    assert_eq!(&serde_json::from_str::<FixedField>(&text).unwrap(), ff);
+
+    assert!(serde_json::from_str::<Field>(r#"{"AA":"1","AB":"2"}"#).is_err());
+}
@@ -0,0 +1,1140 @@
+//! Typed SIP requests and responses.
+//!
+//! Each struct converts to and from a [`Message`], taking care of the
+//! message code, fixed field order and formatting, and field codes.
+//!
+//! ```
+//! use sip2::typed::{CheckoutRequest, CheckoutResponse};
+//! use sip2::Message;
+//!
+//! let req = CheckoutRequest {
+//!     institution: "example".to_string(),
+//!     patron_id: "2222200000001".to_string(),
+//!     item_id: "3333300000001".to_string(),
+//!     ..Default::default()
+//! };
+//!
+//! let msg = req.to_message().unwrap();
+//! assert_eq!(msg.spec().code, "11");
+//! assert_eq!(msg.get_field_value("AA"), Some("2222200000001"));
+//!
+//! let msg = Message::from_sip(
+//!     "121NNY20240101    120000AOexample|AA2222200000001|AB3333300000001|AJTitle|AH20240115    235959|",
+//! )
+//! .unwrap();
+//!
+//! let resp = CheckoutResponse::from_message(&msg).unwrap();
+//! assert!(resp.ok);
+//! assert!(!resp.renewal_ok);
+//! assert_eq!(resp.desensitize, Some(true));
+//! assert_eq!(resp.due_date.as_deref(), Some("20240115    235959"));
+//! ```
+//!
+//! Transaction dates left empty are filled with the current time when
+//! the message is created.  Optional dates are sent as blank fixed
+//! fields when unset.
+use super::error::Error;
+use super::spec;
+use super::util;
+use super::Message;
+use std::time::Duration;
+
+/// An unset date fixed field.
+const BLANK_DATE: &str = "                  ";
+
+/// Login Request (93)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoginRequest {
+    pub sip_user: String,
+    pub sip_pass: String,
+    pub location: Option<String>,
+}
+
+impl LoginRequest {
+    pub fn to_message(&self) -> Result<Message, Error> {
+        let mut msg = Message::from_values(
+            spec::M_LOGIN.code,
+            &["0", "0"],
+            &[
+                (spec::F_LOGIN_UID.code, &self.sip_user),
+                (spec::F_LOGIN_PWD.code, &self.sip_pass),
+            ],
+        )?;
+
+        msg.maybe_add_field(spec::F_LOCATION_CODE.code, self.location.as_deref());
+
+        Ok(msg)
+    }
+
+    pub fn from_message(msg: &Message) -> Result<Self, Error> {
+        check_spec(msg, &spec::M_LOGIN)?;
+
+        Ok(LoginRequest {
+            sip_user: required(msg, &spec::F_LOGIN_UID)?,
+            sip_pass: required(msg, &spec::F_LOGIN_PWD)?,
+            location: optional(msg, &spec::F_LOCATION_CODE),
+        })
+    }
+}
+
+/// Login Response (94)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoginResponse {
+    pub ok: bool,
+}
+
+impl LoginResponse {
+    pub fn to_message(&self) -> Result<Message, Error> {
+        Message::from_ff_values(spec::M_LOGIN_RESP.code, &[util::num_bool(self.ok)])
+    }
+
+    pub fn from_message(msg: &Message) -> Result<Self, Error> {
+        check_spec(msg, &spec::M_LOGIN_RESP)?;
+
+        Ok(LoginResponse {
+            ok: fixed(msg, 0)? == "1",
+        })
+    }
+}
+
+/// Patron Information (63)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PatronInformationRequest {
+    /// Transaction date.  The current time is used when empty.
+    pub date: String,
+
+    /// Zero-based position of the summary item to request details
+    /// for, e.g. 0 for hold items.
+    pub summary: Option<usize>,
+
+    pub institution: String,
+    pub patron_id: String,
+    pub terminal_pwd: Option<String>,
+    pub patron_pwd: Option<String>,
+    pub start_item: Option<usize>,
+    pub end_item: Option<usize>,
+}
+
+impl PatronInformationRequest {
+    pub fn to_message(&self) -> Result<Message, Error> {
+        let mut summary = [' '; 10];
+
+        if let Some(idx) = self.summary {
+            if idx >= summary.len() {
+                return Err(Error::MessageFormatError);
+            }
+            summary[idx] = 'Y';
+        }
+
+        let summary: String = summary.iter().collect();
+
+        let mut msg = Message::from_values(
+            spec::M_PATRON_INFO.code,
+            &["000", &date_or_now(&self.date), &summary],
+            &[
+                (spec::F_INSTITUTION_ID.code, &self.institution),
+                (spec::F_PATRON_ID.code, &self.patron_id),
+            ],
+        )?;
+
+        msg.maybe_add_field(spec::F_TERMINAL_PWD.code, self.terminal_pwd.as_deref());
+        msg.maybe_add_field(spec::F_PATRON_PWD.code, self.patron_pwd.as_deref());
+
+        if let Some(v) = self.start_item {
+            msg.add_field(spec::F_START_ITEM.code, &v.to_string());
+        }
+
+        if let Some(v) = self.end_item {
+            msg.add_field(spec::F_END_ITEM.code, &v.to_string());
+        }
+
+        Ok(msg)
+    }
+
+    pub fn from_message(msg: &Message) -> Result<Self, Error> {
+        check_spec(msg, &spec::M_PATRON_INFO)?;
+
+        Ok(PatronInformationRequest {
+            date: fixed(msg, 1)?.to_string(),
+            summary: fixed(msg, 2)?.find('Y'),
+            institution: required(msg, &spec::F_INSTITUTION_ID)?,
+            patron_id: required(msg, &spec::F_PATRON_ID)?,
+            terminal_pwd: optional(msg, &spec::F_TERMINAL_PWD),
+            patron_pwd: optional(msg, &spec::F_PATRON_PWD),
+            start_item: optional_number(msg, &spec::F_START_ITEM)?,
+            end_item: optional_number(msg, &spec::F_END_ITEM)?,
+        })
+    }
+}
+
+/// Patron Information Response (64)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PatronInformationResponse {
+    /// 14-character patron status, e.g. "Y" in position 0 when the
+    /// patron is denied charge privileges.  Blank when empty.
+    pub patron_status: String,
+
+    /// Transaction date.  The current time is used when empty.
+    pub date: String,
+
+    pub hold_items_count: usize,
+    pub overdue_items_count: usize,
+    pub charged_items_count: usize,
+    pub fine_items_count: usize,
+    pub recall_items_count: usize,
+    pub unavail_holds_count: usize,
+
+    pub institution: String,
+    pub patron_id: String,
+    pub personal_name: String,
+    pub valid_patron: Option<bool>,
+    pub valid_patron_pwd: Option<bool>,
+    pub currency: Option<String>,
+    pub fee_amount: Option<String>,
+    pub fee_limit: Option<String>,
+    pub home_address: Option<String>,
+    pub email_address: Option<String>,
+    pub home_phone: Option<String>,
+
+    pub hold_items: Vec<String>,
+    pub overdue_items: Vec<String>,
+    pub charged_items: Vec<String>,
+    pub fine_items: Vec<String>,
+    pub recall_items: Vec<String>,
+    pub unavail_hold_items: Vec<String>,
+
+    pub screen_msg: Vec<String>,
+    pub print_line: Vec<String>,
+}
+
+impl PatronInformationResponse {
+    pub fn to_message(&self) -> Result<Message, Error> {
+        let patron_status = format!("{:<14}", self.patron_status);
+
+        let mut msg = Message::from_values(
+            spec::M_PATRON_INFO_RESP.code,
+            &[
+                &patron_status,
+                "000",
+                &date_or_now(&self.date),
+                &util::sip_count4(self.hold_items_count),
+                &util::sip_count4(self.overdue_items_count),
+                &util::sip_count4(self.charged_items_count),
+                &util::sip_count4(self.fine_items_count),
+                &util::sip_count4(self.recall_items_count),
+                &util::sip_count4(self.unavail_holds_count),
+            ],
+            &[
+                (spec::F_INSTITUTION_ID.code, &self.institution),
+                (spec::F_PATRON_ID.code, &self.patron_id),
+                (spec::F_PERSONAL_NAME.code, &self.personal_name),
+            ],
+        )?;
+
+        add_bool(&mut msg, &spec::F_VALID_PATRON, self.valid_patron);
+        add_bool(&mut msg, &spec::F_VALID_PATRON_PWD, self.valid_patron_pwd);
+        msg.maybe_add_field(spec::F_CURRENCY.code, self.currency.as_deref());
+        msg.maybe_add_field(spec::F_FEE_AMOUNT.code, self.fee_amount.as_deref());
+        msg.maybe_add_field(spec::F_FEE_LIMIT.code, self.fee_limit.as_deref());
+        msg.maybe_add_field(spec::F_HOME_ADDRESS.code, self.home_address.as_deref());
+        msg.maybe_add_field(spec::F_EMAIL_ADDRESS.code, self.email_address.as_deref());
+        msg.maybe_add_field(spec::F_HOME_PHONE.code, self.home_phone.as_deref());
+
+        add_all(&mut msg, &spec::F_HOLD_ITEMS, &self.hold_items);
+        add_all(&mut msg, &spec::F_OVERDUE_ITEMS, &self.overdue_items);
+        add_all(&mut msg, &spec::F_CHARGED_ITEMS, &self.charged_items);
+        add_all(&mut msg, &spec::F_FINE_ITEMS, &self.fine_items);
+        add_all(&mut msg, &spec::F_RECALL_ITEMS, &self.recall_items);
+        add_all(
+            &mut msg,
+            &spec::F_UNAVAIL_HOLD_ITEMS,
+            &self.unavail_hold_items,
+        );
+        add_all(&mut msg, &spec::F_SCREEN_MSG, &self.screen_msg);
+        add_all(&mut msg, &spec::F_PRINT_LINE, &self.print_line);
+
+        Ok(msg)
+    }
+
+    pub fn from_message(msg: &Message) -> Result<Self, Error> {
+        check_spec(msg, &spec::M_PATRON_INFO_RESP)?;
+
+        Ok(PatronInformationResponse {
+            patron_status: fixed(msg, 0)?.trim_end().to_string(),
+            date: fixed(msg, 2)?.to_string(),
+            hold_items_count: count(msg, 3)?,
+            overdue_items_count: count(msg, 4)?,
+            charged_items_count: count(msg, 5)?,
+            fine_items_count: count(msg, 6)?,
+            recall_items_count: count(msg, 7)?,
+            unavail_holds_count: count(msg, 8)?,
+            institution: required(msg, &spec::F_INSTITUTION_ID)?,
+            patron_id: required(msg, &spec::F_PATRON_ID)?,
+            personal_name: required(msg, &spec::F_PERSONAL_NAME)?,
+            valid_patron: optional_bool(msg, &spec::F_VALID_PATRON),
+            valid_patron_pwd: optional_bool(msg, &spec::F_VALID_PATRON_PWD),
+            currency: optional(msg, &spec::F_CURRENCY),
+            fee_amount: optional(msg, &spec::F_FEE_AMOUNT),
+            fee_limit: optional(msg, &spec::F_FEE_LIMIT),
+            home_address: optional(msg, &spec::F_HOME_ADDRESS),
+            email_address: optional(msg, &spec::F_EMAIL_ADDRESS),
+            home_phone: optional(msg, &spec::F_HOME_PHONE),
+            hold_items: all(msg, &spec::F_HOLD_ITEMS),
+            overdue_items: all(msg, &spec::F_OVERDUE_ITEMS),
+            charged_items: all(msg, &spec::F_CHARGED_ITEMS),
+            fine_items: all(msg, &spec::F_FINE_ITEMS),
+            recall_items: all(msg, &spec::F_RECALL_ITEMS),
+            unavail_hold_items: all(msg, &spec::F_UNAVAIL_HOLD_ITEMS),
+            screen_msg: all(msg, &spec::F_SCREEN_MSG),
+            print_line: all(msg, &spec::F_PRINT_LINE),
+        })
+    }
+}
+
+/// Item Information Request (17)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemInformationRequest {
+    /// Transaction date.  The current time is used when empty.
+    pub date: String,
+
+    pub institution: String,
+    pub item_id: String,
+    pub terminal_pwd: Option<String>,
+}
+
+impl ItemInformationRequest {
+    pub fn to_message(&self) -> Result<Message, Error> {
+        let mut msg = Message::from_values(
+            spec::M_ITEM_INFO.code,
+            &[&date_or_now(&self.date)],
+            &[
+                (spec::F_INSTITUTION_ID.code, &self.institution),
+                (spec::F_ITEM_IDENT.code, &self.item_id),
+            ],
+        )?;
+
+        msg.maybe_add_field(spec::F_TERMINAL_PWD.code, self.terminal_pwd.as_deref());
+
+        Ok(msg)
+    }
+
+    pub fn from_message(msg: &Message) -> Result<Self, Error> {
+        check_spec(msg, &spec::M_ITEM_INFO)?;
+
+        Ok(ItemInformationRequest {
+            date: fixed(msg, 0)?.to_string(),
+            institution: required(msg, &spec::F_INSTITUTION_ID)?,
+            item_id: required(msg, &spec::F_ITEM_IDENT)?,
+            terminal_pwd: optional(msg, &spec::F_TERMINAL_PWD),
+        })
+    }
+}
+
+/// Item Information Response (18)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ItemInformationResponse {
+    /// 2-digit circulation status, e.g. "03" for available.
+    pub circulation_status: String,
+
+    /// 2-digit security marker, e.g. "00" for other.
+    pub security_marker: String,
+
+    /// 2-digit fee type, e.g. "01" for other/unknown.
+    pub fee_type: String,
+
+    /// Transaction date.  The current time is used when empty.
+    pub date: String,
+
+    pub item_id: String,
+    pub title: String,
+    pub hold_queue_length: Option<String>,
+    pub due_date: Option<String>,
+    pub recall_date: Option<String>,
+    pub hold_pickup_date: Option<String>,
+    pub owner: Option<String>,
+    pub currency: Option<String>,
+    pub fee_amount: Option<String>,
+    pub media_type: Option<String>,
+    pub permanent_location: Option<String>,
+    pub current_location: Option<String>,
+    pub item_properties: Option<String>,
+
+    pub screen_msg: Vec<String>,
+    pub print_line: Vec<String>,
+}
+
+impl ItemInformationResponse {
+    pub fn to_message(&self) -> Result<Message, Error> {
+        let mut msg = Message::from_values(
+            spec::M_ITEM_INFO_RESP.code,
+            &[
+                &self.circulation_status,
+                &self.security_marker,
+                &self.fee_type,
+                &date_or_now(&self.date),
+            ],
+            &[
+                (spec::F_ITEM_IDENT.code, &self.item_id),
+                (spec::F_TITLE_IDENT.code, &self.title),
+            ],
+        )?;
+
+        msg.maybe_add_field(
+            spec::F_HOLD_QUEUE_LENGTH.code,
+            self.hold_queue_length.as_deref(),
+        );
+        msg.maybe_add_field(spec::F_DUE_DATE.code, self.due_date.as_deref());
+        msg.maybe_add_field(spec::F_RECALL_DATE.code, self.recall_date.as_deref());
+        msg.maybe_add_field(
+            spec::F_HOLD_PICKUP_DATE.code,
+            self.hold_pickup_date.as_deref(),
+        );
+        msg.maybe_add_field(spec::F_OWNER.code, self.owner.as_deref());
+        msg.maybe_add_field(spec::F_CURRENCY.code, self.currency.as_deref());
+        msg.maybe_add_field(spec::F_FEE_AMOUNT.code, self.fee_amount.as_deref());
+        msg.maybe_add_field(spec::F_MEDIA_TYPE.code, self.media_type.as_deref());
+        msg.maybe_add_field(
+            spec::F_PERMANENT_LOCATION.code,
+            self.permanent_location.as_deref(),
+        );
+        msg.maybe_add_field(
+            spec::F_CURRENT_LOCATION.code,
+            self.current_location.as_deref(),
+        );
+        msg.maybe_add_field(
+            spec::F_ITEM_PROPERTIES.code,
+            self.item_properties.as_deref(),
+        );
+
+        add_all(&mut msg, &spec::F_SCREEN_MSG, &self.screen_msg);
+        add_all(&mut msg, &spec::F_PRINT_LINE, &self.print_line);
+
+        Ok(msg)
+    }
+
+    pub fn from_message(msg: &Message) -> Result<Self, Error> {
+        check_spec(msg, &spec::M_ITEM_INFO_RESP)?;
+
+        Ok(ItemInformationResponse {
+            circulation_status: fixed(msg, 0)?.to_string(),
+            security_marker: fixed(msg, 1)?.to_string(),
+            fee_type: fixed(msg, 2)?.to_string(),
+            date: fixed(msg, 3)?.to_string(),
+            item_id: required(msg, &spec::F_ITEM_IDENT)?,
+            title: required(msg, &spec::F_TITLE_IDENT)?,
+            hold_queue_length: optional(msg, &spec::F_HOLD_QUEUE_LENGTH),
+            due_date: optional(msg, &spec::F_DUE_DATE),
+            recall_date: optional(msg, &spec::F_RECALL_DATE),
+            hold_pickup_date: optional(msg, &spec::F_HOLD_PICKUP_DATE),
+            owner: optional(msg, &spec::F_OWNER),
+            currency: optional(msg, &spec::F_CURRENCY),
+            fee_amount: optional(msg, &spec::F_FEE_AMOUNT),
+            media_type: optional(msg, &spec::F_MEDIA_TYPE),
+            permanent_location: optional(msg, &spec::F_PERMANENT_LOCATION),
+            current_location: optional(msg, &spec::F_CURRENT_LOCATION),
+            item_properties: optional(msg, &spec::F_ITEM_PROPERTIES),
+            screen_msg: all(msg, &spec::F_SCREEN_MSG),
+            print_line: all(msg, &spec::F_PRINT_LINE),
+        })
+    }
+}
+
+/// Checkout Request (11)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CheckoutRequest {
+    /// True if the SC allows the ACS to renew items already checked
+    /// out to the patron.
+    pub renewal_policy: bool,
+
+    /// True if the checkout already occurred while the SC was offline.
+    pub no_block: bool,
+
+    /// Transaction date.  The current time is used when empty.
+    pub date: String,
+
+    /// Due date given to the patron while offline.
+    pub nb_due_date: Option<String>,
+
+    pub institution: String,
+    pub patron_id: String,
+    pub item_id: String,
+    pub terminal_pwd: Option<String>,
+    pub patron_pwd: Option<String>,
+    pub item_properties: Option<String>,
+    pub fee_acknowledged: Option<bool>,
+    pub cancel: Option<bool>,
+}
+
+impl CheckoutRequest {
+    pub fn to_message(&self) -> Result<Message, Error> {
+        let mut msg = Message::from_values(
+            spec::M_CHECKOUT.code,
+            &[
+                util::sip_bool(self.renewal_policy),
+                util::sip_bool(self.no_block),
+                &date_or_now(&self.date),
+                self.nb_due_date.as_deref().unwrap_or(BLANK_DATE),
+            ],
+            &[
+                (spec::F_INSTITUTION_ID.code, &self.institution),
+                (spec::F_PATRON_ID.code, &self.patron_id),
+                (spec::F_ITEM_IDENT.code, &self.item_id),
+            ],
+        )?;
+
+        msg.maybe_add_field(spec::F_TERMINAL_PWD.code, self.terminal_pwd.as_deref());
+        msg.maybe_add_field(spec::F_PATRON_PWD.code, self.patron_pwd.as_deref());
+        msg.maybe_add_field(
+            spec::F_ITEM_PROPERTIES.code,
+            self.item_properties.as_deref(),
+        );
+        add_bool(&mut msg, &spec::F_FEE_ACKNOWLEGED, self.fee_acknowledged);
+        add_bool(&mut msg, &spec::F_CANCEL, self.cancel);
+
+        Ok(msg)
+    }
+
+    pub fn from_message(msg: &Message) -> Result<Self, Error> {
+        check_spec(msg, &spec::M_CHECKOUT)?;
+
+        Ok(CheckoutRequest {
+            renewal_policy: fixed(msg, 0)? == "Y",
+            no_block: fixed(msg, 1)? == "Y",
+            date: fixed(msg, 2)?.to_string(),
+            nb_due_date: optional_date(fixed(msg, 3)?),
+            institution: required(msg, &spec::F_INSTITUTION_ID)?,
+            patron_id: required(msg, &spec::F_PATRON_ID)?,
+            item_id: required(msg, &spec::F_ITEM_IDENT)?,
+            terminal_pwd: optional(msg, &spec::F_TERMINAL_PWD),
+            patron_pwd: optional(msg, &spec::F_PATRON_PWD),
+            item_properties: optional(msg, &spec::F_ITEM_PROPERTIES),
+            fee_acknowledged: optional_bool(msg, &spec::F_FEE_ACKNOWLEGED),
+            cancel: optional_bool(msg, &spec::F_CANCEL),
+        })
+    }
+}
+
+/// Checkout Response (12)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CheckoutResponse {
+    pub ok: bool,
+    pub renewal_ok: bool,
+
+    /// None when unknown.
+    pub magnetic_media: Option<bool>,
+
+    /// None when unknown.
+    pub desensitize: Option<bool>,
+
+    /// Transaction date.  The current time is used when empty.
+    pub date: String,
+
+    pub institution: String,
+    pub patron_id: String,
+    pub item_id: String,
+    pub title: String,
+    pub due_date: Option<String>,
+    pub fee_type: Option<String>,
+    pub security_inhibit: Option<bool>,
+    pub currency: Option<String>,
+    pub fee_amount: Option<String>,
+    pub media_type: Option<String>,
+    pub item_properties: Option<String>,
+    pub transaction_id: Option<String>,
+
+    pub screen_msg: Vec<String>,
+    pub print_line: Vec<String>,
+}
+
+impl CheckoutResponse {
+    pub fn to_message(&self) -> Result<Message, Error> {
+        let mut msg = Message::from_values(
+            spec::M_CHECKOUT_RESP.code,
+            &[
+                util::num_bool(self.ok),
+                util::sip_bool(self.renewal_ok),
+                unknown_bool(self.magnetic_media),
+                unknown_bool(self.desensitize),
+                &date_or_now(&self.date),
+            ],
+            &[
+                (spec::F_INSTITUTION_ID.code, &self.institution),
+                (spec::F_PATRON_ID.code, &self.patron_id),
+                (spec::F_ITEM_IDENT.code, &self.item_id),
+                (spec::F_TITLE_IDENT.code, &self.title),
+            ],
+        )?;
+
+        msg.maybe_add_field(spec::F_DUE_DATE.code, self.due_date.as_deref());
+        msg.maybe_add_field(spec::F_FEE_TYPE.code, self.fee_type.as_deref());
+        add_bool(&mut msg, &spec::F_SECURITY_INHIBIT, self.security_inhibit);
+        msg.maybe_add_field(spec::F_CURRENCY.code, self.currency.as_deref());
+        msg.maybe_add_field(spec::F_FEE_AMOUNT.code, self.fee_amount.as_deref());
+        msg.maybe_add_field(spec::F_MEDIA_TYPE.code, self.media_type.as_deref());
+        msg.maybe_add_field(
+            spec::F_ITEM_PROPERTIES.code,
+            self.item_properties.as_deref(),
+        );
+        msg.maybe_add_field(spec::F_TRANSACTION_ID.code, self.transaction_id.as_deref());
+
+        add_all(&mut msg, &spec::F_SCREEN_MSG, &self.screen_msg);
+        add_all(&mut msg, &spec::F_PRINT_LINE, &self.print_line);
+
+        Ok(msg)
+    }
+
+    pub fn from_message(msg: &Message) -> Result<Self, Error> {
+        check_spec(msg, &spec::M_CHECKOUT_RESP)?;
+
+        Ok(CheckoutResponse {
+            ok: fixed(msg, 0)? == "1",
+            renewal_ok: fixed(msg, 1)? == "Y",
+            magnetic_media: parse_unknown_bool(fixed(msg, 2)?),
+            desensitize: parse_unknown_bool(fixed(msg, 3)?),
+            date: fixed(msg, 4)?.to_string(),
+            institution: required(msg, &spec::F_INSTITUTION_ID)?,
+            patron_id: required(msg, &spec::F_PATRON_ID)?,
+            item_id: required(msg, &spec::F_ITEM_IDENT)?,
+            title: required(msg, &spec::F_TITLE_IDENT)?,
+            due_date: optional(msg, &spec::F_DUE_DATE),
+            fee_type: optional(msg, &spec::F_FEE_TYPE),
+            security_inhibit: optional_bool(msg, &spec::F_SECURITY_INHIBIT),
+            currency: optional(msg, &spec::F_CURRENCY),
+            fee_amount: optional(msg, &spec::F_FEE_AMOUNT),
+            media_type: optional(msg, &spec::F_MEDIA_TYPE),
+            item_properties: optional(msg, &spec::F_ITEM_PROPERTIES),
+            transaction_id: optional(msg, &spec::F_TRANSACTION_ID),
+            screen_msg: all(msg, &spec::F_SCREEN_MSG),
+            print_line: all(msg, &spec::F_PRINT_LINE),
+        })
+    }
+}
+
+/// Checkin Request (09)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CheckinRequest {
+    /// True if the checkin already occurred while the SC was offline.
+    pub no_block: bool,
+
+    /// Transaction date.  The current time is used when empty.
+    pub date: String,
+
+    /// Date the item was returned.  The current time is used when empty.
+    pub return_date: String,
+
+    pub current_location: String,
+    pub institution: String,
+    pub item_id: String,
+    pub terminal_pwd: Option<String>,
+    pub item_properties: Option<String>,
+    pub cancel: Option<bool>,
+}
+
+impl CheckinRequest {
+    pub fn to_message(&self) -> Result<Message, Error> {
+        let mut msg = Message::from_values(
+            spec::M_CHECKIN.code,
+            &[
+                util::sip_bool(self.no_block),
+                &date_or_now(&self.date),
+                &date_or_now(&self.return_date),
+            ],
+            &[
+                (spec::F_CURRENT_LOCATION.code, &self.current_location),
+                (spec::F_INSTITUTION_ID.code, &self.institution),
+                (spec::F_ITEM_IDENT.code, &self.item_id),
+            ],
+        )?;
+
+        msg.maybe_add_field(spec::F_TERMINAL_PWD.code, self.terminal_pwd.as_deref());
+        msg.maybe_add_field(
+            spec::F_ITEM_PROPERTIES.code,
+            self.item_properties.as_deref(),
+        );
+        add_bool(&mut msg, &spec::F_CANCEL, self.cancel);
+
+        Ok(msg)
+    }
+
+    pub fn from_message(msg: &Message) -> Result<Self, Error> {
+        check_spec(msg, &spec::M_CHECKIN)?;
+
+        Ok(CheckinRequest {
+            no_block: fixed(msg, 0)? == "Y",
+            date: fixed(msg, 1)?.to_string(),
+            return_date: fixed(msg, 2)?.to_string(),
+            current_location: optional(msg, &spec::F_CURRENT_LOCATION).unwrap_or_default(),
+            institution: required(msg, &spec::F_INSTITUTION_ID)?,
+            item_id: required(msg, &spec::F_ITEM_IDENT)?,
+            terminal_pwd: optional(msg, &spec::F_TERMINAL_PWD),
+            item_properties: optional(msg, &spec::F_ITEM_PROPERTIES),
+            cancel: optional_bool(msg, &spec::F_CANCEL),
+        })
+    }
+}
+
+/// Checkin Response (10)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CheckinResponse {
+    pub ok: bool,
+    pub resensitize: bool,
+
+    /// None when unknown.
+    pub magnetic_media: Option<bool>,
+
+    pub alert: bool,
+
+    /// Transaction date.  The current time is used when empty.
+    pub date: String,
+
+    pub institution: String,
+    pub item_id: String,
+    pub permanent_location: String,
+    pub title: Option<String>,
+    pub sort_bin: Option<String>,
+    pub patron_id: Option<String>,
+    pub media_type: Option<String>,
+    pub item_properties: Option<String>,
+    pub alert_type: Option<String>,
+    pub dest_location: Option<String>,
+    pub hold_patron_id: Option<String>,
+    pub hold_patron_name: Option<String>,
+
+    pub screen_msg: Vec<String>,
+    pub print_line: Vec<String>,
+}
+
+impl CheckinResponse {
+    pub fn to_message(&self) -> Result<Message, Error> {
+        let mut msg = Message::from_values(
+            spec::M_CHECKIN_RESP.code,
+            &[
+                util::num_bool(self.ok),
+                util::sip_bool(self.resensitize),
+                unknown_bool(self.magnetic_media),
+                util::sip_bool(self.alert),
+                &date_or_now(&self.date),
+            ],
+            &[
+                (spec::F_INSTITUTION_ID.code, &self.institution),
+                (spec::F_ITEM_IDENT.code, &self.item_id),
+                (spec::F_PERMANENT_LOCATION.code, &self.permanent_location),
+            ],
+        )?;
+
+        msg.maybe_add_field(spec::F_TITLE_IDENT.code, self.title.as_deref());
+        msg.maybe_add_field(spec::F_SORT_BIN.code, self.sort_bin.as_deref());
+        msg.maybe_add_field(spec::F_PATRON_ID.code, self.patron_id.as_deref());
+        msg.maybe_add_field(spec::F_MEDIA_TYPE.code, self.media_type.as_deref());
+        msg.maybe_add_field(
+            spec::F_ITEM_PROPERTIES.code,
+            self.item_properties.as_deref(),
+        );
+        msg.maybe_add_field(spec::F_ALERT_TYPE.code, self.alert_type.as_deref());
+        msg.maybe_add_field(spec::F_DEST_LOCATION.code, self.dest_location.as_deref());
+        msg.maybe_add_field(spec::F_HOLD_PATRON_ID.code, self.hold_patron_id.as_deref());
+        msg.maybe_add_field(
+            spec::F_HOLD_PATRON_NAME.code,
+            self.hold_patron_name.as_deref(),
+        );
+
+        add_all(&mut msg, &spec::F_SCREEN_MSG, &self.screen_msg);
+        add_all(&mut msg, &spec::F_PRINT_LINE, &self.print_line);
+
+        Ok(msg)
+    }
+
+    pub fn from_message(msg: &Message) -> Result<Self, Error> {
+        check_spec(msg, &spec::M_CHECKIN_RESP)?;
+
+        Ok(CheckinResponse {
+            ok: fixed(msg, 0)? == "1",
+            resensitize: fixed(msg, 1)? == "Y",
+            magnetic_media: parse_unknown_bool(fixed(msg, 2)?),
+            alert: fixed(msg, 3)? == "Y",
+            date: fixed(msg, 4)?.to_string(),
+            institution: required(msg, &spec::F_INSTITUTION_ID)?,
+            item_id: required(msg, &spec::F_ITEM_IDENT)?,
+            permanent_location: optional(msg, &spec::F_PERMANENT_LOCATION).unwrap_or_default(),
+            title: optional(msg, &spec::F_TITLE_IDENT),
+            sort_bin: optional(msg, &spec::F_SORT_BIN),
+            patron_id: optional(msg, &spec::F_PATRON_ID),
+            media_type: optional(msg, &spec::F_MEDIA_TYPE),
+            item_properties: optional(msg, &spec::F_ITEM_PROPERTIES),
+            alert_type: optional(msg, &spec::F_ALERT_TYPE),
+            dest_location: optional(msg, &spec::F_DEST_LOCATION),
+            hold_patron_id: optional(msg, &spec::F_HOLD_PATRON_ID),
+            hold_patron_name: optional(msg, &spec::F_HOLD_PATRON_NAME),
+            screen_msg: all(msg, &spec::F_SCREEN_MSG),
+            print_line: all(msg, &spec::F_PRINT_LINE),
+        })
+    }
+}
+
+/// SC Status (99)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScStatusRequest {
+    /// 0 when OK, 1 when out of printer paper, 2 when shutting down.
+    pub status_code: usize,
+
+    /// Maximum printer line width.  None when unknown.
+    pub max_print_width: Option<usize>,
+
+    /// Protocol version, e.g. "2.00".  The supported version is used
+    /// when empty.
+    pub protocol_version: String,
+}
+
+impl ScStatusRequest {
+    pub fn to_message(&self) -> Result<Message, Error> {
+        let protocol_version = match self.protocol_version.is_empty() {
+            true => spec::SIP_PROTOCOL_VERSION,
+            false => self.protocol_version.as_str(),
+        };
+
+        Message::from_ff_values(
+            spec::M_SC_STATUS.code,
+            &[
+                &self.status_code.to_string(),
+                &sip_count3(self.max_print_width),
+                protocol_version,
+            ],
+        )
+    }
+
+    pub fn from_message(msg: &Message) -> Result<Self, Error> {
+        check_spec(msg, &spec::M_SC_STATUS)?;
+
+        Ok(ScStatusRequest {
+            status_code: count(msg, 0)?,
+            max_print_width: count3(msg, 1)?,
+            protocol_version: fixed(msg, 2)?.to_string(),
+        })
+    }
+}
+
+/// Supported Messages (BX) from an ACS Status response.
+///
+/// ```
+/// use sip2::spec;
+/// use sip2::typed::SupportedMessages;
+///
+/// let supported = SupportedMessages::from_bitmap("YYYYYNYYYYYNNNYY");
+///
+/// assert!(supported.supports(&spec::M_CHECKOUT));
+/// assert!(!supported.supports(&spec::M_HOLD));
+/// assert_eq!(supported.to_bitmap(), "YYYYYNYYYYYNNNYY");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SupportedMessages {
+    supported: [bool; 16],
+}
+
+impl SupportedMessages {
+    /// Request message codes by position in the bitmap.
+    const CODES: [&'static str; 16] = [
+        "23", // patron status
+        "11", // checkout
+        "09", // checkin
+        "01", // block patron
+        "99", // sc status
+        "97", // request acs resend
+        "93", // login
+        "63", // patron information
+        "35", // end patron session
+        "37", // fee paid
+        "17", // item information
+        "19", // item status update
+        "25", // patron enable
+        "15", // hold
+        "29", // renew
+        "65", // renew all
+    ];
+
+    /// Parse a "Y"/"N" bitmap.  Missing positions are unsupported.
+    pub fn from_bitmap(bitmap: &str) -> Self {
+        let mut supported = [false; 16];
+
+        for (idx, c) in bitmap.chars().take(supported.len()).enumerate() {
+            supported[idx] = c == 'Y';
+        }
+
+        SupportedMessages { supported }
+    }
+
+    pub fn to_bitmap(&self) -> String {
+        self.supported.iter().map(|s| util::sip_bool(*s)).collect()
+    }
+
+    /// True if the request message is supported.
+    ///
+    /// Messages which have no position in the bitmap are assumed to
+    /// be supported.
+    pub fn supports(&self, message: &spec::Message) -> bool {
+        match SupportedMessages::CODES
+            .iter()
+            .position(|c| *c == message.code)
+        {
+            Some(idx) => self.supported[idx],
+            None => true,
+        }
+    }
+
+    /// Mark a request message as supported or not.
+    pub fn set_supported(&mut self, message: &spec::Message, supported: bool) {
+        if let Some(idx) = SupportedMessages::CODES
+            .iter()
+            .position(|c| *c == message.code)
+        {
+            self.supported[idx] = supported;
+        }
+    }
+}
+
+/// ACS Status (98)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AcsStatusResponse {
+    pub online: bool,
+    pub checkin_ok: bool,
+    pub checkout_ok: bool,
+    pub renewal_policy: bool,
+    pub status_update_ok: bool,
+    pub offline_ok: bool,
+
+    /// Time the SC should wait for a response, in tenths of a second.
+    /// None when unknown.  See [`AcsStatusResponse::timeout`].
+    pub timeout_period: Option<usize>,
+
+    /// Number of times the SC may retry a request.  None when unknown.
+    pub retries_allowed: Option<usize>,
+
+    /// ACS date and time.  The current time is used when empty.
+    pub date_time_sync: String,
+
+    pub protocol_version: String,
+    pub institution: String,
+    pub library_name: Option<String>,
+    pub supported_messages: SupportedMessages,
+    pub terminal_location: Option<String>,
+
+    pub screen_msg: Vec<String>,
+    pub print_line: Vec<String>,
+}
+
+impl AcsStatusResponse {
+    pub fn to_message(&self) -> Result<Message, Error> {
+        let mut msg = Message::from_values(
+            spec::M_ACS_STATUS.code,
+            &[
+                util::sip_bool(self.online),
+                util::sip_bool(self.checkin_ok),
+                util::sip_bool(self.checkout_ok),
+                util::sip_bool(self.renewal_policy),
+                util::sip_bool(self.status_update_ok),
+                util::sip_bool(self.offline_ok),
+                &sip_count3(self.timeout_period),
+                &sip_count3(self.retries_allowed),
+                &date_or_now(&self.date_time_sync),
+                &self.protocol_version,
+            ],
+            &[
+                (spec::F_INSTITUTION_ID.code, &self.institution),
+                (
+                    spec::F_SUPPORTED_MESSAGES.code,
+                    &self.supported_messages.to_bitmap(),
+                ),
+            ],
+        )?;
+
+        msg.maybe_add_field(spec::F_LIBRARY_NAME.code, self.library_name.as_deref());
+        msg.maybe_add_field(
+            spec::F_TERMINAL_LOCATION.code,
+            self.terminal_location.as_deref(),
+        );
+
+        add_all(&mut msg, &spec::F_SCREEN_MSG, &self.screen_msg);
+        add_all(&mut msg, &spec::F_PRINT_LINE, &self.print_line);
+
+        Ok(msg)
+    }
+
+    pub fn from_message(msg: &Message) -> Result<Self, Error> {
+        check_spec(msg, &spec::M_ACS_STATUS)?;
+
+        Ok(AcsStatusResponse {
+            online: fixed(msg, 0)? == "Y",
+            checkin_ok: fixed(msg, 1)? == "Y",
+            checkout_ok: fixed(msg, 2)? == "Y",
+            renewal_policy: fixed(msg, 3)? == "Y",
+            status_update_ok: fixed(msg, 4)? == "Y",
+            offline_ok: fixed(msg, 5)? == "Y",
+            timeout_period: count3(msg, 6)?,
+            retries_allowed: count3(msg, 7)?,
+            date_time_sync: fixed(msg, 8)?.to_string(),
+            protocol_version: fixed(msg, 9)?.to_string(),
+            institution: required(msg, &spec::F_INSTITUTION_ID)?,
+            library_name: optional(msg, &spec::F_LIBRARY_NAME),
+            supported_messages: SupportedMessages::from_bitmap(
+                &optional(msg, &spec::F_SUPPORTED_MESSAGES).unwrap_or_default(),
+            ),
+            terminal_location: optional(msg, &spec::F_TERMINAL_LOCATION),
+            screen_msg: all(msg, &spec::F_SCREEN_MSG),
+            print_line: all(msg, &spec::F_PRINT_LINE),
+        })
+    }
+
+    /// Response timeout suggested by the ACS.
+    ///
+    /// None when unknown or zero.
+    ///
+    /// ```
+    /// use sip2::typed::AcsStatusResponse;
+    /// use std::time::Duration;
+    ///
+    /// let status = AcsStatusResponse {
+    ///     timeout_period: Some(35),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(status.timeout(), Some(Duration::from_millis(3500)));
+    /// ```
+    pub fn timeout(&self) -> Option<Duration> {
+        match self.timeout_period {
+            Some(0) | None => None,
+            Some(t) => Some(Duration::from_millis(t as u64 * 100)),
+        }
+    }
+
+    /// True if the ACS supports the request message.
+    pub fn supports(&self, message: &spec::Message) -> bool {
+        self.supported_messages.supports(message)
+    }
+}
+
+// Helpers for reading and writing message values.
+
+fn check_spec(msg: &Message, expected: &spec::Message) -> Result<(), Error> {
+    if msg.spec().code == expected.code {
+        Ok(())
+    } else {
+        log::error!(
+            "Expected a {} message, found {}",
+            expected.label,
+            msg.spec().label
+        );
+        Err(Error::UnknownMessageError)
+    }
+}
+
+fn fixed(msg: &Message, idx: usize) -> Result<&str, Error> {
+    msg.fixed_fields()
+        .get(idx)
+        .map(|ff| ff.value())
+        .ok_or(Error::MessageFormatError)
+}
+
+/// Fixed field count value.  Blank counts are treated as zero.
+fn count(msg: &Message, idx: usize) -> Result<usize, Error> {
+    let value = fixed(msg, idx)?.trim();
+
+    if value.is_empty() {
+        return Ok(0);
+    }
+
+    value.parse().map_err(|_| Error::MessageFormatError)
+}
+
+/// 3-digit fixed field value where "999" means unknown.
+fn count3(msg: &Message, idx: usize) -> Result<Option<usize>, Error> {
+    match fixed(msg, idx)?.trim() {
+        "999" | "" => Ok(None),
+        v => v.parse().map(Some).map_err(|_| Error::MessageFormatError),
+    }
+}
+
+/// Zero-padded 3-digit value, "999" when unknown.
+fn sip_count3(value: Option<usize>) -> String {
+    match value {
+        Some(v) if v < 999 => format!("{v:0>3}"),
+        _ => "999".to_string(),
+    }
+}
+
+fn required(msg: &Message, field: &spec::Field) -> Result<String, Error> {
+    match msg.get_field_value(field.code) {
+        Some(v) => Ok(v.to_string()),
+        None => {
+            log::error!("Message {} has no {} field", msg.spec().code, field.code);
+            Err(Error::MessageFormatError)
+        }
+    }
+}
+
+fn optional(msg: &Message, field: &spec::Field) -> Option<String> {
+    msg.get_field_value(field.code).map(|v| v.to_string())
+}
+
+fn optional_number(msg: &Message, field: &spec::Field) -> Result<Option<usize>, Error> {
+    match msg.get_field_value(field.code) {
+        Some(v) => v
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| Error::MessageFormatError),
+        None => Ok(None),
+    }
+}
+
+fn optional_bool(msg: &Message, field: &spec::Field) -> Option<bool> {
+    msg.get_field_value(field.code).map(|v| v == "Y")
+}
+
+fn optional_date(value: &str) -> Option<String> {
+    match value.trim().is_empty() {
+        true => None,
+        false => Some(value.to_string()),
+    }
+}
+
+fn all(msg: &Message, field: &spec::Field) -> Vec<String> {
+    msg.fields()
+        .iter()
+        .filter(|f| f.code() == field.code)
+        .map(|f| f.value().to_string())
+        .collect()
+}
+
+fn add_bool(msg: &mut Message, field: &spec::Field, value: Option<bool>) {
+    if let Some(v) = value {
+        msg.add_field(field.code, util::sip_bool(v));
+    }
+}
+
+fn add_all(msg: &mut Message, field: &spec::Field, values: &[String]) {
+    for value in values {
+        msg.add_field(field.code, value);
+    }
+}
+
+fn date_or_now(date: &str) -> String {
+    match date.is_empty() {
+        true => util::sip_date_now(),
+        false => date.to_string(),
+    }
+}
+
+/// "Y", "N", or "U" for unknown.
+fn unknown_bool(value: Option<bool>) -> &'static str {
+    match value {
+        Some(v) => util::sip_bool(v),
+        None => "U",
+    }
+}
+
+fn parse_unknown_bool(value: &str) -> Option<bool> {
+    match value {
+        "Y" => Some(true),
+        "N" => Some(false),
+        _ => None,
+    }
+}
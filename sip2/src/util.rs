@@ -83,3 +83,58 @@ pub fn num_bool(value: bool) -> &'static str {
 pub fn sip_count4(value: usize) -> String {
     format!("{value:0>4}")
 }
+
+/// Checksum for the SIP error detection extension.
+///
+/// The text should include everything up to and including the "AZ"
+/// checksum field code.
+///
+/// ```
+/// use sip2::util;
+/// assert_eq!(util::checksum("9300CNuser|COpass|AY1AZ"), "F83D");
+/// ```
+pub fn checksum(text: &str) -> String {
+    checksum_bytes(text.as_bytes())
+}
+
+/// Checksum of message bytes as sent or received.
+pub(crate) fn checksum_bytes(bytes: &[u8]) -> String {
+    let sum = bytes
+        .iter()
+        .fold(0u16, |sum, b| sum.wrapping_add(*b as u16));
+    format!("{:04X}", sum.wrapping_neg())
+}
+
+/// Verify the checksum which ends a SIP message.
+///
+/// Returns None if the message does not end with a checksum.
+///
+/// ```
+/// use sip2::util;
+/// assert_eq!(util::verify_checksum("9300CNuser|COpass|AY1AZF83D"), Some(true));
+/// assert_eq!(util::verify_checksum("9300CNuser|COpass|AY1AZF83E"), Some(false));
+/// assert_eq!(util::verify_checksum("9300CNuser|COpass|"), None);
+/// ```
+pub fn verify_checksum(text: &str) -> Option<bool> {
+    verify_checksum_bytes(text.as_bytes())
+}
+
+pub(crate) fn verify_checksum_bytes(bytes: &[u8]) -> Option<bool> {
+    let len = bytes.len();
+
+    if len < 6 || &bytes[len - 6..len - 4] != b"AZ" {
+        return None;
+    }
+
+    let value = &bytes[len - 4..];
+
+    if !value.iter().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    Some(
+        checksum_bytes(&bytes[..len - 4])
+            .as_bytes()
+            .eq_ignore_ascii_case(value),
+    )
+}
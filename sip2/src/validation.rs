@@ -0,0 +1,49 @@
+//! Problems found while validating a message against the SIP2 spec.
+use std::fmt;
+
+/// A way in which a message does not conform to the SIP2 specification.
+///
+/// See [`Message::validate`](crate::Message::validate).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// The message has the wrong number of fixed fields.
+    FixedFieldCount { expected: usize, found: usize },
+
+    /// The fixed field at this position is not the one the message
+    /// spec defines there.
+    UnexpectedFixedField {
+        position: usize,
+        expected: &'static str,
+        found: &'static str,
+    },
+
+    /// A field required by the specification is not present.
+    MissingField(&'static str),
+
+    /// A field which may appear only once is repeated.
+    RepeatedField(&'static str),
+
+    /// A field code is not 2 characters long.
+    InvalidFieldCode(String),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FixedFieldCount { expected, found } => {
+                write!(f, "message has {found} fixed fields; expected {expected}")
+            }
+            Self::UnexpectedFixedField {
+                position,
+                expected,
+                found,
+            } => write!(
+                f,
+                "fixed field {position} is '{found}'; expected '{expected}'"
+            ),
+            Self::MissingField(code) => write!(f, "required field {code} is missing"),
+            Self::RepeatedField(code) => write!(f, "field {code} may not be repeated"),
+            Self::InvalidFieldCode(code) => write!(f, "invalid field code '{code}'"),
+        }
+    }
+}